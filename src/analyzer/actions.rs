@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::i18n::{self, Lang};
+use super::safe_io;
+use super::scoring::{QualityAnalysis, QualityStatus};
+
+/// 待处理清单的文件名，与其他报告一起写在报告目录中。
+pub const ACTIONS_CSV_FILE_NAME: &str = "audio_quality_actions.csv";
+
+/// 待处理清单的列名（中文, 英文）。
+const ACTIONS_CSV_HEADERS: [(&str, &str); 5] = [
+    ("问题类别", "category"),
+    ("处理建议", "advice"),
+    ("质量分", "score"),
+    ("文件路径", "file_path"),
+    ("全部问题", "issues"),
+];
+
+/// 按处理方式归类的问题，按建议的处理顺序排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ActionCategory {
+    /// 削波：找未削波的母带版本替换。
+    Clipped,
+    /// 伪无损、二次有损或升采样：重新获取真正的无损来源。
+    SuspectedTranscode,
+    /// 码率或采样率过低：重新获取更高质量的版本。
+    LowBitrate,
+    /// 关键指标缺失：文件可能损坏或截断，先检查文件本身。
+    Incomplete,
+}
+
+impl ActionCategory {
+    /// 命中任一状态即归入该类别（按全部问题判断，同一文件可归入多个类别）。
+    fn for_status(status: &QualityStatus) -> Option<Self> {
+        match status {
+            QualityStatus::Clipped => Some(Self::Clipped),
+            QualityStatus::Suspicious
+            | QualityStatus::TranscodeChain
+            | QualityStatus::Upsampled => Some(Self::SuspectedTranscode),
+            QualityStatus::LowBitrate | QualityStatus::LowSampleRate => Some(Self::LowBitrate),
+            QualityStatus::Incomplete => Some(Self::Incomplete),
+            _ => None,
+        }
+    }
+
+    pub fn label(self, lang: Lang) -> &'static str {
+        let (zh, en) = match self {
+            Self::Clipped => ("已削波", "Clipped"),
+            Self::SuspectedTranscode => ("疑似转码/伪无损", "Suspected transcode"),
+            Self::LowBitrate => ("低码率/低采样率", "Low bitrate/sample rate"),
+            Self::Incomplete => ("数据不完整", "Incomplete data"),
+        };
+        lang.pick(zh, en)
+    }
+
+    pub fn advice(self, lang: Lang) -> &'static str {
+        let (zh, en) = match self {
+            Self::Clipped => (
+                "寻找未削波的母带版本替换",
+                "Replace with an unclipped master",
+            ),
+            Self::SuspectedTranscode => (
+                "重新获取真正的无损来源",
+                "Re-acquire a genuine lossless source",
+            ),
+            Self::LowBitrate => (
+                "重新获取更高码率或无损版本",
+                "Re-acquire a higher-bitrate or lossless version",
+            ),
+            Self::Incomplete => (
+                "检查文件是否损坏或截断，必要时重新获取",
+                "Check the file for corruption or truncation; re-acquire if needed",
+            ),
+        };
+        lang.pick(zh, en)
+    }
+}
+
+/// 一条待处理记录。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionItem {
+    pub category: ActionCategory,
+    pub quality_score: i32,
+    /// CUE 音轨附带曲目标签。
+    pub file_path: String,
+    pub issues: Vec<QualityStatus>,
+}
+
+/// 按类别收集待处理的文件：类别按处理顺序排列，类别内分数最低的在前。
+pub fn collect(analyses: &[QualityAnalysis]) -> Vec<ActionItem> {
+    let mut items = Vec::new();
+    for analysis in analyses {
+        let mut categories: Vec<ActionCategory> = analysis
+            .issues
+            .iter()
+            .filter_map(ActionCategory::for_status)
+            .collect();
+        categories.sort();
+        categories.dedup();
+        for category in categories {
+            items.push(ActionItem {
+                category,
+                quality_score: analysis.quality_score,
+                file_path: match &analysis.metrics.cue_track {
+                    Some(track) => format!("{} [{}]", analysis.file_path, track.label()),
+                    None => analysis.file_path.clone(),
+                },
+                issues: analysis.issues.clone(),
+            });
+        }
+    }
+    items.sort_by(|a, b| {
+        a.category
+            .cmp(&b.category)
+            .then_with(|| a.quality_score.cmp(&b.quality_score))
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    items
+}
+
+/// 各类别的文件数（只列出有记录的类别）。
+pub fn totals(items: &[ActionItem]) -> BTreeMap<ActionCategory, usize> {
+    let mut totals = BTreeMap::new();
+    for item in items {
+        *totals.entry(item.category).or_default() += 1;
+    }
+    totals
+}
+
+/// 按报告语言写出待处理清单；没有待处理文件时删除上次遗留的清单。
+pub fn write_actions_csv(
+    path: &Path,
+    items: &[ActionItem],
+    lang: Lang,
+    safe_mode: bool,
+) -> Result<()> {
+    if items.is_empty() {
+        if path.is_file() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("删除过期的待处理清单失败: {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        writer
+            .write_record(
+                ACTIONS_CSV_HEADERS
+                    .iter()
+                    .map(|&(zh, en)| lang.pick(zh, en)),
+            )
+            .context("写入待处理清单表头失败")?;
+        for item in items {
+            let issues: Vec<&str> = item
+                .issues
+                .iter()
+                .map(|status| i18n::status_label(status, lang))
+                .collect();
+            writer
+                .write_record([
+                    item.category.label(lang),
+                    item.category.advice(lang),
+                    &item.quality_score.to_string(),
+                    &item.file_path,
+                    &issues.join("; "),
+                ])
+                .context("写入待处理记录失败")?;
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    safe_io::atomic_write_bytes(path, &buffer, safe_mode)?;
+    uiprintln!("✅ 待处理清单已保存到: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_action_items_grouped_by_category_worst_first() {
+        let scorer = QualityScorer::new();
+        let mut analyses = scorer.analyze_files(&[
+            FileMetrics {
+                file_path: "/m/a.flac".to_string(),
+                ..FileMetrics::default()
+            },
+            FileMetrics {
+                file_path: "/m/b.flac".to_string(),
+                ..FileMetrics::default()
+            },
+            FileMetrics {
+                file_path: "/m/c.flac".to_string(),
+                ..FileMetrics::default()
+            },
+        ]);
+        analyses[0].issues = vec![QualityStatus::LowBitrate, QualityStatus::Clipped];
+        analyses[0].quality_score = 60;
+        analyses[1].issues = vec![QualityStatus::Clipped];
+        analyses[1].quality_score = 40;
+        analyses[2].issues = vec![QualityStatus::LowDynamic];
+
+        let items = collect(&analyses);
+        let summary: Vec<(ActionCategory, &str)> = items
+            .iter()
+            .map(|item| (item.category, item.file_path.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ActionCategory::Clipped, "/m/b.flac"),
+                (ActionCategory::Clipped, "/m/a.flac"),
+                (ActionCategory::LowBitrate, "/m/a.flac"),
+            ]
+        );
+        assert_eq!(totals(&items)[&ActionCategory::Clipped], 2);
+
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join(ACTIONS_CSV_FILE_NAME);
+        write_actions_csv(&path, &items, Lang::Zh, true).expect("write");
+        let content = std::fs::read_to_string(&path).expect("read");
+        assert!(content.starts_with("问题类别,处理建议,质量分,文件路径,全部问题\n"));
+        assert!(content.contains("已削波,寻找未削波的母带版本替换,40,/m/b.flac,已削波\n"));
+        write_actions_csv(&path, &items, Lang::En, true).expect("write en");
+        let content = std::fs::read_to_string(&path).expect("read");
+        assert!(content.starts_with("category,advice,score,file_path,issues\n"));
+        assert!(content.contains("Clipped,Replace with an unclipped master,40,/m/b.flac,Clipped\n"));
+        write_actions_csv(&path, &[], Lang::En, true).expect("clear");
+        assert!(!path.exists());
+    }
+}
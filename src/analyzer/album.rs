@@ -0,0 +1,427 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use super::metrics::FileMetrics;
+use super::scoring::{QualityAnalysis, QualityStatus};
+
+/// ReplayGain 2.0 参考响度（LUFS）。
+pub const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// 前一曲结尾 RMS 不低于该电平（dBFS）视为没有淡出，音乐在分割点处仍在进行。
+const BOUNDARY_ACTIVE_RMS_DB: f64 = -30.0;
+/// 分割点两侧 RMS 相差超过该值（dB）视为不连续。
+const BOUNDARY_JUMP_DB: f64 = 12.0;
+
+/// 专辑级汇总（同一目录下的曲目视为一张专辑）。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumSummary {
+    pub album_dir: String,
+    pub track_count: usize,
+    /// 参与专辑响度计算的曲目数（需同时具备综合响度与时长）。
+    pub measured_tracks: usize,
+    pub duration_seconds: f64,
+    pub integrated_loudness_lufs: Option<f64>,
+    pub album_gain_db: Option<f64>,
+    pub album_peak_dbtp: Option<f64>,
+    pub findings: Vec<AlbumFinding>,
+    /// 曲目平均质量分。
+    pub average_score: Option<f64>,
+    /// 分数最低的曲目（CUE 音轨附带曲目标签）及其分数。
+    pub worst_file: Option<String>,
+    pub worst_score: Option<i32>,
+    /// 出现最多的状态；数量相同时取分数更低的曲目所在的状态。
+    pub dominant_status: Option<QualityStatus>,
+}
+
+/// 专辑级问题（针对整张专辑而非单曲）。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum AlbumFinding {
+    /// 曲目混用了不同采样率或位深（常见于混合多个来源），不符合统一母带的归档要求。
+    #[serde(rename_all = "camelCase")]
+    InconsistentAlbumFormat {
+        sample_rates_hz: Vec<u32>,
+        bit_depths: Vec<u32>,
+    },
+    /// 相邻曲目衔接处不连续：前一曲结尾没有淡出，后一曲开头的电平却与之明显不符，
+    /// 常见于自动分轨的黑胶/磁带翻录（分割点落在音乐中间或丢失了部分样本）。
+    #[serde(rename_all = "camelCase")]
+    BoundaryGlitch {
+        from_track: String,
+        to_track: String,
+        tail_rms_db: f64,
+        head_rms_db: f64,
+    },
+}
+
+impl AlbumFinding {
+    /// 报告中的简短描述，例如 `格式不一致: 44100/48000Hz, 16/24bit`。
+    pub fn describe(&self) -> String {
+        match self {
+            Self::InconsistentAlbumFormat {
+                sample_rates_hz,
+                bit_depths,
+            } => {
+                let mut parts = Vec::new();
+                if sample_rates_hz.len() > 1 {
+                    parts.push(format!("{}Hz", join_values(sample_rates_hz)));
+                }
+                if bit_depths.len() > 1 {
+                    parts.push(format!("{}bit", join_values(bit_depths)));
+                }
+                format!("格式不一致: {}", parts.join(", "))
+            }
+            Self::BoundaryGlitch {
+                from_track,
+                to_track,
+                tail_rms_db,
+                head_rms_db,
+            } => format!(
+                "衔接异常: {from_track} → {to_track} (结尾 {tail_rms_db:.1} dB, 开头 {head_rms_db:.1} dB)"
+            ),
+        }
+    }
+}
+
+fn join_values(values: &[u32]) -> String {
+    values
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 曲目所属的专辑目录：附加音轨归入其专辑，其余取父目录。
+pub fn album_key(metrics: &FileMetrics) -> String {
+    metrics.extra_of_album.clone().unwrap_or_else(|| {
+        Path::new(&metrics.file_path)
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    })
+}
+
+/// 单曲 ReplayGain 2.0 增益（dB）。
+pub fn track_gain_db(metrics: &FileMetrics) -> Option<f64> {
+    metrics
+        .integrated_loudness_lufs
+        .filter(|lufs| lufs.is_finite())
+        .map(|lufs| REPLAYGAIN_REFERENCE_LUFS - lufs)
+}
+
+/// 按专辑目录分组并计算专辑响度。
+///
+/// 专辑综合响度按时长加权的能量平均合成：
+/// `10·log10(Σ tᵢ·10^(Lᵢ/10) / Σ tᵢ)`，与把整张专辑串接后测量的结果近似
+/// （差异仅来自逐曲目门限）。附加音轨不参与专辑响度。
+pub fn summarize_albums(analyses: &[QualityAnalysis]) -> Vec<AlbumSummary> {
+    let mut groups: BTreeMap<String, Vec<&QualityAnalysis>> = BTreeMap::new();
+    for analysis in analyses {
+        groups
+            .entry(album_key(&analysis.metrics))
+            .or_default()
+            .push(analysis);
+    }
+
+    groups
+        .into_iter()
+        .map(|(album_dir, tracks)| summarize_album(album_dir, &tracks))
+        .collect()
+}
+
+fn summarize_album(album_dir: String, tracks: &[&QualityAnalysis]) -> AlbumSummary {
+    // 多音轨文件每条音轨各有一条结果，专辑响度与分数统计只取每个文件（CUE 音轨）序号最小的音轨。
+    let mut seen = std::collections::HashSet::new();
+    let mut regular_tracks: Vec<&QualityAnalysis> = tracks
+        .iter()
+        .copied()
+        .filter(|a| a.metrics.extra_of_album.is_none())
+        .collect();
+    regular_tracks.sort_by_key(|a| a.metrics.audio_stream_index);
+    regular_tracks.retain(|a| {
+        seen.insert((
+            a.metrics.file_path.as_str(),
+            a.metrics.cue_track.as_ref().map(|track| track.number),
+        ))
+    });
+    let regular: Vec<&FileMetrics> = regular_tracks.iter().map(|a| &a.metrics).collect();
+
+    let measured: Vec<(f64, f64)> = regular
+        .iter()
+        .filter_map(|m| {
+            let lufs = m.integrated_loudness_lufs.filter(|l| l.is_finite())?;
+            let duration = m.duration_seconds.filter(|d| *d > 0.0)?;
+            Some((lufs, duration))
+        })
+        .collect();
+
+    let total_weight: f64 = measured.iter().map(|(_, d)| d).sum();
+    let integrated_loudness_lufs = (total_weight > 0.0).then(|| {
+        let energy: f64 = measured
+            .iter()
+            .map(|(lufs, duration)| duration * 10f64.powf(lufs / 10.0))
+            .sum();
+        10.0 * (energy / total_weight).log10()
+    });
+
+    let album_peak_dbtp = regular
+        .iter()
+        .filter_map(|m| m.true_peak_dbtp)
+        .filter(|tp| tp.is_finite())
+        .reduce(f64::max);
+
+    let worst = regular_tracks.iter().min_by(|a, b| {
+        a.quality_score
+            .cmp(&b.quality_score)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+
+    AlbumSummary {
+        average_score: (!regular_tracks.is_empty()).then(|| {
+            let total: i32 = regular_tracks.iter().map(|a| a.quality_score).sum();
+            f64::from(total) / regular_tracks.len() as f64
+        }),
+        worst_file: worst.map(|a| match &a.metrics.cue_track {
+            Some(track) => format!("{} [{}]", a.file_path, track.label()),
+            None => a.file_path.clone(),
+        }),
+        worst_score: worst.map(|a| a.quality_score),
+        dominant_status: dominant_status(&regular_tracks),
+        album_dir,
+        track_count: regular.len(),
+        measured_tracks: measured.len(),
+        duration_seconds: regular.iter().filter_map(|m| m.duration_seconds).sum(),
+        integrated_loudness_lufs,
+        album_gain_db: integrated_loudness_lufs.map(|lufs| REPLAYGAIN_REFERENCE_LUFS - lufs),
+        album_peak_dbtp,
+        findings: format_findings(&regular)
+            .into_iter()
+            .chain(boundary_findings(&regular))
+            .collect(),
+    }
+}
+
+/// 出现次数最多的状态；次数相同时取该状态下最低分更低的一个，结果与曲目顺序无关。
+fn dominant_status(tracks: &[&QualityAnalysis]) -> Option<QualityStatus> {
+    let mut counts: Vec<(&QualityStatus, usize, i32)> = Vec::new();
+    for track in tracks {
+        match counts
+            .iter_mut()
+            .find(|(status, ..)| **status == track.status)
+        {
+            Some((_, count, lowest)) => {
+                *count += 1;
+                *lowest = (*lowest).min(track.quality_score);
+            }
+            None => counts.push((&track.status, 1, track.quality_score)),
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.2.cmp(&a.2)))
+        .map(|(status, ..)| status.clone())
+}
+
+/// 统计曲目的采样率与位深种类；任一项多于一种即判定为格式不一致。
+/// 缺失的元数据（如有损编码没有位深）不参与比较。
+fn format_findings(tracks: &[&FileMetrics]) -> Vec<AlbumFinding> {
+    let sample_rates: BTreeSet<u32> = tracks.iter().filter_map(|m| m.sample_rate_hz).collect();
+    let bit_depths: BTreeSet<u32> = tracks.iter().filter_map(|m| m.bit_depth).collect();
+    if sample_rates.len() <= 1 && bit_depths.len() <= 1 {
+        return Vec::new();
+    }
+
+    vec![AlbumFinding::InconsistentAlbumFormat {
+        sample_rates_hz: sample_rates.into_iter().collect(),
+        bit_depths: bit_depths.into_iter().collect(),
+    }]
+}
+
+/// 按文件名（CUE 音轨按曲目号）排序后检查每对相邻曲目的分割点：
+/// 前一曲结尾仍有音乐（未淡出），而后一曲开头电平与之相差超过 `BOUNDARY_JUMP_DB`。
+/// 无缝衔接的专辑两侧电平相近，正常曲目以淡出或静音结尾，都不会命中。
+fn boundary_findings(tracks: &[&FileMetrics]) -> Vec<AlbumFinding> {
+    let mut ordered = tracks.to_vec();
+    ordered.sort_by(|a, b| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then_with(|| track_number(a).cmp(&track_number(b)))
+    });
+
+    ordered
+        .windows(2)
+        .filter_map(|pair| {
+            let tail_rms_db = pair[0].edge_levels?.tail_rms_db;
+            let head_rms_db = pair[1].edge_levels?.head_rms_db;
+            (tail_rms_db >= BOUNDARY_ACTIVE_RMS_DB
+                && (tail_rms_db - head_rms_db).abs() > BOUNDARY_JUMP_DB)
+                .then(|| AlbumFinding::BoundaryGlitch {
+                    from_track: track_label(pair[0]),
+                    to_track: track_label(pair[1]),
+                    tail_rms_db,
+                    head_rms_db,
+                })
+        })
+        .collect()
+}
+
+fn track_number(metrics: &FileMetrics) -> Option<u32> {
+    metrics.cue_track.as_ref().map(|track| track.number)
+}
+
+/// 报告中的曲目名：文件名，CUE 音轨附加曲目号。
+fn track_label(metrics: &FileMetrics) -> String {
+    let name = Path::new(&metrics.file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| metrics.file_path.clone());
+    match track_number(metrics) {
+        Some(number) => format!("{name} #{number:02}"),
+        None => name,
+    }
+}
+
+/// 把专辑增益写回各曲目的分析结果（附加音轨除外）。
+pub fn apply_album_gain(analyses: &mut [QualityAnalysis], albums: &[AlbumSummary]) {
+    let gains: BTreeMap<&str, Option<f64>> = albums
+        .iter()
+        .map(|album| (album.album_dir.as_str(), album.album_gain_db))
+        .collect();
+
+    for analysis in analyses.iter_mut() {
+        if analysis.metrics.extra_of_album.is_some() {
+            continue;
+        }
+        let key = album_key(&analysis.metrics);
+        analysis.album_gain_db = gains.get(key.as_str()).copied().flatten();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scoring::QualityScorer;
+    use crate::analyzer::segments::EdgeLevels;
+
+    fn track(path: &str, lufs: f64, duration: f64, tp: f64) -> FileMetrics {
+        FileMetrics {
+            file_path: path.to_string(),
+            integrated_loudness_lufs: Some(lufs),
+            duration_seconds: Some(duration),
+            true_peak_dbtp: Some(tp),
+            ..FileMetrics::default()
+        }
+    }
+
+    #[test]
+    fn test_album_loudness_is_energy_weighted() {
+        let scorer = QualityScorer::new();
+        let mut analyses = scorer.analyze_files(&[
+            track("/m/A/01.flac", -10.0, 200.0, -0.5),
+            track("/m/A/02.flac", -20.0, 200.0, -3.0),
+            track("/m/B/01.flac", -14.0, 100.0, -1.0),
+        ]);
+
+        let albums = summarize_albums(&analyses);
+        assert_eq!(albums.len(), 2);
+        let a = &albums[0];
+        assert_eq!(a.album_dir, "/m/A");
+        // 能量平均: 10·log10((10^-1 + 10^-2) / 2) ≈ -12.596
+        let expected = 10.0 * ((0.1f64 + 0.01) / 2.0).log10();
+        assert!((a.integrated_loudness_lufs.unwrap() - expected).abs() < 1e-9);
+        assert!((a.album_gain_db.unwrap() - (-18.0 - expected)).abs() < 1e-9);
+        assert_eq!(a.album_peak_dbtp, Some(-0.5));
+        let scores = [analyses[0].quality_score, analyses[1].quality_score];
+        let average = f64::from(scores[0] + scores[1]) / 2.0;
+        assert!((a.average_score.unwrap() - average).abs() < 1e-9);
+        assert_eq!(a.worst_score, scores.iter().copied().min());
+        let worst = usize::from(scores[1] < scores[0]);
+        assert_eq!(a.worst_file.as_ref(), Some(&analyses[worst].file_path));
+        // 两首曲目各一种状态时取分数更低者的状态；状态相同时就是该状态。
+        assert_eq!(a.dominant_status.as_ref(), Some(&analyses[worst].status));
+
+        apply_album_gain(&mut analyses, &albums);
+        assert_eq!(analyses[2].album_gain_db, Some(-4.0));
+        assert_eq!(analyses[2].track_gain_db, Some(-4.0));
+    }
+
+    #[test]
+    fn test_extras_are_excluded_from_album_loudness() {
+        let mut extra = track("/m/A/.hidden/ghost.wav", -5.0, 60.0, 0.5);
+        extra.extra_of_album = Some("/m/A".to_string());
+        let analyses =
+            QualityScorer::new().analyze_files(&[track("/m/A/01.flac", -14.0, 180.0, -1.0), extra]);
+
+        let albums = summarize_albums(&analyses);
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].track_count, 1);
+        assert_eq!(albums[0].integrated_loudness_lufs, Some(-14.0));
+        assert_eq!(albums[0].album_peak_dbtp, Some(-1.0));
+    }
+
+    #[test]
+    fn test_mixed_sample_rates_flag_inconsistent_album_format() {
+        let with_format = |path: &str, rate: u32, bits: Option<u32>| FileMetrics {
+            sample_rate_hz: Some(rate),
+            bit_depth: bits,
+            ..track(path, -14.0, 180.0, -1.0)
+        };
+        let analyses = QualityScorer::new().analyze_files(&[
+            with_format("/m/A/01.flac", 44_100, Some(16)),
+            with_format("/m/A/02.flac", 48_000, Some(24)),
+            with_format("/m/B/01.flac", 44_100, Some(16)),
+            with_format("/m/B/02.mp3", 44_100, None),
+        ]);
+
+        let albums = summarize_albums(&analyses);
+        assert_eq!(
+            albums[0].findings,
+            vec![AlbumFinding::InconsistentAlbumFormat {
+                sample_rates_hz: vec![44_100, 48_000],
+                bit_depths: vec![16, 24],
+            }]
+        );
+        assert_eq!(
+            albums[0].findings[0].describe(),
+            "格式不一致: 44100/48000Hz, 16/24bit"
+        );
+        assert!(albums[1].findings.is_empty());
+    }
+
+    #[test]
+    fn test_boundary_glitch_between_adjacent_tracks() {
+        let with_edges = |path: &str, head: f64, tail: f64| FileMetrics {
+            edge_levels: Some(EdgeLevels {
+                head_rms_db: head,
+                head_peak_db: head + 10.0,
+                tail_rms_db: tail,
+                tail_peak_db: tail + 10.0,
+            }),
+            ..track(path, -14.0, 180.0, -1.0)
+        };
+        let analyses = QualityScorer::new().analyze_files(&[
+            // 01 → 02 无缝衔接；02 → 03 结尾仍在演奏，下一曲却从近乎静音开始；03 → 04 正常淡出。
+            with_edges("/m/A/02.flac", -18.0, -16.0),
+            with_edges("/m/A/01.flac", -70.0, -17.0),
+            with_edges("/m/A/03.flac", -65.0, -80.0),
+            with_edges("/m/A/04.flac", -12.0, -90.0),
+        ]);
+
+        let findings = &summarize_albums(&analyses)[0].findings;
+        assert_eq!(
+            findings,
+            &vec![AlbumFinding::BoundaryGlitch {
+                from_track: "02.flac".to_string(),
+                to_track: "03.flac".to_string(),
+                tail_rms_db: -16.0,
+                head_rms_db: -65.0,
+            }]
+        );
+        assert_eq!(
+            findings[0].describe(),
+            "衔接异常: 02.flac → 03.flac (结尾 -16.0 dB, 开头 -65.0 dB)"
+        );
+    }
+}
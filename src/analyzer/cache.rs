@@ -3,38 +3,204 @@ use crate::analyzer::safe_io;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 const CACHE_VERSION: u32 = 1;
+/// 抽样指纹在文件开头、中间与结尾各读取的字节数。
+const SAMPLE_CHUNK_BYTES: u64 = 64 * 1024;
+/// 首尾指纹在文件开头与结尾各读取的字节数。
+const PARTIAL_CHUNK_BYTES: u64 = 1024 * 1024;
+
+/// 缓存指纹使用的内容哈希算法。BLAKE3/xxh3 在高速 NVMe 曲库上比 SHA-256 快数倍。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    /// 与配置/命令行取值一致的小写名称。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+            Self::Xxh3 => "xxh3",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(Self::Sha256),
+            "blake3" => Ok(Self::Blake3),
+            "xxh3" => Ok(Self::Xxh3),
+            other => Err(format!(
+                "不支持的哈希算法: {other}（可选 sha256、blake3、xxh3）"
+            )),
+        }
+    }
+}
+
+/// 判断文件是否变化的方式。完整哈希要读一遍整个文件，在网络存储上会让 I/O 翻倍。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FingerprintMode {
+    /// 大小、修改时间与整个文件的内容哈希。
+    #[default]
+    Strict,
+    /// 大小、修改时间与开头 1 MiB、结尾 1 MiB 的哈希；DSD 等超大文件的折中方案。
+    Partial,
+    /// 大小、修改时间与开头/中间/结尾各 64 KiB 的哈希。
+    Sampled,
+    /// 只比较大小与修改时间，不读取文件内容。
+    Fast,
+}
+
+impl FingerprintMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Partial => "partial",
+            Self::Sampled => "sampled",
+            Self::Fast => "fast",
+        }
+    }
+
+    /// 读取的内容越多越严格。
+    fn strength(self) -> u8 {
+        match self {
+            Self::Fast => 0,
+            Self::Sampled => 1,
+            Self::Partial => 2,
+            Self::Strict => 3,
+        }
+    }
+
+    /// 比本模式宽松、且需要读取内容的模式；计算指纹时一并记录它们的哈希。
+    fn weaker_hashed_modes(self) -> &'static [FingerprintMode] {
+        match self {
+            Self::Strict => &[Self::Partial, Self::Sampled],
+            Self::Partial => &[Self::Sampled],
+            Self::Sampled | Self::Fast => &[],
+        }
+    }
+}
+
+impl FromStr for FingerprintMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "strict" | "full" => Ok(Self::Strict),
+            "partial" => Ok(Self::Partial),
+            "sampled" => Ok(Self::Sampled),
+            "fast" => Ok(Self::Fast),
+            other => Err(format!(
+                "不支持的指纹模式: {other}（可选 strict、partial、sampled、fast）"
+            )),
+        }
+    }
+}
+
+/// 计算指纹所需的设置：哈希算法与读取范围。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FingerprintOptions {
+    pub algorithm: HashAlgorithm,
+    pub mode: FingerprintMode,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileFingerprint {
     pub mtime_unix_secs: u64,
     pub file_size_bytes: u64,
-    pub content_sha256: String,
+    /// 内容哈希（十六进制）；旧版缓存中的字段名为 `content_sha256`。
+    #[serde(alias = "content_sha256")]
+    pub content_hash: String,
+    /// 计算 `content_hash` 所用的算法；旧版缓存没有该字段，视为 SHA-256。
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// `content_hash` 覆盖的范围；`Fast` 时为空串。旧版缓存没有该字段，视为完整哈希。
+    #[serde(default)]
+    pub mode: FingerprintMode,
+    /// 同时记录的较宽松模式的哈希（同一算法），以较宽松的模式复用缓存时与同类哈希比较。
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub weaker_hashes: BTreeMap<FingerprintMode, String>,
+}
+
+impl FileFingerprint {
+    /// 按 `mode` 计算的内容哈希；没有记录时为 `None`。
+    fn hash_for(&self, mode: FingerprintMode) -> Option<&str> {
+        if self.mode == mode {
+            Some(&self.content_hash)
+        } else {
+            self.weaker_hashes.get(&mode).map(String::as_str)
+        }
+    }
+
+    /// 已记录的指纹能否说明文件自那以后没有变化：大小与修改时间必须一致，
+    /// 且记录中有与当前模式、算法相同的哈希并且一致（`Fast` 不比较哈希）。
+    /// 较严格的记录同时保存了较宽松模式的哈希，因此同样可被较宽松的模式复用。
+    fn vouches_for(&self, current: &FileFingerprint) -> bool {
+        if self.mtime_unix_secs != current.mtime_unix_secs
+            || self.file_size_bytes != current.file_size_bytes
+        {
+            return false;
+        }
+        if current.mode == FingerprintMode::Fast {
+            return true;
+        }
+        self.hash_algorithm == current.hash_algorithm
+            && self.hash_for(current.mode) == Some(current.content_hash.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CacheEntry {
     fingerprint: FileFingerprint,
     metrics: FileMetrics,
+    /// 最近一次被分析（或复用）的时间；旧版缓存没有该字段，按载入时间计。
+    #[serde(default = "now_unix_secs")]
+    last_used_unix_secs: u64,
+}
+
+/// 一次清理移除的条目数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// 文件已被删除或移动。
+    pub missing: usize,
+    /// 超过指定天数未被使用。
+    pub stale: usize,
+}
+
+impl PruneStats {
+    pub fn total(&self) -> usize {
+        self.missing + self.stale
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisCache {
     version: u32,
-    entries: HashMap<String, CacheEntry>,
+    /// 按键排序保存，缓存文件在多次运行之间可以逐行对比。
+    entries: BTreeMap<String, CacheEntry>,
 }
 
 impl Default for AnalysisCache {
     fn default() -> Self {
         Self {
             version: CACHE_VERSION,
-            entries: HashMap::new(),
+            entries: BTreeMap::new(),
         }
     }
 }
@@ -62,14 +228,17 @@ impl AnalysisCache {
         safe_io::atomic_write_string(path, &content, safe_mode)
     }
 
-    pub fn lookup(&self, file_path: &Path, fingerprint: &FileFingerprint) -> Option<FileMetrics> {
-        let key = normalize_cache_key(file_path);
+    pub fn lookup(
+        &self,
+        file_path: &Path,
+        audio_stream: u32,
+        cue_track: Option<u32>,
+        fingerprint: &FileFingerprint,
+    ) -> Option<FileMetrics> {
+        let key = cache_key(file_path, audio_stream, cue_track);
         let entry = self.entries.get(&key)?;
 
-        if entry.fingerprint.mtime_unix_secs == fingerprint.mtime_unix_secs
-            && entry.fingerprint.file_size_bytes == fingerprint.file_size_bytes
-            && entry.fingerprint.content_sha256 == fingerprint.content_sha256
-        {
+        if entry.fingerprint.vouches_for(fingerprint) {
             let mut metrics = entry.metrics.clone();
             metrics.cache_hit = true;
             return Some(metrics);
@@ -78,19 +247,97 @@ impl AnalysisCache {
         None
     }
 
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 已有结果对应的指纹（按文件路径）：取同一文件任一音轨的条目，且文件大小与内容哈希（都有记录时）一致。
+    /// `rescore` 不重新读取文件，据此把指纹带入单文件结果与 SQLite 数据库。
+    pub fn fingerprints_for(&self, results: &[FileMetrics]) -> HashMap<String, FileFingerprint> {
+        let by_path: HashMap<&str, &FileFingerprint> = self
+            .entries
+            .values()
+            .map(|entry| (entry.metrics.file_path.as_str(), &entry.fingerprint))
+            .collect();
+        results
+            .iter()
+            .filter_map(|metrics| {
+                let fingerprint = by_path.get(metrics.file_path.as_str())?;
+                let same_content = fingerprint.file_size_bytes == metrics.file_size_bytes
+                    && match (&metrics.content_sha256, fingerprint.hash_algorithm) {
+                        (Some(hash), HashAlgorithm::Sha256) => fingerprint
+                            .hash_for(FingerprintMode::Strict)
+                            .is_none_or(|strict| strict == hash),
+                        _ => true,
+                    };
+                same_content.then(|| (metrics.file_path.clone(), (*fingerprint).clone()))
+            })
+            .collect()
+    }
+
+    /// 曲库是否离线（外接硬盘未挂载、NAS 不可达）：所有条目所在的目录都不存在。
+    /// 此时文件“不存在”不代表已被删除，不应清理。
+    pub fn is_offline(&self) -> bool {
+        !self.entries.is_empty()
+            && self.entries.values().all(|entry| {
+                !Path::new(&entry.metrics.file_path)
+                    .parent()
+                    .is_some_and(Path::is_dir)
+            })
+    }
+
+    /// 移除文件已不存在（删除或移动后旧路径失效）的条目，以及设置了 `max_age_days` 时
+    /// 超过该天数未被使用的条目，避免曲库反复整理后缓存无限增长。
+    pub fn prune(&mut self, max_age_days: Option<u64>, now_unix_secs: u64) -> PruneStats {
+        let cutoff = max_age_days.map(|days| now_unix_secs.saturating_sub(days * 86_400));
+        let mut stats = PruneStats::default();
+        self.entries.retain(|_, entry| {
+            if !Path::new(&entry.metrics.file_path).is_file() {
+                stats.missing += 1;
+                false
+            } else if cutoff.is_some_and(|cutoff| entry.last_used_unix_secs < cutoff) {
+                stats.stale += 1;
+                false
+            } else {
+                true
+            }
+        });
+        stats
+    }
+
     pub fn upsert(&mut self, file_path: &Path, fingerprint: FileFingerprint, metrics: FileMetrics) {
-        let key = normalize_cache_key(file_path);
+        let key = cache_key(
+            file_path,
+            metrics.audio_stream_index,
+            metrics.cue_track.as_ref().map(|track| track.number),
+        );
+        // 以较宽松的模式命中时保留原有的严格指纹，之后切回严格模式无需重新分析。
+        let fingerprint = match self.entries.get(&key) {
+            Some(existing)
+                if existing.fingerprint.mode.strength() > fingerprint.mode.strength()
+                    && existing.fingerprint.vouches_for(&fingerprint) =>
+            {
+                existing.fingerprint.clone()
+            }
+            _ => fingerprint,
+        };
         self.entries.insert(
             key,
             CacheEntry {
                 fingerprint,
                 metrics,
+                last_used_unix_secs: now_unix_secs(),
             },
         );
     }
 }
 
-pub fn fingerprint_file(path: &Path) -> Result<FileFingerprint> {
+pub fn fingerprint_file(path: &Path, options: FingerprintOptions) -> Result<FileFingerprint> {
+    let FingerprintOptions { algorithm, mode } = options;
     let metadata = path
         .metadata()
         .with_context(|| format!("读取文件元数据失败: {}", path.display()))?;
@@ -102,31 +349,135 @@ pub fn fingerprint_file(path: &Path) -> Result<FileFingerprint> {
         .unwrap_or(0);
 
     let file_size_bytes = metadata.len();
-    let content_sha256 = sha256_file(path)?;
+    let content_hash = hash_content(path, file_size_bytes, mode, algorithm)?;
+    let weaker_hashes = mode
+        .weaker_hashed_modes()
+        .iter()
+        .map(|&weaker| {
+            Ok((
+                weaker,
+                hash_content(path, file_size_bytes, weaker, algorithm)?,
+            ))
+        })
+        .collect::<Result<_>>()?;
 
     Ok(FileFingerprint {
         mtime_unix_secs,
         file_size_bytes,
-        content_sha256,
+        content_hash,
+        hash_algorithm: algorithm,
+        mode,
+        weaker_hashes,
     })
 }
 
-fn sha256_file(path: &Path) -> Result<String> {
+/// 按 `mode` 的读取范围计算内容哈希；`Fast` 不读取内容，返回空串。
+fn hash_content(
+    path: &Path,
+    file_size_bytes: u64,
+    mode: FingerprintMode,
+    algorithm: HashAlgorithm,
+) -> Result<String> {
+    match mode {
+        FingerprintMode::Strict => hash_file(path, algorithm),
+        FingerprintMode::Partial => hash_samples(
+            path,
+            file_size_bytes,
+            PARTIAL_CHUNK_BYTES,
+            &[0, file_size_bytes.saturating_sub(PARTIAL_CHUNK_BYTES)],
+            algorithm,
+        ),
+        FingerprintMode::Sampled => hash_samples(
+            path,
+            file_size_bytes,
+            SAMPLE_CHUNK_BYTES,
+            &[
+                0,
+                file_size_bytes.saturating_sub(SAMPLE_CHUNK_BYTES) / 2,
+                file_size_bytes.saturating_sub(SAMPLE_CHUNK_BYTES),
+            ],
+            algorithm,
+        ),
+        FingerprintMode::Fast => Ok(String::new()),
+    }
+}
+
+fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
     let file =
         File::open(path).with_context(|| format!("无法打开文件用于哈希: {}", path.display()))?;
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 64 * 1024];
+    hash_reader(BufReader::new(file), algorithm)
+}
+
+/// 只哈希文件大小与从 `offsets` 开始的各 `chunk_bytes` 字节；文件不大于各段之和时哈希全部内容。
+fn hash_samples(
+    path: &Path,
+    file_size_bytes: u64,
+    chunk_bytes: u64,
+    offsets: &[u64],
+    algorithm: HashAlgorithm,
+) -> Result<String> {
+    let total = chunk_bytes * offsets.len() as u64;
+    if file_size_bytes <= total {
+        return hash_file(path, algorithm);
+    }
+    let mut file =
+        File::open(path).with_context(|| format!("无法打开文件用于哈希: {}", path.display()))?;
+    let mut samples = Vec::with_capacity(8 + total as usize);
+    samples.extend_from_slice(&file_size_bytes.to_le_bytes());
+    for &offset in offsets {
+        file.seek(SeekFrom::Start(offset))?;
+        (&mut file)
+            .take(chunk_bytes)
+            .read_to_end(&mut samples)
+            .with_context(|| format!("读取文件抽样失败: {}", path.display()))?;
+    }
+    hash_reader(samples.as_slice(), algorithm)
+}
 
+fn hash_reader(reader: impl Read, algorithm: HashAlgorithm) -> Result<String> {
+    match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            feed_reader(reader, |chunk| hasher.update(chunk))?;
+            Ok(format!("{:x}", hasher.finalize()))
+        }
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            feed_reader(reader, |chunk| {
+                hasher.update(chunk);
+            })?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+        HashAlgorithm::Xxh3 => {
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            feed_reader(reader, |chunk| hasher.update(chunk))?;
+            Ok(format!("{:032x}", hasher.digest128()))
+        }
+    }
+}
+
+fn feed_reader(mut reader: impl Read, mut update: impl FnMut(&[u8])) -> Result<()> {
+    let mut buffer = [0u8; 64 * 1024];
     loop {
         let n = reader.read(&mut buffer)?;
         if n == 0 {
-            break;
+            return Ok(());
         }
-        hasher.update(&buffer[..n]);
+        update(&buffer[..n]);
     }
+}
 
-    Ok(format!("{:x}", hasher.finalize()))
+/// 缓存键：第 0 条音轨的整个文件沿用纯路径（兼容旧缓存），
+/// 其余音轨追加 `#a:<序号>`，CUE 拆分的音轨再追加 `#t:<音轨号>`。
+fn cache_key(path: &Path, audio_stream: u32, cue_track: Option<u32>) -> String {
+    let mut key = normalize_cache_key(path);
+    if audio_stream != 0 {
+        key.push_str(&format!("#a:{audio_stream}"));
+    }
+    if let Some(number) = cue_track {
+        key.push_str(&format!("#t:{number}"));
+    }
+    key
 }
 
 fn normalize_cache_key(path: &Path) -> String {
@@ -134,6 +485,10 @@ fn normalize_cache_key(path: &Path) -> String {
     canonical.to_string_lossy().into_owned()
 }
 
+pub fn now_unix_secs() -> u64 {
+    system_time_to_unix_secs(SystemTime::now()).unwrap_or(0)
+}
+
 fn system_time_to_unix_secs(time: SystemTime) -> Option<u64> {
     time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
 }
@@ -152,17 +507,30 @@ mod tests {
             rms_db_above_16k: None,
             rms_db_above_18k: None,
             rms_db_above_20k: None,
+            hf_band_rms_db: Default::default(),
             integrated_loudness_lufs: None,
             true_peak_dbtp: None,
+            ceiling_time_percent: None,
             processing_time_ms: 1,
             sample_rate_hz: None,
+            bit_depth: None,
+            effective_bit_depth: None,
             bitrate_kbps: None,
             channels: None,
             codec_name: None,
             container_format: None,
             duration_seconds: None,
             cache_hit: false,
+            sampled: false,
+            extra_of_album: None,
+            cue_track: None,
+            segment_analysis: None,
+            edge_levels: None,
+            audio_stream_index: 0,
+            audio_stream_count: Some(1),
             content_sha256: Some("abc".to_string()),
+            pcm_md5: None,
+            ffmpeg_version: None,
             error_codes: vec![],
         }
     }
@@ -174,11 +542,285 @@ mod tests {
         let fp = FileFingerprint {
             mtime_unix_secs: 1,
             file_size_bytes: 1,
-            content_sha256: "abc".to_string(),
+            content_hash: "abc".to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            mode: FingerprintMode::Strict,
+            weaker_hashes: BTreeMap::new(),
         };
         cache.upsert(path, fp.clone(), sample_metrics());
 
-        let hit = cache.lookup(path, &fp).expect("expected cache hit");
+        let hit = cache
+            .lookup(path, 0, None, &fp)
+            .expect("expected cache hit");
         assert!(hit.cache_hit);
     }
+
+    #[test]
+    fn test_cache_entries_are_per_audio_stream() {
+        let mut cache = AnalysisCache::default();
+        let path = Path::new("/tmp/concert.mkv");
+        let fp = FileFingerprint {
+            mtime_unix_secs: 1,
+            file_size_bytes: 1,
+            content_hash: "abc".to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            mode: FingerprintMode::Strict,
+            weaker_hashes: BTreeMap::new(),
+        };
+        let mut second = sample_metrics();
+        second.audio_stream_index = 1;
+        cache.upsert(path, fp.clone(), second);
+
+        assert!(cache.lookup(path, 0, None, &fp).is_none());
+        let hit = cache.lookup(path, 1, None, &fp).expect("stream 1 cached");
+        assert_eq!(hit.audio_stream_index, 1);
+    }
+
+    #[test]
+    fn test_lookup_requires_matching_hash_algorithm() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let path = dir.path().join("a.flac");
+        std::fs::write(&path, b"audio").expect("write");
+
+        let fingerprint = |algorithm| {
+            let options = FingerprintOptions {
+                algorithm,
+                mode: FingerprintMode::Strict,
+            };
+            fingerprint_file(&path, options).expect("fingerprint")
+        };
+        let sha = fingerprint(HashAlgorithm::Sha256);
+        let blake = fingerprint(HashAlgorithm::Blake3);
+        let xxh = fingerprint(HashAlgorithm::Xxh3);
+        assert_eq!(sha.content_hash.len(), 64);
+        assert_eq!(blake.content_hash.len(), 64);
+        assert_eq!(xxh.content_hash.len(), 32);
+        assert_ne!(sha.content_hash, blake.content_hash);
+
+        let mut cache = AnalysisCache::default();
+        cache.upsert(&path, blake.clone(), sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &blake).is_some());
+        assert!(cache.lookup(&path, 0, None, &sha).is_none());
+    }
+
+    #[test]
+    fn test_fast_fingerprints_reuse_strict_entries() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let path = dir.path().join("a.flac");
+        let mut content = vec![0u8; 4 * SAMPLE_CHUNK_BYTES as usize];
+        std::fs::write(&path, &content).expect("write");
+        let fingerprint = |mode| {
+            let options = FingerprintOptions {
+                algorithm: HashAlgorithm::Xxh3,
+                mode,
+            };
+            fingerprint_file(&path, options).expect("fingerprint")
+        };
+        let strict = fingerprint(FingerprintMode::Strict);
+        let sampled = fingerprint(FingerprintMode::Sampled);
+        let fast = fingerprint(FingerprintMode::Fast);
+        assert!(fast.content_hash.is_empty());
+        assert_ne!(sampled.content_hash, strict.content_hash);
+
+        assert_eq!(
+            strict.hash_for(FingerprintMode::Sampled),
+            Some(sampled.content_hash.as_str())
+        );
+
+        // 严格指纹同时记录抽样哈希，抽样模式与同类哈希比较后复用。
+        let mut cache = AnalysisCache::default();
+        cache.upsert(&path, strict.clone(), sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &sampled).is_some());
+        assert!(cache.lookup(&path, 0, None, &fast).is_some());
+        let resampled = FileFingerprint {
+            content_hash: "changed".to_string(),
+            ..sampled.clone()
+        };
+        assert!(cache.lookup(&path, 0, None, &resampled).is_none());
+        // 快速模式命中后写回不会降级已有的严格指纹。
+        cache.upsert(&path, fast.clone(), sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &strict).is_some());
+        assert!(cache.lookup(&path, 0, None, &sampled).is_some());
+
+        // 旧版缓存的严格指纹没有抽样哈希，无法与抽样指纹比较，不复用。
+        let mut cache = AnalysisCache::default();
+        let legacy = FileFingerprint {
+            weaker_hashes: BTreeMap::new(),
+            ..strict.clone()
+        };
+        cache.upsert(&path, legacy, sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &sampled).is_none());
+        assert!(cache.lookup(&path, 0, None, &fast).is_some());
+
+        // 抽样范围之外的改动只有完整哈希能发现；抽样范围内的改动会被抽样指纹发现。
+        let mut cache = AnalysisCache::default();
+        cache.upsert(&path, sampled.clone(), sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &strict).is_none());
+        content[SAMPLE_CHUNK_BYTES as usize + 1] = 1;
+        std::fs::write(&path, &content).expect("rewrite");
+        let unsampled_edit = FileFingerprint {
+            mtime_unix_secs: sampled.mtime_unix_secs,
+            ..fingerprint(FingerprintMode::Sampled)
+        };
+        assert!(cache.lookup(&path, 0, None, &unsampled_edit).is_some());
+        content[0] = 1;
+        std::fs::write(&path, &content).expect("rewrite");
+        let sampled_edit = FileFingerprint {
+            mtime_unix_secs: sampled.mtime_unix_secs,
+            ..fingerprint(FingerprintMode::Sampled)
+        };
+        assert!(cache.lookup(&path, 0, None, &sampled_edit).is_none());
+        assert_eq!("FAST".parse::<FingerprintMode>(), Ok(FingerprintMode::Fast));
+    }
+
+    #[test]
+    fn test_partial_fingerprint_hashes_head_and_tail() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let path = dir.path().join("a.dsf");
+        let chunk = PARTIAL_CHUNK_BYTES as usize;
+        let mut content = vec![0u8; 3 * chunk];
+        std::fs::write(&path, &content).expect("write");
+        let fingerprint = |mode| {
+            let options = FingerprintOptions {
+                algorithm: HashAlgorithm::Blake3,
+                mode,
+            };
+            fingerprint_file(&path, options).expect("fingerprint")
+        };
+        let partial = fingerprint(FingerprintMode::Partial);
+        assert_ne!(
+            partial.content_hash,
+            fingerprint(FingerprintMode::Strict).content_hash
+        );
+        assert_eq!(partial.mode.as_str(), "partial");
+
+        // 中间的改动不影响首尾哈希，结尾的改动会被发现。
+        content[chunk + 1] = 1;
+        std::fs::write(&path, &content).expect("rewrite");
+        assert_eq!(
+            fingerprint(FingerprintMode::Partial).content_hash,
+            partial.content_hash
+        );
+        content[3 * chunk - 1] = 1;
+        std::fs::write(&path, &content).expect("rewrite");
+        assert_ne!(
+            fingerprint(FingerprintMode::Partial).content_hash,
+            partial.content_hash
+        );
+
+        // 严格模式的缓存可被首尾指纹复用，反之则不行。
+        let mut cache = AnalysisCache::default();
+        let strict = fingerprint(FingerprintMode::Strict);
+        let partial = fingerprint(FingerprintMode::Partial);
+        cache.upsert(&path, partial.clone(), sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &strict).is_none());
+        cache.upsert(&path, strict, sample_metrics());
+        assert!(cache.lookup(&path, 0, None, &partial).is_some());
+    }
+
+    #[test]
+    fn test_fingerprints_for_existing_results() {
+        let fp = FileFingerprint {
+            mtime_unix_secs: 1,
+            file_size_bytes: 1,
+            content_hash: "abc".to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            mode: FingerprintMode::Strict,
+            weaker_hashes: BTreeMap::new(),
+        };
+        let mut cache = AnalysisCache::default();
+        cache.upsert(Path::new("/tmp/a.flac"), fp, sample_metrics());
+
+        let same = FileMetrics {
+            content_sha256: Some("abc".to_string()),
+            ..sample_metrics()
+        };
+        let changed = FileMetrics {
+            content_sha256: Some("def".to_string()),
+            ..sample_metrics()
+        };
+        let other = FileMetrics {
+            file_path: "/tmp/b.flac".to_string(),
+            ..sample_metrics()
+        };
+        assert_eq!(cache.fingerprints_for(&[same, other]).len(), 1);
+        assert!(cache.fingerprints_for(&[changed]).is_empty());
+    }
+
+    #[test]
+    fn test_prune_removes_missing_and_stale_entries() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let fp = FileFingerprint {
+            mtime_unix_secs: 1,
+            file_size_bytes: 1,
+            content_hash: "abc".to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            mode: FingerprintMode::Strict,
+            weaker_hashes: BTreeMap::new(),
+        };
+        let mut cache = AnalysisCache::default();
+        for name in ["kept.flac", "old.flac", "moved.flac"] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"audio").expect("write");
+            let mut metrics = sample_metrics();
+            metrics.file_path = path.to_string_lossy().into_owned();
+            cache.upsert(&path, fp.clone(), metrics);
+        }
+        std::fs::remove_file(dir.path().join("moved.flac")).expect("remove");
+        let old_key = cache_key(&dir.path().join("old.flac"), 0, None);
+        cache
+            .entries
+            .get_mut(&old_key)
+            .expect("old entry")
+            .last_used_unix_secs = 1;
+
+        let now = now_unix_secs();
+        assert_eq!(
+            cache.clone().prune(None, now),
+            PruneStats {
+                missing: 1,
+                stale: 0
+            }
+        );
+        let stats = cache.prune(Some(30), now);
+        assert_eq!(
+            stats,
+            PruneStats {
+                missing: 1,
+                stale: 1
+            }
+        );
+        assert_eq!(cache.len(), 1);
+        assert!(!cache.is_offline());
+        let mut offline = AnalysisCache::default();
+        offline.upsert(
+            Path::new("/Volumes/Unplugged/a.flac"),
+            fp.clone(),
+            FileMetrics {
+                file_path: "/Volumes/Unplugged/a.flac".to_string(),
+                ..sample_metrics()
+            },
+        );
+        assert!(offline.is_offline());
+
+        // 旧版缓存条目没有使用时间，按载入时间计，不会在升级后被立即清理。
+        let legacy: CacheEntry = serde_json::from_value(serde_json::json!({
+            "fingerprint": fp,
+            "metrics": sample_metrics(),
+        }))
+        .expect("legacy entry");
+        assert!(legacy.last_used_unix_secs >= now);
+    }
+
+    #[test]
+    fn test_legacy_fingerprint_defaults_to_sha256() {
+        let fp: FileFingerprint = serde_json::from_str(
+            r#"{"mtime_unix_secs":1,"file_size_bytes":2,"content_sha256":"abc"}"#,
+        )
+        .expect("legacy fingerprint");
+        assert_eq!(fp.content_hash, "abc");
+        assert_eq!(fp.hash_algorithm, HashAlgorithm::Sha256);
+        assert_eq!(fp.mode, FingerprintMode::Strict);
+        assert_eq!("BLAKE3".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Blake3));
+    }
 }
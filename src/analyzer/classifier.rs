@@ -0,0 +1,262 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::metrics::FileMetrics;
+use super::safe_io;
+
+/// 模型文件格式版本，格式不兼容时递增。
+const MODEL_VERSION: u32 = 1;
+
+/// 分类器使用的特征，按顺序与模型中的权重一一对应。
+/// 高频能量都取相对整体 RMS 的差值，安静的原声/古典录音不会因为绝对电平低而被误判。
+pub const FEATURE_NAMES: [&str; 5] = [
+    "hf16MinusRms",
+    "hf18MinusRms",
+    "hf20MinusRms",
+    "hf18MinusHf16",
+    "hf20MinusHf18",
+];
+
+/// 特征所需的高频频点（Hz）；使用分类器或训练时无论 `--hf-bands` 如何设置都会测量。
+pub const FEATURE_HF_BANDS: [u32; 3] = [16_000, 18_000, 20_000];
+
+const TRAIN_ITERATIONS: usize = 3000;
+const LEARNING_RATE: f64 = 0.5;
+const L2_PENALTY: f64 = 1e-3;
+
+/// 提取分类特征；缺少整体 RMS 或 16k/18k/20k 任一频段时返回 `None`（回退到固定阈值）。
+pub fn features(metrics: &FileMetrics) -> Option<[f64; FEATURE_NAMES.len()]> {
+    let rms = metrics.overall_rms_db?;
+    let hf16 = metrics.hf_band_rms(16_000)?;
+    let hf18 = metrics.hf_band_rms(18_000)?;
+    let hf20 = metrics.hf_band_rms(20_000)?;
+    Some([hf16 - rms, hf18 - rms, hf20 - rms, hf18 - hf16, hf20 - hf18])
+}
+
+/// 有损来源分类器：对标准化后的频谱特征做逻辑回归，输出“由有损转码而来”的概率。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LossyClassifier {
+    version: u32,
+    features: Vec<String>,
+    means: Vec<f64>,
+    scales: Vec<f64>,
+    weights: Vec<f64>,
+    bias: f64,
+    /// 概率达到该值即判为有损来源。
+    threshold: f64,
+    lossless_samples: usize,
+    transcode_samples: usize,
+}
+
+impl LossyClassifier {
+    /// 用已知无损与已知转码两组指标训练；两类样本按数量加权平衡，避免偏向样本多的一类。
+    pub fn train(lossless: &[FileMetrics], transcode: &[FileMetrics]) -> Result<Self> {
+        let samples: Vec<([f64; FEATURE_NAMES.len()], f64)> = lossless
+            .iter()
+            .filter_map(|m| features(m).map(|x| (x, 0.0)))
+            .chain(
+                transcode
+                    .iter()
+                    .filter_map(|m| features(m).map(|x| (x, 1.0))),
+            )
+            .collect();
+        let transcode_samples = samples.iter().filter(|(_, y)| *y > 0.5).count();
+        let lossless_samples = samples.len() - transcode_samples;
+        if lossless_samples == 0 || transcode_samples == 0 {
+            return Err(anyhow!(
+                "[E_TRAIN_INPUT] 训练需要两类样本各至少一个具备完整高频指标的文件（无损 {lossless_samples}，转码 {transcode_samples}）"
+            ));
+        }
+
+        let dims = FEATURE_NAMES.len();
+        let count = samples.len() as f64;
+        let means: Vec<f64> = (0..dims)
+            .map(|j| samples.iter().map(|(x, _)| x[j]).sum::<f64>() / count)
+            .collect();
+        let scales: Vec<f64> = (0..dims)
+            .map(|j| {
+                let variance = samples
+                    .iter()
+                    .map(|(x, _)| (x[j] - means[j]).powi(2))
+                    .sum::<f64>()
+                    / count;
+                variance.sqrt().max(1e-6)
+            })
+            .collect();
+        let class_weight = |y: f64| {
+            let n = if y > 0.5 {
+                transcode_samples
+            } else {
+                lossless_samples
+            };
+            count / (2.0 * n as f64)
+        };
+
+        let mut model = Self {
+            version: MODEL_VERSION,
+            features: FEATURE_NAMES.iter().map(|name| name.to_string()).collect(),
+            means,
+            scales,
+            weights: vec![0.0; dims],
+            bias: 0.0,
+            threshold: 0.5,
+            lossless_samples,
+            transcode_samples,
+        };
+        // 批量梯度下降：训练集通常只有几百到几千个文件，无需更复杂的优化器。
+        for _ in 0..TRAIN_ITERATIONS {
+            let mut grad_w = vec![0.0; dims];
+            let mut grad_b = 0.0;
+            for (x, y) in &samples {
+                let z = model.standardize(x);
+                let error = (model.probability_of(&z) - y) * class_weight(*y);
+                for (grad, value) in grad_w.iter_mut().zip(&z) {
+                    *grad += error * value;
+                }
+                grad_b += error;
+            }
+            for (weight, grad) in model.weights.iter_mut().zip(&grad_w) {
+                *weight -= LEARNING_RATE * (grad / count + L2_PENALTY * *weight);
+            }
+            model.bias -= LEARNING_RATE * grad_b / count;
+        }
+        Ok(model)
+    }
+
+    /// 在训练样本上的准确率（0-1），用于训练后的提示。
+    pub fn accuracy(&self, lossless: &[FileMetrics], transcode: &[FileMetrics]) -> f64 {
+        let labeled: Vec<bool> = lossless
+            .iter()
+            .filter_map(|m| self.is_transcode(m).map(|predicted| !predicted))
+            .chain(transcode.iter().filter_map(|m| self.is_transcode(m)))
+            .collect();
+        if labeled.is_empty() {
+            return 0.0;
+        }
+        labeled.iter().filter(|correct| **correct).count() as f64 / labeled.len() as f64
+    }
+
+    /// 判断是否为有损来源；缺少特征时返回 `None`。
+    pub fn is_transcode(&self, metrics: &FileMetrics) -> Option<bool> {
+        self.transcode_probability(metrics)
+            .map(|p| p >= self.threshold)
+    }
+
+    pub fn transcode_probability(&self, metrics: &FileMetrics) -> Option<f64> {
+        features(metrics).map(|x| self.probability_of(&self.standardize(&x)))
+    }
+
+    fn standardize(&self, x: &[f64]) -> Vec<f64> {
+        x.iter()
+            .zip(self.means.iter().zip(&self.scales))
+            .map(|(value, (mean, scale))| (value - mean) / scale)
+            .collect()
+    }
+
+    fn probability_of(&self, z: &[f64]) -> f64 {
+        let logit = self.bias + z.iter().zip(&self.weights).map(|(a, b)| a * b).sum::<f64>();
+        1.0 / (1.0 + (-logit).exp())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("[E_LOSSY_MODEL] 无法读取模型文件: {}", path.display()))?;
+        let model: Self = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("[E_LOSSY_MODEL] 模型文件格式错误 {}: {e}", path.display()))?;
+        let dims = FEATURE_NAMES.len();
+        if model.version != MODEL_VERSION
+            || model.features != FEATURE_NAMES
+            || [&model.means, &model.scales, &model.weights]
+                .iter()
+                .any(|values| values.len() != dims)
+        {
+            return Err(anyhow!(
+                "[E_LOSSY_MODEL] 模型与当前版本的特征不兼容，请重新训练: {}",
+                path.display()
+            ));
+        }
+        Ok(model)
+    }
+
+    pub fn save(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("序列化模型失败")?;
+        safe_io::atomic_write_string(path, &content, safe_mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scoring::{QualityScorer, QualityStatus};
+    use tempfile::TempDir;
+
+    /// 构造一条无损容器的指标：`rms` 为整体电平，`hf*` 为各高频段相对整体电平的落差。
+    fn flac(rms: f64, hf16: f64, hf18: f64, hf20: f64) -> FileMetrics {
+        FileMetrics {
+            file_path: "/m/a.flac".to_string(),
+            codec_name: Some("flac".to_string()),
+            lra: Some(8.0),
+            integrated_loudness_lufs: Some(rms + 3.0),
+            true_peak_dbtp: Some(-1.0),
+            overall_rms_db: Some(rms),
+            rms_db_above_16k: Some(rms + hf16),
+            rms_db_above_18k: Some(rms + hf18),
+            rms_db_above_20k: Some(rms + hf20),
+            ..FileMetrics::default()
+        }
+    }
+
+    #[test]
+    fn test_classifier_separates_quiet_lossless_from_transcodes() {
+        let lossless: Vec<FileMetrics> = (0..12)
+            .map(|i| {
+                let rms = -14.0 - 3.0 * i as f64;
+                flac(rms, -38.0 - i as f64, -45.0 - i as f64, -52.0 - i as f64)
+            })
+            .collect();
+        let transcode: Vec<FileMetrics> = (0..12)
+            .map(|i| {
+                let rms = -12.0 - 2.0 * i as f64;
+                flac(rms, -42.0 - i as f64, -95.0 - i as f64, -100.0 - i as f64)
+            })
+            .collect();
+        let model = LossyClassifier::train(&lossless, &transcode).expect("train");
+        assert_eq!(model.accuracy(&lossless, &transcode), 1.0);
+
+        // 安静的原声录音：18 kHz 以上绝对电平低于 -85 dB，但相对整体电平并不异常。
+        let quiet_acoustic = flac(-45.0, -40.0, -47.0, -54.0);
+        assert!(quiet_acoustic.rms_db_above_18k.unwrap() < -85.0);
+        let fixed = QualityScorer::new();
+        assert_eq!(
+            fixed.analyze_file(&quiet_acoustic).status,
+            QualityStatus::Suspicious
+        );
+        let learned = QualityScorer::new().with_lossy_classifier(model.clone());
+        assert_ne!(
+            learned.analyze_file(&quiet_acoustic).status,
+            QualityStatus::Suspicious
+        );
+        assert_eq!(
+            learned.analyze_file(&transcode[3]).status,
+            QualityStatus::Suspicious
+        );
+
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join("lossy_model.json");
+        model.save(&path, true).expect("save");
+        let loaded = LossyClassifier::load(&path).expect("load");
+        let probability = |m: &LossyClassifier| m.transcode_probability(&quiet_acoustic).unwrap();
+        assert!((probability(&loaded) - probability(&model)).abs() < 1e-9);
+
+        std::fs::write(&path, r#"{"version":1,"features":["x"],"means":[],"scales":[],"weights":[],"bias":0,"threshold":0.5,"losslessSamples":1,"transcodeSamples":1}"#)
+            .expect("write");
+        let err = LossyClassifier::load(&path).expect_err("incompatible");
+        assert!(err.to_string().contains("E_LOSSY_MODEL"));
+        assert!(LossyClassifier::train(&lossless, &[])
+            .expect_err("one class")
+            .to_string()
+            .contains("E_TRAIN_INPUT"));
+    }
+}
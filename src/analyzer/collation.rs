@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::Locale;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// 默认排序规则：ICU 根排序（与具体语言无关的 Unicode 排序）。
+pub const DEFAULT_COLLATION: &str = "root";
+
+/// 文件名/专辑名排序器：按 ICU 本地化规则或原始字节顺序比较路径。
+pub enum FileNameCollator {
+    /// 原始字节顺序（旧版本行为）。
+    Bytes,
+    Icu {
+        locale: String,
+        collator: Box<Collator>,
+    },
+}
+
+impl FileNameCollator {
+    /// 解析 `--collation` 参数：`bytes`、`root`，或 BCP 47 语言标签（如 `zh`、`ja`、`de`、`zh-u-co-stroke`）。
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec.eq_ignore_ascii_case("bytes") {
+            return Ok(Self::Bytes);
+        }
+
+        let tag = if spec.eq_ignore_ascii_case(DEFAULT_COLLATION) {
+            "und"
+        } else {
+            spec
+        };
+        let locale = Locale::from_str(tag)
+            .map_err(|e| anyhow!("collation 参数错误: 无法解析语言标签 {spec} ({e})"))?;
+
+        let mut options = CollatorOptions::new();
+        // 区分重音但不区分大小写；数字按数值比较，使 "Track 2" 排在 "Track 10" 之前。
+        options.strength = Some(Strength::Secondary);
+        options.numeric = Some(Numeric::On);
+        let collator = Collator::try_new(&(&locale).into(), options)
+            .map_err(|e| anyhow!("collation 参数错误: 不支持的排序规则 {spec} ({e})"))?;
+
+        Ok(Self::Icu {
+            locale: spec.to_owned(),
+            collator: Box::new(collator),
+        })
+    }
+
+    pub fn compare(&self, left: &str, right: &str) -> Ordering {
+        match self {
+            Self::Bytes => left.cmp(right),
+            // 排序规则视为相等的名称（如仅大小写不同）再按字节顺序区分，保证结果稳定。
+            Self::Icu { collator, .. } => {
+                collator.compare(left, right).then_with(|| left.cmp(right))
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Bytes => "bytes",
+            Self::Icu { locale, .. } => locale,
+        }
+    }
+}
+
+impl Default for FileNameCollator {
+    fn default() -> Self {
+        Self::parse(DEFAULT_COLLATION).expect("root collation is always available")
+    }
+}
+
+impl fmt::Debug for FileNameCollator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FileNameCollator({})", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collator: &FileNameCollator, names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        names.sort_by(|a, b| collator.compare(a, b));
+        names
+    }
+
+    #[test]
+    fn test_root_collation_orders_naturally() {
+        let collator = FileNameCollator::default();
+        assert_eq!(
+            sorted(
+                &collator,
+                &["Zoë/b.flac", "Éclair/a.flac", "ant/10.flac", "ant/2.flac"]
+            ),
+            vec!["ant/2.flac", "ant/10.flac", "Éclair/a.flac", "Zoë/b.flac"]
+        );
+
+        let bytes = FileNameCollator::parse("bytes").expect("bytes");
+        assert_eq!(
+            sorted(&bytes, &["b", "Éclair", "a"]),
+            vec!["a", "b", "Éclair"]
+        );
+    }
+
+    #[test]
+    fn test_chinese_pinyin_collation() {
+        let collator = FileNameCollator::parse("zh").expect("zh collation");
+        // 拼音顺序: ài (爱) < bái (白) < chūn (春)
+        assert_eq!(
+            sorted(&collator, &["春.flac", "爱.flac", "白.flac"]),
+            vec!["爱.flac", "白.flac", "春.flac"]
+        );
+        assert!(FileNameCollator::parse("not a locale!").is_err());
+    }
+}
@@ -0,0 +1,805 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use super::metrics::FileMetrics;
+use super::safe_io;
+use super::schema;
+use super::scoring::{QualityAnalysis, QualityStatus};
+use super::versions::{Side, Verdict, VersionComparison};
+
+/// HTML 报告中列出的分数变化最大的曲目数量。
+const TOP_MOVERS: usize = 20;
+/// 分数分布直方图的分箱宽度。
+const HISTOGRAM_BIN: i32 = 10;
+/// 差异清单的文件名，默认写在新一次运行的目录中。
+pub const DIFF_CSV_FILE_NAME: &str = "audio_quality_diff.csv";
+
+/// 两次运行中同一曲目的分数对比。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScorePair {
+    /// 相对各自分析根目录的路径（含音轨/CUE 后缀），用于匹配两次运行中的同一曲目。
+    pub key: String,
+    pub old_score: i32,
+    pub new_score: i32,
+    pub old_status: String,
+    pub new_status: String,
+}
+
+impl ScorePair {
+    pub fn delta(&self) -> i32 {
+        self.new_score - self.old_score
+    }
+
+    pub fn status_changed(&self) -> bool {
+        self.old_status != self.new_status
+    }
+}
+
+/// 差异清单中的变化类型，按需要关注的程度排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffKind {
+    /// 只出现在旧一次运行中，迁移时可能丢失。
+    Removed,
+    Degraded,
+    /// 分数不变但状态变化。
+    StatusChanged,
+    Improved,
+    Added,
+}
+
+impl DiffKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Removed => "已移除",
+            Self::Degraded => "分数下降",
+            Self::StatusChanged => "状态变化",
+            Self::Improved => "分数提升",
+            Self::Added => "新增",
+        }
+    }
+}
+
+impl Serialize for DiffKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.label())
+    }
+}
+
+/// 差异清单中的一行；新增或移除的曲目只有一侧的分数与状态。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffRecord {
+    #[serde(rename = "变化类型")]
+    pub kind: DiffKind,
+    #[serde(rename = "曲目")]
+    pub key: String,
+    #[serde(rename = "旧分数")]
+    pub old_score: Option<i32>,
+    #[serde(rename = "新分数")]
+    pub new_score: Option<i32>,
+    #[serde(rename = "分数变化")]
+    pub score_delta: Option<i32>,
+    #[serde(rename = "旧状态")]
+    pub old_status: Option<String>,
+    #[serde(rename = "新状态")]
+    pub new_status: Option<String>,
+}
+
+/// 两次运行（如原版与重制版曲库）的对比结果。
+#[derive(Debug, Clone, Default)]
+pub struct RunComparison {
+    pub old_label: String,
+    pub new_label: String,
+    pub old_scores: Vec<i32>,
+    pub new_scores: Vec<i32>,
+    pub pairs: Vec<ScorePair>,
+    /// 只出现在新一次运行中的曲目。
+    pub added: Vec<ScoreSide>,
+    /// 只出现在旧一次运行中的曲目（已移除）。
+    pub removed: Vec<ScoreSide>,
+    /// 匹配曲目中疑似由另一侧有损衍生的曲目及衍生的一侧（`A` 为旧、`B` 为新）。
+    pub derivatives: Vec<(String, Side)>,
+}
+
+/// 只出现在一次运行中的曲目。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreSide {
+    pub key: String,
+    pub score: i32,
+    pub status: String,
+}
+
+/// 与基线运行（`--baseline`）中同一曲目相比的变化。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BaselineDelta {
+    pub previous_score: i32,
+    /// 本次分数减去基线分数，负数表示变差。
+    pub score_delta: i32,
+    pub previous_status: QualityStatus,
+    pub status_changed: bool,
+}
+
+/// 某次运行的报告目录（已规范化）：参数为目录时即该目录，否则为 `analysis_data.json` 所在目录。
+pub fn run_dir(path: &Path) -> PathBuf {
+    let json_path = run_data_path(path);
+    json_path
+        .canonicalize()
+        .unwrap_or(json_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+fn run_data_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join(schema::ANALYSIS_DATA_FILE_NAME)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// 读取某次运行写出的 `analysis_data.json`；参数为目录时读取其中的该文件。
+/// 同时返回该运行的曲库根目录：报告写在 `--output-dir` 下时取自来源标记，否则为报告目录本身。
+pub fn load_run(path: &Path) -> Result<(Vec<FileMetrics>, PathBuf)> {
+    let json_path = run_data_path(path);
+    let content = std::fs::read_to_string(&json_path).with_context(|| {
+        format!(
+            "[E_COMPARE_INPUT] 无法读取运行结果: {}",
+            json_path.display()
+        )
+    })?;
+    let metrics = schema::parse_analysis_data(&content).with_context(|| {
+        format!(
+            "[E_COMPARE_INPUT] 运行结果格式错误: {}",
+            json_path.display()
+        )
+    })?;
+    // 结果中的路径是绝对路径，根目录需规范化后才能去掉前缀。
+    let run_dir = run_dir(path);
+    let root = schema::read_source_marker(&run_dir).unwrap_or(run_dir);
+    Ok((metrics, root))
+}
+
+/// 按相对路径配对两次运行的曲目，使不同目录下结构相同的两个版本也能一一对应。
+/// 路径不同的曲目再按去掉扩展名的路径配对（如同一专辑的 FLAC 与 MP3 版本），两侧各只有一个候选时才配对。
+pub fn compare_runs(
+    old: &[QualityAnalysis],
+    old_root: &Path,
+    new: &[QualityAnalysis],
+    new_root: &Path,
+) -> RunComparison {
+    let old_by_key: HashMap<String, &QualityAnalysis> = old
+        .iter()
+        .map(|analysis| (record_key(analysis, old_root), analysis))
+        .collect();
+    let new_keys: HashMap<String, &QualityAnalysis> = new
+        .iter()
+        .map(|analysis| (record_key(analysis, new_root), analysis))
+        .collect();
+
+    let mut comparison = RunComparison {
+        old_label: old_root.display().to_string(),
+        new_label: new_root.display().to_string(),
+        old_scores: old.iter().map(|a| a.quality_score).collect(),
+        new_scores: new.iter().map(|a| a.quality_score).collect(),
+        ..RunComparison::default()
+    };
+
+    let mut matched: Vec<(&str, &QualityAnalysis, &QualityAnalysis)> = Vec::new();
+    let mut added: HashMap<String, Vec<(&str, &QualityAnalysis)>> = HashMap::new();
+    for (key, new_analysis) in &new_keys {
+        match old_by_key.get(key) {
+            Some(old_analysis) => matched.push((key, old_analysis, new_analysis)),
+            None => added
+                .entry(stem_key(new_analysis, new_root))
+                .or_default()
+                .push((key, new_analysis)),
+        }
+    }
+    let mut removed: HashMap<String, Vec<(&str, &QualityAnalysis)>> = HashMap::new();
+    for (key, old_analysis) in &old_by_key {
+        if !new_keys.contains_key(key) {
+            removed
+                .entry(stem_key(old_analysis, old_root))
+                .or_default()
+                .push((key, old_analysis));
+        }
+    }
+    for (stem, new_side) in &mut added {
+        if let (Some(old_side), [(key, new_analysis)]) =
+            (removed.get_mut(stem), new_side.as_slice())
+        {
+            if let [(_, old_analysis)] = old_side.as_slice() {
+                matched.push((key, old_analysis, new_analysis));
+                old_side.clear();
+                new_side.clear();
+            }
+        }
+    }
+
+    for (key, old_analysis, new_analysis) in matched {
+        let versions = VersionComparison {
+            a: old_analysis,
+            b: new_analysis,
+        };
+        if let Verdict::LossyDerivative { derived } = versions.verdict() {
+            comparison.derivatives.push((key.to_string(), derived));
+        }
+        comparison.pairs.push(ScorePair {
+            key: key.to_string(),
+            old_score: old_analysis.quality_score,
+            new_score: new_analysis.quality_score,
+            old_status: old_analysis.status.to_string(),
+            new_status: new_analysis.status.to_string(),
+        });
+    }
+    comparison.added = added
+        .values()
+        .flatten()
+        .map(|(key, analysis)| ScoreSide::new(key, analysis))
+        .collect();
+    comparison.removed = removed
+        .values()
+        .flatten()
+        .map(|(key, analysis)| ScoreSide::new(key, analysis))
+        .collect();
+
+    comparison.pairs.sort_by(|a, b| {
+        b.delta()
+            .abs()
+            .cmp(&a.delta().abs())
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    comparison.added.sort_by(|a, b| a.key.cmp(&b.key));
+    comparison.removed.sort_by(|a, b| a.key.cmp(&b.key));
+    comparison.derivatives.sort_by(|a, b| a.0.cmp(&b.0));
+    comparison
+}
+
+impl ScoreSide {
+    fn new(key: &str, analysis: &QualityAnalysis) -> Self {
+        Self {
+            key: key.to_string(),
+            score: analysis.quality_score,
+            status: analysis.status.to_string(),
+        }
+    }
+}
+
+/// 为基线中也存在的曲目（按相对各自根目录的路径匹配）填写 `baseline`，返回匹配的曲目数。
+/// 基线应先用本次的评分设置重新评分，分数变化才只反映文件本身的变化。
+pub fn apply_baseline(
+    analyses: &mut [QualityAnalysis],
+    root: &Path,
+    baseline: &[QualityAnalysis],
+    baseline_root: &Path,
+) -> usize {
+    let previous: HashMap<String, &QualityAnalysis> = baseline
+        .iter()
+        .map(|analysis| (record_key(analysis, baseline_root), analysis))
+        .collect();
+    let mut matched = 0;
+    for analysis in analyses {
+        let Some(old) = previous.get(&record_key(analysis, root)) else {
+            continue;
+        };
+        analysis.baseline = Some(BaselineDelta {
+            previous_score: old.quality_score,
+            score_delta: analysis.quality_score - old.quality_score,
+            previous_status: old.status.clone(),
+            status_changed: old.status != analysis.status,
+        });
+        matched += 1;
+    }
+    matched
+}
+
+/// 相比基线分数下降的曲目，降幅最大的在前。
+pub fn regressions(analyses: &[QualityAnalysis]) -> Vec<&QualityAnalysis> {
+    let mut regressed: Vec<&QualityAnalysis> = analyses
+        .iter()
+        .filter(|a| a.baseline.as_ref().is_some_and(|b| b.score_delta < 0))
+        .collect();
+    regressed.sort_by_key(|a| a.baseline.as_ref().map_or(0, |b| b.score_delta));
+    regressed
+}
+
+fn record_key(analysis: &QualityAnalysis, root: &Path) -> String {
+    track_key(Path::new(&analysis.file_path), analysis, root)
+}
+
+/// 去掉扩展名的 `record_key`，用于配对转换过格式的同一曲目。
+fn stem_key(analysis: &QualityAnalysis, root: &Path) -> String {
+    track_key(
+        &Path::new(&analysis.file_path).with_extension(""),
+        analysis,
+        root,
+    )
+}
+
+fn track_key(path: &Path, analysis: &QualityAnalysis, root: &Path) -> String {
+    let mut key = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    if analysis.metrics.audio_stream_index != 0 {
+        let _ = write!(key, "#a:{}", analysis.metrics.audio_stream_index);
+    }
+    if let Some(track) = &analysis.metrics.cue_track {
+        let _ = write!(key, "#t:{}", track.number);
+    }
+    key
+}
+
+impl RunComparison {
+    pub fn mean_delta(&self) -> Option<f64> {
+        if self.pairs.is_empty() {
+            return None;
+        }
+        let total: i64 = self.pairs.iter().map(|p| i64::from(p.delta())).sum();
+        Some(total as f64 / self.pairs.len() as f64)
+    }
+
+    /// 匹配曲目的状态变化（旧状态 → 新状态）及曲目数，数量多的在前。
+    pub fn status_transitions(&self) -> Vec<((&str, &str), usize)> {
+        let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for pair in self.pairs.iter().filter(|p| p.status_changed()) {
+            *counts
+                .entry((pair.old_status.as_str(), pair.new_status.as_str()))
+                .or_default() += 1;
+        }
+        let mut transitions: Vec<_> = counts.into_iter().collect();
+        transitions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        transitions
+    }
+
+    /// 所有差异：移除、分数下降、状态变化、分数提升、新增；分数与状态都不变的曲目不列出。
+    /// 同类内变化幅度大的在前，其余按曲目排列。
+    pub fn diff_records(&self) -> Vec<DiffRecord> {
+        let side = |kind, side: &ScoreSide| DiffRecord {
+            kind,
+            key: side.key.clone(),
+            old_score: (kind == DiffKind::Removed).then_some(side.score),
+            new_score: (kind == DiffKind::Added).then_some(side.score),
+            score_delta: None,
+            old_status: (kind == DiffKind::Removed).then(|| side.status.clone()),
+            new_status: (kind == DiffKind::Added).then(|| side.status.clone()),
+        };
+        let mut records: Vec<DiffRecord> = self
+            .removed
+            .iter()
+            .map(|s| side(DiffKind::Removed, s))
+            .chain(self.added.iter().map(|s| side(DiffKind::Added, s)))
+            .collect();
+        for pair in &self.pairs {
+            let kind = match pair.delta() {
+                d if d < 0 => DiffKind::Degraded,
+                d if d > 0 => DiffKind::Improved,
+                _ if pair.status_changed() => DiffKind::StatusChanged,
+                _ => continue,
+            };
+            records.push(DiffRecord {
+                kind,
+                key: pair.key.clone(),
+                old_score: Some(pair.old_score),
+                new_score: Some(pair.new_score),
+                score_delta: Some(pair.delta()),
+                old_status: Some(pair.old_status.clone()),
+                new_status: Some(pair.new_status.clone()),
+            });
+        }
+        records.sort_by(|a, b| {
+            a.kind
+                .cmp(&b.kind)
+                .then_with(|| {
+                    let magnitude = |r: &DiffRecord| r.score_delta.map_or(0, i32::abs);
+                    magnitude(b).cmp(&magnitude(a))
+                })
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        records
+    }
+
+    /// 写出差异清单；两次运行没有差异时删除上次遗留的清单。
+    pub fn write_diff_csv(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        let records = self.diff_records();
+        if records.is_empty() {
+            if path.is_file() {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("删除过期的差异清单失败: {}", path.display()))?;
+            }
+            uiprintln!("✅ 两次运行没有差异");
+            return Ok(());
+        }
+
+        let mut buffer = Vec::new();
+        {
+            let mut writer = csv::Writer::from_writer(&mut buffer);
+            for record in &records {
+                writer.serialize(record).context("写入差异记录失败")?;
+            }
+            writer.flush().context("刷新CSV缓冲失败")?;
+        }
+        safe_io::atomic_write_bytes(path, &buffer, safe_mode)?;
+        uiprintln!("✅ 差异清单已保存到: {}", path.display());
+        Ok(())
+    }
+
+    pub fn print_summary(&self) {
+        uiprintln!("\n📊 运行对比: {} → {}", self.old_label, self.new_label);
+        uiprintln!(
+            "  匹配曲目: {}，新增: {}，已移除: {}",
+            self.pairs.len(),
+            self.added.len(),
+            self.removed.len()
+        );
+        if let Some(mean) = self.mean_delta() {
+            uiprintln!("  平均分数变化: {mean:+.1}");
+        }
+        for pair in self.pairs.iter().filter(|p| p.delta() != 0).take(10) {
+            uiprintln!(
+                "  {:+4}  {} → {}  {}",
+                pair.delta(),
+                pair.old_score,
+                pair.new_score,
+                pair.key
+            );
+        }
+        let transitions = self.status_transitions();
+        if !transitions.is_empty() {
+            uiprintln!("  状态变化:");
+            for ((old, new), count) in transitions {
+                uiprintln!("    {old} → {new}: {count}");
+            }
+        }
+        for side in &self.removed {
+            uiprintln!("  已移除: {} ({}, {})", side.key, side.score, side.status);
+        }
+        if !self.derivatives.is_empty() {
+            uiprintln!("  疑似有损衍生: {} 首", self.derivatives.len());
+            for (key, derived) in self.derivatives.iter().take(10) {
+                let side = match derived {
+                    Side::A => "旧",
+                    Side::B => "新",
+                };
+                uiprintln!("    {key}（{side}版本疑似由另一版本有损转换）");
+            }
+        }
+    }
+
+    /// 写出 HTML 对比视图：分数分布、旧/新分数散点图与变化最大的曲目。
+    pub fn write_html(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        safe_io::atomic_write_string(path, &self.render_html(), safe_mode)?;
+        uiprintln!("✅ 对比报告已保存到: {}", path.display());
+        Ok(())
+    }
+
+    fn render_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"zh\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>音频质量运行对比</title>\n<style>\n\
+             body{{font-family:sans-serif;margin:2em;color:#222}}\n\
+             .charts{{display:flex;flex-wrap:wrap;gap:2em}}\n\
+             table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:4px 8px}}\n\
+             .up{{color:#1a7f37}}.down{{color:#c62828}}\n\
+             </style>\n</head>\n<body>\n<h1>音频质量运行对比</h1>\n\
+             <p>旧: {}<br>新: {}</p>\n\
+             <p>匹配曲目 {}，新增 {}，已移除 {}{}</p>\n",
+            escape_html(&self.old_label),
+            escape_html(&self.new_label),
+            self.pairs.len(),
+            self.added.len(),
+            self.removed.len(),
+            self.mean_delta()
+                .map(|mean| format!("，平均分数变化 {mean:+.1}"))
+                .unwrap_or_default()
+        );
+
+        html.push_str("<h2>分数分布</h2>\n<div class=\"charts\">\n");
+        html.push_str(&histogram_svg("旧", &self.old_scores, "#90a4ae"));
+        html.push_str(&histogram_svg("新", &self.new_scores, "#42a5f5"));
+        html.push_str("</div>\n<h2>旧分数 vs 新分数</h2>\n");
+        html.push_str(&scatter_svg(&self.pairs));
+
+        html.push_str(
+            "<h2>变化最大的曲目</h2>\n<table>\n\
+             <tr><th>曲目</th><th>旧分数</th><th>新分数</th><th>变化</th><th>旧状态</th><th>新状态</th></tr>\n",
+        );
+        for pair in self
+            .pairs
+            .iter()
+            .filter(|p| p.delta() != 0)
+            .take(TOP_MOVERS)
+        {
+            let class = if pair.delta() > 0 { "up" } else { "down" };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"{class}\">{:+}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&pair.key),
+                pair.old_score,
+                pair.new_score,
+                pair.delta(),
+                escape_html(&pair.old_status),
+                escape_html(&pair.new_status)
+            );
+        }
+        html.push_str("</table>\n");
+
+        for (title, keys) in [("新增曲目", &self.added), ("已移除曲目", &self.removed)] {
+            if keys.is_empty() {
+                continue;
+            }
+            let _ = writeln!(html, "<h2>{title}</h2>\n<ul>");
+            for side in keys {
+                let _ = writeln!(
+                    html,
+                    "<li>{}（{}，{}）</li>",
+                    escape_html(&side.key),
+                    side.score,
+                    escape_html(&side.status)
+                );
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn histogram_svg(title: &str, scores: &[i32], color: &str) -> String {
+    const WIDTH: f64 = 360.0;
+    const HEIGHT: f64 = 200.0;
+    let bins = (100 / HISTOGRAM_BIN) as usize;
+    let mut counts = vec![0usize; bins];
+    for score in scores {
+        let bin = ((*score).clamp(0, 99) / HISTOGRAM_BIN) as usize;
+        counts[bin] += 1;
+    }
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let bar_width = WIDTH / bins as f64;
+
+    let mut svg = format!(
+        "<figure><figcaption>{}（{} 首）</figcaption>\n\
+         <svg width=\"{WIDTH}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n",
+        escape_html(title),
+        scores.len(),
+        HEIGHT + 20.0
+    );
+    for (i, count) in counts.iter().enumerate() {
+        let height = *count as f64 / max * HEIGHT;
+        let x = i as f64 * bar_width;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{x:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{height:.1}\" fill=\"{color}\">\
+             <title>{}-{}: {count}</title></rect>\
+             <text x=\"{:.1}\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\">{}</text>",
+            HEIGHT - height,
+            bar_width - 2.0,
+            i as i32 * HISTOGRAM_BIN,
+            i as i32 * HISTOGRAM_BIN + HISTOGRAM_BIN - 1,
+            x + bar_width / 2.0,
+            HEIGHT + 14.0,
+            i as i32 * HISTOGRAM_BIN
+        );
+    }
+    svg.push_str("</svg></figure>\n");
+    svg
+}
+
+fn scatter_svg(pairs: &[ScorePair]) -> String {
+    const SIZE: f64 = 400.0;
+    let scale = |score: i32| f64::from(score.clamp(0, 100)) / 100.0 * SIZE;
+
+    let mut svg = format!(
+        "<svg width=\"{SIZE}\" height=\"{SIZE}\" xmlns=\"http://www.w3.org/2000/svg\" \
+         style=\"border:1px solid #ccc\">\n\
+         <line x1=\"0\" y1=\"{SIZE}\" x2=\"{SIZE}\" y2=\"0\" stroke=\"#bbb\" stroke-dasharray=\"4\"/>\n"
+    );
+    for pair in pairs {
+        let color = match pair.delta() {
+            d if d > 0 => "#1a7f37",
+            d if d < 0 => "#c62828",
+            _ => "#607d8b",
+        };
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{color}\" fill-opacity=\"0.7\">\
+             <title>{}: {} → {}</title></circle>",
+            scale(pair.old_score),
+            SIZE - scale(pair.new_score),
+            escape_html(&pair.key),
+            pair.old_score,
+            pair.new_score
+        );
+    }
+    svg.push_str("</svg>\n<p>横轴为旧分数，纵轴为新分数；对角线以上为提升。</p>\n");
+    svg
+}
+
+fn escape_html(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scoring::QualityScorer;
+
+    fn analyses(paths: &[(&str, f64)]) -> Vec<QualityAnalysis> {
+        let metrics: Vec<FileMetrics> = paths
+            .iter()
+            .map(|(path, lufs)| FileMetrics {
+                file_path: path.to_string(),
+                integrated_loudness_lufs: Some(*lufs),
+                true_peak_dbtp: Some(-1.0),
+                lra: Some(7.0),
+                rms_db_above_18k: Some(-70.0),
+                ..FileMetrics::default()
+            })
+            .collect();
+        QualityScorer::new().analyze_files(&metrics)
+    }
+
+    #[test]
+    fn test_runs_pair_by_relative_path() {
+        let old = analyses(&[
+            ("/music/original/A/01.flac", -20.0),
+            ("/music/original/A/02.flac", -9.0),
+        ]);
+        let new = analyses(&[
+            ("/music/remaster/A/01.flac", -9.0),
+            ("/music/remaster/A/03.flac", -9.0),
+        ]);
+        let comparison = compare_runs(
+            &old,
+            Path::new("/music/original"),
+            &new,
+            Path::new("/music/remaster"),
+        );
+
+        assert_eq!(comparison.pairs.len(), 1);
+        assert_eq!(comparison.pairs[0].key, "A/01.flac");
+        assert!(comparison.pairs[0].delta() > 0);
+        assert_eq!(comparison.added[0].key, "A/03.flac");
+        assert_eq!(comparison.removed[0].key, "A/02.flac");
+
+        // 格式不同的同一曲目按去掉扩展名的路径配对；同名候选不唯一时不配对。
+        let old = analyses(&[
+            ("/music/flac/A/01.flac", -20.0),
+            ("/music/flac/A/02.flac", -9.0),
+            ("/music/flac/A/02.wav", -9.0),
+        ]);
+        let new = analyses(&[("/music/mp3/A/01.mp3", -9.0), ("/music/mp3/A/02.mp3", -9.0)]);
+        let comparison = compare_runs(
+            &old,
+            Path::new("/music/flac"),
+            &new,
+            Path::new("/music/mp3"),
+        );
+        assert_eq!(comparison.pairs.len(), 1);
+        assert_eq!(comparison.pairs[0].key, "A/01.mp3");
+        assert_eq!(comparison.added.len(), 1);
+        assert_eq!(comparison.removed.len(), 2);
+    }
+
+    #[test]
+    fn test_load_run_resolves_library_root_from_source_marker() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let report_dir = dir.path().join("reports").join("Music");
+        std::fs::create_dir_all(&report_dir).expect("mkdir");
+        let files = vec![FileMetrics {
+            file_path: "/music/A/01.flac".to_string(),
+            ..FileMetrics::default()
+        }];
+        schema::write_analysis_data(
+            &report_dir.join(schema::ANALYSIS_DATA_FILE_NAME),
+            &files,
+            true,
+        )
+        .expect("write");
+        let (_, root) = load_run(&report_dir).expect("load");
+        assert_eq!(root, run_dir(&report_dir));
+
+        std::fs::write(
+            report_dir.join(schema::SOURCE_MARKER_FILE_NAME),
+            r#"{"libraryRoot":"/music"}"#,
+        )
+        .expect("marker");
+        let (metrics, root) = load_run(&report_dir).expect("load");
+        assert_eq!(root, Path::new("/music"));
+        let run = QualityScorer::new().analyze_files(&metrics);
+        let comparison = compare_runs(&run, &root, &run, &root);
+        assert_eq!(comparison.pairs[0].key, "A/01.flac");
+    }
+
+    #[test]
+    fn test_diff_csv_lists_removed_first_and_transitions() {
+        let old = analyses(&[("/o/A/01.flac", -9.0), ("/o/A/02.flac", -9.0)]);
+        let new = analyses(&[("/n/A/01.flac", -20.0), ("/n/A/03.flac", -9.0)]);
+        let comparison = compare_runs(&old, Path::new("/o"), &new, Path::new("/n"));
+        let records = comparison.diff_records();
+        let kinds: Vec<(DiffKind, &str)> =
+            records.iter().map(|r| (r.kind, r.key.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (DiffKind::Removed, "A/02.flac"),
+                (DiffKind::Degraded, "A/01.flac"),
+                (DiffKind::Added, "A/03.flac"),
+            ]
+        );
+        let transitions = comparison.status_transitions();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].0 .0, QualityStatus::Good.to_string());
+
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let path = dir.path().join(DIFF_CSV_FILE_NAME);
+        comparison.write_diff_csv(&path, true).expect("write");
+        let content = std::fs::read_to_string(&path).expect("read");
+        assert!(content
+            .starts_with("变化类型,曲目,旧分数,新分数,分数变化,旧状态,新状态\n已移除,A/02.flac,"));
+        let unchanged = compare_runs(&old, Path::new("/o"), &old, Path::new("/o"));
+        unchanged.write_diff_csv(&path, true).expect("clear");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_baseline_deltas_and_regressions() {
+        let baseline = analyses(&[
+            ("/old/A/01.flac", -9.0),
+            ("/old/A/02.flac", -20.0),
+            ("/old/A/03.flac", -9.0),
+        ]);
+        let mut current = analyses(&[
+            ("/lib/A/01.flac", -20.0),
+            ("/lib/A/02.flac", -9.0),
+            ("/lib/A/04.flac", -9.0),
+        ]);
+        let matched = apply_baseline(
+            &mut current,
+            Path::new("/lib"),
+            &baseline,
+            Path::new("/old"),
+        );
+
+        assert_eq!(matched, 2);
+        assert!(current[2].baseline.is_none());
+        let worse = current[0].baseline.as_ref().expect("baseline");
+        assert!(worse.score_delta < 0);
+        assert!(worse.status_changed);
+        assert_eq!(worse.previous_status, QualityStatus::Good);
+        let regressed = regressions(&current);
+        assert_eq!(regressed.len(), 1);
+        assert_eq!(regressed[0].file_path, "/lib/A/01.flac");
+    }
+
+    #[test]
+    fn test_html_contains_charts_and_escapes_names() {
+        let old = analyses(&[("/a/<b>&.flac", -20.0)]);
+        let new = analyses(&[("/c/<b>&.flac", -9.0)]);
+        let html = compare_runs(&old, Path::new("/a"), &new, Path::new("/c")).render_html();
+
+        assert!(html.contains("<svg"));
+        assert!(html.contains("<circle"));
+        assert!(html.contains("&lt;b&gt;&amp;.flac"));
+        assert!(!html.contains("<b>&"));
+    }
+}
@@ -0,0 +1,206 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::metrics::FileMetrics;
+
+/// 广播响度规范。合规判定与 0–99 质量分相互独立，只给出通过/未通过。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplianceStandard {
+    /// EBU R128：节目响度 -23.0 LUFS ±0.5 LU，最大真峰值 -1 dBTP。
+    EbuR128,
+    /// ATSC A/85：目标响度 -24 LKFS ±2 dB，真峰值不超过 -2 dBTP。
+    AtscA85,
+}
+
+/// 某一规范的限值与对应条款。
+struct ComplianceLimits {
+    target_lufs: f64,
+    tolerance_lu: f64,
+    loudness_clause: &'static str,
+    true_peak_max_dbtp: f64,
+    true_peak_clause: &'static str,
+}
+
+impl ComplianceStandard {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ComplianceStandard::EbuR128 => "ebu-r128",
+            ComplianceStandard::AtscA85 => "atsc-a85",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ComplianceStandard::EbuR128 => "EBU R128",
+            ComplianceStandard::AtscA85 => "ATSC A/85",
+        }
+    }
+
+    fn limits(self) -> ComplianceLimits {
+        match self {
+            ComplianceStandard::EbuR128 => ComplianceLimits {
+                target_lufs: -23.0,
+                tolerance_lu: 0.5,
+                loudness_clause: "EBU R128 第 2 条：节目响度 -23.0 LUFS ±0.5 LU",
+                true_peak_max_dbtp: -1.0,
+                true_peak_clause: "EBU R128 第 3 条：最大真峰值 -1 dBTP",
+            },
+            ComplianceStandard::AtscA85 => ComplianceLimits {
+                target_lufs: -24.0,
+                tolerance_lu: 2.0,
+                loudness_clause: "ATSC A/85 目标响度：-24 LKFS ±2 dB",
+                true_peak_max_dbtp: -2.0,
+                true_peak_clause: "ATSC A/85 真峰值：不超过 -2 dBTP",
+            },
+        }
+    }
+}
+
+impl FromStr for ComplianceStandard {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "r128" | "ebu-r128" | "ebu" => Ok(ComplianceStandard::EbuR128),
+            "a85" | "atsc-a85" | "atsc" => Ok(ComplianceStandard::AtscA85),
+            other => Err(format!("未知的合规规范: {other}（可选 r128, a85）")),
+        }
+    }
+}
+
+/// 单个文件的合规判定。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceResult {
+    pub standard: String,
+    pub passed: bool,
+    /// 未满足的条款；通过时为空。
+    pub violations: Vec<ComplianceViolation>,
+}
+
+/// 一项未满足的条款：条款原文、实测值（无法测量时为空）。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceViolation {
+    pub clause: String,
+    pub measured: Option<f64>,
+}
+
+impl ComplianceResult {
+    /// `PASS`，或 `FAIL: 条款 (实测 x); ...`，用于 CSV 与控制台。
+    pub fn describe(&self) -> String {
+        if self.passed {
+            return "PASS".to_string();
+        }
+        let details: Vec<String> = self
+            .violations
+            .iter()
+            .map(|v| match v.measured {
+                Some(value) => format!("{} (实测 {value:.1})", v.clause),
+                None => format!("{} (未能测量)", v.clause),
+            })
+            .collect();
+        format!("FAIL: {}", details.join("; "))
+    }
+}
+
+/// 按规范检查综合响度、真峰值与（设置了上限时的）LRA。
+/// 规范本身未规定 LRA 上限，`lra_max` 由交付方自行指定；快速采样得到的指标不代表整个节目，一律判为未通过。
+pub fn evaluate(
+    metrics: &FileMetrics,
+    standard: ComplianceStandard,
+    lra_max: Option<f64>,
+) -> ComplianceResult {
+    let limits = standard.limits();
+    let mut violations = Vec::new();
+    let mut check = |clause: String, measured: Option<f64>, ok: &dyn Fn(f64) -> bool| {
+        if !measured.is_some_and(ok) {
+            violations.push(ComplianceViolation { clause, measured });
+        }
+    };
+
+    check(
+        limits.loudness_clause.to_string(),
+        metrics.integrated_loudness_lufs,
+        &|lufs| (lufs - limits.target_lufs).abs() <= limits.tolerance_lu,
+    );
+    check(
+        limits.true_peak_clause.to_string(),
+        metrics.true_peak_dbtp,
+        &|tp| tp <= limits.true_peak_max_dbtp,
+    );
+    if let Some(max) = lra_max {
+        check(
+            format!("LRA 上限 {max:.1} LU（交付要求）"),
+            metrics.lra,
+            &|lra| lra <= max,
+        );
+    }
+    if metrics.sampled {
+        violations.push(ComplianceViolation {
+            clause: "快速采样模式的测量不能用于合规判定".to_string(),
+            measured: None,
+        });
+    }
+
+    ComplianceResult {
+        standard: standard.label().to_string(),
+        passed: violations.is_empty(),
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn programme(lufs: f64, tp: f64, lra: f64) -> FileMetrics {
+        FileMetrics {
+            integrated_loudness_lufs: Some(lufs),
+            true_peak_dbtp: Some(tp),
+            lra: Some(lra),
+            ..FileMetrics::default()
+        }
+    }
+
+    #[test]
+    fn test_r128_and_a85_pass_fail_with_violated_clause() {
+        let r128: ComplianceStandard = "r128".parse().expect("r128");
+        let pass = evaluate(&programme(-23.3, -1.2, 9.0), r128, None);
+        assert!(pass.passed);
+        assert_eq!(pass.describe(), "PASS");
+
+        let loud = evaluate(&programme(-21.0, -0.5, 9.0), r128, None);
+        assert!(!loud.passed);
+        assert_eq!(loud.violations.len(), 2);
+        assert_eq!(
+            loud.describe(),
+            "FAIL: EBU R128 第 2 条：节目响度 -23.0 LUFS ±0.5 LU (实测 -21.0); EBU R128 第 3 条：最大真峰值 -1 dBTP (实测 -0.5)"
+        );
+
+        // 同一节目在 A/85 下响度容差更宽，但真峰值要求更严。
+        let a85 = ComplianceStandard::from_str("atsc-a85").expect("a85");
+        let result = evaluate(&programme(-22.5, -1.5, 9.0), a85, Some(8.0));
+        let clauses: Vec<&str> = result
+            .violations
+            .iter()
+            .map(|v| v.clause.as_str())
+            .collect();
+        assert_eq!(
+            clauses,
+            [
+                "ATSC A/85 真峰值：不超过 -2 dBTP",
+                "LRA 上限 8.0 LU（交付要求）"
+            ]
+        );
+
+        let mut sampled = programme(-23.0, -2.0, 9.0);
+        sampled.sampled = true;
+        sampled.true_peak_dbtp = None;
+        let result = evaluate(&sampled, r128, None);
+        assert!(result.describe().contains("(未能测量)"));
+        assert!(result.describe().contains("快速采样"));
+        assert!("bbc".parse::<ComplianceStandard>().is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// CUE 时间码每秒的帧数（`mm:ss:ff`）。
+const CUE_FRAMES_PER_SECOND: f64 = 75.0;
+
+/// CUE 中的一条音轨：整轨镜像内的时间范围与曲目信息。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CueTrack {
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub start_seconds: f64,
+    /// 下一轨的起点；最后一轨为 `None`，表示到文件结尾。
+    pub end_seconds: Option<f64>,
+}
+
+impl CueTrack {
+    /// 音轨时长；最后一轨需要整个文件的时长才能确定。
+    pub fn duration_seconds(&self, file_duration_seconds: Option<f64>) -> Option<f64> {
+        self.end_seconds
+            .or(file_duration_seconds)
+            .map(|end| (end - self.start_seconds).max(0.0))
+    }
+
+    /// 报告中显示的曲目名，例如 `03 - Allegro`。
+    pub fn label(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{:02} - {title}", self.number),
+            None => format!("{:02}", self.number),
+        }
+    }
+}
+
+/// CUE 中的一个 `FILE` 块及其音轨。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueFile {
+    pub name: String,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueSheet {
+    pub files: Vec<CueFile>,
+}
+
+/// 读取 CUE 文件；非 UTF-8 内容（如 GBK 编码）按有损方式解码，文件名无法匹配时由调用方按同名规则回退。
+pub fn read_cue(path: &Path) -> Result<CueSheet> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("读取 CUE 文件失败: {}", path.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(parse_cue(text.trim_start_matches('\u{feff}')))
+}
+
+pub fn parse_cue(text: &str) -> CueSheet {
+    let mut files: Vec<CueFile> = Vec::new();
+    let mut album_performer: Option<String> = None;
+    let mut current: Option<CueTrack> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match keyword.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                flush_track(&mut files, current.take());
+                files.push(CueFile {
+                    name: quoted_value(rest_without_file_type(rest)),
+                    tracks: Vec::new(),
+                });
+            }
+            "TRACK" => {
+                flush_track(&mut files, current.take());
+                let number = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0);
+                current = Some(CueTrack {
+                    number,
+                    title: None,
+                    performer: album_performer.clone(),
+                    start_seconds: f64::NAN,
+                    end_seconds: None,
+                });
+            }
+            "TITLE" => {
+                if let Some(track) = current.as_mut() {
+                    track.title = Some(quoted_value(rest));
+                }
+            }
+            "PERFORMER" => match current.as_mut() {
+                Some(track) => track.performer = Some(quoted_value(rest)),
+                None => album_performer = Some(quoted_value(rest)),
+            },
+            "INDEX" => {
+                let mut parts = rest.split_whitespace();
+                let index = parts.next();
+                if let (Some("01"), Some(timestamp), Some(track)) =
+                    (index, parts.next(), current.as_mut())
+                {
+                    if let Some(seconds) = parse_timestamp(timestamp) {
+                        track.start_seconds = seconds;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    flush_track(&mut files, current.take());
+
+    // 同一 FILE 内，每轨结束于下一轨的起点。
+    for file in &mut files {
+        let starts: Vec<f64> = file.tracks.iter().map(|t| t.start_seconds).collect();
+        for (track, next_start) in file.tracks.iter_mut().zip(starts.iter().skip(1)) {
+            track.end_seconds = Some(*next_start);
+        }
+    }
+
+    CueSheet { files }
+}
+
+/// 没有 `INDEX 01` 的音轨无法定位，直接丢弃。
+fn flush_track(files: &mut [CueFile], track: Option<CueTrack>) {
+    if let (Some(file), Some(track)) = (files.last_mut(), track) {
+        if track.start_seconds.is_finite() {
+            file.tracks.push(track);
+        }
+    }
+}
+
+/// `FILE "a b.flac" WAVE` 去掉末尾的文件类型。
+fn rest_without_file_type(rest: &str) -> &str {
+    let rest = rest.trim();
+    if rest.starts_with('"') {
+        return rest;
+    }
+    rest.rsplit_once(char::is_whitespace)
+        .map(|(name, _)| name)
+        .unwrap_or(rest)
+}
+
+fn quoted_value(rest: &str) -> String {
+    let rest = rest.trim();
+    match rest.strip_prefix('"') {
+        Some(inner) => inner.split('"').next().unwrap_or_default().to_string(),
+        None => rest.to_string(),
+    }
+}
+
+/// 解析 `mm:ss:ff` 时间码。
+fn parse_timestamp(text: &str) -> Option<f64> {
+    let mut parts = text.split(':').map(|p| p.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(f64::from(minutes) * 60.0 + f64::from(seconds) + f64::from(frames) / CUE_FRAMES_PER_SECOND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single_file_album_image() {
+        let sheet = parse_cue(
+            "PERFORMER \"Berliner Philharmoniker\"\n\
+             TITLE \"Symphony No. 5\"\n\
+             FILE \"Symphony No. 5.flac\" WAVE\n\
+             \x20 TRACK 01 AUDIO\n\
+             \x20   TITLE \"Allegro con brio\"\n\
+             \x20   INDEX 01 00:00:00\n\
+             \x20 TRACK 02 AUDIO\n\
+             \x20   TITLE \"Andante con moto\"\n\
+             \x20   INDEX 00 07:30:00\n\
+             \x20   INDEX 01 07:31:37\n",
+        );
+
+        assert_eq!(sheet.files.len(), 1);
+        let file = &sheet.files[0];
+        assert_eq!(file.name, "Symphony No. 5.flac");
+        assert_eq!(file.tracks.len(), 2);
+        assert_eq!(file.tracks[0].end_seconds, Some(451.0 + 37.0 / 75.0));
+        assert_eq!(
+            file.tracks[0].performer.as_deref(),
+            Some("Berliner Philharmoniker")
+        );
+        assert_eq!(file.tracks[1].label(), "02 - Andante con moto");
+        assert_eq!(file.tracks[1].end_seconds, None);
+        assert_eq!(
+            file.tracks[1].duration_seconds(Some(600.0)),
+            Some(600.0 - 451.0 - 37.0 / 75.0)
+        );
+    }
+
+    #[test]
+    fn test_unquoted_file_name_and_missing_index() {
+        let sheet =
+            parse_cue("FILE image.wav WAVE\nTRACK 01 AUDIO\nTRACK 02 AUDIO\nINDEX 01 01:00:00\n");
+        assert_eq!(sheet.files[0].name, "image.wav");
+        assert_eq!(sheet.files[0].tracks.len(), 1);
+        assert_eq!(sheet.files[0].tracks[0].number, 2);
+        assert_eq!(sheet.files[0].tracks[0].start_seconds, 60.0);
+    }
+}
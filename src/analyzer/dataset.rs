@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::safe_io;
+use super::scoring::QualityAnalysis;
+
+/// 数据集表格文件名。
+pub const DATASET_CSV_FILE_NAME: &str = "dataset.csv";
+/// 数据集清单文件名。
+pub const DATASET_MANIFEST_FILE_NAME: &str = "manifest.json";
+/// 匿名 ID 盐值文件名（放在用户配置目录中，每个安装首次导出时随机生成）。
+pub const DATASET_SALT_FILE_NAME: &str = "dataset_salt";
+
+/// 导出给研究者的一行匿名数据：只含技术指标与评分结果，不含路径、文件名或标签。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct DatasetRow {
+    /// 由本机盐值与文件路径（及音轨/CUE 序号）哈希得到的 ID：同一安装重复导出时保持一致，
+    /// 但无法通过猜测常见路径反推，也无法与其他安装导出的数据集对照。
+    id: String,
+    quality_score: i32,
+    status: String,
+    issues: String,
+    confidence: f64,
+    integrated_loudness_lufs: Option<f64>,
+    true_peak_dbtp: Option<f64>,
+    lra: Option<f64>,
+    peak_amplitude_db: Option<f64>,
+    overall_rms_db: Option<f64>,
+    rms_db_above_16k: Option<f64>,
+    rms_db_above_18k: Option<f64>,
+    rms_db_above_20k: Option<f64>,
+    sample_rate_hz: Option<u32>,
+    bit_depth: Option<u32>,
+    bitrate_kbps: Option<u32>,
+    channels: Option<u32>,
+    codec_name: Option<String>,
+    container_format: Option<String>,
+    duration_seconds: Option<f64>,
+    sampled: bool,
+}
+
+impl DatasetRow {
+    fn from_analysis(analysis: &QualityAnalysis, salt: &str) -> Self {
+        let metrics = &analysis.metrics;
+        Self {
+            id: anonymous_id(analysis, salt),
+            quality_score: analysis.quality_score,
+            status: format!("{:?}", analysis.status),
+            issues: analysis
+                .issues
+                .iter()
+                .map(|issue| format!("{issue:?}"))
+                .collect::<Vec<_>>()
+                .join("|"),
+            confidence: analysis.confidence,
+            integrated_loudness_lufs: metrics.integrated_loudness_lufs,
+            true_peak_dbtp: metrics.true_peak_dbtp,
+            lra: metrics.lra,
+            peak_amplitude_db: metrics.peak_amplitude_db,
+            overall_rms_db: metrics.overall_rms_db,
+            rms_db_above_16k: metrics.rms_db_above_16k,
+            rms_db_above_18k: metrics.rms_db_above_18k,
+            rms_db_above_20k: metrics.rms_db_above_20k,
+            sample_rate_hz: metrics.sample_rate_hz,
+            bit_depth: metrics.bit_depth,
+            bitrate_kbps: metrics.bitrate_kbps,
+            channels: metrics.channels,
+            codec_name: metrics.codec_name.clone(),
+            container_format: metrics.container_format.clone(),
+            duration_seconds: metrics.duration_seconds,
+            sampled: metrics.sampled,
+        }
+    }
+}
+
+fn anonymous_id(analysis: &QualityAnalysis, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(analysis.file_path.as_bytes());
+    hasher.update(analysis.metrics.audio_stream_index.to_le_bytes());
+    if let Some(track) = &analysis.metrics.cue_track {
+        hasher.update(track.number.to_le_bytes());
+    }
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// 读取本机的匿名 ID 盐值；不存在时生成 128 位随机值并写入 `path`。
+pub fn load_or_create_salt(path: &Path, safe_mode: bool) -> Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => return Ok(content.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("读取数据集盐值失败: {}", path.display())),
+    }
+    let salt = format!("{:016x}{:016x}", fastrand::u64(..), fastrand::u64(..));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("无法创建目录: {}", parent.display()))?;
+    }
+    safe_io::atomic_write_string(path, &salt, safe_mode)?;
+    Ok(salt)
+}
+
+/// 数据集清单：记录生成工具、评分档案与数据文件校验和，使他人能够复现评分。
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DatasetManifest {
+    tool_version: String,
+    created_at: String,
+    row_count: usize,
+    data_file: String,
+    data_sha256: String,
+    /// 评分档案名称、阈值与维度权重。
+    profile: serde_json::Value,
+    hf_bands_hz: Vec<u32>,
+    sampled_analysis: bool,
+}
+
+/// 写出匿名数据集目录（`dataset.csv` + `manifest.json`）。行按匿名 ID 排序，不保留曲库目录顺序。
+/// 盐值不写入清单。
+pub fn export_dataset(
+    output_dir: &Path,
+    analyses: &[QualityAnalysis],
+    salt: &str,
+    profile: serde_json::Value,
+    hf_bands_hz: &[u32],
+    safe_mode: bool,
+) -> Result<()> {
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("无法创建数据集目录: {}", output_dir.display()))?;
+
+    let mut rows: Vec<DatasetRow> = analyses
+        .iter()
+        .map(|analysis| DatasetRow::from_analysis(analysis, salt))
+        .collect();
+    rows.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        for row in &rows {
+            writer.serialize(row).context("写入数据集记录失败")?;
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    let data_sha256 = format!("{:x}", Sha256::digest(&buffer));
+    safe_io::atomic_write_bytes(&output_dir.join(DATASET_CSV_FILE_NAME), &buffer, safe_mode)?;
+
+    let manifest = DatasetManifest {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Local::now().to_rfc3339(),
+        row_count: rows.len(),
+        data_file: DATASET_CSV_FILE_NAME.to_string(),
+        data_sha256,
+        profile,
+        hf_bands_hz: hf_bands_hz.to_vec(),
+        sampled_analysis: rows.iter().any(|row| row.sampled),
+    };
+    let content = serde_json::to_string_pretty(&manifest).context("序列化数据集清单失败")?;
+    safe_io::atomic_write_string(
+        &output_dir.join(DATASET_MANIFEST_FILE_NAME),
+        &content,
+        safe_mode,
+    )?;
+
+    uiprintln!(
+        "✅ 匿名数据集已导出到: {}（{} 行）",
+        output_dir.display(),
+        rows.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_dataset_has_no_paths_and_matching_manifest() {
+        let dir = TempDir::new().expect("tempdir");
+        let metrics: Vec<FileMetrics> = ["/home/alice/Music/Secret Album/01 Song.flac"]
+            .iter()
+            .map(|path| FileMetrics {
+                file_path: path.to_string(),
+                integrated_loudness_lufs: Some(-9.0),
+                codec_name: Some("flac".to_string()),
+                ..FileMetrics::default()
+            })
+            .collect();
+        let scorer = QualityScorer::new();
+        let analyses = scorer.analyze_files(&metrics);
+
+        let salt_path = dir.path().join("config").join(DATASET_SALT_FILE_NAME);
+        let salt = load_or_create_salt(&salt_path, true).expect("salt");
+        assert_eq!(salt.len(), 32);
+        assert_eq!(load_or_create_salt(&salt_path, true).expect("reload"), salt);
+
+        export_dataset(
+            dir.path(),
+            &analyses,
+            &salt,
+            scorer.profile_snapshot(),
+            &[16_000, 18_000, 20_000],
+            true,
+        )
+        .expect("export");
+
+        let csv = std::fs::read_to_string(dir.path().join(DATASET_CSV_FILE_NAME)).expect("csv");
+        assert!(!csv.contains("alice"));
+        assert!(!csv.contains("Secret Album"));
+        assert!(csv.starts_with("id,quality_score,status"));
+
+        let manifest: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join(DATASET_MANIFEST_FILE_NAME)).expect("read"),
+        )
+        .expect("manifest json");
+        assert_eq!(manifest["rowCount"], 1);
+        assert_eq!(
+            manifest["dataSha256"],
+            format!("{:x}", Sha256::digest(csv.as_bytes()))
+        );
+        assert_eq!(manifest["profile"]["name"], "pop");
+        assert_eq!(manifest["profile"]["thresholds"]["target_lufs"], -9.0);
+        assert!(!manifest.to_string().contains(&salt));
+
+        // 不同安装的盐值不同，同一文件得到的 ID 无法对照。
+        assert_eq!(
+            anonymous_id(&analyses[0], &salt),
+            anonymous_id(&analyses[0], &salt)
+        );
+        assert_ne!(
+            anonymous_id(&analyses[0], &salt),
+            anonymous_id(&analyses[0], "another-install")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::safe_io;
+use super::scoring::{QualityAnalysis, QualityStatus, ScoringProfile};
+
+/// 交付规格文件名：放在交付目录中，对该目录（含子目录）下的全部文件生效。
+pub const DELIVERY_FILE_NAME: &str = "delivery.toml";
+
+/// 交付检查汇总的文件名，与其他报告一起写在报告目录中。
+pub const DELIVERY_CSV_FILE_NAME: &str = "audio_quality_deliveries.csv";
+
+/// 交付规格（`delivery.toml`）。各项均可省略，省略的项不做检查。
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeliverySpec {
+    /// 交付名称（如客户/项目名），默认取目录名。
+    pub name: Option<String>,
+    /// 评分档案：该目录下的文件按此档案重新评分后再检查。
+    pub profile: Option<String>,
+    /// 每个文件的最低质量分。
+    pub min_score: Option<i32>,
+    /// 允许出现的状态（如 `["质量良好", "响度偏离目标"]`），文件的全部问题都须在其中。
+    pub allowed_statuses: Option<Vec<QualityStatus>>,
+}
+
+/// 扫描到的一个交付目录及其规格。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub dir: PathBuf,
+    pub name: String,
+    pub profile: Option<ScoringProfile>,
+    pub spec: DeliverySpec,
+}
+
+impl Delivery {
+    fn load(spec_path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(spec_path).with_context(|| {
+            format!(
+                "[E_DELIVERY_SPEC] 无法读取交付规格: {}",
+                spec_path.display()
+            )
+        })?;
+        let spec: DeliverySpec = toml::from_str(&content).map_err(|e| {
+            anyhow!(
+                "[E_DELIVERY_SPEC] 交付规格格式错误 {}: {e}",
+                spec_path.display()
+            )
+        })?;
+        let profile = spec
+            .profile
+            .as_deref()
+            .map(str::parse::<ScoringProfile>)
+            .transpose()
+            .map_err(|e| anyhow!("[E_DELIVERY_SPEC] {}: {e}", spec_path.display()))?;
+        let dir = spec_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let name = spec.name.clone().unwrap_or_else(|| {
+            dir.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| dir.display().to_string())
+        });
+        Ok(Self {
+            dir,
+            name,
+            profile,
+            spec,
+        })
+    }
+
+    /// 文件是否归属该交付目录（嵌套时由 `assign` 选最近的一个）。
+    fn contains(&self, file_path: &str) -> bool {
+        Path::new(file_path).starts_with(&self.dir)
+    }
+}
+
+/// 查找 `root` 下的全部交付规格，按目录排序。
+pub fn discover(root: &Path) -> Result<Vec<Delivery>> {
+    let mut deliveries = WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.file_name() == DELIVERY_FILE_NAME)
+        .map(|e| Delivery::load(e.path()))
+        .collect::<Result<Vec<_>>>()?;
+    deliveries.sort_by(|a, b| a.dir.cmp(&b.dir));
+    Ok(deliveries)
+}
+
+/// 每个文件所属的交付序号：嵌套的交付目录以最内层为准，不属于任何交付的文件为 `None`。
+pub fn assign(deliveries: &[Delivery], analyses: &[QualityAnalysis]) -> Vec<Option<usize>> {
+    analyses
+        .iter()
+        .map(|analysis| {
+            deliveries
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.contains(&analysis.file_path))
+                .max_by_key(|(_, d)| d.dir.components().count())
+                .map(|(index, _)| index)
+        })
+        .collect()
+}
+
+/// 一个交付目录的检查结果。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeliveryResult {
+    #[serde(rename = "交付")]
+    pub name: String,
+    #[serde(rename = "目录")]
+    pub dir: String,
+    #[serde(rename = "评分档案")]
+    pub profile: String,
+    #[serde(rename = "文件数")]
+    pub files: usize,
+    #[serde(rename = "未通过文件数")]
+    pub failed_files: usize,
+    #[serde(rename = "判定")]
+    pub verdict: String,
+    /// 未通过的文件及原因，`; ` 分隔。
+    #[serde(rename = "未通过明细")]
+    pub failures: String,
+}
+
+impl DeliveryResult {
+    pub fn passed(&self) -> bool {
+        self.failed_files == 0 && self.files > 0
+    }
+}
+
+/// 按交付规格检查已按其档案评分的文件；交付目录中没有任何音频文件时判为未通过。
+pub fn evaluate(
+    delivery: &Delivery,
+    profile_name: &str,
+    analyses: &[QualityAnalysis],
+) -> DeliveryResult {
+    let mut failures = Vec::new();
+    for analysis in analyses {
+        let mut reasons = Vec::new();
+        if let Some(min) = delivery.spec.min_score {
+            if analysis.quality_score < min {
+                reasons.push(format!("分数 {} < {min}", analysis.quality_score));
+            }
+        }
+        if let Some(allowed) = &delivery.spec.allowed_statuses {
+            let statuses = if analysis.issues.is_empty() {
+                std::slice::from_ref(&analysis.status)
+            } else {
+                analysis.issues.as_slice()
+            };
+            for status in statuses.iter().filter(|s| !allowed.contains(s)) {
+                reasons.push(format!("状态 {status} 不在允许列表中"));
+            }
+        }
+        if !reasons.is_empty() {
+            let file = Path::new(&analysis.file_path)
+                .strip_prefix(&delivery.dir)
+                .unwrap_or(Path::new(&analysis.file_path))
+                .display()
+                .to_string();
+            failures.push(format!("{file}（{}）", reasons.join("，")));
+        }
+    }
+
+    let mut result = DeliveryResult {
+        name: delivery.name.clone(),
+        dir: delivery.dir.display().to_string(),
+        profile: profile_name.to_string(),
+        files: analyses.len(),
+        failed_files: failures.len(),
+        verdict: String::new(),
+        failures: failures.join("; "),
+    };
+    result.verdict = if result.passed() {
+        "PASS".to_string()
+    } else if analyses.is_empty() {
+        "FAIL: 没有音频文件".to_string()
+    } else {
+        "FAIL".to_string()
+    };
+    result
+}
+
+/// 写出交付检查汇总，每个交付目录一行。
+pub fn write_deliveries_csv(
+    path: &Path,
+    results: &[DeliveryResult],
+    safe_mode: bool,
+) -> Result<()> {
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        for result in results {
+            writer.serialize(result).context("写入交付检查记录失败")?;
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    safe_io::atomic_write_bytes(path, &buffer, safe_mode)?;
+    uiprintln!("✅ 交付检查汇总已保存到: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_nested_deliveries_gate_against_their_own_spec() {
+        let dir = TempDir::new().expect("tempdir");
+        let client = dir.path().join("client_a");
+        let single = client.join("single");
+        std::fs::create_dir_all(&single).expect("dirs");
+        std::fs::write(
+            client.join(DELIVERY_FILE_NAME),
+            "name = \"Client A\"\nprofile = \"broadcast\"\nmin_score = 0\nallowed_statuses = [\"质量良好\"]\n",
+        )
+        .expect("spec");
+        std::fs::write(single.join(DELIVERY_FILE_NAME), "min_score = 99\n").expect("spec");
+        std::fs::write(dir.path().join(DELIVERY_FILE_NAME), "min_scor = 1\n").expect("typo");
+        let err = discover(dir.path()).expect_err("unknown key");
+        assert!(err.to_string().contains("E_DELIVERY_SPEC"));
+        std::fs::remove_file(dir.path().join(DELIVERY_FILE_NAME)).expect("remove");
+
+        let deliveries = discover(dir.path()).expect("discover");
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].name, "Client A");
+        assert_eq!(deliveries[0].profile, Some(ScoringProfile::Broadcast));
+        assert_eq!(deliveries[1].name, "single");
+
+        let file = |path: &Path| FileMetrics {
+            file_path: path.to_string_lossy().into_owned(),
+            ..FileMetrics::default()
+        };
+        let analyses = QualityScorer::new().analyze_files(&[
+            file(&client.join("01.wav")),
+            file(&single.join("01.wav")),
+            file(&dir.path().join("loose.wav")),
+        ]);
+        assert_eq!(assign(&deliveries, &analyses), [Some(0), Some(1), None]);
+
+        let result = evaluate(&deliveries[0], "broadcast", &analyses[..1]);
+        assert!(!result.passed());
+        assert_eq!(result.verdict, "FAIL");
+        assert!(result
+            .failures
+            .starts_with("01.wav（状态 数据不完整 不在允许列表中"));
+        assert_eq!(
+            evaluate(&deliveries[1], "pop", &[]).verdict,
+            "FAIL: 没有音频文件"
+        );
+    }
+}
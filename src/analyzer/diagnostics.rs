@@ -0,0 +1,216 @@
+use chrono::Local;
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// 崩溃诊断包中保留的最近日志行数。
+const MAX_LOG_LINES: usize = 200;
+
+#[derive(Debug, Default)]
+struct DiagnosticsState {
+    log_lines: VecDeque<String>,
+    files_in_progress: BTreeSet<String>,
+    commands_in_flight: BTreeMap<u64, String>,
+    next_command_id: u64,
+    bundle_dir: Option<PathBuf>,
+}
+
+lazy_static! {
+    static ref STATE: Mutex<DiagnosticsState> = Mutex::new(DiagnosticsState::default());
+}
+
+fn state() -> MutexGuard<'static, DiagnosticsState> {
+    STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 记录一条诊断日志（环形缓冲，仅在崩溃时写出）。
+pub fn log_line(message: impl AsRef<str>) {
+    let line = format!(
+        "[{}] {}",
+        Local::now().format("%H:%M:%S%.3f"),
+        message.as_ref()
+    );
+    let mut state = state();
+    if state.log_lines.len() >= MAX_LOG_LINES {
+        state.log_lines.pop_front();
+    }
+    state.log_lines.push_back(line);
+}
+
+/// 环形缓冲中的日志行（供测试检查转发结果）。
+#[cfg(test)]
+pub fn recent_log_lines() -> Vec<String> {
+    state().log_lines.iter().cloned().collect()
+}
+
+/// 设置诊断包输出目录（通常为应用日志目录）。
+pub fn set_bundle_dir(dir: &Path) {
+    state().bundle_dir = Some(dir.to_path_buf());
+}
+
+/// 正在处理的文件登记，离开作用域时自动注销。
+pub struct FileGuard {
+    path: String,
+}
+
+impl Drop for FileGuard {
+    fn drop(&mut self) {
+        state().files_in_progress.remove(&self.path);
+    }
+}
+
+pub fn begin_file(path: &Path) -> FileGuard {
+    let path = path.to_string_lossy().into_owned();
+    state().files_in_progress.insert(path.clone());
+    FileGuard { path }
+}
+
+/// 正在执行的外部命令登记，离开作用域时自动注销。
+pub struct CommandGuard {
+    id: u64,
+}
+
+impl Drop for CommandGuard {
+    fn drop(&mut self) {
+        state().commands_in_flight.remove(&self.id);
+    }
+}
+
+pub fn begin_command(description: String) -> CommandGuard {
+    let mut state = state();
+    let id = state.next_command_id;
+    state.next_command_id += 1;
+    state.commands_in_flight.insert(id, description);
+    CommandGuard { id }
+}
+
+/// 安装 panic hook：崩溃时把诊断包写入工作目录，然后继续执行默认 hook。
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // 崩溃时可能正持有锁，使用 try_lock 避免死锁。
+        if let Ok(state) = STATE.try_lock() {
+            write_bundle(&state, info);
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_bundle(state: &DiagnosticsState, info: &PanicHookInfo<'_>) {
+    let dir = state.bundle_dir.clone().unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!(
+        "audio_quality_crash_{}.txt",
+        Local::now().format("%Y%m%d_%H%M%S")
+    ));
+    let content = render_bundle(state, &panic_summary(info));
+    match std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, content)) {
+        Ok(()) => eprintln!("程序崩溃，诊断包已写入: {}", path.display()),
+        Err(e) => eprintln!("程序崩溃，且无法写入诊断包 {}: {e}", path.display()),
+    }
+}
+
+fn panic_summary(info: &PanicHookInfo<'_>) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| (*s).to_owned())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "<非字符串 panic 负载>".to_owned());
+    match info.location() {
+        Some(location) => format!("{message} ({}:{})", location.file(), location.line()),
+        None => message,
+    }
+}
+
+fn render_bundle(state: &DiagnosticsState, panic_summary: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "AudioQuality-rs 崩溃诊断包");
+    let _ = writeln!(out, "时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    let _ = writeln!(out, "Panic: {panic_summary}");
+
+    let _ = writeln!(out, "\n## 环境");
+    let _ = writeln!(out, "版本: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        out,
+        "平台: {}-{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(
+        out,
+        "CPU 并行度: {}",
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(0)
+    );
+    let _ = writeln!(
+        out,
+        "命令行: {}",
+        std::env::args().collect::<Vec<_>>().join(" ")
+    );
+    if let Ok(cwd) = std::env::current_dir() {
+        let _ = writeln!(out, "工作目录: {}", cwd.display());
+    }
+
+    let _ = writeln!(out, "\n## 正在处理的文件");
+    for file in &state.files_in_progress {
+        let _ = writeln!(out, "- {file}");
+    }
+
+    let _ = writeln!(out, "\n## 执行中的外部命令");
+    for command in state.commands_in_flight.values() {
+        let _ = writeln!(out, "- {command}");
+    }
+
+    let _ = writeln!(out, "\n## 最近日志");
+    for line in &state.log_lines {
+        let _ = writeln!(out, "{line}");
+    }
+
+    let _ = writeln!(out, "\n## Backtrace");
+    let _ = writeln!(out, "{}", std::backtrace::Backtrace::force_capture());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_bundle_contains_context() {
+        let mut state = DiagnosticsState::default();
+        state
+            .log_lines
+            .push_back("[00:00:00.000] 开始分析".to_string());
+        state.files_in_progress.insert("/music/a.flac".to_string());
+        state
+            .commands_in_flight
+            .insert(0, "\"ffmpeg\" \"-i\" \"/music/a.flac\"".to_string());
+
+        let bundle = render_bundle(&state, "boom (src/main.rs:1)");
+        assert!(bundle.contains("Panic: boom"));
+        assert!(bundle.contains("/music/a.flac"));
+        assert!(bundle.contains("\"ffmpeg\""));
+        assert!(bundle.contains("开始分析"));
+    }
+
+    #[test]
+    fn test_guards_unregister_on_drop() {
+        let path = Path::new("/tmp/diagnostics_guard_test.flac");
+        {
+            let _file = begin_file(path);
+            let _command = begin_command("ffmpeg -i x".to_string());
+            assert!(state()
+                .files_in_progress
+                .contains("/tmp/diagnostics_guard_test.flac"));
+        }
+        assert!(!state()
+            .files_in_progress
+            .contains("/tmp/diagnostics_guard_test.flac"));
+    }
+}
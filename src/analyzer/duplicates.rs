@@ -0,0 +1,331 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::safe_io;
+use super::scoring::QualityAnalysis;
+
+/// 重复文件清单的文件名，与其他报告一起写在报告目录中。
+pub const DUPLICATES_CSV_FILE_NAME: &str = "audio_quality_duplicates.csv";
+
+/// 副本之间的相同程度。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKind {
+    /// 文件内容哈希相同，逐字节一致。
+    Identical,
+    /// 解码后的 PCM 相同（`--pcm-md5`），标签、封面或容器不同。
+    SameAudio,
+}
+
+impl DuplicateKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DuplicateKind::Identical => "内容完全相同",
+            DuplicateKind::SameAudio => "音频相同",
+        }
+    }
+}
+
+/// 一组互为副本的文件。
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup<'a> {
+    pub kind: DuplicateKind,
+    /// 按分数从高到低（同分按路径）排列，第一份为建议保留的副本。
+    pub copies: Vec<&'a QualityAnalysis>,
+}
+
+impl<'a> DuplicateGroup<'a> {
+    pub fn keep(&self) -> &'a QualityAnalysis {
+        self.copies[0]
+    }
+
+    /// 保留一份后可以删除的副本。
+    pub fn redundant(&self) -> &[&'a QualityAnalysis] {
+        &self.copies[1..]
+    }
+
+    /// 删除多余副本可释放的字节数。
+    pub fn wasted_bytes(&self) -> u64 {
+        self.redundant()
+            .iter()
+            .map(|a| a.metrics.file_size_bytes)
+            .sum()
+    }
+}
+
+/// 按内容哈希与解码后 PCM 的 MD5 把文件分组，只返回至少两份的组（按浪费空间从大到小）。
+/// 两种哈希任一相同即视为副本；CUE 分轨与多音轨共用同一文件，按文件而非按记录计算。
+pub fn find(analyses: &[QualityAnalysis]) -> Vec<DuplicateGroup<'_>> {
+    let mut records_by_path: HashMap<&str, Vec<&QualityAnalysis>> = HashMap::new();
+    let mut files: Vec<&QualityAnalysis> = Vec::new();
+    for analysis in analyses {
+        let records = records_by_path
+            .entry(analysis.file_path.as_str())
+            .or_default();
+        if records.is_empty() {
+            files.push(analysis);
+        }
+        records.push(analysis);
+    }
+    let pcm_keys: Vec<Option<String>> = files
+        .iter()
+        .map(|file| file_pcm_key(&records_by_path[file.file_path.as_str()]))
+        .collect();
+
+    // 并查集：共享任一哈希的文件归入同一组。
+    let mut parent: Vec<usize> = (0..files.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut first_by_content: HashMap<&str, usize> = HashMap::new();
+    let mut first_by_pcm: HashMap<&str, usize> = HashMap::new();
+    for (i, analysis) in files.iter().enumerate() {
+        let hashes = [
+            (
+                &mut first_by_content,
+                analysis.metrics.content_sha256.as_deref(),
+            ),
+            (&mut first_by_pcm, pcm_keys[i].as_deref()),
+        ];
+        for (first, hash) in hashes {
+            if let Some(hash) = hash {
+                let other = *first.entry(hash).or_insert(i);
+                let (a, b) = (root(&mut parent, i), root(&mut parent, other));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<&QualityAnalysis>> = HashMap::new();
+    for (i, analysis) in files.iter().enumerate() {
+        members
+            .entry(root(&mut parent, i))
+            .or_default()
+            .push(analysis);
+    }
+    let mut groups: Vec<DuplicateGroup> = members
+        .into_values()
+        .filter(|copies| copies.len() > 1)
+        .map(|mut copies| {
+            copies.sort_by(|a, b| {
+                b.quality_score
+                    .cmp(&a.quality_score)
+                    .then_with(|| a.file_path.cmp(&b.file_path))
+            });
+            let first = copies[0].metrics.content_sha256.as_deref();
+            let kind = if first.is_some()
+                && copies
+                    .iter()
+                    .all(|c| c.metrics.content_sha256.as_deref() == first)
+            {
+                DuplicateKind::Identical
+            } else {
+                DuplicateKind::SameAudio
+            };
+            DuplicateGroup { kind, copies }
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then_with(|| a.keep().file_path.cmp(&b.keep().file_path))
+    });
+    groups
+}
+
+/// 没有内容哈希（`contentSha256`）的文件数（按文件计）。非 `strict` 指纹模式下新分析的文件没有内容哈希，
+/// 只能按 PCM 摘要（`--pcm-md5`）匹配，重复文件清单可能不完整。
+pub fn count_unhashed(analyses: &[QualityAnalysis]) -> usize {
+    let mut seen = std::collections::HashSet::new();
+    analyses
+        .iter()
+        .filter(|analysis| analysis.metrics.content_sha256.is_none())
+        .filter(|analysis| seen.insert(analysis.file_path.as_str()))
+        .count()
+}
+
+/// 整个文件的 PCM 摘要。CUE 分轨与多音轨的每条记录只覆盖文件的一部分，
+/// 把全部音轨的摘要按音轨排序后拼接：只有全部音轨都相同的文件才视为音频相同，
+/// 单独的第 1 轨文件不会与整轨镜像归为一组。任一音轨缺少摘要，或多音轨文件只分析了部分音轨时返回 `None`。
+fn file_pcm_key(records: &[&QualityAnalysis]) -> Option<String> {
+    let stream_count = records[0].metrics.audio_stream_count.unwrap_or(1).max(1);
+    let mut streams: Vec<u32> = records
+        .iter()
+        .map(|record| record.metrics.audio_stream_index)
+        .collect();
+    streams.sort_unstable();
+    streams.dedup();
+    if (streams.len() as u32) < stream_count {
+        return None;
+    }
+    if let [record] = records {
+        if record.metrics.cue_track.is_none() {
+            return record.metrics.pcm_md5.clone();
+        }
+    }
+    let mut parts = records
+        .iter()
+        .map(|record| {
+            let metrics = &record.metrics;
+            let track = metrics.cue_track.as_ref().map_or(0, |t| t.number);
+            Some(format!(
+                "{}:{track}:{}",
+                metrics.audio_stream_index,
+                metrics.pcm_md5.as_deref()?
+            ))
+        })
+        .collect::<Option<Vec<String>>>()?;
+    parts.sort();
+    Some(parts.join(","))
+}
+
+#[derive(Serialize)]
+struct DuplicateRow<'a> {
+    #[serde(rename = "组")]
+    group: usize,
+    #[serde(rename = "匹配方式")]
+    kind: &'static str,
+    #[serde(rename = "建议")]
+    action: &'static str,
+    #[serde(rename = "质量分")]
+    quality_score: i32,
+    #[serde(rename = "文件大小(字节)")]
+    file_size_bytes: u64,
+    #[serde(rename = "文件路径")]
+    file_path: &'a str,
+}
+
+/// 写出重复文件清单（每组先列保留的副本）；没有重复时删除上次遗留的清单。
+pub fn write_duplicates_csv(path: &Path, groups: &[DuplicateGroup], safe_mode: bool) -> Result<()> {
+    if groups.is_empty() {
+        if path.is_file() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("删除过期的重复文件清单失败: {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        for (index, group) in groups.iter().enumerate() {
+            for (position, copy) in group.copies.iter().enumerate() {
+                writer
+                    .serialize(DuplicateRow {
+                        group: index + 1,
+                        kind: group.kind.label(),
+                        action: if position == 0 { "保留" } else { "可删除" },
+                        quality_score: copy.quality_score,
+                        file_size_bytes: copy.metrics.file_size_bytes,
+                        file_path: &copy.file_path,
+                    })
+                    .context("写入重复文件记录失败")?;
+            }
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    safe_io::atomic_write_bytes(path, &buffer, safe_mode)?;
+    uiprintln!("✅ 重复文件清单已保存到: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::cue::CueTrack;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    fn analysis(path: &str, hash: &str, pcm: Option<&str>, score: i32) -> QualityAnalysis {
+        let mut analysis = QualityScorer::new().analyze_file(&FileMetrics {
+            file_path: path.to_string(),
+            file_size_bytes: 1_000,
+            content_sha256: Some(hash.to_string()),
+            pcm_md5: pcm.map(str::to_string),
+            ..FileMetrics::default()
+        });
+        analysis.quality_score = score;
+        analysis
+    }
+
+    #[test]
+    fn test_duplicate_groups_keep_highest_score() {
+        let analyses = vec![
+            analysis("/m/a.flac", "h1", Some("p1"), 70),
+            analysis("/m/copy/a.flac", "h1", Some("p1"), 70),
+            analysis("/m/retagged/a.flac", "h2", Some("p1"), 90),
+            analysis("/m/b.flac", "h3", None, 80),
+            analysis("/m/backup/b.flac", "h3", None, 80),
+            analysis("/m/unique.flac", "h4", Some("p4"), 60),
+        ];
+        let groups = find(&analyses);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].kind, DuplicateKind::SameAudio);
+        assert_eq!(groups[0].keep().file_path, "/m/retagged/a.flac");
+        assert_eq!(groups[0].wasted_bytes(), 2_000);
+        assert_eq!(groups[1].kind, DuplicateKind::Identical);
+        assert_eq!(groups[1].keep().file_path, "/m/b.flac");
+        assert_eq!(groups[1].redundant()[0].file_path, "/m/backup/b.flac");
+
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join(DUPLICATES_CSV_FILE_NAME);
+        write_duplicates_csv(&path, &groups, true).expect("write");
+        let csv = std::fs::read_to_string(&path).expect("read");
+        assert_eq!(csv.lines().count(), 6);
+        assert!(csv.contains("1,音频相同,保留,90,1000,/m/retagged/a.flac"));
+        write_duplicates_csv(&path, &[], true).expect("remove");
+        assert!(!path.exists());
+
+        // 缺少内容哈希的文件按文件计数，提示重复检测可能不完整。
+        let mut unhashed = analyses.clone();
+        for record in &mut unhashed[..2] {
+            record.metrics.content_sha256 = None;
+        }
+        unhashed.push(unhashed[0].clone());
+        assert_eq!(count_unhashed(&analyses), 0);
+        assert_eq!(count_unhashed(&unhashed), 2);
+    }
+
+    #[test]
+    fn test_cue_image_is_not_a_copy_of_its_first_track() {
+        let track = |number| CueTrack {
+            number,
+            title: None,
+            performer: None,
+            start_seconds: 0.0,
+            end_seconds: None,
+        };
+        let image_track = |path: &str, number, pcm: &str| {
+            let mut record = analysis(path, path, Some(pcm), 80);
+            record.metrics.cue_track = Some(track(number));
+            record
+        };
+        let analyses = vec![
+            image_track("/m/image.flac", 1, "t1"),
+            image_track("/m/image.flac", 2, "t2"),
+            analysis("/m/01.flac", "h1", Some("t1"), 80),
+            image_track("/m/backup/image.wav", 2, "t2"),
+            image_track("/m/backup/image.wav", 1, "t1"),
+        ];
+        let groups = find(&analyses);
+        assert_eq!(groups.len(), 1);
+        let paths: Vec<&str> = groups[0]
+            .copies
+            .iter()
+            .map(|c| c.file_path.as_str())
+            .collect();
+        assert_eq!(paths, ["/m/backup/image.wav", "/m/image.flac"]);
+
+        // 多音轨文件只分析了一条音轨时，不能据此判定为副本。
+        let mut concert = analysis("/m/concert.mkv", "h9", Some("t1"), 80);
+        concert.metrics.audio_stream_count = Some(2);
+        let single = analysis("/m/01 copy.flac", "h8", Some("t1"), 80);
+        assert!(find(&[concert, single]).is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::ffmpeg;
+use super::safe_io;
+
+/// 失败文件清单的文件名，与其他报告一起写在分析目录中。
+pub const ERRORS_CSV_FILE_NAME: &str = "audio_quality_errors.csv";
+
+/// 整个文件处理失败（如 NAS 掉线导致读取失败）时的记录。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedFile {
+    #[serde(rename = "文件路径")]
+    pub file_path: String,
+    #[serde(rename = "错误码")]
+    pub error_code: String,
+    #[serde(rename = "错误信息")]
+    pub message: String,
+}
+
+impl FailedFile {
+    pub fn new(path: &Path, err: &anyhow::Error) -> Self {
+        Self {
+            file_path: path.to_string_lossy().into_owned(),
+            error_code: ffmpeg::extract_error_code(err, "E_FILE_FAILED"),
+            message: err.to_string(),
+        }
+    }
+
+    /// 内容不是音频而被跳过的文件，不计入处理失败，也不写入失败清单。
+    pub fn is_not_audio(&self) -> bool {
+        self.error_code == ffmpeg::NOT_AUDIO_CODE
+    }
+}
+
+/// 写出失败文件清单；没有失败时删除上次遗留的清单，避免误用过期列表重试。
+pub fn write_errors_csv(path: &Path, failures: &[FailedFile], safe_mode: bool) -> Result<()> {
+    if failures.is_empty() {
+        if path.is_file() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("删除过期的失败清单失败: {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        for failure in failures {
+            writer.serialize(failure).context("写入失败清单记录失败")?;
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    safe_io::atomic_write_bytes(path, &buffer, safe_mode)?;
+    uiprintln!("⚠️ 失败文件清单已保存到: {}", path.display());
+    Ok(())
+}
+
+/// 读取失败文件清单中的路径（去重，保持原有顺序）。
+pub fn read_failed_paths(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|e| anyhow!("[E_RETRY_LIST] 无法读取失败清单 {}: {e}", path.display()))?;
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for record in reader.deserialize::<FailedFile>() {
+        let record = record
+            .map_err(|e| anyhow!("[E_RETRY_LIST] 失败清单格式错误 {}: {e}", path.display()))?;
+        let file_path = PathBuf::from(record.file_path);
+        if !paths.contains(&file_path) {
+            paths.push(file_path);
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_errors_csv_round_trip_and_cleanup() {
+        let dir = TempDir::new().expect("tempdir");
+        let csv_path = dir.path().join(ERRORS_CSV_FILE_NAME);
+        let err = anyhow!("[E_EXEC_TIMEOUT] 命令执行超时");
+        let failures = vec![
+            FailedFile::new(Path::new("/nas/A/01.flac"), &err),
+            FailedFile::new(Path::new("/nas/A/02.flac"), &anyhow!("Input/output error")),
+            FailedFile::new(Path::new("/nas/A/01.flac"), &err),
+        ];
+        assert_eq!(failures[0].error_code, "E_EXEC_TIMEOUT");
+        assert_eq!(failures[1].error_code, "E_FILE_FAILED");
+
+        write_errors_csv(&csv_path, &failures, true).expect("write");
+        assert_eq!(
+            read_failed_paths(&csv_path).expect("read"),
+            vec![
+                PathBuf::from("/nas/A/01.flac"),
+                PathBuf::from("/nas/A/02.flac")
+            ]
+        );
+
+        write_errors_csv(&csv_path, &[], true).expect("cleanup");
+        assert!(!csv_path.exists());
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::{anyhow, Context, Result};
 use lazy_static::lazy_static;
+use rayon::prelude::*;
 use regex::Regex;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -9,7 +11,19 @@ use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
+use super::cue::CueTrack;
+use super::diagnostics;
 use super::metrics::{AudioStats, FileMetrics};
+use super::segments::{self, EdgeLevels, SegmentAnalysis};
+
+/// 降级链路成功时写入 `error_codes` 的前缀（警告，而非失败）。
+pub const FALLBACK_CODE_PREFIX: &str = "W_FALLBACK_";
+/// 硬件加速解码失败、改用软件解码成功时写入 `error_codes` 的前缀（警告；测量值与正常解码相同）。
+pub const SOFTWARE_DECODE_CODE_PREFIX: &str = "W_SOFTWARE_DECODE_";
+
+/// 扩展名是音频、ffprobe 却列不出任何音频流（游戏数据、图片等）的文件：跳过分析，不计为处理失败。
+/// ffprobe 本身无法解析（截断、损坏的音频也会如此）时仍按处理失败计，便于重试或排查。
+pub const NOT_AUDIO_CODE: &str = "E_NOT_AUDIO";
 
 #[derive(Debug, Clone)]
 pub struct ProcessingConfig {
@@ -17,6 +31,91 @@ pub struct ProcessingConfig {
     pub ffprobe_path: Option<PathBuf>,
     pub command_timeout: Duration,
     pub process_limiter: ProcessLimiter,
+    /// 解码线程数（作为输入选项 `-threads` 传给 ffmpeg）。
+    pub decode_threads: Option<usize>,
+    /// 硬件加速解码方式（`-hwaccel`），降级重试时会退回软件解码。
+    pub hwaccel: Option<String>,
+    /// 需要测量高通 RMS 的频点（Hz）。
+    pub hf_bands: Vec<u32>,
+    /// 快速采样模式：只分析开头/中间/结尾三个窗口。
+    pub fast_sampling: bool,
+    /// 分段分析的窗口长度（秒）；`None` 表示不做分段分析。
+    pub segment_seconds: Option<u32>,
+    /// 多音轨文件（如演唱会视频）中要分析的音轨序号（从 0 开始，仅计音频流）。
+    pub audio_stream: u32,
+    /// 整轨镜像按 CUE 拆分时要分析的音轨；`None` 表示分析整个文件。
+    pub cue_track: Option<CueTrack>,
+    /// 当前 FFmpeg 版本，写入每条新分析结果。
+    pub ffmpeg_version: Option<String>,
+    /// 缓存结果由主版本号不同的 FFmpeg 生成时重新分析（滤镜输出字段可能随版本变化）。
+    pub reanalyze_on_ffmpeg_change: bool,
+    /// 额外计算解码后 PCM 的 MD5（`--pcm-md5`），用于识别标签或容器不同但音频相同的副本。
+    pub pcm_md5: bool,
+    /// 额外测量贴顶时间占比（`--detect-limiting`），用于重度限幅检测。
+    pub detect_limiting: bool,
+}
+
+/// 多音轨文件的音轨选择：指定序号，或逐条分析全部音轨。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamSelection {
+    Index(u32),
+    All,
+}
+
+impl std::str::FromStr for StreamSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        if text.eq_ignore_ascii_case("all") {
+            return Ok(StreamSelection::All);
+        }
+        text.parse::<u32>()
+            .map(StreamSelection::Index)
+            .map_err(|_| format!("无效的音轨选择: {s}，可选: 音轨序号 (0, 1, ...) 或 all"))
+    }
+}
+
+/// 默认高频分析频点（Hz），评分依赖其中的 14k/16k/18k。
+pub const DEFAULT_HF_BANDS: [u32; 4] = [14_000, 16_000, 18_000, 20_000];
+
+/// 评分必需的频点（Hz），无论 `--hf-bands` 如何设置都会测量。
+/// 14k 作为参照，用于识别 16 kHz 处的截止（128 kbps 级别来源的典型特征）。
+pub const SCORING_HF_BANDS: [u32; 3] = [14_000, 16_000, 18_000];
+
+/// 升采样检测频点（Hz）：由 44.1/48 kHz 来源升采样的文件在此频率以上几乎没有能量。
+pub const UPSAMPLE_CHECK_HZ: u32 = 24_000;
+
+/// 声明采样率高于 48 kHz 时才需要做升采样检测（额外测量 `UPSAMPLE_CHECK_HZ` 频段）。
+pub fn needs_upsample_check(sample_rate_hz: Option<u32>) -> bool {
+    sample_rate_hz.is_some_and(|sr| sr > 48_000)
+}
+
+/// 快速采样模式下每个分析窗口的长度（秒）。
+pub const SAMPLE_WINDOW_SECONDS: f64 = 30.0;
+const SAMPLE_WINDOW_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SampleWindow {
+    start_seconds: f64,
+    length_seconds: f64,
+}
+
+/// 文件内的时间范围（CUE 音轨）；长度未知时分析到文件结尾。
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TimeRange {
+    start_seconds: f64,
+    length_seconds: Option<f64>,
+}
+
+/// 单个文件的分析输入：路径、音轨序号、可选的时间范围以及可选的采样窗口
+/// （`None` 表示分析整个范围；采样窗口使用文件内的绝对时间）。
+#[derive(Debug, Clone)]
+struct AnalysisInput<'a> {
+    path: &'a Path,
+    audio_stream: u32,
+    range: Option<TimeRange>,
+    windows: Option<Vec<SampleWindow>>,
 }
 
 #[derive(Debug, Clone)]
@@ -65,7 +164,9 @@ impl Drop for ProcessPermit {
 
 #[derive(Debug, Default, Clone)]
 struct ProbeData {
+    audio_stream_count: Option<u32>,
     sample_rate_hz: Option<u32>,
+    bit_depth: Option<u32>,
     bitrate_kbps: Option<u32>,
     channels: Option<u32>,
     codec_name: Option<String>,
@@ -81,34 +182,49 @@ struct Ebur128Stats {
 }
 
 #[derive(Debug)]
-struct CommandOutput {
-    status_ok: bool,
+pub(super) struct CommandOutput {
+    pub(super) status_ok: bool,
     stdout: String,
-    stderr: String,
-    status_text: String,
+    pub(super) stderr: String,
+    pub(super) status_text: String,
 }
 
 lazy_static! {
-    static ref EBUR128_LRA_REGEX: Regex = Regex::new(r"LRA:\s*([0-9.+-]+)").unwrap();
+    static ref EBUR128_LRA_REGEX: Regex = Regex::new(r"LRA:\s*([0-9.,+-]+)").unwrap();
     static ref EBUR128_SUMMARY_LRA_REGEX: Regex =
-        Regex::new(r"(?m)^\s*LRA:\s*([0-9.+-]+)\s*LU\s*$").unwrap();
+        Regex::new(r"(?m)^\s*LRA:\s*([0-9.,+-]+)\s*LU\s*$").unwrap();
     static ref EBUR128_SUMMARY_I_REGEX: Regex =
-        Regex::new(r"(?m)^\s*I:\s*([0-9.+-]+)\s*LUFS\s*$").unwrap();
+        Regex::new(r"(?m)^\s*I:\s*([0-9.,+-]+)\s*LUFS\s*$").unwrap();
     static ref EBUR128_SUMMARY_TP_REGEX: Regex =
-        Regex::new(r"(?m)^\s*Peak:\s*([0-9.+-]+)\s*dBFS\s*$").unwrap();
-    static ref EBUR128_STREAM_TPK_REGEX: Regex = Regex::new(r"TPK:\s*([0-9.+-]+)").unwrap();
+        Regex::new(r"(?m)^\s*Peak:\s*([0-9.,+-]+)\s*dBFS\s*$").unwrap();
+    static ref EBUR128_STREAM_TPK_REGEX: Regex = Regex::new(r"TPK:\s*([0-9.,+-]+)").unwrap();
     static ref OVERALL_STATS_REGEX: Regex =
-        Regex::new(r"(?s)Overall.*?Peak level dB:\s*([-\d.]+).*?RMS level dB:\s*([-\d.]+)")
+        Regex::new(r"(?s)Overall.*?Peak level dB:\s*([-\d.,]+).*?RMS level dB:\s*([-\d.,]+)")
             .unwrap();
+    static ref EFFECTIVE_BIT_DEPTH_REGEX: Regex =
+        Regex::new(r"(?s)Overall.*?Bit depth:\s*(\d+)").unwrap();
     static ref HIGHPASS_ASTATS_REGEX: Regex =
-        Regex::new(r"(?s)Overall.*?RMS level dB:\s*([-\d.]+)").unwrap();
+        Regex::new(r"(?s)Overall.*?RMS level dB:\s*([-\d.,]+)").unwrap();
     static ref ERROR_CODE_REGEX: Regex = Regex::new(r"\[(E_[A-Z0-9_]+)\]").unwrap();
 }
 
-fn run_command(mut command: Command, config: &ProcessingConfig) -> Result<CommandOutput> {
+fn run_command(command: Command, config: &ProcessingConfig) -> Result<CommandOutput> {
+    run_command_with_timeout(command, config, config.command_timeout)
+}
+
+/// 同 `run_command`，但使用单独的超时（如下载远程文件）。
+pub(super) fn run_command_with_timeout(
+    mut command: Command,
+    config: &ProcessingConfig,
+    timeout: Duration,
+) -> Result<CommandOutput> {
     let _permit = config.process_limiter.acquire();
+    let description = format!("{command:?}");
+    let _in_flight = diagnostics::begin_command(description.clone());
 
+    // 固定 C locale：部分 FFmpeg 构建在非 C locale 下以逗号作小数点输出统计值。
     command
+        .env("LC_ALL", "C")
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -143,15 +259,16 @@ fn run_command(mut command: Command, config: &ProcessingConfig) -> Result<Comman
             break status;
         }
 
-        if start.elapsed() > config.command_timeout {
+        if start.elapsed() > timeout {
             let _ = child.kill();
             let _ = child.wait();
 
             let _ = stdout_thread.join();
             let _ = stderr_thread.join();
+            tracing::debug!("外部命令超时，已终止: {description}");
             return Err(anyhow!(
                 "[E_TIMEOUT] 外部命令执行超时 (>{}s)",
-                config.command_timeout.as_secs()
+                timeout.as_secs()
             ));
         }
 
@@ -164,6 +281,11 @@ fn run_command(mut command: Command, config: &ProcessingConfig) -> Result<Comman
     let stderr_bytes = stderr_thread
         .join()
         .map_err(|_| anyhow!("[E_EXEC_STDERR] 读取 stderr 线程崩溃"))??;
+    tracing::debug!(
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        %status,
+        "外部命令结束: {description}"
+    );
 
     Ok(CommandOutput {
         status_ok: status.success(),
@@ -173,122 +295,663 @@ fn run_command(mut command: Command, config: &ProcessingConfig) -> Result<Comman
     })
 }
 
-fn run_command_and_get_stderr(command: Command, config: &ProcessingConfig) -> Result<String> {
+/// 滤镜链的执行路径，按重试顺序排列：使用 `--hwaccel` 时主路径失败先去掉硬件加速、以软件解码重试；
+/// 因滤镜/格式协商失败（例如不支持的声道布局）时，再以强制 `aformat` 下混为立体声的简化链路重试一次。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum FilterAttempt {
+    Primary,
+    Software,
+    Fallback,
+}
+
+const FALLBACK_AFORMAT: &str = "aformat=sample_fmts=fltp:channel_layouts=stereo";
+
+/// ffmpeg 滤镜图或格式协商失败时的 stderr 特征；只有这类失败才值得以下混链路重试。
+/// 损坏、截断或不支持的文件重试也不会成功，且下混后的测量值不能代表多声道源文件。
+const FILTER_NEGOTIATION_ERRORS: [&str; 4] = [
+    "Failed to configure",
+    "Error reinitializing filters",
+    "Invalid channel layout",
+    "Cannot find a matching",
+];
+
+fn is_filter_negotiation_failure(stderr: &str) -> bool {
+    FILTER_NEGOTIATION_ERRORS
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+#[derive(Debug)]
+struct Measured<T> {
+    value: T,
+    /// 成功的链路；由多次命令组成的测量取其中最靠后的链路。
+    attempt: FilterAttempt,
+}
+
+fn build_filter_chain(filter: &str, attempt: FilterAttempt) -> String {
+    match attempt {
+        FilterAttempt::Primary | FilterAttempt::Software => filter.to_owned(),
+        FilterAttempt::Fallback => format!("{FALLBACK_AFORMAT},{filter}"),
+    }
+}
+
+/// 追加解码相关的输入选项；只有主路径使用硬件加速，重试链路均为软件解码。
+fn apply_decode_options(command: &mut Command, config: &ProcessingConfig, attempt: FilterAttempt) {
+    if let Some(threads) = config.decode_threads {
+        command.arg("-threads").arg(threads.to_string());
+    }
+    if attempt == FilterAttempt::Primary {
+        if let Some(hwaccel) = &config.hwaccel {
+            command.arg("-hwaccel").arg(hwaccel);
+        }
+    }
+}
+
+/// 检查当前 ffmpeg 是否支持指定的硬件加速方式（`auto` 始终视为可用）。
+pub fn hwaccel_available(config: &ProcessingConfig, name: &str) -> Result<bool> {
+    if name.eq_ignore_ascii_case("auto") {
+        return Ok(true);
+    }
+
+    let mut command = Command::new(&config.ffmpeg_path);
+    command.arg("-hide_banner").arg("-hwaccels");
+    let output = run_command(command, config)?;
+    if !output.status_ok {
+        return Err(anyhow!(
+            "[E_EXEC_FAILED] 无法查询硬件加速列表 (status: {})",
+            output.status_text
+        ));
+    }
+
+    Ok(parse_hwaccels(&output.stdout)
+        .iter()
+        .any(|method| method.eq_ignore_ascii_case(name)))
+}
+
+/// 查找 ffmpeg/ffprobe 时校验候选文件：`-version` 在 10 秒内成功退出才视为可用，
+/// 避免同名的损坏文件、其他程序或缺少动态库的构建被当作 FFmpeg 使用。
+pub fn responds_to_version(path: &Path) -> bool {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    let Ok(mut child) = Command::new(path)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return false;
+            }
+        }
+    }
+}
+
+/// 查询 ffmpeg 版本号；无法执行或输出无法解析时返回 `None`。
+pub fn ffmpeg_version(config: &ProcessingConfig) -> Option<String> {
+    let mut command = Command::new(&config.ffmpeg_path);
+    command.arg("-version");
+    let output = run_command(command, config).ok()?;
+    if !output.status_ok {
+        return None;
+    }
+    parse_tool_version(&output.stdout, "ffmpeg")
+}
+
+/// 当前 ffprobe 的版本号；没有 ffprobe 或无法识别时为 `None`。
+pub fn ffprobe_version(config: &ProcessingConfig) -> Option<String> {
+    let mut command = Command::new(config.ffprobe_path.as_ref()?);
+    command.arg("-version");
+    let output = run_command(command, config).ok()?;
+    if !output.status_ok {
+        return None;
+    }
+    parse_tool_version(&output.stdout, "ffprobe")
+}
+
+/// 频谱图缩略图的尺寸（宽x高，像素），用于 `serve` 界面。
+pub const SPECTROGRAM_SIZE: &str = "480x160";
+/// 报告中频谱图的尺寸，表格中缩小显示，点击后按原尺寸查看。
+pub const SPECTROGRAM_FULL_SIZE: &str = "1200x400";
+
+/// 用 `showspectrumpic` 为分析结果对应的音轨渲染一张 `size` 尺寸的 PNG 频谱图，写到 `output`。
+/// CUE 分轨只渲染该轨的时间范围，多音轨文件渲染当时分析的那条音轨。
+pub fn render_spectrogram(
+    metrics: &FileMetrics,
+    output: &Path,
+    size: &str,
+    config: &ProcessingConfig,
+) -> Result<()> {
+    let mut command = Command::new(&config.ffmpeg_path);
+    command.arg("-hide_banner").arg("-y");
+    if let Some(track) = &metrics.cue_track {
+        command
+            .arg("-ss")
+            .arg(format!("{:.3}", track.start_seconds));
+        if let Some(length) = track.duration_seconds(metrics.duration_seconds) {
+            command.arg("-t").arg(format!("{length:.3}"));
+        }
+    }
+    command
+        .arg("-i")
+        .arg(&metrics.file_path)
+        .arg("-filter_complex")
+        .arg(format!(
+            "[0:a:{}]showspectrumpic=s={size}:legend=0",
+            metrics.audio_stream_index
+        ))
+        .arg("-frames:v")
+        .arg("1")
+        .arg(output);
     let output = run_command(command, config)?;
     if !output.status_ok {
         let preview = output.stderr.chars().take(500).collect::<String>();
         return Err(anyhow!(
-            "[E_EXEC_FAILED] 命令执行失败 (status: {}): {}",
+            "[E_SPECTROGRAM] 频谱图渲染失败 (status: {}): {}",
             output.status_text,
             preview
         ));
     }
-    Ok(output.stderr)
+    Ok(())
+}
+
+/// 从 `ffmpeg version 6.1.1-3ubuntu5 Copyright ...`（ffprobe 同理）中取出版本号。
+fn parse_tool_version(stdout: &str, tool: &str) -> Option<String> {
+    stdout
+        .lines()
+        .next()?
+        .strip_prefix(tool)?
+        .strip_prefix(" version ")?
+        .split_whitespace()
+        .next()
+        .map(ToOwned::to_owned)
+}
+
+/// 两个 FFmpeg 版本的测量结果是否可以混用：能解析出主版本号时比较主版本号，
+/// 否则（如 `N-113000-g...` 的开发版）要求完全一致。
+pub fn ffmpeg_versions_compatible(recorded: Option<&str>, current: &str) -> bool {
+    let major = |version: &str| -> Option<u32> {
+        version
+            .trim_start_matches(['n', 'N'])
+            .split(['.', '-'])
+            .next()?
+            .parse()
+            .ok()
+    };
+    match recorded {
+        Some(recorded) => match (major(recorded), major(current)) {
+            (Some(a), Some(b)) => a == b,
+            _ => recorded == current,
+        },
+        None => false,
+    }
 }
 
-fn get_ebur128_stats(path: &Path, config: &ProcessingConfig) -> Result<Ebur128Stats> {
+/// 当前 ffmpeg 内置的滤镜名称（`ffmpeg -filters`）。
+pub fn available_filters(config: &ProcessingConfig) -> Result<Vec<String>> {
     let mut command = Command::new(&config.ffmpeg_path);
+    command.arg("-hide_banner").arg("-filters");
+    let output = run_command(command, config)?;
+    if !output.status_ok {
+        return Err(anyhow!(
+            "[E_EXEC_FAILED] 无法查询滤镜列表 (status: {})",
+            output.status_text
+        ));
+    }
+    Ok(parse_filters(&output.stdout))
+}
+
+/// 解析 `ffmpeg -filters`：跳过说明行，每行为 `标志 名称 输入->输出 描述`。
+fn parse_filters(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (flags, name, io) = (fields.next()?, fields.next()?, fields.next()?);
+            let is_flags = flags.len() == 3 && flags.chars().all(|c| "TSC.".contains(c));
+            (is_flags && io.contains("->")).then(|| name.to_owned())
+        })
+        .collect()
+}
+
+fn parse_hwaccels(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("Hardware acceleration methods"))
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// 计算开头/中间/结尾三个采样窗口；时长未知或不长于全部窗口之和时返回 `None`，退回全文件分析。
+fn sample_windows(duration_seconds: Option<f64>) -> Option<Vec<SampleWindow>> {
+    let duration = duration_seconds.filter(|d| d.is_finite())?;
+    if duration <= SAMPLE_WINDOW_SECONDS * SAMPLE_WINDOW_COUNT as f64 {
+        return None;
+    }
+
+    let last_start = duration - SAMPLE_WINDOW_SECONDS;
+    let windows = (0..SAMPLE_WINDOW_COUNT)
+        .map(|i| SampleWindow {
+            start_seconds: last_start * i as f64 / (SAMPLE_WINDOW_COUNT - 1) as f64,
+            length_seconds: SAMPLE_WINDOW_SECONDS,
+        })
+        .collect();
+    Some(windows)
+}
+
+/// 把多个采样输入的指定音轨拼接为一路音频后再接分析滤镜链。
+fn sampled_filter_graph(filter_chain: &str, inputs: usize, audio_stream: u32) -> String {
+    let labels: String = (0..inputs)
+        .map(|i| format!("[{i}:a:{audio_stream}]"))
+        .collect();
+    format!("{labels}concat=n={inputs}:v=0:a=1,{filter_chain}")
+}
+
+fn build_filter_command(
+    input: &AnalysisInput<'_>,
+    filter_flag: &str,
+    filter: &str,
+    config: &ProcessingConfig,
+    attempt: FilterAttempt,
+) -> Command {
+    let mut command = Command::new(&config.ffmpeg_path);
+    let filter_chain = build_filter_chain(filter, attempt);
+
+    match &input.windows {
+        None => {
+            apply_decode_options(&mut command, config, attempt);
+            if let Some(range) = input.range {
+                command
+                    .arg("-ss")
+                    .arg(format!("{:.3}", range.start_seconds));
+                if let Some(length) = range.length_seconds {
+                    command.arg("-t").arg(format!("{length:.3}"));
+                }
+            }
+            command.arg("-i").arg(input.path);
+            // 显式选择音轨，视频容器与多音轨文件都只分析指定的音频流。
+            if filter_flag == "-filter_complex" {
+                command
+                    .arg(filter_flag)
+                    .arg(format!("[0:a:{}]{filter_chain}", input.audio_stream));
+            } else {
+                command
+                    .arg("-map")
+                    .arg(format!("0:a:{}", input.audio_stream))
+                    .arg(filter_flag)
+                    .arg(filter_chain);
+            }
+        }
+        Some(windows) => {
+            // 每个窗口作为一路独立输入（输入级 -ss 可快速定位），再经 concat 拼接。
+            for window in windows {
+                apply_decode_options(&mut command, config, attempt);
+                command
+                    .arg("-ss")
+                    .arg(format!("{:.3}", window.start_seconds))
+                    .arg("-t")
+                    .arg(format!("{:.3}", window.length_seconds))
+                    .arg("-i")
+                    .arg(input.path);
+            }
+            command.arg("-filter_complex").arg(sampled_filter_graph(
+                &filter_chain,
+                windows.len(),
+                input.audio_stream,
+            ));
+        }
+    }
+
+    // 视频容器中的画面/字幕/数据流不参与分析，避免无谓解码。
     command
-        .arg("-i")
-        .arg(path)
-        .arg("-filter_complex")
-        .arg("ebur128=peak=true")
+        .arg("-vn")
+        .arg("-sn")
+        .arg("-dn")
         .arg("-f")
         .arg("null")
         .arg("-");
+    command
+}
 
-    let stderr = run_command_and_get_stderr(command, config)?;
+fn run_filter_command(
+    input: &AnalysisInput<'_>,
+    filter_flag: &str,
+    filter: &str,
+    config: &ProcessingConfig,
+) -> Result<Measured<String>> {
+    let mut attempt = FilterAttempt::Primary;
+    let output = loop {
+        let command = build_filter_command(input, filter_flag, filter, config, attempt);
+
+        // 超时/启动失败等非滤镜问题直接返回，不做降级重试。
+        let output = run_command(command, config)?;
+        if output.status_ok {
+            return Ok(Measured {
+                value: output.stderr,
+                attempt,
+            });
+        }
+        match next_attempt(attempt, &output.stderr, config.hwaccel.is_some()) {
+            Some(next) => attempt = next,
+            None => break output,
+        }
+    };
+
+    let preview = output.stderr.chars().take(500).collect::<String>();
+    Err(anyhow!(
+        "[E_EXEC_FAILED] 命令执行失败 (status: {}): {}",
+        output.status_text,
+        preview
+    ))
+}
 
+/// 失败后的下一条链路：硬件加速的主路径先以软件解码重试（不改变测量内容）；
+/// 只有滤镜/格式协商失败才进一步以下混链路重试。
+fn next_attempt(attempt: FilterAttempt, stderr: &str, hwaccel: bool) -> Option<FilterAttempt> {
+    match attempt {
+        FilterAttempt::Primary if hwaccel => Some(FilterAttempt::Software),
+        FilterAttempt::Primary | FilterAttempt::Software
+            if is_filter_negotiation_failure(stderr) =>
+        {
+            Some(FilterAttempt::Fallback)
+        }
+        _ => None,
+    }
+}
+
+fn get_ebur128_stats(
+    input: &AnalysisInput<'_>,
+    config: &ProcessingConfig,
+) -> Result<Measured<Ebur128Stats>> {
+    let run = run_filter_command(input, "-filter_complex", "ebur128=peak=true", config)?;
+    let value = parse_ebur128_output(&run.value)
+        .ok_or_else(|| anyhow!("[E_PARSE_EBUR128] 无法完整解析 ebur128 输出"))?;
+    Ok(Measured {
+        value,
+        attempt: run.attempt,
+    })
+}
+
+/// 解析 ebur128 输出：优先取结尾的 Summary，没有时退回逐帧输出的最后一个值。
+/// LRA 与综合响度缺一即视为解析失败。
+fn parse_ebur128_output(stderr: &str) -> Option<Ebur128Stats> {
     let lra = EBUR128_SUMMARY_LRA_REGEX
-        .captures(&stderr)
+        .captures(stderr)
         .and_then(|caps| caps.get(1))
         .and_then(|m| parse_float_token(m.as_str()))
         .or_else(|| {
             EBUR128_LRA_REGEX
-                .captures_iter(&stderr)
+                .captures_iter(stderr)
                 .filter_map(|caps| caps.get(1).and_then(|m| parse_float_token(m.as_str())))
                 .last()
         });
 
     let integrated_loudness_lufs = EBUR128_SUMMARY_I_REGEX
-        .captures(&stderr)
+        .captures(stderr)
         .and_then(|caps| caps.get(1))
         .and_then(|m| parse_float_token(m.as_str()));
 
     let true_peak_dbtp = EBUR128_SUMMARY_TP_REGEX
-        .captures(&stderr)
+        .captures(stderr)
         .and_then(|caps| caps.get(1))
         .and_then(|m| parse_float_token(m.as_str()))
         .or_else(|| {
             EBUR128_STREAM_TPK_REGEX
-                .captures_iter(&stderr)
+                .captures_iter(stderr)
                 .filter_map(|caps| caps.get(1).and_then(|m| parse_float_token(m.as_str())))
                 .last()
         });
 
     if lra.is_none() || integrated_loudness_lufs.is_none() {
-        return Err(anyhow!("[E_PARSE_EBUR128] 无法完整解析 ebur128 输出"));
+        return None;
     }
-
-    Ok(Ebur128Stats {
+    Some(Ebur128Stats {
         lra,
         integrated_loudness_lufs,
         true_peak_dbtp,
     })
 }
 
-fn parse_float_token(token: &str) -> Option<f64> {
+/// 解析 FFmpeg 输出中的数值，兼容 `inf`/`-inf` 与逗号小数点（如 `-12,3`）。
+pub fn parse_float_token(token: &str) -> Option<f64> {
     let text = token.trim().to_ascii_lowercase();
     match text.as_str() {
         "inf" | "+inf" => Some(f64::INFINITY),
         "-inf" => Some(f64::NEG_INFINITY),
         "nan" => None,
-        _ => text.parse::<f64>().ok(),
+        _ => text
+            .parse::<f64>()
+            .ok()
+            .or_else(|| text.replace(',', ".").parse::<f64>().ok()),
     }
 }
 
-fn get_stats_ffmpeg(path: &Path, config: &ProcessingConfig) -> Result<AudioStats> {
-    let mut command = Command::new(&config.ffmpeg_path);
-    command
-        .arg("-i")
-        .arg(path)
-        .arg("-filter:a")
-        .arg("astats=metadata=1")
-        .arg("-f")
-        .arg("null")
-        .arg("-");
+fn get_stats_ffmpeg(
+    input: &AnalysisInput<'_>,
+    config: &ProcessingConfig,
+) -> Result<Measured<AudioStats>> {
+    let run = run_filter_command(input, "-filter:a", "astats=metadata=1", config)?;
+
+    let mut value = parse_astats_output(&run.value)
+        .ok_or_else(|| anyhow!("[E_PARSE_STATS] 无法解析峰值/RMS"))?;
+    // 降级链路已转为浮点样本，统计到的位数不代表源文件。
+    if run.attempt == FilterAttempt::Fallback {
+        value.effective_bit_depth = None;
+    }
+    Ok(Measured {
+        value,
+        attempt: run.attempt,
+    })
+}
+
+/// 解析 astats 的 `Overall` 统计块：峰值、RMS 与实测位深。
+fn parse_astats_output(stderr: &str) -> Option<AudioStats> {
+    OVERALL_STATS_REGEX.captures(stderr).map(|caps| AudioStats {
+        peak_db: caps.get(1).and_then(|m| parse_float_token(m.as_str())),
+        rms_db: caps.get(2).and_then(|m| parse_float_token(m.as_str())),
+        effective_bit_depth: EFFECTIVE_BIT_DEPTH_REGEX
+            .captures(stderr)
+            .and_then(|caps| caps[1].parse::<u32>().ok()),
+    })
+}
+
+fn get_highpass_rms_ffmpeg(
+    input: &AnalysisInput<'_>,
+    freq: u32,
+    config: &ProcessingConfig,
+) -> Result<Measured<f64>> {
+    let filter_str = format!("highpass=f={freq},astats=metadata=1");
+    let run = run_filter_command(input, "-filter:a", &filter_str, config)?;
+
+    HIGHPASS_ASTATS_REGEX
+        .captures(&run.value)
+        .and_then(|caps| caps.get(1))
+        .and_then(|m| parse_float_token(m.as_str()))
+        .map(|value| Measured {
+            value,
+            attempt: run.attempt,
+        })
+        .ok_or_else(|| anyhow!("[E_PARSE_HIGHPASS] 无法解析高通 RMS (freq: {freq})"))
+}
+
+/// 逐窗口测量 RMS/峰值与高频能量。始终分析整个文件（CUE 音轨则为整轨），以便时间段与原曲对应。
+fn get_segment_analysis(
+    input: &AnalysisInput<'_>,
+    window_seconds: u32,
+    probe: &ProbeData,
+    duration_seconds: Option<f64>,
+    config: &ProcessingConfig,
+) -> Result<Measured<SegmentAnalysis>> {
+    let input = AnalysisInput {
+        windows: None,
+        ..input.clone()
+    };
+    // 采样率未知时按 44.1kHz 切帧；窗口时间取自 pts_time，不受影响。
+    let sample_rate_hz = probe.sample_rate_hz.unwrap_or(44_100);
+    let full_filter = segments::segment_filter(window_seconds, sample_rate_hz, None);
+    let hf_filter = segments::segment_filter(
+        window_seconds,
+        sample_rate_hz,
+        Some(segments::SEGMENT_HF_FREQ),
+    );
 
-    let stderr = run_command_and_get_stderr(command, config)?;
+    let (full_run, hf_run) = rayon::join(
+        || run_filter_command(&input, "-filter:a", &full_filter, config),
+        || run_filter_command(&input, "-filter:a", &hf_filter, config),
+    );
+    let (full_run, hf_run) = (full_run?, hf_run?);
+
+    let full = segments::parse_window_stats(&full_run.value);
+    if full.is_empty() {
+        return Err(anyhow!("[E_PARSE_SEGMENTS] 无法解析分段统计输出"));
+    }
+    let highpass = segments::parse_window_stats(&hf_run.value);
+
+    Ok(Measured {
+        value: SegmentAnalysis::from_stats(window_seconds, duration_seconds, full, &highpass),
+        attempt: full_run.attempt.max(hf_run.attempt),
+    })
+}
+
+/// 贴顶时间占比（限幅检测），与其他测量使用相同的输入范围（含快速采样窗口）。
+/// 需要额外一次完整解码，只在 `--detect-limiting` 时测量。
+fn get_ceiling_time_percent(
+    input: &AnalysisInput<'_>,
+    probe: &ProbeData,
+    config: &ProcessingConfig,
+) -> Option<Result<Measured<Option<f64>>>> {
+    if !config.detect_limiting {
+        return None;
+    }
+    let filter = segments::ceiling_filter(probe.sample_rate_hz.unwrap_or(44_100));
+    Some(
+        run_filter_command(input, "-filter:a", &filter, config).and_then(|run| {
+            let windows = segments::parse_window_stats(&run.value);
+            if windows.is_empty() {
+                return Err(anyhow!("[E_PARSE_CEILING] 无法解析限幅检测输出"));
+            }
+            Ok(Measured {
+                value: segments::ceiling_time_percent(&windows),
+                attempt: run.attempt,
+            })
+        }),
+    )
+}
 
-    OVERALL_STATS_REGEX
-        .captures(&stderr)
-        .map(|caps| {
-            let peak_db = caps.get(1).and_then(|m| m.as_str().parse::<f64>().ok());
-            let rms_db = caps.get(2).and_then(|m| m.as_str().parse::<f64>().ok());
-            AudioStats { peak_db, rms_db }
+/// 开头/结尾各 `EDGE_WINDOW_SECONDS` 的电平，供专辑级曲目衔接检查使用。
+/// 始终按整个范围（CUE 音轨则为整轨）定位，时长未知或过短时不测量。
+fn get_edge_levels(
+    input: &AnalysisInput<'_>,
+    duration_seconds: Option<f64>,
+    config: &ProcessingConfig,
+) -> Option<Result<Measured<EdgeLevels>>> {
+    let duration = duration_seconds.filter(|d| *d >= 2.0 * segments::EDGE_WINDOW_SECONDS)?;
+    let offset = input.range.map_or(0.0, |r| r.start_seconds);
+    let edge_input = |start_seconds: f64| AnalysisInput {
+        range: Some(TimeRange {
+            start_seconds,
+            length_seconds: Some(segments::EDGE_WINDOW_SECONDS),
+        }),
+        windows: None,
+        ..input.clone()
+    };
+    let (head, tail) = rayon::join(
+        || get_stats_ffmpeg(&edge_input(offset), config),
+        || {
+            get_stats_ffmpeg(
+                &edge_input(offset + duration - segments::EDGE_WINDOW_SECONDS),
+                config,
+            )
+        },
+    );
+    Some(head.and_then(|head| {
+        let tail = tail?;
+        Ok(Measured {
+            value: EdgeLevels::from_stats(head.value, tail.value),
+            attempt: head.attempt.max(tail.attempt),
         })
-        .ok_or_else(|| anyhow!("[E_PARSE_STATS] 无法解析峰值/RMS"))
+    }))
 }
 
-fn get_highpass_rms_ffmpeg(path: &Path, freq: u32, config: &ProcessingConfig) -> Result<f64> {
+/// 解码后 PCM 的 MD5（`-f md5`）。只在 `--pcm-md5` 时计算；
+/// 显式转为 64 位浮点 PCM 再计算：`md5` 封装默认输出 16 位 PCM，24 位文件与其截断到 16 位的副本会得到相同的摘要，
+/// 而 16/24/32 位整数与 32 位浮点样本都能无损转为 64 位浮点。
+/// 快速采样只覆盖部分内容，此时不计算。不做降级重试：下混后的 PCM 不再能与其他副本比较。
+fn get_pcm_md5(
+    input: &AnalysisInput<'_>,
+    config: &ProcessingConfig,
+) -> Option<Result<Measured<String>>> {
+    if !config.pcm_md5 || input.windows.is_some() {
+        return None;
+    }
     let mut command = Command::new(&config.ffmpeg_path);
-    let filter_str = format!("highpass=f={freq},astats=metadata=1");
+    apply_decode_options(&mut command, config, FilterAttempt::Primary);
+    if let Some(range) = input.range {
+        command
+            .arg("-ss")
+            .arg(format!("{:.3}", range.start_seconds));
+        if let Some(length) = range.length_seconds {
+            command.arg("-t").arg(format!("{length:.3}"));
+        }
+    }
     command
         .arg("-i")
-        .arg(path)
-        .arg("-filter:a")
-        .arg(filter_str)
+        .arg(input.path)
+        .arg("-map")
+        .arg(format!("0:a:{}", input.audio_stream))
+        .arg("-vn")
+        .arg("-sn")
+        .arg("-dn")
+        .arg("-c:a")
+        .arg("pcm_f64le")
         .arg("-f")
-        .arg("null")
+        .arg("md5")
         .arg("-");
+    Some(run_command(command, config).and_then(|output| {
+        if !output.status_ok {
+            let preview = output.stderr.chars().take(500).collect::<String>();
+            return Err(anyhow!(
+                "[E_EXEC_FAILED] 命令执行失败 (status: {}): {}",
+                output.status_text,
+                preview
+            ));
+        }
+        let value = parse_md5_output(&output.stdout)
+            .ok_or_else(|| anyhow!("[E_PARSE_PCM_MD5] 无法解析 PCM MD5 输出"))?;
+        Ok(Measured {
+            value,
+            attempt: FilterAttempt::Primary,
+        })
+    }))
+}
 
-    let stderr = run_command_and_get_stderr(command, config)?;
+/// 解析 `-f md5` 的输出行 `MD5=<32 位十六进制>`。
+fn parse_md5_output(stdout: &str) -> Option<String> {
+    stdout.lines().find_map(|line| {
+        let hex = line.trim().strip_prefix("MD5=")?;
+        (hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| hex.to_ascii_lowercase())
+    })
+}
 
-    HIGHPASS_ASTATS_REGEX
-        .captures(&stderr)
-        .and_then(|caps| caps.get(1))
-        .and_then(|m| m.as_str().parse::<f64>().ok())
-        .ok_or_else(|| anyhow!("[E_PARSE_HIGHPASS] 无法解析高通 RMS (freq: {freq})"))
+/// 文件中的音轨数量；ffprobe 不可用时返回 `None`。
+pub fn count_audio_streams(path: &Path, config: &ProcessingConfig) -> Result<Option<u32>> {
+    let probe_config = ProcessingConfig {
+        audio_stream: 0,
+        ..config.clone()
+    };
+    Ok(get_probe_data(path, &probe_config)?.audio_stream_count)
 }
 
 fn get_probe_data(path: &Path, config: &ProcessingConfig) -> Result<ProbeData> {
@@ -302,9 +965,9 @@ fn get_probe_data(path: &Path, config: &ProcessingConfig) -> Result<ProbeData> {
         .arg("-v")
         .arg("error")
         .arg("-select_streams")
-        .arg("a:0")
+        .arg("a")
         .arg("-show_entries")
-        .arg("stream=codec_name,sample_rate,channels,bit_rate:format=format_name,bit_rate,duration")
+        .arg("stream=codec_name,sample_rate,channels,bit_rate,bits_per_raw_sample,bits_per_sample:format=format_name,bit_rate,duration")
         .arg("-of")
         .arg("json")
         .arg(path);
@@ -319,17 +982,32 @@ fn get_probe_data(path: &Path, config: &ProcessingConfig) -> Result<ProbeData> {
         ));
     }
 
-    parse_probe_json(&output.stdout)
+    parse_probe_json(&output.stdout, config.audio_stream)
 }
 
-fn parse_probe_json(text: &str) -> Result<ProbeData> {
+/// 解析 ffprobe 输出（`streams` 仅包含音频流），取第 `audio_stream` 条音轨的参数。
+fn parse_probe_json(text: &str, audio_stream: u32) -> Result<ProbeData> {
     let value: Value = serde_json::from_str(text)
         .map_err(|_| anyhow!("[E_PARSE_FFPROBE] ffprobe JSON 解析失败"))?;
 
-    let stream = value
+    let streams = value
         .get("streams")
         .and_then(|v| v.as_array())
-        .and_then(|arr| arr.first())
+        .cloned()
+        .unwrap_or_default();
+    if streams.is_empty() {
+        return Err(anyhow!("[{NOT_AUDIO_CODE}] 文件中没有音频流"));
+    }
+    let audio_stream_count = u32::try_from(streams.len()).ok();
+    if audio_stream as usize >= streams.len() {
+        return Err(anyhow!(
+            "[E_NO_AUDIO_STREAM] 文件只有 {} 条音轨，无法分析第 {} 条",
+            streams.len(),
+            audio_stream
+        ));
+    }
+    let stream = streams
+        .get(audio_stream as usize)
         .cloned()
         .unwrap_or(Value::Null);
 
@@ -337,6 +1015,10 @@ fn parse_probe_json(text: &str) -> Result<ProbeData> {
 
     let sample_rate_hz = parse_u32(stream.get("sample_rate"));
     let channels = parse_u32(stream.get("channels"));
+    // FLAC/ALAC 等报告 bits_per_raw_sample，PCM 报告 bits_per_sample；有损编码两者均为 0。
+    let bit_depth = parse_u32(stream.get("bits_per_raw_sample"))
+        .filter(|bits| *bits > 0)
+        .or_else(|| parse_u32(stream.get("bits_per_sample")).filter(|bits| *bits > 0));
     let codec_name = parse_string(stream.get("codec_name"));
     let container_format = parse_string(format.get("format_name"));
     let duration_seconds = parse_f64(format.get("duration"));
@@ -348,7 +1030,9 @@ fn parse_probe_json(text: &str) -> Result<ProbeData> {
         .map(|bps| ((bps as f64) / 1000.0).round() as u32);
 
     Ok(ProbeData {
+        audio_stream_count,
         sample_rate_hz,
+        bit_depth,
         bitrate_kbps,
         channels,
         codec_name,
@@ -381,7 +1065,8 @@ fn parse_string(value: Option<&Value>) -> Option<String> {
     value.and_then(|v| v.as_str()).map(ToOwned::to_owned)
 }
 
-fn extract_error_code(err: &anyhow::Error, fallback: &str) -> String {
+/// 从错误信息中提取 `[E_XXX]` 错误码，没有时使用 `fallback`。
+pub fn extract_error_code(err: &anyhow::Error, fallback: &str) -> String {
     let msg = err.to_string();
     ERROR_CODE_REGEX
         .captures(&msg)
@@ -389,78 +1074,178 @@ fn extract_error_code(err: &anyhow::Error, fallback: &str) -> String {
         .unwrap_or_else(|| fallback.to_owned())
 }
 
+/// 错误是否表示文件内容不是音频（见 [`NOT_AUDIO_CODE`]）。
+pub fn is_not_audio(err: &anyhow::Error) -> bool {
+    extract_error_code(err, "") == NOT_AUDIO_CODE
+}
+
+/// 高频段对应的错误码阶段名：整千赫兹沿用 `RMS16K` 形式，其余为 `RMS15500HZ`。
+fn band_stage_name(freq: u32) -> String {
+    if freq.is_multiple_of(1000) {
+        format!("RMS{}K", freq / 1000)
+    } else {
+        format!("RMS{freq}HZ")
+    }
+}
+
+/// 收集单项测量结果：失败时记录 `E_<stage>` 类错误码，
+/// 经降级链路成功时记录 `W_FALLBACK_<stage>`，改用软件解码成功时记录 `W_SOFTWARE_DECODE_<stage>`，便于追溯数据来源。
+fn take_measured<T>(
+    result: Result<Measured<T>>,
+    stage: &str,
+    error_codes: &mut Vec<String>,
+) -> Option<T> {
+    match result {
+        Ok(measured) => {
+            match measured.attempt {
+                FilterAttempt::Primary => {}
+                FilterAttempt::Software => {
+                    error_codes.push(format!("{SOFTWARE_DECODE_CODE_PREFIX}{stage}"))
+                }
+                FilterAttempt::Fallback => {
+                    error_codes.push(format!("{FALLBACK_CODE_PREFIX}{stage}"))
+                }
+            }
+            Some(measured.value)
+        }
+        Err(err) => {
+            error_codes.push(extract_error_code(&err, &format!("E_{stage}")));
+            None
+        }
+    }
+}
+
 pub fn process_file(path: &Path, config: &ProcessingConfig) -> Result<FileMetrics> {
     let start_time = Instant::now();
     let file_size_bytes = path.metadata()?.len();
 
-    let (ebur_res, (stats_res, (rms_16k_res, (rms_18k_res, rms_20k_res)))) = rayon::join(
-        || get_ebur128_stats(path, config),
-        || {
-            rayon::join(
-                || get_stats_ffmpeg(path, config),
-                || {
-                    rayon::join(
-                        || get_highpass_rms_ffmpeg(path, 16000, config),
-                        || {
-                            rayon::join(
-                                || get_highpass_rms_ffmpeg(path, 18000, config),
-                                || get_highpass_rms_ffmpeg(path, 20000, config),
+    // 先取元数据：快速采样模式需要时长来确定采样窗口。
+    // 内容不是音频时直接返回，不再逐项运行注定失败的 ffmpeg 测量。
+    let probe_res = match get_probe_data(path, config) {
+        Err(err) if is_not_audio(&err) => return Err(err),
+        other => other,
+    };
+    let probe_for_segments = probe_res.as_ref().cloned().unwrap_or_default();
+    // CUE 音轨只分析其时间范围，时长与采样窗口都以该范围为准。
+    let range = config.cue_track.as_ref().map(|track| TimeRange {
+        start_seconds: track.start_seconds,
+        length_seconds: track.duration_seconds(probe_for_segments.duration_seconds),
+    });
+    let duration_seconds = match &range {
+        Some(range) => range.length_seconds,
+        None => probe_for_segments.duration_seconds,
+    };
+    let windows = if config.fast_sampling {
+        let offset = range.map_or(0.0, |r| r.start_seconds);
+        sample_windows(duration_seconds).map(|windows| {
+            windows
+                .into_iter()
+                .map(|w| SampleWindow {
+                    start_seconds: w.start_seconds + offset,
+                    ..w
+                })
+                .collect()
+        })
+    } else {
+        None
+    };
+    let input = AnalysisInput {
+        path,
+        audio_stream: config.audio_stream,
+        range,
+        windows,
+    };
+    let input = &input;
+    // 高采样率文件额外测量 24 kHz 以上能量，用于升采样检测。
+    let mut hf_bands = config.hf_bands.clone();
+    if needs_upsample_check(probe_for_segments.sample_rate_hz)
+        && !hf_bands.contains(&UPSAMPLE_CHECK_HZ)
+    {
+        hf_bands.push(UPSAMPLE_CHECK_HZ);
+    }
+
+    let ((ebur_res, (stats_res, band_results)), (segment_res, (ceiling_res, (edge_res, md5_res)))) =
+        rayon::join(
+            || {
+                rayon::join(
+                    || get_ebur128_stats(input, config),
+                    || {
+                        rayon::join(
+                            || get_stats_ffmpeg(input, config),
+                            || {
+                                hf_bands
+                                    .par_iter()
+                                    .map(|&freq| {
+                                        (freq, get_highpass_rms_ffmpeg(input, freq, config))
+                                    })
+                                    .collect::<Vec<_>>()
+                            },
+                        )
+                    },
+                )
+            },
+            || {
+                rayon::join(
+                    || {
+                        config.segment_seconds.map(|seconds| {
+                            get_segment_analysis(
+                                input,
+                                seconds,
+                                &probe_for_segments,
+                                duration_seconds,
+                                config,
                             )
-                        },
-                    )
-                },
-            )
-        },
-    );
+                        })
+                    },
+                    || {
+                        rayon::join(
+                            || get_ceiling_time_percent(input, &probe_for_segments, config),
+                            || {
+                                rayon::join(
+                                    || get_edge_levels(input, duration_seconds, config),
+                                    || get_pcm_md5(input, config),
+                                )
+                            },
+                        )
+                    },
+                )
+            },
+        );
 
-    let probe_res = get_probe_data(path, config);
     let processing_time_ms = start_time.elapsed().as_millis() as u64;
 
     let mut error_codes = Vec::new();
 
-    let (lra, integrated_loudness_lufs, true_peak_dbtp) = match ebur_res {
-        Ok(stats) => (
-            stats.lra,
-            stats.integrated_loudness_lufs,
-            stats.true_peak_dbtp,
-        ),
-        Err(err) => {
-            error_codes.push(extract_error_code(&err, "E_EBUR128"));
-            (None, None, None)
+    let (lra, integrated_loudness_lufs, true_peak_dbtp) =
+        match take_measured(ebur_res, "EBUR128", &mut error_codes) {
+            Some(stats) => (
+                stats.lra,
+                stats.integrated_loudness_lufs,
+                stats.true_peak_dbtp,
+            ),
+            None => (None, None, None),
+        };
+
+    let (peak_amplitude_db, overall_rms_db, effective_bit_depth) =
+        match take_measured(stats_res, "STATS", &mut error_codes) {
+            Some(stats) => (stats.peak_db, stats.rms_db, stats.effective_bit_depth),
+            None => (None, None, None),
+        };
+
+    let mut hf_band_rms_db = BTreeMap::new();
+    for (freq, result) in band_results {
+        if let Some(value) = take_measured(result, &band_stage_name(freq), &mut error_codes) {
+            hf_band_rms_db.insert(freq, value);
         }
-    };
-
-    let (peak_amplitude_db, overall_rms_db) = match stats_res {
-        Ok(stats) => (stats.peak_db, stats.rms_db),
-        Err(err) => {
-            error_codes.push(extract_error_code(&err, "E_STATS"));
-            (None, None)
-        }
-    };
-
-    let rms_db_above_16k = match rms_16k_res {
-        Ok(value) => Some(value),
-        Err(err) => {
-            error_codes.push(extract_error_code(&err, "E_RMS16K"));
-            None
-        }
-    };
+    }
 
-    let rms_db_above_18k = match rms_18k_res {
-        Ok(value) => Some(value),
-        Err(err) => {
-            error_codes.push(extract_error_code(&err, "E_RMS18K"));
-            None
-        }
-    };
-
-    let rms_db_above_20k = match rms_20k_res {
-        Ok(value) => Some(value),
-        Err(err) => {
-            error_codes.push(extract_error_code(&err, "E_RMS20K"));
-            None
-        }
-    };
+    let segment_analysis =
+        segment_res.and_then(|result| take_measured(result, "SEGMENTS", &mut error_codes));
+    let ceiling_time_percent = ceiling_res
+        .and_then(|result| take_measured(result, "CEILING", &mut error_codes))
+        .flatten();
+    let edge_levels = edge_res.and_then(|result| take_measured(result, "EDGES", &mut error_codes));
+    let pcm_md5 = md5_res.and_then(|result| take_measured(result, "PCM_MD5", &mut error_codes));
 
     let probe = match probe_res {
         Ok(probe) => probe,
@@ -479,20 +1264,413 @@ pub fn process_file(path: &Path, config: &ProcessingConfig) -> Result<FileMetric
         lra,
         peak_amplitude_db,
         overall_rms_db,
-        rms_db_above_16k,
-        rms_db_above_18k,
-        rms_db_above_20k,
+        rms_db_above_16k: hf_band_rms_db.get(&16_000).copied(),
+        rms_db_above_18k: hf_band_rms_db.get(&18_000).copied(),
+        rms_db_above_20k: hf_band_rms_db.get(&20_000).copied(),
+        hf_band_rms_db,
         integrated_loudness_lufs,
         true_peak_dbtp,
+        ceiling_time_percent,
         processing_time_ms,
         sample_rate_hz: probe.sample_rate_hz,
+        bit_depth: probe.bit_depth,
+        effective_bit_depth,
         bitrate_kbps: probe.bitrate_kbps,
         channels: probe.channels,
         codec_name: probe.codec_name,
         container_format: probe.container_format,
-        duration_seconds: probe.duration_seconds,
+        duration_seconds: if config.cue_track.is_some() {
+            duration_seconds
+        } else {
+            probe.duration_seconds
+        },
         cache_hit: false,
+        sampled: input.windows.is_some(),
+        extra_of_album: None,
+        cue_track: config.cue_track.clone(),
+        segment_analysis,
+        edge_levels,
+        audio_stream_index: config.audio_stream,
+        audio_stream_count: probe.audio_stream_count,
         content_sha256: None,
+        pcm_md5,
+        ffmpeg_version: config.ffmpeg_version.clone(),
         error_codes,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_filter_chain_fallback_prepends_aformat() {
+        assert_eq!(
+            build_filter_chain("ebur128=peak=true", FilterAttempt::Primary),
+            "ebur128=peak=true"
+        );
+        assert_eq!(
+            build_filter_chain("astats=metadata=1", FilterAttempt::Fallback),
+            "aformat=sample_fmts=fltp:channel_layouts=stereo,astats=metadata=1"
+        );
+    }
+
+    fn test_config() -> ProcessingConfig {
+        ProcessingConfig {
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            ffprobe_path: None,
+            command_timeout: Duration::from_secs(1),
+            process_limiter: ProcessLimiter::new(1),
+            decode_threads: Some(4),
+            hwaccel: Some("videotoolbox".to_string()),
+            hf_bands: DEFAULT_HF_BANDS.to_vec(),
+            fast_sampling: false,
+            segment_seconds: None,
+            audio_stream: 0,
+            cue_track: None,
+            ffmpeg_version: Some("7.1".to_string()),
+            reanalyze_on_ffmpeg_change: false,
+            pcm_md5: false,
+            detect_limiting: false,
+        }
+    }
+
+    #[test]
+    fn test_decode_options_drop_hwaccel_on_fallback() {
+        let config = test_config();
+
+        let mut primary = Command::new("ffmpeg");
+        apply_decode_options(&mut primary, &config, FilterAttempt::Primary);
+        let args: Vec<_> = primary.get_args().collect();
+        assert_eq!(args, ["-threads", "4", "-hwaccel", "videotoolbox"]);
+
+        for attempt in [FilterAttempt::Software, FilterAttempt::Fallback] {
+            let mut retry = Command::new("ffmpeg");
+            apply_decode_options(&mut retry, &config, attempt);
+            let args: Vec<_> = retry.get_args().collect();
+            assert_eq!(args, ["-threads", "4"]);
+        }
+        assert_eq!(
+            build_filter_chain("astats=metadata=1", FilterAttempt::Software),
+            "astats=metadata=1"
+        );
+    }
+
+    #[test]
+    fn test_fallback_only_after_filter_negotiation_failure() {
+        let layout_failure = "[auto_aresample_0 @ 0x5600] Cannot find a matching \
+                              channel layout\nError reinitializing filters!\n\
+                              Failed to inject frame into filter network: Invalid argument\n";
+        assert_eq!(
+            next_attempt(FilterAttempt::Primary, layout_failure, false),
+            Some(FilterAttempt::Fallback)
+        );
+        assert_eq!(
+            next_attempt(FilterAttempt::Fallback, layout_failure, false),
+            None
+        );
+
+        let corrupt_file = "[flac @ 0x5600] invalid sync code\n\
+                            /music/broken.flac: Invalid data found when processing input\n";
+        assert_eq!(
+            next_attempt(FilterAttempt::Primary, corrupt_file, false),
+            None
+        );
+
+        // 硬件加速失败时先以软件解码重试，仍是滤镜协商失败才下混。
+        let hwaccel_failure = "[hevc @ 0x5600] Failed setup for format videotoolbox_vld\n";
+        assert_eq!(
+            next_attempt(FilterAttempt::Primary, hwaccel_failure, true),
+            Some(FilterAttempt::Software)
+        );
+        assert_eq!(
+            next_attempt(FilterAttempt::Software, hwaccel_failure, true),
+            None
+        );
+        assert_eq!(
+            next_attempt(FilterAttempt::Software, layout_failure, true),
+            Some(FilterAttempt::Fallback)
+        );
+    }
+
+    #[test]
+    fn test_sample_windows_cover_start_middle_end() {
+        assert_eq!(sample_windows(None), None);
+        assert_eq!(sample_windows(Some(90.0)), None);
+
+        let windows = sample_windows(Some(300.0)).expect("long file is sampled");
+        let starts: Vec<f64> = windows.iter().map(|w| w.start_seconds).collect();
+        assert_eq!(starts, vec![0.0, 135.0, 270.0]);
+        assert!(windows
+            .iter()
+            .all(|w| w.length_seconds == SAMPLE_WINDOW_SECONDS));
+    }
+
+    #[test]
+    fn test_sampled_command_concatenates_windows() {
+        let config = test_config();
+        let input = AnalysisInput {
+            path: Path::new("/music/a.flac"),
+            audio_stream: 1,
+            range: None,
+            windows: sample_windows(Some(300.0)),
+        };
+        let command = build_filter_command(
+            &input,
+            "-filter:a",
+            "astats=metadata=1",
+            &config,
+            FilterAttempt::Fallback,
+        );
+        let args: Vec<String> = command
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 3);
+        assert!(args.contains(&"135.000".to_string()));
+        assert!(args.contains(&format!(
+            "[0:a:1][1:a:1][2:a:1]concat=n=3:v=0:a=1,{FALLBACK_AFORMAT},astats=metadata=1"
+        )));
+    }
+
+    #[test]
+    fn test_full_command_selects_audio_stream_and_skips_video() {
+        let mut config = test_config();
+        config.audio_stream = 2;
+        let input = AnalysisInput {
+            path: Path::new("/videos/concert.mkv"),
+            audio_stream: config.audio_stream,
+            range: None,
+            windows: None,
+        };
+        let args = |flag: &str, filter: &str| -> Vec<String> {
+            build_filter_command(&input, flag, filter, &config, FilterAttempt::Primary)
+                .get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let ebur = args("-filter_complex", "ebur128=peak=true");
+        assert!(ebur.contains(&"[0:a:2]ebur128=peak=true".to_string()));
+        assert!(ebur.contains(&"-vn".to_string()));
+
+        let stats = args("-filter:a", "astats=metadata=1");
+        let map_pos = stats.iter().position(|a| a == "-map").expect("-map");
+        assert_eq!(stats[map_pos + 1], "0:a:2");
+    }
+
+    #[test]
+    fn test_cue_track_range_seeks_before_input() {
+        let config = test_config();
+        let input = AnalysisInput {
+            path: Path::new("/music/image.flac"),
+            audio_stream: 0,
+            range: Some(TimeRange {
+                start_seconds: 451.5,
+                length_seconds: Some(300.0),
+            }),
+            windows: None,
+        };
+        let args: Vec<String> = build_filter_command(
+            &input,
+            "-filter:a",
+            "astats=metadata=1",
+            &config,
+            FilterAttempt::Primary,
+        )
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+
+        let input_pos = args.iter().position(|a| a == "-i").expect("-i");
+        assert_eq!(
+            args[input_pos - 4..input_pos],
+            ["-ss", "451.500", "-t", "300.000"]
+        );
+    }
+
+    #[test]
+    fn test_parse_probe_json_picks_requested_audio_stream() {
+        let json = r#"{
+            "streams": [
+                {"codec_name": "aac", "sample_rate": "48000", "channels": 2, "bits_per_raw_sample": "0"},
+                {"codec_name": "truehd", "sample_rate": "96000", "channels": 8, "bits_per_raw_sample": "24"}
+            ],
+            "format": {"format_name": "matroska,webm", "duration": "5400.0"}
+        }"#;
+        let probe = parse_probe_json(json, 1).expect("probe");
+        assert_eq!(probe.audio_stream_count, Some(2));
+        assert_eq!(probe.codec_name.as_deref(), Some("truehd"));
+        assert_eq!(probe.channels, Some(8));
+        assert_eq!(probe.bit_depth, Some(24));
+        assert_eq!(parse_probe_json(json, 0).expect("probe").bit_depth, None);
+        assert!(parse_probe_json(json, 2).is_err());
+
+        // 游戏数据等扩展名为 .wav 但没有音频流的文件。
+        let err = parse_probe_json(r#"{"streams": [], "format": {"format_name": "bin"}}"#, 0)
+            .expect_err("no audio");
+        assert!(is_not_audio(&err));
+        assert!(!is_not_audio(
+            &parse_probe_json(json, 2).expect_err("range")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_responds_to_version_runs_the_candidate() {
+        assert!(responds_to_version(Path::new("/bin/true")));
+        assert!(!responds_to_version(Path::new("/bin/false")));
+        assert!(!responds_to_version(Path::new("/nonexistent/ffmpeg")));
+    }
+
+    #[test]
+    fn test_stream_selection_parse() {
+        assert_eq!("all".parse(), Ok(StreamSelection::All));
+        assert_eq!(" 2 ".parse(), Ok(StreamSelection::Index(2)));
+        assert!("-1".parse::<StreamSelection>().is_err());
+    }
+
+    #[test]
+    fn test_band_stage_name() {
+        assert_eq!(band_stage_name(16_000), "RMS16K");
+        assert_eq!(band_stage_name(15_500), "RMS15500HZ");
+    }
+
+    #[test]
+    fn test_parse_ffmpeg_version_and_compatibility() {
+        let stdout = "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 the FFmpeg developers\nbuilt with gcc 13\n";
+        assert_eq!(
+            parse_tool_version(stdout, "ffmpeg").as_deref(),
+            Some("6.1.1-3ubuntu5")
+        );
+        assert_eq!(parse_tool_version(stdout, "ffprobe"), None);
+        assert_eq!(
+            parse_tool_version("ffprobe version n7.0 Copyright", "ffprobe").as_deref(),
+            Some("n7.0")
+        );
+        assert_eq!(parse_tool_version("garbage", "ffmpeg"), None);
+
+        assert!(ffmpeg_versions_compatible(Some("6.1.1-3ubuntu5"), "6.0"));
+        assert!(ffmpeg_versions_compatible(
+            Some("n7.0"),
+            "7.1-full_build-www.gyan.dev"
+        ));
+        assert!(!ffmpeg_versions_compatible(Some("6.1.1"), "7.0"));
+        assert!(!ffmpeg_versions_compatible(
+            Some("N-113000-gabc"),
+            "N-113001-gdef"
+        ));
+        assert!(!ffmpeg_versions_compatible(None, "7.0"));
+    }
+
+    #[test]
+    fn test_parse_hwaccels() {
+        let stdout = "Hardware acceleration methods:\nvdpau\ncuda\nvideotoolbox\n\n";
+        assert_eq!(parse_hwaccels(stdout), ["vdpau", "cuda", "videotoolbox"]);
+        assert!(parse_hwaccels("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        let stdout = "Filters:\n  T.. = Timeline support\n  .S. = Slice threading\n  \
+                      A = Audio input/output\n ... abench            A->A       Benchmark part of a filtergraph.\n \
+                      T.C astats            A->A       Show time domain statistics about audio frames.\n \
+                      ... ebur128           A->N       EBU R128 scanner.\n";
+        assert_eq!(parse_filters(stdout), ["abench", "astats", "ebur128"]);
+        assert!(parse_filters("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_md5_output() {
+        assert_eq!(
+            parse_md5_output("MD5=D41D8CD98F00B204E9800998ECF8427E\n").as_deref(),
+            Some("d41d8cd98f00b204e9800998ecf8427e")
+        );
+        assert_eq!(parse_md5_output("MD5=xyz\n"), None);
+        assert_eq!(parse_md5_output(""), None);
+    }
+
+    #[test]
+    fn test_parsers_accept_comma_decimal_output() {
+        let ebur128 = "\
+[Parsed_ebur128_0 @ 0x1] t: 2,9     TARGET:-23 LUFS    M: -10,2 S:-120,7     I: -9,8 LUFS       LRA:   0,0 LU  FTPK: -0,4 dBFS  TPK: -0,4 dBFS
+[Parsed_ebur128_0 @ 0x1] Summary:
+
+  Integrated loudness:
+    I:          -9,6 LUFS
+    Threshold: -19,8 LUFS
+
+  Loudness range:
+    LRA:         5,4 LU
+    Threshold: -29,9 LUFS
+    LRA low:   -13,1 LUFS
+    LRA high:   -7,7 LUFS
+
+  True peak:
+    Peak:       -0,3 dBFS
+";
+        let stats = parse_ebur128_output(ebur128).expect("ebur128");
+        assert_eq!(stats.integrated_loudness_lufs, Some(-9.6));
+        assert_eq!(stats.lra, Some(5.4));
+        assert_eq!(stats.true_peak_dbtp, Some(-0.3));
+
+        let astats = "\
+[Parsed_astats_0 @ 0x1] Overall
+[Parsed_astats_0 @ 0x1] Peak level dB: -0,512000
+[Parsed_astats_0 @ 0x1] RMS level dB: -11,250000
+[Parsed_astats_0 @ 0x1] Bit depth: 16/16
+";
+        let stats = parse_astats_output(astats).expect("astats");
+        assert_eq!(stats.peak_db, Some(-0.512));
+        assert_eq!(stats.rms_db, Some(-11.25));
+        assert_eq!(stats.effective_bit_depth, Some(16));
+
+        assert_eq!(parse_float_token("-12,3"), Some(-12.3));
+        assert_eq!(parse_float_token("-inf"), Some(f64::NEG_INFINITY));
+        assert_eq!(parse_float_token("1,234.5"), None);
+    }
+
+    #[test]
+    fn test_take_measured_records_fallback_and_errors() {
+        let mut codes = Vec::new();
+        let ok = take_measured(
+            Ok(Measured {
+                value: 1.0,
+                attempt: FilterAttempt::Fallback,
+            }),
+            "STATS",
+            &mut codes,
+        );
+        assert_eq!(ok, Some(1.0));
+        let software = take_measured(
+            Ok(Measured {
+                value: 2.0,
+                attempt: FilterAttempt::Software,
+            }),
+            "EBUR128",
+            &mut codes,
+        );
+        assert_eq!(software, Some(2.0));
+
+        let failed: Option<f64> = take_measured(
+            Err(anyhow!("[E_TIMEOUT] 外部命令执行超时")),
+            "RMS16K",
+            &mut codes,
+        );
+        assert!(failed.is_none());
+
+        let unknown: Option<f64> = take_measured(Err(anyhow!("boom")), "RMS18K", &mut codes);
+        assert!(unknown.is_none());
+
+        assert_eq!(
+            codes,
+            vec![
+                "W_FALLBACK_STATS",
+                "W_SOFTWARE_DECODE_EBUR128",
+                "E_TIMEOUT",
+                "E_RMS18K"
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::album::{album_key, AlbumSummary};
+use super::safe_io;
+use super::scoring::QualityAnalysis;
+use super::sidecar::SidecarLocation;
+
+/// 写在专辑目录中的摘要文件名。
+pub const FOLDER_SUMMARY_FILE_NAME: &str = "AQ_SUMMARY.txt";
+
+/// 摘要中最多列出的需关注曲目数。
+const MAX_LISTED_TRACKS: usize = 10;
+
+/// 生成一个专辑目录的摘要文本：分数范围、等级与状态分布、专辑级问题与需关注的曲目，
+/// 供在文件管理器中浏览曲库时直接查看结论。
+pub fn render_folder_summary(
+    album: &AlbumSummary,
+    tracks: &[&QualityAnalysis],
+    analyzed_at: &str,
+) -> String {
+    let mut lines = vec![
+        "AudioQuality-rs 质量摘要".to_string(),
+        format!("目录: {}", album.album_dir),
+        format!("分析时间: {analyzed_at}"),
+    ];
+    if let Some(first) = tracks.first() {
+        lines.push(format!(
+            "评分档案: {}（评分规则版本 {}，工具版本 {}）",
+            first.profile,
+            first.scorer_version,
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+    lines.push(format!("曲目数: {}", tracks.len()));
+
+    let scores: Vec<i32> = tracks.iter().map(|t| t.quality_score).collect();
+    if let (Some(min), Some(max)) = (scores.iter().min(), scores.iter().max()) {
+        let average = f64::from(scores.iter().sum::<i32>()) / scores.len() as f64;
+        lines.push(format!("质量分: {min}–{max}（平均 {average:.1}）"));
+    }
+
+    let mut grades: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut statuses: BTreeMap<String, usize> = BTreeMap::new();
+    for track in tracks {
+        *grades.entry(track.grade.as_str()).or_default() += 1;
+        *statuses.entry(track.status.to_string()).or_default() += 1;
+    }
+    let join = |counts: Vec<String>| counts.join("，");
+    lines.push(format!(
+        "等级: {}",
+        join(grades.iter().map(|(g, n)| format!("{g} ×{n}")).collect())
+    ));
+    lines.push(format!(
+        "状态: {}",
+        join(statuses.iter().map(|(s, n)| format!("{s} ×{n}")).collect())
+    ));
+
+    if !album.findings.is_empty() {
+        lines.push("专辑问题:".to_string());
+        lines.extend(
+            album
+                .findings
+                .iter()
+                .map(|f| format!("  - {}", f.describe())),
+        );
+    }
+
+    let mut flagged: Vec<&&QualityAnalysis> =
+        tracks.iter().filter(|t| !t.issues.is_empty()).collect();
+    flagged.sort_by_key(|t| t.quality_score);
+    if !flagged.is_empty() {
+        lines.push("需关注的曲目:".to_string());
+        for track in flagged.iter().take(MAX_LISTED_TRACKS) {
+            let name = Path::new(&track.file_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| track.file_path.clone());
+            lines.push(format!(
+                "  - {name}: {}（{} 分）",
+                track.status, track.quality_score
+            ));
+        }
+        if flagged.len() > MAX_LISTED_TRACKS {
+            lines.push(format!(
+                "  ... 另有 {} 首",
+                flagged.len() - MAX_LISTED_TRACKS
+            ));
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// 为每个专辑目录写出摘要文件（设置了输出目录时按相对路径镜像到报告目录）。
+/// 单个目录写入失败（如只读）只警告不中断，返回成功写出的数量。
+pub fn write_folder_summaries(
+    location: &SidecarLocation,
+    albums: &[AlbumSummary],
+    analyses: &[QualityAnalysis],
+    analyzed_at: &str,
+    safe_mode: bool,
+) -> usize {
+    let mut by_album: BTreeMap<String, Vec<&QualityAnalysis>> = BTreeMap::new();
+    for analysis in analyses {
+        by_album
+            .entry(album_key(&analysis.metrics))
+            .or_default()
+            .push(analysis);
+    }
+
+    let mut written = 0;
+    for album in albums {
+        let Some(tracks) = by_album.get(&album.album_dir) else {
+            continue;
+        };
+        let path = location
+            .dir_for(Path::new(&album.album_dir))
+            .join(FOLDER_SUMMARY_FILE_NAME);
+        let text = render_folder_summary(album, tracks, analyzed_at);
+        match write_summary(&path, &text, safe_mode) {
+            Ok(()) => written += 1,
+            Err(e) => tracing::warn!("写入目录摘要失败 {}: {e:#}", path.display()),
+        }
+    }
+    written
+}
+
+fn write_summary(path: &Path, text: &str, safe_mode: bool) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("无法创建目录: {}", parent.display()))?;
+    }
+    safe_io::atomic_write_string(path, text, safe_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::album::summarize_albums;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_folder_summaries_are_written_per_album() {
+        let dir = TempDir::new().expect("tempdir");
+        let album = dir.path().join("Artist").join("Album");
+        std::fs::create_dir_all(&album).expect("album dir");
+        let track = |name: &str, peak: f64| FileMetrics {
+            file_path: album.join(name).to_string_lossy().into_owned(),
+            integrated_loudness_lufs: Some(-9.0),
+            true_peak_dbtp: Some(peak),
+            peak_amplitude_db: Some(peak),
+            ..FileMetrics::default()
+        };
+        let analyses =
+            QualityScorer::new().analyze_files(&[track("01.flac", -1.0), track("02.flac", 0.0)]);
+        let albums = summarize_albums(&analyses);
+
+        let written = write_folder_summaries(
+            &SidecarLocation::new(dir.path(), None),
+            &albums,
+            &analyses,
+            "2026-10-17 09:00",
+            true,
+        );
+        assert_eq!(written, 1);
+        let text = std::fs::read_to_string(album.join(FOLDER_SUMMARY_FILE_NAME)).expect("summary");
+        assert!(text.starts_with("AudioQuality-rs 质量摘要\n"));
+        assert!(text.contains("分析时间: 2026-10-17 09:00\n"));
+        assert!(text.contains("曲目数: 2\n"));
+        assert!(text.contains("评分档案: pop（评分规则版本 "));
+        assert!(text.contains("需关注的曲目:\n  - "));
+
+        // 输出目录模式下镜像到报告目录，不写入音乐库。
+        let mirror = dir.path().join("reports");
+        let location = SidecarLocation::new(dir.path(), Some(mirror.clone()));
+        write_folder_summaries(&location, &albums, &analyses, "now", true);
+        assert!(mirror
+            .join("Artist")
+            .join("Album")
+            .join(FOLDER_SUMMARY_FILE_NAME)
+            .is_file());
+    }
+}
@@ -0,0 +1,89 @@
+use super::scoring::{QualityAnalysis, QualityStatus};
+
+/// 质量门禁（`--fail-under`、`--fail-on-status`）：有文件违反时以退出码 `2` 结束，
+/// 供自动入库流程与 CI 据此拦截交付。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QualityGate {
+    /// 质量分低于该值的文件违反门禁。
+    pub fail_under: Option<i32>,
+    /// 状态或任一问题属于这些状态的文件违反门禁。
+    pub fail_on_status: Vec<QualityStatus>,
+}
+
+/// 违反门禁的文件及原因。
+#[derive(Debug, Clone)]
+pub struct GateViolation<'a> {
+    pub analysis: &'a QualityAnalysis,
+    pub reasons: Vec<String>,
+}
+
+impl QualityGate {
+    pub fn is_enabled(&self) -> bool {
+        self.fail_under.is_some() || !self.fail_on_status.is_empty()
+    }
+
+    /// 按结果顺序列出违反门禁的文件。
+    pub fn check<'a>(&self, analyses: &'a [QualityAnalysis]) -> Vec<GateViolation<'a>> {
+        analyses
+            .iter()
+            .filter_map(|analysis| {
+                let mut reasons = Vec::new();
+                if let Some(min) = self.fail_under.filter(|&min| analysis.quality_score < min) {
+                    reasons.push(format!("质量分 {} 低于 {min}", analysis.quality_score));
+                }
+                let mut matched: Vec<&QualityStatus> = Vec::new();
+                for status in std::iter::once(&analysis.status).chain(&analysis.issues) {
+                    if self.fail_on_status.contains(status) && !matched.contains(&status) {
+                        matched.push(status);
+                    }
+                }
+                reasons.extend(matched.into_iter().map(|status| format!("状态: {status}")));
+                (!reasons.is_empty()).then_some(GateViolation { analysis, reasons })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+
+    #[test]
+    fn test_gate_flags_low_scores_and_listed_statuses() {
+        let mut analyses = QualityScorer::new().analyze_files(
+            &(0..3)
+                .map(|i| FileMetrics {
+                    file_path: format!("/m/{i}.flac"),
+                    ..FileMetrics::default()
+                })
+                .collect::<Vec<_>>(),
+        );
+        for (analysis, score) in analyses.iter_mut().zip([95, 55, 90]) {
+            analysis.quality_score = score;
+            analysis.status = QualityStatus::Good;
+            analysis.issues.clear();
+        }
+        analyses[2].status = QualityStatus::Clipped;
+        analyses[2].issues = vec![QualityStatus::Clipped, QualityStatus::LowDynamic];
+
+        let gate = QualityGate {
+            fail_under: Some(60),
+            fail_on_status: vec![
+                "clipped".parse().expect("status"),
+                "可疑 (伪造)".parse().expect("zh"),
+            ],
+        };
+        let violations = gate.check(&analyses);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].analysis.file_path, "/m/1.flac");
+        assert_eq!(violations[0].reasons, ["质量分 55 低于 60"]);
+        assert_eq!(violations[1].reasons, ["状态: 已削波"]);
+
+        assert!(!QualityGate::default().is_enabled());
+        assert!(QualityGate::default().check(&analyses).is_empty());
+        assert!("true-peak-risk".parse::<QualityStatus>().is_ok());
+        assert!("loud".parse::<QualityStatus>().is_err());
+    }
+}
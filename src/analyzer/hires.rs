@@ -0,0 +1,156 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::ffmpeg::{self, UPSAMPLE_CHECK_HZ};
+use super::metrics::FileMetrics;
+use super::scoring::{is_lossless, QualityStatus, UPSAMPLED_HF_THRESHOLD_DB};
+
+/// 超过该位深（bit）才算高解析度位深。
+const STANDARD_BIT_DEPTH: u32 = 16;
+
+/// Hi-Res 判定结论。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum HiResVerdict {
+    /// 声明为高解析度，且实测的位深与超声频段内容都支持这一声明。
+    #[serde(rename = "hi-res 真实")]
+    Genuine,
+    /// 声明为高解析度，但至少一项测量与之矛盾或无法确认。
+    #[serde(rename = "hi-res 存疑")]
+    Doubtful,
+    /// 未声明高解析度（≤ 48 kHz 且 ≤ 16 bit）。
+    #[default]
+    #[serde(rename = "标准")]
+    Standard,
+}
+
+impl HiResVerdict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HiResVerdict::Genuine => "hi-res 真实",
+            HiResVerdict::Doubtful => "hi-res 存疑",
+            HiResVerdict::Standard => "标准",
+        }
+    }
+}
+
+/// 单个文件的 Hi-Res 判定及存疑原因。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HiResAssessment {
+    pub verdict: HiResVerdict,
+    /// 判为存疑的原因；真实与标准时为空。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+impl HiResAssessment {
+    /// `hi-res 真实`、`标准`，或 `hi-res 存疑: 原因; ...`，用于 CSV。
+    pub fn describe(&self) -> String {
+        if self.reasons.is_empty() {
+            self.verdict.as_str().to_string()
+        } else {
+            format!("{}: {}", self.verdict.as_str(), self.reasons.join("; "))
+        }
+    }
+}
+
+/// 综合声明的采样率/位深、实测位深与超声频段内容给出 Hi-Res 判定。
+/// 声明的每一项高解析度参数都需要对应的实测证据：高采样率需要 24 kHz 以上有真实内容，
+/// 高位深需要样本实际用到 16 bit 以上；来自有损来源或本身是有损编码的文件一律存疑。
+pub fn assess(metrics: &FileMetrics, issues: &[QualityStatus]) -> HiResAssessment {
+    let high_rate = ffmpeg::needs_upsample_check(metrics.sample_rate_hz);
+    let high_bits = metrics
+        .bit_depth
+        .is_some_and(|bits| bits > STANDARD_BIT_DEPTH);
+    if !high_rate && !high_bits {
+        return HiResAssessment::default();
+    }
+
+    let mut reasons = Vec::new();
+    if !is_lossless(metrics) {
+        reasons.push("有损编码".to_string());
+    }
+    if issues
+        .iter()
+        .any(|s| matches!(s, QualityStatus::Suspicious | QualityStatus::TranscodeChain))
+    {
+        reasons.push("频谱显示有损来源".to_string());
+    }
+    if high_rate {
+        let khz = UPSAMPLE_CHECK_HZ / 1000;
+        match metrics.hf_band_rms(UPSAMPLE_CHECK_HZ) {
+            Some(rms) if rms < UPSAMPLED_HF_THRESHOLD_DB => {
+                reasons.push(format!("{khz} kHz 以上无超声内容 ({rms:.1} dB)"));
+            }
+            Some(_) => {}
+            None => reasons.push(format!("未测量 {khz} kHz 以上频段")),
+        }
+    }
+    if high_bits {
+        match metrics.effective_bit_depth {
+            Some(bits) if bits <= STANDARD_BIT_DEPTH => {
+                reasons.push(format!("实测位深仅 {bits} bit"));
+            }
+            Some(_) => {}
+            None => reasons.push("未测量实际位深".to_string()),
+        }
+    }
+
+    HiResAssessment {
+        verdict: if reasons.is_empty() {
+            HiResVerdict::Genuine
+        } else {
+            HiResVerdict::Doubtful
+        },
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hires(rate: u32, bits: u32, effective: Option<u32>, ultrasonic_db: f64) -> FileMetrics {
+        let mut metrics = FileMetrics {
+            file_path: "/m/a.flac".to_string(),
+            sample_rate_hz: Some(rate),
+            bit_depth: Some(bits),
+            effective_bit_depth: effective,
+            ..FileMetrics::default()
+        };
+        metrics
+            .hf_band_rms_db
+            .insert(UPSAMPLE_CHECK_HZ, ultrasonic_db);
+        metrics
+    }
+
+    #[test]
+    fn test_hires_verdict_needs_evidence_for_each_claim() {
+        let genuine = assess(&hires(96_000, 24, Some(24), -98.0), &[]);
+        assert_eq!(genuine.verdict, HiResVerdict::Genuine);
+        assert_eq!(genuine.describe(), "hi-res 真实");
+
+        let padded = assess(&hires(96_000, 24, Some(16), -135.0), &[]);
+        assert_eq!(padded.verdict, HiResVerdict::Doubtful);
+        assert_eq!(
+            padded.describe(),
+            "hi-res 存疑: 24 kHz 以上无超声内容 (-135.0 dB); 实测位深仅 16 bit"
+        );
+
+        let cd = FileMetrics {
+            sample_rate_hz: Some(44_100),
+            bit_depth: Some(16),
+            ..FileMetrics::default()
+        };
+        assert_eq!(assess(&cd, &[]).verdict, HiResVerdict::Standard);
+
+        // 24/44.1 只需实测位深支持；来自有损来源时仍为存疑。
+        let mut studio = hires(44_100, 24, Some(24), -140.0);
+        studio.hf_band_rms_db.clear();
+        assert_eq!(assess(&studio, &[]).verdict, HiResVerdict::Genuine);
+        assert_eq!(
+            assess(&studio, &[QualityStatus::Suspicious]).reasons,
+            ["频谱显示有损来源"]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use crate::analyzer::safe_io;
+use crate::analyzer::scoring::QualityAnalysis;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+const HISTORY_VERSION: u32 = 1;
+
+/// 单个文件跨运行的历史记录。文件消失后保留记录并标记 `removedAt`（墓碑），而不是删除。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistory {
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    /// 最近一次扫描发现文件已不存在的时间；文件重新出现时清除。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<String>,
+    /// 最近一次成功分析的质量分（多音轨/CUE 拆分时取最低分）。
+    #[serde(default)]
+    pub quality_score: Option<i32>,
+    #[serde(default)]
+    pub content_sha256: Option<String>,
+}
+
+/// 一个音乐库根目录的文件历史，按文件路径索引。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryHistory {
+    version: u32,
+    pub files: BTreeMap<String, FileHistory>,
+}
+
+/// 本次运行相对上一次的文件变化。
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HistoryChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub restored: Vec<String>,
+}
+
+impl Default for LibraryHistory {
+    fn default() -> Self {
+        Self {
+            version: HISTORY_VERSION,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+impl LibraryHistory {
+    /// 读取已有历史；文件缺失时从零开始，损坏时报错，避免覆盖掉整份历史。
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("读取历史文件失败: {}", path.display()))?;
+        let history: LibraryHistory = serde_json::from_str(&content)
+            .with_context(|| format!("解析历史文件失败: {}", path.display()))?;
+        if history.version != HISTORY_VERSION {
+            return Ok(Self::default());
+        }
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("序列化历史失败")?;
+        safe_io::atomic_write_string(path, &content, safe_mode)
+    }
+
+    /// 记录一次运行。`scanned_paths` 为本次扫描到的文件（含处理失败的文件）；
+    /// 只有完整扫描（`full_scan`）才能判断文件是否消失，重试等局部运行不会标记移除。
+    pub fn record_run(
+        &mut self,
+        scanned_paths: &[String],
+        analyses: &[QualityAnalysis],
+        full_scan: bool,
+    ) -> HistoryChanges {
+        let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut changes = HistoryChanges::default();
+
+        for path in scanned_paths {
+            match self.files.get_mut(path) {
+                Some(entry) => {
+                    entry.last_seen_at = now.clone();
+                    if entry.removed_at.take().is_some() {
+                        changes.restored.push(path.clone());
+                    }
+                }
+                None => {
+                    self.files.insert(
+                        path.clone(),
+                        FileHistory {
+                            first_seen_at: now.clone(),
+                            last_seen_at: now.clone(),
+                            removed_at: None,
+                            quality_score: None,
+                            content_sha256: None,
+                        },
+                    );
+                    changes.added.push(path.clone());
+                }
+            }
+        }
+
+        let mut scored: HashSet<&str> = HashSet::new();
+        for analysis in analyses {
+            let Some(entry) = self.files.get_mut(&analysis.file_path) else {
+                continue;
+            };
+            let first_record = scored.insert(analysis.file_path.as_str());
+            entry.quality_score = match entry.quality_score {
+                Some(score) if !first_record => Some(score.min(analysis.quality_score)),
+                _ => Some(analysis.quality_score),
+            };
+            if analysis.metrics.content_sha256.is_some() {
+                entry.content_sha256 = analysis.metrics.content_sha256.clone();
+            }
+        }
+
+        if full_scan {
+            let present: HashSet<&str> = scanned_paths.iter().map(String::as_str).collect();
+            for (path, entry) in &mut self.files {
+                if entry.removed_at.is_none() && !present.contains(path.as_str()) {
+                    entry.removed_at = Some(now.clone());
+                    changes.removed.push(path.clone());
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// 已标记为移除的文件数量。
+    pub fn removed_count(&self) -> usize {
+        self.files
+            .values()
+            .filter(|f| f.removed_at.is_some())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    fn analyses(paths: &[&str]) -> Vec<QualityAnalysis> {
+        let metrics: Vec<FileMetrics> = paths
+            .iter()
+            .map(|p| FileMetrics {
+                file_path: p.to_string(),
+                ..FileMetrics::default()
+            })
+            .collect();
+        QualityScorer::new().analyze_files(&metrics)
+    }
+
+    fn paths(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_missing_files_become_tombstones_and_can_return() {
+        let mut history = LibraryHistory::default();
+        let first = history.record_run(
+            &paths(&["/m/a.flac", "/m/b.flac"]),
+            &analyses(&["/m/a.flac", "/m/b.flac"]),
+            true,
+        );
+        assert_eq!(first.added.len(), 2);
+
+        // 重试等局部运行不判定移除。
+        let partial = history.record_run(&paths(&["/m/a.flac"]), &[], false);
+        assert!(partial.removed.is_empty());
+
+        let second = history.record_run(&paths(&["/m/a.flac"]), &analyses(&["/m/a.flac"]), true);
+        assert_eq!(second.removed, vec!["/m/b.flac".to_string()]);
+        let tombstone = &history.files["/m/b.flac"];
+        assert!(tombstone.removed_at.is_some());
+        assert!(tombstone.quality_score.is_some());
+        assert_eq!(history.removed_count(), 1);
+
+        let third = history.record_run(&paths(&["/m/a.flac", "/m/b.flac"]), &[], true);
+        assert_eq!(third.restored, vec!["/m/b.flac".to_string()]);
+        assert_eq!(history.removed_count(), 0);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join("library.json");
+        let mut history = LibraryHistory::default();
+        history.record_run(&paths(&["/m/a.flac"]), &analyses(&["/m/a.flac"]), true);
+        history.save(&path, true).expect("save");
+
+        let loaded = LibraryHistory::load(&path).expect("load");
+        assert_eq!(loaded.files, history.files);
+    }
+}
@@ -0,0 +1,315 @@
+use std::str::FromStr;
+
+use super::scoring::QualityStatus;
+
+/// 报告语言：影响状态名称、备注、CSV 表头与控制台摘要。
+/// JSON / JSONL / SQLite 等机器可读输出的字段名与状态取值保持不变，下游解析不受影响。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Lang {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lang::Zh => "zh",
+            Lang::En => "en",
+        }
+    }
+
+    /// 按语言选取两种写法之一。
+    pub fn pick<'a>(self, zh: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Lang::Zh => zh,
+            Lang::En => en,
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "zh" | "zh-cn" | "zh_cn" | "cn" => Ok(Lang::Zh),
+            "en" | "en-us" | "en_us" => Ok(Lang::En),
+            _ => Err(format!("不支持的报告语言: {s}，可选: zh/en")),
+        }
+    }
+}
+
+/// 状态的显示名称；中文与 `QualityStatus` 的 `Display` 一致。
+pub fn status_label(status: &QualityStatus, lang: Lang) -> &'static str {
+    let (zh, en) = match status {
+        QualityStatus::Good => ("质量良好", "Good"),
+        QualityStatus::Incomplete => ("数据不完整", "Incomplete data"),
+        QualityStatus::Suspicious => ("可疑 (伪造)", "Suspicious (fake lossless)"),
+        QualityStatus::Processed => ("疑似处理", "Possibly processed"),
+        QualityStatus::TranscodeChain => ("疑似二次有损", "Possible lossy transcode chain"),
+        QualityStatus::Upsampled => ("疑似升采样", "Possibly upsampled"),
+        QualityStatus::Clipped => ("已削波", "Clipped"),
+        QualityStatus::TruePeakRisk => ("真峰值风险", "True peak risk"),
+        QualityStatus::LimitingHeavy => ("重度限幅", "Heavy limiting"),
+        QualityStatus::LoudnessOffTarget => ("响度偏离目标", "Loudness off target"),
+        QualityStatus::SeverelyCompressed => ("严重压缩", "Severely compressed"),
+        QualityStatus::LowDynamic => ("低动态", "Low dynamics"),
+        QualityStatus::LowBitrate => ("低码率", "Low bitrate"),
+        QualityStatus::LowSampleRate => ("低采样率", "Low sample rate"),
+        QualityStatus::Mono => ("单声道", "Mono"),
+    };
+    lang.pick(zh, en)
+}
+
+/// 评分备注模板。评分器按检测结果组装，再按报告语言渲染。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Note<'a> {
+    Profile(&'a str),
+    Incomplete,
+    SuspiciousSpectrum,
+    ClassifierProbability(f64),
+    ProcessedSpectrum,
+    TranscodeChain {
+        bitrate_kbps: u32,
+        low_khz: f64,
+        high_khz: f64,
+        drop_db: f64,
+    },
+    Upsampled {
+        sample_rate_hz: u32,
+        check_khz: u32,
+        rms_db: f64,
+    },
+    Clipped(Option<f64>),
+    TruePeakNear(f64),
+    LimitingHeavy {
+        percent: f64,
+        crest_db: f64,
+    },
+    LoudnessOffTarget {
+        integrated_lufs: f64,
+        target_lufs: f64,
+    },
+    SeverelyCompressed(f64),
+    LowDynamic(f64),
+    LowBitrate(u32),
+    LowSampleRate(u32),
+    Mono,
+    Good,
+    MultipleStreams {
+        count: u32,
+        current: u32,
+    },
+    MeasurementDisagreement(String),
+}
+
+impl Note<'_> {
+    pub fn render(&self, lang: Lang) -> String {
+        let en = lang == Lang::En;
+        match self {
+            Note::Profile(name) if en => format!("Profile: {name}"),
+            Note::Profile(name) => format!("评分档案: {name}"),
+            Note::Incomplete if en => "Key data missing; low confidence.".to_string(),
+            Note::Incomplete => "关键数据缺失，分析置信度较低。".to_string(),
+            Note::SuspiciousSpectrum if en => {
+                "Abnormal high-frequency energy in a lossless container; likely upconverted from a lossy source.".to_string()
+            }
+            Note::SuspiciousSpectrum => {
+                "无损容器下高频能量异常，疑似有损升频来源。".to_string()
+            }
+            Note::ClassifierProbability(p) if en => {
+                format!("Lossy-source classifier probability {:.0}%.", p * 100.0)
+            }
+            Note::ClassifierProbability(p) => {
+                format!("有损来源分类器概率 {:.0}%。", p * 100.0)
+            }
+            Note::ProcessedSpectrum if en => {
+                "Low high-frequency energy; possible soft cutoff or post-processing.".to_string()
+            }
+            Note::ProcessedSpectrum => "高频能量偏低，可能存在软截止或后期处理。".to_string(),
+            Note::TranscodeChain {
+                bitrate_kbps,
+                low_khz,
+                high_khz,
+                drop_db,
+            } if en => format!(
+                "Bitrate {bitrate_kbps} kbps, but energy drops {drop_db:.0} dB between {low_khz:.0}–{high_khz:.0} kHz; likely re-encoded from a lower-bitrate lossy file."
+            ),
+            Note::TranscodeChain {
+                bitrate_kbps,
+                low_khz,
+                high_khz,
+                drop_db,
+            } => format!(
+                "码率 {bitrate_kbps} kbps，但能量在 {low_khz:.0}–{high_khz:.0} kHz 之间陡降 {drop_db:.0} dB，疑似由更低码率的有损文件重新编码。"
+            ),
+            Note::Upsampled {
+                sample_rate_hz,
+                check_khz,
+                rms_db,
+            } if en => format!(
+                "Declared sample rate {sample_rate_hz} Hz, but almost no content above {check_khz} kHz ({rms_db:.1} dB); likely upsampled from a 44.1/48 kHz source."
+            ),
+            Note::Upsampled {
+                sample_rate_hz,
+                check_khz,
+                rms_db,
+            } => format!(
+                "声明采样率 {sample_rate_hz} Hz，但 {check_khz} kHz 以上几乎无内容 ({rms_db:.1} dB)，疑似由 44.1/48 kHz 来源升采样。"
+            ),
+            Note::Clipped(Some(tp)) if en => {
+                format!("True peak too high (TP: {tp:.2} dBTP); clipping risk.")
+            }
+            Note::Clipped(Some(tp)) => format!("真峰值过高 (TP: {tp:.2} dBTP)，存在削波风险。"),
+            Note::Clipped(None) if en => "Peak too high; clipping risk.".to_string(),
+            Note::Clipped(None) => "峰值过高，存在削波风险。".to_string(),
+            Note::TruePeakNear(tp) if en => {
+                format!("True peak close to the limit (TP: {tp:.2} dBTP).")
+            }
+            Note::TruePeakNear(tp) => format!("真峰值接近阈值 (TP: {tp:.2} dBTP)。"),
+            Note::LimitingHeavy { percent, crest_db } if en => format!(
+                "Heavy limiting: {percent:.1}% of the time near the peak ceiling, crest factor only {crest_db:.1} dB."
+            ),
+            Note::LimitingHeavy { percent, crest_db } => format!(
+                "重度限幅：{percent:.1}% 的时间贴近峰值天花板，峰值因数仅 {crest_db:.1} dB。"
+            ),
+            Note::LoudnessOffTarget {
+                integrated_lufs,
+                target_lufs,
+            } if en => format!(
+                "Integrated loudness off target (I: {integrated_lufs:.1} LUFS, target: {target_lufs:.1} LUFS)."
+            ),
+            Note::LoudnessOffTarget {
+                integrated_lufs,
+                target_lufs,
+            } => format!(
+                "综合响度偏离目标 (I: {integrated_lufs:.1} LUFS, target: {target_lufs:.1} LUFS)。"
+            ),
+            Note::SeverelyCompressed(lra) if en => {
+                format!("Extremely low dynamic range (LRA: {lra:.1} LU).")
+            }
+            Note::SeverelyCompressed(lra) => format!("动态范围极低 (LRA: {lra:.1} LU)。"),
+            Note::LowDynamic(lra) if en => format!("Low dynamic range (LRA: {lra:.1} LU)."),
+            Note::LowDynamic(lra) => format!("动态范围偏低 (LRA: {lra:.1} LU)。"),
+            Note::LowBitrate(bitrate) if en => format!("Low lossy bitrate ({bitrate} kbps)."),
+            Note::LowBitrate(bitrate) => format!("有损码率偏低 ({bitrate} kbps)。"),
+            Note::LowSampleRate(sr) if en => format!("Low sample rate ({sr} Hz)."),
+            Note::LowSampleRate(sr) => format!("采样率偏低 ({sr} Hz)。"),
+            Note::Mono if en => "File is mono.".to_string(),
+            Note::Mono => "当前文件为单声道。".to_string(),
+            Note::Good if en => "Key technical metrics are within target ranges.".to_string(),
+            Note::Good => "关键技术指标在目标范围内。".to_string(),
+            Note::MultipleStreams { count, current } if en => {
+                format!("Contains {count} audio streams; analyzing stream {current}.")
+            }
+            Note::MultipleStreams { count, current } => {
+                format!("包含 {count} 条音轨，当前分析第 {current} 条。")
+            }
+            Note::MeasurementDisagreement(reasons) if en => {
+                format!("Measurement disagreement: {reasons}")
+            }
+            Note::MeasurementDisagreement(reasons) => format!("测量不一致: {reasons}"),
+        }
+    }
+}
+
+/// CSV 表头的英文名称（与 `CsvRecord`、`AlbumCsvRecord` 的中文列名一一对应）。
+const CSV_HEADERS: &[(&str, &str)] = &[
+    ("质量分", "score"),
+    ("等级", "grade"),
+    ("百分位", "percentile"),
+    ("状态", "status"),
+    ("全部问题", "issues"),
+    ("合规判定", "compliance"),
+    ("Hi-Res判定", "hi_res"),
+    ("较基线分数变化", "score_delta_vs_baseline"),
+    ("较基线状态变化", "status_change_vs_baseline"),
+    ("评分档案", "profile"),
+    ("置信度", "confidence"),
+    ("各结论置信度", "status_confidence"),
+    ("文件路径", "file_path"),
+    ("音轨序号", "audio_stream"),
+    ("CUE音轨", "cue_track"),
+    ("备注", "notes"),
+    ("分项得分", "score_breakdown"),
+    ("响度范围(LRA)", "lra_lu"),
+    ("峰值电平(dB)", "peak_db"),
+    ("整体RMS(dB)", "rms_db"),
+    ("16kHz以上RMS(dB)", "rms_above_16k_db"),
+    ("18kHz以上RMS(dB)", "rms_above_18k_db"),
+    ("20kHz以上RMS(dB)", "rms_above_20k_db"),
+    ("高频段RMS(dB)", "hf_band_rms_db"),
+    ("综合响度(LUFS)", "integrated_lufs"),
+    ("真峰值(dBTP)", "true_peak_dbtp"),
+    ("单曲增益(dB)", "track_gain_db"),
+    ("专辑增益(dB)", "album_gain_db"),
+    ("采样率(Hz)", "sample_rate_hz"),
+    ("位深(bit)", "bit_depth"),
+    ("实测位深(bit)", "effective_bit_depth"),
+    ("码率(kbps)", "bitrate_kbps"),
+    ("声道数", "channels"),
+    ("编码器", "codec"),
+    ("容器格式", "container"),
+    ("时长(秒)", "duration_seconds"),
+    ("缓存命中", "cache_hit"),
+    ("采样分析", "sampled"),
+    ("附加音轨所属专辑", "extra_of_album"),
+    ("错误码", "error_codes"),
+    ("文件大小(字节)", "file_size_bytes"),
+    ("处理时间(毫秒)", "processing_time_ms"),
+    ("专辑目录", "album_dir"),
+    ("曲目数", "tracks"),
+    ("参与响度计算曲目数", "measured_tracks"),
+    ("总时长(秒)", "total_duration_seconds"),
+    ("专辑综合响度(LUFS)", "album_integrated_lufs"),
+    ("专辑峰值(dBTP)", "album_peak_dbtp"),
+    ("专辑问题", "album_findings"),
+    ("平均质量分", "average_score"),
+    ("最低分", "worst_score"),
+    ("最低分文件", "worst_file"),
+    ("主要状态", "dominant_status"),
+];
+
+/// 按中文列名或英文列名（不区分大小写）查找中文列名。
+pub fn csv_header_canonical(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    CSV_HEADERS
+        .iter()
+        .find(|(zh, en)| *zh == name || en.eq_ignore_ascii_case(name))
+        .map(|(zh, _)| *zh)
+}
+
+/// CSV 列名；没有对应译名的列保持原名。
+pub fn csv_header(name: &str, lang: Lang) -> &str {
+    match lang {
+        Lang::Zh => name,
+        Lang::En => CSV_HEADERS
+            .iter()
+            .find(|(zh, _)| *zh == name)
+            .map_or(name, |(_, en)| en),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lang_labels_and_notes() {
+        assert_eq!("EN".parse::<Lang>(), Ok(Lang::En));
+        assert!("fr".parse::<Lang>().is_err());
+        assert_eq!(
+            status_label(&QualityStatus::Clipped, Lang::Zh),
+            QualityStatus::Clipped.to_string()
+        );
+        assert_eq!(status_label(&QualityStatus::Clipped, Lang::En), "Clipped");
+        assert_eq!(
+            Note::LowBitrate(128).render(Lang::En),
+            "Low lossy bitrate (128 kbps)."
+        );
+        assert_eq!(csv_header("质量分", Lang::En), "score");
+        assert_eq!(csv_header("质量分", Lang::Zh), "质量分");
+        assert_eq!(csv_header("unknown", Lang::En), "unknown");
+    }
+}
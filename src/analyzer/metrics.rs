@@ -5,7 +5,12 @@
 //      这些结构体被设计为可序列化和反序列化，以便与 JSON 格式兼容。
 // ----------------------------------------------------------------
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::cue::CueTrack;
+use super::segments::{EdgeLevels, SegmentAnalysis};
 
 /// `AudioStats` 结构体是一个辅助性的数据容器。
 /// 它用于临时存储从 FFmpeg 的 `astats` 滤波器一次性返回的两个关键指标：
@@ -23,6 +28,8 @@ pub struct AudioStats {
     /// 音频的均方根 (RMS) 电平，单位是分贝 (dB)。
     /// 这反映了音频的平均功率。
     pub rms_db: Option<f64>,
+    /// 样本实际用到的位数（`astats` 的 `Bit depth`），用于识别补零得到的“假 24 bit”。
+    pub effective_bit_depth: Option<u32>,
 }
 
 /// `FileMetrics` 结构体是核心数据模型，用于存储从单个音频文件中提取的所有最终技术指标。
@@ -35,7 +42,7 @@ pub struct AudioStats {
 /// - `Serialize`, `Deserialize`: `serde` 的核心功能，使其能够与 JSON 等格式进行转换。
 /// - `Default`: 方便创建空的或默认的实例。
 /// - `Clone`: 允许复制实例。
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, JsonSchema)]
 pub struct FileMetrics {
     /// 文件的完整路径。
     /// `#[serde(rename = "filePath")]` 指定在 JSON 中此字段的名称应为 "filePath"。
@@ -78,6 +85,11 @@ pub struct FileMetrics {
     #[serde(rename = "rmsDbAbove20k")]
     pub rms_db_above_20k: Option<f64>,
 
+    /// 用户配置的各高频段 RMS 电平（键为高通截止频率 Hz，值为 dB）。
+    /// 默认频点为 16k/18k/20k，与上面三个固定字段一致。
+    #[serde(rename = "hfBandRmsDb", default)]
+    pub hf_band_rms_db: BTreeMap<u32, f64>,
+
     /// 综合响度，单位 LUFS（ITU-R BS.1770 / EBU R128）。
     #[serde(rename = "integratedLoudnessLufs")]
     pub integrated_loudness_lufs: Option<f64>,
@@ -86,6 +98,10 @@ pub struct FileMetrics {
     #[serde(rename = "truePeakDbtp")]
     pub true_peak_dbtp: Option<f64>,
 
+    /// 贴顶时间占比（%）：非静音的 100 ms 窗口中峰值距全曲最高峰值 0.5 dB 以内的比例，用于识别重度限幅。
+    #[serde(rename = "ceilingTimePercent", default)]
+    pub ceiling_time_percent: Option<f64>,
+
     /// 处理单个文件所花费的时间，单位是毫秒 (ms)。
     /// 用于性能评估。
     #[serde(rename = "processingTimeMs")]
@@ -95,6 +111,15 @@ pub struct FileMetrics {
     #[serde(rename = "sampleRateHz")]
     pub sample_rate_hz: Option<u32>,
 
+    /// 位深（bit），来自 ffprobe 元数据；有损编码没有固定位深，为 `None`。
+    #[serde(rename = "bitDepth", default)]
+    pub bit_depth: Option<u32>,
+
+    /// 实测位深（bit）：样本中实际用到的位数；低位全为零的 24 bit 文件实测为 16。
+    /// 降级链路会把样本转为浮点，此时无法测量，为 `None`。
+    #[serde(rename = "effectiveBitDepth", default)]
+    pub effective_bit_depth: Option<u32>,
+
     /// 码率（kbps），来自 ffprobe 元数据。
     #[serde(rename = "bitrateKbps")]
     pub bitrate_kbps: Option<u32>,
@@ -119,11 +144,76 @@ pub struct FileMetrics {
     #[serde(rename = "cacheHit", default)]
     pub cache_hit: bool,
 
+    /// 是否为快速采样结果（仅分析开头/中间/结尾窗口，而非整个文件）。
+    #[serde(rename = "sampled", default)]
+    pub sampled: bool,
+
+    /// 附加音轨（隐藏文件、前置间隙、`.mka` 等）所属的专辑目录；普通曲目为空。
+    #[serde(rename = "extraOfAlbum", default)]
+    pub extra_of_album: Option<String>,
+
+    /// 整轨镜像按 CUE 拆分时对应的音轨；此时各项指标只覆盖该音轨的时间范围。
+    #[serde(rename = "cueTrack", default, skip_serializing_if = "Option::is_none")]
+    pub cue_track: Option<CueTrack>,
+
+    /// 分段分析结果（使用 `--segment-seconds` 时生成），用于定位问题时间段。
+    #[serde(
+        rename = "segmentAnalysis",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub segment_analysis: Option<SegmentAnalysis>,
+
+    /// 开头/结尾各 250 ms 的电平，用于专辑内相邻曲目的衔接检查。
+    #[serde(
+        rename = "edgeLevels",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub edge_levels: Option<EdgeLevels>,
+
+    /// 分析的音轨序号（仅计音频流，从 0 开始）。
+    #[serde(rename = "audioStreamIndex", default)]
+    pub audio_stream_index: u32,
+
+    /// 文件中的音轨总数（视频容器/多音轨文件可能大于 1）。
+    #[serde(rename = "audioStreamCount", default)]
+    pub audio_stream_count: Option<u32>,
+
     /// 文件内容 SHA-256（用于缓存一致性验证）。
     #[serde(rename = "contentSha256")]
     pub content_sha256: Option<String>,
 
+    /// 解码后 PCM 的 MD5（`--pcm-md5` 时计算），标签或容器不同但音频相同的副本一致。
+    #[serde(rename = "pcmMd5", default, skip_serializing_if = "Option::is_none")]
+    pub pcm_md5: Option<String>,
+
+    /// 生成该结果的 FFmpeg 版本（`ffmpeg -version` 首行中的版本号）；旧结果中没有该字段。
+    #[serde(
+        rename = "ffmpegVersion",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ffmpeg_version: Option<String>,
+
     /// 风险/失败原因码（例如 E_TIMEOUT, E_PARSE_LRA）。
     #[serde(rename = "errorCodes", default)]
     pub error_codes: Vec<String>,
 }
+
+impl FileMetrics {
+    /// 读取指定频点的高频 RMS；兼容旧缓存中只有固定字段的条目。
+    pub fn hf_band_rms(&self, freq: u32) -> Option<f64> {
+        self.hf_band_rms_db.get(&freq).copied().or(match freq {
+            16_000 => self.rms_db_above_16k,
+            18_000 => self.rms_db_above_18k,
+            20_000 => self.rms_db_above_20k,
+            _ => None,
+        })
+    }
+
+    /// 是否已包含全部指定频点的测量结果。
+    pub fn covers_hf_bands(&self, bands: &[u32]) -> bool {
+        bands.iter().all(|&freq| self.hf_band_rms(freq).is_some())
+    }
+}
@@ -27,8 +27,104 @@ pub mod scoring;
 /// `pub` 关键字使其成为公共模块。该模块负责生成CSV报告和排名显示功能。
 pub mod report;
 
+/// 分段分析模块，按固定时长窗口定位削波、高频缺失等问题时间段。
+pub mod segments;
+
+/// 本地化排序模块，按 ICU 排序规则比较文件/专辑名称。
+pub mod collation;
+
+/// 专辑级汇总模块，按目录分组计算专辑响度与 ReplayGain 专辑增益。
+pub mod album;
+
+/// 广播合规模块，按 EBU R128 / ATSC A/85 给出独立于质量分的通过/未通过判定。
+pub mod compliance;
+
 /// 增量缓存模块，负责缓存命中和一致性校验。
 pub mod cache;
 
 /// 安全文件输出模块，负责原子写入和路径安全检查。
 pub mod safe_io;
+
+/// CUE 解析模块，把整轨镜像按音轨边界拆分。
+pub mod cue;
+
+/// 文件扫描模块，负责识别普通曲目与可选的附加音轨。
+pub mod scan;
+
+/// 失败文件清单模块，记录处理失败的文件以便只重试这些文件。
+pub mod failures;
+
+/// 文件历史模块，跨运行记录每个文件，文件消失时保留墓碑记录。
+pub mod history;
+
+/// 本地使用统计模块，记录运行次数、吞吐量与常见错误（不联网）。
+pub mod usage_stats;
+
+/// 运行对比模块，对比两次运行的分数并生成 HTML 对比视图。
+pub mod compare;
+
+/// 研究数据集导出模块，输出不含路径与标签的匿名指标与评分结果。
+pub mod dataset;
+
+/// 用户规则模块，加载 Rhai 脚本作为追加在内置检测之后的评分规则。
+pub mod rules;
+
+/// 有损来源分类器模块，用已知无损/转码样本训练逻辑回归模型，取代固定高频阈值。
+pub mod classifier;
+
+/// 播放列表模块，按状态与分数段导出 M3U8 播放列表，便于在播放器中逐类试听。
+pub mod playlist;
+
+/// 单文件结果模块，为每个音频文件写出 `.aq.json` 指标与评分。
+pub mod sidecar;
+
+/// Hi-Res 判定模块，检验高采样率/高位深声明是否有实测依据。
+pub mod hires;
+
+/// 空间节省分析模块，统计重复文件、伪无损与未压缩 PCM 可释放的磁盘空间。
+pub mod savings;
+
+/// 重复文件模块，按内容哈希与解码后 PCM 的 MD5 找出互为副本的文件，并建议保留分数最高的一份。
+pub mod duplicates;
+
+/// 待处理清单模块，按削波、疑似转码、低码率等可处理的问题归类文件，列出应优先修复或重新获取的文件。
+pub mod actions;
+
+/// 目录摘要模块，按需在每个专辑目录写出易读的 `AQ_SUMMARY.txt`。
+pub mod folder_summary;
+
+/// 报告语言模块，提供状态名称、备注模板与 CSV 表头的中英文版本。
+pub mod i18n;
+
+/// SQLite 报告模块，把结果按内容哈希 upsert 到数据库，便于对大型曲库用 SQL 查询。
+pub mod sqlite_report;
+
+/// 本地结果服务模块，为 `serve` 子命令提供只读 JSON 接口与可选的内置网页界面。
+pub mod server;
+
+/// 交付检查模块，按各交付目录中的 `delivery.toml` 规格逐个判定交付是否达标。
+pub mod delivery;
+
+/// 崩溃诊断模块，记录最近日志与在途任务，并在 panic 时写出诊断包。
+pub mod diagnostics;
+
+/// 模板报告模块，用 Tera 模板渲染 HTML/Markdown/文本报告，内置格式即默认模板。
+pub mod template_report;
+
+/// 频谱图模块，为报告批量渲染 PNG 频谱图，供 HTML 模板报告内嵌查看。
+pub mod spectrogram;
+
+/// 摘要统计模块，计算分数的中位数、标准差、分位数、各状态平均分与吞吐量，供终端摘要与 JSON 使用。
+pub mod summary;
+
+/// 输出格式版本模块，为 JSON 输出加上 `schemaVersion`、兼容读取旧格式，并生成 JSON Schema。
+pub mod schema;
+
+/// 质量门禁模块，按 `--fail-under` 与 `--fail-on-status` 找出违反门禁的文件，供 CI 以退出码拦截。
+pub mod gate;
+
+/// 远程输入模块，把 http(s) 地址经 FFmpeg 流复制下载到临时目录，按本地文件分析并在报告中保留原始地址。
+pub mod remote;
+
+/// 版本对比模块，逐项对比同一录音的两个版本并给出取舍结论（如疑似有损衍生），供 `compare` 子命令使用。
+pub mod versions;
@@ -0,0 +1,456 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use super::safe_io;
+use super::scoring::{QualityAnalysis, QualityStatus};
+
+/// 播放列表写在报告目录下的该子目录中。
+pub const PLAYLIST_DIR_NAME: &str = "playlists";
+
+/// 分数段播放列表：文件名与该段的最低分，从高到低排列。
+const SCORE_BUCKETS: [(&str, i32); 5] = [
+    ("score_90_plus", 90),
+    ("score_80_89", 80),
+    ("score_70_79", 70),
+    ("score_60_69", 60),
+    ("score_below_60", i32::MIN),
+];
+
+fn score_bucket(score: i32) -> &'static str {
+    SCORE_BUCKETS
+        .iter()
+        .find(|(_, min)| score >= *min)
+        .map(|(name, _)| *name)
+        .unwrap_or("score_below_60")
+}
+
+/// 按状态（含全部问题，同一文件可出现在多个列表中）与分数段写出 M3U8 播放列表，返回写出的列表数。
+/// 列表中使用相对播放列表所在目录的路径，报告目录随曲库一起移动后仍可直接打开；
+/// 本次为空的分类删除上次遗留的列表，避免播放过期结果。
+pub fn export_playlists(
+    dir: &Path,
+    analyses: &[QualityAnalysis],
+    safe_mode: bool,
+) -> Result<usize> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("无法创建播放列表目录: {}", dir.display()))?;
+    // 状态播放列表使用英文文件名，避免部分播放器与文件系统处理中文文件名出错。
+    let mut playlists: BTreeMap<&str, Vec<&QualityAnalysis>> = QualityStatus::ALL
+        .iter()
+        .map(QualityStatus::slug)
+        .chain(SCORE_BUCKETS.iter().map(|(name, _)| *name))
+        .map(|name| (name, Vec::new()))
+        .collect();
+    for analysis in analyses {
+        let statuses = if analysis.issues.is_empty() {
+            std::slice::from_ref(&analysis.status)
+        } else {
+            analysis.issues.as_slice()
+        };
+        for status in statuses {
+            if let Some(entries) = playlists.get_mut(status.slug()) {
+                entries.push(analysis);
+            }
+        }
+        if let Some(entries) = playlists.get_mut(score_bucket(analysis.quality_score)) {
+            entries.push(analysis);
+        }
+    }
+
+    let absolute_dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let mut written = 0;
+    for (name, entries) in playlists {
+        let path = dir.join(format!("{name}.m3u8"));
+        if entries.is_empty() {
+            if path.is_file() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("删除过期的播放列表失败: {}", path.display()))?;
+            }
+            continue;
+        }
+        safe_io::atomic_write_string(&path, &render(&absolute_dir, &entries), safe_mode)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// 扩展 M3U 格式；CUE 分轨与多音轨共用同一音频文件，每个文件只列一次。
+fn render(dir: &Path, entries: &[&QualityAnalysis]) -> String {
+    let mut content = String::from("#EXTM3U\n");
+    let mut seen = HashSet::new();
+    for analysis in entries {
+        // 换行会破坏逐行格式，这类路径无法写入播放列表。
+        if analysis.file_path.contains(['\n', '\r']) || !seen.insert(&analysis.file_path) {
+            continue;
+        }
+        let audio = Path::new(&analysis.file_path);
+        let seconds = match (
+            &analysis.metrics.cue_track,
+            analysis.metrics.duration_seconds,
+        ) {
+            (None, Some(duration)) => duration.round() as i64,
+            _ => -1,
+        };
+        let title = audio
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let absolute = std::path::absolute(audio).unwrap_or_else(|_| audio.to_path_buf());
+        content.push_str(&format!(
+            "#EXTINF:{seconds},{title} [{}]\n{}\n",
+            analysis.quality_score,
+            relative_path(dir, &absolute)
+        ));
+    }
+    content
+}
+
+/// 从 `from_dir` 指向 `target` 的相对路径，统一用 `/` 分隔（各平台播放器都能识别）；
+/// 两者没有公共前缀（如 Windows 上位于不同盘符）时返回原路径。
+fn relative_path(from_dir: &Path, target: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return target.to_string_lossy().into_owned();
+    }
+    std::iter::repeat_n("..".to_string(), from.len() - common)
+        .chain(
+            to[common..]
+                .iter()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 可作为分析输入的播放列表扩展名。
+const INPUT_EXTENSIONS: [&str; 3] = ["m3u", "m3u8", "pls"];
+
+/// 路径是否为可作为输入的播放列表（M3U/M3U8/PLS）。
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| INPUT_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+/// 读取播放列表中的曲目，按列表顺序返回；相对路径按播放列表所在目录解析，
+/// http(s) 地址原样返回，其他网络地址提示后跳过。编码见 [`PlaylistEncoding`]。
+pub fn read_playlist(path: &Path) -> Result<Vec<PathBuf>> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("[E_PLAYLIST] 无法读取播放列表: {}", path.display()))?;
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+    let encoding = PlaylistEncoding::detect(bytes)
+        .map_err(|e| anyhow::anyhow!("[E_PLAYLIST] {e}: {}", path.display()))?;
+    let content = encoding.decode(bytes);
+    let is_pls = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pls"));
+    let entries = if is_pls {
+        pls_entries(&content)
+    } else {
+        m3u_entries(&content)
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let resolved = resolve_entry(base, entry, encoding);
+            if resolved.is_none() {
+                tracing::warn!("跳过播放列表中不支持的地址: {entry}");
+            }
+            resolved
+        })
+        .collect())
+}
+
+/// 播放列表的文本编码：有效的 UTF-8 直接使用，`#EXTENC:` 声明的编码优先。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlaylistEncoding {
+    Utf8,
+    /// `#EXTENC: Windows-1252`（或 Latin-1）：西欧语言的旧版 Windows 播放器常用。
+    Windows1252,
+    /// 未声明编码且不是 UTF-8 的旧式 `.m3u`：Unix 上文件名就是字节串，逐字节保留即可找到原文件。
+    /// 解码时每个字节对应 U+0000–U+00FF 中的一个字符，生成路径时再还原为字节。
+    NativeBytes,
+}
+
+impl PlaylistEncoding {
+    fn detect(bytes: &[u8]) -> std::result::Result<Self, String> {
+        let declared = bytes.split(|&b| b == b'\n').find_map(|line| {
+            let line = line.trim_ascii();
+            let name = line
+                .get(..8)
+                .filter(|tag| tag.eq_ignore_ascii_case(b"#EXTENC:"))
+                .and(line.get(8..))?;
+            Some(String::from_utf8_lossy(name.trim_ascii()).to_ascii_lowercase())
+        });
+        match declared.as_deref() {
+            Some("utf-8" | "utf8") if std::str::from_utf8(bytes).is_ok() => Ok(Self::Utf8),
+            Some("utf-8" | "utf8") => {
+                Err("播放列表声明为 UTF-8 但内容不是有效的 UTF-8".to_string())
+            }
+            Some("windows-1252" | "cp1252" | "latin1" | "latin-1" | "iso-8859-1") => {
+                Ok(Self::Windows1252)
+            }
+            Some(other) => Err(format!(
+                "不支持播放列表声明的编码 {other}，请另存为 UTF-8（.m3u8）"
+            )),
+            None if std::str::from_utf8(bytes).is_ok() => Ok(Self::Utf8),
+            None if cfg!(unix) => Ok(Self::NativeBytes),
+            None => Err(
+                "播放列表不是 UTF-8 编码，请另存为 UTF-8（.m3u8）或在开头声明 #EXTENC".to_string(),
+            ),
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Self::Windows1252 => bytes.iter().map(|&b| windows_1252_char(b)).collect(),
+            Self::NativeBytes => bytes.iter().map(|&b| char::from(b)).collect(),
+        }
+    }
+
+    /// 把解码后的条目文本还原为路径字节。
+    fn entry_bytes(self, entry: &str) -> Vec<u8> {
+        match self {
+            // 解码时每个字节对应一个不超过 U+00FF 的字符，这里原样还原。
+            Self::NativeBytes => entry.chars().map(|c| c as u8).collect(),
+            Self::Utf8 | Self::Windows1252 => entry.as_bytes().to_vec(),
+        }
+    }
+
+    fn path_from_bytes(self, bytes: Vec<u8>) -> PathBuf {
+        #[cfg(unix)]
+        if self == Self::NativeBytes {
+            use std::os::unix::ffi::OsStringExt;
+            return PathBuf::from(std::ffi::OsString::from_vec(bytes));
+        }
+        PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// Windows-1252 单字节解码：0x80–0x9F 是印刷符号，其余与 Latin-1 相同。
+fn windows_1252_char(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}',
+        '\u{8F}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}',
+        '\u{178}',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+/// M3U/M3U8：`#` 开头的行是注释或扩展信息（如 `#EXTINF`），其余非空行各为一个曲目。
+fn m3u_entries(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// PLS：`FileN=路径`，按编号排列。
+fn pls_entries(content: &str) -> Vec<&str> {
+    let mut entries: Vec<(u32, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().split_once('=')?;
+            let number = key
+                .get(..4)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("file"))
+                .and(key.get(4..))?
+                .parse()
+                .ok()?;
+            let value = value.trim();
+            (!value.is_empty()).then_some((number, value))
+        })
+        .collect();
+    entries.sort_by_key(|(number, _)| *number);
+    entries.into_iter().map(|(_, value)| value).collect()
+}
+
+/// 把一个条目解析为路径：支持 `file://` 地址（含 `file:///C:/…` 形式的 Windows 盘符）
+/// 与 Windows 上生成的反斜杠相对路径，http(s) 地址原样保留，其他网络地址返回 `None`。
+fn resolve_entry(base: &Path, entry: &str, encoding: PlaylistEncoding) -> Option<PathBuf> {
+    let path = match entry.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("file") => {
+            // `file:///music/a.flac` 与 `file://localhost/music/a.flac` 均指本机路径。
+            let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+            let mut bytes = percent_decode(&encoding.entry_bytes(rest));
+            // `file:///C:/music` 的路径部分是 `/C:/music`，去掉盘符前的斜杠。
+            if let [b'/', drive, b':', ..] = bytes[..] {
+                if drive.is_ascii_alphabetic() {
+                    bytes.remove(0);
+                }
+            }
+            encoding.path_from_bytes(bytes)
+        }
+        // http(s) 地址原样保留，作为网络输入下载后分析。
+        Some(_) if super::remote::is_url(entry) => return Some(PathBuf::from(entry)),
+        Some((scheme, _)) if scheme.len() > 1 => return None,
+        _ if cfg!(windows) => encoding.path_from_bytes(encoding.entry_bytes(entry)),
+        _ => {
+            let bytes = encoding.entry_bytes(entry);
+            encoding.path_from_bytes(
+                bytes
+                    .into_iter()
+                    .map(|b| if b == b'\\' { b'/' } else { b })
+                    .collect(),
+            )
+        }
+    };
+    Some(if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    })
+}
+
+/// `file://` 地址中的 `%XX` 转义；无效的转义原样保留。
+fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        let escaped = (input[i] == b'%')
+            .then(|| input.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(input[i]);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_playlists_by_status_and_score_use_relative_paths() {
+        let dir = TempDir::new().expect("tempdir");
+        let album = dir.path().join("library").join("Artist");
+        let track = |name: &str| FileMetrics {
+            file_path: album.join(name).to_string_lossy().into_owned(),
+            duration_seconds: Some(241.6),
+            ..FileMetrics::default()
+        };
+        let scorer = QualityScorer::new();
+        let mut clipped = scorer.analyze_file(&track("01 Loud.flac"));
+        clipped.quality_score = 55;
+        clipped.status = QualityStatus::Clipped;
+        clipped.issues = vec![QualityStatus::Clipped, QualityStatus::LowDynamic];
+        let mut good = scorer.analyze_file(&track("02 Fine.flac"));
+        good.quality_score = 93;
+        good.status = QualityStatus::Good;
+        good.issues = vec![];
+
+        let playlists = dir.path().join("reports").join(PLAYLIST_DIR_NAME);
+        std::fs::create_dir_all(&playlists).expect("playlist dir");
+        std::fs::write(playlists.join("suspicious.m3u8"), "#EXTM3U\n").expect("stale");
+        let written = export_playlists(&playlists, &[clipped, good], true).expect("export");
+        assert_eq!(written, 5);
+
+        let read = |name: &str| std::fs::read_to_string(playlists.join(name)).expect(name);
+        assert_eq!(
+            read("clipped.m3u8"),
+            "#EXTM3U\n#EXTINF:242,01 Loud [55]\n../../library/Artist/01 Loud.flac\n"
+        );
+        assert!(read("low_dynamic.m3u8").contains("01 Loud.flac"));
+        assert!(read("score_below_60.m3u8").contains("01 Loud.flac"));
+        assert!(read("score_90_plus.m3u8").contains("02 Fine.flac"));
+        assert!(read("good.m3u8").contains("02 Fine.flac"));
+        assert!(!playlists.join("suspicious.m3u8").exists());
+    }
+
+    #[test]
+    fn test_read_playlist_resolves_entries() {
+        let dir = TempDir::new().expect("tempdir");
+        let m3u = dir.path().join("set.m3u8");
+        std::fs::write(
+            &m3u,
+            "\u{feff}#EXTM3U\n#EXTINF:200,Intro\nintro.flac\r\n\n/abs/b.mp3\n\
+             sub\\c.flac\nfile:///music/My%20Song.flac\nhttps://cdn.example.com/x.mp3\nrtsp://radio/live\n",
+        )
+        .expect("write m3u");
+        let entries = read_playlist(&m3u).expect("m3u");
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0], dir.path().join("intro.flac"));
+        #[cfg(unix)]
+        assert_eq!(
+            entries[1..4],
+            [
+                PathBuf::from("/abs/b.mp3"),
+                dir.path().join("sub").join("c.flac"),
+                PathBuf::from("/music/My Song.flac"),
+            ]
+        );
+        assert_eq!(entries[4], PathBuf::from("https://cdn.example.com/x.mp3"));
+
+        let pls = dir.path().join("radio.PLS");
+        std::fs::write(
+            &pls,
+            "[playlist]\nFile2=two.mp3\nTitle2=Two\nFile1=one.mp3\nNumberOfEntries=2\n",
+        )
+        .expect("write pls");
+        assert!(is_playlist(&pls) && is_playlist(&m3u));
+        assert!(!is_playlist(Path::new("a.flac")));
+        assert_eq!(
+            read_playlist(&pls).expect("pls"),
+            [dir.path().join("one.mp3"), dir.path().join("two.mp3")]
+        );
+        assert!(read_playlist(&dir.path().join("missing.m3u")).is_err());
+    }
+
+    #[test]
+    fn test_read_playlist_decodes_legacy_encodings() {
+        let dir = TempDir::new().expect("tempdir");
+        let m3u = dir.path().join("legacy.m3u");
+        std::fs::write(
+            &m3u,
+            b"#EXTM3U\n#EXTENC: Windows-1252\nCaf\xE9 \x96 Live.flac\n",
+        )
+        .expect("write m3u");
+        assert_eq!(
+            read_playlist(&m3u).expect("windows-1252"),
+            [dir.path().join("Caf\u{e9} \u{2013} Live.flac")]
+        );
+
+        std::fs::write(&m3u, b"#EXTENC:GBK\n\xD2\xF4.flac\n").expect("write m3u");
+        let err = read_playlist(&m3u).expect_err("unsupported encoding");
+        assert!(err.to_string().contains("E_PLAYLIST"));
+
+        // 未声明编码的旧式列表：Unix 上按原始字节找回文件名。
+        std::fs::write(&m3u, b"\xD2\xF4.flac\nfile:///C:/Music/a%20b.flac\n").expect("write m3u");
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let entries = read_playlist(&m3u).expect("native bytes");
+            assert_eq!(
+                entries[0],
+                dir.path()
+                    .join(std::ffi::OsStr::from_bytes(b"\xD2\xF4.flac"))
+            );
+            assert_eq!(entries[1], dir.path().join("C:/Music/a b.flac"));
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use super::ffmpeg::{self, ProcessingConfig};
+
+/// 单个远程文件的下载超时。大文件在慢速网络上远超分析命令的超时，单独设置。
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 输入是否为 http(s) 网络地址。
+pub fn is_url(input: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| {
+        input
+            .get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// 以路径形式传入的网络地址（命令行参数、路径列表与播放列表中的条目）。
+pub fn is_url_path(path: &Path) -> bool {
+    path.to_str().is_some_and(is_url)
+}
+
+/// 下载到临时目录的远程文件，离开作用域时连同目录一起删除。
+pub struct RemoteDownloads {
+    /// 只为持有临时目录，离开作用域时删除。
+    _dir: TempDir,
+    /// 临时文件路径 → 原始地址。
+    sources: HashMap<String, String>,
+    /// 下载失败的地址与原因。
+    pub failures: Vec<(String, anyhow::Error)>,
+}
+
+impl RemoteDownloads {
+    /// 并行下载全部地址（并发受 `--max-ffmpeg-processes` 限制）；单个地址失败不影响其他地址。
+    pub fn fetch(urls: &[String], config: &ProcessingConfig) -> Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("audioquality_remote_")
+            .tempdir()
+            .context("[E_DOWNLOAD] 无法创建临时下载目录")?;
+        let results: Vec<(String, Result<PathBuf>)> = urls
+            .par_iter()
+            .enumerate()
+            .map(|(index, url)| {
+                let path = dir.path().join(download_file_name(index, url));
+                tracing::info!("下载: {url}");
+                let result = download(url, &path, config).map(|()| path);
+                (url.clone(), result)
+            })
+            .collect();
+
+        let mut sources = HashMap::new();
+        let mut failures = Vec::new();
+        for (url, result) in results {
+            match result {
+                Ok(path) => {
+                    sources.insert(path.to_string_lossy().into_owned(), url);
+                }
+                Err(e) => {
+                    tracing::warn!("下载失败 [{url}]: {e:#}");
+                    failures.push((url, e));
+                }
+            }
+        }
+        Ok(Self {
+            _dir: dir,
+            sources,
+            failures,
+        })
+    }
+
+    /// 下载成功的临时文件，按下载顺序排列。
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.sources.keys().map(PathBuf::from).collect();
+        files.sort();
+        files
+    }
+
+    /// 临时文件对应的原始地址。
+    pub fn source_of(&self, path: &str) -> Option<&str> {
+        self.sources.get(path).map(String::as_str)
+    }
+}
+
+/// 用 curl 原样保存网络地址上的文件：分析的就是发布的文件本身，而不是重新封装的副本。
+fn download(url: &str, output: &Path, config: &ProcessingConfig) -> Result<()> {
+    let curl = which::which("curl")
+        .map_err(|_| anyhow!("[E_DOWNLOAD] 下载网络地址需要 curl，请先安装并加入 PATH"))?;
+    let mut command = Command::new(curl);
+    command
+        .arg("--fail")
+        .arg("--location")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--output")
+        .arg(output)
+        .arg("--")
+        .arg(url);
+    let result = ffmpeg::run_command_with_timeout(command, config, DOWNLOAD_TIMEOUT)?;
+    if !result.status_ok {
+        let preview = result.stderr.chars().take(500).collect::<String>();
+        return Err(anyhow!(
+            "[E_DOWNLOAD] 下载失败 (status: {}): {}",
+            result.status_text,
+            preview.trim()
+        ));
+    }
+    Ok(())
+}
+
+/// 临时文件名：序号前缀保证唯一，保留地址中的文件名便于排查。
+/// 内容原样保存，格式由 FFmpeg 按内容识别，文件名不必带可识别的扩展名。
+fn download_file_name(index: usize, url: &str) -> String {
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .unwrap_or_default();
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(80)
+        .collect();
+    if sanitized.is_empty() {
+        format!("{index:04}_remote")
+    } else {
+        format!("{index:04}_{sanitized}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_detection_and_download_names() {
+        assert!(is_url("https://cdn.example.com/a.flac"));
+        assert!(is_url("HTTP://cdn.example.com/a.flac"));
+        assert!(!is_url("/music/http.flac"));
+        assert!(!is_url("ftp://cdn.example.com/a.flac"));
+        assert!(is_url_path(Path::new("https://cdn.example.com/a.flac")));
+
+        assert_eq!(
+            download_file_name(3, "https://cdn.example.com/in/My Take.FLAC?sig=abc#t=1"),
+            "0003_My_Take.FLAC"
+        );
+        assert_eq!(
+            download_file_name(0, "https://cdn.example.com/stream?id=5"),
+            "0000_stream"
+        );
+        assert_eq!(
+            download_file_name(12, "https://cdn.example.com/"),
+            "0012_remote"
+        );
+    }
+}
@@ -1,19 +1,340 @@
 use anyhow::{Context, Result};
+use comfy_table::{presets, Cell, CellAlignment, Color, ContentArrangement, Row, Table};
 use csv::WriterBuilder;
 use serde::Serialize;
 use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
 
+use super::album::{AlbumFinding, AlbumSummary};
+use super::collation::FileNameCollator;
+use super::cue::CueTrack;
+use super::i18n::{self, Lang};
+use super::metrics::FileMetrics;
 use super::safe_io;
-use super::scoring::QualityAnalysis;
+use super::schema;
+use super::scoring::{QualityAnalysis, QualityStatus};
+use super::summary::{self, SummaryStats, Throughput, Toolchain};
+
+/// 边分析边追加的 JSONL 输出：每个文件处理完立即写入并刷新，运行中途崩溃时已完成的结果仍保留在磁盘上。
+/// 此时的记录按完成先后排列，且没有曲库百分位、专辑增益与合规判定。调用方应写入单独的中间文件，
+/// 运行正常结束后由 `generate_jsonl_report` 原子写出完整、有序的正式报告，上次的报告在此之前不受影响。
+pub struct JsonlStream {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl JsonlStream {
+    /// 创建（截断）输出文件。
+    pub fn create(path: &Path, safe_mode: bool) -> Result<Self> {
+        if safe_mode {
+            safe_io::reject_symlink(path)?;
+        }
+        let file =
+            File::create(path).with_context(|| format!("无法创建JSONL报告: {}", path.display()))?;
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    /// 追加一个文件的全部记录（多音轨/CUE 为多行）并刷新到磁盘。
+    pub fn append(&self, analyses: &[QualityAnalysis]) -> Result<()> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow::anyhow!("JSONL写入锁已损坏"))?;
+        for analysis in analyses {
+            serde_json::to_writer(&mut *writer, analysis).context("序列化JSONL记录失败")?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush().context("刷新JSONL报告失败")
+    }
+}
+
+/// CSV 报告与摘要排名的排序键。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportSort {
+    /// 按质量分排序，默认降序（默认）。
+    #[default]
+    Score,
+    /// 按路径排序，同一艺人/专辑的文件相邻，默认升序。
+    Path,
+    /// 按响度范围 (LRA) 排序，默认降序。
+    Lra,
+    /// 按真峰值排序，默认降序（最容易削波的在前）。
+    TruePeak,
+    /// 按文件大小排序，默认降序。
+    FileSize,
+    /// 按单文件处理耗时排序，默认降序（最慢的在前）。
+    ProcessingTime,
+}
+
+impl ReportSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Score => "score",
+            Self::Path => "path",
+            Self::Lra => "lra",
+            Self::TruePeak => "true-peak",
+            Self::FileSize => "size",
+            Self::ProcessingTime => "time",
+        }
+    }
+
+    fn label(self, lang: Lang) -> &'static str {
+        match self {
+            Self::Score => lang.pick("质量分", "score"),
+            Self::Path => lang.pick("路径", "path"),
+            Self::Lra => "LRA",
+            Self::TruePeak => lang.pick("真峰值", "true peak"),
+            Self::FileSize => lang.pick("文件大小", "file size"),
+            Self::ProcessingTime => lang.pick("处理耗时", "processing time"),
+        }
+    }
+
+    /// 未指定方向时的默认方向：路径升序，其余降序。
+    pub fn default_direction(self) -> SortDirection {
+        match self {
+            Self::Path => SortDirection::Asc,
+            _ => SortDirection::Desc,
+        }
+    }
+
+    /// 数值排序键；路径排序与缺失的指标返回 `None`。
+    fn value(self, analysis: &QualityAnalysis) -> Option<f64> {
+        let metrics = &analysis.metrics;
+        match self {
+            Self::Score => Some(f64::from(analysis.quality_score)),
+            Self::Path => None,
+            Self::Lra => metrics.lra,
+            Self::TruePeak => metrics.true_peak_dbtp,
+            Self::FileSize => Some(metrics.file_size_bytes as f64),
+            Self::ProcessingTime => Some(metrics.processing_time_ms as f64),
+        }
+    }
+}
+
+impl FromStr for ReportSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "score" => Ok(ReportSort::Score),
+            "path" | "name" => Ok(ReportSort::Path),
+            "lra" => Ok(ReportSort::Lra),
+            "true-peak" | "truepeak" | "peak" => Ok(ReportSort::TruePeak),
+            "size" | "file-size" => Ok(ReportSort::FileSize),
+            "time" | "processing-time" => Ok(ReportSort::ProcessingTime),
+            _ => Err(format!(
+                "不支持的排序方式: {s}，可选: score/path/lra/true-peak/size/time"
+            )),
+        }
+    }
+}
+
+/// 排序方向。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl FromStr for SortDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "asc" => Ok(SortDirection::Asc),
+            "desc" => Ok(SortDirection::Desc),
+            _ => Err(format!("不支持的排序方向: {s}，可选: asc/desc")),
+        }
+    }
+}
+
+/// 排序键与方向，CSV 报告与摘要排名共用。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReportOrder {
+    pub key: ReportSort,
+    pub direction: Option<SortDirection>,
+}
+
+impl ReportOrder {
+    /// 实际生效的方向（未指定时取排序键的默认方向）。
+    pub fn direction(self) -> SortDirection {
+        self.direction
+            .unwrap_or_else(|| self.key.default_direction())
+    }
+}
+
+impl From<ReportSort> for ReportOrder {
+    fn from(key: ReportSort) -> Self {
+        Self {
+            key,
+            direction: None,
+        }
+    }
+}
+
+/// 报告结果过滤（`--min-score`、`--max-score`、`--status`）：只把符合条件的结果写入报告并计入摘要。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultFilter {
+    pub min_score: Option<i32>,
+    pub max_score: Option<i32>,
+    /// 状态或任一问题属于这些状态的结果保留；为空时不按状态过滤。
+    pub statuses: Vec<QualityStatus>,
+}
+
+impl ResultFilter {
+    pub fn is_active(&self) -> bool {
+        self.min_score.is_some() || self.max_score.is_some() || !self.statuses.is_empty()
+    }
+
+    pub fn matches(&self, analysis: &QualityAnalysis) -> bool {
+        let score = analysis.quality_score;
+        self.min_score.is_none_or(|min| score >= min)
+            && self.max_score.is_none_or(|max| score <= max)
+            && (self.statuses.is_empty()
+                || std::iter::once(&analysis.status)
+                    .chain(&analysis.issues)
+                    .any(|status| self.statuses.contains(status)))
+    }
+
+    /// 符合条件的结果（保持原有顺序）。
+    pub fn apply(&self, analyses: &[QualityAnalysis]) -> Vec<QualityAnalysis> {
+        analyses
+            .iter()
+            .filter(|analysis| self.matches(analysis))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 摘要中默认显示的排名条数。
+pub const DEFAULT_RANKING_SIZE: usize = 10;
+
+/// 摘要中分布条形图的最大宽度（字符数）。
+const BAR_WIDTH: usize = 30;
+/// 分数直方图的分段宽度；最后一段包含 100 分。
+const SCORE_BUCKET: i32 = 10;
+
+/// 标签映射文件中的标签字段名，在播放器中显示为 `%AQ_SCORE%` 等。
+const TAG_FIELDS: [&str; 3] = ["AQ_SCORE", "AQ_GRADE", "AQ_STATUS"];
 
 pub struct ReportGenerator {
     safe_mode: bool,
+    collator: FileNameCollator,
+    sort: ReportOrder,
+    ranking_size: usize,
+    lang: Lang,
+    /// 主 CSV 报告输出的列（中文列名，按给定顺序）；`None` 输出全部列。
+    columns: Option<Vec<String>>,
+    /// 终端表格是否按分数与状态着色（`--no-color` 关闭；输出不是终端时表格本身也不着色）。
+    color: bool,
+    /// 提取阶段的吞吐量，摘要统计中显示；未设置时不显示。
+    throughput: Option<Throughput>,
+    toolchain: Option<Toolchain>,
 }
 
 impl ReportGenerator {
     pub fn new(safe_mode: bool) -> Self {
-        Self { safe_mode }
+        Self {
+            safe_mode,
+            collator: FileNameCollator::default(),
+            sort: ReportOrder::default(),
+            ranking_size: DEFAULT_RANKING_SIZE,
+            lang: Lang::default(),
+            columns: None,
+            color: true,
+            throughput: None,
+            toolchain: None,
+        }
+    }
+
+    pub fn with_collation(mut self, collator: FileNameCollator) -> Self {
+        self.collator = collator;
+        self
+    }
+
+    pub fn with_sort(mut self, sort: ReportOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// CSV 表头、状态名称与控制台摘要使用的语言。
+    pub fn with_lang(mut self, lang: Lang) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// 主 CSV 报告只输出指定的列（见 `resolve_csv_columns`）。
+    pub fn with_columns(mut self, columns: Option<Vec<String>>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn with_toolchain(mut self, toolchain: Toolchain) -> Self {
+        self.toolchain = Some(toolchain);
+        self
+    }
+
+    pub fn with_throughput(mut self, throughput: Throughput) -> Self {
+        self.throughput = Some(throughput);
+        self
+    }
+
+    /// 摘要排名显示的条数。
+    pub fn with_ranking_size(mut self, ranking_size: usize) -> Self {
+        self.ranking_size = ranking_size;
+        self
+    }
+
+    /// 按文件路径的本地化排序规则比较（用于分组与同分排序）。
+    pub fn compare_paths(&self, left: &str, right: &str) -> std::cmp::Ordering {
+        self.collator.compare(left, right)
+    }
+
+    /// 结果记录的全序：路径相同（多音轨文件、CUE 整轨镜像）时再按音轨序号与 CUE 曲目号区分，
+    /// 排序结果不依赖输入顺序，各报告在多次运行之间可以逐行对比。
+    pub fn compare_records(&self, left: &FileMetrics, right: &FileMetrics) -> std::cmp::Ordering {
+        self.compare_paths(&left.file_path, &right.file_path)
+            .then_with(|| compare_tracks(left, right))
+    }
+
+    fn compare_analyses(
+        &self,
+        left: &QualityAnalysis,
+        right: &QualityAnalysis,
+    ) -> std::cmp::Ordering {
+        self.compare_paths(&left.file_path, &right.file_path)
+            .then_with(|| compare_tracks(&left.metrics, &right.metrics))
+    }
+
+    /// 按排序键与方向排列；缺少该指标的记录无论方向都排在最后，相同值按路径排序。
+    fn sorted_by(&self, analyses: &[QualityAnalysis], order: ReportOrder) -> Vec<QualityAnalysis> {
+        let mut sorted = analyses.to_vec();
+        let descending = order.direction() == SortDirection::Desc;
+        sorted.sort_by(|a, b| {
+            let primary = match (order.key.value(a), order.key.value(b)) {
+                (Some(x), Some(y)) if descending => y.total_cmp(&x),
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) if order.key == ReportSort::Path && descending => {
+                    return self.compare_analyses(b, a);
+                }
+                (None, None) => std::cmp::Ordering::Equal,
+            };
+            primary.then_with(|| self.compare_analyses(a, b))
+        });
+        sorted
     }
 
     pub fn generate_csv_report<P: AsRef<Path>>(
@@ -27,36 +348,76 @@ impl ReportGenerator {
                 .has_headers(true)
                 .from_writer(&mut buffer);
 
-            let mut sorted_analyses = analyses.to_vec();
-            sorted_analyses.sort_by(|a, b| b.quality_score.cmp(&a.quality_score));
-
-            for analysis in &sorted_analyses {
-                let csv_record = CsvRecord::from_analysis(analysis);
+            for analysis in &self.sorted_by(analyses, self.sort) {
+                let csv_record = CsvRecord::from_analysis(analysis, self.lang);
                 writer.serialize(&csv_record).context("写入CSV记录失败")?;
             }
 
             writer.flush().context("刷新CSV缓冲失败")?;
         }
+        if let Some(columns) = &self.columns {
+            buffer = select_csv_columns(&buffer, columns)?;
+        }
+        let buffer = localize_csv_header(buffer, self.lang)?;
 
         safe_io::atomic_write_bytes(output_path.as_ref(), &buffer, self.safe_mode)?;
-        println!("✅ CSV报告已保存到: {}", output_path.as_ref().display());
+        uiprintln!("✅ CSV报告已保存到: {}", output_path.as_ref().display());
         Ok(())
     }
 
-    pub fn generate_jsonl_report<P: AsRef<Path>>(
+    /// 专辑级报告：每个专辑目录一行，按本地化排序规则排列。
+    pub fn generate_album_csv_report<P: AsRef<Path>>(
         &self,
-        analyses: &[QualityAnalysis],
+        albums: &[AlbumSummary],
         output_path: P,
     ) -> Result<()> {
-        let mut output = String::new();
-        for analysis in analyses {
-            let line = serde_json::to_string(analysis).context("序列化JSONL记录失败")?;
-            output.push_str(&line);
-            output.push('\n');
+        let mut sorted: Vec<&AlbumSummary> = albums.iter().collect();
+        sorted.sort_by(|a, b| self.compare_paths(&a.album_dir, &b.album_dir));
+
+        let mut buffer: Vec<u8> = Vec::new();
+        {
+            let mut writer = WriterBuilder::new()
+                .has_headers(true)
+                .from_writer(&mut buffer);
+            for album in sorted {
+                writer
+                    .serialize(AlbumCsvRecord::from_summary(album, self.lang))
+                    .context("写入专辑CSV记录失败")?;
+            }
+            writer.flush().context("刷新CSV缓冲失败")?;
         }
+        let buffer = localize_csv_header(buffer, self.lang)?;
 
-        safe_io::atomic_write_string(output_path.as_ref(), &output, self.safe_mode)?;
-        println!("✅ JSONL报告已保存到: {}", output_path.as_ref().display());
+        safe_io::atomic_write_bytes(output_path.as_ref(), &buffer, self.safe_mode)?;
+        uiprintln!("✅ 专辑报告已保存到: {}", output_path.as_ref().display());
+        Ok(())
+    }
+
+    /// 写出完整的评分结果（分数、状态、备注、置信度及原始指标），格式为带 `schemaVersion` 的 JSON 对象，
+    /// 下游工具无需重新实现评分逻辑。与 `analysis_data.json`（仅原始指标）互为补充。
+    pub fn generate_json_report<P: AsRef<Path>>(
+        &self,
+        analyses: &[QualityAnalysis],
+        output_path: P,
+    ) -> Result<()> {
+        schema::write_scored_report(output_path.as_ref(), analyses, self.safe_mode)?;
+        uiprintln!("✅ JSON报告已保存到: {}", output_path.as_ref().display());
+        Ok(())
+    }
+
+    pub fn generate_jsonl_report<P: AsRef<Path>>(
+        &self,
+        analyses: &[QualityAnalysis],
+        output_path: P,
+    ) -> Result<()> {
+        safe_io::atomic_write_with(output_path.as_ref(), self.safe_mode, |writer| {
+            for analysis in analyses {
+                serde_json::to_writer(&mut *writer, analysis).context("序列化JSONL记录失败")?;
+                writer.write_all(b"\n")?;
+            }
+            Ok(())
+        })?;
+        uiprintln!("✅ JSONL报告已保存到: {}", output_path.as_ref().display());
         Ok(())
     }
 
@@ -99,80 +460,547 @@ impl ReportGenerator {
 
         let content = serde_json::to_string_pretty(&sarif).context("序列化SARIF失败")?;
         safe_io::atomic_write_string(output_path.as_ref(), &content, self.safe_mode)?;
-        println!("✅ SARIF报告已保存到: {}", output_path.as_ref().display());
+        uiprintln!("✅ SARIF报告已保存到: {}", output_path.as_ref().display());
+        Ok(())
+    }
+
+    /// 写出供 foobar2000 / MusicBee 批量写标签使用的映射文件：制表符分隔，首行为标签字段名
+    /// （`AQ_SCORE`、`AQ_GRADE`、`AQ_STATUS`），之后每行一个音频文件。
+    /// 标签写在整个文件上，CUE 分轨与多音轨共用同一文件时取其中最低分的一条；
+    /// 带 UTF-8 BOM，Windows 上的播放器才能正确识别中文状态名与路径。
+    pub fn generate_tag_export<P: AsRef<Path>>(
+        &self,
+        analyses: &[QualityAnalysis],
+        output_path: P,
+    ) -> Result<()> {
+        let mut worst: BTreeMap<&str, &QualityAnalysis> = BTreeMap::new();
+        for analysis in analyses {
+            // 制表符与换行会破坏逐行格式，这类路径无法写入映射文件。
+            if analysis.file_path.contains(['\t', '\n', '\r']) {
+                continue;
+            }
+            worst
+                .entry(&analysis.file_path)
+                .and_modify(|current| {
+                    if analysis.quality_score < current.quality_score {
+                        *current = analysis;
+                    }
+                })
+                .or_insert(analysis);
+        }
+
+        let mut content = format!("\u{feff}path\t{}\n", TAG_FIELDS.join("\t"));
+        for (path, analysis) in worst {
+            content.push_str(&format!(
+                "{path}\t{}\t{}\t{}\n",
+                analysis.quality_score, analysis.grade, analysis.status
+            ));
+        }
+        safe_io::atomic_write_string(output_path.as_ref(), &content, self.safe_mode)?;
+        uiprintln!(
+            "✅ 标签映射文件已保存到: {}",
+            output_path.as_ref().display()
+        );
         Ok(())
     }
 
     pub fn display_summary(&self, analyses: &[QualityAnalysis]) {
+        let lang = self.lang;
         if analyses.is_empty() {
-            println!("没有可显示的分析结果。");
+            uiprintln!(
+                "{}",
+                lang.pick("没有可显示的分析结果。", "No results to display.")
+            );
             return;
         }
 
-        println!("\n--- 📊 质量分析摘要 ---");
+        uiprintln!(
+            "\n--- 📊 {} ---",
+            lang.pick("质量分析摘要", "Quality analysis summary")
+        );
         self.display_status_distribution(analyses);
-        self.display_top_rankings(analyses, 10);
+        self.display_grade_distribution(analyses);
+        self.display_top_rankings(analyses);
+        self.display_bottom_rankings(analyses);
         self.display_statistics(analyses);
     }
 
     fn display_status_distribution(&self, analyses: &[QualityAnalysis]) {
-        use std::collections::HashMap;
+        let lang = self.lang;
+        uiprintln!("\n📈 {}:", lang.pick("质量状态分布", "Status distribution"));
+        let distribution = status_distribution(analyses, lang);
+        let max = distribution.first().map_or(0, |(_, count)| *count);
+        for (status, count) in distribution {
+            let percentage = (count as f64 / analyses.len() as f64) * 100.0;
+            let files = lang.pick("个文件", "files");
+            uiprintln!(
+                " {:<BAR_WIDTH$} {status}: {count} {files} ({percentage:.1}%)",
+                text_bar(count, max)
+            );
+        }
+    }
 
-        let mut status_counts: HashMap<String, usize> = HashMap::new();
-        for analysis in analyses {
-            let status_str = analysis.status.to_string();
-            *status_counts.entry(status_str).or_insert(0) += 1;
+    fn display_grade_distribution(&self, analyses: &[QualityAnalysis]) {
+        let lang = self.lang;
+        uiprintln!("\n🎓 {}:", lang.pick("等级分布", "Grade distribution"));
+        for (grade, count) in grade_distribution(analyses) {
+            let percentage = (count as f64 / analyses.len() as f64) * 100.0;
+            let files = lang.pick("个文件", "files");
+            uiprintln!(" - {grade}: {count} {files} ({percentage:.1}%)");
         }
+    }
 
-        println!("\n📈 质量状态分布:");
-        for (status, count) in &status_counts {
-            let percentage = (*count as f64 / analyses.len() as f64) * 100.0;
-            println!(" - {status}: {count} 个文件 ({percentage:.1}%)");
+    fn display_top_rankings(&self, analyses: &[QualityAnalysis]) {
+        if self.ranking_size == 0 {
+            return;
         }
+        let sorted_analyses = self.sorted_by(analyses, self.sort);
+
+        let lang = self.lang;
+        let display_count = self.ranking_size.min(sorted_analyses.len());
+        let key = self.sort.key.label(lang);
+        match (lang, self.sort == ReportOrder::default()) {
+            (Lang::Zh, true) => uiprintln!("\n🏆 质量排名前 {display_count} 的文件:"),
+            (Lang::En, true) => uiprintln!("\n🏆 Top {display_count} files by quality:"),
+            (_, false) => {
+                let direction = match self.sort.direction() {
+                    SortDirection::Asc => lang.pick("升序", "ascending"),
+                    SortDirection::Desc => lang.pick("降序", "descending"),
+                };
+                match lang {
+                    Lang::Zh => {
+                        uiprintln!("\n🏆 按{key}{direction}排列的前 {display_count} 个文件:")
+                    }
+                    Lang::En => {
+                        uiprintln!("\n🏆 First {display_count} files by {key} ({direction}):")
+                    }
+                }
+            }
+        }
+
+        let key_column = match self.sort.key {
+            ReportSort::Score | ReportSort::Path => None,
+            _ => Some(self.sort.key),
+        };
+        uiprintln!(
+            "{}",
+            self.ranking_table(&sorted_analyses[..display_count], key_column)
+        );
     }
 
-    fn display_top_rankings(&self, analyses: &[QualityAnalysis], top_n: usize) {
-        let mut sorted_analyses = analyses.to_vec();
-        sorted_analyses.sort_by(|a, b| b.quality_score.cmp(&a.quality_score));
+    /// 排名表：序号、分数、等级、状态、（非分数/路径排序时的）排序键的值与文件名。
+    /// 文件名列只占一行，终端宽度不够时截断；`color` 时分数与状态按严重程度着色。
+    fn ranking_table(&self, rows: &[QualityAnalysis], key_column: Option<ReportSort>) -> Table {
+        let lang = self.lang;
+        let mut header = vec![
+            Cell::new("#"),
+            Cell::new(lang.pick("分数", "Score")),
+            Cell::new(lang.pick("等级", "Grade")),
+            Cell::new(lang.pick("状态", "Status")),
+        ];
+        if let Some(key) = key_column {
+            header.push(Cell::new(key.label(lang)));
+        }
+        header.push(Cell::new(lang.pick("文件", "File")));
 
-        let display_count = top_n.min(sorted_analyses.len());
-        println!("\n🏆 质量排名前 {display_count} 的文件:");
+        let mut table = Table::new();
+        table
+            .load_preset(presets::UTF8_FULL_CONDENSED)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(header);
+        for (i, analysis) in rows.iter().enumerate() {
+            let mut score = Cell::new(analysis.quality_score);
+            let mut status = Cell::new(i18n::status_label(&analysis.status, lang));
+            if self.color {
+                score = score.fg(score_color(analysis.quality_score));
+                status = status.fg(status_color(&analysis.status));
+            }
+            let mut cells = vec![Cell::new(i + 1), score, Cell::new(&analysis.grade), status];
+            if let Some(key) = key_column {
+                cells.push(Cell::new(sort_key_value(analysis, key)));
+            }
+            cells.push(Cell::new(display_name(analysis)));
+            let mut row = Row::from(cells);
+            row.max_height(1);
+            table.add_row(row);
+        }
+        for index in [0, 1] {
+            if let Some(column) = table.column_mut(index) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+        if key_column.is_some() {
+            if let Some(column) = table.column_mut(4) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+        table
+    }
+
+    /// 质量分最低的 N 个文件（条数与排名相同），不受排序键影响，便于先处理问题最严重的文件。
+    fn display_bottom_rankings(&self, analyses: &[QualityAnalysis]) {
+        if self.ranking_size == 0 {
+            return;
+        }
+        let lowest_first = ReportOrder {
+            key: ReportSort::Score,
+            direction: Some(SortDirection::Asc),
+        };
+        let sorted_analyses = self.sorted_by(analyses, lowest_first);
+        let display_count = self.ranking_size.min(sorted_analyses.len());
+        let lang = self.lang;
+        match lang {
+            Lang::Zh => uiprintln!("\n⚠️ 质量最低的 {display_count} 个文件:"),
+            Lang::En => uiprintln!("\n⚠️ Bottom {display_count} files by quality:"),
+        }
+        uiprintln!(
+            "{}",
+            self.ranking_table(&sorted_analyses[..display_count], None)
+        );
+    }
+
+    /// 单个文件的评分、分数构成与主要指标（交互模式浏览已有报告时使用）。
+    pub fn display_file_details(&self, analysis: &QualityAnalysis) {
+        let lang = self.lang;
+        let metrics = &analysis.metrics;
+        let opt = |value: Option<f64>, unit: &str| {
+            value.map_or_else(|| "-".to_string(), |v| format!("{v:.2} {unit}"))
+        };
+        let issues: Vec<&str> = analysis
+            .issues
+            .iter()
+            .map(|status| i18n::status_label(status, lang))
+            .collect();
+        let breakdown = &analysis.score_breakdown;
+        let mut rows: Vec<(&str, String)> = vec![
+            (
+                lang.pick("文件", "File"),
+                sanitize_for_terminal(&analysis.file_path),
+            ),
+            (
+                lang.pick("分数 / 等级", "Score / grade"),
+                format!("{} / {}", analysis.quality_score, analysis.grade),
+            ),
+            (
+                lang.pick("状态", "Status"),
+                i18n::status_label(&analysis.status, lang).to_string(),
+            ),
+            (lang.pick("问题", "Issues"), issues.join(", ")),
+            (lang.pick("备注", "Notes"), analysis.notes.clone()),
+            (
+                lang.pick("分数构成", "Score breakdown"),
+                format!(
+                    "{:.1} + {:.1} + {:.1} + {:.1} + {:.1}",
+                    breakdown.compliance,
+                    breakdown.dynamics,
+                    breakdown.spectrum,
+                    breakdown.authenticity,
+                    breakdown.integrity
+                ),
+            ),
+        ];
+        for penalty in &breakdown.penalties {
+            rows.push((
+                lang.pick("扣分", "Penalty"),
+                format!("-{:.1} {}", penalty.points, penalty.reason),
+            ));
+        }
+        rows.extend([
+            (
+                lang.pick("编码 / 容器", "Codec / container"),
+                format!(
+                    "{} / {}",
+                    metrics.codec_name.as_deref().unwrap_or("-"),
+                    metrics.container_format.as_deref().unwrap_or("-")
+                ),
+            ),
+            (
+                lang.pick("采样率 / 位深", "Sample rate / bit depth"),
+                format!(
+                    "{} Hz / {} bit",
+                    metrics
+                        .sample_rate_hz
+                        .map_or_else(|| "-".to_string(), |v| v.to_string()),
+                    metrics
+                        .bit_depth
+                        .map_or_else(|| "-".to_string(), |v| v.to_string())
+                ),
+            ),
+            (
+                lang.pick("码率", "Bitrate"),
+                metrics
+                    .bitrate_kbps
+                    .map_or_else(|| "-".to_string(), |v| format!("{v} kbps")),
+            ),
+            (
+                lang.pick("声道", "Channels"),
+                metrics
+                    .channels
+                    .map_or_else(|| "-".to_string(), |v| v.to_string()),
+            ),
+            (
+                lang.pick("时长", "Duration"),
+                metrics
+                    .duration_seconds
+                    .map_or_else(|| "-".to_string(), summary::format_duration),
+            ),
+            (
+                lang.pick("综合响度", "Integrated loudness"),
+                opt(metrics.integrated_loudness_lufs, "LUFS"),
+            ),
+            (
+                lang.pick("真峰值", "True peak"),
+                opt(metrics.true_peak_dbtp, "dBTP"),
+            ),
+            ("LRA", opt(metrics.lra, "LU")),
+            (
+                lang.pick("峰值 / RMS", "Peak / RMS"),
+                format!(
+                    "{} / {}",
+                    opt(metrics.peak_amplitude_db, "dB"),
+                    opt(metrics.overall_rms_db, "dB")
+                ),
+            ),
+            (
+                lang.pick("高频 RMS 16k/18k/20k", "HF RMS 16k/18k/20k"),
+                format!(
+                    "{} / {} / {}",
+                    opt(metrics.rms_db_above_16k, "dB"),
+                    opt(metrics.rms_db_above_18k, "dB"),
+                    opt(metrics.rms_db_above_20k, "dB")
+                ),
+            ),
+        ]);
+
+        let mut table = Table::new();
+        table
+            .load_preset(presets::UTF8_FULL_CONDENSED)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        for (label, value) in rows {
+            table.add_row(vec![Cell::new(label), Cell::new(value)]);
+        }
+        uiprintln!("{table}");
+    }
 
-        for (i, analysis) in sorted_analyses.iter().take(display_count).enumerate() {
-            let filename = Path::new(&analysis.file_path)
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("Unknown");
-            let filename = sanitize_for_terminal(filename);
+    /// 按专辑目录汇总：平均分最低的目录在前，条数与排名相同（`--top`），
+    /// 每行给出平均分、最低分曲目与主要状态。
+    pub fn display_album_summary(&self, albums: &[AlbumSummary]) {
+        let mut scored: Vec<&AlbumSummary> = albums
+            .iter()
+            .filter(|album| album.average_score.is_some())
+            .collect();
+        if scored.is_empty() || self.ranking_size == 0 {
+            return;
+        }
+        scored.sort_by(|a, b| {
+            a.average_score
+                .unwrap_or_default()
+                .total_cmp(&b.average_score.unwrap_or_default())
+                .then_with(|| self.compare_paths(&a.album_dir, &b.album_dir))
+        });
 
-            println!(
-                " {}. [分数: {}] [状态: {}] {}",
-                i + 1,
-                analysis.quality_score,
-                analysis.status,
-                filename
+        let lang = self.lang;
+        let display_count = self.ranking_size.min(scored.len());
+        match lang {
+            Lang::Zh => uiprintln!(
+                "\n📁 专辑目录概览（平均分最低的 {display_count} 个，共 {} 个目录）:",
+                scored.len()
+            ),
+            Lang::En => uiprintln!(
+                "\n📁 Album folders ({display_count} lowest average scores of {}):",
+                scored.len()
+            ),
+        }
+        for album in scored.iter().take(display_count) {
+            let worst = match (&album.worst_file, album.worst_score) {
+                (Some(path), Some(score)) => format!(
+                    " [{}: {} ({score})]",
+                    lang.pick("最低", "worst"),
+                    album_relative(&album.album_dir, path)
+                ),
+                _ => String::new(),
+            };
+            let status = album
+                .dominant_status
+                .as_ref()
+                .map(|status| i18n::status_label(status, lang))
+                .unwrap_or_default();
+            uiprintln!(
+                " - {} [{}: {:.1}] [{}: {status}] [{} {}]{}",
+                sanitize_for_terminal(&album.album_dir),
+                lang.pick("平均", "avg"),
+                album.average_score.unwrap_or_default(),
+                lang.pick("主要状态", "dominant"),
+                album.track_count,
+                lang.pick("首", "tracks"),
+                sanitize_for_terminal(&worst)
             );
         }
     }
 
+    /// 写出与终端摘要统计口径相同的机器可读摘要（中位数、分位数、各状态平均分、总时长与吞吐量）。
+    pub fn generate_summary_json<P: AsRef<Path>>(
+        &self,
+        analyses: &[QualityAnalysis],
+        output_path: P,
+    ) -> Result<()> {
+        self.summary_stats(analyses)
+            .write_json(output_path.as_ref(), self.safe_mode)
+    }
+
+    /// 摘要统计（含设置的吞吐量）。
+    pub fn summary_stats(&self, analyses: &[QualityAnalysis]) -> SummaryStats {
+        SummaryStats {
+            toolchain: self.toolchain.clone(),
+            ..SummaryStats::compute(analyses, self.throughput)
+        }
+    }
+
     fn display_statistics(&self, analyses: &[QualityAnalysis]) {
-        let scores: Vec<i32> = analyses.iter().map(|a| a.quality_score).collect();
+        let stats = self.summary_stats(analyses);
+        if let Some(score_stats) = &stats.scores {
+            let scores: Vec<i32> = analyses.iter().map(|a| a.quality_score).collect();
+            let lang = self.lang;
+            uiprintln!("\n📊 {}:", lang.pick("分数统计", "Score statistics"));
+            uiprintln!(
+                " - {}: {}",
+                lang.pick("总文件数", "Total files"),
+                stats.file_count
+            );
+            uiprintln!(
+                " - {}: {:.1}",
+                lang.pick("平均分数", "Average score"),
+                score_stats.mean
+            );
+            uiprintln!(
+                " - {}: {:.1}",
+                lang.pick("中位数", "Median score"),
+                score_stats.median
+            );
+            uiprintln!(
+                " - {}: {:.1}",
+                lang.pick("标准差", "Standard deviation"),
+                score_stats.std_dev
+            );
+            uiprintln!(
+                " - {}: {:.1} / {:.1} / {:.1} / {:.1}",
+                lang.pick("分位数 P10/P25/P75/P90", "Percentiles P10/P25/P75/P90"),
+                score_stats.p10,
+                score_stats.p25,
+                score_stats.p75,
+                score_stats.p90
+            );
+            uiprintln!(
+                " - {}: {}",
+                lang.pick("最高分数", "Highest score"),
+                score_stats.max
+            );
+            uiprintln!(
+                " - {}: {}",
+                lang.pick("最低分数", "Lowest score"),
+                score_stats.min
+            );
+            uiprintln!(
+                " - {}: {}",
+                lang.pick("音频总时长", "Total audio duration"),
+                summary::format_duration(stats.total_duration_seconds)
+            );
+            if let Some(throughput) = &stats.throughput {
+                uiprintln!(
+                    " - {}: {:.1} {} | {:.1} MB/s",
+                    lang.pick("处理速度", "Throughput"),
+                    throughput.files_per_minute,
+                    lang.pick("文件/分钟", "files/min"),
+                    throughput.mb_per_second
+                );
+            }
 
-        if !scores.is_empty() {
-            let total_files = analyses.len();
-            let avg_score = scores.iter().sum::<i32>() as f64 / total_files as f64;
-            let max_score = scores.iter().copied().max().unwrap_or(0);
-            let min_score = scores.iter().copied().min().unwrap_or(0);
+            uiprintln!(
+                "\n🧮 {}:",
+                lang.pick("各状态平均分", "Average score by status")
+            );
+            for entry in &stats.status_averages {
+                uiprintln!(
+                    " - {}: {:.1} ({} {})",
+                    i18n::status_label(&entry.status, lang),
+                    entry.average_score,
+                    entry.count,
+                    lang.pick("个文件", "files")
+                );
+            }
 
-            println!("\n📊 分数统计:");
-            println!(" - 总文件数: {total_files}");
-            println!(" - 平均分数: {avg_score:.1}");
-            println!(" - 最高分数: {max_score}");
-            println!(" - 最低分数: {min_score}");
+            uiprintln!("\n📶 {}:", lang.pick("分数分布", "Score histogram"));
+            let histogram = score_histogram(&scores);
+            let max = histogram.iter().copied().max().unwrap_or(0);
+            for (i, count) in histogram.iter().enumerate().rev() {
+                let low = i as i32 * SCORE_BUCKET;
+                let high = if i + 1 == histogram.len() {
+                    100
+                } else {
+                    low + SCORE_BUCKET - 1
+                };
+                uiprintln!(
+                    " {low:>3}-{high:<3} {:<BAR_WIDTH$} {count}",
+                    text_bar(*count, max)
+                );
+            }
         }
     }
 }
 
+/// 控制台显示的文件名（CUE 音轨附带曲目标签），已清洗终端控制字符。
+fn display_name(analysis: &QualityAnalysis) -> String {
+    let filename = Path::new(&analysis.file_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Unknown");
+    match &analysis.metrics.cue_track {
+        Some(track) => sanitize_for_terminal(&format!("{filename} [{}]", track.label())),
+        None => sanitize_for_terminal(filename),
+    }
+}
+
+/// 排名表中排序键列的值（带单位）。
+fn sort_key_value(analysis: &QualityAnalysis, key: ReportSort) -> String {
+    let optional = |value: Option<f64>, unit: &str| match value {
+        Some(value) => format!("{value:.1} {unit}"),
+        None => "N/A".to_string(),
+    };
+    let metrics = &analysis.metrics;
+    match key {
+        ReportSort::Score => analysis.quality_score.to_string(),
+        ReportSort::Path => analysis.file_path.clone(),
+        ReportSort::Lra => optional(metrics.lra, "LU"),
+        ReportSort::TruePeak => optional(metrics.true_peak_dbtp, "dBTP"),
+        ReportSort::FileSize => format!("{:.1} MB", metrics.file_size_bytes as f64 / 1_048_576.0),
+        ReportSort::ProcessingTime => format!("{} ms", metrics.processing_time_ms),
+    }
+}
+
+/// 分数颜色：90 以上绿色，70 以上黄色，其余红色。
+fn score_color(score: i32) -> Color {
+    match score {
+        90.. => Color::Green,
+        70..=89 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// 状态颜色：质量良好为绿色；需要重新获取或替换的问题（与待处理清单的类别一致）为红色；其余为黄色。
+fn status_color(status: &QualityStatus) -> Color {
+    match status {
+        QualityStatus::Good => Color::Green,
+        QualityStatus::Clipped
+        | QualityStatus::Suspicious
+        | QualityStatus::TranscodeChain
+        | QualityStatus::Upsampled
+        | QualityStatus::LowBitrate
+        | QualityStatus::LowSampleRate
+        | QualityStatus::Incomplete => Color::Red,
+        _ => Color::Yellow,
+    }
+}
+
 impl Default for ReportGenerator {
     fn default() -> Self {
         Self::new(true)
@@ -183,16 +1011,41 @@ impl Default for ReportGenerator {
 struct CsvRecord {
     #[serde(rename = "质量分")]
     quality_score: i32,
+    #[serde(rename = "等级")]
+    grade: String,
+    #[serde(rename = "百分位")]
+    percentile: f64,
     #[serde(rename = "状态")]
     status: String,
+    #[serde(rename = "全部问题")]
+    issues: String,
+    /// 启用 `--compliance` 时为 `PASS` 或 `FAIL: 条款...`，否则为空。
+    #[serde(rename = "合规判定")]
+    compliance: String,
+    #[serde(rename = "Hi-Res判定")]
+    hi_res: String,
+    /// 启用 `--baseline` 且基线中有该曲目时为本次分数减基线分数。
+    #[serde(rename = "较基线分数变化")]
+    score_delta: Option<i32>,
+    /// 状态相比基线发生变化时为 `旧状态 → 新状态`，否则为空。
+    #[serde(rename = "较基线状态变化")]
+    status_change: String,
     #[serde(rename = "评分档案")]
     profile: String,
     #[serde(rename = "置信度")]
     confidence: f64,
+    #[serde(rename = "各结论置信度")]
+    status_confidence: String,
     #[serde(rename = "文件路径")]
     file_path: String,
+    #[serde(rename = "音轨序号")]
+    audio_stream_index: u32,
+    #[serde(rename = "CUE音轨")]
+    cue_track: String,
     #[serde(rename = "备注")]
     notes: String,
+    #[serde(rename = "分项得分")]
+    score_breakdown: String,
     #[serde(rename = "响度范围(LRA)")]
     lra: Option<f64>,
     #[serde(rename = "峰值电平(dB)")]
@@ -205,12 +1058,22 @@ struct CsvRecord {
     rms_db_above_18k: Option<f64>,
     #[serde(rename = "20kHz以上RMS(dB)")]
     rms_db_above_20k: Option<f64>,
+    #[serde(rename = "高频段RMS(dB)")]
+    hf_band_rms_db: String,
     #[serde(rename = "综合响度(LUFS)")]
     integrated_loudness_lufs: Option<f64>,
     #[serde(rename = "真峰值(dBTP)")]
     true_peak_dbtp: Option<f64>,
+    #[serde(rename = "单曲增益(dB)")]
+    track_gain_db: Option<f64>,
+    #[serde(rename = "专辑增益(dB)")]
+    album_gain_db: Option<f64>,
     #[serde(rename = "采样率(Hz)")]
     sample_rate_hz: Option<u32>,
+    #[serde(rename = "位深(bit)")]
+    bit_depth: Option<u32>,
+    #[serde(rename = "实测位深(bit)")]
+    effective_bit_depth: Option<u32>,
     #[serde(rename = "码率(kbps)")]
     bitrate_kbps: Option<u32>,
     #[serde(rename = "声道数")]
@@ -223,6 +1086,10 @@ struct CsvRecord {
     duration_seconds: Option<f64>,
     #[serde(rename = "缓存命中")]
     cache_hit: bool,
+    #[serde(rename = "采样分析")]
+    sampled: bool,
+    #[serde(rename = "附加音轨所属专辑")]
+    extra_of_album: Option<String>,
     #[serde(rename = "错误码")]
     error_codes: String,
     #[serde(rename = "文件大小(字节)")]
@@ -232,29 +1099,78 @@ struct CsvRecord {
 }
 
 impl CsvRecord {
-    fn from_analysis(analysis: &QualityAnalysis) -> Self {
+    fn from_analysis(analysis: &QualityAnalysis, lang: Lang) -> Self {
+        let label = |status| i18n::status_label(status, lang);
         Self {
             quality_score: analysis.quality_score,
-            status: analysis.status.to_string(),
+            grade: analysis.grade.clone(),
+            percentile: analysis.percentile,
+            status: label(&analysis.status).to_string(),
+            issues: analysis
+                .issues
+                .iter()
+                .map(label)
+                .collect::<Vec<_>>()
+                .join("; "),
+            compliance: analysis
+                .compliance
+                .as_ref()
+                .map(|result| result.describe())
+                .unwrap_or_default(),
+            hi_res: analysis.hi_res.describe(),
+            score_delta: analysis.baseline.as_ref().map(|b| b.score_delta),
+            status_change: analysis
+                .baseline
+                .as_ref()
+                .filter(|b| b.status_changed)
+                .map(|b| {
+                    format!(
+                        "{} → {}",
+                        label(&b.previous_status),
+                        label(&analysis.status)
+                    )
+                })
+                .unwrap_or_default(),
             profile: analysis.profile.clone(),
             confidence: analysis.confidence,
+            status_confidence: analysis
+                .status_confidence
+                .iter()
+                .map(|c| format!("{} {:.2}", label(&c.status), c.confidence))
+                .collect::<Vec<_>>()
+                .join("; "),
             file_path: analysis.file_path.clone(),
+            audio_stream_index: analysis.metrics.audio_stream_index,
+            cue_track: analysis
+                .metrics
+                .cue_track
+                .as_ref()
+                .map(CueTrack::label)
+                .unwrap_or_default(),
             notes: analysis.notes.clone(),
+            score_breakdown: analysis.score_breakdown.describe(),
             lra: analysis.metrics.lra,
             peak_amplitude_db: analysis.metrics.peak_amplitude_db,
             overall_rms_db: analysis.metrics.overall_rms_db,
             rms_db_above_16k: analysis.metrics.rms_db_above_16k,
             rms_db_above_18k: analysis.metrics.rms_db_above_18k,
             rms_db_above_20k: analysis.metrics.rms_db_above_20k,
+            hf_band_rms_db: format_hf_bands(&analysis.metrics),
             integrated_loudness_lufs: analysis.metrics.integrated_loudness_lufs,
             true_peak_dbtp: analysis.metrics.true_peak_dbtp,
+            track_gain_db: analysis.track_gain_db.map(round_gain),
+            album_gain_db: analysis.album_gain_db.map(round_gain),
             sample_rate_hz: analysis.metrics.sample_rate_hz,
+            bit_depth: analysis.metrics.bit_depth,
+            effective_bit_depth: analysis.metrics.effective_bit_depth,
             bitrate_kbps: analysis.metrics.bitrate_kbps,
             channels: analysis.metrics.channels,
             codec_name: analysis.metrics.codec_name.clone(),
             container_format: analysis.metrics.container_format.clone(),
             duration_seconds: analysis.metrics.duration_seconds,
             cache_hit: analysis.metrics.cache_hit,
+            sampled: analysis.metrics.sampled,
+            extra_of_album: analysis.metrics.extra_of_album.clone(),
             error_codes: analysis.metrics.error_codes.join("|"),
             file_size_bytes: analysis.metrics.file_size_bytes,
             processing_time_ms: analysis.metrics.processing_time_ms,
@@ -262,6 +1178,263 @@ impl CsvRecord {
     }
 }
 
+/// `--columns minimal` 输出的列。
+const MINIMAL_CSV_COLUMNS: [&str; 4] = ["质量分", "等级", "状态", "文件路径"];
+
+/// 主 CSV 报告的全部列名（中文），按输出顺序。
+fn csv_column_names() -> Vec<String> {
+    let analysis = super::scoring::QualityScorer::new().analyze_file(&FileMetrics::default());
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        // 写入内存缓冲不会失败；表头由 `CsvRecord` 的字段决定。
+        let _ = writer.serialize(CsvRecord::from_analysis(&analysis, Lang::Zh));
+        let _ = writer.flush();
+    }
+    let mut reader = csv::Reader::from_reader(buffer.as_slice());
+    reader
+        .headers()
+        .map(|headers| headers.iter().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// 解析列选择：`full` 为全部列（返回 `None`），`minimal` 为分数、等级、状态与路径，
+/// 其余按逗号分隔的列名（中文或 `--lang en` 的英文列名）依次输出。
+pub fn resolve_csv_columns(spec: &str) -> Result<Option<Vec<String>>> {
+    match spec.trim().to_ascii_lowercase().as_str() {
+        "" | "full" | "all" => return Ok(None),
+        "minimal" => return Ok(Some(MINIMAL_CSV_COLUMNS.map(String::from).to_vec())),
+        _ => {}
+    }
+    let available = csv_column_names();
+    let mut columns = Vec::new();
+    for name in spec.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let column = i18n::csv_header_canonical(name)
+            .filter(|column| available.iter().any(|a| a == column))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "[E_CSV_COLUMNS] 未知的CSV列: {name}（可用 full、minimal 或 {} 等列名）",
+                    available
+                        .iter()
+                        .map(|c| i18n::csv_header(c, Lang::En))
+                        .collect::<Vec<_>>()
+                        .join("/")
+                )
+            })?;
+        if !columns.iter().any(|c| c == column) {
+            columns.push(column.to_string());
+        }
+    }
+    if columns.is_empty() {
+        anyhow::bail!("[E_CSV_COLUMNS] 未指定任何CSV列");
+    }
+    Ok(Some(columns))
+}
+
+/// 只保留指定的列，按指定顺序输出。
+fn select_csv_columns(buffer: &[u8], columns: &[String]) -> Result<Vec<u8>> {
+    let mut reader = csv::Reader::from_reader(buffer);
+    let headers = reader.headers().context("解析CSV表头失败")?.clone();
+    let indices: Vec<usize> = columns
+        .iter()
+        .filter_map(|column| headers.iter().position(|h| h == column))
+        .collect();
+    let mut selected = Vec::with_capacity(buffer.len());
+    {
+        let mut writer = csv::Writer::from_writer(&mut selected);
+        writer
+            .write_record(indices.iter().map(|&i| &headers[i]))
+            .context("写入CSV表头失败")?;
+        for record in reader.records() {
+            let record = record.context("解析CSV记录失败")?;
+            writer
+                .write_record(indices.iter().map(|&i| &record[i]))
+                .context("写入CSV记录失败")?;
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    Ok(selected)
+}
+
+/// 按报告语言替换 CSV 首行的列名，数据行保持不变。
+fn localize_csv_header(buffer: Vec<u8>, lang: Lang) -> Result<Vec<u8>> {
+    if lang == Lang::Zh {
+        return Ok(buffer);
+    }
+    let header_end = buffer
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(buffer.len(), |i| i + 1);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(&buffer[..header_end]);
+    let Some(header) = reader
+        .records()
+        .next()
+        .transpose()
+        .context("解析CSV表头失败")?
+    else {
+        return Ok(buffer);
+    };
+    let mut localized = Vec::with_capacity(buffer.len());
+    {
+        let mut writer = csv::Writer::from_writer(&mut localized);
+        writer
+            .write_record(header.iter().map(|name| i18n::csv_header(name, lang)))
+            .context("写入CSV表头失败")?;
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    localized.extend_from_slice(&buffer[header_end..]);
+    Ok(localized)
+}
+
+/// 同一路径下的记录按音轨序号、CUE 曲目号排列。
+fn compare_tracks(left: &FileMetrics, right: &FileMetrics) -> std::cmp::Ordering {
+    let cue_number = |m: &FileMetrics| m.cue_track.as_ref().map(|track| track.number);
+    left.audio_stream_index
+        .cmp(&right.audio_stream_index)
+        .then_with(|| cue_number(left).cmp(&cue_number(right)))
+}
+
+/// 各状态的文件数，按数量从多到少排列，同数量按状态名排列，输出顺序与运行无关。
+pub fn status_distribution(analyses: &[QualityAnalysis], lang: Lang) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for analysis in analyses {
+        let label = i18n::status_label(&analysis.status, lang).to_string();
+        *counts.entry(label).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+/// 各等级的文件数，按等级从高到低排列（等级随分数单调，按组内最高分排序即可）。
+pub fn grade_distribution(analyses: &[QualityAnalysis]) -> Vec<(String, usize)> {
+    let mut groups: Vec<(String, usize, i32)> = Vec::new();
+    for analysis in analyses {
+        match groups
+            .iter_mut()
+            .find(|(grade, _, _)| *grade == analysis.grade)
+        {
+            Some((_, count, max_score)) => {
+                *count += 1;
+                *max_score = (*max_score).max(analysis.quality_score);
+            }
+            None => groups.push((analysis.grade.clone(), 1, analysis.quality_score)),
+        }
+    }
+    groups.sort_by_key(|(_, _, max_score)| std::cmp::Reverse(*max_score));
+    groups
+        .into_iter()
+        .map(|(grade, count, _)| (grade, count))
+        .collect()
+}
+
+/// 各分数段的文件数，从低分段到高分段；超出 0–100 的分数计入两端。
+fn score_histogram(scores: &[i32]) -> Vec<usize> {
+    let buckets = (100 / SCORE_BUCKET) as usize;
+    let mut counts = vec![0; buckets];
+    for score in scores {
+        let bucket = ((*score).clamp(0, 100) / SCORE_BUCKET) as usize;
+        counts[bucket.min(buckets - 1)] += 1;
+    }
+    counts
+}
+
+/// 按与最大值的比例画出的条形；非零数量至少占一格，便于看出少量异常。
+fn text_bar(count: usize, max: usize) -> String {
+    if count == 0 || max == 0 {
+        return String::new();
+    }
+    let len = (count * BAR_WIDTH).div_ceil(max).clamp(1, BAR_WIDTH);
+    "█".repeat(len)
+}
+
+/// 增益保留两位小数，与 ReplayGain 标签的常见写法一致。
+fn round_gain(gain: f64) -> f64 {
+    (gain * 100.0).round() / 100.0
+}
+
+#[derive(Debug, Serialize)]
+struct AlbumCsvRecord {
+    #[serde(rename = "专辑目录")]
+    album_dir: String,
+    #[serde(rename = "曲目数")]
+    track_count: usize,
+    #[serde(rename = "参与响度计算曲目数")]
+    measured_tracks: usize,
+    #[serde(rename = "总时长(秒)")]
+    duration_seconds: f64,
+    #[serde(rename = "专辑综合响度(LUFS)")]
+    integrated_loudness_lufs: Option<f64>,
+    #[serde(rename = "专辑增益(dB)")]
+    album_gain_db: Option<f64>,
+    #[serde(rename = "专辑峰值(dBTP)")]
+    album_peak_dbtp: Option<f64>,
+    #[serde(rename = "专辑问题")]
+    findings: String,
+    #[serde(rename = "平均质量分")]
+    average_score: Option<f64>,
+    #[serde(rename = "最低分")]
+    worst_score: Option<i32>,
+    #[serde(rename = "最低分文件")]
+    worst_file: String,
+    #[serde(rename = "主要状态")]
+    dominant_status: String,
+}
+
+impl AlbumCsvRecord {
+    fn from_summary(album: &AlbumSummary, lang: Lang) -> Self {
+        Self {
+            album_dir: album.album_dir.clone(),
+            track_count: album.track_count,
+            measured_tracks: album.measured_tracks,
+            duration_seconds: album.duration_seconds,
+            integrated_loudness_lufs: album.integrated_loudness_lufs,
+            album_gain_db: album.album_gain_db.map(round_gain),
+            album_peak_dbtp: album.album_peak_dbtp,
+            findings: album
+                .findings
+                .iter()
+                .map(AlbumFinding::describe)
+                .collect::<Vec<_>>()
+                .join("; "),
+            average_score: album
+                .average_score
+                .map(|score| (score * 10.0).round() / 10.0),
+            worst_score: album.worst_score,
+            worst_file: album
+                .worst_file
+                .as_deref()
+                .map(|path| album_relative(&album.album_dir, path))
+                .unwrap_or_default(),
+            dominant_status: album
+                .dominant_status
+                .as_ref()
+                .map(|status| i18n::status_label(status, lang).to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 专辑内文件相对专辑目录的路径（附加音轨等不在目录内的文件保留原路径）。
+fn album_relative(album_dir: &str, path: &str) -> String {
+    Path::new(path)
+        .strip_prefix(album_dir)
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// 将动态高频段映射格式化为单列，例如 `14000Hz:-61.2|19000Hz:-88.0`。
+fn format_hf_bands(metrics: &FileMetrics) -> String {
+    metrics
+        .hf_band_rms_db
+        .iter()
+        .map(|(freq, value)| format!("{freq}Hz:{value:.1}"))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
 fn map_sarif_level(score: i32) -> &'static str {
     if score >= 90 {
         "note"
@@ -285,7 +1458,6 @@ fn sanitize_for_terminal(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::analyzer::metrics::FileMetrics;
     use crate::analyzer::scoring::QualityStatus;
     use tempfile::NamedTempFile;
 
@@ -299,27 +1471,51 @@ mod tests {
             rms_db_above_16k: Some(-60.0),
             rms_db_above_18k: Some(-75.0),
             rms_db_above_20k: Some(-85.0),
+            hf_band_rms_db: Default::default(),
             integrated_loudness_lufs: Some(-14.2),
             true_peak_dbtp: Some(-1.2),
+            ceiling_time_percent: None,
             processing_time_ms: 1000,
             sample_rate_hz: Some(44_100),
+            bit_depth: Some(16),
+            effective_bit_depth: Some(16),
             bitrate_kbps: Some(320),
             channels: Some(2),
             codec_name: Some("flac".to_string()),
             container_format: Some("flac".to_string()),
             duration_seconds: Some(123.0),
             cache_hit: false,
+            sampled: false,
+            extra_of_album: None,
+            cue_track: None,
+            segment_analysis: None,
+            edge_levels: None,
+            audio_stream_index: 0,
+            audio_stream_count: Some(1),
             content_sha256: Some("abc".to_string()),
+            pcm_md5: None,
+            ffmpeg_version: None,
             error_codes: vec![],
         };
 
         QualityAnalysis {
             file_path: "test.flac".to_string(),
             quality_score: 85,
+            grade: "B".to_string(),
+            percentile: 50.0,
             status: QualityStatus::Good,
+            issues: vec![],
             notes: "未发现明显的硬性技术问题。".to_string(),
             profile: "pop".to_string(),
+            scorer_version: 2,
             confidence: 1.0,
+            status_confidence: vec![],
+            track_gain_db: Some(-4.0),
+            album_gain_db: None,
+            compliance: None,
+            baseline: None,
+            hi_res: Default::default(),
+            score_breakdown: Default::default(),
             metrics,
         }
     }
@@ -336,7 +1532,7 @@ mod tests {
     #[test]
     fn test_csv_record_from_analysis() {
         let analysis = create_test_analysis();
-        let csv_record = CsvRecord::from_analysis(&analysis);
+        let csv_record = CsvRecord::from_analysis(&analysis, Lang::Zh);
 
         assert_eq!(csv_record.quality_score, 85);
         assert_eq!(csv_record.status, "质量良好");
@@ -346,6 +1542,42 @@ mod tests {
         assert_eq!(csv_record.sample_rate_hz, Some(44_100));
     }
 
+    #[test]
+    fn test_result_filter() {
+        let mut clipped = create_test_analysis();
+        clipped.quality_score = 40;
+        clipped.status = QualityStatus::Clipped;
+        clipped.issues = vec![QualityStatus::Clipped, QualityStatus::LowDynamic];
+        let analyses = vec![create_test_analysis(), clipped];
+
+        assert!(!ResultFilter::default().is_active());
+        assert_eq!(ResultFilter::default().apply(&analyses).len(), 2);
+        let below = ResultFilter {
+            max_score: Some(60),
+            ..ResultFilter::default()
+        };
+        assert_eq!(below.apply(&analyses)[0].quality_score, 40);
+        let by_issue = ResultFilter {
+            min_score: Some(30),
+            statuses: vec![QualityStatus::LowDynamic],
+            ..ResultFilter::default()
+        };
+        assert_eq!(by_issue.apply(&analyses).len(), 1);
+        let above = ResultFilter {
+            min_score: Some(90),
+            ..ResultFilter::default()
+        };
+        assert!(above.apply(&analyses).is_empty());
+    }
+
+    #[test]
+    fn test_format_hf_bands() {
+        let mut analysis = create_test_analysis();
+        analysis.metrics.hf_band_rms_db = [(14_000, -61.23), (19_000, -88.0)].into();
+        let csv_record = CsvRecord::from_analysis(&analysis, Lang::Zh);
+        assert_eq!(csv_record.hf_band_rms_db, "14000Hz:-61.2|19000Hz:-88.0");
+    }
+
     #[test]
     fn test_generate_csv_report() {
         let generator = ReportGenerator::new(true);
@@ -364,6 +1596,200 @@ mod tests {
         assert!(content.contains("采样率(Hz)"));
     }
 
+    #[test]
+    fn test_generate_csv_report_in_english() {
+        let generator = ReportGenerator::new(true).with_lang(Lang::En);
+        let mut analysis = create_test_analysis();
+        analysis.status = QualityStatus::Clipped;
+        analysis.issues = vec![QualityStatus::Clipped, QualityStatus::LowDynamic];
+
+        let temp_file = NamedTempFile::new().expect("failed to create temp file");
+        generator
+            .generate_csv_report(&[analysis], temp_file.path())
+            .expect("csv report");
+
+        let content = std::fs::read_to_string(temp_file.path()).expect("read csv");
+        let mut reader = csv::Reader::from_reader(content.as_bytes());
+        let headers = reader.headers().expect("headers").clone();
+        assert_eq!(&headers[0], "score");
+        assert!(headers.iter().all(|h| h.is_ascii()), "{headers:?}");
+        let record = reader.records().next().expect("row").expect("record");
+        let column = |name: &str| headers.iter().position(|h| h == name).expect(name);
+        assert_eq!(&record[column("status")], "Clipped");
+        assert_eq!(&record[column("issues")], "Clipped; Low dynamics");
+    }
+
+    #[test]
+    fn test_csv_column_selection() {
+        assert_eq!(resolve_csv_columns("full").expect("full"), None);
+        let columns = resolve_csv_columns("file_path, 质量分, TRUE_PEAK_DBTP, score")
+            .expect("columns")
+            .expect("selected");
+        assert_eq!(columns, ["文件路径", "质量分", "真峰值(dBTP)"]);
+        let err = resolve_csv_columns("score,album_dir").expect_err("album column");
+        assert!(err.to_string().contains("E_CSV_COLUMNS"));
+
+        let generator = ReportGenerator::new(true)
+            .with_lang(Lang::En)
+            .with_columns(resolve_csv_columns("minimal").expect("minimal"));
+        let temp_file = NamedTempFile::new().expect("failed to create temp file");
+        generator
+            .generate_csv_report(&[create_test_analysis()], temp_file.path())
+            .expect("csv report");
+        let content = std::fs::read_to_string(temp_file.path()).expect("read csv");
+        let mut lines = content.lines();
+        assert_eq!(lines.next(), Some("score,grade,status,file_path"));
+        assert!(lines.next().expect("row").starts_with("85,"));
+    }
+
+    #[test]
+    fn test_csv_sort_orders() {
+        let mut a = create_test_analysis();
+        a.file_path = "Zoë/01.flac".to_string();
+        a.quality_score = 90;
+        let mut b = create_test_analysis();
+        b.file_path = "Éclair/01.flac".to_string();
+        b.quality_score = 70;
+        let mut c = create_test_analysis();
+        c.file_path = "Abba/01.flac".to_string();
+        c.quality_score = 70;
+        let analyses = vec![a, b, c];
+
+        let paths = |sorted: Vec<QualityAnalysis>| -> Vec<String> {
+            sorted.into_iter().map(|a| a.file_path).collect()
+        };
+        let generator = ReportGenerator::new(true);
+        assert_eq!(
+            paths(generator.sorted_by(&analyses, ReportSort::Score.into())),
+            vec!["Zoë/01.flac", "Abba/01.flac", "Éclair/01.flac"]
+        );
+        assert_eq!(
+            paths(generator.sorted_by(&analyses, ReportSort::Path.into())),
+            vec!["Abba/01.flac", "Éclair/01.flac", "Zoë/01.flac"]
+        );
+
+        // 数值键可指定方向，缺少该指标的记录始终排在最后。
+        let mut with_lra = analyses.clone();
+        with_lra[0].metrics.lra = Some(12.0);
+        with_lra[1].metrics.lra = None;
+        with_lra[2].metrics.lra = Some(4.0);
+        let order = |key: &str, direction: Option<&str>| ReportOrder {
+            key: key.parse().expect("key"),
+            direction: direction.map(|d| d.parse().expect("direction")),
+        };
+        assert_eq!(
+            paths(generator.sorted_by(&with_lra, order("lra", None))),
+            vec!["Zoë/01.flac", "Abba/01.flac", "Éclair/01.flac"]
+        );
+        assert_eq!(
+            paths(generator.sorted_by(&with_lra, order("lra", Some("asc")))),
+            vec!["Abba/01.flac", "Zoë/01.flac", "Éclair/01.flac"]
+        );
+        assert_eq!(
+            paths(generator.sorted_by(&analyses, order("path", Some("desc")))),
+            vec!["Zoë/01.flac", "Éclair/01.flac", "Abba/01.flac"]
+        );
+        assert!("bogus".parse::<ReportSort>().is_err());
+    }
+
+    #[test]
+    fn test_same_path_records_order_independent_of_input() {
+        let record = |stream: u32, cue: Option<u32>| {
+            let mut analysis = create_test_analysis();
+            analysis.metrics.audio_stream_index = stream;
+            analysis.metrics.cue_track = cue.map(|number| CueTrack {
+                number,
+                title: None,
+                performer: None,
+                start_seconds: 0.0,
+                end_seconds: None,
+            });
+            analysis
+        };
+        let key = |sorted: Vec<QualityAnalysis>| -> Vec<(u32, Option<u32>)> {
+            sorted
+                .iter()
+                .map(|a| {
+                    let cue = a.metrics.cue_track.as_ref().map(|track| track.number);
+                    (a.metrics.audio_stream_index, cue)
+                })
+                .collect()
+        };
+        let generator = ReportGenerator::new(true);
+        let forward = vec![record(0, Some(1)), record(0, Some(2)), record(1, None)];
+        let mut shuffled = vec![forward[2].clone(), forward[1].clone(), forward[0].clone()];
+        for sort in [ReportSort::Score, ReportSort::Path] {
+            assert_eq!(
+                key(generator.sorted_by(&forward, sort.into())),
+                key(generator.sorted_by(&shuffled, sort.into()))
+            );
+        }
+        assert_eq!(
+            key(generator.sorted_by(&shuffled, ReportSort::Path.into())),
+            vec![(0, Some(1)), (0, Some(2)), (1, None)]
+        );
+
+        shuffled[0].status = QualityStatus::Suspicious;
+        shuffled[1].status = QualityStatus::Suspicious;
+        let distribution = status_distribution(&shuffled, Lang::Zh);
+        assert_eq!(distribution[0], (QualityStatus::Suspicious.to_string(), 2));
+        assert_eq!(distribution.len(), 2);
+    }
+
+    #[test]
+    fn test_generate_album_csv_report() {
+        let generator = ReportGenerator::new(true);
+        let albums = vec![AlbumSummary {
+            album_dir: "/music/Album".to_string(),
+            track_count: 2,
+            measured_tracks: 2,
+            duration_seconds: 400.0,
+            integrated_loudness_lufs: Some(-12.596),
+            album_gain_db: Some(-5.404),
+            album_peak_dbtp: Some(-0.5),
+            findings: vec![AlbumFinding::InconsistentAlbumFormat {
+                sample_rates_hz: vec![44_100, 48_000],
+                bit_depths: vec![16],
+            }],
+            average_score: Some(81.25),
+            worst_file: Some("/music/Album/02.flac".to_string()),
+            worst_score: Some(70),
+            dominant_status: Some(QualityStatus::LowDynamic),
+        }];
+        let temp_file = NamedTempFile::new().expect("failed to create temp file");
+        generator
+            .generate_album_csv_report(&albums, temp_file.path())
+            .expect("album csv");
+
+        let content = std::fs::read_to_string(temp_file.path()).expect("read album csv");
+        assert!(content.contains("专辑增益(dB)"));
+        assert!(content.contains(
+            "/music/Album,2,2,400.0,-12.596,-5.4,-0.5,格式不一致: 44100/48000Hz,81.3,70,02.flac,低动态"
+        ));
+    }
+
+    #[test]
+    fn test_generate_json_report_includes_scores() {
+        let generator = ReportGenerator::new(true);
+        let analyses = vec![create_test_analysis()];
+        let temp_file = NamedTempFile::new().expect("failed to create temp file");
+
+        generator
+            .generate_json_report(&analyses, temp_file.path())
+            .expect("json report");
+
+        let content =
+            std::fs::read_to_string(temp_file.path()).expect("failed to read generated json");
+        let parsed: serde_json::Value = serde_json::from_str(&content).expect("parse json");
+        assert_eq!(parsed["schemaVersion"], schema::SCHEMA_VERSION);
+        let record = &parsed["results"].as_array().expect("array")[0];
+        assert_eq!(record["质量分"], 85);
+        assert_eq!(record["状态"], analyses[0].status.to_string());
+        assert_eq!(record["备注"], analyses[0].notes);
+        assert!(record["confidence"].is_number());
+        assert_eq!(record["filePath"], analyses[0].file_path);
+    }
+
     #[test]
     fn test_generate_jsonl_report() {
         let generator = ReportGenerator::new(true);
@@ -378,6 +1804,26 @@ mod tests {
         assert!(content.contains("\"质量分\":85"));
     }
 
+    #[test]
+    fn test_jsonl_stream_flushes_each_append() {
+        let temp_file = NamedTempFile::new().expect("failed to create temp file");
+        let stream = JsonlStream::create(temp_file.path(), true).expect("create stream");
+        let analysis = create_test_analysis();
+        stream
+            .append(std::slice::from_ref(&analysis))
+            .expect("append");
+
+        // 未关闭写入端时内容已落盘，进程中途退出也不会丢失。
+        let content = std::fs::read_to_string(temp_file.path()).expect("read jsonl");
+        assert_eq!(content.lines().count(), 1);
+        stream
+            .append(&[analysis.clone(), analysis])
+            .expect("append");
+        let content = std::fs::read_to_string(temp_file.path()).expect("read jsonl");
+        assert_eq!(content.lines().count(), 3);
+        assert!(content.lines().all(|line| line.contains("\"质量分\":85")));
+    }
+
     #[test]
     fn test_generate_sarif_report() {
         let generator = ReportGenerator::new(true);
@@ -393,6 +1839,75 @@ mod tests {
         assert!(content.contains("AudioQuality-rs"));
     }
 
+    #[test]
+    fn test_tag_export_keeps_lowest_score_per_file() {
+        let generator = ReportGenerator::new(true);
+        let mut track_two = create_test_analysis();
+        track_two.quality_score = 61;
+        track_two.grade = "D".to_string();
+        track_two.status = QualityStatus::Clipped;
+        let mut other = create_test_analysis();
+        other.file_path = "a.flac".to_string();
+        let temp_file = NamedTempFile::new().expect("failed to create temp file");
+
+        generator
+            .generate_tag_export(
+                &[create_test_analysis(), track_two, other],
+                temp_file.path(),
+            )
+            .expect("tag export");
+        let content = std::fs::read_to_string(temp_file.path()).expect("read tag export");
+        assert_eq!(
+            content,
+            "\u{feff}path\tAQ_SCORE\tAQ_GRADE\tAQ_STATUS\n\
+             a.flac\t85\tB\t质量良好\n\
+             test.flac\t61\tD\t已削波\n"
+        );
+    }
+
+    #[test]
+    fn test_grade_distribution_orders_best_first() {
+        let mut low = create_test_analysis();
+        low.quality_score = 40;
+        low.grade = "F".to_string();
+        let analyses = vec![low.clone(), create_test_analysis(), low];
+        assert_eq!(
+            grade_distribution(&analyses),
+            [("B".to_string(), 1), ("F".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_score_histogram_and_bars() {
+        assert_eq!(
+            score_histogram(&[0, 9, 10, 55, 99, 100, 120, -5]),
+            [3, 1, 0, 0, 0, 1, 0, 0, 0, 3]
+        );
+        assert_eq!(text_bar(0, 10), "");
+        assert_eq!(text_bar(10, 10).chars().count(), BAR_WIDTH);
+        assert_eq!(text_bar(1, 1000).chars().count(), 1);
+    }
+
+    #[test]
+    fn test_ranking_table_aligns_and_truncates_names() {
+        let mut long = create_test_analysis();
+        long.file_path = format!("/music/{}.flac", "very long file name ".repeat(8));
+        let rows = [create_test_analysis(), long];
+        let generator = ReportGenerator::new(true)
+            .with_color(false)
+            .with_lang(Lang::En);
+        let mut table = generator.ranking_table(&rows, Some(ReportSort::FileSize));
+        table.set_width(70);
+        let rendered = table.to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert!(lines[1].contains("Score") && lines[1].contains("File"));
+        // 每条记录占一行，长文件名被截断，表格不超过终端宽度。
+        assert_eq!(lines.len(), 6);
+        assert!(rendered.contains("..."));
+        assert!(lines.iter().all(|line| line.chars().count() <= 70));
+        assert!(!rendered.contains('\u{1b}'));
+    }
+
     #[test]
     fn test_display_summary() {
         let generator = ReportGenerator::new(true);
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Context, Result};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::path::Path;
+
+use super::scoring::{QualityStatus, Rule, RuleContext, RuleOutcome, ScorePenalty};
+
+/// 脚本对单个文件最多执行的操作数，防止死循环拖住整个批次。
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// 用户规则脚本（Rhai），在内置规则之后对每个文件运行一次。
+///
+/// 脚本可读取 `metrics`（字段名与 `analysis_data.json` 一致，缺失的指标为 `()`）、
+/// `issues`（前面规则已发现的问题名称）与 `profile`（评分档案名），返回：
+/// - `()`：未命中；
+/// - 字符串：作为备注写入报告；
+/// - 对象 `#{ note: "...", penalty: 5.0, status: "低码率" }`：各键均可省略，
+///   `penalty` 计入附加扣分，`status` 须为已有状态名，追加到问题列表。
+pub struct ScriptRule {
+    name: String,
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptRule {
+    /// 读取并编译规则脚本，规则名取文件名（不含扩展名）。
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("[E_RULE_SCRIPT] 无法读取规则脚本: {}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "rule".to_string());
+        Self::compile(name, &source)
+    }
+
+    fn compile(name: String, source: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow!("[E_RULE_SCRIPT] 规则脚本 {name} 语法错误: {e}"))?;
+        Ok(Self { name, engine, ast })
+    }
+
+    fn run(&self, ctx: &RuleContext<'_>) -> std::result::Result<RuleOutcome, String> {
+        // 先转为 JSON 值：频段表等以数字为键的字段需要转成字符串键。
+        let metrics = serde_json::to_value(ctx.metrics).map_err(|e| e.to_string())?;
+        let metrics: Dynamic = rhai::serde::to_dynamic(&metrics).map_err(|e| e.to_string())?;
+        let issues: rhai::Array = ctx
+            .issues
+            .iter()
+            .map(|issue| Dynamic::from(issue.to_string()))
+            .collect();
+        let mut scope = Scope::new();
+        scope.push("metrics", metrics);
+        scope.push("issues", issues);
+        scope.push("profile", ctx.profile.to_string());
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| e.to_string())?;
+        self.outcome_from(result)
+    }
+
+    fn outcome_from(&self, result: Dynamic) -> std::result::Result<RuleOutcome, String> {
+        if result.is_unit() {
+            return Ok(RuleOutcome::default());
+        }
+        if result.is_string() {
+            let note = result.into_string().map_err(|e| e.to_string())?;
+            return Ok(self.note(&note));
+        }
+        let Some(map) = result.try_cast::<Map>() else {
+            return Err("返回值须为 ()、字符串或对象".to_string());
+        };
+        let mut outcome = RuleOutcome::default();
+        if let Some(note) = map.get("note") {
+            let note = note.clone().into_string().map_err(|_| "note 须为字符串")?;
+            outcome.note = self.note(&note).note;
+        }
+        if let Some(penalty) = map.get("penalty") {
+            let points = penalty
+                .as_float()
+                .or_else(|_| penalty.as_int().map(|v| v as f64))
+                .map_err(|_| "penalty 须为数字")?;
+            if !(0.0..=100.0).contains(&points) {
+                return Err(format!("penalty 须在 0-100 之间: {points}"));
+            }
+            outcome.penalty = Some(ScorePenalty {
+                reason: self.name.clone(),
+                points,
+            });
+        }
+        if let Some(status) = map.get("status") {
+            let status = status
+                .clone()
+                .into_string()
+                .map_err(|_| "status 须为字符串")?;
+            let parsed: QualityStatus =
+                serde_json::from_value(serde_json::Value::String(status.clone()))
+                    .map_err(|_| format!("未知的状态: {status}"))?;
+            outcome.issue = Some(parsed);
+        }
+        Ok(outcome)
+    }
+
+    fn note(&self, text: &str) -> RuleOutcome {
+        RuleOutcome {
+            note: Some(format!("规则 {}: {text}", self.name)),
+            ..RuleOutcome::default()
+        }
+    }
+}
+
+impl Rule for ScriptRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 脚本运行出错只记入该文件的备注，不中断整批评分。
+    fn evaluate(&self, ctx: &RuleContext<'_>) -> RuleOutcome {
+        self.run(ctx)
+            .unwrap_or_else(|e| self.note(&format!("执行失败: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+
+    fn lossy(bitrate: u32) -> FileMetrics {
+        FileMetrics {
+            file_path: "/m/a.mp3".to_string(),
+            container_format: Some("mp3".to_string()),
+            codec_name: Some("mp3".to_string()),
+            bitrate_kbps: Some(bitrate),
+            lra: Some(8.0),
+            integrated_loudness_lufs: Some(-9.0),
+            true_peak_dbtp: Some(-1.0),
+            rms_db_above_18k: Some(-60.0),
+            ..FileMetrics::default()
+        }
+    }
+
+    #[test]
+    fn test_script_rule_adds_penalty_status_and_note() {
+        let rule = ScriptRule::compile(
+            "archive_320k".to_string(),
+            r#"
+                if metrics.bitrateKbps != () && metrics.bitrateKbps < 320 {
+                    #{ note: `码率 ${metrics.bitrateKbps} kbps 低于归档要求`, penalty: 6, status: "低码率" }
+                }
+            "#,
+        )
+        .expect("compile");
+        let scorer = QualityScorer::new().with_rule(Box::new(rule));
+        assert_eq!(scorer.profile_snapshot()["rules"][0], "archive_320k");
+
+        let plain = QualityScorer::new().analyze_file(&lossy(256));
+        let analysis = scorer.analyze_file(&lossy(256));
+        assert!(analysis.issues.contains(&QualityStatus::LowBitrate));
+        assert!(!plain.issues.contains(&QualityStatus::LowBitrate));
+        assert!(analysis
+            .notes
+            .contains("规则 archive_320k: 码率 256 kbps 低于归档要求"));
+        assert!(analysis
+            .score_breakdown
+            .penalties
+            .iter()
+            .any(|p| p.reason == "archive_320k" && p.points == 6.0));
+        assert!(analysis.quality_score < plain.quality_score);
+        assert!(!scorer.analyze_file(&lossy(320)).notes.contains("规则"));
+
+        // 运行期错误只记入备注；语法错误在加载时报错。
+        let broken =
+            ScriptRule::compile("broken".to_string(), r#"#{ status: "不存在" }"#).expect("compile");
+        let scorer = QualityScorer::new().with_rule(Box::new(broken));
+        assert!(scorer
+            .analyze_file(&lossy(256))
+            .notes
+            .contains("规则 broken: 执行失败: 未知的状态"));
+        let err = ScriptRule::compile("bad".to_string(), "if {")
+            .err()
+            .expect("syntax error");
+        assert!(err.to_string().contains("E_RULE_SCRIPT"));
+    }
+}
@@ -1,11 +1,23 @@
 use anyhow::{anyhow, Context, Result};
 use std::fs;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 use tempfile::Builder;
 
 /// 原子写入文件，避免符号链接跟随导致的外部文件覆盖风险。
 pub fn atomic_write_bytes(path: &Path, data: &[u8], safe_mode: bool) -> Result<()> {
+    atomic_write_with(path, safe_mode, |writer| {
+        writer
+            .write_all(data)
+            .with_context(|| format!("写入临时文件失败: {}", path.display()))
+    })
+}
+
+/// 原子写入，内容由 `write` 流式写入临时文件，不必先在内存中拼出完整内容。
+pub fn atomic_write_with<F>(path: &Path, safe_mode: bool, write: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> Result<()>,
+{
     let parent = path
         .parent()
         .ok_or_else(|| anyhow!("输出路径缺少父目录: {}", path.display()))?;
@@ -19,8 +31,13 @@ pub fn atomic_write_bytes(path: &Path, data: &[u8], safe_mode: bool) -> Result<(
         .tempfile_in(parent)
         .with_context(|| format!("无法在目录中创建临时文件: {}", parent.display()))?;
 
-    tmp.write_all(data)
-        .with_context(|| format!("写入临时文件失败: {}", path.display()))?;
+    {
+        let mut writer = BufWriter::with_capacity(256 * 1024, tmp.as_file_mut());
+        write(&mut writer)?;
+        writer
+            .flush()
+            .with_context(|| format!("写入临时文件失败: {}", path.display()))?;
+    }
     tmp.as_file()
         .sync_all()
         .with_context(|| format!("同步临时文件失败: {}", path.display()))?;
@@ -41,7 +58,8 @@ pub fn atomic_write_string(path: &Path, content: &str, safe_mode: bool) -> Resul
     atomic_write_bytes(path, content.as_bytes(), safe_mode)
 }
 
-fn reject_symlink(path: &Path) -> Result<()> {
+/// 输出路径是符号链接时拒绝写入（安全模式下用于非原子写入的输出文件）。
+pub fn reject_symlink(path: &Path) -> Result<()> {
     match fs::symlink_metadata(path) {
         Ok(metadata) if metadata.file_type().is_symlink() => Err(anyhow!(
             "检测到符号链接输出路径，已拒绝写入: {}",
@@ -65,6 +83,32 @@ mod tests {
         assert_eq!(content, "hello");
     }
 
+    #[test]
+    fn test_atomic_write_with_streams_and_keeps_old_file_on_error() {
+        let dir = TempDir::new().expect("tempdir");
+        let output = dir.path().join("out.jsonl");
+        atomic_write_with(&output, true, |writer| {
+            for i in 0..3 {
+                writeln!(writer, "{{\"n\":{i}}}")?;
+            }
+            Ok(())
+        })
+        .expect("stream write");
+        assert_eq!(
+            std::fs::read_to_string(&output).expect("read"),
+            "{\"n\":0}\n{\"n\":1}\n{\"n\":2}\n"
+        );
+
+        let result = atomic_write_with(&output, true, |writer| {
+            writer.write_all(b"partial")?;
+            Err(anyhow!("序列化失败"))
+        });
+        assert!(result.is_err());
+        assert!(std::fs::read_to_string(&output)
+            .expect("read")
+            .starts_with("{\"n\":0}"));
+    }
+
     #[cfg(unix)]
     #[test]
     fn test_atomic_write_reject_symlink() {
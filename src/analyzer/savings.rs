@@ -0,0 +1,317 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use super::duplicates::{self, DuplicateKind};
+use super::safe_io;
+use super::scoring::{is_lossless, QualityAnalysis, QualityStatus};
+
+/// 空间节省清单的文件名，与其他报告一起写在报告目录中。
+pub const SAVINGS_CSV_FILE_NAME: &str = "audio_quality_savings.csv";
+
+/// 伪无损文件换成“诚实”有损版本时按该码率（kbps）估算替换后的大小。
+const HONEST_LOSSY_KBPS: f64 = 320.0;
+
+/// WAV/AIFF 转为 FLAC 后的典型体积比例（FLAC 对流行音乐通常压缩到 55%–65%）。
+const FLAC_SIZE_RATIO: f64 = 0.6;
+
+/// 可节省空间的类别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SavingsCategory {
+    /// 内容或解码后音频相同的副本，保留分数最高的一份即可。
+    Duplicate,
+    /// 无损容器中实为有损转码的内容，可换成同等音质的有损文件。
+    FakeLossless,
+    /// 由 44.1/48 kHz 升采样的无损文件，降回原采样率的 FLAC 不损失任何内容。
+    Upsampled,
+    /// 未压缩的 PCM（WAV/AIFF），无损压缩为 FLAC 即可节省空间。
+    Uncompressed,
+}
+
+impl SavingsCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            SavingsCategory::Duplicate => "重复文件",
+            SavingsCategory::FakeLossless => "伪无损",
+            SavingsCategory::Upsampled => "升采样",
+            SavingsCategory::Uncompressed => "可转 FLAC",
+        }
+    }
+}
+
+impl Serialize for SavingsCategory {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.label())
+    }
+}
+
+/// 一个可节省空间的文件。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SavingsCandidate {
+    #[serde(rename = "类别")]
+    pub category: SavingsCategory,
+    #[serde(rename = "文件路径")]
+    pub file_path: String,
+    #[serde(rename = "文件大小(字节)")]
+    pub file_size_bytes: u64,
+    #[serde(rename = "可节省(字节)")]
+    pub savings_bytes: u64,
+    #[serde(rename = "说明")]
+    pub reason: String,
+}
+
+/// 整个批次的空间节省分析结果。
+#[derive(Debug, Clone, Default)]
+pub struct SavingsReport {
+    pub candidates: Vec<SavingsCandidate>,
+}
+
+impl SavingsReport {
+    /// 各类别的文件数与可节省字节数（只列出有候选的类别）。
+    pub fn totals(&self) -> BTreeMap<SavingsCategory, (usize, u64)> {
+        let mut totals: BTreeMap<SavingsCategory, (usize, u64)> = BTreeMap::new();
+        for candidate in &self.candidates {
+            let entry = totals.entry(candidate.category).or_default();
+            entry.0 += 1;
+            entry.1 += candidate.savings_bytes;
+        }
+        totals
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.candidates.iter().map(|c| c.savings_bytes).sum()
+    }
+}
+
+/// 找出可节省空间的文件。每个文件只计入一个类别，避免重复计算：
+/// 重复副本整份可删（保留哪份与重复文件清单一致），其余文件依次看是否为伪无损、升采样或未压缩 PCM。
+/// CUE 分轨与多音轨共用同一文件，按文件而非按记录计算。
+pub fn analyze(analyses: &[QualityAnalysis]) -> SavingsReport {
+    let mut seen_paths = HashSet::new();
+    let redundant: HashMap<&str, (&str, DuplicateKind)> = duplicates::find(analyses)
+        .iter()
+        .flat_map(|group| {
+            let keep = group.keep().file_path.as_str();
+            group
+                .redundant()
+                .iter()
+                .map(move |copy| (copy.file_path.as_str(), (keep, group.kind)))
+        })
+        .collect();
+    let mut candidates = Vec::new();
+
+    for analysis in analyses {
+        if !seen_paths.insert(analysis.file_path.as_str()) {
+            continue;
+        }
+        let metrics = &analysis.metrics;
+        let size = metrics.file_size_bytes;
+
+        if let Some((keep, kind)) = redundant.get(analysis.file_path.as_str()) {
+            candidates.push(SavingsCandidate {
+                category: SavingsCategory::Duplicate,
+                file_path: analysis.file_path.clone(),
+                file_size_bytes: size,
+                savings_bytes: size,
+                reason: match kind {
+                    DuplicateKind::Identical => format!("与 {keep} 内容相同"),
+                    DuplicateKind::SameAudio => format!("与 {keep} 音频相同（标签或容器不同）"),
+                },
+            });
+            continue;
+        }
+
+        if !is_lossless(metrics) {
+            continue;
+        }
+        let uncompressed = is_uncompressed_pcm(&analysis.file_path, metrics.codec_name.as_deref());
+        // 只有来源本身有损的文件才建议换成有损版本；升采样的内容仍是无损的。
+        let fake = analysis.issues.iter().find(|status| {
+            matches!(
+                status,
+                QualityStatus::Suspicious | QualityStatus::TranscodeChain
+            )
+        });
+        if let (Some(status), Some(duration)) = (fake, metrics.duration_seconds) {
+            let lossy_size = (duration * HONEST_LOSSY_KBPS * 1000.0 / 8.0) as u64;
+            if size > lossy_size {
+                candidates.push(SavingsCandidate {
+                    category: SavingsCategory::FakeLossless,
+                    file_path: analysis.file_path.clone(),
+                    file_size_bytes: size,
+                    savings_bytes: size - lossy_size,
+                    reason: format!("{status}，可换成 {HONEST_LOSSY_KBPS:.0} kbps 有损版本"),
+                });
+            }
+            continue;
+        }
+        let original_rate = metrics.sample_rate_hz.and_then(original_sample_rate);
+        if let (true, Some((declared, original))) = (
+            analysis.issues.contains(&QualityStatus::Upsampled),
+            original_rate,
+        ) {
+            // 体积大致与采样率成正比；未压缩的 PCM 同时转为 FLAC。
+            let mut ratio = f64::from(original) / f64::from(declared);
+            if uncompressed {
+                ratio *= FLAC_SIZE_RATIO;
+            }
+            candidates.push(SavingsCandidate {
+                category: SavingsCategory::Upsampled,
+                file_path: analysis.file_path.clone(),
+                file_size_bytes: size,
+                savings_bytes: (size as f64 * (1.0 - ratio)) as u64,
+                reason: format!(
+                    "{}，可降回 {:.1} kHz 的 FLAC（按采样率比例估算）",
+                    QualityStatus::Upsampled,
+                    f64::from(original) / 1000.0
+                ),
+            });
+            continue;
+        }
+        if uncompressed {
+            candidates.push(SavingsCandidate {
+                category: SavingsCategory::Uncompressed,
+                file_path: analysis.file_path.clone(),
+                file_size_bytes: size,
+                savings_bytes: (size as f64 * (1.0 - FLAC_SIZE_RATIO)) as u64,
+                reason: "未压缩 PCM，可无损转为 FLAC（按 60% 体积估算）".to_string(),
+            });
+        }
+    }
+
+    SavingsReport { candidates }
+}
+
+/// 升采样文件的声明采样率与推测的原始采样率：44.1 kHz 的整数倍来自 44.1 kHz，其余来自 48 kHz；
+/// 不高于 48 kHz 时没有可降的空间，返回 `None`。
+fn original_sample_rate(declared: u32) -> Option<(u32, u32)> {
+    let original = if declared.is_multiple_of(44_100) {
+        44_100
+    } else {
+        48_000
+    };
+    (declared > 48_000).then_some((declared, original))
+}
+
+/// 未压缩 PCM：编码为 `pcm_*`；缺少编码信息时按 `.wav`/`.aiff` 扩展名判断。
+fn is_uncompressed_pcm(path: &str, codec: Option<&str>) -> bool {
+    match codec {
+        Some(codec) => codec.to_ascii_lowercase().starts_with("pcm_"),
+        None => Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "wav" | "aiff" | "aif")),
+    }
+}
+
+/// 以 1024 进位的易读大小，如 `1.5 GiB`。
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// 写出空间节省清单（按可节省字节数降序）；没有候选时删除上次遗留的清单。
+pub fn write_savings_csv(path: &Path, report: &SavingsReport, safe_mode: bool) -> Result<()> {
+    if report.candidates.is_empty() {
+        if path.is_file() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("删除过期的空间节省清单失败: {}", path.display()))?;
+        }
+        return Ok(());
+    }
+
+    let mut sorted: Vec<&SavingsCandidate> = report.candidates.iter().collect();
+    sorted.sort_by(|a, b| {
+        b.savings_bytes
+            .cmp(&a.savings_bytes)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        for candidate in sorted {
+            writer
+                .serialize(candidate)
+                .context("写入空间节省记录失败")?;
+        }
+        writer.flush().context("刷新CSV缓冲失败")?;
+    }
+    safe_io::atomic_write_bytes(path, &buffer, safe_mode)?;
+    uiprintln!("✅ 空间节省清单已保存到: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+
+    fn analysis(
+        path: &str,
+        codec: &str,
+        hash: &str,
+        issues: Vec<QualityStatus>,
+    ) -> QualityAnalysis {
+        let mut analysis = QualityScorer::new().analyze_file(&FileMetrics {
+            file_path: path.to_string(),
+            file_size_bytes: 40_000_000,
+            codec_name: Some(codec.to_string()),
+            duration_seconds: Some(200.0),
+            content_sha256: Some(hash.to_string()),
+            ..FileMetrics::default()
+        });
+        analysis.issues = issues;
+        analysis
+    }
+
+    #[test]
+    fn test_savings_counts_each_file_once() {
+        let analyses = vec![
+            analysis("/m/a.wav", "pcm_s16le", "h1", vec![]),
+            analysis("/m/copy/a.wav", "pcm_s16le", "h1", vec![]),
+            analysis(
+                "/m/fake.flac",
+                "flac",
+                "h2",
+                vec![QualityStatus::Suspicious],
+            ),
+            analysis("/m/real.flac", "flac", "h3", vec![]),
+            analysis("/m/x.mp3", "mp3", "h4", vec![QualityStatus::Suspicious]),
+            analysis(
+                "/m/hires.flac",
+                "flac",
+                "h5",
+                vec![QualityStatus::Upsampled],
+            ),
+        ];
+        let mut analyses = analyses;
+        analyses[5].metrics.sample_rate_hz = Some(96_000);
+        let report = analyze(&analyses);
+        let totals = report.totals();
+        assert_eq!(totals[&SavingsCategory::Duplicate], (1, 40_000_000));
+        // 200 秒 320 kbps 约 8 MB。
+        assert_eq!(totals[&SavingsCategory::FakeLossless], (1, 32_000_000));
+        assert_eq!(totals[&SavingsCategory::Uncompressed], (1, 16_000_000));
+        // 96 kHz 升采样降回 48 kHz FLAC，约省一半；不建议换成有损版本。
+        assert_eq!(totals[&SavingsCategory::Upsampled], (1, 20_000_000));
+        assert!(report.candidates[3].reason.contains("48.0 kHz 的 FLAC"));
+        assert_eq!(report.total_bytes(), 108_000_000);
+        // 同分时按路径保留一份并照常检查，其余副本整份计为可删除。
+        assert_eq!(report.candidates[1].file_path, "/m/copy/a.wav");
+        assert!(report.candidates[1].reason.contains("/m/a.wav"));
+        assert_eq!(format_bytes(report.total_bytes()), "103.0 MiB");
+        assert_eq!(format_bytes(512), "512 B");
+    }
+}
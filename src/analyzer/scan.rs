@@ -0,0 +1,726 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use walkdir::WalkDir;
+
+use super::cue::{self, CueTrack};
+
+/// 默认扫描的音频扩展名（小写）。
+pub const SUPPORTED_EXTENSIONS: [&str; 10] = [
+    "wav", "mp3", "m4a", "flac", "aac", "ogg", "opus", "wma", "aiff", "alac",
+];
+
+/// 视频容器扩展名：只分析其中的音频流（演唱会视频等）。
+pub const VIDEO_EXTENSIONS: [&str; 4] = ["mkv", "mp4", "webm", "mov"];
+
+/// 默认视为“附加音轨”的扩展名（如带章节的 `.mka`）。
+pub const DEFAULT_EXTRA_EXTENSIONS: [&str; 1] = ["mka"];
+
+/// 文件名（不含扩展名）包含这些关键字时视为隐藏音轨/前置间隙（HTOA）。
+const PREGAP_STEM_MARKERS: [&str; 2] = ["pregap", "htoa"];
+
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// 是否扫描附加音轨（隐藏文件、隐藏目录、前置间隙及附加扩展名）。
+    pub include_extras: bool,
+    /// 附加扩展名（小写，不含点）。
+    pub extra_extensions: Vec<String>,
+    /// 忽略 CUE 文件，整轨镜像按单个文件分析。
+    pub ignore_cue: bool,
+    /// 最大扫描深度：`1` 只扫描文件夹顶层，`None` 不限。
+    pub max_depth: Option<usize>,
+    /// 跟随符号链接（默认跳过）；链接成环时跳过该链接，指向同一文件的多个路径只保留一个。
+    pub follow_symlinks: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedFile {
+    pub path: PathBuf,
+    /// 附加音轨所属的专辑目录；普通曲目为 `None`。
+    pub extra_of_album: Option<PathBuf>,
+    /// 整轨镜像对应 CUE 中的音轨，分析时逐轨拆分；普通文件为空。
+    pub cue_tracks: Vec<CueTrack>,
+}
+
+impl ScannedFile {
+    /// 不经扩展名与附加音轨过滤、直接分析的文件（如下载到临时目录的远程文件）。
+    pub fn forced(path: PathBuf) -> Self {
+        Self {
+            path,
+            extra_of_album: None,
+            cue_tracks: Vec::new(),
+        }
+    }
+}
+
+/// 递归扫描音频文件，结果按路径排序保证输出稳定。
+pub fn scan_audio_files(root: &Path, options: &ScanOptions) -> Vec<ScannedFile> {
+    scan_audio_files_counted(root, options).0
+}
+
+/// 同 [`scan_audio_files`]，另返回因未开启 `include_extras` 而跳过的附加音轨数。
+pub fn scan_audio_files_counted(root: &Path, options: &ScanOptions) -> (Vec<ScannedFile>, usize) {
+    let mut files = Vec::new();
+    let mut skipped_extras = 0;
+    let mut cue_sheets = Vec::new();
+    let mut walker = WalkDir::new(root).follow_links(options.follow_symlinks);
+    if let Some(depth) = options.max_depth {
+        walker = walker.max_depth(depth);
+    }
+    for entry in walker
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                if let (Some(path), Some(ancestor)) = (e.path(), e.loop_ancestor()) {
+                    tracing::warn!(
+                        "跳过成环的符号链接: {} → {}",
+                        path.display(),
+                        ancestor.display()
+                    );
+                } else {
+                    tracing::debug!("扫描时跳过无法读取的条目: {e}");
+                }
+                None
+            }
+        })
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        if is_cue_sheet(path) {
+            cue_sheets.push(path.to_path_buf());
+        } else {
+            match classify(root, path, options) {
+                Classified::Audio(file) => files.push(file),
+                Classified::SkippedExtra => skipped_extras += 1,
+                Classified::NotAudio => {}
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    cue_sheets.sort();
+    if options.follow_symlinks {
+        dedup_by_target(&mut files, |file| &file.path);
+        dedup_by_target(&mut cue_sheets, |cue| cue);
+    }
+
+    if options.ignore_cue || cue_sheets.is_empty() {
+        return (files, skipped_extras);
+    }
+    attach_cue_tracks(&mut files, &cue_sheets);
+    (files, skipped_extras)
+}
+
+/// 只对给定的文件列表做分类（用于重试失败文件），CUE 取自这些文件所在目录。
+/// 明确列出的隐藏文件与前置间隙音轨即使未开启 `include_extras` 也照常分析（作为附加音轨）。
+pub fn scan_listed_files(
+    root: &Path,
+    paths: &[PathBuf],
+    options: &ScanOptions,
+) -> Vec<ScannedFile> {
+    let listed = ScanOptions {
+        include_extras: true,
+        ..options.clone()
+    };
+    let mut files: Vec<ScannedFile> = paths
+        .iter()
+        .filter_map(|path| match classify(root, path, &listed) {
+            Classified::Audio(file) => Some(file),
+            Classified::SkippedExtra | Classified::NotAudio => None,
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    if options.ignore_cue {
+        return files;
+    }
+
+    let mut dirs: Vec<&Path> = paths.iter().filter_map(|p| p.parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    let mut cue_sheets: Vec<PathBuf> = dirs
+        .into_iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_cue_sheet(path))
+        .collect();
+    cue_sheets.sort();
+    attach_cue_tracks(&mut files, &cue_sheets);
+    files
+}
+
+/// 提取阶段的处理顺序；报告中的顺序不受影响。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Schedule {
+    /// 大文件优先（默认）：几个数 GB 的 DSD/高解析度文件不会在并行阶段末尾拖出一条串行的长尾。
+    #[default]
+    Size,
+    /// 按扫描顺序（路径）。
+    Name,
+    /// 随机顺序。
+    Random,
+}
+
+impl Schedule {
+    /// 与命令行取值一致的小写名称。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Size => "size",
+            Self::Name => "name",
+            Self::Random => "random",
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "size" => Ok(Self::Size),
+            "name" | "path" => Ok(Self::Name),
+            "random" | "shuffle" => Ok(Self::Random),
+            other => Err(format!(
+                "不支持的处理顺序: {other}（可选 size、name、random）"
+            )),
+        }
+    }
+}
+
+/// 按 `schedule` 给出处理顺序（`files` 的下标）。无法读取大小的文件按 0 字节计，大小相同时保持扫描顺序。
+pub fn processing_order(files: &[ScannedFile], schedule: Schedule) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    match schedule {
+        Schedule::Size => {
+            let sizes: Vec<u64> = files
+                .iter()
+                .map(|file| std::fs::metadata(&file.path).map_or(0, |m| m.len()))
+                .collect();
+            order.sort_by_key(|&i| std::cmp::Reverse(sizes[i]));
+        }
+        Schedule::Name => {}
+        Schedule::Random => fastrand::shuffle(&mut order),
+    }
+    order
+}
+
+/// 扫描结果的概况（`--dry-run`）：文件数、总字节数与按扩展名的分布。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanSummary {
+    pub files: usize,
+    pub bytes: u64,
+    pub extra_files: usize,
+    /// 带 CUE 的整轨镜像数与其中的曲目数。
+    pub cue_images: usize,
+    pub cue_tracks: usize,
+    /// `(扩展名, 文件数, 字节数)`，按文件数从多到少排列。
+    pub by_extension: Vec<(String, usize, u64)>,
+    /// 因未开启 `--include-extras` 而跳过的附加音轨数（隐藏文件、前置间隙及附加扩展名）。
+    pub skipped_extras: usize,
+}
+
+impl ScanSummary {
+    /// 统计扫描结果；无法读取大小的文件按 0 字节计。
+    pub fn of(files: &[ScannedFile]) -> Self {
+        let mut summary = ScanSummary::default();
+        let mut by_extension: HashMap<String, (usize, u64)> = HashMap::new();
+        for file in files {
+            let bytes = std::fs::metadata(&file.path).map_or(0, |m| m.len());
+            let ext = file
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let entry = by_extension.entry(ext).or_default();
+            entry.0 += 1;
+            entry.1 += bytes;
+            summary.files += 1;
+            summary.bytes += bytes;
+            if file.extra_of_album.is_some() {
+                summary.extra_files += 1;
+            }
+            if !file.cue_tracks.is_empty() {
+                summary.cue_images += 1;
+                summary.cue_tracks += file.cue_tracks.len();
+            }
+        }
+        summary.by_extension = by_extension
+            .into_iter()
+            .map(|(ext, (count, bytes))| (ext, count, bytes))
+            .collect();
+        summary
+            .by_extension
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        summary
+    }
+}
+
+/// 把命令行给出的文件与目录展开为去重后的文件列表：目录递归扫描，文件原样保留（分类在分析时进行）。
+pub fn expand_inputs(inputs: &[PathBuf], options: &ScanOptions) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            files.extend(
+                scan_audio_files(input, options)
+                    .into_iter()
+                    .map(|file| file.path),
+            );
+        } else {
+            files.push(input.clone());
+        }
+    }
+    files.sort();
+    files.dedup();
+    if options.follow_symlinks {
+        dedup_by_target(&mut files, |file| file);
+    }
+    files
+}
+
+/// 按规范化路径去重（保留排序后的第一个路径），避免通过不同链接重复分析同一文件。
+fn dedup_by_target<T>(files: &mut Vec<T>, path: impl Fn(&T) -> &PathBuf) {
+    let mut seen = HashSet::new();
+    files.retain(|file| {
+        let path = path(file);
+        seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone()))
+    });
+}
+
+/// 解析路径列表（`--files-from`）：按换行（兼容 `\r\n`）或 NUL 分隔，忽略空项。
+pub fn parse_path_list(content: &[u8], null_separated: bool) -> Vec<PathBuf> {
+    let separator = if null_separated { b'\0' } else { b'\n' };
+    content
+        .split(|&byte| byte == separator)
+        .map(|entry| {
+            if null_separated {
+                entry
+            } else {
+                entry.strip_suffix(b"\r").unwrap_or(entry)
+            }
+        })
+        .filter(|entry| !entry.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// 多个输入路径的最近公共目录，作为报告、缓存与历史记录所属的曲库根目录。
+/// 文件取其所在目录；路径应已规范化为绝对路径。
+pub fn common_root(inputs: &[PathBuf]) -> Option<PathBuf> {
+    let mut dirs = inputs.iter().map(|input| {
+        if input.is_dir() {
+            input.as_path()
+        } else {
+            input.parent().unwrap_or(input)
+        }
+    });
+    let mut root = dirs.next()?.to_path_buf();
+    for dir in dirs {
+        while !dir.starts_with(&root) {
+            if !root.pop() {
+                break;
+            }
+        }
+    }
+    Some(root)
+}
+
+fn is_cue_sheet(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+        && !path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("._"))
+}
+
+/// 为带 CUE 的整轨镜像（至少两轨）附上音轨列表。
+///
+/// CUE 中的 `FILE` 名称优先；编码不同导致无法匹配时，单 `FILE` 的 CUE 回退到同目录下同名（不含扩展名）的音频文件。
+/// 多个 CUE 指向同一镜像时按路径排序取第一个，其余记录警告后忽略。
+fn attach_cue_tracks(files: &mut [ScannedFile], cue_sheets: &[PathBuf]) {
+    let mut tracks_by_image: HashMap<PathBuf, (&Path, Vec<CueTrack>)> = HashMap::new();
+    for cue_path in cue_sheets {
+        let Ok(sheet) = cue::read_cue(cue_path) else {
+            continue;
+        };
+        let dir = cue_path.parent().unwrap_or(Path::new(""));
+        let single_file = sheet.files.len() == 1;
+        for cue_file in sheet.files {
+            if cue_file.tracks.len() < 2 {
+                continue;
+            }
+            let named = dir.join(&cue_file.name);
+            let image = files
+                .iter()
+                .find(|f| f.path == named)
+                .or_else(|| {
+                    files.iter().find(|f| {
+                        single_file
+                            && f.path.parent() == Some(dir)
+                            && f.path.file_stem() == cue_path.file_stem()
+                    })
+                })
+                .map(|f| f.path.clone());
+            let Some(image) = image else {
+                continue;
+            };
+            match tracks_by_image.get(&image) {
+                Some((first, _)) => tracing::warn!(
+                    "多个 CUE 指向同一音频文件，使用 {}，忽略 {}: {}",
+                    first.display(),
+                    cue_path.display(),
+                    image.display()
+                ),
+                None => {
+                    tracks_by_image.insert(image, (cue_path, cue_file.tracks));
+                }
+            }
+        }
+    }
+
+    for file in files.iter_mut() {
+        if let Some((_, tracks)) = tracks_by_image.remove(&file.path) {
+            file.cue_tracks = tracks;
+        }
+    }
+}
+
+/// 单个文件的分类结果。
+enum Classified {
+    Audio(ScannedFile),
+    /// 附加音轨，但未开启 `include_extras`。
+    SkippedExtra,
+    NotAudio,
+}
+
+fn classify(root: &Path, path: &Path, options: &ScanOptions) -> Classified {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Classified::NotAudio;
+    };
+    // macOS 在非 HFS 卷上生成的 AppleDouble 元数据文件，永远不是音频。
+    if file_name.starts_with("._") {
+        return Classified::NotAudio;
+    }
+
+    let Some(ext) = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+    else {
+        return Classified::NotAudio;
+    };
+    let standard_ext =
+        SUPPORTED_EXTENSIONS.contains(&ext.as_str()) || VIDEO_EXTENSIONS.contains(&ext.as_str());
+    let extra_ext = options.extra_extensions.contains(&ext);
+    if !standard_ext && !extra_ext {
+        return Classified::NotAudio;
+    }
+
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let hidden = relative
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let pregap = PREGAP_STEM_MARKERS.iter().any(|m| stem.contains(m));
+
+    if !(hidden || pregap || !standard_ext) {
+        return Classified::Audio(ScannedFile {
+            path: path.to_path_buf(),
+            extra_of_album: None,
+            cue_tracks: Vec::new(),
+        });
+    }
+    if !options.include_extras {
+        return Classified::SkippedExtra;
+    }
+
+    Classified::Audio(ScannedFile {
+        path: path.to_path_buf(),
+        extra_of_album: Some(album_dir_for(root, path)),
+        cue_tracks: Vec::new(),
+    })
+}
+
+/// 附加音轨归属的专辑目录：向上越过隐藏目录后的第一个普通目录。
+fn album_dir_for(root: &Path, path: &Path) -> PathBuf {
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| {
+            *dir == root
+                || !dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().starts_with('.'))
+                    .unwrap_or(false)
+        })
+        .unwrap_or(root)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_supported_extensions_are_lowercase() {
+        for ext in SUPPORTED_EXTENSIONS.iter().chain(&VIDEO_EXTENSIONS) {
+            assert_eq!(*ext, ext.to_ascii_lowercase());
+        }
+    }
+
+    #[test]
+    fn test_extras_are_opt_in_and_grouped_under_album() {
+        let dir = TempDir::new().expect("tempdir");
+        let album = dir.path().join("Album");
+        fs::create_dir_all(album.join(".extras")).expect("mkdir");
+        for name in [
+            "01.flac",
+            "live.mkv",
+            "00 - pregap.wav",
+            ".hidden.wav",
+            "._01.flac",
+            "chapters.mka",
+            ".extras/bonus.flac",
+            "cover.jpg",
+        ] {
+            fs::write(album.join(name), b"x").expect("write");
+        }
+
+        let default_scan = scan_audio_files(dir.path(), &ScanOptions::default());
+        let regular = |name: &str| ScannedFile {
+            path: album.join(name),
+            extra_of_album: None,
+            cue_tracks: Vec::new(),
+        };
+        assert_eq!(default_scan, vec![regular("01.flac"), regular("live.mkv")]);
+        // 跳过的附加音轨计数（`--dry-run` 中列出），明确列出的附加音轨照常分析。
+        let (_, skipped) = scan_audio_files_counted(dir.path(), &ScanOptions::default());
+        assert_eq!(skipped, 3);
+        let listed = scan_listed_files(
+            dir.path(),
+            &[album.join("00 - pregap.wav"), album.join("cover.jpg")],
+            &ScanOptions::default(),
+        );
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].extra_of_album.as_deref(), Some(album.as_path()));
+
+        let options = ScanOptions {
+            include_extras: true,
+            extra_extensions: DEFAULT_EXTRA_EXTENSIONS.map(String::from).to_vec(),
+            ..ScanOptions::default()
+        };
+        let with_extras = scan_audio_files(dir.path(), &options);
+        assert_eq!(with_extras.len(), 6);
+        assert!(with_extras
+            .iter()
+            .filter(|f| !default_scan.contains(f))
+            .all(|f| f.extra_of_album.as_deref() == Some(album.as_path())));
+    }
+
+    #[test]
+    fn test_cue_sheet_splits_album_image_into_tracks() {
+        let dir = TempDir::new().expect("tempdir");
+        // CUE 中的文件名与实际文件不同（如编码问题），按同名规则回退匹配。
+        fs::write(dir.path().join("Live.flac"), b"x").expect("write");
+        fs::write(dir.path().join("single.flac"), b"x").expect("write");
+        fs::write(
+            dir.path().join("Live.cue"),
+            "FILE \"???.flac\" WAVE\nTRACK 01 AUDIO\nTITLE \"Intro\"\nINDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\nTITLE \"Encore\"\nINDEX 01 04:00:00\n",
+        )
+        .expect("write cue");
+        // 指向同一镜像的第二个 CUE 排在后面，被忽略。
+        fs::write(
+            dir.path().join("Zz.cue"),
+            "FILE \"Live.flac\" WAVE\nTRACK 01 AUDIO\nTITLE \"A\"\nINDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\nTITLE \"B\"\nINDEX 01 01:00:00\n",
+        )
+        .expect("write cue");
+
+        let files = scan_audio_files(dir.path(), &ScanOptions::default());
+        assert_eq!(files.len(), 2);
+        let labels: Vec<String> = files[0].cue_tracks.iter().map(CueTrack::label).collect();
+        assert_eq!(labels, vec!["01 - Intro", "02 - Encore"]);
+        assert_eq!(files[0].cue_tracks[0].end_seconds, Some(240.0));
+        assert!(files[1].cue_tracks.is_empty());
+
+        let listed = scan_listed_files(
+            dir.path(),
+            &[dir.path().join("Live.flac")],
+            &ScanOptions::default(),
+        );
+        assert_eq!(listed, files[..1]);
+
+        let ignored = ScanOptions {
+            ignore_cue: true,
+            ..ScanOptions::default()
+        };
+        assert!(scan_audio_files(dir.path(), &ignored)
+            .iter()
+            .all(|f| f.cue_tracks.is_empty()));
+    }
+
+    #[test]
+    fn test_expand_inputs_mixes_files_and_directories() {
+        let dir = TempDir::new().expect("tempdir");
+        let album = dir.path().join("Album");
+        let downloads = dir.path().join("Downloads");
+        fs::create_dir_all(&album).expect("mkdir");
+        fs::create_dir_all(&downloads).expect("mkdir");
+        for path in [
+            album.join("01.flac"),
+            album.join("02.flac"),
+            downloads.join("single.mp3"),
+        ] {
+            fs::write(path, b"x").expect("write");
+        }
+
+        let inputs = vec![
+            downloads.join("single.mp3"),
+            album.clone(),
+            album.join("01.flac"),
+        ];
+        let files = expand_inputs(&inputs, &ScanOptions::default());
+        assert_eq!(
+            files,
+            [
+                album.join("01.flac"),
+                album.join("02.flac"),
+                downloads.join("single.mp3"),
+            ]
+        );
+        assert_eq!(common_root(&inputs).as_deref(), Some(dir.path()));
+        assert_eq!(
+            common_root(&[downloads.join("single.mp3")]),
+            Some(downloads)
+        );
+        assert!(common_root(&[]).is_none());
+    }
+
+    #[test]
+    fn test_max_depth_limits_recursion() {
+        let dir = TempDir::new().expect("tempdir");
+        let nested = dir.path().join("archive").join("disc1");
+        fs::create_dir_all(&nested).expect("mkdir");
+        fs::write(dir.path().join("top.flac"), b"x").expect("write");
+        fs::write(dir.path().join("archive").join("mid.flac"), b"x").expect("write");
+        fs::write(nested.join("deep.flac"), b"x").expect("write");
+
+        let count = |max_depth| {
+            let options = ScanOptions {
+                max_depth,
+                ..ScanOptions::default()
+            };
+            scan_audio_files(dir.path(), &options).len()
+        };
+        assert_eq!(count(Some(1)), 1);
+        assert_eq!(count(Some(2)), 2);
+        assert_eq!(count(None), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_skips_loops_and_duplicates() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().expect("tempdir");
+        let store = dir.path().join("store");
+        let farm = dir.path().join("farm");
+        fs::create_dir_all(&store).expect("mkdir");
+        fs::create_dir_all(&farm).expect("mkdir");
+        fs::write(store.join("01.flac"), b"x").expect("write");
+        symlink(&store, farm.join("Album")).expect("dir link");
+        symlink(store.join("01.flac"), farm.join("favorite.flac")).expect("file link");
+        symlink(&farm, farm.join("loop")).expect("loop link");
+
+        assert!(scan_audio_files(&farm, &ScanOptions::default()).is_empty());
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..ScanOptions::default()
+        };
+        let files = scan_audio_files(&farm, &options);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, farm.join("Album").join("01.flac"));
+
+        let inputs = vec![farm.clone(), store.join("01.flac")];
+        assert_eq!(expand_inputs(&inputs, &options).len(), 1);
+    }
+
+    #[test]
+    fn test_scan_summary_groups_by_extension() {
+        let dir = TempDir::new().expect("tempdir");
+        fs::write(dir.path().join("01.flac"), [0u8; 100]).expect("write");
+        fs::write(dir.path().join("02.FLAC"), [0u8; 50]).expect("write");
+        fs::write(dir.path().join("03.mp3"), [0u8; 10]).expect("write");
+
+        let summary = ScanSummary::of(&scan_audio_files(dir.path(), &ScanOptions::default()));
+        assert_eq!(summary.files, 3);
+        assert_eq!(summary.bytes, 160);
+        assert_eq!(
+            summary.by_extension,
+            [("flac".to_string(), 2, 150), ("mp3".to_string(), 1, 10)]
+        );
+    }
+
+    #[test]
+    fn test_processing_order_puts_largest_first() {
+        let dir = TempDir::new().expect("tempdir");
+        let files: Vec<ScannedFile> = [
+            ("a.flac", 10),
+            ("b.dsf", 300),
+            ("c.flac", 10),
+            ("d.wav", 200),
+        ]
+        .into_iter()
+        .map(|(name, size)| {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; size]).expect("write");
+            ScannedFile {
+                path,
+                extra_of_album: None,
+                cue_tracks: Vec::new(),
+            }
+        })
+        .collect();
+        assert_eq!(processing_order(&files, Schedule::Size), [1, 3, 0, 2]);
+        assert_eq!(processing_order(&files, Schedule::Name), [0, 1, 2, 3]);
+        let mut random = processing_order(&files, Schedule::Random);
+        random.sort_unstable();
+        assert_eq!(random, [0, 1, 2, 3]);
+        assert_eq!("Shuffle".parse::<Schedule>(), Ok(Schedule::Random));
+        assert!("biggest".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn test_parse_path_list() {
+        assert_eq!(
+            parse_path_list(b"/m/a.flac\r\n\n/m/b c.flac\n", false),
+            [PathBuf::from("/m/a.flac"), PathBuf::from("/m/b c.flac")]
+        );
+        assert_eq!(
+            parse_path_list(b"./line\nbreak.flac\0./x.mp3\0", true),
+            [
+                PathBuf::from("./line\nbreak.flac"),
+                PathBuf::from("./x.mp3")
+            ]
+        );
+        assert!(parse_path_list(b"", true).is_empty());
+    }
+}
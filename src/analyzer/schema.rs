@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::metrics::FileMetrics;
+use super::safe_io;
+use super::scoring::QualityAnalysis;
+
+/// 输出文件（`analysis_data.json`、`audio_quality_report.json`）的格式版本。
+/// 字段增删或含义变化时递增，下游据此校验与迁移；旧版本的裸数组视为版本 0。
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 原始指标文件名。
+pub const ANALYSIS_DATA_FILE_NAME: &str = "analysis_data.json";
+
+/// 报告写到独立输出目录时记录对应的音乐库根目录，供 `--retry-failed`、对比与重新评分找回曲库。
+pub const SOURCE_MARKER_FILE_NAME: &str = "audio_quality_source.json";
+
+/// 报告目录对应的音乐库根目录；报告写在库目录中（没有来源标记）时为 `None`。
+pub fn read_source_marker(report_dir: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(report_dir.join(SOURCE_MARKER_FILE_NAME)).ok()?;
+    let marker: serde_json::Value = serde_json::from_str(&content).ok()?;
+    marker["libraryRoot"].as_str().map(PathBuf::from)
+}
+
+/// `analysis_data.json`：每个文件（音轨/CUE 曲目）的原始指标。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisData {
+    pub schema_version: u32,
+    pub files: Vec<FileMetrics>,
+}
+
+/// `audio_quality_report.json`：完整评分结果。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoredReport {
+    pub schema_version: u32,
+    pub results: Vec<QualityAnalysis>,
+}
+
+/// 写出时借用结果，避免为序列化复制整个曲库的记录。
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalysisDataRef<'a> {
+    schema_version: u32,
+    files: &'a [FileMetrics],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScoredReportRef<'a> {
+    schema_version: u32,
+    results: &'a [QualityAnalysis],
+}
+
+/// 读取时兼容没有版本号的旧格式（裸数组）。
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AnalysisDataInput {
+    Versioned(AnalysisData),
+    Legacy(Vec<FileMetrics>),
+}
+
+/// `schema` 子命令可输出的文档。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// `audio_quality_report.json`。
+    Report,
+    /// `analysis_data.json`。
+    AnalysisData,
+}
+
+impl FromStr for SchemaKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "report" => Ok(SchemaKind::Report),
+            "analysis-data" | "analysis_data" => Ok(SchemaKind::AnalysisData),
+            _ => Err(format!("未知的格式 {s}，可选 report、analysis-data")),
+        }
+    }
+}
+
+/// 当前版本输出文件的 JSON Schema（美化格式）。
+pub fn json_schema(kind: SchemaKind) -> Result<String> {
+    let schema = match kind {
+        SchemaKind::Report => schemars::schema_for!(ScoredReport),
+        SchemaKind::AnalysisData => schemars::schema_for!(AnalysisData),
+    };
+    serde_json::to_string_pretty(&schema).context("序列化 JSON Schema 失败")
+}
+
+/// 流式写出 `analysis_data.json`，峰值内存不随曲库规模增长。
+pub fn write_analysis_data(path: &Path, files: &[FileMetrics], safe_mode: bool) -> Result<()> {
+    safe_io::atomic_write_with(path, safe_mode, |writer| {
+        let data = AnalysisDataRef {
+            schema_version: SCHEMA_VERSION,
+            files,
+        };
+        serde_json::to_writer_pretty(writer, &data).context("序列化分析结果失败")
+    })
+}
+
+pub fn write_scored_report(
+    path: &Path,
+    results: &[QualityAnalysis],
+    safe_mode: bool,
+) -> Result<()> {
+    safe_io::atomic_write_with(path, safe_mode, |writer| {
+        let report = ScoredReportRef {
+            schema_version: SCHEMA_VERSION,
+            results,
+        };
+        serde_json::to_writer_pretty(writer, &report).context("序列化JSON报告失败")
+    })
+}
+
+/// 解析 `analysis_data.json`：接受当前与更早的版本（含无版本号的裸数组），拒绝更新版本写出的文件。
+pub fn parse_analysis_data(content: &str) -> Result<Vec<FileMetrics>> {
+    match serde_json::from_str(content)? {
+        AnalysisDataInput::Legacy(files) => Ok(files),
+        AnalysisDataInput::Versioned(data) if data.schema_version > SCHEMA_VERSION => Err(anyhow!(
+            "格式版本 {} 由更新的版本写出，当前最高支持 {SCHEMA_VERSION}",
+            data.schema_version
+        )),
+        AnalysisDataInput::Versioned(data) => Ok(data.files),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_analysis_data_round_trip_and_legacy() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join(ANALYSIS_DATA_FILE_NAME);
+        let files = vec![FileMetrics {
+            file_path: "/m/a.flac".to_string(),
+            ..FileMetrics::default()
+        }];
+        write_analysis_data(&path, &files, true).expect("write");
+        let content = std::fs::read_to_string(&path).expect("read");
+        let value: serde_json::Value = serde_json::from_str(&content).expect("json");
+        assert_eq!(value["schemaVersion"], SCHEMA_VERSION);
+        assert_eq!(parse_analysis_data(&content).expect("parse").len(), 1);
+
+        let legacy = serde_json::to_string(&files).expect("legacy");
+        assert_eq!(parse_analysis_data(&legacy).expect("legacy parse").len(), 1);
+
+        let newer = format!(
+            r#"{{"schemaVersion": {}, "files": []}}"#,
+            SCHEMA_VERSION + 1
+        );
+        assert!(parse_analysis_data(&newer).is_err());
+    }
+
+    #[test]
+    fn test_json_schema_describes_versioned_documents() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&json_schema(SchemaKind::Report).expect("schema")).expect("json");
+        assert!(schema["required"]
+            .as_array()
+            .expect("required")
+            .contains(&"schemaVersion".into()));
+        assert!(schema.to_string().contains("质量分"));
+
+        let schema = json_schema("analysis-data".parse().expect("kind")).expect("schema");
+        assert!(schema.contains("integratedLoudnessLufs"));
+        assert!("csv".parse::<SchemaKind>().is_err());
+    }
+}
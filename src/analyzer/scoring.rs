@@ -1,7 +1,18 @@
+use super::album;
+use super::classifier::LossyClassifier;
+use super::compare::BaselineDelta;
+use super::compliance::ComplianceResult;
+use super::ffmpeg::{self, FALLBACK_CODE_PREFIX, SOFTWARE_DECODE_CODE_PREFIX, UPSAMPLE_CHECK_HZ};
+use super::hires::{self, HiResAssessment};
+use super::i18n::{self, Lang, Note};
 use super::metrics::FileMetrics;
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ScoringProfile {
@@ -11,14 +22,41 @@ pub enum ScoringProfile {
     Broadcast,
     #[serde(rename = "archive")]
     Archive,
+    #[serde(rename = "classical")]
+    Classical,
+    #[serde(rename = "edm")]
+    Edm,
+    #[serde(rename = "podcast")]
+    Podcast,
+    #[serde(rename = "audiobook")]
+    Audiobook,
+    #[serde(rename = "vinyl")]
+    VinylRip,
 }
 
 impl ScoringProfile {
+    /// 全部内置档案，按交互菜单中的顺序排列。
+    pub const ALL: [ScoringProfile; 8] = [
+        ScoringProfile::Pop,
+        ScoringProfile::Broadcast,
+        ScoringProfile::Archive,
+        ScoringProfile::Classical,
+        ScoringProfile::Edm,
+        ScoringProfile::Podcast,
+        ScoringProfile::Audiobook,
+        ScoringProfile::VinylRip,
+    ];
+
     pub fn as_str(self) -> &'static str {
         match self {
             ScoringProfile::Pop => "pop",
             ScoringProfile::Broadcast => "broadcast",
             ScoringProfile::Archive => "archive",
+            ScoringProfile::Classical => "classical",
+            ScoringProfile::Edm => "edm",
+            ScoringProfile::Podcast => "podcast",
+            ScoringProfile::Audiobook => "audiobook",
+            ScoringProfile::VinylRip => "vinyl",
         }
     }
 }
@@ -26,19 +64,30 @@ impl ScoringProfile {
 impl FromStr for ScoringProfile {
     type Err = String;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         match s.trim().to_ascii_lowercase().as_str() {
             "pop" | "kpop" | "jpop" | "apop" => Ok(ScoringProfile::Pop),
             "broadcast" => Ok(ScoringProfile::Broadcast),
             "archive" => Ok(ScoringProfile::Archive),
+            "classical" => Ok(ScoringProfile::Classical),
+            "edm" | "electronic" => Ok(ScoringProfile::Edm),
+            "podcast" | "spoken-word" | "spoken_word" | "speech" => Ok(ScoringProfile::Podcast),
+            "audiobook" => Ok(ScoringProfile::Audiobook),
+            "vinyl" | "vinyl-rip" | "vinyl_rip" => Ok(ScoringProfile::VinylRip),
             _ => Err(format!(
-                "不支持的 profile: {s}，可选: pop/broadcast/archive"
+                "不支持的 profile: {s}，可选: pop/broadcast/archive/classical/edm/podcast/audiobook/vinyl"
             )),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// `UPSAMPLE_CHECK_HZ` 以上 RMS 低于该电平（dB）即视为没有超声频段内容。
+/// 真实的高解析度录音在此频段至少有 ADC 噪声（约 -100 ~ -115 dB），升采样结果接近数字静音。
+pub const UPSAMPLED_HF_THRESHOLD_DB: f64 = -120.0;
+
+/// 档案阈值。自定义档案文件中的键与字段名一致，未写出的字段沿用基础档案。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct ProfileConfig {
     target_lufs: f64,
     loudness_soft_range_low: f64,
@@ -56,6 +105,161 @@ struct ProfileConfig {
     lra_too_high: f64,
     bitrate_low_kbps: u32,
     bitrate_high_kbps: u32,
+    #[serde(default)]
+    weights: ScoreWeights,
+}
+
+/// 各维度满分（总和 100）。默认 35/20/25/10/10，可通过 `--score-weights` 或档案文件的 `[weights]` 调整，
+/// 例如档案馆可以提高真实性的权重、降低响度合规的权重。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreWeights {
+    pub compliance: f64,
+    pub dynamics: f64,
+    pub spectrum: f64,
+    pub authenticity: f64,
+    pub integrity: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            compliance: 35.0,
+            dynamics: 20.0,
+            spectrum: 25.0,
+            authenticity: 10.0,
+            integrity: 10.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    fn as_array(&self) -> [f64; 5] {
+        [
+            self.compliance,
+            self.dynamics,
+            self.spectrum,
+            self.authenticity,
+            self.integrity,
+        ]
+    }
+
+    /// 权重必须非负且总和为 100，否则分数区间与 elite 压缩阈值都会失去意义。
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let weights = self.as_array();
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("权重必须是非负数".to_string());
+        }
+        let total: f64 = weights.iter().sum();
+        if (total - 100.0).abs() > 1e-6 {
+            return Err(format!("权重总和必须为 100，当前为 {total}"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ScoreWeights {
+    type Err = String;
+
+    /// 按 合规,动态,频谱,真实性,完整性 的顺序解析，如 `25,20,25,20,10`。
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let parts = value
+            .split(',')
+            .map(|part| {
+                part.trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("无效的权重: {part}"))
+            })
+            .collect::<std::result::Result<Vec<f64>, String>>()?;
+        let [compliance, dynamics, spectrum, authenticity, integrity] = parts[..] else {
+            return Err(format!(
+                "需要 5 个权重（合规,动态,频谱,真实性,完整性），实际 {} 个",
+                parts.len()
+            ));
+        };
+        let weights = Self {
+            compliance,
+            dynamics,
+            spectrum,
+            authenticity,
+            integrity,
+        };
+        weights.validate()?;
+        Ok(weights)
+    }
+}
+
+/// 分数到字母等级的映射，按下限从高到低排列，例如默认的 `A+=95,A=90,B=80,C=70,D=60,F=0`。
+/// 可通过 `--grade-scale` 或配置文件中的 `grade_scale` 调整，便于向非技术客户展示报告。
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradeScale {
+    grades: Vec<(String, i32)>,
+}
+
+impl Default for GradeScale {
+    fn default() -> Self {
+        "A+=95,A=90,B=80,C=70,D=60,F=0"
+            .parse()
+            .expect("default grade scale is valid")
+    }
+}
+
+impl GradeScale {
+    /// 分数达到的最高等级；低于所有下限时取最低一级。
+    pub fn grade_for(&self, score: i32) -> &str {
+        self.grades
+            .iter()
+            .find(|(_, min)| score >= *min)
+            .or(self.grades.last())
+            .map(|(label, _)| label.as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for GradeScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .grades
+            .iter()
+            .map(|(label, min)| format!("{label}={min}"))
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+impl FromStr for GradeScale {
+    type Err = String;
+
+    /// 解析 `等级=最低分` 列表，顺序不限，同一下限或同名等级不可重复。
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let mut grades = value
+            .split(',')
+            .map(|part| {
+                let (label, min) = part
+                    .split_once('=')
+                    .ok_or_else(|| format!("无效的等级: {part}，格式为 等级=最低分"))?;
+                let label = label.trim();
+                if label.is_empty() {
+                    return Err(format!("等级名称不能为空: {part}"));
+                }
+                let min = min
+                    .trim()
+                    .parse::<i32>()
+                    .map_err(|_| format!("无效的最低分: {part}"))?;
+                Ok((label.to_string(), min))
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()?;
+        grades.sort_by_key(|(_, min)| std::cmp::Reverse(*min));
+        if grades.windows(2).any(|pair| pair[0].1 == pair[1].1) {
+            return Err("不同等级的最低分不能相同".to_string());
+        }
+        let mut labels: Vec<&str> = grades.iter().map(|(label, _)| label.as_str()).collect();
+        labels.sort_unstable();
+        if labels.windows(2).any(|pair| pair[0] == pair[1]) {
+            return Err("等级名称不能重复".to_string());
+        }
+        Ok(Self { grades })
+    }
 }
 
 impl ProfileConfig {
@@ -78,6 +282,7 @@ impl ProfileConfig {
                 lra_too_high: 18.0,
                 bitrate_low_kbps: 192,
                 bitrate_high_kbps: 256,
+                weights: ScoreWeights::default(),
             },
             ScoringProfile::Broadcast => Self {
                 target_lufs: -23.0,
@@ -96,6 +301,7 @@ impl ProfileConfig {
                 lra_too_high: 24.0,
                 bitrate_low_kbps: 192,
                 bitrate_high_kbps: 256,
+                weights: ScoreWeights::default(),
             },
             ScoringProfile::Archive => Self {
                 target_lufs: -18.0,
@@ -114,12 +320,233 @@ impl ProfileConfig {
                 lra_too_high: 24.0,
                 bitrate_low_kbps: 160,
                 bitrate_high_kbps: 256,
+                weights: ScoreWeights::default(),
+            },
+            // 古典录音通常不做响度最大化，动态范围远大于流行乐。
+            ScoringProfile::Classical => Self {
+                target_lufs: -20.0,
+                loudness_soft_range_low: -30.0,
+                loudness_soft_range_high: -14.0,
+                true_peak_warn: -1.0,
+                true_peak_critical: 0.0,
+                spectrum_fake_threshold: -85.0,
+                spectrum_processed_threshold: -80.0,
+                spectrum_good_threshold: -70.0,
+                lra_poor_max: 4.0,
+                lra_low_max: 6.0,
+                lra_excellent_min: 8.0,
+                lra_excellent_max: 22.0,
+                lra_acceptable_max: 28.0,
+                lra_too_high: 32.0,
+                bitrate_low_kbps: 192,
+                bitrate_high_kbps: 256,
+                weights: ScoreWeights::default(),
+            },
+            // 电子舞曲普遍母带更响、动态更窄，轻微过峰是常态。
+            ScoringProfile::Edm => Self {
+                target_lufs: -7.0,
+                loudness_soft_range_low: -10.0,
+                loudness_soft_range_high: -5.0,
+                true_peak_warn: 0.3,
+                true_peak_critical: 1.5,
+                spectrum_fake_threshold: -85.0,
+                spectrum_processed_threshold: -80.0,
+                spectrum_good_threshold: -70.0,
+                lra_poor_max: 2.0,
+                lra_low_max: 3.5,
+                lra_excellent_min: 4.0,
+                lra_excellent_max: 8.0,
+                lra_acceptable_max: 11.0,
+                lra_too_high: 15.0,
+                bitrate_low_kbps: 256,
+                bitrate_high_kbps: 320,
+                weights: ScoreWeights::default(),
+            },
+            // 语音内容高频能量天然较少，常以较低码率发布。
+            ScoringProfile::Podcast => Self {
+                target_lufs: -16.0,
+                loudness_soft_range_low: -19.0,
+                loudness_soft_range_high: -14.0,
+                true_peak_warn: -2.0,
+                true_peak_critical: -1.0,
+                spectrum_fake_threshold: -100.0,
+                spectrum_processed_threshold: -95.0,
+                spectrum_good_threshold: -85.0,
+                lra_poor_max: 1.5,
+                lra_low_max: 2.5,
+                lra_excellent_min: 3.0,
+                lra_excellent_max: 9.0,
+                lra_acceptable_max: 12.0,
+                lra_too_high: 16.0,
+                bitrate_low_kbps: 64,
+                bitrate_high_kbps: 128,
+                weights: ScoreWeights::default(),
+            },
+            // 参考有声书平台的常见交付要求（约 -20 LUFS、峰值不高于 -3 dB）。
+            ScoringProfile::Audiobook => Self {
+                target_lufs: -20.0,
+                loudness_soft_range_low: -23.0,
+                loudness_soft_range_high: -18.0,
+                true_peak_warn: -3.5,
+                true_peak_critical: -3.0,
+                spectrum_fake_threshold: -100.0,
+                spectrum_processed_threshold: -95.0,
+                spectrum_good_threshold: -85.0,
+                lra_poor_max: 1.5,
+                lra_low_max: 2.5,
+                lra_excellent_min: 3.0,
+                lra_excellent_max: 8.0,
+                lra_acceptable_max: 10.0,
+                lra_too_high: 14.0,
+                bitrate_low_kbps: 128,
+                bitrate_high_kbps: 192,
+                weights: ScoreWeights::default(),
+            },
+            // 黑胶转录的高频自然滚降，爆音会产生孤立的高峰值。
+            ScoringProfile::VinylRip => Self {
+                target_lufs: -14.0,
+                loudness_soft_range_low: -20.0,
+                loudness_soft_range_high: -9.0,
+                true_peak_warn: -0.5,
+                true_peak_critical: 0.0,
+                spectrum_fake_threshold: -95.0,
+                spectrum_processed_threshold: -90.0,
+                spectrum_good_threshold: -78.0,
+                lra_poor_max: 3.0,
+                lra_low_max: 5.0,
+                lra_excellent_min: 6.0,
+                lra_excellent_max: 16.0,
+                lra_acceptable_max: 20.0,
+                lra_too_high: 24.0,
+                bitrate_low_kbps: 160,
+                bitrate_high_kbps: 256,
+                weights: ScoreWeights::default(),
             },
         }
     }
+
+    /// 阈值之间的大小关系不成立时评分会出现断层，加载自定义档案时提前拒绝。
+    fn validate(&self) -> std::result::Result<(), String> {
+        let ordered: [(&str, f64, f64); 6] = [
+            (
+                "loudness_soft_range_low <= loudness_soft_range_high",
+                self.loudness_soft_range_low,
+                self.loudness_soft_range_high,
+            ),
+            (
+                "true_peak_warn <= true_peak_critical",
+                self.true_peak_warn,
+                self.true_peak_critical,
+            ),
+            (
+                "spectrum_fake_threshold <= spectrum_processed_threshold",
+                self.spectrum_fake_threshold,
+                self.spectrum_processed_threshold,
+            ),
+            (
+                "spectrum_processed_threshold <= spectrum_good_threshold",
+                self.spectrum_processed_threshold,
+                self.spectrum_good_threshold,
+            ),
+            (
+                "lra_poor_max <= lra_low_max",
+                self.lra_poor_max,
+                self.lra_low_max,
+            ),
+            (
+                "bitrate_low_kbps <= bitrate_high_kbps",
+                f64::from(self.bitrate_low_kbps),
+                f64::from(self.bitrate_high_kbps),
+            ),
+        ];
+        if let Some((rule, _, _)) = ordered.iter().find(|(_, low, high)| low > high) {
+            return Err(format!("阈值不满足 {rule}"));
+        }
+        self.weights.validate()
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// 命令行对单项阈值的覆盖（`--target-lufs` 等），在档案（含自定义档案）之上生效。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdOverrides {
+    /// 目标综合响度；响度容差区间随之平移，保持与目标的相对位置。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_lufs: Option<f64>,
+    /// 真峰值上限，超过即为真峰值风险（覆盖 `true_peak_warn`）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub true_peak_max: Option<f64>,
+    /// 最低码率（覆盖 `bitrate_low_kbps`）；高于 `bitrate_high_kbps` 时一并提高后者。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_bitrate_kbps: Option<u32>,
+}
+
+impl ThresholdOverrides {
+    fn apply(&self, config: &mut ProfileConfig) {
+        if let Some(target) = self.target_lufs {
+            let shift = target - config.target_lufs;
+            config.target_lufs = target;
+            config.loudness_soft_range_low += shift;
+            config.loudness_soft_range_high += shift;
+        }
+        if let Some(max) = self.true_peak_max {
+            config.true_peak_warn = max;
+        }
+        if let Some(min) = self.min_bitrate_kbps {
+            config.bitrate_low_kbps = min;
+            config.bitrate_high_kbps = config.bitrate_high_kbps.max(min);
+        }
+    }
+}
+
+/// 从 `--profile-file` 加载的自定义评分档案（如录音棚的交付规范）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomProfile {
+    pub name: String,
+    /// 未覆盖的阈值与 elite 区间取自该内置档案。
+    pub base: ScoringProfile,
+    config: ProfileConfig,
+}
+
+/// 读取 TOML 档案文件。可选键 `name`（默认取文件名）与 `base`（默认 `default_base`），
+/// 其余键覆盖基础档案中的同名阈值，未知键视为错误以免拼写错误被静默忽略。
+pub fn load_profile_file(path: &Path, default_base: ScoringProfile) -> Result<CustomProfile> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("[E_PROFILE_FILE] 无法读取评分档案: {}", path.display()))?;
+    parse_profile(&text, path, default_base)
+}
+
+fn parse_profile(text: &str, path: &Path, default_base: ScoringProfile) -> Result<CustomProfile> {
+    let invalid =
+        |detail: String| anyhow!("[E_PROFILE_FILE] 评分档案无效 {}: {detail}", path.display());
+
+    let mut overrides: toml::Table = toml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    let name = match overrides.remove("name") {
+        Some(toml::Value::String(name)) if !name.trim().is_empty() => name.trim().to_string(),
+        Some(_) => return Err(invalid("name 必须是非空字符串".to_string())),
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "custom".to_string()),
+    };
+    let base = match overrides.remove("base") {
+        Some(toml::Value::String(base)) => base.parse::<ScoringProfile>().map_err(invalid)?,
+        Some(_) => return Err(invalid("base 必须是字符串".to_string())),
+        None => default_base,
+    };
+
+    let mut merged = toml::Table::try_from(ProfileConfig::from_profile(base))
+        .map_err(|e| invalid(e.to_string()))?;
+    merged.extend(overrides);
+    let config: ProfileConfig = merged
+        .try_into()
+        .map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+    config.validate().map_err(invalid)?;
+
+    Ok(CustomProfile { name, base, config })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum QualityStatus {
     #[serde(rename = "质量良好")]
     Good,
@@ -129,10 +556,18 @@ pub enum QualityStatus {
     Suspicious,
     #[serde(rename = "疑似处理")]
     Processed,
+    /// 二次有损：高码率有损文件的高频在远低于该码率应有的位置陡降，疑似由低码率有损文件重新编码。
+    #[serde(rename = "疑似二次有损")]
+    TranscodeChain,
+    #[serde(rename = "疑似升采样")]
+    Upsampled,
     #[serde(rename = "已削波")]
     Clipped,
     #[serde(rename = "真峰值风险")]
     TruePeakRisk,
+    /// 重度限幅：大部分时间贴近峰值天花板且峰值因数很小，区别于数字削波。
+    #[serde(rename = "重度限幅")]
+    LimitingHeavy,
     #[serde(rename = "响度偏离目标")]
     LoudnessOffTarget,
     #[serde(rename = "严重压缩")]
@@ -147,47 +582,517 @@ pub enum QualityStatus {
     Mono,
 }
 
+impl QualityStatus {
+    pub const ALL: [QualityStatus; 15] = [
+        QualityStatus::Good,
+        QualityStatus::Incomplete,
+        QualityStatus::Suspicious,
+        QualityStatus::Processed,
+        QualityStatus::TranscodeChain,
+        QualityStatus::Upsampled,
+        QualityStatus::Clipped,
+        QualityStatus::TruePeakRisk,
+        QualityStatus::LimitingHeavy,
+        QualityStatus::LoudnessOffTarget,
+        QualityStatus::SeverelyCompressed,
+        QualityStatus::LowDynamic,
+        QualityStatus::LowBitrate,
+        QualityStatus::LowSampleRate,
+        QualityStatus::Mono,
+    ];
+
+    /// 英文标识，用于命令行参数与播放列表文件名。
+    pub fn slug(&self) -> &'static str {
+        match self {
+            QualityStatus::Good => "good",
+            QualityStatus::Incomplete => "incomplete",
+            QualityStatus::Suspicious => "suspicious",
+            QualityStatus::Processed => "processed",
+            QualityStatus::TranscodeChain => "transcode_chain",
+            QualityStatus::Upsampled => "upsampled",
+            QualityStatus::Clipped => "clipped",
+            QualityStatus::TruePeakRisk => "true_peak_risk",
+            QualityStatus::LimitingHeavy => "limiting_heavy",
+            QualityStatus::LoudnessOffTarget => "loudness_off_target",
+            QualityStatus::SeverelyCompressed => "severely_compressed",
+            QualityStatus::LowDynamic => "low_dynamic",
+            QualityStatus::LowBitrate => "low_bitrate",
+            QualityStatus::LowSampleRate => "low_sample_rate",
+            QualityStatus::Mono => "mono",
+        }
+    }
+}
+
+/// 接受英文标识（`-` 与 `_` 等价，不区分大小写）或中文状态名。
+impl FromStr for QualityStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let key = s.trim().to_ascii_lowercase().replace('-', "_");
+        QualityStatus::ALL
+            .into_iter()
+            .find(|status| status.slug() == key || i18n::status_label(status, Lang::Zh) == s.trim())
+            .ok_or_else(|| {
+                let slugs: Vec<&str> = QualityStatus::ALL.iter().map(|st| st.slug()).collect();
+                format!("未知的状态 {s}，可选: {}", slugs.join("/"))
+            })
+    }
+}
+
 impl std::fmt::Display for QualityStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let status_str = match self {
-            QualityStatus::Good => "质量良好",
-            QualityStatus::Incomplete => "数据不完整",
-            QualityStatus::Suspicious => "可疑 (伪造)",
-            QualityStatus::Processed => "疑似处理",
-            QualityStatus::Clipped => "已削波",
-            QualityStatus::TruePeakRisk => "真峰值风险",
-            QualityStatus::LoudnessOffTarget => "响度偏离目标",
-            QualityStatus::SeverelyCompressed => "严重压缩",
-            QualityStatus::LowDynamic => "低动态",
-            QualityStatus::LowBitrate => "低码率",
-            QualityStatus::LowSampleRate => "低采样率",
-            QualityStatus::Mono => "单声道",
-        };
-        write!(f, "{status_str}")
+        f.write_str(i18n::status_label(self, Lang::Zh))
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct QualityAnalysis {
     #[serde(rename = "filePath")]
     pub file_path: String,
     #[serde(rename = "质量分")]
     pub quality_score: i32,
+    /// 按等级映射得到的字母等级（如 A+/B/F）。
+    #[serde(rename = "等级", default)]
+    pub grade: String,
+    /// 本批次内的百分位排名（0-100），分数高于或等于同批次多少比例的文件（同分各计一半）。
+    #[serde(rename = "percentile", default)]
+    pub percentile: f64,
     #[serde(rename = "状态")]
     pub status: QualityStatus,
+    /// 检测到的全部问题（按优先级排序，首项即 `status`）；质量良好时为空。
+    #[serde(rename = "全部问题", default)]
+    pub issues: Vec<QualityStatus>,
     #[serde(rename = "备注")]
     pub notes: String,
     #[serde(rename = "profile")]
     pub profile: String,
+    /// 产生该结果的评分规则版本（见 `SCORER_VERSION`）。
+    #[serde(rename = "scorerVersion", default = "legacy_scorer_version")]
+    pub scorer_version: u32,
     #[serde(rename = "confidence")]
     pub confidence: f64,
+    /// 每个检测结论（无问题时为“质量良好”）的置信度，只计入与该结论相关的测量不一致。
+    #[serde(rename = "statusConfidence", default)]
+    pub status_confidence: Vec<StatusConfidence>,
+    /// ReplayGain 2.0 单曲增益（dB，参考 -18 LUFS）。
+    #[serde(rename = "trackGainDb", default)]
+    pub track_gain_db: Option<f64>,
+    /// ReplayGain 2.0 专辑增益（dB），仅在启用专辑响度计算时填写。
+    #[serde(
+        rename = "albumGainDb",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub album_gain_db: Option<f64>,
+    /// 广播合规判定（PASS/FAIL 与未满足的条款），仅在启用 `--compliance` 时填写。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ComplianceResult>,
+    /// 与 `--baseline` 指定的上次运行相比的分数与状态变化；基线中没有该曲目时为空。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineDelta>,
+    /// Hi-Res 判定：综合声明的采样率/位深、实测位深与超声频段内容，不影响质量分。
+    #[serde(rename = "hiRes", default)]
+    pub hi_res: HiResAssessment,
+    /// 质量分构成，解释分数从何而来。
+    #[serde(rename = "scoreBreakdown", default)]
+    pub score_breakdown: ScoreBreakdown,
     #[serde(flatten)]
     pub metrics: FileMetrics,
 }
 
+/// 单个检测结论的置信度。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StatusConfidence {
+    pub status: QualityStatus,
+    pub confidence: f64,
+}
+
+/// 两个本应相互印证的指标之间的矛盾，会降低依赖这些指标的结论的置信度。
+#[derive(Debug, Clone, PartialEq)]
+struct MeasurementDisagreement {
+    reason: String,
+    penalty: f64,
+    /// 依赖这些指标的状态；“质量良好”依赖全部指标，总会受影响。
+    affects: &'static [QualityStatus],
+}
+
+/// 一项附加扣分。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScorePenalty {
+    pub reason: String,
+    pub points: f64,
+}
+
+/// 质量分的构成：各维度得分、附加扣分、状态上限与 elite gate 压缩，用于解释最终分数。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreBreakdown {
+    pub compliance: f64,
+    pub dynamics: f64,
+    pub spectrum: f64,
+    pub authenticity: f64,
+    pub integrity: f64,
+    pub penalties: Vec<ScorePenalty>,
+    /// 状态上限实际压低了分数时记录该上限（如削波上限 85）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_cap: Option<f64>,
+    /// 未通过 elite gate 时连续压缩扣掉的分数。
+    pub elite_compression: f64,
+    pub final_score: i32,
+}
+
+impl ScoreBreakdown {
+    fn dimension_total(&self) -> f64 {
+        self.compliance + self.dynamics + self.spectrum + self.authenticity + self.integrity
+    }
+
+    /// 输出保留两位小数，避免报告中出现浮点噪声。
+    fn round_for_output(&mut self) {
+        let round = |value: f64| (value * 100.0).round() / 100.0;
+        self.compliance = round(self.compliance);
+        self.dynamics = round(self.dynamics);
+        self.spectrum = round(self.spectrum);
+        self.authenticity = round(self.authenticity);
+        self.integrity = round(self.integrity);
+        self.elite_compression = round(self.elite_compression);
+    }
+
+    /// CSV 中的简要说明，例如 `合规 30.5 + 动态 18 + 频谱 25 + 真实性 10 + 完整性 10; 低码率 -12; 状态上限 85; elite压缩 -3.2`。
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!(
+            "合规 {} + 动态 {} + 频谱 {} + 真实性 {} + 完整性 {}",
+            format_points(self.compliance),
+            format_points(self.dynamics),
+            format_points(self.spectrum),
+            format_points(self.authenticity),
+            format_points(self.integrity)
+        )];
+        for penalty in &self.penalties {
+            parts.push(format!(
+                "{} -{}",
+                penalty.reason,
+                format_points(penalty.points)
+            ));
+        }
+        if let Some(cap) = self.status_cap {
+            parts.push(format!("状态上限 {}", format_points(cap)));
+        }
+        if self.elite_compression > 0.0 {
+            parts.push(format!(
+                "elite压缩 -{}",
+                format_points(self.elite_compression)
+            ));
+        }
+        parts.join("; ")
+    }
+}
+
+fn format_points(value: f64) -> String {
+    let text = format!("{value:.1}");
+    text.strip_suffix(".0").map(str::to_string).unwrap_or(text)
+}
+
+/// 当前评分规则版本。规则变化会改变已有结果的状态或分数时递增，旧规则保留在 `--scorer-version` 中：
+/// - 1：不检测重度限幅与疑似二次有损；
+/// - 2：增加重度限幅（贴顶占比 + 峰值因数）与疑似二次有损（高码率有损文件的低频截止）。
+pub const SCORER_VERSION: u32 = 2;
+/// 仍可重放的最早评分规则版本。
+pub const OLDEST_SCORER_VERSION: u32 = 1;
+
+/// 未记录评分版本的旧结果产生于版本号引入之前，按版本 1 处理。
+fn legacy_scorer_version() -> u32 {
+    OLDEST_SCORER_VERSION
+}
+
+/// 相邻高频段之间能量陡降达到该值（dB）视为编码器低通截止。
+const TRANSCODE_CLIFF_DB: f64 = 25.0;
+/// 高码率有损文件的截止不应出现在该频点（Hz）及以下。
+const TRANSCODE_CLIFF_MAX_HZ: u32 = 18_000;
+
+/// 贴顶时间占比达到该值（%）且峰值因数不超过 `LIMITING_MAX_CREST_DB` 时判为重度限幅。
+const LIMITING_CEILING_PERCENT: f64 = 40.0;
+const LIMITING_MAX_CREST_DB: f64 = 10.0;
+
+/// 一条评分规则：检查一个文件，报告问题、附加扣分或备注。
+/// 内置检测按优先级排成规则列表，用户规则（如 `rules::ScriptRule`）追加在其后，无需修改评分代码。
+pub trait Rule: Send + Sync {
+    fn name(&self) -> &str;
+    fn evaluate(&self, ctx: &RuleContext<'_>) -> RuleOutcome;
+}
+
+/// 规则可读取的上下文。
+pub struct RuleContext<'a> {
+    pub metrics: &'a FileMetrics,
+    /// 排在前面的规则已发现的问题（按优先级）。
+    pub issues: &'a [QualityStatus],
+    pub profile: &'a str,
+    scorer: &'a QualityScorer,
+}
+
+/// 单条规则的结果；全部为空表示未命中。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleOutcome {
+    pub issue: Option<QualityStatus>,
+    pub penalty: Option<ScorePenalty>,
+    pub note: Option<String>,
+}
+
+impl RuleOutcome {
+    fn issue(issue: QualityStatus) -> Self {
+        Self {
+            issue: Some(issue),
+            ..Self::default()
+        }
+    }
+
+    fn with_penalty(mut self, reason: &str, points: f64) -> Self {
+        self.penalty = Some(ScorePenalty {
+            reason: reason.to_string(),
+            points,
+        });
+        self
+    }
+}
+
+/// 全部规则的汇总结果。
+#[derive(Debug, Default)]
+struct RuleEvaluation {
+    /// 按优先级排列的问题，首项为主状态；同一维度只取最严重的一项。
+    issues: Vec<QualityStatus>,
+    penalties: Vec<ScorePenalty>,
+    notes: Vec<String>,
+}
+
+impl RuleEvaluation {
+    /// 主状态：第一个发现的问题，没有问题时为 `Good`。
+    fn status(&self) -> QualityStatus {
+        self.issues.first().cloned().unwrap_or(QualityStatus::Good)
+    }
+}
+
+struct BuiltinRule {
+    name: &'static str,
+    check: fn(&RuleContext<'_>) -> RuleOutcome,
+}
+
+impl Rule for BuiltinRule {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn evaluate(&self, ctx: &RuleContext<'_>) -> RuleOutcome {
+        (self.check)(ctx)
+    }
+}
+
+/// 内置规则，顺序即问题的优先级。
+const BUILTIN_RULES: [BuiltinRule; 10] = [
+    BuiltinRule {
+        name: "incomplete",
+        check: rule_incomplete,
+    },
+    BuiltinRule {
+        name: "spectrum",
+        check: rule_spectrum,
+    },
+    BuiltinRule {
+        name: "upsampled",
+        check: rule_upsampled,
+    },
+    BuiltinRule {
+        name: "peak",
+        check: rule_peak,
+    },
+    BuiltinRule {
+        name: "limiting",
+        check: rule_limiting,
+    },
+    BuiltinRule {
+        name: "loudness",
+        check: rule_loudness,
+    },
+    BuiltinRule {
+        name: "bitrate",
+        check: rule_bitrate,
+    },
+    BuiltinRule {
+        name: "sampleRate",
+        check: rule_sample_rate,
+    },
+    BuiltinRule {
+        name: "channels",
+        check: rule_channels,
+    },
+    BuiltinRule {
+        name: "dynamics",
+        check: rule_dynamics,
+    },
+];
+
+fn rule_incomplete(ctx: &RuleContext<'_>) -> RuleOutcome {
+    if ctx.scorer.count_missing_critical_fields(ctx.metrics) >= 2 {
+        RuleOutcome::issue(QualityStatus::Incomplete)
+    } else {
+        RuleOutcome::default()
+    }
+}
+
+fn rule_spectrum(ctx: &RuleContext<'_>) -> RuleOutcome {
+    let (scorer, metrics) = (ctx.scorer, ctx.metrics);
+    let threshold = scorer.config.spectrum_processed_threshold;
+    let mut outcome = RuleOutcome::default();
+    if let Some(rms_18k) = metrics.rms_db_above_18k {
+        if scorer.is_suspected_transcode(metrics) {
+            outcome.issue = Some(QualityStatus::Suspicious);
+        } else if scorer.transcode_chain_cliff(metrics).is_some() {
+            outcome.issue = Some(QualityStatus::TranscodeChain);
+        } else if rms_18k < threshold {
+            outcome.issue = Some(QualityStatus::Processed);
+        }
+    }
+    if scorer.is_lossy(metrics)
+        && matches!(metrics.bitrate_kbps, Some(bitrate) if bitrate > scorer.config.bitrate_high_kbps)
+        && matches!(metrics.rms_db_above_18k, Some(rms_18k) if rms_18k < threshold)
+    {
+        outcome = outcome.with_penalty("高码率但高频缺失", 8.0);
+    }
+    outcome
+}
+
+fn rule_upsampled(ctx: &RuleContext<'_>) -> RuleOutcome {
+    if ctx.scorer.is_upsampled(ctx.metrics) {
+        RuleOutcome::issue(QualityStatus::Upsampled)
+    } else {
+        RuleOutcome::default()
+    }
+}
+
+fn rule_peak(ctx: &RuleContext<'_>) -> RuleOutcome {
+    let config = &ctx.scorer.config;
+    match ctx.metrics.true_peak_dbtp {
+        Some(tp) if tp >= config.true_peak_critical => RuleOutcome::issue(QualityStatus::Clipped),
+        Some(tp) if tp >= config.true_peak_warn => RuleOutcome::issue(QualityStatus::TruePeakRisk),
+        Some(_) => RuleOutcome::default(),
+        None if matches!(ctx.metrics.peak_amplitude_db, Some(peak) if peak >= -0.1) => {
+            RuleOutcome::issue(QualityStatus::Clipped)
+        }
+        None => RuleOutcome::default(),
+    }
+}
+
+fn rule_limiting(ctx: &RuleContext<'_>) -> RuleOutcome {
+    if ctx.scorer.limiting_signature(ctx.metrics).is_none() {
+        return RuleOutcome::default();
+    }
+    // 已判为削波时不再重复报告限幅，但仍计入扣分。
+    let outcome = if ctx.issues.contains(&QualityStatus::Clipped) {
+        RuleOutcome::default()
+    } else {
+        RuleOutcome::issue(QualityStatus::LimitingHeavy)
+    };
+    outcome.with_penalty("重度限幅", 5.0)
+}
+
+fn rule_loudness(ctx: &RuleContext<'_>) -> RuleOutcome {
+    let config = &ctx.scorer.config;
+    match ctx.metrics.integrated_loudness_lufs {
+        Some(i_lufs)
+            if i_lufs < config.loudness_soft_range_low
+                || i_lufs > config.loudness_soft_range_high =>
+        {
+            RuleOutcome::issue(QualityStatus::LoudnessOffTarget)
+        }
+        _ => RuleOutcome::default(),
+    }
+}
+
+fn rule_bitrate(ctx: &RuleContext<'_>) -> RuleOutcome {
+    let scorer = ctx.scorer;
+    if scorer.is_lossy(ctx.metrics)
+        && matches!(ctx.metrics.bitrate_kbps, Some(bitrate) if bitrate < scorer.config.bitrate_low_kbps)
+    {
+        RuleOutcome::issue(QualityStatus::LowBitrate).with_penalty("低码率", 12.0)
+    } else {
+        RuleOutcome::default()
+    }
+}
+
+fn rule_sample_rate(ctx: &RuleContext<'_>) -> RuleOutcome {
+    if matches!(ctx.metrics.sample_rate_hz, Some(sr) if sr < 44_100) {
+        RuleOutcome::issue(QualityStatus::LowSampleRate).with_penalty("低采样率", 10.0)
+    } else {
+        RuleOutcome::default()
+    }
+}
+
+fn rule_channels(ctx: &RuleContext<'_>) -> RuleOutcome {
+    if matches!(ctx.metrics.channels, Some(ch) if ch < 2) {
+        RuleOutcome::issue(QualityStatus::Mono).with_penalty("单声道", 3.0)
+    } else {
+        RuleOutcome::default()
+    }
+}
+
+fn rule_dynamics(ctx: &RuleContext<'_>) -> RuleOutcome {
+    let config = &ctx.scorer.config;
+    match ctx.metrics.lra {
+        Some(lra) if lra < config.lra_poor_max => {
+            RuleOutcome::issue(QualityStatus::SeverelyCompressed)
+        }
+        Some(lra) if lra < config.lra_low_max => RuleOutcome::issue(QualityStatus::LowDynamic),
+        _ => RuleOutcome::default(),
+    }
+}
+
+/// 按扩展名、编码或容器判断是否为无损格式。
+pub fn is_lossless(metrics: &FileMetrics) -> bool {
+    let ext = Path::new(&metrics.file_path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let codec = metrics
+        .codec_name
+        .as_deref()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let container = metrics
+        .container_format
+        .as_deref()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let lossless_by_ext = matches!(ext.as_str(), "flac" | "alac" | "wav" | "aiff" | "aif");
+    let lossless_by_codec = codec.starts_with("pcm_")
+        || matches!(
+            codec.as_str(),
+            "flac" | "alac" | "wavpack" | "ape" | "truehd" | "mlp"
+        );
+    let lossless_by_container =
+        container.contains("flac") || container.contains("wav") || container.contains("aiff");
+
+    lossless_by_ext || lossless_by_codec || lossless_by_container
+}
+
 pub struct QualityScorer {
     profile: ScoringProfile,
+    profile_name: String,
     config: ProfileConfig,
+    grade_scale: GradeScale,
+    /// 训练得到的有损来源分类器；设置后取代固定的高频阈值判定“可疑 (伪造)”。
+    lossy_classifier: Option<LossyClassifier>,
+    /// 批量评分使用的线程池；未设置时使用 rayon 全局线程池。
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    threshold_overrides: ThresholdOverrides,
+    /// 使用的评分规则版本，默认 `SCORER_VERSION`。
+    scorer_version: u32,
+    /// 用户规则，在内置规则之后依次运行。
+    custom_rules: Vec<Box<dyn Rule>>,
+    /// 备注使用的语言。
+    lang: Lang,
 }
 
 impl QualityScorer {
@@ -198,41 +1103,220 @@ impl QualityScorer {
     pub fn with_profile(profile: ScoringProfile) -> Self {
         Self {
             profile,
+            profile_name: profile.as_str().to_string(),
             config: ProfileConfig::from_profile(profile),
+            grade_scale: GradeScale::default(),
+            lossy_classifier: None,
+            thread_pool: None,
+            threshold_overrides: ThresholdOverrides::default(),
+            scorer_version: SCORER_VERSION,
+            custom_rules: Vec::new(),
+            lang: Lang::default(),
         }
     }
 
+    pub fn with_custom_profile(custom: &CustomProfile) -> Self {
+        Self {
+            profile: custom.base,
+            profile_name: custom.name.clone(),
+            config: custom.config.clone(),
+            grade_scale: GradeScale::default(),
+            lossy_classifier: None,
+            thread_pool: None,
+            threshold_overrides: ThresholdOverrides::default(),
+            scorer_version: SCORER_VERSION,
+            custom_rules: Vec::new(),
+            lang: Lang::default(),
+        }
+    }
+
+    /// 当前评分档案的名称、实际生效的阈值与权重（含命令行覆盖），写入运行结果与数据集清单以便复现评分。
+    pub fn profile_snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.profile_name,
+            "base": self.profile.as_str(),
+            "scorerVersion": self.scorer_version,
+            "rules": self.custom_rules.iter().map(|rule| rule.name()).collect::<Vec<_>>(),
+            "thresholds": self.config,
+            "overrides": self.threshold_overrides,
+        })
+    }
+
+    /// 应用命令行阈值覆盖；覆盖后阈值顺序不成立（如真峰值上限高于削波阈值）时报错。
+    pub fn with_threshold_overrides(mut self, overrides: ThresholdOverrides) -> Result<Self> {
+        overrides.apply(&mut self.config);
+        self.config
+            .validate()
+            .map_err(|detail| anyhow!("[E_THRESHOLD_OVERRIDE] 阈值覆盖无效: {detail}"))?;
+        self.threshold_overrides = overrides;
+        Ok(self)
+    }
+
+    /// 按指定版本的评分规则评分，便于与旧版本产生的结果对比而不必整体重新评级。
+    pub fn with_scorer_version(mut self, version: u32) -> Result<Self> {
+        if !(OLDEST_SCORER_VERSION..=SCORER_VERSION).contains(&version) {
+            return Err(anyhow!(
+                "[E_SCORER_VERSION] 不支持的评分规则版本 {version}（可用 {OLDEST_SCORER_VERSION}-{SCORER_VERSION}）"
+            ));
+        }
+        self.scorer_version = version;
+        Ok(self)
+    }
+
+    /// 追加一条用户规则，在内置规则与先前追加的规则之后运行。
+    pub fn with_rule(mut self, rule: Box<dyn Rule>) -> Self {
+        self.custom_rules.push(rule);
+        self
+    }
+
+    /// 覆盖档案中的维度权重（调用方需先校验）。
+    pub fn with_weights(mut self, weights: ScoreWeights) -> Self {
+        self.config.weights = weights;
+        self
+    }
+
+    pub fn with_grade_scale(mut self, grade_scale: GradeScale) -> Self {
+        self.grade_scale = grade_scale;
+        self
+    }
+
+    /// 备注按指定语言生成（状态与字段名不受影响）。
+    pub fn with_lang(mut self, lang: Lang) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    pub fn with_lossy_classifier(mut self, classifier: LossyClassifier) -> Self {
+        self.lossy_classifier = Some(classifier);
+        self
+    }
+
+    /// 批量评分改在指定线程池中并行，避免与调用方自己的全局线程池任务争抢。
+    pub fn with_thread_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(pool);
+        self
+    }
+
     pub fn analyze_file(&self, metrics: &FileMetrics) -> QualityAnalysis {
-        let status = self.determine_status(metrics);
-        let notes = self.generate_notes(metrics, &status);
-        let quality_score = self.calculate_quality_score(metrics, &status);
-        let confidence = self.estimate_confidence(metrics);
+        self.analyze_owned(metrics.clone())
+    }
+
+    /// 与 `analyze_file` 相同，但直接移入指标，省去一次复制。
+    pub fn analyze_owned(&self, owned: FileMetrics) -> QualityAnalysis {
+        let metrics = &owned;
+        let evaluation = self.evaluate_rules(metrics);
+        let status = evaluation.status();
+        let mut notes = self.generate_notes(metrics, &status);
+        for note in &evaluation.notes {
+            notes.push_str(&format!(" | {note}"));
+        }
+        let score_breakdown = self.score_breakdown(metrics, &evaluation);
+        let issues = evaluation.issues;
+        let hi_res = hires::assess(metrics, &issues);
+        let quality_score = score_breakdown.final_score;
+        let disagreements = self.measurement_disagreements(metrics);
+        let base_confidence = self.estimate_confidence(metrics);
+        let confidence = (base_confidence - disagreements.iter().map(|d| d.penalty).sum::<f64>())
+            .clamp(0.1, 1.0);
+        let status_confidence = self.status_confidence(base_confidence, &issues, &disagreements);
+        if !disagreements.is_empty() {
+            let reasons: Vec<&str> = disagreements.iter().map(|d| d.reason.as_str()).collect();
+            let note = Note::MeasurementDisagreement(reasons.join("；"));
+            notes.push_str(&format!(" | {}", note.render(self.lang)));
+        }
 
         QualityAnalysis {
             file_path: metrics.file_path.clone(),
             quality_score,
+            grade: self.grade_scale.grade_for(quality_score).to_string(),
+            percentile: 100.0,
             status,
+            issues,
             notes,
-            profile: self.profile.as_str().to_string(),
+            profile: self.profile_name.clone(),
+            scorer_version: self.scorer_version,
             confidence,
-            metrics: metrics.clone(),
+            status_confidence,
+            track_gain_db: album::track_gain_db(metrics),
+            album_gain_db: None,
+            compliance: None,
+            baseline: None,
+            hi_res,
+            score_breakdown,
+            metrics: owned,
         }
     }
 
-    pub fn analyze_files(&self, metrics_list: &[FileMetrics]) -> Vec<QualityAnalysis> {
-        use rayon::prelude::*;
+    /// 逐条惰性评分，适合从数据库等来源流式读取大量指标而不先收集为 `Vec`。
+    /// 批次百分位需要整批结果，这里每条都按单独一批计算（`percentile` 为 100）。
+    pub fn analyze_iter<'a, I>(&'a self, metrics: I) -> impl Iterator<Item = QualityAnalysis> + 'a
+    where
+        I: IntoIterator<Item = &'a FileMetrics>,
+        I::IntoIter: 'a,
+    {
+        metrics.into_iter().map(|m| self.analyze_file(m))
+    }
 
-        if metrics_list.len() < 10 {
-            metrics_list.iter().map(|m| self.analyze_file(m)).collect()
+    pub fn analyze_files(&self, metrics_list: &[FileMetrics]) -> Vec<QualityAnalysis> {
+        let mut analyses: Vec<QualityAnalysis> = if metrics_list.len() < 10 {
+            self.analyze_iter(metrics_list).collect()
         } else {
+            self.in_pool(|| {
+                metrics_list
+                    .par_iter()
+                    .map(|m| self.analyze_file(m))
+                    .collect()
+            })
+        };
+        assign_percentiles(&mut analyses);
+        analyses
+    }
+
+    /// 与 `analyze_files` 相同，但直接移入指标，结果中不再复制一份原始指标。
+    pub fn analyze_files_owned(&self, metrics_list: Vec<FileMetrics>) -> Vec<QualityAnalysis> {
+        let mut analyses: Vec<QualityAnalysis> = self.in_pool(|| {
             metrics_list
-                .par_iter()
-                .map(|m| self.analyze_file(m))
+                .into_par_iter()
+                .map(|m| self.analyze_owned(m))
                 .collect()
+        });
+        assign_percentiles(&mut analyses);
+        analyses
+    }
+
+    fn in_pool<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) => pool.install(op),
+            None => op(),
         }
     }
 }
 
+/// 已测量的高频段电平，按截止频率升序（含旧结果中的 16k/18k/20k 固定字段）。
+fn hf_levels(metrics: &FileMetrics) -> Vec<(u32, f64)> {
+    let mut bands: Vec<u32> = metrics.hf_band_rms_db.keys().copied().collect();
+    bands.extend([16_000, 18_000, 20_000]);
+    bands.sort_unstable();
+    bands.dedup();
+    bands
+        .into_iter()
+        .filter_map(|freq| metrics.hf_band_rms(freq).map(|rms| (freq, rms)))
+        .collect()
+}
+
+/// 计算批次内百分位：(低于该分数的数量 + 同分数量 / 2) / 总数 × 100，保留一位小数。
+fn assign_percentiles(analyses: &mut [QualityAnalysis]) {
+    let mut scores: Vec<i32> = analyses.iter().map(|a| a.quality_score).collect();
+    scores.sort_unstable();
+    let total = scores.len() as f64;
+    for analysis in analyses {
+        let below = scores.partition_point(|&s| s < analysis.quality_score);
+        let equal = scores.partition_point(|&s| s <= analysis.quality_score) - below;
+        let percentile = (below as f64 + equal as f64 / 2.0) / total * 100.0;
+        analysis.percentile = (percentile * 10.0).round() / 10.0;
+    }
+}
+
 impl Default for QualityScorer {
     fn default() -> Self {
         Self::new()
@@ -240,64 +1324,40 @@ impl Default for QualityScorer {
 }
 
 impl QualityScorer {
-    fn determine_status(&self, metrics: &FileMetrics) -> QualityStatus {
-        let critical_fields_missing = self.count_missing_critical_fields(metrics);
-        if critical_fields_missing >= 2 {
-            return QualityStatus::Incomplete;
-        }
-
-        if let Some(rms_18k) = metrics.rms_db_above_18k {
-            if self.is_lossless(metrics) && rms_18k < self.config.spectrum_fake_threshold {
-                return QualityStatus::Suspicious;
-            }
-            if rms_18k < self.config.spectrum_processed_threshold {
-                return QualityStatus::Processed;
-            }
-        }
-
-        if let Some(tp) = metrics.true_peak_dbtp {
-            if tp >= self.config.true_peak_critical {
-                return QualityStatus::Clipped;
-            }
-            if tp >= self.config.true_peak_warn {
-                return QualityStatus::TruePeakRisk;
-            }
-        } else if matches!(metrics.peak_amplitude_db, Some(peak) if peak >= -0.1) {
-            return QualityStatus::Clipped;
-        }
-
-        if let Some(i_lufs) = metrics.integrated_loudness_lufs {
-            if i_lufs < self.config.loudness_soft_range_low
-                || i_lufs > self.config.loudness_soft_range_high
-            {
-                return QualityStatus::LoudnessOffTarget;
+    /// 依次运行内置规则与用户规则，收集问题、附加扣分与规则备注。
+    fn evaluate_rules(&self, metrics: &FileMetrics) -> RuleEvaluation {
+        let mut evaluation = RuleEvaluation::default();
+        let rules = BUILTIN_RULES
+            .iter()
+            .map(|rule| rule as &dyn Rule)
+            .chain(self.custom_rules.iter().map(|rule| rule.as_ref()));
+        for rule in rules {
+            let outcome = rule.evaluate(&RuleContext {
+                metrics,
+                issues: &evaluation.issues,
+                profile: &self.profile_name,
+                scorer: self,
+            });
+            if let Some(issue) = outcome.issue {
+                if !evaluation.issues.contains(&issue) {
+                    evaluation.issues.push(issue);
+                }
             }
+            evaluation.penalties.extend(outcome.penalty);
+            evaluation.notes.extend(outcome.note);
         }
+        evaluation
+    }
 
-        if self.is_lossy(metrics)
-            && matches!(metrics.bitrate_kbps, Some(bitrate) if bitrate < self.config.bitrate_low_kbps)
-        {
-            return QualityStatus::LowBitrate;
-        }
-
-        if matches!(metrics.sample_rate_hz, Some(sr) if sr < 44_100) {
-            return QualityStatus::LowSampleRate;
-        }
-
-        if matches!(metrics.channels, Some(ch) if ch < 2) {
-            return QualityStatus::Mono;
-        }
-
-        if let Some(lra) = metrics.lra {
-            if lra < self.config.lra_poor_max {
-                return QualityStatus::SeverelyCompressed;
-            }
-            if lra < self.config.lra_low_max {
-                return QualityStatus::LowDynamic;
-            }
+    /// 重度限幅特征：贴顶时间占比高且峰值因数被压平。返回 (贴顶占比 %, 峰值因数 dB)。
+    fn limiting_signature(&self, metrics: &FileMetrics) -> Option<(f64, f64)> {
+        if self.scorer_version < 2 {
+            return None;
         }
-
-        QualityStatus::Good
+        let percent = metrics.ceiling_time_percent?;
+        let crest = metrics.peak_amplitude_db? - metrics.overall_rms_db?;
+        (percent >= LIMITING_CEILING_PERCENT && crest <= LIMITING_MAX_CREST_DB)
+            .then_some((percent, crest))
     }
 
     fn count_missing_critical_fields(&self, metrics: &FileMetrics) -> i32 {
@@ -320,115 +1380,135 @@ impl QualityScorer {
     }
 
     fn generate_notes(&self, metrics: &FileMetrics, status: &QualityStatus) -> String {
-        let mut notes = Vec::new();
-        notes.push(format!("评分档案: {}", self.profile.as_str()));
+        let mut notes = vec![Note::Profile(&self.profile_name)];
 
         match status {
-            QualityStatus::Incomplete => {
-                notes.push("关键数据缺失，分析置信度较低。".to_string());
-            }
+            QualityStatus::Incomplete => notes.push(Note::Incomplete),
             QualityStatus::Suspicious => {
-                notes.push("无损容器下高频能量异常，疑似有损升频来源。".to_string());
+                notes.push(Note::SuspiciousSpectrum);
+                if let Some(p) = self
+                    .lossy_classifier
+                    .as_ref()
+                    .and_then(|classifier| classifier.transcode_probability(metrics))
+                {
+                    notes.push(Note::ClassifierProbability(p));
+                }
             }
-            QualityStatus::Processed => {
-                notes.push("高频能量偏低，可能存在软截止或后期处理。".to_string());
+            QualityStatus::Processed => notes.push(Note::ProcessedSpectrum),
+            QualityStatus::TranscodeChain => {
+                if let (Some(bitrate_kbps), Some((low, high, drop_db))) =
+                    (metrics.bitrate_kbps, self.transcode_chain_cliff(metrics))
+                {
+                    notes.push(Note::TranscodeChain {
+                        bitrate_kbps,
+                        low_khz: f64::from(low) / 1000.0,
+                        high_khz: f64::from(high) / 1000.0,
+                        drop_db,
+                    });
+                }
             }
-            QualityStatus::Clipped => {
-                if let Some(tp) = metrics.true_peak_dbtp {
-                    notes.push(format!("真峰值过高 (TP: {tp:.2} dBTP)，存在削波风险。"));
-                } else {
-                    notes.push("峰值过高，存在削波风险。".to_string());
+            QualityStatus::Upsampled => {
+                if let (Some(sample_rate_hz), Some(rms_db)) = (
+                    metrics.sample_rate_hz,
+                    metrics.hf_band_rms(UPSAMPLE_CHECK_HZ),
+                ) {
+                    notes.push(Note::Upsampled {
+                        sample_rate_hz,
+                        check_khz: UPSAMPLE_CHECK_HZ / 1000,
+                        rms_db,
+                    });
                 }
             }
+            QualityStatus::Clipped => notes.push(Note::Clipped(metrics.true_peak_dbtp)),
             QualityStatus::TruePeakRisk => {
-                if let Some(tp) = metrics.true_peak_dbtp {
-                    notes.push(format!("真峰值接近阈值 (TP: {tp:.2} dBTP)。"));
+                notes.extend(metrics.true_peak_dbtp.map(Note::TruePeakNear));
+            }
+            QualityStatus::LimitingHeavy => {
+                if let Some((percent, crest_db)) = self.limiting_signature(metrics) {
+                    notes.push(Note::LimitingHeavy { percent, crest_db });
                 }
             }
             QualityStatus::LoudnessOffTarget => {
-                if let Some(i) = metrics.integrated_loudness_lufs {
-                    notes.push(format!(
-                        "综合响度偏离目标 (I: {i:.1} LUFS, target: {:.1} LUFS)。",
-                        self.config.target_lufs
-                    ));
+                if let Some(integrated_lufs) = metrics.integrated_loudness_lufs {
+                    notes.push(Note::LoudnessOffTarget {
+                        integrated_lufs,
+                        target_lufs: self.config.target_lufs,
+                    });
                 }
             }
             QualityStatus::SeverelyCompressed => {
-                if let Some(lra) = metrics.lra {
-                    notes.push(format!("动态范围极低 (LRA: {lra:.1} LU)。"));
-                }
-            }
-            QualityStatus::LowDynamic => {
-                if let Some(lra) = metrics.lra {
-                    notes.push(format!("动态范围偏低 (LRA: {lra:.1} LU)。"));
-                }
-            }
-            QualityStatus::LowBitrate => {
-                if let Some(bitrate) = metrics.bitrate_kbps {
-                    notes.push(format!("有损码率偏低 ({bitrate} kbps)。"));
-                }
+                notes.extend(metrics.lra.map(Note::SeverelyCompressed));
             }
+            QualityStatus::LowDynamic => notes.extend(metrics.lra.map(Note::LowDynamic)),
+            QualityStatus::LowBitrate => notes.extend(metrics.bitrate_kbps.map(Note::LowBitrate)),
             QualityStatus::LowSampleRate => {
-                if let Some(sr) = metrics.sample_rate_hz {
-                    notes.push(format!("采样率偏低 ({sr} Hz)。"));
-                }
-            }
-            QualityStatus::Mono => {
-                notes.push("当前文件为单声道。".to_string());
-            }
-            QualityStatus::Good => {
-                notes.push("关键技术指标在目标范围内。".to_string());
+                notes.extend(metrics.sample_rate_hz.map(Note::LowSampleRate));
             }
+            QualityStatus::Mono => notes.push(Note::Mono),
+            QualityStatus::Good => notes.push(Note::Good),
         }
 
-        notes.join(" | ")
-    }
-
-    fn calculate_quality_score(&self, metrics: &FileMetrics, status: &QualityStatus) -> i32 {
-        let compliance_score = self.calculate_compliance_score(metrics); // 35
-        let dynamics_score = self.calculate_dynamics_score(metrics); // 20
-        let spectrum_score = self.calculate_spectrum_score(metrics); // 25
-        let authenticity_score = self.calculate_authenticity_score(metrics); // 10
-        let integrity_score = self.calculate_integrity_score(metrics); // 10
-
-        let mut total_score = compliance_score
-            + dynamics_score
-            + spectrum_score
-            + authenticity_score
-            + integrity_score;
-
-        if self.is_lossy(metrics)
-            && matches!(metrics.bitrate_kbps, Some(bitrate) if bitrate < self.config.bitrate_low_kbps)
-        {
-            total_score -= 12.0;
+        if let Some(count) = metrics.audio_stream_count.filter(|&c| c > 1) {
+            notes.push(Note::MultipleStreams {
+                count,
+                current: metrics.audio_stream_index + 1,
+            });
         }
 
-        if self.is_lossy(metrics)
-            && matches!(metrics.bitrate_kbps, Some(bitrate) if bitrate > self.config.bitrate_high_kbps)
-            && matches!(metrics.rms_db_above_18k, Some(rms_18k) if rms_18k < self.config.spectrum_processed_threshold)
-        {
-            total_score -= 8.0;
+        let mut rendered: Vec<String> = notes.iter().map(|note| note.render(self.lang)).collect();
+        if let Some(segments) = &metrics.segment_analysis {
+            rendered.extend(segments.problem_notes());
         }
 
-        if matches!(metrics.sample_rate_hz, Some(sr) if sr < 44_100) {
-            total_score -= 10.0;
-        }
-        if matches!(metrics.channels, Some(ch) if ch < 2) {
-            total_score -= 3.0;
-        }
+        rendered.join(" | ")
+    }
 
-        match status {
-            QualityStatus::Suspicious => total_score = total_score.min(25.0),
-            QualityStatus::Incomplete => total_score = total_score.min(45.0),
-            QualityStatus::Clipped => total_score = total_score.min(85.0),
-            QualityStatus::TruePeakRisk => total_score = total_score.min(92.0),
-            _ => {}
+    fn score_breakdown(
+        &self,
+        metrics: &FileMetrics,
+        evaluation: &RuleEvaluation,
+    ) -> ScoreBreakdown {
+        // 各维度按默认满分计算，再按配置的权重换算。
+        let defaults = ScoreWeights::default();
+        let weights = self.config.weights;
+        let mut breakdown = ScoreBreakdown {
+            compliance: self.calculate_compliance_score(metrics) / defaults.compliance
+                * weights.compliance,
+            dynamics: self.calculate_dynamics_score(metrics) / defaults.dynamics * weights.dynamics,
+            spectrum: self.calculate_spectrum_score(metrics) / defaults.spectrum * weights.spectrum,
+            authenticity: self.calculate_authenticity_score(metrics) / defaults.authenticity
+                * weights.authenticity,
+            integrity: self.calculate_integrity_score(metrics) / defaults.integrity
+                * weights.integrity,
+            penalties: evaluation.penalties.clone(),
+            ..ScoreBreakdown::default()
+        };
+
+        let mut total_score =
+            breakdown.dimension_total() - breakdown.penalties.iter().map(|p| p.points).sum::<f64>();
+
+        let cap = match evaluation.status() {
+            QualityStatus::Suspicious => Some(25.0),
+            QualityStatus::Upsampled => Some(70.0),
+            QualityStatus::TranscodeChain => Some(70.0),
+            QualityStatus::Incomplete => Some(45.0),
+            QualityStatus::Clipped => Some(85.0),
+            QualityStatus::TruePeakRisk => Some(92.0),
+            _ => None,
+        };
+        if let Some(cap) = cap.filter(|cap| total_score > *cap) {
+            total_score = cap;
+            breakdown.status_cap = Some(cap);
         }
 
-        total_score = self.apply_continuous_scaling(total_score, metrics);
+        let scaled = self.apply_continuous_scaling(total_score, metrics);
+        breakdown.elite_compression = total_score - scaled;
 
         const HARD_MAX_SCORE: i32 = 99;
-        (total_score.clamp(0.0, HARD_MAX_SCORE as f64).round() as i32).clamp(0, HARD_MAX_SCORE)
+        breakdown.final_score =
+            (scaled.clamp(0.0, HARD_MAX_SCORE as f64).round() as i32).clamp(0, HARD_MAX_SCORE);
+        breakdown.round_for_output();
+        breakdown
     }
 
     fn apply_continuous_scaling(&self, raw_score: f64, metrics: &FileMetrics) -> f64 {
@@ -561,6 +1641,11 @@ impl QualityScorer {
             ScoringProfile::Pop => (-10.5, -7.5),
             ScoringProfile::Broadcast => (-24.0, -22.0),
             ScoringProfile::Archive => (-20.0, -12.0),
+            ScoringProfile::Classical => (-24.0, -16.0),
+            ScoringProfile::Edm => (-9.0, -6.0),
+            ScoringProfile::Podcast => (-17.0, -15.0),
+            ScoringProfile::Audiobook => (-21.0, -19.0),
+            ScoringProfile::VinylRip => (-18.0, -11.0),
         }
     }
 
@@ -569,6 +1654,11 @@ impl QualityScorer {
             ScoringProfile::Pop => -0.2,
             ScoringProfile::Broadcast => -1.0,
             ScoringProfile::Archive => -0.3,
+            ScoringProfile::Classical => -1.0,
+            ScoringProfile::Edm => 0.0,
+            ScoringProfile::Podcast => -1.5,
+            ScoringProfile::Audiobook => -3.5,
+            ScoringProfile::VinylRip => -0.5,
         }
     }
 
@@ -577,6 +1667,11 @@ impl QualityScorer {
             ScoringProfile::Pop => (4.5, 11.0),
             ScoringProfile::Broadcast => (6.0, 15.0),
             ScoringProfile::Archive => (4.0, 16.0),
+            ScoringProfile::Classical => (8.0, 22.0),
+            ScoringProfile::Edm => (3.5, 9.0),
+            ScoringProfile::Podcast => (3.0, 9.0),
+            ScoringProfile::Audiobook => (3.0, 8.0),
+            ScoringProfile::VinylRip => (6.0, 16.0),
         }
     }
 
@@ -734,9 +1829,7 @@ impl QualityScorer {
 
     fn calculate_authenticity_score(&self, metrics: &FileMetrics) -> f64 {
         let mut score: f64 = 10.0;
-        if self.is_lossless(metrics)
-            && matches!(metrics.rms_db_above_18k, Some(v) if v < self.config.spectrum_fake_threshold)
-        {
+        if self.is_suspected_transcode(metrics) {
             score = 0.0;
         } else if matches!(metrics.rms_db_above_18k, Some(v) if v < self.config.spectrum_processed_threshold)
         {
@@ -756,8 +1849,9 @@ impl QualityScorer {
     fn calculate_integrity_score(&self, metrics: &FileMetrics) -> f64 {
         let missing = self.count_missing_critical_fields(metrics) as f64;
         let mut score = (10.0 - missing * 3.0).max(0.0);
-        if !metrics.error_codes.is_empty() {
-            score = (score - 2.0_f64.min(metrics.error_codes.len() as f64)).max(0.0);
+        let (hard_errors, _) = self.count_error_codes(metrics);
+        if hard_errors > 0 {
+            score = (score - 2.0_f64.min(hard_errors as f64)).max(0.0);
         }
         score
     }
@@ -765,12 +1859,140 @@ impl QualityScorer {
     fn estimate_confidence(&self, metrics: &FileMetrics) -> f64 {
         let missing = self.count_missing_critical_fields(metrics) as f64;
         let mut confidence = 1.0 - missing * 0.18;
-        if !metrics.error_codes.is_empty() {
-            confidence -= 0.08 * metrics.error_codes.len() as f64;
+        let (hard_errors, fallbacks) = self.count_error_codes(metrics);
+        confidence -= 0.08 * hard_errors as f64;
+        // 降级链路（下混）测得的数据仍可用，但与原始声道布局存在偏差。
+        confidence -= 0.04 * fallbacks as f64;
+        // 快速采样只覆盖部分时长，LRA 等全曲指标存在估算误差。
+        if metrics.sampled {
+            confidence -= 0.1;
         }
         confidence.clamp(0.1, 1.0)
     }
 
+    /// 检查相互关联的指标是否一致：真峰值与采样峰值、LRA 与峰值因数、综合响度与 RMS、各高频段能量的单调性。
+    fn measurement_disagreements(&self, metrics: &FileMetrics) -> Vec<MeasurementDisagreement> {
+        let mut disagreements = Vec::new();
+        let mut flag = |reason: String, affects: &'static [QualityStatus]| {
+            disagreements.push(MeasurementDisagreement {
+                reason,
+                penalty: 0.1,
+                affects,
+            });
+        };
+
+        const PEAK_STATUSES: &[QualityStatus] =
+            &[QualityStatus::Clipped, QualityStatus::TruePeakRisk];
+        if let (Some(tp), Some(peak)) = (metrics.true_peak_dbtp, metrics.peak_amplitude_db) {
+            // 真峰值包含样本间过冲，不应低于采样峰值，通常也只高出 1-2 dB。
+            if tp < peak - 0.5 {
+                flag(
+                    format!("真峰值 ({tp:.1} dBTP) 低于采样峰值 ({peak:.1} dB)"),
+                    PEAK_STATUSES,
+                );
+            } else if tp - peak > 3.0 {
+                flag(
+                    format!(
+                        "真峰值比采样峰值高出 {:.1} dB，超出常见的样本间过冲范围",
+                        tp - peak
+                    ),
+                    PEAK_STATUSES,
+                );
+            }
+        }
+
+        const DYNAMICS_STATUSES: &[QualityStatus] = &[
+            QualityStatus::LimitingHeavy,
+            QualityStatus::SeverelyCompressed,
+            QualityStatus::LowDynamic,
+        ];
+        if let (Some(lra), Some(peak), Some(rms)) = (
+            metrics.lra,
+            metrics.peak_amplitude_db,
+            metrics.overall_rms_db,
+        ) {
+            let crest = peak - rms;
+            if (lra > 15.0 && crest < 8.0) || (lra < 2.0 && crest > 20.0) {
+                flag(
+                    format!("LRA ({lra:.1} LU) 与峰值因数 ({crest:.1} dB) 不匹配"),
+                    DYNAMICS_STATUSES,
+                );
+            }
+        }
+
+        if let (Some(lufs), Some(rms)) = (metrics.integrated_loudness_lufs, metrics.overall_rms_db)
+        {
+            if (lufs - rms).abs() > 12.0 {
+                flag(
+                    format!("综合响度 ({lufs:.1} LUFS) 与整体 RMS ({rms:.1} dB) 相差过大"),
+                    &[QualityStatus::LoudnessOffTarget],
+                );
+            }
+        }
+
+        // 高通截止频率越高，剩余能量应越少。
+        let levels = hf_levels(metrics);
+        if let Some(pair) = levels.windows(2).find(|pair| pair[1].1 > pair[0].1 + 1.0) {
+            flag(
+                format!(
+                    "{} Hz 以上能量 ({:.1} dB) 高于 {} Hz 以上能量 ({:.1} dB)",
+                    pair[1].0, pair[1].1, pair[0].0, pair[0].1
+                ),
+                &[
+                    QualityStatus::Suspicious,
+                    QualityStatus::TranscodeChain,
+                    QualityStatus::Processed,
+                    QualityStatus::Upsampled,
+                ],
+            );
+        }
+
+        disagreements
+    }
+
+    /// 每个检测结论的置信度：基础置信度减去与该结论相关的测量不一致。
+    fn status_confidence(
+        &self,
+        base_confidence: f64,
+        issues: &[QualityStatus],
+        disagreements: &[MeasurementDisagreement],
+    ) -> Vec<StatusConfidence> {
+        let statuses = if issues.is_empty() {
+            vec![QualityStatus::Good]
+        } else {
+            issues.to_vec()
+        };
+        statuses
+            .into_iter()
+            .map(|status| {
+                let penalty: f64 = disagreements
+                    .iter()
+                    .filter(|d| status == QualityStatus::Good || d.affects.contains(&status))
+                    .map(|d| d.penalty)
+                    .sum();
+                StatusConfidence {
+                    confidence: (base_confidence - penalty).clamp(0.1, 1.0),
+                    status,
+                }
+            })
+            .collect()
+    }
+
+    /// 返回 (失败错误码数, 降级链路警告数)；改用软件解码的警告两者都不计。
+    fn count_error_codes(&self, metrics: &FileMetrics) -> (usize, usize) {
+        let fallbacks = metrics
+            .error_codes
+            .iter()
+            .filter(|code| code.starts_with(FALLBACK_CODE_PREFIX))
+            .count();
+        let software = metrics
+            .error_codes
+            .iter()
+            .filter(|code| code.starts_with(SOFTWARE_DECODE_CODE_PREFIX))
+            .count();
+        (metrics.error_codes.len() - fallbacks - software, fallbacks)
+    }
+
     fn map_to_score(
         &self,
         value: f64,
@@ -787,36 +2009,51 @@ impl QualityScorer {
         out_min + (clamped_value - in_min) * (out_max - out_min) / (in_max - in_min)
     }
 
-    fn is_lossless(&self, metrics: &FileMetrics) -> bool {
-        let ext = Path::new(&metrics.file_path)
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or_default()
-            .to_ascii_lowercase();
-
-        let codec = metrics
-            .codec_name
-            .as_deref()
-            .unwrap_or_default()
-            .to_ascii_lowercase();
-
-        let container = metrics
-            .container_format
-            .as_deref()
-            .unwrap_or_default()
-            .to_ascii_lowercase();
+    /// 无损容器中的有损来源：有分类器且特征齐全时按分类器判定，否则按 18 kHz 以上的固定阈值。
+    fn is_suspected_transcode(&self, metrics: &FileMetrics) -> bool {
+        if !is_lossless(metrics) {
+            return false;
+        }
+        self.lossy_classifier
+            .as_ref()
+            .and_then(|classifier| classifier.is_transcode(metrics))
+            .unwrap_or(
+                matches!(metrics.rms_db_above_18k, Some(v) if v < self.config.spectrum_fake_threshold),
+            )
+    }
 
-        let lossless_by_ext = matches!(ext.as_str(), "flac" | "alac" | "wav" | "aiff" | "aif");
-        let lossless_by_codec = codec.starts_with("pcm_")
-            || matches!(codec.as_str(), "flac" | "alac" | "wavpack" | "ape");
-        let lossless_by_container =
-            container.contains("flac") || container.contains("wav") || container.contains("aiff");
+    /// 二次有损特征：码率不低于 `bitrate_high_kbps` 的有损文件，在 `TRANSCODE_CLIFF_MAX_HZ` 及以下
+    /// 的相邻频段之间能量陡降超过 `TRANSCODE_CLIFF_DB`，且陡降前仍有正常内容。
+    /// 高码率编码器的低通通常在 19–20 kHz，16 kHz 左右的截止是 128 kbps 级别来源的特征，
+    /// 由 14k 参照频段与 16k 之间的陡降识别（旧缓存没有 14k 时只能比较 16k/18k）。
+    /// 返回 (下侧频点, 上侧频点, 陡降 dB)。
+    fn transcode_chain_cliff(&self, metrics: &FileMetrics) -> Option<(u32, u32, f64)> {
+        if self.scorer_version < 2
+            || !self.is_lossy(metrics)
+            || !matches!(metrics.bitrate_kbps, Some(b) if b >= self.config.bitrate_high_kbps)
+        {
+            return None;
+        }
+        hf_levels(metrics)
+            .windows(2)
+            .filter(|pair| pair[1].0 <= TRANSCODE_CLIFF_MAX_HZ)
+            .find(|pair| {
+                pair[0].1 >= self.config.spectrum_processed_threshold
+                    && pair[0].1 - pair[1].1 >= TRANSCODE_CLIFF_DB
+            })
+            .map(|pair| (pair[0].0, pair[1].0, pair[0].1 - pair[1].1))
+    }
 
-        lossless_by_ext || lossless_by_codec || lossless_by_container
+    /// 声明为高采样率、但超声频段几乎没有能量（与有损转码检测相互独立）。
+    fn is_upsampled(&self, metrics: &FileMetrics) -> bool {
+        ffmpeg::needs_upsample_check(metrics.sample_rate_hz)
+            && metrics
+                .hf_band_rms(UPSAMPLE_CHECK_HZ)
+                .is_some_and(|rms| rms < UPSAMPLED_HF_THRESHOLD_DB)
     }
 
     fn is_lossy(&self, metrics: &FileMetrics) -> bool {
-        if self.is_lossless(metrics) {
+        if is_lossless(metrics) {
             return false;
         }
 
@@ -834,7 +2071,7 @@ impl QualityScorer {
         let lossy_by_ext = matches!(ext.as_str(), "mp3" | "aac" | "m4a" | "ogg" | "opus" | "wma");
         let lossy_by_codec = matches!(
             codec.as_str(),
-            "mp3" | "aac" | "vorbis" | "opus" | "wmav2" | "mp2" | "ac3"
+            "mp3" | "aac" | "vorbis" | "opus" | "wmav2" | "mp2" | "ac3" | "eac3" | "dts"
         );
 
         lossy_by_ext || lossy_by_codec
@@ -855,17 +2092,30 @@ mod tests {
             rms_db_above_16k: Some(-60.0),
             rms_db_above_18k: Some(-75.0),
             rms_db_above_20k: Some(-85.0),
+            hf_band_rms_db: Default::default(),
             integrated_loudness_lufs: Some(-9.5),
             true_peak_dbtp: Some(-1.2),
+            ceiling_time_percent: None,
             processing_time_ms: 1000,
             sample_rate_hz: Some(44_100),
+            bit_depth: Some(16),
+            effective_bit_depth: Some(16),
             bitrate_kbps: Some(900),
             channels: Some(2),
             codec_name: Some("flac".to_string()),
             container_format: Some("flac".to_string()),
             duration_seconds: Some(60.0),
             cache_hit: false,
+            sampled: false,
+            extra_of_album: None,
+            cue_track: None,
+            segment_analysis: None,
+            edge_levels: None,
+            audio_stream_index: 0,
+            audio_stream_count: Some(1),
             content_sha256: Some("abc".to_string()),
+            pcm_md5: None,
+            ffmpeg_version: None,
             error_codes: vec![],
         }
     }
@@ -881,6 +2131,382 @@ mod tests {
             Some(ScoringProfile::Pop)
         );
         assert!(ScoringProfile::from_str("unknown").is_err());
+        for profile in ScoringProfile::ALL {
+            assert_eq!(ScoringProfile::from_str(profile.as_str()), Ok(profile));
+        }
+    }
+
+    #[test]
+    fn test_custom_profile_overrides_base_thresholds() {
+        let path = Path::new("/profiles/studio_delivery.toml");
+        let custom = parse_profile(
+            "base = \"broadcast\"\ntarget_lufs = -16\nbitrate_low_kbps = 256\nbitrate_high_kbps = 320\n",
+            path,
+            ScoringProfile::Pop,
+        )
+        .expect("valid profile");
+        assert_eq!(custom.name, "studio_delivery");
+        assert_eq!(custom.base, ScoringProfile::Broadcast);
+        assert_eq!(custom.config.target_lufs, -16.0);
+        assert_eq!(custom.config.bitrate_low_kbps, 256);
+        assert_eq!(
+            custom.config.lra_too_high,
+            ProfileConfig::from_profile(ScoringProfile::Broadcast).lra_too_high
+        );
+
+        let analysis =
+            QualityScorer::with_custom_profile(&custom).analyze_file(&create_test_metrics());
+        assert_eq!(analysis.profile, "studio_delivery");
+
+        let typo = parse_profile("target_lufs_typo = -16\n", path, ScoringProfile::Pop)
+            .expect_err("unknown key");
+        assert!(typo.to_string().contains("E_PROFILE_FILE"));
+        assert!(parse_profile("true_peak_warn = 2.0\n", path, ScoringProfile::Pop).is_err());
+    }
+
+    #[test]
+    fn test_classical_profile_accepts_quiet_wide_recordings() {
+        let mut metrics = create_test_metrics();
+        metrics.integrated_loudness_lufs = Some(-21.0);
+        metrics.lra = Some(16.0);
+
+        let pop = QualityScorer::new();
+        assert_eq!(
+            pop.evaluate_rules(&metrics).status(),
+            QualityStatus::LoudnessOffTarget
+        );
+
+        let classical = QualityScorer::with_profile(ScoringProfile::Classical);
+        assert_eq!(
+            classical.evaluate_rules(&metrics).status(),
+            QualityStatus::Good
+        );
+        assert!(
+            classical.analyze_file(&metrics).quality_score
+                > pop.analyze_file(&metrics).quality_score
+        );
+        assert_eq!(
+            ScoringProfile::from_str("spoken-word").ok(),
+            Some(ScoringProfile::Podcast)
+        );
+        assert_eq!(
+            ScoringProfile::from_str("vinyl-rip").ok(),
+            Some(ScoringProfile::VinylRip)
+        );
+    }
+
+    #[test]
+    fn test_all_issues_are_reported_with_primary_status_first() {
+        let scorer = QualityScorer::new();
+        let mut metrics = create_test_metrics();
+        metrics.file_path = "/music/live.mp3".to_string();
+        metrics.true_peak_dbtp = Some(1.5);
+        metrics.codec_name = Some("mp3".to_string());
+        metrics.container_format = Some("mp3".to_string());
+        metrics.bitrate_kbps = Some(128);
+        metrics.channels = Some(1);
+
+        let analysis = scorer.analyze_file(&metrics);
+        assert_eq!(analysis.status, QualityStatus::Clipped);
+        assert_eq!(
+            analysis.issues,
+            vec![
+                QualityStatus::Clipped,
+                QualityStatus::LowBitrate,
+                QualityStatus::Mono
+            ]
+        );
+        assert!(scorer
+            .analyze_file(&create_test_metrics())
+            .issues
+            .is_empty());
+    }
+
+    #[test]
+    fn test_score_weights_shift_emphasis() {
+        assert_eq!(
+            "35,20,25,10,10".parse::<ScoreWeights>(),
+            Ok(ScoreWeights::default())
+        );
+        assert!("35,20,25,10".parse::<ScoreWeights>().is_err());
+        assert!("40,20,25,10,10".parse::<ScoreWeights>().is_err());
+
+        // 响度严重偏离目标但其他维度良好：降低合规权重后分数提高。
+        let mut metrics = create_test_metrics();
+        metrics.integrated_loudness_lufs = Some(-20.0);
+        let default_score = QualityScorer::new().analyze_file(&metrics).quality_score;
+        let archival = "15,20,25,30,10".parse::<ScoreWeights>().expect("weights");
+        let archival_score = QualityScorer::new()
+            .with_weights(archival)
+            .analyze_file(&metrics)
+            .quality_score;
+        assert!(archival_score > default_score);
+
+        let path = Path::new("/profiles/archive.toml");
+        let custom = parse_profile(
+            "[weights]\ncompliance = 15\nauthenticity = 30\n",
+            path,
+            ScoringProfile::Pop,
+        )
+        .expect("weights table");
+        assert_eq!(custom.config.weights, archival);
+        assert!(parse_profile("[weights]\ncompliance = 50\n", path, ScoringProfile::Pop).is_err());
+    }
+
+    #[test]
+    fn test_score_breakdown_explains_final_score() {
+        let scorer = QualityScorer::new();
+        let mut metrics = create_test_metrics();
+        metrics.true_peak_dbtp = Some(1.5);
+        metrics.channels = Some(1);
+
+        let analysis = scorer.analyze_file(&metrics);
+        let breakdown = &analysis.score_breakdown;
+        assert_eq!(breakdown.final_score, analysis.quality_score);
+        assert_eq!(breakdown.penalties.len(), 1);
+        assert_eq!(breakdown.penalties[0].reason, "单声道");
+
+        let before_cap = breakdown.dimension_total() - 3.0;
+        let expected = breakdown.status_cap.unwrap_or(before_cap) - breakdown.elite_compression;
+        assert!((expected - f64::from(breakdown.final_score)).abs() <= 0.6);
+        assert!(breakdown.describe().contains("单声道 -3"));
+    }
+
+    #[test]
+    fn test_grade_scale() {
+        let scale = GradeScale::default();
+        assert_eq!(scale.grade_for(99), "A+");
+        assert_eq!(scale.grade_for(90), "A");
+        assert_eq!(scale.grade_for(59), "F");
+
+        let custom: GradeScale = "Pass=50, Excellent=85, Fail=0".parse().expect("custom");
+        assert_eq!(custom.to_string(), "Excellent=85,Pass=50,Fail=0");
+        assert_eq!(custom.grade_for(70), "Pass");
+        // 低于所有下限时取最低一级。
+        let strict: GradeScale = "A=90,B=80".parse().expect("strict");
+        assert_eq!(strict.grade_for(10), "B");
+
+        assert!("A=90,B=90".parse::<GradeScale>().is_err());
+        assert!("A=90,A=80".parse::<GradeScale>().is_err());
+        assert!("A90".parse::<GradeScale>().is_err());
+
+        let analysis = QualityScorer::new()
+            .with_grade_scale(custom.clone())
+            .analyze_file(&create_test_metrics());
+        assert_eq!(analysis.grade, custom.grade_for(analysis.quality_score));
+    }
+
+    #[test]
+    fn test_percentiles_within_batch() {
+        let scorer = QualityScorer::new();
+        let mut analyses: Vec<QualityAnalysis> = [90, 70, 70, 50]
+            .iter()
+            .map(|&score| QualityAnalysis {
+                quality_score: score,
+                ..scorer.analyze_file(&create_test_metrics())
+            })
+            .collect();
+        assign_percentiles(&mut analyses);
+        let percentiles: Vec<f64> = analyses.iter().map(|a| a.percentile).collect();
+        assert_eq!(percentiles, [87.5, 50.0, 50.0, 12.5]);
+    }
+
+    #[test]
+    fn test_measurement_disagreements_lower_related_confidence() {
+        let scorer = QualityScorer::new();
+        let clean = scorer.analyze_file(&create_test_metrics());
+        assert!(scorer
+            .measurement_disagreements(&create_test_metrics())
+            .is_empty());
+        assert_eq!(clean.status_confidence.len(), 1);
+        assert_eq!(clean.status_confidence[0].status, QualityStatus::Good);
+
+        // 真峰值高于阈值判为削波，但它比采样峰值低得多，削波结论不可靠。
+        let mut metrics = create_test_metrics();
+        metrics.true_peak_dbtp = Some(1.5);
+        metrics.peak_amplitude_db = Some(3.0);
+        metrics.lra = Some(3.0);
+        let analysis = scorer.analyze_file(&metrics);
+        assert!(analysis.issues.contains(&QualityStatus::Clipped));
+        assert!(analysis.notes.contains("真峰值 (1.5 dBTP) 低于采样峰值"));
+        assert!(analysis.confidence < clean.confidence);
+
+        let confidence_of = |status: QualityStatus| {
+            analysis
+                .status_confidence
+                .iter()
+                .find(|c| c.status == status)
+                .map(|c| c.confidence)
+        };
+        let clipped = confidence_of(QualityStatus::Clipped).expect("clipped");
+        let low_dynamic = confidence_of(QualityStatus::LowDynamic).expect("low dynamic");
+        assert!(clipped < low_dynamic);
+    }
+
+    #[test]
+    fn test_limiting_heavy_is_distinct_from_clipping() {
+        let scorer = QualityScorer::new();
+        let mut smashed = create_test_metrics();
+        smashed.ceiling_time_percent = Some(72.0);
+        smashed.peak_amplitude_db = Some(-1.0);
+        smashed.overall_rms_db = Some(-8.5);
+        let analysis = scorer.analyze_file(&smashed);
+        assert!(analysis.issues.contains(&QualityStatus::LimitingHeavy));
+        assert!(analysis.notes.contains("72.0% 的时间贴近峰值天花板"));
+        assert!(analysis
+            .score_breakdown
+            .penalties
+            .iter()
+            .any(|p| p.reason == "重度限幅"));
+
+        // 响亮但动态正常（峰值因数大）的母带不算限幅。
+        let mut loud_clean = smashed.clone();
+        loud_clean.overall_rms_db = Some(-16.0);
+        assert!(!scorer
+            .evaluate_rules(&loud_clean)
+            .issues
+            .contains(&QualityStatus::LimitingHeavy));
+
+        // 真正削波时只报告削波。
+        smashed.true_peak_dbtp = Some(1.5);
+        let issues = scorer.evaluate_rules(&smashed).issues;
+        assert!(issues.contains(&QualityStatus::Clipped));
+        assert!(!issues.contains(&QualityStatus::LimitingHeavy));
+    }
+
+    #[test]
+    fn test_batch_variants_match_analyze_files() {
+        let metrics: Vec<FileMetrics> = (0..12)
+            .map(|i| FileMetrics {
+                file_path: format!("/m/{i:02}.flac"),
+                integrated_loudness_lufs: Some(-20.0 + i as f64),
+                ..create_test_metrics()
+            })
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .expect("pool");
+        let scorer = QualityScorer::new().with_thread_pool(Arc::new(pool));
+
+        let expected = QualityScorer::new().analyze_files(&metrics);
+        let pooled = scorer.analyze_files(&metrics);
+        let owned = scorer.analyze_files_owned(metrics.clone());
+        let streamed: Vec<QualityAnalysis> = scorer.analyze_iter(metrics.iter()).collect();
+        for i in 0..metrics.len() {
+            assert_eq!(pooled[i].quality_score, expected[i].quality_score);
+            assert_eq!(owned[i].percentile, expected[i].percentile);
+            assert_eq!(owned[i].metrics.file_path, metrics[i].file_path);
+            assert_eq!(streamed[i].quality_score, expected[i].quality_score);
+            assert_eq!(streamed[i].percentile, 100.0);
+        }
+    }
+
+    #[test]
+    fn test_transcode_chain_from_low_cutoff_in_high_bitrate_mp3() {
+        let scorer = QualityScorer::new();
+        let mut chained = create_test_metrics();
+        chained.file_path = "/m/song.mp3".to_string();
+        chained.codec_name = Some("mp3".to_string());
+        chained.container_format = Some("mp3".to_string());
+        chained.bitrate_kbps = Some(320);
+        chained.rms_db_above_16k = Some(-62.0);
+        chained.rms_db_above_18k = Some(-98.0);
+        chained.rms_db_above_20k = Some(-110.0);
+        let analysis = scorer.analyze_file(&chained);
+        assert_eq!(analysis.status, QualityStatus::TranscodeChain);
+        assert!(!analysis.issues.contains(&QualityStatus::Processed));
+        assert!(analysis.notes.contains("16–18 kHz 之间陡降 36 dB"));
+        assert!(analysis.quality_score <= 70);
+
+        // 128 kbps 来源的截止落在 16 kHz：由 14k 参照频段识别。
+        let mut cut_at_16k = chained.clone();
+        cut_at_16k.hf_band_rms_db = [(14_000, -55.0)].into();
+        cut_at_16k.rms_db_above_16k = Some(-95.0);
+        cut_at_16k.rms_db_above_18k = Some(-105.0);
+        let analysis = scorer.analyze_file(&cut_at_16k);
+        assert_eq!(analysis.status, QualityStatus::TranscodeChain);
+        assert!(analysis.notes.contains("14–16 kHz 之间陡降 40 dB"));
+
+        // 低码率文件本身就会在 16 kHz 附近截止，不算二次有损。
+        chained.bitrate_kbps = Some(128);
+        assert_ne!(
+            scorer.evaluate_rules(&chained).status(),
+            QualityStatus::TranscodeChain
+        );
+
+        // 正常的 320 kbps 文件截止在 20 kHz 附近。
+        let mut clean = chained.clone();
+        clean.bitrate_kbps = Some(320);
+        clean.rms_db_above_18k = Some(-68.0);
+        assert!(!scorer
+            .evaluate_rules(&clean)
+            .issues
+            .contains(&QualityStatus::TranscodeChain));
+    }
+
+    #[test]
+    fn test_threshold_overrides_apply_and_are_recorded() {
+        let overrides = ThresholdOverrides {
+            target_lufs: Some(-14.0),
+            true_peak_max: Some(-1.0),
+            min_bitrate_kbps: Some(320),
+        };
+        let scorer = QualityScorer::new()
+            .with_threshold_overrides(overrides)
+            .expect("valid overrides");
+        assert_eq!(scorer.config.target_lufs, -14.0);
+        // pop 的容差区间 -13 ~ -6 随目标从 -9 平移到 -14。
+        assert_eq!(scorer.config.loudness_soft_range_low, -18.0);
+        assert_eq!(scorer.config.loudness_soft_range_high, -11.0);
+        assert_eq!(scorer.config.bitrate_high_kbps, 320);
+
+        let mut metrics = create_test_metrics();
+        metrics.true_peak_dbtp = Some(-0.5);
+        assert!(scorer
+            .evaluate_rules(&metrics)
+            .issues
+            .contains(&QualityStatus::TruePeakRisk));
+
+        let snapshot = scorer.profile_snapshot();
+        assert_eq!(snapshot["thresholds"]["target_lufs"], -14.0);
+        assert_eq!(snapshot["overrides"]["truePeakMax"], -1.0);
+
+        let err = QualityScorer::new()
+            .with_threshold_overrides(ThresholdOverrides {
+                true_peak_max: Some(3.0),
+                ..ThresholdOverrides::default()
+            })
+            .err()
+            .expect("warn above critical");
+        assert!(err.to_string().contains("E_THRESHOLD_OVERRIDE"));
+    }
+
+    #[test]
+    fn test_scorer_version_replays_older_rules() {
+        let mut smashed = create_test_metrics();
+        smashed.ceiling_time_percent = Some(72.0);
+        smashed.peak_amplitude_db = Some(-1.0);
+        smashed.overall_rms_db = Some(-8.5);
+
+        let current = QualityScorer::new().analyze_file(&smashed);
+        assert_eq!(current.scorer_version, SCORER_VERSION);
+        assert!(current.issues.contains(&QualityStatus::LimitingHeavy));
+
+        let legacy = QualityScorer::new()
+            .with_scorer_version(1)
+            .expect("version 1");
+        let replayed = legacy.analyze_file(&smashed);
+        assert_eq!(replayed.scorer_version, 1);
+        assert!(!replayed.issues.contains(&QualityStatus::LimitingHeavy));
+        assert!(replayed.quality_score > current.quality_score);
+        assert_eq!(legacy.profile_snapshot()["scorerVersion"], 1);
+
+        let err = QualityScorer::new()
+            .with_scorer_version(SCORER_VERSION + 1)
+            .err()
+            .expect("future version");
+        assert!(err.to_string().contains("E_SCORER_VERSION"));
     }
 
     #[test]
@@ -893,16 +2519,43 @@ mod tests {
     fn test_determine_status_good_quality() {
         let scorer = QualityScorer::new();
         let metrics = create_test_metrics();
-        let status = scorer.determine_status(&metrics);
+        let status = scorer.evaluate_rules(&metrics).status();
         assert_eq!(status, QualityStatus::Good);
     }
 
+    #[test]
+    fn test_determine_status_upsampled() {
+        let scorer = QualityScorer::new();
+        let mut metrics = create_test_metrics();
+        metrics.sample_rate_hz = Some(96_000);
+        metrics.hf_band_rms_db.insert(UPSAMPLE_CHECK_HZ, -138.0);
+        assert_eq!(
+            scorer.evaluate_rules(&metrics).status(),
+            QualityStatus::Upsampled
+        );
+        assert!(scorer.analyze_file(&metrics).quality_score <= 70);
+
+        metrics.hf_band_rms_db.insert(UPSAMPLE_CHECK_HZ, -104.0);
+        assert_eq!(
+            scorer.evaluate_rules(&metrics).status(),
+            QualityStatus::Good
+        );
+
+        // 48 kHz 文件不做升采样检测。
+        metrics.sample_rate_hz = Some(48_000);
+        metrics.hf_band_rms_db.insert(UPSAMPLE_CHECK_HZ, -138.0);
+        assert_eq!(
+            scorer.evaluate_rules(&metrics).status(),
+            QualityStatus::Good
+        );
+    }
+
     #[test]
     fn test_determine_status_loudness_off_target() {
         let scorer = QualityScorer::new();
         let mut metrics = create_test_metrics();
         metrics.integrated_loudness_lufs = Some(-4.0);
-        let status = scorer.determine_status(&metrics);
+        let status = scorer.evaluate_rules(&metrics).status();
         assert_eq!(status, QualityStatus::LoudnessOffTarget);
     }
 
@@ -911,7 +2564,7 @@ mod tests {
         let scorer = QualityScorer::new();
         let mut metrics = create_test_metrics();
         metrics.true_peak_dbtp = Some(0.3);
-        let status = scorer.determine_status(&metrics);
+        let status = scorer.evaluate_rules(&metrics).status();
         assert_eq!(status, QualityStatus::TruePeakRisk);
     }
 
@@ -920,7 +2573,7 @@ mod tests {
         let scorer = QualityScorer::new();
         let mut metrics = create_test_metrics();
         metrics.true_peak_dbtp = Some(1.2);
-        let status = scorer.determine_status(&metrics);
+        let status = scorer.evaluate_rules(&metrics).status();
         assert_eq!(status, QualityStatus::Clipped);
     }
 
@@ -934,7 +2587,7 @@ mod tests {
         metrics.bitrate_kbps = Some(128);
         metrics.integrated_loudness_lufs = Some(-9.5);
         metrics.true_peak_dbtp = Some(-2.0);
-        let status = scorer.determine_status(&metrics);
+        let status = scorer.evaluate_rules(&metrics).status();
         assert_eq!(status, QualityStatus::LowBitrate);
     }
 
@@ -944,7 +2597,7 @@ mod tests {
         let mut metrics = create_test_metrics();
         metrics.lra = None;
         metrics.integrated_loudness_lufs = None;
-        let status = scorer.determine_status(&metrics);
+        let status = scorer.evaluate_rules(&metrics).status();
         assert_eq!(status, QualityStatus::Incomplete);
     }
 
@@ -952,8 +2605,9 @@ mod tests {
     fn test_calculate_quality_score() {
         let scorer = QualityScorer::new();
         let metrics = create_test_metrics();
-        let status = QualityStatus::Good;
-        let score = scorer.calculate_quality_score(&metrics, &status);
+        let evaluation = scorer.evaluate_rules(&metrics);
+        assert_eq!(evaluation.status(), QualityStatus::Good);
+        let score = scorer.score_breakdown(&metrics, &evaluation).final_score;
         assert!((70..=99).contains(&score));
     }
 
@@ -962,10 +2616,10 @@ mod tests {
         let scorer = QualityScorer::new();
         let mut metrics = create_test_metrics();
         metrics.true_peak_dbtp = Some(0.3);
-        let status = scorer.determine_status(&metrics);
-        assert_eq!(status, QualityStatus::TruePeakRisk);
+        let evaluation = scorer.evaluate_rules(&metrics);
+        assert_eq!(evaluation.status(), QualityStatus::TruePeakRisk);
 
-        let score = scorer.calculate_quality_score(&metrics, &status);
+        let score = scorer.score_breakdown(&metrics, &evaluation).final_score;
         // With continuous scaling, score depends on elite_readiness.
         // TruePeakRisk caps raw at 92, then scaling compresses from 82.
         assert!((80..=92).contains(&score));
@@ -975,10 +2629,10 @@ mod tests {
     fn test_elite_track_can_stay_in_90_plus() {
         let scorer = QualityScorer::new();
         let metrics = create_test_metrics();
-        let status = scorer.determine_status(&metrics);
-        assert_eq!(status, QualityStatus::Good);
+        let evaluation = scorer.evaluate_rules(&metrics);
+        assert_eq!(evaluation.status(), QualityStatus::Good);
 
-        let score = scorer.calculate_quality_score(&metrics, &status);
+        let score = scorer.score_breakdown(&metrics, &evaluation).final_score;
         assert!(score >= 90);
     }
 
@@ -1007,6 +2661,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fallback_codes_are_warnings_not_failures() {
+        let scorer = QualityScorer::new();
+        let clean = create_test_metrics();
+        let mut fallback = create_test_metrics();
+        fallback.error_codes = vec!["W_FALLBACK_EBUR128".to_string()];
+        let mut failed = create_test_metrics();
+        failed.error_codes = vec!["E_RMS20K".to_string()];
+
+        assert_eq!(
+            scorer.calculate_integrity_score(&fallback),
+            scorer.calculate_integrity_score(&clean)
+        );
+        assert!(
+            scorer.calculate_integrity_score(&failed) < scorer.calculate_integrity_score(&clean)
+        );
+        assert!(scorer.estimate_confidence(&fallback) > scorer.estimate_confidence(&failed));
+
+        // 硬件加速失败后改用软件解码，测量值与正常解码相同，不影响评分与置信度。
+        let mut software = create_test_metrics();
+        software.error_codes = vec!["W_SOFTWARE_DECODE_EBUR128".to_string()];
+        assert_eq!(
+            scorer.calculate_integrity_score(&software),
+            scorer.calculate_integrity_score(&clean)
+        );
+        assert_eq!(
+            scorer.estimate_confidence(&software),
+            scorer.estimate_confidence(&clean)
+        );
+    }
+
     #[test]
     fn test_continuous_scaling_spread() {
         let scorer = QualityScorer::new();
@@ -1020,11 +2705,15 @@ mod tests {
         // High readiness: perfect metrics
         let high_ready = create_test_metrics(); // already has good defaults
 
-        let low_status = scorer.determine_status(&low_ready);
-        let high_status = scorer.determine_status(&high_ready);
+        let low_evaluation = scorer.evaluate_rules(&low_ready);
+        let high_evaluation = scorer.evaluate_rules(&high_ready);
 
-        let low_score = scorer.calculate_quality_score(&low_ready, &low_status);
-        let high_score = scorer.calculate_quality_score(&high_ready, &high_status);
+        let low_score = scorer
+            .score_breakdown(&low_ready, &low_evaluation)
+            .final_score;
+        let high_score = scorer
+            .score_breakdown(&high_ready, &high_evaluation)
+            .final_score;
 
         // High readiness track should score notably higher than low readiness track
         assert!(
@@ -0,0 +1,366 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::ffmpeg;
+use super::metrics::AudioStats;
+
+/// 分段分析中测量高频能量使用的高通频点（Hz）。
+pub const SEGMENT_HF_FREQ: u32 = 16_000;
+
+/// 窗口峰值达到该电平（dBFS）即视为削波。
+const CLIP_PEAK_DB: f64 = -0.1;
+/// 窗口高频能量比全曲中位数低这么多（dB）时视为高频缺失。
+const HF_DIP_DB: f64 = 20.0;
+/// 窗口 RMS 低于该电平视为静音，不参与高频缺失判定。
+const SILENCE_RMS_DB: f64 = -60.0;
+/// astats 对纯静音输出 `-inf`，统一记为该下限，便于序列化。
+const SILENCE_FLOOR_DB: f64 = -150.0;
+/// 限幅检测的窗口长度（毫秒）。
+const CEILING_WINDOW_MS: u64 = 100;
+/// 窗口峰值距全曲最高峰值不超过该值（dB）即视为“贴顶”。
+const CEILING_MARGIN_DB: f64 = 0.5;
+/// 参与统计的最少非静音窗口数，过短的文件不做判断。
+const CEILING_MIN_WINDOWS: usize = 20;
+/// 短于该时长（秒）的文件测不出贴顶时间占比。
+pub const CEILING_MIN_SECONDS: f64 =
+    (CEILING_MIN_WINDOWS as u64 * CEILING_WINDOW_MS) as f64 / 1000.0;
+/// 曲目衔接检查测量的开头/结尾时长（秒）。
+pub const EDGE_WINDOW_SECONDS: f64 = 0.25;
+/// 每类问题最多列出的时间段数量。
+const MAX_RANGES_PER_KIND: usize = 3;
+
+lazy_static! {
+    static ref PTS_TIME_REGEX: Regex = Regex::new(r"pts_time:\s*([0-9.,]+)").unwrap();
+    static ref RMS_KEY_REGEX: Regex =
+        Regex::new(r"lavfi\.astats\.Overall\.RMS_level=\s*([-\w.,+]+)").unwrap();
+    static ref PEAK_KEY_REGEX: Regex =
+        Regex::new(r"lavfi\.astats\.Overall\.Peak_level=\s*([-\w.,+]+)").unwrap();
+}
+
+/// 单个时间窗口的测量结果。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentWindow {
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+    pub rms_db: Option<f64>,
+    pub peak_db: Option<f64>,
+    /// `SEGMENT_HF_FREQ` 以上的 RMS 电平。
+    pub hf_rms_db: Option<f64>,
+}
+
+/// 按固定时长切分的逐窗口分析结果。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentAnalysis {
+    pub window_seconds: u32,
+    pub windows: Vec<SegmentWindow>,
+}
+
+/// 曲目开头与结尾 `EDGE_WINDOW_SECONDS` 内的 RMS/峰值电平（dBFS，数字静音记为 -150）。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeLevels {
+    pub head_rms_db: f64,
+    pub head_peak_db: f64,
+    pub tail_rms_db: f64,
+    pub tail_peak_db: f64,
+}
+
+impl EdgeLevels {
+    /// astats 对数字静音输出 `-inf`（解析为 `None`），此时按静音下限记录。
+    pub fn from_stats(head: AudioStats, tail: AudioStats) -> Self {
+        let level = |value: Option<f64>| value.unwrap_or(SILENCE_FLOOR_DB);
+        Self {
+            head_rms_db: level(head.rms_db),
+            head_peak_db: level(head.peak_db),
+            tail_rms_db: level(tail.rms_db),
+            tail_peak_db: level(tail.peak_db),
+        }
+    }
+}
+
+/// `ametadata=mode=print` 输出中单帧（即单个窗口）的统计。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowStats {
+    pub start_seconds: f64,
+    pub rms_db: Option<f64>,
+    pub peak_db: Option<f64>,
+}
+
+/// 分段分析的滤镜链：每帧固定样本数，astats 逐帧重置并把统计打印到 stderr。
+pub fn segment_filter(window_seconds: u32, sample_rate_hz: u32, highpass: Option<u32>) -> String {
+    let samples = u64::from(window_seconds) * u64::from(sample_rate_hz);
+    let chain =
+        format!("asetnsamples=n={samples}:p=0,astats=metadata=1:reset=1,ametadata=mode=print");
+    match highpass {
+        Some(freq) => format!("highpass=f={freq},{chain}"),
+        None => chain,
+    }
+}
+
+/// 限幅检测的滤镜链：按短窗口逐帧重置 astats，只打印窗口峰值以控制输出量。
+pub fn ceiling_filter(sample_rate_hz: u32) -> String {
+    let samples = (CEILING_WINDOW_MS * u64::from(sample_rate_hz) / 1000).max(1);
+    format!(
+        "asetnsamples=n={samples}:p=0,astats=metadata=1:reset=1,\
+         ametadata=mode=print:key=lavfi.astats.Overall.Peak_level"
+    )
+}
+
+/// 非静音窗口中峰值贴近全曲最高峰值（`CEILING_MARGIN_DB` 以内）的时间占比（%）。
+/// 重度限幅的母带几乎每个窗口都顶到天花板，干净的响亮母带只在少数高潮处接近峰值。
+pub fn ceiling_time_percent(windows: &[WindowStats]) -> Option<f64> {
+    let peaks: Vec<f64> = windows
+        .iter()
+        .filter_map(|w| w.peak_db)
+        .filter(|&peak| peak > SILENCE_RMS_DB)
+        .collect();
+    if peaks.len() < CEILING_MIN_WINDOWS {
+        return None;
+    }
+    let ceiling = peaks.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let at_ceiling = peaks
+        .iter()
+        .filter(|&&peak| peak >= ceiling - CEILING_MARGIN_DB)
+        .count();
+    let percent = at_ceiling as f64 / peaks.len() as f64 * 100.0;
+    Some((percent * 10.0).round() / 10.0)
+}
+
+pub fn parse_window_stats(stderr: &str) -> Vec<WindowStats> {
+    let mut windows: Vec<WindowStats> = Vec::new();
+    for line in stderr.lines() {
+        if let Some(caps) = PTS_TIME_REGEX.captures(line) {
+            let start_seconds = ffmpeg::parse_float_token(&caps[1]).unwrap_or_default();
+            windows.push(WindowStats {
+                start_seconds,
+                ..WindowStats::default()
+            });
+        } else if let Some(current) = windows.last_mut() {
+            if let Some(caps) = RMS_KEY_REGEX.captures(line) {
+                current.rms_db = parse_level(&caps[1]);
+            } else if let Some(caps) = PEAK_KEY_REGEX.captures(line) {
+                current.peak_db = parse_level(&caps[1]);
+            }
+        }
+    }
+    windows
+}
+
+fn parse_level(token: &str) -> Option<f64> {
+    match token.trim().to_ascii_lowercase().as_str() {
+        "-inf" => Some(SILENCE_FLOOR_DB),
+        "nan" | "inf" | "+inf" => None,
+        text => ffmpeg::parse_float_token(text),
+    }
+}
+
+impl SegmentAnalysis {
+    /// 合并全频与高通两路逐窗口统计；窗口结束时间取下一窗口起点（最后一个取时长）。
+    pub fn from_stats(
+        window_seconds: u32,
+        duration_seconds: Option<f64>,
+        full: Vec<WindowStats>,
+        highpass: &[WindowStats],
+    ) -> Self {
+        let starts: Vec<f64> = full.iter().map(|w| w.start_seconds).collect();
+        let windows = full
+            .into_iter()
+            .enumerate()
+            .map(|(i, stats)| {
+                let nominal_end = stats.start_seconds + f64::from(window_seconds);
+                let end_seconds = starts
+                    .get(i + 1)
+                    .copied()
+                    .or_else(|| duration_seconds.map(|d| d.min(nominal_end)))
+                    .unwrap_or(nominal_end);
+                SegmentWindow {
+                    start_seconds: stats.start_seconds,
+                    end_seconds,
+                    rms_db: stats.rms_db,
+                    peak_db: stats.peak_db,
+                    hf_rms_db: highpass.get(i).and_then(|hp| hp.rms_db),
+                }
+            })
+            .collect();
+
+        Self {
+            window_seconds,
+            windows,
+        }
+    }
+
+    /// 生成定位到时间段的问题备注，例如 `削波: 01:23–01:31`。
+    pub fn problem_notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+
+        let clipped = self.ranges_where(|w| w.peak_db.is_some_and(|p| p >= CLIP_PEAK_DB));
+        if !clipped.is_empty() {
+            notes.push(format!("削波: {}", format_ranges(&clipped)));
+        }
+
+        if let Some(median_hf) = self.median_hf_rms() {
+            let dips = self.ranges_where(|w| {
+                w.rms_db.is_some_and(|r| r > SILENCE_RMS_DB)
+                    && w.hf_rms_db.is_some_and(|hf| hf < median_hf - HF_DIP_DB)
+            });
+            if !dips.is_empty() {
+                notes.push(format!("高频缺失: {}", format_ranges(&dips)));
+            }
+        }
+
+        notes
+    }
+
+    fn median_hf_rms(&self) -> Option<f64> {
+        let mut values: Vec<f64> = self
+            .windows
+            .iter()
+            .filter(|w| w.rms_db.is_some_and(|r| r > SILENCE_RMS_DB))
+            .filter_map(|w| w.hf_rms_db)
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        Some(values[values.len() / 2])
+    }
+
+    /// 合并满足条件的相邻窗口，按时长降序取最严重的几段，再按时间排序。
+    fn ranges_where(&self, predicate: impl Fn(&SegmentWindow) -> bool) -> Vec<(f64, f64)> {
+        let mut ranges: Vec<(f64, f64)> = Vec::new();
+        let mut previous_matched = false;
+        for window in &self.windows {
+            let matched = predicate(window);
+            match ranges.last_mut() {
+                Some(last) if matched && previous_matched => last.1 = window.end_seconds,
+                _ if matched => ranges.push((window.start_seconds, window.end_seconds)),
+                _ => {}
+            }
+            previous_matched = matched;
+        }
+
+        ranges.sort_by(|a, b| (b.1 - b.0).total_cmp(&(a.1 - a.0)));
+        ranges.truncate(MAX_RANGES_PER_KIND);
+        ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranges
+    }
+}
+
+fn format_ranges(ranges: &[(f64, f64)]) -> String {
+    ranges
+        .iter()
+        .map(|(start, end)| format!("{}–{}", format_timestamp(*start), format_timestamp(*end)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 秒数格式化为 `mm:ss`（超过一小时为 `h:mm:ss`）。
+pub fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total / 3600, total % 3600 / 60, total % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes:02}:{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_window_stats_from_ametadata_output() {
+        let stderr = "\
+[Parsed_ametadata_2 @ 0x1] frame:0    pts:0       pts_time:0
+[Parsed_ametadata_2 @ 0x1] lavfi.astats.Overall.Peak_level=-3.500000
+[Parsed_ametadata_2 @ 0x1] lavfi.astats.Overall.RMS_level=-18.200000
+[Parsed_ametadata_2 @ 0x1] frame:1    pts:441000  pts_time:10
+[Parsed_ametadata_2 @ 0x1] lavfi.astats.Overall.Peak_level=-inf
+[Parsed_ametadata_2 @ 0x1] lavfi.astats.Overall.RMS_level=-inf
+";
+        let windows = parse_window_stats(stderr);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].rms_db, Some(-18.2));
+        assert_eq!(windows[1].start_seconds, 10.0);
+        assert_eq!(windows[1].peak_db, Some(SILENCE_FLOOR_DB));
+
+        // 非 C locale 下以逗号作小数点的输出。
+        let comma = "\
+[Parsed_ametadata_2 @ 0x1] frame:1    pts:463050  pts_time:10,5
+[Parsed_ametadata_2 @ 0x1] lavfi.astats.Overall.Peak_level=-3,500000
+[Parsed_ametadata_2 @ 0x1] lavfi.astats.Overall.RMS_level=-18,200000
+";
+        let windows = parse_window_stats(comma);
+        assert_eq!(windows[0].start_seconds, 10.5);
+        assert_eq!(windows[0].peak_db, Some(-3.5));
+        assert_eq!(windows[0].rms_db, Some(-18.2));
+        assert_eq!(
+            segment_filter(10, 44_100, Some(SEGMENT_HF_FREQ)),
+            "highpass=f=16000,asetnsamples=n=441000:p=0,astats=metadata=1:reset=1,ametadata=mode=print"
+        );
+    }
+
+    #[test]
+    fn test_ceiling_time_percent() {
+        let windows = |peaks: &[f64]| -> Vec<WindowStats> {
+            peaks
+                .iter()
+                .map(|&peak| WindowStats {
+                    peak_db: Some(peak),
+                    ..WindowStats::default()
+                })
+                .collect()
+        };
+        // 限幅母带：大部分窗口贴顶；静音窗口不计入。
+        let mut smashed = vec![-0.3; 18];
+        smashed.extend([-6.0, -8.0, -0.1, -0.2, SILENCE_FLOOR_DB]);
+        assert_eq!(ceiling_time_percent(&windows(&smashed)), Some(90.9));
+
+        let mut dynamic = vec![-12.0; 18];
+        dynamic.extend([-1.0, -0.8]);
+        assert_eq!(ceiling_time_percent(&windows(&dynamic)), Some(10.0));
+        assert_eq!(ceiling_time_percent(&windows(&[-1.0; 5])), None);
+
+        assert_eq!(
+            ceiling_filter(44_100),
+            "asetnsamples=n=4410:p=0,astats=metadata=1:reset=1,ametadata=mode=print:key=lavfi.astats.Overall.Peak_level"
+        );
+    }
+
+    #[test]
+    fn test_problem_notes_localize_clipping_and_hf_dips() {
+        let stats = |start: f64, peak: f64| WindowStats {
+            start_seconds: start,
+            rms_db: Some(-14.0),
+            peak_db: Some(peak),
+        };
+        let hf = |rms: f64| WindowStats {
+            rms_db: Some(rms),
+            ..WindowStats::default()
+        };
+        let full = vec![
+            stats(0.0, -3.0),
+            stats(80.0, 0.0),
+            stats(88.0, -0.05),
+            stats(96.0, -2.0),
+            stats(104.0, -2.0),
+        ];
+        let highpass = vec![hf(-60.0), hf(-61.0), hf(-62.0), hf(-95.0), hf(-60.0)];
+        let analysis = SegmentAnalysis::from_stats(8, Some(110.0), full, &highpass);
+
+        assert_eq!(analysis.windows[4].end_seconds, 110.0);
+        assert_eq!(
+            analysis.problem_notes(),
+            vec![
+                "削波: 01:20–01:36".to_string(),
+                "高频缺失: 01:36–01:44".to_string()
+            ]
+        );
+        assert_eq!(format_timestamp(3725.0), "1:02:05");
+    }
+}
@@ -0,0 +1,438 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::ffmpeg::{self, ProcessingConfig};
+use super::scoring::QualityAnalysis;
+
+/// `serve` 子命令的默认端口。
+pub const DEFAULT_PORT: u16 = 8787;
+
+/// 请求行与请求头的长度上限，超出即断开连接。
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+/// 没有新连接时检查停止标志的间隔。
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 处理连接的工作线程数；渲染频谱图较慢，其余请求仍可由空闲线程处理。
+const WORKER_THREADS: usize = 4;
+
+/// 等待工作线程的连接数上限，超出时直接断开新连接。
+const MAX_QUEUED_CONNECTIONS: usize = 32;
+
+/// 读取请求与写出响应的超时，避免慢速或不发数据的客户端一直占用工作线程。
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 启用 `webui` 特性时内置的单页界面，只通过下方的 JSON 接口读取数据。
+#[cfg(feature = "webui")]
+const INDEX_HTML: &str = include_str!("webui/index.html");
+
+/// 只读的本地结果服务：
+/// - `GET /api/summary`：文件数、平均分、状态与等级分布；
+/// - `GET /api/results`：全部评分结果（每条带 `id`，即在列表中的序号）；
+/// - `GET /api/spectrogram/<id>`：该结果的 PNG 频谱图（首次请求时调用 ffmpeg 渲染并缓存）；
+/// - `GET /`：内置网页界面（需 `webui` 特性与 `--ui`）。
+pub struct ResultsServer {
+    root: PathBuf,
+    analyses: Vec<QualityAnalysis>,
+    /// 未找到 ffmpeg 时为 `None`，频谱图接口返回 503。
+    processing: Option<ProcessingConfig>,
+    ui: bool,
+    spectrograms: Mutex<HashMap<usize, Arc<Vec<u8>>>>,
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(value: &Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json; charset=utf-8",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn text(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.into().into_bytes(),
+        }
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+impl ResultsServer {
+    pub fn new(
+        root: PathBuf,
+        analyses: Vec<QualityAnalysis>,
+        processing: Option<ProcessingConfig>,
+        ui: bool,
+    ) -> Self {
+        Self {
+            root,
+            analyses,
+            processing,
+            ui,
+            spectrograms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 绑定地址并由固定数量的工作线程处理请求，直到 `stop` 被置位（Ctrl-C）。
+    pub fn serve(self, addr: SocketAddr, stop: &AtomicBool) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .with_context(|| format!("[E_SERVE_BIND] 无法监听地址: {addr}"))?;
+        // 非阻塞监听，才能在等待连接期间响应停止请求。
+        listener
+            .set_nonblocking(true)
+            .context("[E_SERVE_BIND] 无法设置非阻塞监听")?;
+        let server = Arc::new(self);
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(MAX_QUEUED_CONNECTIONS);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers: Vec<_> = (0..WORKER_THREADS)
+            .map(|_| {
+                let server = Arc::clone(&server);
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || loop {
+                    let stream = match receiver.lock().expect("connection queue").recv() {
+                        Ok(stream) => stream,
+                        Err(_) => break,
+                    };
+                    // 客户端中途断开、超时等 I/O 错误只影响该连接。
+                    let _ = server.handle_connection(stream, addr.port());
+                })
+            })
+            .collect();
+        while !stop.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let configured = stream
+                        .set_nonblocking(false)
+                        .and_then(|_| stream.set_read_timeout(Some(SOCKET_TIMEOUT)))
+                        .and_then(|_| stream.set_write_timeout(Some(SOCKET_TIMEOUT)));
+                    if configured.is_err() {
+                        continue;
+                    }
+                    // 队列已满时丢弃（即关闭）新连接，不再为其另开线程。
+                    if let Err(TrySendError::Disconnected(_)) = sender.try_send(stream) {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+                Err(_) => {}
+            }
+        }
+        drop(sender);
+        for worker in workers {
+            let _ = worker.join();
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: TcpStream, port: u16) -> std::io::Result<()> {
+        let mut reader = BufReader::new(std::io::Read::take(&stream, MAX_REQUEST_BYTES));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // 只关心 Host 与 Origin，读到空行即可，不读请求体。
+        let mut host = None;
+        let mut origin = None;
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+            if let Some((name, value)) = header.split_once(':') {
+                let value = Some(value.trim().to_string());
+                if name.trim().eq_ignore_ascii_case("host") {
+                    host = value;
+                } else if name.trim().eq_ignore_ascii_case("origin") {
+                    origin = value;
+                }
+            }
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let response = match (parts.next(), parts.next()) {
+            // 只接受以本机地址访问的请求：其他 Host 或 Origin 可能来自 DNS 重绑定的网页。
+            (Some(_), Some(_)) if !is_local_request(host.as_deref(), origin.as_deref(), port) => {
+                Response::text(403, "只接受来自本机地址的请求")
+            }
+            (Some("GET"), Some(target)) => self.route(target),
+            (Some(_), Some(_)) => Response::text(405, "只支持 GET 请求"),
+            _ => Response::text(400, "无法解析的请求"),
+        };
+
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+            response.status,
+            reason_phrase(response.status),
+            response.content_type,
+            response.body.len()
+        )?;
+        stream.write_all(&response.body)?;
+        stream.flush()
+    }
+
+    fn route(&self, target: &str) -> Response {
+        let path = target.split(['?', '#']).next().unwrap_or_default();
+        match path {
+            "/api/summary" => Response::json(&self.summary()),
+            "/api/results" => Response::json(&self.results()),
+            "/" | "/index.html" if self.ui => index_page(),
+            "/" => Response::text(
+                404,
+                "未启用网页界面；请使用 --ui 启动，或直接访问 /api/results",
+            ),
+            _ => match path.strip_prefix("/api/spectrogram/") {
+                Some(id) => match id.parse::<usize>() {
+                    Ok(id) => self.spectrogram(id),
+                    Err(_) => Response::text(400, format!("无效的结果序号: {id}")),
+                },
+                None => Response::text(404, format!("未知路径: {path}")),
+            },
+        }
+    }
+
+    fn summary(&self) -> Value {
+        let mut statuses: BTreeMap<String, usize> = BTreeMap::new();
+        let mut grades: BTreeMap<&str, usize> = BTreeMap::new();
+        for analysis in &self.analyses {
+            *statuses.entry(analysis.status.to_string()).or_default() += 1;
+            *grades.entry(analysis.grade.as_str()).or_default() += 1;
+        }
+        let average = (!self.analyses.is_empty()).then(|| {
+            self.analyses
+                .iter()
+                .map(|a| f64::from(a.quality_score))
+                .sum::<f64>()
+                / self.analyses.len() as f64
+        });
+        json!({
+            "root": self.root,
+            "total": self.analyses.len(),
+            "averageScore": average,
+            "statuses": statuses,
+            "grades": grades,
+            "spectrogramAvailable": self.processing.is_some(),
+        })
+    }
+
+    fn results(&self) -> Value {
+        let results: Vec<Value> = self
+            .analyses
+            .iter()
+            .enumerate()
+            .map(|(id, analysis)| {
+                let mut value = serde_json::to_value(analysis).unwrap_or(Value::Null);
+                if let Value::Object(map) = &mut value {
+                    map.insert("id".to_string(), json!(id));
+                }
+                value
+            })
+            .collect();
+        Value::Array(results)
+    }
+
+    fn spectrogram(&self, id: usize) -> Response {
+        let Some(analysis) = self.analyses.get(id) else {
+            return Response::text(404, format!("不存在序号为 {id} 的结果"));
+        };
+        let Some(config) = &self.processing else {
+            return Response::text(503, "未找到 ffmpeg，无法生成频谱图");
+        };
+        if let Some(png) = self
+            .spectrograms
+            .lock()
+            .expect("spectrogram cache")
+            .get(&id)
+        {
+            return png_response(png);
+        }
+        match render_png(analysis, config) {
+            Ok(png) => {
+                let png = Arc::new(png);
+                self.spectrograms
+                    .lock()
+                    .expect("spectrogram cache")
+                    .insert(id, Arc::clone(&png));
+                png_response(&png)
+            }
+            Err(e) => Response::text(500, format!("{e:#}")),
+        }
+    }
+}
+
+/// Host 必须是本机地址（可带监听端口）；带 Origin 时它也必须指向本服务。
+fn is_local_request(host: Option<&str>, origin: Option<&str>, port: u16) -> bool {
+    let is_local_host = |host: &str| {
+        let name = match host.strip_suffix(&format!(":{port}")) {
+            Some(name) => name,
+            None => host,
+        };
+        matches!(
+            name.to_ascii_lowercase().as_str(),
+            "localhost" | "127.0.0.1" | "[::1]"
+        )
+    };
+    let host_ok = host.is_some_and(is_local_host);
+    let origin_ok = match origin {
+        None => true,
+        Some(origin) => origin.strip_prefix("http://").is_some_and(is_local_host),
+    };
+    host_ok && origin_ok
+}
+
+fn png_response(png: &[u8]) -> Response {
+    Response {
+        status: 200,
+        content_type: "image/png",
+        body: png.to_vec(),
+    }
+}
+
+fn render_png(analysis: &QualityAnalysis, config: &ProcessingConfig) -> Result<Vec<u8>> {
+    let output = tempfile::Builder::new()
+        .prefix("aq_spectrogram_")
+        .suffix(".png")
+        .tempfile()
+        .context("[E_SPECTROGRAM] 无法创建临时文件")?;
+    ffmpeg::render_spectrogram(
+        &analysis.metrics,
+        output.path(),
+        ffmpeg::SPECTROGRAM_SIZE,
+        config,
+    )?;
+    let png = std::fs::read(output.path()).context("[E_SPECTROGRAM] 无法读取渲染结果")?;
+    if png.is_empty() {
+        return Err(anyhow!("[E_SPECTROGRAM] ffmpeg 未输出图像"));
+    }
+    Ok(png)
+}
+
+#[cfg(feature = "webui")]
+fn index_page() -> Response {
+    Response {
+        status: 200,
+        content_type: "text/html; charset=utf-8",
+        body: INDEX_HTML.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(not(feature = "webui"))]
+fn index_page() -> Response {
+    Response::text(404, "当前构建未启用 webui 特性")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use std::io::Read;
+
+    fn get(addr: SocketAddr, path: &str) -> (String, String) {
+        request(
+            addr,
+            &format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+        )
+    }
+
+    fn request(addr: SocketAddr, request: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(addr).expect("connect");
+        stream.write_all(request.as_bytes()).expect("request");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("response");
+        let (head, body) = response.split_once("\r\n\r\n").expect("head");
+        (
+            head.lines().next().unwrap_or_default().to_string(),
+            body.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_results_api_serves_summary_results_and_errors() {
+        let scorer = QualityScorer::new();
+        let analyses = scorer.analyze_files_owned(vec![
+            FileMetrics {
+                file_path: "/m/a.flac".to_string(),
+                ..FileMetrics::default()
+            },
+            FileMetrics {
+                file_path: "/m/b.flac".to_string(),
+                ..FileMetrics::default()
+            },
+        ]);
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        drop(listener);
+        static STOP: AtomicBool = AtomicBool::new(false);
+        let server = ResultsServer::new(PathBuf::from("/m"), analyses, None, false);
+        let handle = thread::spawn(move || server.serve(addr, &STOP));
+        thread::sleep(Duration::from_millis(100));
+
+        let (status, body) = get(addr, "/api/summary");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let summary: Value = serde_json::from_str(&body).expect("summary json");
+        assert_eq!(summary["total"], 2);
+        assert_eq!(summary["spectrogramAvailable"], false);
+
+        let (_, body) = get(addr, "/api/results?status=any");
+        let results: Value = serde_json::from_str(&body).expect("results json");
+        assert_eq!(results[1]["id"], 1);
+        assert_eq!(results[1]["filePath"], "/m/b.flac");
+
+        assert_eq!(
+            get(addr, "/api/spectrogram/0").0,
+            "HTTP/1.1 503 Service Unavailable"
+        );
+        assert_eq!(get(addr, "/api/spectrogram/9").0, "HTTP/1.1 404 Not Found");
+        // 未以 --ui 启动时不提供网页界面。
+        assert_eq!(get(addr, "/").0, "HTTP/1.1 404 Not Found");
+
+        let port = addr.port();
+        let allowed = format!(
+            "GET /api/summary HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\n\
+             Origin: http://localhost:{port}\r\n\r\n"
+        );
+        assert_eq!(request(addr, &allowed).0, "HTTP/1.1 200 OK");
+        for forbidden in [
+            "GET /api/results HTTP/1.1\r\nHost: attacker.example\r\n\r\n".to_string(),
+            format!(
+                "GET /api/results HTTP/1.1\r\nHost: localhost:{port}\r\n\
+                 Origin: http://attacker.example\r\n\r\n"
+            ),
+            "GET /api/results HTTP/1.0\r\n\r\n".to_string(),
+        ] {
+            assert_eq!(request(addr, &forbidden).0, "HTTP/1.1 403 Forbidden");
+        }
+
+        STOP.store(true, Ordering::SeqCst);
+        handle.join().expect("join").expect("serve");
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::cache::{FileFingerprint, FingerprintMode, HashAlgorithm};
+use super::metrics::FileMetrics;
+use super::safe_io;
+use super::scoring::QualityAnalysis;
+
+/// 单文件结果文件的扩展名，附加在音频文件名之后，如 `01 Song.flac.aq.json`。
+pub const SIDECAR_EXTENSION: &str = "aq.json";
+
+/// 输出目录模式下镜像单文件结果的子目录名。
+pub const SIDECAR_MIRROR_DIR_NAME: &str = "sidecars";
+
+/// 单文件结果：完整指标与评分，供播放器、标签工具或资产管理系统直接读取。
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Sidecar<'a> {
+    tool_version: &'static str,
+    analyzed_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<SidecarFingerprint>,
+    analysis: &'a QualityAnalysis,
+}
+
+/// 读取已有单文件结果时只需要指纹与其中展平的原始指标。
+#[derive(Debug, Deserialize)]
+struct StoredSidecar {
+    fingerprint: Option<SidecarFingerprint>,
+    analysis: FileMetrics,
+}
+
+/// 单文件结果中记录的内容指纹。不含修改时间，文件夹复制到其他系统后仍可匹配。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarFingerprint {
+    file_size_bytes: u64,
+    hash_algorithm: HashAlgorithm,
+    content_hash: String,
+    /// 旧版单文件结果没有该字段，均为完整哈希。
+    #[serde(default)]
+    mode: FingerprintMode,
+}
+
+impl From<&FileFingerprint> for SidecarFingerprint {
+    fn from(fingerprint: &FileFingerprint) -> Self {
+        Self {
+            file_size_bytes: fingerprint.file_size_bytes,
+            hash_algorithm: fingerprint.hash_algorithm,
+            content_hash: fingerprint.content_hash.clone(),
+            mode: fingerprint.mode,
+        }
+    }
+}
+
+/// 单文件结果的存放位置：音频文件旁边，或按相对库目录的路径镜像到 `mirror_root` 下。
+#[derive(Debug, Clone)]
+pub struct SidecarLocation {
+    library_root: PathBuf,
+    mirror_root: Option<PathBuf>,
+}
+
+impl SidecarLocation {
+    pub fn new(library_root: &Path, mirror_root: Option<PathBuf>) -> Self {
+        Self {
+            library_root: library_root.to_path_buf(),
+            mirror_root,
+        }
+    }
+
+    /// 某个音频文件（及音轨/CUE 曲目）的单文件结果路径。
+    /// 多音轨文件与 CUE 音轨分别在名称中加上 `.a<音轨>`、`.t<曲目>`。
+    pub fn path_for(
+        &self,
+        audio_path: &Path,
+        audio_stream: u32,
+        cue_track: Option<u32>,
+    ) -> PathBuf {
+        let mut file_name = audio_path
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| "audio".into());
+        if audio_stream > 0 {
+            file_name.push(format!(".a{audio_stream}"));
+        }
+        if let Some(number) = cue_track {
+            file_name.push(format!(".t{number:02}"));
+        }
+        file_name.push(format!(".{SIDECAR_EXTENSION}"));
+
+        self.dir_for(audio_path.parent().unwrap_or(Path::new("")))
+            .join(file_name)
+    }
+
+    /// 库中某个目录对应的输出目录：该目录本身，或镜像到 `mirror_root` 下的相同相对路径。
+    pub fn dir_for(&self, library_dir: &Path) -> PathBuf {
+        match &self.mirror_root {
+            Some(root) => root.join(
+                library_dir
+                    .strip_prefix(&self.library_root)
+                    .unwrap_or(Path::new("")),
+            ),
+            None => library_dir.to_path_buf(),
+        }
+    }
+
+    fn path_for_analysis(&self, analysis: &QualityAnalysis) -> PathBuf {
+        self.path_for(
+            Path::new(&analysis.file_path),
+            analysis.metrics.audio_stream_index,
+            analysis
+                .metrics
+                .cue_track
+                .as_ref()
+                .map(|track| track.number),
+        )
+    }
+
+    /// 读取已有单文件结果；记录的内容指纹与当前文件一致时返回其中的指标（标记为缓存命中）。
+    pub fn load_matching(
+        &self,
+        audio_path: &Path,
+        audio_stream: u32,
+        cue_track: Option<u32>,
+        fingerprint: &FileFingerprint,
+    ) -> Option<FileMetrics> {
+        // 单文件结果不记录修改时间，只有大小的快速指纹无法确认文件未变。
+        if fingerprint.mode == FingerprintMode::Fast {
+            return None;
+        }
+        let content =
+            std::fs::read_to_string(self.path_for(audio_path, audio_stream, cue_track)).ok()?;
+        // `analysis` 中 `filePath` 会出现两次（展平的指标也带有该字段），先解析为 Value 去重。
+        let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+        let stored: StoredSidecar = serde_json::from_value(value).ok()?;
+        if stored.fingerprint? != SidecarFingerprint::from(fingerprint) {
+            return None;
+        }
+        let mut metrics = stored.analysis;
+        // 文件夹可能来自其他系统，路径以当前位置为准。
+        metrics.file_path = audio_path.to_string_lossy().into_owned();
+        metrics.cache_hit = true;
+        Some(metrics)
+    }
+
+    /// 为每条分析结果写出单文件结果，单个文件写入失败只警告不中断，返回成功写出的数量。
+    /// `fingerprints` 按文件路径提供内容指纹；缺少指纹的结果（如重试时沿用的旧结果）不会被跳过逻辑复用。
+    pub fn write_all(
+        &self,
+        analyses: &[QualityAnalysis],
+        fingerprints: &HashMap<String, FileFingerprint>,
+        safe_mode: bool,
+    ) -> usize {
+        let analyzed_at = Local::now().to_rfc3339();
+        let mut written = 0;
+        for analysis in analyses {
+            let path = self.path_for_analysis(analysis);
+            let sidecar = Sidecar {
+                tool_version: env!("CARGO_PKG_VERSION"),
+                analyzed_at: analyzed_at.clone(),
+                fingerprint: fingerprints.get(&analysis.file_path).map(Into::into),
+                analysis,
+            };
+            match write_sidecar(&path, &sidecar, safe_mode) {
+                Ok(()) => written += 1,
+                Err(e) => tracing::warn!("写入单文件结果失败 {}: {e:#}", path.display()),
+            }
+        }
+        written
+    }
+}
+
+fn write_sidecar(path: &Path, sidecar: &Sidecar<'_>, safe_mode: bool) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("无法创建目录: {}", parent.display()))?;
+    }
+    let content = serde_json::to_string_pretty(sidecar).context("序列化单文件结果失败")?;
+    safe_io::atomic_write_string(path, &content, safe_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::cue::CueTrack;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sidecar_paths_and_mirrored_output() {
+        let dir = TempDir::new().expect("tempdir");
+        let library = dir.path().join("library");
+        let album = library.join("Artist").join("Album");
+        std::fs::create_dir_all(&album).expect("album dir");
+        let audio = album.join("01 Song.flac");
+
+        let mut metrics = vec![FileMetrics {
+            file_path: audio.to_string_lossy().into_owned(),
+            ..FileMetrics::default()
+        }];
+        metrics.push(FileMetrics {
+            cue_track: Some(CueTrack {
+                number: 3,
+                title: None,
+                performer: None,
+                start_seconds: 0.0,
+                end_seconds: None,
+            }),
+            ..metrics[0].clone()
+        });
+        let analyses = QualityScorer::new().analyze_files(&metrics);
+
+        let beside = SidecarLocation::new(&library, None);
+        assert_eq!(
+            beside.path_for_analysis(&analyses[0]),
+            album.join("01 Song.flac.aq.json")
+        );
+        let mirror = dir.path().join("reports").join(SIDECAR_MIRROR_DIR_NAME);
+        let mirrored = SidecarLocation::new(&library, Some(mirror.clone()));
+        assert_eq!(
+            mirrored.path_for_analysis(&analyses[1]),
+            mirror
+                .join("Artist")
+                .join("Album")
+                .join("01 Song.flac.t03.aq.json")
+        );
+
+        let fingerprint = FileFingerprint {
+            mtime_unix_secs: 1,
+            file_size_bytes: 42,
+            content_hash: "abc".to_string(),
+            hash_algorithm: HashAlgorithm::Blake3,
+            mode: FingerprintMode::Strict,
+            weaker_hashes: Default::default(),
+        };
+        let fingerprints = HashMap::from([(analyses[0].file_path.clone(), fingerprint.clone())]);
+        assert_eq!(mirrored.write_all(&analyses, &fingerprints, true), 2);
+        let content =
+            std::fs::read_to_string(mirrored.path_for_analysis(&analyses[0])).expect("sidecar");
+        let value: serde_json::Value = serde_json::from_str(&content).expect("json");
+        assert_eq!(value["analysis"]["filePath"], analyses[0].file_path);
+        assert!(value["analysis"]["质量分"].is_i64());
+
+        // 复制到其他系统后修改时间变化，只要内容指纹一致即可复用。
+        let copied = FileFingerprint {
+            mtime_unix_secs: 999,
+            ..fingerprint.clone()
+        };
+        let reused = mirrored
+            .load_matching(&audio, 0, None, &copied)
+            .expect("matching sidecar");
+        assert!(reused.cache_hit);
+        let changed = FileFingerprint {
+            content_hash: "def".to_string(),
+            ..fingerprint
+        };
+        assert!(mirrored.load_matching(&audio, 0, None, &changed).is_none());
+        assert!(mirrored.load_matching(&audio, 1, None, &copied).is_none());
+        let fast = FileFingerprint {
+            mode: FingerprintMode::Fast,
+            ..copied
+        };
+        assert!(mirrored.load_matching(&audio, 0, None, &fast).is_none());
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::path::Path;
+
+use super::ffmpeg::{self, ProcessingConfig};
+use super::safe_io;
+use super::scoring::{QualityAnalysis, QualityStatus};
+
+/// 频谱图写在报告目录下的该子目录中，模板报告按相对路径引用。
+pub const SPECTROGRAM_DIR_NAME: &str = "spectrograms";
+
+/// 为哪些文件生成频谱图（`--spectrograms`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrogramScope {
+    /// 只为有问题（状态不是质量良好）的文件生成。
+    Flagged,
+    All,
+}
+
+impl SpectrogramScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Flagged => "flagged",
+            Self::All => "all",
+        }
+    }
+
+    fn includes(self, analysis: &QualityAnalysis) -> bool {
+        match self {
+            Self::Flagged => analysis.status != QualityStatus::Good,
+            Self::All => true,
+        }
+    }
+}
+
+impl std::str::FromStr for SpectrogramScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "flagged" => Ok(Self::Flagged),
+            "all" => Ok(Self::All),
+            other => Err(format!("未知的频谱图范围: {other}（可用 flagged、all）")),
+        }
+    }
+}
+
+/// 删除上次运行留下的频谱图，避免模板报告引用到已不对应的图片。
+fn clear_stale(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("无法读取频谱图目录: {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "png") && path.is_file() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("删除过期的频谱图失败: {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// 并行渲染频谱图，返回与 `analyses` 一一对应的相对报告目录的路径；未选中或渲染失败的为 `None`。
+/// 单个文件渲染失败只计数提示，不中断报告生成。
+pub fn render_spectrograms(
+    analyses: &[QualityAnalysis],
+    scope: SpectrogramScope,
+    report_dir: &Path,
+    config: &ProcessingConfig,
+    safe_mode: bool,
+) -> Result<Vec<Option<String>>> {
+    let dir = report_dir.join(SPECTROGRAM_DIR_NAME);
+    clear_stale(&dir)?;
+    let selected = analyses.iter().filter(|a| scope.includes(a)).count();
+    if selected == 0 {
+        return Ok(vec![None; analyses.len()]);
+    }
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("无法创建频谱图目录: {}", dir.display()))?;
+    uiprintln!("正在生成频谱图: {selected} 个文件...");
+
+    let results: Vec<Option<Result<String>>> = analyses
+        .par_iter()
+        .enumerate()
+        .map(|(index, analysis)| {
+            scope.includes(analysis).then(|| {
+                let name = format!("{index:05}.png");
+                let output = tempfile::Builder::new()
+                    .prefix("aq_spectrogram_")
+                    .suffix(".png")
+                    .tempfile_in(&dir)
+                    .context("[E_SPECTROGRAM] 无法创建临时文件")?;
+                ffmpeg::render_spectrogram(
+                    &analysis.metrics,
+                    output.path(),
+                    ffmpeg::SPECTROGRAM_FULL_SIZE,
+                    config,
+                )?;
+                let png =
+                    std::fs::read(output.path()).context("[E_SPECTROGRAM] 无法读取渲染结果")?;
+                safe_io::atomic_write_bytes(&dir.join(&name), &png, safe_mode)?;
+                Ok(format!("{SPECTROGRAM_DIR_NAME}/{name}"))
+            })
+        })
+        .collect();
+
+    let mut failures = 0;
+    let paths = results
+        .into_iter()
+        .map(|result| match result {
+            Some(Ok(path)) => Some(path),
+            Some(Err(_)) => {
+                failures += 1;
+                None
+            }
+            None => None,
+        })
+        .collect();
+    uiprintln!(
+        "✅ 频谱图已保存到: {}（{} 个）",
+        dir.display(),
+        selected - failures
+    );
+    if failures > 0 {
+        uiprintln!("⚠️ {failures} 个文件的频谱图生成失败，报告中不显示");
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scope_selection_and_stale_cleanup() {
+        let mut analyses = QualityScorer::new().analyze_files(&[FileMetrics::default()]);
+        analyses[0].status = QualityStatus::Good;
+        assert!(!SpectrogramScope::Flagged.includes(&analyses[0]));
+        assert!(SpectrogramScope::All.includes(&analyses[0]));
+        assert_eq!("ALL".parse(), Ok(SpectrogramScope::All));
+        assert!("some".parse::<SpectrogramScope>().is_err());
+
+        let dir = TempDir::new().expect("tempdir");
+        let spectrograms = dir.path().join(SPECTROGRAM_DIR_NAME);
+        std::fs::create_dir_all(&spectrograms).expect("dir");
+        std::fs::write(spectrograms.join("00000.png"), b"old").expect("stale");
+        std::fs::write(spectrograms.join("notes.txt"), b"keep").expect("other");
+        clear_stale(&spectrograms).expect("clear");
+        assert!(!spectrograms.join("00000.png").exists());
+        assert!(spectrograms.join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,365 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::cache::{FileFingerprint, FingerprintMode};
+use super::scoring::QualityAnalysis;
+
+/// 数据库结构版本（`PRAGMA user_version`），结构变化时递增。
+/// 1：以内容哈希为主键；2：以路径为主键，内容哈希降为普通列。
+const SCHEMA_VERSION: i32 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    path             TEXT PRIMARY KEY,
+    content_hash     TEXT,
+    hash_algorithm   TEXT,
+    size_bytes       INTEGER NOT NULL,
+    last_analyzed_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_files_content_hash ON files(content_hash);
+
+CREATE TABLE IF NOT EXISTS metrics (
+    path             TEXT NOT NULL REFERENCES files(path) ON DELETE CASCADE,
+    audio_stream     INTEGER NOT NULL,
+    cue_track        INTEGER NOT NULL,
+    codec            TEXT,
+    sample_rate_hz   INTEGER,
+    bit_depth        INTEGER,
+    bitrate_kbps     INTEGER,
+    channels         INTEGER,
+    duration_seconds REAL,
+    integrated_lufs  REAL,
+    true_peak_dbtp   REAL,
+    lra              REAL,
+    peak_db          REAL,
+    rms_db           REAL,
+    rms_16k_db       REAL,
+    rms_18k_db       REAL,
+    rms_20k_db       REAL,
+    metrics_json     TEXT NOT NULL,
+    PRIMARY KEY (path, audio_stream, cue_track)
+);
+
+CREATE TABLE IF NOT EXISTS analyses (
+    path           TEXT NOT NULL REFERENCES files(path) ON DELETE CASCADE,
+    audio_stream   INTEGER NOT NULL,
+    cue_track      INTEGER NOT NULL,
+    quality_score  INTEGER NOT NULL,
+    grade          TEXT NOT NULL,
+    status         TEXT NOT NULL,
+    issues         TEXT NOT NULL,
+    notes          TEXT NOT NULL,
+    profile        TEXT NOT NULL,
+    scorer_version INTEGER NOT NULL,
+    confidence     REAL NOT NULL,
+    analyzed_at    TEXT NOT NULL,
+    PRIMARY KEY (path, audio_stream, cue_track)
+);
+CREATE INDEX IF NOT EXISTS idx_analyses_status ON analyses(status);
+CREATE INDEX IF NOT EXISTS idx_analyses_score ON analyses(quality_score);
+";
+
+/// 把版本 1（以内容哈希为主键）的数据迁移到当前结构。同一路径对应多个哈希（文件内容变过）时保留最近一次分析。
+const MIGRATE_FROM_V1: &str = "
+DROP INDEX IF EXISTS idx_files_path;
+DROP INDEX IF EXISTS idx_analyses_status;
+DROP INDEX IF EXISTS idx_analyses_score;
+ALTER TABLE analyses RENAME TO analyses_v1;
+ALTER TABLE metrics RENAME TO metrics_v1;
+ALTER TABLE files RENAME TO files_v1;
+";
+
+const COPY_FROM_V1: &str = "
+INSERT OR REPLACE INTO files (path, content_hash, hash_algorithm, size_bytes, last_analyzed_at)
+    SELECT path,
+           CASE WHEN hash_algorithm = 'path' THEN NULL ELSE content_hash END,
+           NULLIF(hash_algorithm, 'path'),
+           size_bytes, last_analyzed_at
+    FROM files_v1 ORDER BY last_analyzed_at;
+INSERT OR REPLACE INTO metrics
+    SELECT f.path, m.audio_stream, m.cue_track, m.codec, m.sample_rate_hz, m.bit_depth, m.bitrate_kbps,
+           m.channels, m.duration_seconds, m.integrated_lufs, m.true_peak_dbtp, m.lra, m.peak_db, m.rms_db,
+           m.rms_16k_db, m.rms_18k_db, m.rms_20k_db, m.metrics_json
+    FROM metrics_v1 m JOIN files_v1 f USING (content_hash) ORDER BY f.last_analyzed_at;
+INSERT OR REPLACE INTO analyses
+    SELECT f.path, a.audio_stream, a.cue_track, a.quality_score, a.grade, a.status, a.issues, a.notes,
+           a.profile, a.scorer_version, a.confidence, a.analyzed_at
+    FROM analyses_v1 a JOIN files_v1 f USING (content_hash) ORDER BY f.last_analyzed_at;
+DROP TABLE analyses_v1;
+DROP TABLE metrics_v1;
+DROP TABLE files_v1;
+";
+
+/// 把本次结果写入 SQLite 数据库（不存在时创建），返回写入的记录数。
+/// 三张表均以路径为键做 upsert：同一文件再次分析时覆盖旧结果，多次运行可累积到同一个数据库中。
+/// CUE 分轨与多音轨以 `(path, audio_stream, cue_track)` 区分，非 CUE 记录的 `cue_track` 为 0。
+/// `files.content_hash` 记录完整内容指纹（严格模式指纹或 `contentSha256`），没有时为空；
+/// 内容哈希相同、旧路径已不存在的记录视为文件被移动或改名，随之删除（级联删除其指标与结果）。
+pub fn write_sqlite_report(
+    db_path: &Path,
+    analyses: &[QualityAnalysis],
+    fingerprints: &HashMap<String, FileFingerprint>,
+    analyzed_at: &str,
+) -> Result<usize> {
+    let mut conn = Connection::open(db_path)
+        .with_context(|| format!("[E_SQLITE] 无法打开数据库: {}", db_path.display()))?;
+    let version: i32 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("[E_SQLITE] 无法读取数据库版本")?;
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "[E_SQLITE] 数据库结构版本 {version} 高于当前支持的 {SCHEMA_VERSION}，请使用更新版本的程序: {}",
+            db_path.display()
+        );
+    }
+    {
+        let tx = conn.transaction().context("[E_SQLITE] 开启事务失败")?;
+        if version == 1 {
+            tx.execute_batch(MIGRATE_FROM_V1)
+                .context("[E_SQLITE] 迁移旧版数据表失败")?;
+        }
+        tx.execute_batch(SCHEMA)
+            .context("[E_SQLITE] 创建数据表失败")?;
+        if version == 1 {
+            tx.execute_batch(COPY_FROM_V1)
+                .context("[E_SQLITE] 迁移旧版数据失败")?;
+        }
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .context("[E_SQLITE] 写入数据库版本失败")?;
+        tx.commit().context("[E_SQLITE] 提交事务失败")?;
+    }
+    // 外键约束默认关闭，需按连接开启，`ON DELETE CASCADE` 才会生效。
+    conn.pragma_update(None, "foreign_keys", true)
+        .context("[E_SQLITE] 开启外键约束失败")?;
+
+    let tx = conn.transaction().context("[E_SQLITE] 开启事务失败")?;
+    {
+        let mut upsert_file = tx.prepare(
+            "INSERT INTO files (path, content_hash, hash_algorithm, size_bytes, last_analyzed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(path) DO UPDATE SET
+                 content_hash = excluded.content_hash,
+                 hash_algorithm = excluded.hash_algorithm,
+                 size_bytes = excluded.size_bytes,
+                 last_analyzed_at = excluded.last_analyzed_at",
+        )?;
+        let mut same_content =
+            tx.prepare("SELECT path FROM files WHERE content_hash = ?1 AND path <> ?2")?;
+        let mut delete_file = tx.prepare("DELETE FROM files WHERE path = ?1")?;
+        let mut upsert_metrics = tx.prepare(
+            "INSERT OR REPLACE INTO metrics (path, audio_stream, cue_track, codec, sample_rate_hz,
+                 bit_depth, bitrate_kbps, channels, duration_seconds, integrated_lufs, true_peak_dbtp, lra,
+                 peak_db, rms_db, rms_16k_db, rms_18k_db, rms_20k_db, metrics_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+        )?;
+        let mut upsert_analysis = tx.prepare(
+            "INSERT OR REPLACE INTO analyses (path, audio_stream, cue_track, quality_score, grade,
+                 status, issues, notes, profile, scorer_version, confidence, analyzed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+
+        for analysis in analyses {
+            let metrics = &analysis.metrics;
+            let path = &analysis.file_path;
+            let (hash, algorithm) = match fingerprints.get(path) {
+                Some(fp) if fp.mode == FingerprintMode::Strict => (
+                    Some(fp.content_hash.as_str()),
+                    Some(fp.hash_algorithm.as_str()),
+                ),
+                _ => match &metrics.content_sha256 {
+                    Some(hash) => (Some(hash.as_str()), Some("sha256")),
+                    None => (None, None),
+                },
+            };
+            let cue_track = metrics.cue_track.as_ref().map_or(0, |t| t.number);
+            let issues: Vec<String> = analysis.issues.iter().map(|s| s.to_string()).collect();
+
+            upsert_file.execute(params![
+                path,
+                hash,
+                algorithm,
+                metrics.file_size_bytes as i64,
+                analyzed_at
+            ])?;
+            if let Some(hash) = hash {
+                let moved_from: Vec<String> = same_content
+                    .query_map(params![hash, path], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?;
+                for old_path in moved_from {
+                    if !Path::new(&old_path).exists() {
+                        delete_file.execute(params![old_path])?;
+                    }
+                }
+            }
+            upsert_metrics.execute(params![
+                path,
+                metrics.audio_stream_index,
+                cue_track,
+                metrics.codec_name,
+                metrics.sample_rate_hz,
+                metrics.bit_depth,
+                metrics.bitrate_kbps,
+                metrics.channels,
+                metrics.duration_seconds,
+                metrics.integrated_loudness_lufs,
+                metrics.true_peak_dbtp,
+                metrics.lra,
+                metrics.peak_amplitude_db,
+                metrics.overall_rms_db,
+                metrics.rms_db_above_16k,
+                metrics.rms_db_above_18k,
+                metrics.rms_db_above_20k,
+                serde_json::to_string(metrics).context("序列化指标失败")?
+            ])?;
+            upsert_analysis.execute(params![
+                path,
+                metrics.audio_stream_index,
+                cue_track,
+                analysis.quality_score,
+                analysis.grade,
+                analysis.status.to_string(),
+                issues.join("; "),
+                analysis.notes,
+                analysis.profile,
+                analysis.scorer_version,
+                analysis.confidence,
+                analyzed_at
+            ])?;
+        }
+    }
+    tx.commit().context("[E_SQLITE] 提交事务失败")?;
+    Ok(analyses.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sqlite_report_upserts_by_path() {
+        let dir = TempDir::new().expect("tempdir");
+        let db = dir.path().join("results.sqlite");
+        let metrics = |path: &Path, lufs: f64| FileMetrics {
+            file_path: path.to_string_lossy().into_owned(),
+            integrated_loudness_lufs: Some(lufs),
+            content_sha256: Some("abc".to_string()),
+            ..FileMetrics::default()
+        };
+        let (original, copy) = (dir.path().join("a.flac"), dir.path().join("copy.flac"));
+        std::fs::write(&original, b"audio").expect("write");
+        std::fs::write(&copy, b"audio").expect("write");
+        let scorer = QualityScorer::new();
+        let first = scorer.analyze_files(&[metrics(&original, -9.0), metrics(&copy, -9.0)]);
+        write_sqlite_report(&db, &first, &HashMap::new(), "2026-10-16").expect("first write");
+
+        let conn = Connection::open(&db).expect("open");
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM analyses", [], |row| row.get(0))
+                .expect("count")
+        };
+        // 内容相同的两份拷贝各占一条记录。
+        assert_eq!(count(&conn), 2);
+
+        // 原文件移动到新路径后再次分析：旧路径的记录连同指标一并删除，拷贝保留。
+        let moved = dir.path().join("moved.flac");
+        std::fs::rename(&original, &moved).expect("move");
+        let second = scorer.analyze_files(&[metrics(&moved, -30.0)]);
+        write_sqlite_report(&db, &second, &HashMap::new(), "2026-10-17").expect("second write");
+
+        let paths: Vec<String> = conn
+            .prepare("SELECT path FROM files ORDER BY path")
+            .expect("prepare")
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("paths");
+        assert_eq!(paths, [copy.to_string_lossy(), moved.to_string_lossy()]);
+        assert_eq!(count(&conn), 2);
+        let (score, analyzed_at, lufs): (i32, String, f64) = conn
+            .query_row(
+                "SELECT a.quality_score, a.analyzed_at, m.integrated_lufs
+                 FROM analyses a JOIN metrics m USING (path, audio_stream, cue_track)
+                 WHERE path = ?1",
+                params![moved.to_string_lossy()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .expect("query");
+        assert_eq!(score, second[0].quality_score);
+        assert_eq!(analyzed_at, "2026-10-17");
+        assert_eq!(lufs, -30.0);
+        let orphans: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM metrics WHERE path NOT IN (SELECT path FROM files)",
+                [],
+                |row| row.get(0),
+            )
+            .expect("orphans");
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn test_sqlite_report_migrates_content_hash_keys() {
+        let dir = TempDir::new().expect("tempdir");
+        let db = dir.path().join("results.sqlite");
+        {
+            let conn = Connection::open(&db).expect("open");
+            conn.execute_batch(
+                "CREATE TABLE files (content_hash TEXT PRIMARY KEY, hash_algorithm TEXT NOT NULL,
+                     path TEXT NOT NULL, size_bytes INTEGER NOT NULL, last_analyzed_at TEXT NOT NULL);
+                 CREATE INDEX idx_files_path ON files(path);
+                 CREATE TABLE metrics (content_hash TEXT NOT NULL, audio_stream INTEGER NOT NULL,
+                     cue_track INTEGER NOT NULL, codec TEXT, sample_rate_hz INTEGER, bit_depth INTEGER,
+                     bitrate_kbps INTEGER, channels INTEGER, duration_seconds REAL, integrated_lufs REAL,
+                     true_peak_dbtp REAL, lra REAL, peak_db REAL, rms_db REAL, rms_16k_db REAL,
+                     rms_18k_db REAL, rms_20k_db REAL, metrics_json TEXT NOT NULL,
+                     PRIMARY KEY (content_hash, audio_stream, cue_track));
+                 CREATE TABLE analyses (content_hash TEXT NOT NULL, audio_stream INTEGER NOT NULL,
+                     cue_track INTEGER NOT NULL, quality_score INTEGER NOT NULL, grade TEXT NOT NULL,
+                     status TEXT NOT NULL, issues TEXT NOT NULL, notes TEXT NOT NULL, profile TEXT NOT NULL,
+                     scorer_version INTEGER NOT NULL, confidence REAL NOT NULL, analyzed_at TEXT NOT NULL,
+                     PRIMARY KEY (content_hash, audio_stream, cue_track));
+                 CREATE INDEX idx_analyses_status ON analyses(status);
+                 INSERT INTO files VALUES ('abc', 'sha256', '/m/a.flac', 1, '2026-10-01');
+                 INSERT INTO files VALUES ('path:/m/b.flac', 'path', '/m/b.flac', 2, '2026-10-01');
+                 INSERT INTO metrics (content_hash, audio_stream, cue_track, metrics_json)
+                     VALUES ('abc', 0, 0, '{}');
+                 INSERT INTO analyses VALUES ('path:/m/b.flac', 0, 0, 80, 'B', '正常', '', '', 'pop', 1, 1.0,
+                     '2026-10-01');
+                 PRAGMA user_version = 1;",
+            )
+            .expect("v1 schema");
+        }
+        write_sqlite_report(&db, &[], &HashMap::new(), "2026-10-17").expect("migrate");
+
+        let conn = Connection::open(&db).expect("open");
+        let version: i32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .expect("version");
+        assert_eq!(version, SCHEMA_VERSION);
+        let hash: Option<String> = conn
+            .query_row(
+                "SELECT content_hash FROM files WHERE path = '/m/b.flac'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("path-keyed file");
+        assert_eq!(hash, None);
+        let metrics_path: String = conn
+            .query_row("SELECT path FROM metrics", [], |row| row.get(0))
+            .expect("metrics");
+        assert_eq!(metrics_path, "/m/a.flac");
+        let score: i32 = conn
+            .query_row(
+                "SELECT quality_score FROM analyses WHERE path = '/m/b.flac'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("analyses");
+        assert_eq!(score, 80);
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+use super::safe_io;
+use super::scoring::{QualityAnalysis, QualityStatus};
+
+/// 机器可读的摘要统计文件名，与其他报告一起写在报告目录中。
+pub const SUMMARY_JSON_FILE_NAME: &str = "audio_quality_summary.json";
+
+/// 提取阶段的吞吐量输入：本次实际处理的文件数、字节数与耗时（不含合并进来的旧结果）。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Throughput {
+    pub files: usize,
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    pub fn files_per_minute(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.files as f64 / seconds * 60.0
+        } else {
+            0.0
+        }
+    }
+
+    pub fn mb_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.bytes as f64 / 1_048_576.0 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// 分数的集中趋势与离散程度。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreStats {
+    pub mean: f64,
+    pub median: f64,
+    /// 总体标准差。
+    pub std_dev: f64,
+    pub min: i32,
+    pub max: i32,
+    pub p10: f64,
+    pub p25: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+/// 某个状态的文件数与平均分。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusAverage {
+    pub status: QualityStatus,
+    pub count: usize,
+    pub average_score: f64,
+}
+
+/// 写入 JSON 的吞吐量。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThroughputStats {
+    pub files: usize,
+    pub bytes: u64,
+    pub elapsed_seconds: f64,
+    pub files_per_minute: f64,
+    pub mb_per_second: f64,
+}
+
+/// 一批结果的摘要统计，终端摘要与 `audio_quality_summary.json` 使用同一份数据。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryStats {
+    pub file_count: usize,
+    /// 没有结果时为空。
+    pub scores: Option<ScoreStats>,
+    /// 各状态的平均分，按文件数从多到少排列。
+    pub status_averages: Vec<StatusAverage>,
+    /// 已分析音频的总时长（秒），缺少时长的文件不计入。
+    pub total_duration_seconds: f64,
+    pub throughput: Option<ThroughputStats>,
+    /// 本次分析使用的 FFmpeg/FFprobe。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<Toolchain>,
+}
+
+/// 实际使用的外部工具路径与版本，便于复现结果与排查版本差异。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Toolchain {
+    pub ffmpeg_path: String,
+    pub ffmpeg_version: Option<String>,
+    pub ffprobe_path: Option<String>,
+    pub ffprobe_version: Option<String>,
+}
+
+impl SummaryStats {
+    pub fn compute(analyses: &[QualityAnalysis], throughput: Option<Throughput>) -> Self {
+        let mut scores: Vec<i32> = analyses.iter().map(|a| a.quality_score).collect();
+        scores.sort_unstable();
+
+        let mut status_averages: Vec<StatusAverage> = Vec::new();
+        for analysis in analyses {
+            match status_averages
+                .iter_mut()
+                .find(|entry| entry.status == analysis.status)
+            {
+                // 先累计总分，最后再除以数量。
+                Some(entry) => {
+                    entry.count += 1;
+                    entry.average_score += f64::from(analysis.quality_score);
+                }
+                None => status_averages.push(StatusAverage {
+                    status: analysis.status.clone(),
+                    count: 1,
+                    average_score: f64::from(analysis.quality_score),
+                }),
+            }
+        }
+        for entry in &mut status_averages {
+            entry.average_score /= entry.count as f64;
+        }
+        status_averages.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| b.average_score.total_cmp(&a.average_score))
+        });
+
+        Self {
+            file_count: analyses.len(),
+            scores: score_stats(&scores),
+            status_averages,
+            total_duration_seconds: analyses
+                .iter()
+                .filter_map(|a| a.metrics.duration_seconds)
+                .filter(|d| d.is_finite() && *d > 0.0)
+                .sum(),
+            throughput: throughput.map(|t| ThroughputStats {
+                files: t.files,
+                bytes: t.bytes,
+                elapsed_seconds: t.elapsed.as_secs_f64(),
+                files_per_minute: t.files_per_minute(),
+                mb_per_second: t.mb_per_second(),
+            }),
+            toolchain: None,
+        }
+    }
+
+    pub fn write_json(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("序列化摘要统计失败")?;
+        safe_io::atomic_write_string(path, &content, safe_mode)?;
+        uiprintln!("✅ 摘要统计已保存到: {}", path.display());
+        Ok(())
+    }
+}
+
+/// 已排序分数的统计量；分位数按相邻两个分数线性插值。
+fn score_stats(sorted: &[i32]) -> Option<ScoreStats> {
+    let (&min, &max) = (sorted.first()?, sorted.last()?);
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().map(|s| f64::from(*s)).sum::<f64>() / n;
+    let variance = sorted
+        .iter()
+        .map(|s| (f64::from(*s) - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    Some(ScoreStats {
+        mean,
+        median: percentile(sorted, 50.0),
+        std_dev: variance.sqrt(),
+        min,
+        max,
+        p10: percentile(sorted, 10.0),
+        p25: percentile(sorted, 25.0),
+        p75: percentile(sorted, 75.0),
+        p90: percentile(sorted, 90.0),
+    })
+}
+
+fn percentile(sorted: &[i32], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+    let fraction = rank - low as f64;
+    f64::from(sorted[low]) + (f64::from(sorted[high]) - f64::from(sorted[low])) * fraction
+}
+
+/// 时长显示为 `H:MM:SS`。
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    format!("{}:{:02}:{:02}", total / 3600, total / 60 % 60, total % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_summary_stats() {
+        let scorer = QualityScorer::new();
+        let mut analyses = scorer.analyze_files(
+            &(0..4)
+                .map(|i| FileMetrics {
+                    file_path: format!("/m/{i}.flac"),
+                    duration_seconds: Some(60.0),
+                    ..FileMetrics::default()
+                })
+                .collect::<Vec<_>>(),
+        );
+        for (analysis, score) in analyses.iter_mut().zip([90, 70, 80, 100]) {
+            analysis.quality_score = score;
+        }
+        analyses[0].status = QualityStatus::Good;
+        analyses[3].status = QualityStatus::Good;
+        analyses[1].status = QualityStatus::Clipped;
+        analyses[2].status = QualityStatus::Clipped;
+
+        let stats = SummaryStats::compute(
+            &analyses,
+            Some(Throughput {
+                files: 4,
+                bytes: 2 * 1_048_576,
+                elapsed: Duration::from_secs(2),
+            }),
+        );
+        let scores = stats.scores.as_ref().expect("scores");
+        assert_eq!(scores.mean, 85.0);
+        assert_eq!(scores.median, 85.0);
+        assert_eq!(scores.p25, 77.5);
+        assert_eq!(scores.p75, 92.5);
+        assert!((scores.std_dev - 125f64.sqrt()).abs() < 1e-9);
+        assert_eq!((scores.min, scores.max), (70, 100));
+        assert_eq!(stats.status_averages[0].status, QualityStatus::Good);
+        assert_eq!(stats.status_averages[0].average_score, 95.0);
+        assert_eq!(stats.status_averages[1].average_score, 75.0);
+        assert_eq!(stats.total_duration_seconds, 240.0);
+        let throughput = stats.throughput.as_ref().expect("throughput");
+        assert_eq!(throughput.files_per_minute, 120.0);
+        assert_eq!(throughput.mb_per_second, 1.0);
+        assert_eq!(format_duration(stats.total_duration_seconds), "0:04:00");
+
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join(SUMMARY_JSON_FILE_NAME);
+        stats.write_json(&path, true).expect("write");
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read")).expect("json");
+        assert_eq!(json["scores"]["median"], 85.0);
+        assert_eq!(json["statusAverages"][1]["status"], "已削波");
+        assert_eq!(json["throughput"]["filesPerMinute"], 120.0);
+    }
+
+    #[test]
+    fn test_summary_stats_empty() {
+        let stats = SummaryStats::compute(&[], None);
+        assert_eq!(stats.file_count, 0);
+        assert!(stats.scores.is_none());
+        assert!(stats.throughput.is_none());
+    }
+}
@@ -0,0 +1,424 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::i18n::{self, Lang};
+use super::report::{grade_distribution, status_distribution};
+use super::safe_io;
+use super::scoring::QualityAnalysis;
+
+/// 内置的报告模板；自定义模板可从这些模板复制后修改。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinTemplate {
+    Html,
+    Markdown,
+    Text,
+}
+
+impl BuiltinTemplate {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown => "markdown",
+            Self::Text => "text",
+        }
+    }
+
+    /// 模板名，同时决定输出文件名；以 `.html` 结尾的模板自动转义 HTML。
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::Html => "audio_quality_report.html",
+            Self::Markdown => "audio_quality_report.md",
+            Self::Text => "audio_quality_report.txt",
+        }
+    }
+
+    pub fn source(self) -> &'static str {
+        match self {
+            Self::Html => include_str!("templates/report.html.tera"),
+            Self::Markdown => include_str!("templates/report.md.tera"),
+            Self::Text => include_str!("templates/report.txt.tera"),
+        }
+    }
+}
+
+/// `--template` 指定的模板：内置格式名或模板文件路径。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportTemplate {
+    Builtin(BuiltinTemplate),
+    /// 输出文件名为模板文件名去掉 `.tera` 后缀（如 `summary.md.tera` → `summary.md`）。
+    File(PathBuf),
+}
+
+impl std::str::FromStr for ReportTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let builtin = match s.to_ascii_lowercase().as_str() {
+            "html" => Some(BuiltinTemplate::Html),
+            "markdown" | "md" => Some(BuiltinTemplate::Markdown),
+            "text" | "txt" => Some(BuiltinTemplate::Text),
+            _ => None,
+        };
+        match builtin {
+            Some(builtin) => Ok(Self::Builtin(builtin)),
+            None if Path::new(s).is_file() => Ok(Self::File(PathBuf::from(s))),
+            None => Err(format!(
+                "未知的模板: {s}（可用 html、markdown、text 或模板文件路径）"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ReportTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Builtin(builtin) => f.write_str(builtin.as_str()),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl ReportTemplate {
+    /// 返回模板名（即输出文件名）与模板内容。
+    fn load(&self) -> Result<(String, String)> {
+        match self {
+            Self::Builtin(builtin) => Ok((
+                builtin.file_name().to_string(),
+                builtin.source().to_string(),
+            )),
+            Self::File(path) => {
+                let source = std::fs::read_to_string(path)
+                    .with_context(|| format!("[E_TEMPLATE] 无法读取模板: {}", path.display()))?;
+                let file_name = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("report.txt.tera");
+                let name = file_name.strip_suffix(".tera").unwrap_or(file_name);
+                Ok((name.to_string(), source))
+            }
+        }
+    }
+}
+
+/// 模板中的标题与列名：(键, 中文, 英文)。
+const LABELS: [(&str, &str, &str); 22] = [
+    ("title", "音频质量报告", "Audio quality report"),
+    ("root", "分析目录", "Analyzed folder"),
+    ("generated_at", "生成时间", "Generated at"),
+    ("summary", "摘要", "Summary"),
+    ("total", "文件数", "Files"),
+    ("average_score", "平均分数", "Average score"),
+    ("min_score", "最低分数", "Lowest score"),
+    ("max_score", "最高分数", "Highest score"),
+    ("status_distribution", "质量状态分布", "Status distribution"),
+    ("grade_distribution", "等级分布", "Grade distribution"),
+    ("files", "文件列表", "Files"),
+    ("score", "质量分", "Score"),
+    ("grade", "等级", "Grade"),
+    ("status", "状态", "Status"),
+    ("file", "文件", "File"),
+    ("lufs", "响度(LUFS)", "Loudness (LUFS)"),
+    ("true_peak", "真峰值(dBTP)", "True peak (dBTP)"),
+    ("bitrate", "码率(kbps)", "Bitrate (kbps)"),
+    ("issues", "全部问题", "Issues"),
+    ("notes", "备注", "Notes"),
+    ("spectrogram", "频谱图", "Spectrogram"),
+    ("close", "点击图片关闭", "click the image to close"),
+];
+
+#[derive(Debug, Serialize)]
+struct Bucket {
+    name: String,
+    count: usize,
+    percentage: f64,
+}
+
+/// 模板中的单个文件，字段名使用英文 snake_case，与报告语言无关。
+#[derive(Debug, Serialize)]
+struct TemplateFile {
+    path: String,
+    name: String,
+    cue_track: Option<String>,
+    score: i32,
+    grade: String,
+    status: String,
+    issues: Vec<String>,
+    notes: String,
+    confidence: f64,
+    integrated_lufs: Option<f64>,
+    true_peak_dbtp: Option<f64>,
+    lra: Option<f64>,
+    bitrate_kbps: Option<u32>,
+    sample_rate_hz: Option<u32>,
+    bit_depth: Option<u32>,
+    codec: Option<String>,
+    duration_seconds: Option<f64>,
+    /// 相对报告目录的频谱图路径（使用 `--spectrograms` 且已渲染时）。
+    spectrogram: Option<String>,
+}
+
+/// 模板可用的全部数据；结构见 API 文档「模板报告 API」。
+#[derive(Debug, Serialize)]
+struct ReportContext {
+    lang: &'static str,
+    labels: BTreeMap<&'static str, &'static str>,
+    tool_version: &'static str,
+    root: String,
+    generated_at: String,
+    total: usize,
+    average_score: Option<f64>,
+    min_score: Option<i32>,
+    max_score: Option<i32>,
+    status_distribution: Vec<Bucket>,
+    grade_distribution: Vec<Bucket>,
+    files: Vec<TemplateFile>,
+    /// 是否有文件带频谱图，供模板决定是否显示频谱图列。
+    has_spectrograms: bool,
+}
+
+/// 渲染模板所需的本次运行数据。
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateData<'a> {
+    pub analyses: &'a [QualityAnalysis],
+    /// 与 `analyses` 一一对应的频谱图相对路径，未生成频谱图时为空。
+    pub spectrograms: &'a [Option<String>],
+    pub root: &'a Path,
+    pub generated_at: &'a str,
+    pub lang: Lang,
+}
+
+impl ReportContext {
+    fn new(data: TemplateData<'_>) -> Self {
+        let TemplateData {
+            analyses,
+            spectrograms,
+            root,
+            generated_at,
+            lang,
+        } = data;
+        let total = analyses.len();
+        let bucket = |(name, count): (String, usize)| Bucket {
+            name,
+            count,
+            percentage: count as f64 / total.max(1) as f64 * 100.0,
+        };
+        let scores = analyses.iter().map(|a| a.quality_score);
+        Self {
+            lang: lang.as_str(),
+            labels: LABELS
+                .iter()
+                .map(|(key, zh, en)| (*key, lang.pick(zh, en)))
+                .collect(),
+            tool_version: env!("CARGO_PKG_VERSION"),
+            root: root.display().to_string(),
+            generated_at: generated_at.to_string(),
+            total,
+            average_score: (total > 0)
+                .then(|| scores.clone().map(f64::from).sum::<f64>() / total as f64),
+            min_score: scores.clone().min(),
+            max_score: scores.max(),
+            status_distribution: status_distribution(analyses, lang)
+                .into_iter()
+                .map(bucket)
+                .collect(),
+            grade_distribution: grade_distribution(analyses)
+                .into_iter()
+                .map(bucket)
+                .collect(),
+            has_spectrograms: spectrograms.iter().any(Option::is_some),
+            files: analyses
+                .iter()
+                .enumerate()
+                .map(|(index, analysis)| {
+                    let metrics = &analysis.metrics;
+                    TemplateFile {
+                        path: analysis.file_path.clone(),
+                        name: Path::new(&analysis.file_path)
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| analysis.file_path.clone()),
+                        cue_track: metrics.cue_track.as_ref().map(|track| track.label()),
+                        score: analysis.quality_score,
+                        grade: analysis.grade.clone(),
+                        status: i18n::status_label(&analysis.status, lang).to_string(),
+                        issues: analysis
+                            .issues
+                            .iter()
+                            .map(|issue| i18n::status_label(issue, lang).to_string())
+                            .collect(),
+                        notes: analysis.notes.clone(),
+                        confidence: analysis.confidence,
+                        integrated_lufs: metrics.integrated_loudness_lufs,
+                        true_peak_dbtp: metrics.true_peak_dbtp,
+                        lra: metrics.lra,
+                        bitrate_kbps: metrics.bitrate_kbps,
+                        sample_rate_hz: metrics.sample_rate_hz,
+                        bit_depth: metrics.bit_depth,
+                        codec: metrics.codec_name.clone(),
+                        duration_seconds: metrics.duration_seconds,
+                        spectrogram: spectrograms.get(index).cloned().flatten(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 读取并解析模板，返回输出文件名与解析好的模板。
+fn compile(template: &ReportTemplate) -> Result<(String, tera::Tera)> {
+    let (name, source) = template.load()?;
+    let mut tera = tera::Tera::default();
+    tera.add_raw_template(&name, &source)
+        .map_err(|e| anyhow::anyhow!("[E_TEMPLATE] 模板解析失败 ({template}): {e:#}"))?;
+    Ok((name, tera))
+}
+
+/// 在分析开始前检查模板：都能解析，且输出文件名互不相同（否则后写的报告会覆盖先写的）。
+pub fn validate(templates: &[ReportTemplate]) -> Result<()> {
+    let mut seen: BTreeMap<String, &ReportTemplate> = BTreeMap::new();
+    for template in templates {
+        let (name, _) = compile(template)?;
+        if let Some(previous) = seen.insert(name.clone(), template) {
+            return Err(anyhow::anyhow!(
+                "[E_TEMPLATE] 模板 {previous} 与 {template} 的输出文件名相同: {name}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 用模板渲染报告，返回输出文件名与内容。
+pub fn render_report(
+    template: &ReportTemplate,
+    data: TemplateData<'_>,
+) -> Result<(String, String)> {
+    let (name, tera) = compile(template)?;
+    let context = tera::Context::from_serialize(ReportContext::new(data))
+        .context("[E_TEMPLATE] 构建模板数据失败")?;
+    let rendered = tera
+        .render(&name, &context)
+        .map_err(|e| anyhow::anyhow!("[E_TEMPLATE] 模板渲染失败 ({template}): {e:?}"))?;
+    Ok((name, rendered))
+}
+
+/// 渲染模板并写入报告目录，返回写出的路径。
+pub fn write_template_report(
+    template: &ReportTemplate,
+    data: TemplateData<'_>,
+    report_dir: &Path,
+    safe_mode: bool,
+) -> Result<PathBuf> {
+    let (name, rendered) = render_report(template, data)?;
+    let path = report_dir.join(name);
+    safe_io::atomic_write_string(&path, &rendered, safe_mode)?;
+    uiprintln!("✅ 模板报告已保存到: {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+    use tempfile::TempDir;
+
+    fn analyses() -> Vec<QualityAnalysis> {
+        QualityScorer::new().analyze_files(&[FileMetrics {
+            file_path: "/m/<A&B>.flac".to_string(),
+            integrated_loudness_lufs: Some(-14.0),
+            ..FileMetrics::default()
+        }])
+    }
+
+    fn data<'a>(
+        analyses: &'a [QualityAnalysis],
+        spectrograms: &'a [Option<String>],
+        lang: Lang,
+    ) -> TemplateData<'a> {
+        TemplateData {
+            analyses,
+            spectrograms,
+            root: Path::new("/m"),
+            generated_at: "2026-10-17 12:00:00",
+            lang,
+        }
+    }
+
+    #[test]
+    fn test_builtin_templates_render_and_escape_html() {
+        let analyses = analyses();
+        let spectrograms = [Some("spectrograms/00000.png".to_string())];
+        for builtin in [
+            BuiltinTemplate::Html,
+            BuiltinTemplate::Markdown,
+            BuiltinTemplate::Text,
+        ] {
+            let (name, rendered) = render_report(
+                &ReportTemplate::Builtin(builtin),
+                data(&analyses, &spectrograms, Lang::Zh),
+            )
+            .expect("render");
+            assert_eq!(name, builtin.file_name());
+            assert!(rendered.contains("音频质量报告"), "{name}");
+            if builtin == BuiltinTemplate::Html {
+                assert!(rendered.contains("&lt;A&amp;B&gt;.flac"));
+                assert!(!rendered.contains("<A&B>"));
+                assert!(rendered.contains("<img src=\"spectrograms&#x2F;00000.png\""));
+                assert!(rendered.contains("id=\"spectrogram-1\""));
+            } else {
+                assert!(rendered.contains("<A&B>.flac"), "{name}");
+            }
+        }
+        let (_, without) = render_report(
+            &ReportTemplate::Builtin(BuiltinTemplate::Html),
+            data(&analyses, &[], Lang::Zh),
+        )
+        .expect("render");
+        assert!(!without.contains("<img"));
+    }
+
+    #[test]
+    fn test_custom_template_file_names_output_and_reports_errors() {
+        let analyses = analyses();
+        let dir = TempDir::new().expect("tempdir");
+        let template_path = dir.path().join("brief.md.tera");
+        std::fs::write(
+            &template_path,
+            "{{ labels.total }}={{ total }}{% for f in files %};{{ f.name }} {{ f.score }}{% endfor %}",
+        )
+        .expect("write template");
+        let template: ReportTemplate = template_path
+            .to_str()
+            .expect("utf-8 path")
+            .parse()
+            .expect("parse");
+        let path =
+            write_template_report(&template, data(&analyses, &[], Lang::En), dir.path(), true)
+                .expect("write");
+        assert_eq!(path, dir.path().join("brief.md"));
+        let content = std::fs::read_to_string(&path).expect("read");
+        assert!(content.starts_with("Files=1;<A&B>.flac "));
+
+        let html_named = dir.path().join("audio_quality_report.html.tera");
+        std::fs::write(&html_named, "{{ total }}").expect("write template");
+        let html_named: ReportTemplate = html_named
+            .to_str()
+            .expect("utf-8 path")
+            .parse()
+            .expect("parse");
+        validate(&[template.clone(), "html".parse().expect("builtin")]).expect("distinct names");
+        let err = validate(&["html".parse().expect("builtin"), html_named])
+            .expect_err("duplicate output name");
+        assert!(err.to_string().contains("audio_quality_report.html"));
+
+        std::fs::write(&template_path, "{% for f in files %}").expect("write broken");
+        assert!(validate(std::slice::from_ref(&template)).is_err());
+        let err =
+            write_template_report(&template, data(&analyses, &[], Lang::Zh), dir.path(), true)
+                .expect_err("broken template");
+        assert!(err.to_string().contains("E_TEMPLATE"));
+        assert!("nope".parse::<ReportTemplate>().is_err());
+    }
+}
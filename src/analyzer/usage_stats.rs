@@ -0,0 +1,166 @@
+use crate::analyzer::safe_io;
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const USAGE_STATS_VERSION: u32 = 1;
+
+/// 本地使用统计文件名（仅写入磁盘，从不联网上报）。
+pub const USAGE_STATS_FILE_NAME: &str = "audio_quality_usage_stats.json";
+
+/// 单次运行的统计输入。
+#[derive(Debug, Default, Clone)]
+pub struct RunUsage {
+    pub files_total: u64,
+    pub files_failed: u64,
+    pub cache_hits: u64,
+    pub bytes_total: u64,
+    pub wall_time_ms: u64,
+    pub error_codes: Vec<String>,
+}
+
+/// 跨运行累计的使用统计，便于用户附在问题反馈中。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStats {
+    version: u32,
+    pub tool_version: String,
+    pub platform: String,
+    pub runs: u64,
+    pub files_total: u64,
+    pub files_failed: u64,
+    pub cache_hits: u64,
+    pub bytes_total: u64,
+    pub wall_time_ms_total: u64,
+    pub average_files_per_minute: f64,
+    pub average_mb_per_second: f64,
+    pub error_counts: BTreeMap<String, u64>,
+    pub first_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+}
+
+impl Default for UsageStats {
+    fn default() -> Self {
+        Self {
+            version: USAGE_STATS_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            runs: 0,
+            files_total: 0,
+            files_failed: 0,
+            cache_hits: 0,
+            bytes_total: 0,
+            wall_time_ms_total: 0,
+            average_files_per_minute: 0.0,
+            average_mb_per_second: 0.0,
+            error_counts: BTreeMap::new(),
+            first_run_at: None,
+            last_run_at: None,
+        }
+    }
+}
+
+impl UsageStats {
+    /// 读取已有统计；文件缺失、损坏或版本不符时从零开始，统计文件不应阻塞分析。
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<UsageStats>(&content).ok())
+            .filter(|stats| stats.version == USAGE_STATS_VERSION)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("序列化使用统计失败")?;
+        safe_io::atomic_write_string(path, &content, safe_mode)
+    }
+
+    pub fn record_run(&mut self, run: &RunUsage) {
+        let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        if self.first_run_at.is_none() {
+            self.first_run_at = Some(now.clone());
+        }
+        self.last_run_at = Some(now);
+        self.tool_version = env!("CARGO_PKG_VERSION").to_string();
+
+        self.runs += 1;
+        self.files_total += run.files_total;
+        self.files_failed += run.files_failed;
+        self.cache_hits += run.cache_hits;
+        self.bytes_total += run.bytes_total;
+        self.wall_time_ms_total += run.wall_time_ms;
+
+        for code in &run.error_codes {
+            *self.error_counts.entry(code.clone()).or_insert(0) += 1;
+        }
+
+        let seconds = self.wall_time_ms_total as f64 / 1000.0;
+        if seconds > 0.0 {
+            self.average_files_per_minute = self.files_total as f64 / seconds * 60.0;
+            self.average_mb_per_second = self.bytes_total as f64 / 1_048_576.0 / seconds;
+        }
+    }
+
+    /// 出现次数最多的错误码（次数降序，同次数按错误码排序）。
+    pub fn top_errors(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut errors: Vec<(&str, u64)> = self
+            .error_counts
+            .iter()
+            .map(|(code, count)| (code.as_str(), *count))
+            .collect();
+        errors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        errors.truncate(n);
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_run_accumulates() {
+        let mut stats = UsageStats::default();
+        stats.record_run(&RunUsage {
+            files_total: 10,
+            files_failed: 1,
+            cache_hits: 2,
+            bytes_total: 10 * 1_048_576,
+            wall_time_ms: 5_000,
+            error_codes: vec!["E_TIMEOUT".to_string(), "E_TIMEOUT".to_string()],
+        });
+        stats.record_run(&RunUsage {
+            files_total: 20,
+            wall_time_ms: 5_000,
+            error_codes: vec!["E_PARSE_STATS".to_string()],
+            ..RunUsage::default()
+        });
+
+        assert_eq!(stats.runs, 2);
+        assert_eq!(stats.files_total, 30);
+        assert!((stats.average_files_per_minute - 180.0).abs() < 1e-9);
+        assert_eq!(stats.top_errors(1), vec![("E_TIMEOUT", 2)]);
+    }
+
+    #[test]
+    fn test_load_missing_or_corrupt_starts_fresh() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join(USAGE_STATS_FILE_NAME);
+        assert_eq!(UsageStats::load(&path).runs, 0);
+
+        std::fs::write(&path, "not json").expect("write");
+        assert_eq!(UsageStats::load(&path).runs, 0);
+
+        let mut stats = UsageStats::default();
+        stats.record_run(&RunUsage {
+            files_total: 1,
+            wall_time_ms: 10,
+            ..RunUsage::default()
+        });
+        stats.save(&path, true).expect("save");
+        assert_eq!(UsageStats::load(&path).runs, 1);
+    }
+}
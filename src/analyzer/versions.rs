@@ -0,0 +1,337 @@
+use comfy_table::{presets, Cell, CellAlignment, ContentArrangement, Table};
+use std::path::Path;
+
+use super::scoring::{self, QualityAnalysis, QualityStatus};
+use super::summary;
+
+/// 两侧时长相差超过该值（秒）时视为不同的录音，不再比较谁由谁衍生。
+const SAME_RECORDING_TOLERANCE_SECONDS: f64 = 2.0;
+/// 16 kHz 以上能量比另一侧低出该值（dB）时，视为高频被有损编码截断。
+const HF_DERIVATIVE_GAP_DB: f64 = 20.0;
+/// 分数相差不足该值时视为质量相当。
+const SCORE_MARGIN: i32 = 5;
+
+/// 对比中的一侧：`A` 为第一个参数，`B` 为第二个。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    pub fn label(self) -> &'static str {
+        match self {
+            Side::A => "A",
+            Side::B => "B",
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        }
+    }
+}
+
+/// 两个版本的取舍结论。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// 时长明显不同，多半不是同一录音。
+    DifferentRecordings { duration_gap: f64 },
+    /// `derived` 疑似由另一侧经有损编码得到（有损编码，或无损封装但高频被截断）。
+    LossyDerivative { derived: Side },
+    /// 一侧分数明显更高。
+    Better { side: Side, margin: i32 },
+    /// 分数接近，保留哪个都可以。
+    Equivalent,
+}
+
+impl Verdict {
+    pub fn describe(&self) -> String {
+        match self {
+            Verdict::DifferentRecordings { duration_gap } => {
+                format!("两者时长相差 {duration_gap:.1} 秒，可能不是同一录音，结论仅供参考")
+            }
+            Verdict::LossyDerivative { derived } => format!(
+                "{} 疑似为 {} 的有损衍生版本，建议保留 {}",
+                derived.label(),
+                derived.other().label(),
+                derived.other().label()
+            ),
+            Verdict::Better { side, margin } => {
+                format!(
+                    "{} 质量更好（分数高 {margin}），建议保留 {}",
+                    side.label(),
+                    side.label()
+                )
+            }
+            Verdict::Equivalent => "两者质量相当，保留哪个都可以".to_string(),
+        }
+    }
+}
+
+/// 同一录音的两个版本（如 FLAC 与 MP3）的逐项对比。
+pub struct VersionComparison<'a> {
+    pub a: &'a QualityAnalysis,
+    pub b: &'a QualityAnalysis,
+}
+
+impl VersionComparison<'_> {
+    pub fn verdict(&self) -> Verdict {
+        let (a, b) = (&self.a.metrics, &self.b.metrics);
+        if let (Some(da), Some(db)) = (a.duration_seconds, b.duration_seconds) {
+            let duration_gap = (da - db).abs();
+            if duration_gap > SAME_RECORDING_TOLERANCE_SECONDS {
+                return Verdict::DifferentRecordings { duration_gap };
+            }
+        }
+        for derived in [Side::B, Side::A] {
+            let (source, candidate) = match derived {
+                Side::A => (self.b, self.a),
+                Side::B => (self.a, self.b),
+            };
+            if is_clean_lossless(source)
+                && (!is_clean_lossless(candidate) || hf_gap(source, candidate))
+            {
+                return Verdict::LossyDerivative { derived };
+            }
+        }
+        let margin = self.b.quality_score - self.a.quality_score;
+        match margin {
+            m if m >= SCORE_MARGIN => Verdict::Better {
+                side: Side::B,
+                margin: m,
+            },
+            m if m <= -SCORE_MARGIN => Verdict::Better {
+                side: Side::A,
+                margin: -m,
+            },
+            _ => Verdict::Equivalent,
+        }
+    }
+
+    /// 逐项指标：名称、A、B 与差值（B − A，仅数值指标）。
+    pub fn rows(&self) -> Vec<[String; 4]> {
+        let (a, b) = (&self.a.metrics, &self.b.metrics);
+        let mut rows = vec![
+            text_row(
+                "文件",
+                file_name(&self.a.file_path),
+                file_name(&self.b.file_path),
+            ),
+            number_row(
+                "质量分",
+                Some(f64::from(self.a.quality_score)),
+                Some(f64::from(self.b.quality_score)),
+                "",
+                0,
+            ),
+            text_row("状态", self.a.status.to_string(), self.b.status.to_string()),
+            text_row(
+                "编码 / 容器",
+                codec(a.codec_name.as_deref(), a.container_format.as_deref()),
+                codec(b.codec_name.as_deref(), b.container_format.as_deref()),
+            ),
+            number_row(
+                "采样率",
+                a.sample_rate_hz.map(f64::from),
+                b.sample_rate_hz.map(f64::from),
+                "Hz",
+                0,
+            ),
+            number_row(
+                "位深（实测）",
+                a.effective_bit_depth.or(a.bit_depth).map(f64::from),
+                b.effective_bit_depth.or(b.bit_depth).map(f64::from),
+                "bit",
+                0,
+            ),
+            number_row(
+                "码率",
+                a.bitrate_kbps.map(f64::from),
+                b.bitrate_kbps.map(f64::from),
+                "kbps",
+                0,
+            ),
+            text_row(
+                "时长",
+                a.duration_seconds
+                    .map_or_else(|| "-".to_string(), summary::format_duration),
+                b.duration_seconds
+                    .map_or_else(|| "-".to_string(), summary::format_duration),
+            ),
+            number_row(
+                "文件大小",
+                Some(a.file_size_bytes as f64 / 1_048_576.0),
+                Some(b.file_size_bytes as f64 / 1_048_576.0),
+                "MB",
+                1,
+            ),
+            number_row(
+                "综合响度",
+                a.integrated_loudness_lufs,
+                b.integrated_loudness_lufs,
+                "LUFS",
+                1,
+            ),
+            number_row("真峰值", a.true_peak_dbtp, b.true_peak_dbtp, "dBTP", 1),
+            number_row("LRA", a.lra, b.lra, "LU", 1),
+            number_row("峰值", a.peak_amplitude_db, b.peak_amplitude_db, "dB", 1),
+            number_row("RMS", a.overall_rms_db, b.overall_rms_db, "dB", 1),
+        ];
+        for freq in [16_000, 18_000, 20_000] {
+            rows.push(number_row(
+                &format!("高频 RMS >{}k", freq / 1000),
+                a.hf_band_rms(freq),
+                b.hf_band_rms(freq),
+                "dB",
+                1,
+            ));
+        }
+        rows
+    }
+
+    /// 在终端打印对比表与结论。
+    pub fn print(&self) {
+        uiprintln!("\n🔍 版本对比");
+        uiprintln!(" A: {}", self.a.file_path);
+        uiprintln!(" B: {}", self.b.file_path);
+        let mut table = Table::new();
+        table
+            .load_preset(presets::UTF8_FULL_CONDENSED)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["指标", "A", "B", "差值 (B − A)"]);
+        for row in self.rows() {
+            table.add_row(row.map(Cell::new).to_vec());
+        }
+        if let Some(column) = table.column_mut(3) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+        uiprintln!("{table}");
+        uiprintln!("结论: {}", self.verdict().describe());
+    }
+}
+
+/// 无损编码且没有被判为伪无损。
+fn is_clean_lossless(analysis: &QualityAnalysis) -> bool {
+    scoring::is_lossless(&analysis.metrics)
+        && analysis.status != QualityStatus::Suspicious
+        && !analysis.issues.contains(&QualityStatus::Suspicious)
+}
+
+/// `candidate` 的 16 kHz 以上能量比 `source` 低得多：高频被截断。
+fn hf_gap(source: &QualityAnalysis, candidate: &QualityAnalysis) -> bool {
+    match (
+        source.metrics.hf_band_rms(16_000),
+        candidate.metrics.hf_band_rms(16_000),
+    ) {
+        (Some(source), Some(candidate)) => source - candidate >= HF_DERIVATIVE_GAP_DB,
+        _ => false,
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+fn codec(codec: Option<&str>, container: Option<&str>) -> String {
+    format!("{} / {}", codec.unwrap_or("-"), container.unwrap_or("-"))
+}
+
+fn text_row(label: &str, a: String, b: String) -> [String; 4] {
+    [label.to_string(), a, b, String::new()]
+}
+
+fn number_row(
+    label: &str,
+    a: Option<f64>,
+    b: Option<f64>,
+    unit: &str,
+    precision: usize,
+) -> [String; 4] {
+    let value = |v: Option<f64>| {
+        v.filter(|v| v.is_finite()).map_or_else(
+            || "-".to_string(),
+            |v| format!("{v:.precision$} {unit}").trim_end().to_string(),
+        )
+    };
+    let delta = match (a, b) {
+        (Some(a), Some(b)) if a.is_finite() && b.is_finite() => {
+            format!("{:+.precision$}", b - a)
+        }
+        _ => String::new(),
+    };
+    [label.to_string(), value(a), value(b), delta]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::metrics::FileMetrics;
+    use crate::analyzer::scoring::QualityScorer;
+
+    #[test]
+    fn test_verdict_flags_lossy_derivative_and_different_recordings() {
+        let metrics = [
+            FileMetrics {
+                file_path: "/m/a.flac".to_string(),
+                codec_name: Some("flac".to_string()),
+                duration_seconds: Some(200.0),
+                rms_db_above_16k: Some(-45.0),
+                rms_db_above_18k: Some(-50.0),
+                ..FileMetrics::default()
+            },
+            FileMetrics {
+                file_path: "/m/b.mp3".to_string(),
+                codec_name: Some("mp3".to_string()),
+                duration_seconds: Some(200.5),
+                rms_db_above_16k: Some(-95.0),
+                rms_db_above_18k: Some(-110.0),
+                ..FileMetrics::default()
+            },
+        ];
+        let analyses = QualityScorer::new().analyze_files(&metrics);
+        let comparison = VersionComparison {
+            a: &analyses[0],
+            b: &analyses[1],
+        };
+        assert_eq!(
+            comparison.verdict(),
+            Verdict::LossyDerivative { derived: Side::B }
+        );
+        assert!(comparison
+            .verdict()
+            .describe()
+            .starts_with("B 疑似为 A 的有损衍生版本"));
+        let swapped = VersionComparison {
+            a: &analyses[1],
+            b: &analyses[0],
+        };
+        assert_eq!(
+            swapped.verdict(),
+            Verdict::LossyDerivative { derived: Side::A }
+        );
+
+        let hf = comparison
+            .rows()
+            .into_iter()
+            .find(|row| row[0] == "高频 RMS >16k")
+            .expect("hf row");
+        assert_eq!(hf, ["高频 RMS >16k", "-45.0 dB", "-95.0 dB", "-50.0"]);
+
+        let mut other = analyses[1].clone();
+        other.metrics.duration_seconds = Some(90.0);
+        let different = VersionComparison {
+            a: &analyses[0],
+            b: &other,
+        };
+        assert!(matches!(
+            different.verdict(),
+            Verdict::DifferentRecordings { .. }
+        ));
+    }
+}
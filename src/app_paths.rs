@@ -0,0 +1,159 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: app_paths.rs
+// 描述: 用户级应用目录布局（配置、缓存、历史、日志）。
+//      按平台约定放在 XDG / AppData / Library 目录下，
+//      不再把工具自身的状态文件散落在被分析的音乐目录中。
+// ----------------------------------------------------------------
+
+use anyhow::{anyhow, Context, Result};
+use directories::ProjectDirs;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// 覆盖全部应用目录根路径的环境变量（便携安装或测试使用）。
+pub const HOME_ENV_VAR: &str = "AUDIOQUALITY_HOME";
+
+/// 旧版本写在分析目录中的缓存文件名，首次运行时迁移。
+pub const LEGACY_CACHE_FILE_NAME: &str = ".audio_quality_cache.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppPaths {
+    pub config_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    pub history_dir: PathBuf,
+    pub logs_dir: PathBuf,
+}
+
+impl AppPaths {
+    /// 解析当前用户的应用目录；设置了 `AUDIOQUALITY_HOME` 时全部放在其下。
+    pub fn resolve() -> Result<Self> {
+        if let Some(home) = std::env::var_os(HOME_ENV_VAR).filter(|v| !v.is_empty()) {
+            return Ok(Self::under_root(Path::new(&home)));
+        }
+
+        let dirs = ProjectDirs::from("", "", "audioquality")
+            .ok_or_else(|| anyhow!("无法确定用户主目录，请设置 {HOME_ENV_VAR} 环境变量"))?;
+        let state_dir = dirs
+            .state_dir()
+            .unwrap_or_else(|| dirs.data_local_dir())
+            .to_path_buf();
+
+        Ok(Self {
+            config_dir: dirs.config_dir().to_path_buf(),
+            cache_dir: dirs.cache_dir().to_path_buf(),
+            history_dir: dirs.data_dir().join("history"),
+            logs_dir: state_dir.join("logs"),
+        })
+    }
+
+    /// 全部目录放在 `root` 之下（`AUDIOQUALITY_HOME` 的布局）。
+    pub fn under_root(root: &Path) -> Self {
+        Self {
+            config_dir: root.join("config"),
+            cache_dir: root.join("cache"),
+            history_dir: root.join("history"),
+            logs_dir: root.join("logs"),
+        }
+    }
+
+    /// 把缓存目录换成 `cache_dir`（`--cache-path`），其余目录不变；相对路径按当前目录展开。
+    pub fn with_cache_dir(self, cache_dir: &Path) -> Self {
+        Self {
+            cache_dir: std::path::absolute(cache_dir).unwrap_or_else(|_| cache_dir.to_path_buf()),
+            ..self
+        }
+    }
+
+    /// 用户配置文件（首次运行向导写入）。
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join(crate::user_config::CONFIG_FILE_NAME)
+    }
+
+    /// 指定音乐库根目录对应的缓存文件，按根目录路径哈希区分，避免不同库互相覆盖。
+    pub fn library_cache_file(&self, library_root: &Path) -> PathBuf {
+        self.cache_dir.join(library_file_name(library_root))
+    }
+
+    /// 缓存目录中所有曲库的缓存文件（`cache prune` 逐个清理）。
+    pub fn library_cache_files(&self) -> Result<Vec<PathBuf>> {
+        let entries = match std::fs::read_dir(&self.cache_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("读取缓存目录失败: {}", self.cache_dir.display()))
+            }
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("library_") && name.ends_with(".json"))
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// 指定音乐库根目录的文件历史（含已移除文件的墓碑记录）。
+    pub fn library_history_file(&self, library_root: &Path) -> PathBuf {
+        self.history_dir.join(library_file_name(library_root))
+    }
+
+    /// 确保目录存在，返回该目录路径。
+    pub fn ensure_dir(dir: &Path) -> Result<&Path> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("创建应用目录失败: {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    pub fn print(&self) {
+        println!("配置目录: {}", self.config_dir.display());
+        println!("缓存目录: {}", self.cache_dir.display());
+        println!("历史目录: {}", self.history_dir.display());
+        println!("日志目录: {}", self.logs_dir.display());
+    }
+}
+
+fn library_file_name(library_root: &Path) -> String {
+    format!("library_{}.json", library_hash(library_root))
+}
+
+/// 音乐库根目录路径的短哈希（16 位十六进制），用于区分同名的不同曲库。
+pub fn library_hash(library_root: &Path) -> String {
+    let digest = Sha256::digest(library_root.to_string_lossy().as_bytes());
+    digest.iter().take(8).map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_under_root_layout() {
+        let paths = AppPaths::under_root(Path::new("/tmp/aq"));
+        assert_eq!(paths.config_dir, Path::new("/tmp/aq/config"));
+        assert_eq!(paths.logs_dir, Path::new("/tmp/aq/logs"));
+    }
+
+    #[test]
+    fn test_library_cache_file_is_namespaced_by_root() {
+        let paths = AppPaths::under_root(Path::new("/tmp/aq"));
+        let a = paths.library_cache_file(Path::new("/music/a"));
+        let b = paths.library_cache_file(Path::new("/music/b"));
+        assert_ne!(a, b);
+        assert_eq!(a, paths.library_cache_file(Path::new("/music/a")));
+        assert!(a.starts_with("/tmp/aq/cache"));
+
+        let moved = paths.with_cache_dir(Path::new("shared-cache"));
+        assert!(moved.cache_dir.is_absolute());
+        assert!(moved.cache_dir.ends_with("shared-cache"));
+        assert_eq!(moved.history_dir, Path::new("/tmp/aq/history"));
+        assert_eq!(
+            moved.library_cache_file(Path::new("/music/a")).file_name(),
+            a.file_name()
+        );
+    }
+}
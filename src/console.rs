@@ -0,0 +1,77 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: console.rs
+// 描述: 面向用户的控制台输出。`--json-stdout` 时标准输出只保留最终的 JSON 结果，
+//      横幅、进度提示与摘要等一律改写到标准错误，便于在管道中使用；`--quiet` 时
+//      只保留最终摘要（与 `--json-stdout` 同用时什么也不打印）。
+// ----------------------------------------------------------------
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON_STDOUT: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+static IN_SUMMARY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json_stdout(enabled: bool) {
+    JSON_STDOUT.store(enabled, Ordering::SeqCst);
+}
+
+/// 标准输出是否保留给 JSON 结果。
+pub fn json_stdout() -> bool {
+    JSON_STDOUT.load(Ordering::SeqCst)
+}
+
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::SeqCst);
+}
+
+/// 是否处于 `--quiet`。
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::SeqCst)
+}
+
+/// 当前的提示是否不打印：`--quiet` 时只有最终摘要段落例外。
+pub fn suppressed() -> bool {
+    quiet() && !IN_SUMMARY.load(Ordering::SeqCst)
+}
+
+/// 最终摘要段落，离开作用域时结束；`--quiet` 时其中的提示照常打印。
+pub struct SummarySection;
+
+impl Drop for SummarySection {
+    fn drop(&mut self) {
+        IN_SUMMARY.store(false, Ordering::SeqCst);
+    }
+}
+
+/// 进入最终摘要段落。`--json-stdout` 时摘要已在 JSON 中，安静模式下不再另行打印。
+pub fn summary_section() -> SummarySection {
+    IN_SUMMARY.store(!json_stdout(), Ordering::SeqCst);
+    SummarySection
+}
+
+/// 面向用户的提示（同 `println!`）；`--json-stdout` 时写到标准错误，`--quiet` 时不打印。
+macro_rules! uiprintln {
+    ($($arg:tt)*) => {
+        if !$crate::console::suppressed() {
+            if $crate::console::json_stdout() {
+                eprintln!($($arg)*)
+            } else {
+                println!($($arg)*)
+            }
+        }
+    };
+}
+
+/// 面向用户的提示（同 `print!`）；`--json-stdout` 时写到标准错误，`--quiet` 时不打印。
+macro_rules! uiprint {
+    ($($arg:tt)*) => {
+        if !$crate::console::suppressed() {
+            if $crate::console::json_stdout() {
+                eprint!($($arg)*)
+            } else {
+                print!($($arg)*)
+            }
+        }
+    };
+}
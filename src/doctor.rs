@@ -0,0 +1,391 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: doctor.rs
+// 描述: `doctor` 子命令的环境诊断。逐项检查 FFmpeg/FFprobe 及其版本、分析所需的滤镜、
+//      终端编码、应用目录与输出目录是否可写以及 CPU/内存，并为每个问题给出可直接照做的修复方法。
+// ----------------------------------------------------------------
+
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::ffmpeg::{self, ProcessingConfig};
+
+/// 指标提取必需的滤镜，缺少任何一个都无法完成分析。
+const REQUIRED_FILTERS: [&str; 4] = ["ebur128", "astats", "highpass", "aformat"];
+/// 可选功能依赖的滤镜，缺少时只影响对应功能。
+const OPTIONAL_FILTERS: [(&str, &str); 4] = [
+    ("concat", "--fast 快速采样"),
+    ("asetnsamples", "--segment-seconds 分段分析"),
+    ("ametadata", "--segment-seconds 分段分析"),
+    ("showspectrumpic", "--spectrograms 频谱图"),
+];
+/// 可用内存低于该值（MB）时建议降低并发。
+const LOW_MEMORY_MB: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// 一项检查的结果；有问题时附带修复方法。
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(
+        name: &'static str,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// 诊断所需的环境信息，由调用方按正式分析相同的规则解析。
+pub struct Environment<'a> {
+    /// 读取的配置文件（配置目录的 config.toml 与 `--config` 指定的文件）。
+    pub config_files: Vec<PathBuf>,
+    /// 配置文件或命令行参数无效时的原因；此时其余检查按默认设置进行。
+    pub config_error: Option<String>,
+    /// 找到 ffmpeg 时的处理配置；找不到时为查找失败的原因。
+    pub processing: Result<&'a ProcessingConfig, String>,
+    /// 找不到 ffmpeg 时提示的安装方式。
+    pub install_hint: &'a str,
+    /// 需要写入的目录（名称、路径）。
+    pub writable_dirs: Vec<(&'static str, PathBuf)>,
+    /// `--jobs`；未指定时按 CPU 核心数。
+    pub jobs: Option<usize>,
+}
+
+/// 依次执行全部检查。
+pub fn diagnose(env: &Environment) -> Vec<Check> {
+    let mut checks = vec![check_config(&env.config_files, env.config_error.as_deref())];
+    match &env.processing {
+        Ok(processing) => check_toolchain(processing, &mut checks),
+        Err(reason) => checks.push(Check::problem(
+            "FFmpeg",
+            CheckStatus::Fail,
+            reason.clone(),
+            format!(
+                "安装 FFmpeg（{}），或用 --ffmpeg-path / FFMPEG_PATH 指定可执行文件",
+                env.install_hint
+            ),
+        )),
+    }
+    checks.push(check_locale(
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+            .as_deref(),
+    ));
+    for (name, dir) in &env.writable_dirs {
+        checks.push(check_writable(name, dir));
+    }
+    checks.push(check_writable("临时目录", &std::env::temp_dir()));
+    checks.push(check_cpu(env.jobs));
+    checks.push(check_memory(
+        std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|content| parse_mem_available_mb(&content)),
+    ));
+    checks
+}
+
+fn check_toolchain(processing: &ProcessingConfig, checks: &mut Vec<Check>) {
+    let ffmpeg_version = ffmpeg::ffmpeg_version(processing);
+    checks.push(match &ffmpeg_version {
+        Some(version) => Check::ok(
+            "FFmpeg",
+            format!("{} ({version})", processing.ffmpeg_path.display()),
+        ),
+        None => Check::problem(
+            "FFmpeg",
+            CheckStatus::Fail,
+            format!(
+                "{} 无法运行或无法识别版本",
+                processing.ffmpeg_path.display()
+            ),
+            "确认该文件是可执行的 ffmpeg（运行 ffmpeg -version 检查），必要时重新安装",
+        ),
+    });
+
+    let ffprobe_version = ffmpeg::ffprobe_version(processing);
+    checks.push(match (&processing.ffprobe_path, &ffprobe_version) {
+        (Some(path), Some(version)) if ffmpeg_version.as_ref() == Some(version) => {
+            Check::ok("FFprobe", format!("{} ({version})", path.display()))
+        }
+        (Some(path), Some(version)) => Check::problem(
+            "FFprobe",
+            CheckStatus::Warn,
+            format!("{} ({version}) 与 FFmpeg 版本不一致", path.display()),
+            "使用同一套 FFmpeg 安装中的 ffmpeg 与 ffprobe，或用 --ffprobe-path 指定",
+        ),
+        (Some(path), None) => Check::problem(
+            "FFprobe",
+            CheckStatus::Warn,
+            format!("{} 无法运行或无法识别版本", path.display()),
+            "确认该文件是可执行的 ffprobe，或用 --ffprobe-path 指定",
+        ),
+        (None, _) => Check::problem(
+            "FFprobe",
+            CheckStatus::Warn,
+            "未找到，采样率、位深、编码等元数据将缺失",
+            "安装完整的 FFmpeg 套件（含 ffprobe），或用 --ffprobe-path / FFPROBE_PATH 指定",
+        ),
+    });
+
+    if ffmpeg_version.is_none() {
+        return;
+    }
+    match ffmpeg::available_filters(processing) {
+        Ok(filters) => checks.extend(check_filters(&filters)),
+        Err(e) => checks.push(Check::problem(
+            "滤镜",
+            CheckStatus::Warn,
+            format!("无法查询滤镜列表: {e}"),
+            "运行 ffmpeg -filters 确认 FFmpeg 可正常工作",
+        )),
+    }
+}
+
+fn check_filters(filters: &[String]) -> Vec<Check> {
+    let has = |name: &str| filters.iter().any(|filter| filter == name);
+    let missing: Vec<&str> = REQUIRED_FILTERS
+        .into_iter()
+        .filter(|name| !has(name))
+        .collect();
+    let mut checks = vec![if missing.is_empty() {
+        Check::ok("必需滤镜", REQUIRED_FILTERS.join(", "))
+    } else {
+        Check::problem(
+            "必需滤镜",
+            CheckStatus::Fail,
+            format!("缺少 {}", missing.join(", ")),
+            "当前 FFmpeg 为精简构建，请安装完整版本（如发行版的 ffmpeg 包或官方静态构建）",
+        )
+    }];
+    let missing_optional: Vec<String> = OPTIONAL_FILTERS
+        .into_iter()
+        .filter(|(name, _)| !has(name))
+        .map(|(name, feature)| format!("{name}（{feature}）"))
+        .collect();
+    if !missing_optional.is_empty() {
+        checks.push(Check::problem(
+            "可选滤镜",
+            CheckStatus::Warn,
+            format!("缺少 {}", missing_optional.join(", ")),
+            "不使用这些功能可忽略；需要时请安装完整版本的 FFmpeg",
+        ));
+    }
+    checks
+}
+
+/// 终端编码不是 UTF-8 时中文输出与含非 ASCII 字符的路径可能显示为乱码。
+fn check_locale(locale: Option<&str>) -> Check {
+    if cfg!(windows) {
+        return Check::ok("终端编码", "Windows 控制台");
+    }
+    match locale {
+        Some(value) if is_utf8_locale(value) => Check::ok("终端编码", value),
+        other => Check::problem(
+            "终端编码",
+            CheckStatus::Warn,
+            other.map_or_else(
+                || "未设置 LANG / LC_ALL".to_string(),
+                |value| format!("{value} 不是 UTF-8"),
+            ),
+            "设置 UTF-8 区域，如 export LANG=zh_CN.UTF-8（或 C.UTF-8）",
+        ),
+    }
+}
+
+fn is_utf8_locale(value: &str) -> bool {
+    let value = value.to_ascii_lowercase();
+    value.contains("utf-8") || value.contains("utf8")
+}
+
+/// 创建目录并写入一个临时文件，确认确实可写（只读挂载、权限不足时失败）。
+fn check_config(files: &[PathBuf], error: Option<&str>) -> Check {
+    let listed: Vec<String> = files
+        .iter()
+        .map(|path| {
+            if path.is_file() {
+                path.display().to_string()
+            } else {
+                format!("{}（不存在）", path.display())
+            }
+        })
+        .collect();
+    match error {
+        None => Check::ok("配置文件", listed.join("、")),
+        Some(error) => Check::problem(
+            "配置文件",
+            CheckStatus::Fail,
+            error,
+            "按提示修正配置文件或命令行参数；以下检查按默认设置进行",
+        ),
+    }
+}
+
+/// 目录能否写入。尚不存在的目录在首次使用时才创建，这里只检查最近的已存在上级目录，诊断本身不创建任何目录。
+fn check_writable(name: &'static str, dir: &Path) -> Check {
+    let existing = dir.ancestors().find(|path| path.is_dir());
+    let result = match existing {
+        Some(existing) => tempfile::tempfile_in(existing).map(drop),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "没有已存在的上级目录",
+        )),
+    };
+    match result {
+        Ok(()) if existing == Some(dir) => Check::ok(name, dir.display().to_string()),
+        Ok(()) => Check::ok(
+            name,
+            format!("{}（尚未创建，首次使用时创建）", dir.display()),
+        ),
+        Err(e) => Check::problem(
+            name,
+            CheckStatus::Fail,
+            format!("{} 不可写: {e}", dir.display()),
+            format!(
+                "检查该目录的权限，或设置 {} 把应用目录放到可写位置",
+                crate::app_paths::HOME_ENV_VAR
+            ),
+        ),
+    }
+}
+
+fn check_cpu(jobs: Option<usize>) -> Check {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    match jobs {
+        Some(jobs) if jobs > cores * 2 => Check::problem(
+            "CPU",
+            CheckStatus::Warn,
+            format!("{cores} 个逻辑核心，但 --jobs 为 {jobs}"),
+            format!("把 --jobs 降到 {cores} 左右，过多的并行解码只会互相争抢"),
+        ),
+        Some(jobs) => Check::ok("CPU", format!("{cores} 个逻辑核心，并行 {jobs} 个文件")),
+        None => Check::ok(
+            "CPU",
+            format!("{cores} 个逻辑核心，默认并行 {cores} 个文件"),
+        ),
+    }
+}
+
+fn check_memory(available_mb: Option<u64>) -> Check {
+    match available_mb {
+        Some(mb) if mb < LOW_MEMORY_MB => Check::problem(
+            "内存",
+            CheckStatus::Warn,
+            format!("可用 {mb} MB"),
+            "用 --jobs 2 或 --max-ffmpeg-processes 2 降低并发，避免 FFmpeg 子进程被系统终止",
+        ),
+        Some(mb) => Check::ok("内存", format!("可用 {mb} MB")),
+        None => Check::ok("内存", "未检测（仅支持 Linux）"),
+    }
+}
+
+/// 从 `/proc/meminfo` 中读取 `MemAvailable`（kB）并换算为 MB。
+fn parse_mem_available_mb(meminfo: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .split_whitespace()
+        .next()?
+        .parse::<u64>()
+        .ok()
+        .map(|kb| kb / 1024)
+}
+
+/// 打印诊断结果；返回是否存在必须修复的问题。
+pub fn print(checks: &[Check]) -> bool {
+    uiprintln!("🩺 环境诊断");
+    for check in checks {
+        let icon = match check.status {
+            CheckStatus::Ok => "✅",
+            CheckStatus::Warn => "⚠️",
+            CheckStatus::Fail => "❌",
+        };
+        uiprintln!(" {icon} {}: {}", check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            uiprintln!("    → {fix}");
+        }
+    }
+    let failures = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    let warnings = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Warn)
+        .count();
+    match (failures, warnings) {
+        (0, 0) => uiprintln!("\n环境正常，可以开始分析。"),
+        (0, _) => uiprintln!("\n可以开始分析，另有 {warnings} 项建议处理。"),
+        _ => uiprintln!("\n有 {failures} 项问题需要修复后才能正常分析（另有 {warnings} 项建议）。"),
+    }
+    failures > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_flag_problems_with_fixes() {
+        let filters: Vec<String> = ["ebur128", "astats", "aformat", "concat"]
+            .map(String::from)
+            .to_vec();
+        let checks = check_filters(&filters);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+        assert_eq!(checks[0].detail, "缺少 highpass");
+        assert_eq!(checks[1].status, CheckStatus::Warn);
+        assert!(checks[1]
+            .detail
+            .contains("showspectrumpic（--spectrograms 频谱图）"));
+
+        assert!(is_utf8_locale("zh_CN.UTF-8"));
+        assert!(is_utf8_locale("C.utf8"));
+        assert!(!is_utf8_locale("POSIX"));
+
+        let meminfo = "MemTotal:       16318040 kB\nMemFree:          512000 kB\nMemAvailable:     524288 kB\n";
+        assert_eq!(parse_mem_available_mb(meminfo), Some(512));
+        assert_eq!(check_memory(Some(512)).status, CheckStatus::Warn);
+        assert_eq!(check_memory(None).status, CheckStatus::Ok);
+
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let nested = dir.path().join("cache");
+        assert_eq!(check_writable("缓存目录", &nested).status, CheckStatus::Ok);
+        assert!(!nested.exists());
+
+        let config = dir.path().join("config.toml");
+        assert_eq!(
+            check_config(std::slice::from_ref(&config), None).status,
+            CheckStatus::Ok
+        );
+        let broken = check_config(&[config], Some("[E_CONFIG_FILE] 配置文件格式错误"));
+        assert_eq!(broken.status, CheckStatus::Fail);
+        assert!(broken.detail.contains("E_CONFIG_FILE"));
+    }
+}
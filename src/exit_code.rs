@@ -0,0 +1,108 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: exit_code.rs
+// 描述: 进程退出码约定。包装脚本可以直接根据退出码分支处理，
+//      而无需解析本地化的控制台输出。
+// ----------------------------------------------------------------
+
+use std::fmt;
+use std::process::ExitCode;
+
+/// 进程退出码分类。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// 0: 分析完成。
+    Success,
+    /// 1: 未归类的一般错误（参数错误、I/O 错误等）。
+    Failure,
+    /// 2: 质量门禁未通过（如 `--compliance` 检查有文件未通过、有文件低于 `--fail-under`）。
+    GateFailed,
+    /// 3: 运行环境问题（如找不到 FFmpeg）。
+    Environment,
+    /// 4: 处理失败的文件比例超过阈值。
+    PartialFailure,
+    /// 5: 用户取消（Ctrl-C）。
+    Cancelled,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> u8 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Failure => 1,
+            ExitStatus::GateFailed => 2,
+            ExitStatus::Environment => 3,
+            ExitStatus::PartialFailure => 4,
+            ExitStatus::Cancelled => 5,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status.code())
+    }
+}
+
+/// 携带退出码分类的错误，可放入 `anyhow::Error` 链中，由 `main` 统一解析。
+#[derive(Debug)]
+pub struct ExitError {
+    pub status: ExitStatus,
+    pub message: String,
+}
+
+impl ExitError {
+    pub fn new(status: ExitStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+/// 从错误链中找出退出码分类；未标注的错误归为一般错误。
+pub fn classify(err: &anyhow::Error) -> ExitStatus {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<ExitError>())
+        .map(|exit| exit.status)
+        .unwrap_or(ExitStatus::Failure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_classify_finds_status_in_chain() {
+        let err = Err::<(), _>(ExitError::new(ExitStatus::Environment, "ffmpeg missing"))
+            .context("分析失败")
+            .unwrap_err();
+        assert_eq!(classify(&err), ExitStatus::Environment);
+        assert_eq!(classify(&anyhow!("plain")), ExitStatus::Failure);
+    }
+
+    #[test]
+    fn test_exit_code_values_are_stable() {
+        let codes: Vec<u8> = [
+            ExitStatus::Success,
+            ExitStatus::Failure,
+            ExitStatus::GateFailed,
+            ExitStatus::Environment,
+            ExitStatus::PartialFailure,
+            ExitStatus::Cancelled,
+        ]
+        .iter()
+        .map(|s| s.code())
+        .collect();
+        assert_eq!(codes, vec![0, 1, 2, 3, 4, 5]);
+    }
+}
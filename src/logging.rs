@@ -0,0 +1,186 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: logging.rs
+// 描述: 基于 tracing 的运行日志。警告与错误写到标准错误（`--log-level` 可调），
+//      `--log-file` 另行记录带时间戳、线程与逐文件耗时的完整日志，供无人值守的长时间
+//      运行事后排查；同时把日志转发到崩溃诊断包的环形缓冲。
+// ----------------------------------------------------------------
+
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::Context as LayerContext;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::analyzer::diagnostics;
+
+/// 未指定 `--log-level` 时终端只显示警告与错误；`--quiet` 时只显示错误。
+const DEFAULT_STDERR_LEVEL: LevelFilter = LevelFilter::WARN;
+const QUIET_STDERR_LEVEL: LevelFilter = LevelFilter::ERROR;
+/// 未指定 `--log-level` 时日志文件记录到 info（含逐文件耗时）。
+const DEFAULT_FILE_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// 当前显示中的进度条；终端日志经由它输出，避免与进度条互相覆盖。
+static ACTIVE_BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// 安装全局日志订阅者；`level` 同时作用于终端与日志文件。
+pub fn init(level: Option<LevelFilter>, quiet: bool, log_file: Option<&Path>) -> Result<()> {
+    let stderr_level = level.unwrap_or(if quiet {
+        QUIET_STDERR_LEVEL
+    } else {
+        DEFAULT_STDERR_LEVEL
+    });
+    let stderr_layer = tracing_subscriber::fmt::layer()
+        .with_writer(|| StderrWriter)
+        .with_ansi(io::stderr().is_terminal())
+        .with_target(false)
+        .without_time()
+        .with_filter(stderr_level);
+
+    let file_layer = match log_file {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent).with_context(|| {
+                    format!("[E_LOG_FILE] 创建日志目录失败: {}", parent.display())
+                })?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("[E_LOG_FILE] 无法打开日志文件: {}", path.display()))?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(Mutex::new(file))
+                    .with_ansi(false)
+                    .with_thread_names(true)
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_filter(level.unwrap_or(DEFAULT_FILE_LEVEL)),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer)
+        .with(DiagnosticsLayer.with_filter(LevelFilter::INFO))
+        .try_init()
+        .context("初始化日志失败")
+}
+
+/// 进度条显示期间登记它，离开作用域时自动注销。
+pub struct ProgressBarGuard;
+
+impl Drop for ProgressBarGuard {
+    fn drop(&mut self) {
+        *active_bar() = None;
+    }
+}
+
+pub fn attach_progress_bar(bar: &ProgressBar) -> ProgressBarGuard {
+    *active_bar() = Some(bar.clone());
+    ProgressBarGuard
+}
+
+fn active_bar() -> std::sync::MutexGuard<'static, Option<ProgressBar>> {
+    ACTIVE_BAR
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 标准错误；有进度条时先暂停它再写，日志行不会被进度条刷新覆盖。
+struct StderrWriter;
+
+impl Write for StderrWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bar = active_bar().clone();
+        match bar {
+            Some(bar) => bar.suspend(|| io::stderr().write(buf)),
+            None => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// 把日志事件连同所在 span 的字段（如文件路径）写入崩溃诊断包的环形缓冲。
+struct DiagnosticsLayer;
+
+/// span 创建时记录的字段。
+struct SpanFields(String);
+
+impl<S> Layer<S> for DiagnosticsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: LayerContext<'_, S>) {
+        let mut fields = String::new();
+        attrs.record(&mut LineVisitor(&mut fields));
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: LayerContext<'_, S>) {
+        let mut line = format!("{:>5} ", event.metadata().level());
+        for span in ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|s| s.from_root())
+        {
+            line.push_str(span.name());
+            if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                line.push_str(&format!("{{{}}}", fields.trim_start()));
+            }
+            line.push_str(": ");
+        }
+        event.record(&mut LineVisitor(&mut line));
+        diagnostics::log_line(line);
+    }
+}
+
+/// 先写消息，其余字段以 `name=value` 追加在后面。
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        use std::fmt::Write as _;
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_layer_keeps_span_fields() {
+        let subscriber = tracing_subscriber::registry().with(DiagnosticsLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("file", path = "/m/诊断转发.flac").entered();
+            tracing::warn!(code = 7, "处理失败: 超时");
+        });
+        assert!(diagnostics::recent_log_lines()
+            .iter()
+            .any(|line| line
+                .ends_with(" WARN file{path=\"/m/诊断转发.flac\"}: 处理失败: 超时 code=7")));
+    }
+}
@@ -1,60 +1,447 @@
+#[macro_use]
+mod console;
 mod analyzer;
+mod app_paths;
+mod doctor;
+mod exit_code;
+mod logging;
+mod pending_session;
+mod session_log;
+mod user_config;
 
 use crate::analyzer::{
-    cache::{self, AnalysisCache, FileFingerprint},
-    ffmpeg,
+    actions, album,
+    cache::{
+        self, AnalysisCache, FileFingerprint, FingerprintMode, FingerprintOptions, HashAlgorithm,
+    },
+    classifier::{self, LossyClassifier},
+    collation::{self, FileNameCollator},
+    compare,
+    compliance::{self, ComplianceStandard},
+    cue::CueTrack,
+    dataset, delivery, diagnostics, duplicates,
+    failures::{self, FailedFile},
+    ffmpeg, folder_summary,
+    gate::QualityGate,
+    history::LibraryHistory,
+    i18n::Lang,
     metrics::FileMetrics,
-    report::ReportGenerator,
-    safe_io,
-    scoring::{QualityScorer, ScoringProfile},
+    playlist, remote,
+    report::{
+        self, JsonlStream, ReportGenerator, ReportOrder, ReportSort, ResultFilter, SortDirection,
+    },
+    rules::ScriptRule,
+    safe_io, savings,
+    scan::{self, ScanOptions},
+    schema::{self, SchemaKind},
+    scoring::{
+        self, CustomProfile, GradeScale, QualityScorer, ScoreWeights, ScoringProfile,
+        ThresholdOverrides,
+    },
+    segments, server, sidecar,
+    spectrogram::{self, SpectrogramScope},
+    sqlite_report,
+    summary::{self, SummaryStats, Throughput, Toolchain},
+    template_report::{self, ReportTemplate},
+    usage_stats::{self, RunUsage, UsageStats},
+    versions,
 };
+use crate::app_paths::AppPaths;
+use crate::exit_code::{ExitError, ExitStatus};
+use crate::pending_session::PendingSession;
+use crate::user_config::UserConfig;
 use anyhow::{anyhow, Context, Result};
 use chrono::Local;
-use clap::Parser;
-use indicatif::{ProgressBar, ProgressStyle};
+use clap::{ArgGroup, Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::str::FromStr;
-use std::time::Duration;
-use walkdir::WalkDir;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::level_filters::LevelFilter;
 use which::which;
 
-const SUPPORTED_EXTENSIONS: [&str; 10] = [
-    "wav", "mp3", "m4a", "flac", "aac", "ogg", "opus", "wma", "aiff", "alac",
-];
+/// Ctrl-C 取消标记：置位后不再派发新文件，已完成的结果照常写出。
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// 正在进行、可由 Ctrl-C 平稳取消的任务数（分析、结果服务）；为 0 时 Ctrl-C 直接退出。
+static CANCELLABLE_TASKS: AtomicUsize = AtomicUsize::new(0);
+
+/// 标记一段可平稳取消的任务，离开作用域时解除。
+struct CancellableTask;
+
+impl CancellableTask {
+    fn begin() -> Self {
+        CANCELLABLE_TASKS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for CancellableTask {
+    fn drop(&mut self) {
+        CANCELLABLE_TASKS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
 #[derive(Parser, Debug, Clone)]
 #[command(
     author,
     version,
     about = "一个基于 FFmpeg 的纯 Rust 音频质量分析工具",
-    long_about = "递归扫描目录中的音频文件，提取技术指标并输出 CSV/JSON 报告。默认启用安全模式（原子写入、符号链接防护、超时与并发限制）。"
+    long_about = "递归扫描目录中的音频文件，提取技术指标并输出 CSV/JSON 报告。默认启用安全模式（原子写入、符号链接防护、超时与并发限制）。",
+    args_conflicts_with_subcommands = true,
+    group(ArgGroup::new("inputs").multiple(true))
 )]
 struct Cli {
-    #[arg(value_name = "PATH", help = "要递归扫描和处理的音频文件夹路径")]
-    path: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(
+        value_name = "PATH",
+        group = "inputs",
+        help = "要分析的音频文件、文件夹（递归扫描）、播放列表（m3u/m3u8/pls，只分析其中的曲目）或 http(s) 地址（下载到临时目录后分析），可给出多个，结果取并集"
+    )]
+    paths: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE|-",
+        group = "inputs",
+        help = "从文件（- 为标准输入）读取要分析的路径，每行一个；与 PATH 一起取并集，如 find ... -print0 | AudioQuality-rs --files-from - -0"
+    )]
+    files_from: Option<PathBuf>,
+
+    #[arg(
+        short = '0',
+        long = "null",
+        requires = "files_from",
+        help = "--files-from 的路径以 NUL 分隔（配合 find -print0，路径可含换行）"
+    )]
+    null_separated: bool,
+
+    #[arg(
+        long,
+        value_name = "TOML",
+        help = "本次运行的配置文件：在配置目录的 config.toml 之上覆盖其中写出的项，命令行参数仍然优先"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        env = "FFMPEG_PATH",
+        help = "使用指定的 ffmpeg 可执行文件（优先于配置文件与 PATH；路径无效时直接报错）"
+    )]
+    ffmpeg_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        env = "FFPROBE_PATH",
+        help = "使用指定的 ffprobe 可执行文件（默认在 PATH 与 ffmpeg 所在目录中查找）"
+    )]
+    ffprobe_path: Option<PathBuf>,
+
+    #[arg(long, help = "每个 FFmpeg/FFprobe 子进程超时（秒，默认: 90）")]
+    ffmpeg_timeout_seconds: Option<u64>,
 
     #[arg(
+        short = 'j',
         long,
-        default_value_t = 90,
-        help = "每个 FFmpeg/FFprobe 子进程超时（秒）"
+        value_name = "N",
+        help = "同时分析的文件数（提取阶段的线程池大小，默认: CPU 核心数）；也是 --max-ffmpeg-processes 与 --scoring-threads 的默认值"
     )]
-    ffmpeg_timeout_seconds: u64,
+    jobs: Option<usize>,
 
     #[arg(
         long,
-        help = "允许同时运行的 FFmpeg/FFprobe 子进程数（默认: CPU 核心数）"
+        help = "允许同时运行的 FFmpeg/FFprobe 子进程数（默认: --jobs，未指定时为 CPU 核心数）"
     )]
     max_ffmpeg_processes: Option<usize>,
 
+    #[arg(
+        long,
+        value_name = "N",
+        help = "FFmpeg 解码线程数（默认由 FFmpeg 自动决定）"
+    )]
+    decode_threads: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "评分阶段使用的线程数（默认: --jobs，未指定时与全局线程池相同）"
+    )]
+    scoring_threads: Option<usize>,
+
+    #[arg(
+        long,
+        default_value = "size",
+        value_name = "size|name|random",
+        help = "提取阶段的处理顺序: size(大文件优先, 默认，避免几个超大文件拖成串行长尾), name(按路径), random(随机)；不影响报告顺序"
+    )]
+    schedule: String,
+
+    #[arg(
+        long,
+        value_name = "METHOD",
+        help = "FFmpeg 硬件加速解码方式（如 auto、videotoolbox、cuda），不可用时自动回退软件解码"
+    )]
+    hwaccel: Option<String>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "HZ,...",
+        default_values_t = ffmpeg::DEFAULT_HF_BANDS,
+        help = "高通分析频点列表（Hz），如 14000,15500,19000；评分所需的 16000/18000 总是额外测量"
+    )]
+    hf_bands: Vec<u32>,
+
+    #[arg(
+        long,
+        help = "快速采样模式：每个文件只分析开头/中间/结尾三个 30 秒窗口，结果标记 sampled=true"
+    )]
+    fast: bool,
+
+    #[arg(
+        long,
+        default_value = "0",
+        value_name = "N|all",
+        help = "多音轨文件（如演唱会视频）分析第 N 条音轨（从 0 开始），all 为逐条分析全部音轨"
+    )]
+    audio_stream: String,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u32).range(1..=3600),
+        help = "分段分析：按该时长切分每个文件，在备注中标出削波、高频缺失的时间段"
+    )]
+    segment_seconds: Option<u32>,
+
+    #[arg(
+        long,
+        help = "同时分析附加音轨：隐藏文件/目录、前置间隙 (pregap/HTOA) 及 --extra-extensions 指定的格式"
+    )]
+    include_extras: bool,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "EXT,...",
+        default_values_t = scan::DEFAULT_EXTRA_EXTENSIONS.map(String::from),
+        help = "视为附加音轨的扩展名（仅在 --include-extras 时生效）"
+    )]
+    extra_extensions: Vec<String>,
+
+    #[arg(long, help = "附加音轨计入摘要统计（默认仅写入报告）")]
+    extras_in_stats: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "最大扫描深度：1 只扫描文件夹顶层，2 再进入一层子目录，依此类推（默认不限）"
+    )]
+    max_depth: Option<usize>,
+
+    #[arg(
+        long,
+        conflicts_with = "max_depth",
+        help = "不进入子目录，只分析文件夹顶层的文件（同 --max-depth 1）"
+    )]
+    no_recursive: bool,
+
+    #[arg(
+        long,
+        help = "只扫描并列出将被分析的文件数、按扩展名的分布与总大小，不启动 FFmpeg、不写出报告"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        help = "扫描时跟随符号链接（默认跳过）；成环的链接会被跳过，指向同一文件的多个路径只分析一次"
+    )]
+    follow_symlinks: bool,
+
+    #[arg(
+        long,
+        value_name = "CSV",
+        conflicts_with = "inputs",
+        help = "只重新分析失败清单 (audio_quality_errors.csv) 中的文件，并把结果合并进该目录已有的报告"
+    )]
+    retry_failed: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "REPORT_DIR",
+        requires = "inputs",
+        help = "把 PATH 的分析结果合并进已有报告目录（如整个曲库的报告），同一路径或相同内容哈希的旧记录被替换，其余保留"
+    )]
+    merge: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "忽略 CUE 文件，整轨镜像按单个文件分析（默认按 CUE 音轨逐轨分析）"
+    )]
+    no_cue: bool,
+
+    #[arg(
+        long,
+        default_value = collation::DEFAULT_COLLATION,
+        value_name = "LOCALE",
+        help = "文件名排序规则: root(默认, Unicode 通用), bytes(原始字节), 或语言标签如 zh、ja、de"
+    )]
+    collation: String,
+
+    #[arg(
+        long,
+        default_value = "score",
+        help = "CSV 行顺序与摘要排名的排序键: score(按质量分, 默认), path(按路径分组), lra, true-peak, size(文件大小), time(处理耗时)"
+    )]
+    sort_by: String,
+
+    #[arg(
+        long,
+        value_name = "zh|en",
+        default_value = "zh",
+        help = "报告语言: 主报告与待处理清单的状态名称、备注、CSV 表头及控制台摘要，其余 CSV 与提示信息仍为中文（JSON 等机器可读输出的字段名不变）"
+    )]
+    lang: String,
+
+    #[arg(
+        long,
+        value_name = "asc|desc",
+        help = "排序方向（默认: path 升序，其余降序）"
+    )]
+    sort_order: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = report::DEFAULT_RANKING_SIZE,
+        help = "摘要中显示的排名条数（0 表示不显示排名）"
+    )]
+    top: usize,
+
+    #[arg(
+        long,
+        help = "终端摘要表格不着色（输出重定向到文件或管道时自动不着色；也可设置 NO_COLOR 环境变量）"
+    )]
+    no_color: bool,
+
+    #[arg(
+        long,
+        value_name = "full|minimal|COL,...",
+        help = "CSV 报告输出的列：full（默认）、minimal（分数/等级/状态/路径）或逗号分隔的列名（中文或英文列名）；未指定时使用配置文件中的 csv_columns"
+    )]
+    columns: Option<String>,
+
+    #[arg(
+        long,
+        help = "按专辑目录计算专辑综合响度与 ReplayGain 2.0 专辑增益，并输出专辑报告"
+    )]
+    album_gain: bool,
+
+    #[arg(
+        long,
+        value_name = "r128|a85",
+        help = "广播合规检查：按 EBU R128 或 ATSC A/85 判定综合响度与真峰值，输出 PASS/FAIL 及未满足的条款；有文件未通过时退出码为 2"
+    )]
+    compliance: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "LU",
+        requires = "compliance",
+        help = "合规检查额外要求的 LRA 上限（规范本身未规定，由交付方指定）"
+    )]
+    compliance_lra_max: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "SCORE",
+        help = "报告只包含质量分不低于 SCORE 的文件（原始数据、缓存与历史仍记录全部文件）"
+    )]
+    min_score: Option<i32>,
+
+    #[arg(
+        long,
+        value_name = "SCORE",
+        help = "报告只包含质量分不高于 SCORE 的文件，如 --max-score 70 只输出问题文件"
+    )]
+    max_score: Option<i32>,
+
+    #[arg(
+        long = "status",
+        value_delimiter = ',',
+        value_name = "STATUS,...",
+        help = "报告只包含状态或问题属于这些状态（如 clipped,suspicious）的文件"
+    )]
+    status_filter: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "SCORE",
+        help = "质量门禁：有文件质量分低于 SCORE 时以退出码 2 结束"
+    )]
+    fail_under: Option<i32>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "STATUS,...",
+        help = "质量门禁：有文件的状态或问题属于这些状态（如 clipped,suspicious）时以退出码 2 结束"
+    )]
+    fail_on_status: Vec<String>,
+
     #[arg(long, help = "禁用安全模式（不推荐）")]
     unsafe_mode: bool,
 
     #[arg(long, help = "禁用增量缓存（默认开启）")]
     no_cache: bool,
 
+    #[arg(
+        long,
+        value_name = "DAYS",
+        help = "保存缓存时同时移除超过该天数未被使用的条目（文件已删除或移动的条目总是移除）；未指定时使用配置文件中的 cache_max_age_days"
+    )]
+    cache_max_age_days: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "缓存目录（每个曲库按根目录各用一个缓存文件）；未指定时使用配置文件中的 cache_path，默认为 $XDG_CACHE_HOME/audioquality"
+    )]
+    cache_path: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "sha256|blake3|xxh3",
+        help = "增量缓存指纹的内容哈希算法（默认 sha256；blake3/xxh3 在高速磁盘上更快）；未指定时使用配置文件中的 hash_algorithm"
+    )]
+    hash_algorithm: Option<String>,
+
+    #[arg(
+        long = "fingerprint",
+        default_value = "strict",
+        value_name = "strict|partial|sampled|fast",
+        help = "判断文件是否变化的方式：完整哈希、首尾 1 MiB 哈希、抽样哈希，或只比较大小与修改时间（网络存储上更快）"
+    )]
+    fingerprint_mode: String,
+
+    #[arg(
+        long,
+        help = "额外生成完整评分结果的 JSON 报告（audio_quality_report.json：分数、状态、备注与原始指标）"
+    )]
+    json_report: bool,
+
     #[arg(long, help = "额外生成 JSONL 报告")]
     jsonl: bool,
 
@@ -63,381 +450,4080 @@ struct Cli {
 
     #[arg(
         long,
-        default_value = "pop",
-        help = "评分档案: pop(默认, 适合A-pop/J-pop/K-pop), broadcast, archive"
+        help = "在标准输出打印最终的摘要与全部评分结果（JSON），横幅、进度与提示等改写到标准错误，便于在管道中使用"
     )]
-    profile: String,
-}
+    json_stdout: bool,
 
-#[derive(Debug, Clone)]
-struct AppConfig {
-    command_timeout: Duration,
-    max_ffmpeg_processes: usize,
-    safe_mode: bool,
-    cache_enabled: bool,
-    emit_jsonl: bool,
-    emit_sarif: bool,
-    scoring_profile: ScoringProfile,
-}
+    #[arg(
+        short,
+        long,
+        help = "安静模式：不打印横幅、进度条与逐项提示，只输出最终摘要（与 --json-stdout 同用时只输出 JSON），适合定时任务"
+    )]
+    quiet: bool,
 
-#[derive(Debug)]
-struct ProcessedRecord {
-    metrics: FileMetrics,
-    fingerprint: FileFingerprint,
-}
+    #[arg(
+        long,
+        value_name = "error|warn|info|debug|trace",
+        help = "日志级别，同时作用于终端与 --log-file [默认: 终端 warn，日志文件 info]"
+    )]
+    log_level: Option<LevelFilter>,
 
-fn show_menu() -> Result<()> {
-    println!("\n--- 音频质量分析器交互模式 ---");
-    println!("1. 分析音频文件");
-    println!("2. 退出程序");
-    print!("请选择一个操作 (1-2): ");
-    io::stdout().flush()?;
-    Ok(())
-}
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "把带时间戳、线程与逐文件耗时的日志追加写入该文件，便于排查无人值守的长时间运行"
+    )]
+    log_file: Option<PathBuf>,
 
-fn interactive_mode(config: &AppConfig) -> Result<()> {
-    loop {
-        show_menu()?;
+    #[arg(
+        long,
+        help = "按状态与分数段导出 M3U8 播放列表（如 suspicious.m3u8、score_90_plus.m3u8）到报告目录的 playlists 子目录"
+    )]
+    export_playlists: bool,
 
-        let mut choice = String::new();
-        io::stdin().read_line(&mut choice)?;
+    #[arg(
+        long,
+        help = "导出 foobar2000/MusicBee 批量写标签用的映射文件 audio_quality_tags.tsv（路径 → AQ_SCORE/AQ_GRADE/AQ_STATUS）"
+    )]
+    export_tags: bool,
 
-        match choice.trim() {
-            "1" => {
-                println!("\n准备开始音频质量分析...");
-                match get_path_from_user_interaction() {
-                    Ok(path) => {
-                        if let Err(e) = run_analysis(&path, config) {
-                            eprintln!("\n分析过程中发生错误: {e}");
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("\n无法获取有效路径: {e}");
-                    }
-                }
-            }
-            "2" => {
-                println!("\n感谢使用，再见。");
-                break;
-            }
-            _ => eprintln!("\n无效选择，请输入 1 或 2"),
-        }
-    }
-    Ok(())
-}
+    #[arg(
+        long,
+        help = "为每个文件写出单文件结果 <文件名>.aq.json（完整指标与评分）；设置了输出目录时镜像到其中的 sidecars 子目录"
+    )]
+    sidecar: bool,
 
-fn get_path_from_user_interaction() -> Result<PathBuf> {
-    println!("\n请输入音频文件夹路径（支持相对路径或绝对路径）");
+    #[arg(
+        long,
+        help = "在每个专辑目录写出易读的质量摘要 AQ_SUMMARY.txt（默认不写入音乐库）；设置了输出目录时镜像到其中的 sidecars 子目录"
+    )]
+    folder_summary: bool,
 
-    loop {
-        print!("\n路径: ");
-        io::stdout().flush()?;
+    #[arg(
+        long,
+        help = "复用内容指纹一致的已有单文件结果 (.aq.json)，跳过重新分析（无需中心缓存，随文件夹一起迁移）"
+    )]
+    reuse_sidecars: bool,
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let path_str = input.trim();
+    #[arg(
+        long,
+        help = "缓存结果由主版本号不同（或未记录版本）的 FFmpeg 生成时重新分析，避免混用不同版本的测量值"
+    )]
+    reanalyze_on_ffmpeg_change: bool,
 
-        if path_str.is_empty() {
-            eprintln!("路径不能为空，请重试。");
+    #[arg(
+        long,
+        help = "额外计算解码后 PCM 的 MD5，把标签、封面或容器不同但音频相同的文件也列入重复文件清单（每个文件多解码一遍）"
+    )]
+    pcm_md5: bool,
+
+    #[arg(
+        long,
+        help = "检测重度限幅：逐 100 ms 窗口统计峰值贴顶的时间占比（每个文件多解码一遍）"
+    )]
+    detect_limiting: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "导出匿名研究数据集（dataset.csv + manifest.json，不含路径与标签）到指定目录"
+    )]
+    export_dataset: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "DB",
+        help = "把结果写入 SQLite 数据库（files/metrics/analyses 三张表，按内容哈希 upsert，可多次运行累积）"
+    )]
+    sqlite: Option<PathBuf>,
+
+    #[arg(
+        long = "template",
+        value_name = "html|markdown|text|PATH",
+        help = "按模板生成报告：内置 html、markdown、text，或 Tera 模板文件（输出名为模板文件名去掉 .tera）；可重复"
+    )]
+    templates: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "flagged|all",
+        help = "在报告目录的 spectrograms/ 下生成频谱图（flagged 仅有问题的文件，all 全部文件），HTML 模板报告中可点击放大查看"
+    )]
+    spectrograms: Option<String>,
+
+    #[arg(
+        long,
+        help = "评分档案: pop(默认, 适合A-pop/J-pop/K-pop), broadcast, archive, classical, edm, podcast, audiobook, vinyl；未指定时使用配置文件中的 profile"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "报告输出目录（每个音乐库一个子目录）；默认写入被分析的文件夹"
+    )]
+    output_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "TOML",
+        help = "自定义评分档案（TOML）：覆盖目标 LUFS、LRA 区间、频谱与码率等阈值，未写出的沿用 --profile 或文件中的 base"
+    )]
+    profile_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "JSON",
+        help = "有损来源分类器模型（由 train 子命令生成），取代固定高频阈值判定无损文件是否由有损转码而来"
+    )]
+    lossy_model: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "JSON",
+        help = "与上次运行的 analysis_data.json（或其所在目录）对比：报告中加入分数变化与状态变化，摘要列出分数下降的文件"
+    )]
+    baseline: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "C,D,S,A,I",
+        help = "各维度满分：合规,动态,频谱,真实性,完整性，总和须为 100（默认 35,20,25,10,10，覆盖档案文件中的 [weights]）"
+    )]
+    score_weights: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "LUFS",
+        allow_negative_numbers = true,
+        help = "覆盖档案的目标响度（响度容差区间随之平移），如 -14"
+    )]
+    target_lufs: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "DBTP",
+        allow_negative_numbers = true,
+        help = "覆盖档案的真峰值上限，超过即标记为真峰值风险，如 -1.0"
+    )]
+    true_peak_max: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "KBPS",
+        help = "覆盖档案的最低码率，低于该值标记为低码率，如 256"
+    )]
+    min_bitrate: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "按旧版本的评分规则评分（默认当前版本），用于与旧版本产生的结果对比"
+    )]
+    scorer_version: Option<u32>,
+
+    #[arg(
+        long = "rule",
+        value_name = "RHAI",
+        help = "追加用户评分规则脚本（Rhai），在内置检测之后运行，可报告问题、附加扣分或写入备注；可重复"
+    )]
+    rules: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "GRADE=MIN,...",
+        help = "字母等级映射（等级=最低分），默认 A+=95,A=90,B=80,C=70,D=60,F=0；未指定时使用配置文件中的 grade_scale"
+    )]
+    grade_scale: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 10.0,
+        value_name = "PERCENT",
+        help = "处理失败文件占比超过该百分比时以退出码 4 结束"
+    )]
+    max_failure_percent: f64,
+
+    #[arg(
+        long,
+        help = "写入本地使用统计文件（仅本地，不联网），便于附在问题反馈中"
+    )]
+    usage_stats: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// 打印配置、缓存、历史与日志目录的位置
+    Paths,
+    /// 检查运行环境（FFmpeg/FFprobe、所需滤镜、终端编码、目录权限、CPU 与内存）并给出修复方法
+    Doctor,
+    /// 管理增量缓存
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// 对比两个文件、两个文件夹或两次运行（如原版与重制版）的分数与状态，给出取舍结论
+    #[command(visible_alias = "diff")]
+    Compare {
+        /// A：音频文件、文件夹（现场分析），或旧一次运行的 analysis_data.json（或其所在目录）
+        old: PathBuf,
+        /// B：音频文件、文件夹（现场分析），或新一次运行的 analysis_data.json（或其所在目录）
+        new: PathBuf,
+        /// HTML 对比报告路径（默认写入新运行目录下的 audio_quality_compare.html）
+        #[arg(long, value_name = "PATH")]
+        html: Option<PathBuf>,
+        /// 差异清单 CSV 路径（默认写入新运行目录下的 audio_quality_diff.csv）
+        #[arg(long, value_name = "PATH")]
+        csv: Option<PathBuf>,
+    },
+    /// 用已知无损与已知转码的文件夹训练有损来源分类器
+    Train {
+        /// 已知为真无损的文件夹（可多次指定）
+        #[arg(long, value_name = "DIR", required = true)]
+        lossless: Vec<PathBuf>,
+        /// 已知由有损转码而来的文件夹（可多次指定）
+        #[arg(long, value_name = "DIR", required = true)]
+        transcode: Vec<PathBuf>,
+        /// 模型输出路径，供 --lossy-model 使用
+        #[arg(long, short, value_name = "JSON", default_value = "lossy_model.json")]
+        output: PathBuf,
+    },
+    /// 打印当前版本输出文件的 JSON Schema，供下游校验与迁移
+    Schema {
+        /// 要输出的文档：report（audio_quality_report.json）或 analysis-data（analysis_data.json）
+        #[arg(default_value = "report", value_name = "report|analysis-data")]
+        kind: String,
+    },
+    /// 在本机提供只读的结果接口（JSON），可选内置网页界面
+    Serve {
+        /// 运行结果 analysis_data.json（或其所在目录）
+        run: PathBuf,
+        /// 监听端口（仅监听 127.0.0.1）
+        #[arg(long, default_value_t = server::DEFAULT_PORT)]
+        port: u16,
+        /// 同时提供内置网页界面（需以 webui 特性编译）
+        #[arg(long)]
+        ui: bool,
+    },
+    /// 用已有的 analysis_data.json 按新的评分设置重新评分并生成全部报告，不调用 FFmpeg
+    Rescore {
+        /// 运行结果 analysis_data.json（或其所在目录）
+        run: PathBuf,
+        /// 评分与输出参数，与分析时相同（如 --profile broadcast --fail-under 70）
+        #[arg(
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "OPTIONS"
+        )]
+        options: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum CacheCommand {
+    /// 清理所有曲库缓存中文件已删除或移动的条目，可选同时清理长期未使用的条目
+    Prune {
+        /// 同时移除超过该天数未被使用的条目（默认使用配置文件中的 cache_max_age_days）
+        #[arg(long, value_name = "DAYS")]
+        max_age_days: Option<u64>,
+        /// 只统计将被移除的条目，不修改缓存
+        #[arg(long)]
+        dry_run: bool,
+        /// 缓存目录（默认使用配置文件中的 cache_path 或平台缓存目录）
+        #[arg(long, value_name = "DIR")]
+        cache_path: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct AppConfig {
+    command_timeout: Duration,
+    max_ffmpeg_processes: usize,
+    safe_mode: bool,
+    cache_enabled: bool,
+    /// 缓存条目的最长未使用天数（`--cache-max-age-days`）；`None` 表示不按时间清理。
+    cache_max_age_days: Option<u64>,
+    hash_algorithm: HashAlgorithm,
+    /// 缓存/单文件结果指纹的读取范围（`--fingerprint`）。
+    fingerprint_mode: FingerprintMode,
+    emit_json_report: bool,
+    emit_jsonl: bool,
+    emit_sarif: bool,
+    /// 标准输出只写最终的 JSON 结果（`--json-stdout`）。
+    json_stdout: bool,
+    /// 只打印最终摘要（`--quiet`）。
+    quiet: bool,
+    emit_playlists: bool,
+    emit_tags: bool,
+    emit_sidecars: bool,
+    /// 在每个专辑目录写出 `AQ_SUMMARY.txt`。
+    emit_folder_summaries: bool,
+    /// 只扫描并打印概况（`--dry-run`）。
+    dry_run: bool,
+    reuse_sidecars: bool,
+    reanalyze_on_ffmpeg_change: bool,
+    pcm_md5: bool,
+    detect_limiting: bool,
+    dataset_dir: Option<PathBuf>,
+    /// 结果数据库路径（`--sqlite`）。
+    sqlite_path: Option<PathBuf>,
+    /// 模板报告（`--template`）。
+    report_templates: Vec<ReportTemplate>,
+    /// 生成频谱图的范围（`--spectrograms`）。
+    spectrograms: Option<SpectrogramScope>,
+    scoring_profile: ScoringProfile,
+    custom_profile: Option<CustomProfile>,
+    profile_file: Option<PathBuf>,
+    lossy_model_file: Option<PathBuf>,
+    baseline_file: Option<PathBuf>,
+    lossy_classifier: Option<LossyClassifier>,
+    score_weights: Option<ScoreWeights>,
+    threshold_overrides: ThresholdOverrides,
+    scorer_version: u32,
+    rule_files: Vec<PathBuf>,
+    grade_scale: GradeScale,
+    usage_stats_enabled: bool,
+    /// 提取阶段的线程池大小（`--jobs`）；`None` 时使用全局线程池。
+    jobs: Option<usize>,
+    decode_threads: Option<usize>,
+    scoring_threads: Option<usize>,
+    schedule: scan::Schedule,
+    hwaccel: Option<String>,
+    max_failure_percent: f64,
+    hf_bands: Vec<u32>,
+    fast_sampling: bool,
+    segment_seconds: Option<u32>,
+    audio_streams: ffmpeg::StreamSelection,
+    scan_options: ScanOptions,
+    extras_in_stats: bool,
+    collation: String,
+    report_sort: ReportOrder,
+    /// 摘要排名显示的条数（`--top`）。
+    ranking_size: usize,
+    /// 终端表格着色（`--no-color` 或 `NO_COLOR` 关闭）。
+    color: bool,
+    /// 报告语言（`--lang`）。
+    lang: Lang,
+    /// CSV 报告输出的列（`--columns`），`None` 为全部列。
+    csv_columns: Option<Vec<String>>,
+    album_gain: bool,
+    compliance: Option<ComplianceStandard>,
+    compliance_lra_max: Option<f64>,
+    quality_gate: QualityGate,
+    /// 写入报告与摘要的结果过滤。
+    result_filter: ResultFilter,
+    app_paths: AppPaths,
+    output_dir: Option<PathBuf>,
+    /// 命令行或配置文件指定的缓存目录；未指定时为 `None`，使用平台缓存目录。
+    cache_path: Option<PathBuf>,
+    ffmpeg_path: Option<PathBuf>,
+    ffprobe_path: Option<PathBuf>,
+    log_level: Option<LevelFilter>,
+    log_file: Option<PathBuf>,
+}
+
+impl AppConfig {
+    /// 某个音乐库的报告目录：设置了输出目录时为其下以库目录名命名的子目录，否则为库目录本身。
+    fn report_dir(&self, library_root: &Path) -> PathBuf {
+        match &self.output_dir {
+            Some(output_dir) => {
+                let name = library_root
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "library".to_string());
+                // 附上根目录路径的哈希，`/a/Music` 与 `/b/Music` 不会写进同一个报告目录。
+                let hash = app_paths::library_hash(library_root);
+                output_dir.join(format!("{name}_{}", &hash[..8]))
+            }
+            None => library_root.to_path_buf(),
+        }
+    }
+
+    fn fingerprint_options(&self) -> FingerprintOptions {
+        FingerprintOptions {
+            algorithm: self.hash_algorithm,
+            mode: self.fingerprint_mode,
+        }
+    }
+}
+
+/// 本次运行实际生效的评分档案与阈值，与 `analysis_data.json` 写在同一目录。
+const PROFILE_SNAPSHOT_FILE_NAME: &str = "audio_quality_profile.json";
+
+/// 完整评分结果（含分数、状态、备注）的 JSON 报告文件名。
+const JSON_REPORT_FILE_NAME: &str = "audio_quality_report.json";
+
+/// JSONL 报告文件名：运行结束时写入完整、有序的结果。
+const JSONL_REPORT_FILE_NAME: &str = "audio_quality_report.jsonl";
+
+/// 分析过程中逐个文件追加的 JSONL；上次的完整报告保持不动，运行正常结束后删除。
+const JSONL_PARTIAL_FILE_NAME: &str = "audio_quality_report.partial.jsonl";
+
+/// `--export-tags` 写出的标签映射文件名。
+const TAG_EXPORT_FILE_NAME: &str = "audio_quality_tags.tsv";
+
+/// 单次分析的结果概况，用于决定退出码。
+#[derive(Debug, Default, Clone)]
+struct RunOutcome {
+    total_files: usize,
+    failed_files: usize,
+    cancelled: bool,
+    /// 启用合规检查时未通过的文件数。
+    compliance_failures: usize,
+    /// 未通过 `delivery.toml` 规格的交付目录数。
+    delivery_failures: usize,
+    /// 违反 `--fail-under` / `--fail-on-status` 门禁的文件数。
+    gate_failures: usize,
+    /// 取消时尚未处理的文件，交互模式据此保存待续队列。
+    unprocessed: Vec<PathBuf>,
+}
+
+impl RunOutcome {
+    fn exit_status(&self, config: &AppConfig) -> ExitStatus {
+        if self.cancelled {
+            return ExitStatus::Cancelled;
+        }
+        if self.total_files > 0 {
+            let failed_percent = self.failed_files as f64 / self.total_files as f64 * 100.0;
+            if self.failed_files > 0 && failed_percent > config.max_failure_percent {
+                return ExitStatus::PartialFailure;
+            }
+        }
+        if self.compliance_failures > 0 || self.delivery_failures > 0 || self.gate_failures > 0 {
+            return ExitStatus::GateFailed;
+        }
+        ExitStatus::Success
+    }
+}
+
+#[derive(Debug)]
+struct ProcessedRecord {
+    metrics: FileMetrics,
+    fingerprint: FileFingerprint,
+}
+
+fn show_menu(pending: Option<&PendingSession>, config: &AppConfig) -> Result<()> {
+    uiprintln!("\n--- 音频质量分析器交互模式 ---");
+    uiprintln!("1. 分析音频文件");
+    uiprintln!("2. 设置向导（评分档案、输出目录、FFmpeg）");
+    uiprintln!("3. 选择评分档案（当前: {}）", profile_name(config));
+    uiprintln!("4. 浏览已有报告");
+    uiprintln!("5. 退出程序");
+    match pending {
+        Some(session) => {
+            uiprintln!(
+                "6. 继续上次中断的分析: {}",
+                sanitize_for_terminal(&session.describe())
+            );
+            uiprint!("请选择一个操作 (1-6): ");
+        }
+        None => uiprint!("请选择一个操作 (1-5): "),
+    }
+    io::stdout().flush()?;
+    Ok(())
+}
+
+/// 运行一次交互分析，并维护待续会话：开始时记录目录与等效命令，
+/// 取消时记录尚未处理的文件，正常结束（或出错）后删除。
+fn run_tracked_analysis(session: PendingSession, config: &AppConfig) -> Result<RunOutcome> {
+    // 上一次分析出错或在菜单处按下的 Ctrl-C 不应影响这一次。
+    CANCELLED.store(false, Ordering::SeqCst);
+    let cache_dir = &config.app_paths.cache_dir;
+    if let Err(e) = session.save(cache_dir, config.safe_mode) {
+        tracing::warn!("无法保存待续会话: {e}");
+    }
+    let result = match session.remaining.clone() {
+        Some(remaining) => analyze(&session.library, config, AnalysisScope::Resume(remaining)),
+        None => run_analysis(&session.library, config),
+    };
+    match &result {
+        Ok(outcome) if outcome.cancelled && !outcome.unprocessed.is_empty() => {
+            let session = PendingSession {
+                remaining: Some(outcome.unprocessed.clone()),
+                ..session
+            };
+            session.save(cache_dir, config.safe_mode)?;
+            uiprintln!(
+                "剩余 {} 个文件已记入待续队列，下次启动可在菜单中继续。",
+                outcome.unprocessed.len()
+            );
+        }
+        // 出错时保留启动时记下的会话，修复问题（如重新挂载曲库）后可在菜单中继续。
+        Err(_) => {}
+        Ok(_) => PendingSession::clear(cache_dir)?,
+    }
+    result
+}
+
+/// 按中断前的等效命令重建设置，继续分析待续队列；设置已无法解析时丢弃该会话。
+fn resume_pending_session(session: PendingSession, config: &AppConfig) -> Result<()> {
+    let resumed = Cli::try_parse_from(&session.args)
+        .map_err(|e| anyhow!("无法解析上次的分析设置: {e}"))
+        .and_then(|cli| build_app_config(&cli));
+    let resumed = match resumed {
+        Ok(resumed) => resumed,
+        Err(e) => {
+            eprintln!("\n{e}");
+            return PendingSession::clear(&config.app_paths.cache_dir);
+        }
+    };
+    uiprintln!("\n继续上次中断的分析: {}", session.library.display());
+    let path = session.library.clone();
+    let result = run_tracked_analysis(session, &resumed);
+    finish_interactive_run(&path, result, &resumed)
+}
+
+/// 交互分析结束后的提示：取消时说明已保存的部分，出现失败时询问是否重试。
+fn finish_interactive_run(
+    path: &Path,
+    result: Result<RunOutcome>,
+    config: &AppConfig,
+) -> Result<()> {
+    match result {
+        Ok(outcome) if outcome.cancelled => {
+            CANCELLED.store(false, Ordering::SeqCst);
+            uiprintln!("\n分析已取消，已完成部分的结果已保存。");
+        }
+        Ok(outcome) => offer_retry_failed(path, outcome, config)?,
+        Err(e) => {
+            eprintln!("\n分析过程中发生错误: {e}");
+            uiprintln!("排除问题后可在菜单中选择「继续上次中断的分析」重新进行。");
+        }
+    }
+    Ok(())
+}
+
+fn interactive_mode(config: &AppConfig) -> Result<()> {
+    let mut config = config.clone();
+    if !config.app_paths.config_file().exists() {
+        uiprintln!("\n首次运行，先完成几个简单的设置（之后可在菜单中重新设置）。");
+        if let Err(e) = run_setup_wizard(&mut config) {
+            eprintln!("\n设置向导未完成: {e}");
+        }
+    }
+    let config = &mut config;
+    // 本次会话中每次分析对应的等效命令行，退出时打印并写入会话日志。
+    let mut session_commands = Vec::new();
+
+    loop {
+        let pending = PendingSession::load(&config.app_paths.cache_dir);
+        show_menu(pending.as_ref(), config)?;
+
+        let mut choice = String::new();
+        io::stdin().read_line(&mut choice)?;
+
+        match choice.trim() {
+            "1" => {
+                uiprintln!("\n准备开始音频质量分析...");
+                match get_path_from_user_interaction() {
+                    Ok(path) => {
+                        let args = equivalent_command(&program_name(), &path, config);
+                        session_commands.push(session_log::format_command_line(&args));
+                        let result = run_tracked_analysis(PendingSession::new(&path, args), config);
+                        finish_interactive_run(&path, result, config)?;
+                    }
+                    Err(e) => {
+                        eprintln!("\n无法获取有效路径: {e}");
+                    }
+                }
+            }
+            "2" => {
+                if let Err(e) = run_setup_wizard(config) {
+                    eprintln!("\n设置向导未完成: {e}");
+                }
+            }
+            "3" => {
+                if let Err(e) = choose_profile(config) {
+                    eprintln!("\n评分档案未更改: {e}");
+                }
+            }
+            "4" => {
+                if let Err(e) = browse_report(config) {
+                    eprintln!("\n无法打开报告: {e:#}");
+                }
+            }
+            "5" => {
+                print_session_commands(&session_commands, config);
+                uiprintln!("\n感谢使用，再见。");
+                break;
+            }
+            "6" if pending.is_some() => {
+                if let Some(session) = pending {
+                    session_commands.push(session_log::format_command_line(&session.args));
+                    resume_pending_session(session, config)?;
+                }
+            }
+            _ if pending.is_some() => eprintln!("\n无效选择，请输入 1 到 6"),
+            _ => eprintln!("\n无效选择，请输入 1 到 5"),
+        }
+    }
+    Ok(())
+}
+
+fn program_name() -> String {
+    env::args()
+        .next()
+        .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string())
+}
+
+/// 与交互分析等效的非交互命令参数：只写出与默认值不同的选项，评分档案与输出目录总是显式写出，
+/// 使命令不依赖配置文件；缓存目录与 FFmpeg/FFprobe 路径指定了才写出。
+fn equivalent_command(program: &str, path: &Path, config: &AppConfig) -> Vec<String> {
+    let mut args = vec![program.to_string()];
+    let mut push = |flag: &str, value: Option<String>| {
+        args.push(flag.to_string());
+        args.extend(value);
+    };
+
+    let default_parallel = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    if config.command_timeout.as_secs() != 90 {
+        push(
+            "--ffmpeg-timeout-seconds",
+            Some(config.command_timeout.as_secs().to_string()),
+        );
+    }
+    if let Some(jobs) = config.jobs {
+        push("--jobs", Some(jobs.to_string()));
+    }
+    if config.max_ffmpeg_processes != config.jobs.unwrap_or(default_parallel) {
+        push(
+            "--max-ffmpeg-processes",
+            Some(config.max_ffmpeg_processes.to_string()),
+        );
+    }
+    if let Some(threads) = config.decode_threads {
+        push("--decode-threads", Some(threads.to_string()));
+    }
+    if let Some(threads) = config.scoring_threads {
+        push("--scoring-threads", Some(threads.to_string()));
+    }
+    if config.schedule != scan::Schedule::Size {
+        push("--schedule", Some(config.schedule.as_str().to_string()));
+    }
+    if let Some(method) = &config.hwaccel {
+        push("--hwaccel", Some(method.clone()));
+    }
+    if config.hf_bands != ffmpeg::DEFAULT_HF_BANDS {
+        let bands: Vec<String> = config.hf_bands.iter().map(u32::to_string).collect();
+        push("--hf-bands", Some(bands.join(",")));
+    }
+    if config.fast_sampling {
+        push("--fast", None);
+    }
+    if let ffmpeg::StreamSelection::Index(index) = config.audio_streams {
+        if index != 0 {
+            push("--audio-stream", Some(index.to_string()));
+        }
+    } else {
+        push("--audio-stream", Some("all".to_string()));
+    }
+    if let Some(seconds) = config.segment_seconds {
+        push("--segment-seconds", Some(seconds.to_string()));
+    }
+    if config.scan_options.include_extras {
+        push("--include-extras", None);
+    }
+    if config.scan_options.extra_extensions != scan::DEFAULT_EXTRA_EXTENSIONS {
+        push(
+            "--extra-extensions",
+            Some(config.scan_options.extra_extensions.join(",")),
+        );
+    }
+    if config.extras_in_stats {
+        push("--extras-in-stats", None);
+    }
+    if config.scan_options.ignore_cue {
+        push("--no-cue", None);
+    }
+    if let Some(depth) = config.scan_options.max_depth {
+        push("--max-depth", Some(depth.to_string()));
+    }
+    if config.scan_options.follow_symlinks {
+        push("--follow-symlinks", None);
+    }
+    if config.collation != collation::DEFAULT_COLLATION {
+        push("--collation", Some(config.collation.clone()));
+    }
+    if config.report_sort.key != ReportSort::Score {
+        push(
+            "--sort-by",
+            Some(config.report_sort.key.as_str().to_string()),
+        );
+    }
+    if let Some(direction) = config.report_sort.direction {
+        let direction = match direction {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        };
+        push("--sort-order", Some(direction.to_string()));
+    }
+    if !config.color {
+        push("--no-color", None);
+    }
+    if config.ranking_size != report::DEFAULT_RANKING_SIZE {
+        push("--top", Some(config.ranking_size.to_string()));
+    }
+    if let Some(columns) = &config.csv_columns {
+        push("--columns", Some(columns.join(",")));
+    }
+    if config.lang != Lang::default() {
+        push("--lang", Some(config.lang.as_str().to_string()));
+    }
+    if config.album_gain {
+        push("--album-gain", None);
+    }
+    if let Some(standard) = config.compliance {
+        push("--compliance", Some(standard.as_str().to_string()));
+    }
+    if let Some(max) = config.compliance_lra_max {
+        push("--compliance-lra-max", Some(max.to_string()));
+    }
+    if let Some(score) = config.result_filter.min_score {
+        push("--min-score", Some(score.to_string()));
+    }
+    if let Some(score) = config.result_filter.max_score {
+        push("--max-score", Some(score.to_string()));
+    }
+    if !config.result_filter.statuses.is_empty() {
+        let statuses: Vec<&str> = config
+            .result_filter
+            .statuses
+            .iter()
+            .map(|status| status.slug())
+            .collect();
+        push("--status", Some(statuses.join(",")));
+    }
+    if let Some(score) = config.quality_gate.fail_under {
+        push("--fail-under", Some(score.to_string()));
+    }
+    if !config.quality_gate.fail_on_status.is_empty() {
+        let statuses: Vec<&str> = config
+            .quality_gate
+            .fail_on_status
+            .iter()
+            .map(|status| status.slug())
+            .collect();
+        push("--fail-on-status", Some(statuses.join(",")));
+    }
+    if !config.safe_mode {
+        push("--unsafe-mode", None);
+    }
+    if !config.cache_enabled {
+        push("--no-cache", None);
+    }
+    if let Some(days) = config.cache_max_age_days {
+        push("--cache-max-age-days", Some(days.to_string()));
+    }
+    if config.hash_algorithm != HashAlgorithm::Sha256 {
+        push(
+            "--hash-algorithm",
+            Some(config.hash_algorithm.as_str().to_string()),
+        );
+    }
+    if config.fingerprint_mode != FingerprintMode::Strict {
+        push(
+            "--fingerprint",
+            Some(config.fingerprint_mode.as_str().to_string()),
+        );
+    }
+    if let Some(cache_path) = &config.cache_path {
+        push("--cache-path", Some(cache_path.display().to_string()));
+    }
+    if let Some(ffmpeg_path) = &config.ffmpeg_path {
+        push("--ffmpeg-path", Some(ffmpeg_path.display().to_string()));
+    }
+    if let Some(ffprobe_path) = &config.ffprobe_path {
+        push("--ffprobe-path", Some(ffprobe_path.display().to_string()));
+    }
+    if let Some(level) = config.log_level {
+        push("--log-level", Some(level.to_string().to_ascii_lowercase()));
+    }
+    if let Some(log_file) = &config.log_file {
+        push("--log-file", Some(log_file.display().to_string()));
+    }
+    if config.emit_json_report {
+        push("--json-report", None);
+    }
+    if config.emit_jsonl {
+        push("--jsonl", None);
+    }
+    if config.emit_sarif {
+        push("--sarif", None);
+    }
+    if config.json_stdout {
+        push("--json-stdout", None);
+    }
+    if config.quiet {
+        push("--quiet", None);
+    }
+    if config.emit_playlists {
+        push("--export-playlists", None);
+    }
+    if config.emit_tags {
+        push("--export-tags", None);
+    }
+    if config.emit_sidecars {
+        push("--sidecar", None);
+    }
+    if config.emit_folder_summaries {
+        push("--folder-summary", None);
+    }
+    if config.reuse_sidecars {
+        push("--reuse-sidecars", None);
+    }
+    if config.dry_run {
+        push("--dry-run", None);
+    }
+    if config.reanalyze_on_ffmpeg_change {
+        push("--reanalyze-on-ffmpeg-change", None);
+    }
+    if config.pcm_md5 {
+        push("--pcm-md5", None);
+    }
+    if config.detect_limiting {
+        push("--detect-limiting", None);
+    }
+    if let Some(dir) = &config.dataset_dir {
+        push("--export-dataset", Some(dir.display().to_string()));
+    }
+    if let Some(db) = &config.sqlite_path {
+        push("--sqlite", Some(db.display().to_string()));
+    }
+    for template in &config.report_templates {
+        push("--template", Some(template.to_string()));
+    }
+    if let Some(scope) = config.spectrograms {
+        push("--spectrograms", Some(scope.as_str().to_string()));
+    }
+    push(
+        "--profile",
+        Some(config.scoring_profile.as_str().to_string()),
+    );
+    if let Some(dir) = &config.output_dir {
+        push("--output-dir", Some(dir.display().to_string()));
+    }
+    if let Some(file) = &config.profile_file {
+        push("--profile-file", Some(file.display().to_string()));
+    }
+    if let Some(file) = &config.lossy_model_file {
+        push("--lossy-model", Some(file.display().to_string()));
+    }
+    if let Some(file) = &config.baseline_file {
+        push("--baseline", Some(file.display().to_string()));
+    }
+    if let Some(weights) = config.score_weights {
+        let values = [
+            weights.compliance,
+            weights.dynamics,
+            weights.spectrum,
+            weights.authenticity,
+            weights.integrity,
+        ];
+        let values: Vec<String> = values.iter().map(f64::to_string).collect();
+        push("--score-weights", Some(values.join(",")));
+    }
+    if let Some(target) = config.threshold_overrides.target_lufs {
+        push("--target-lufs", Some(target.to_string()));
+    }
+    if let Some(max) = config.threshold_overrides.true_peak_max {
+        push("--true-peak-max", Some(max.to_string()));
+    }
+    if let Some(min) = config.threshold_overrides.min_bitrate_kbps {
+        push("--min-bitrate", Some(min.to_string()));
+    }
+    if config.scorer_version != scoring::SCORER_VERSION {
+        push("--scorer-version", Some(config.scorer_version.to_string()));
+    }
+    for file in &config.rule_files {
+        push("--rule", Some(file.display().to_string()));
+    }
+    if config.grade_scale != GradeScale::default() {
+        push("--grade-scale", Some(config.grade_scale.to_string()));
+    }
+    if config.max_failure_percent != 10.0 {
+        push(
+            "--max-failure-percent",
+            Some(config.max_failure_percent.to_string()),
+        );
+    }
+    if config.usage_stats_enabled {
+        push("--usage-stats", None);
+    }
+
+    args.push(path.display().to_string());
+    args
+}
+
+/// 会话结束时打印等效命令，并追加到日志目录中的会话日志。
+fn print_session_commands(commands: &[String], config: &AppConfig) {
+    if commands.is_empty() {
+        return;
+    }
+    uiprintln!("\n本次会话的等效命令（可直接用于脚本或定时任务）:");
+    for command in commands {
+        uiprintln!("  {command}");
+    }
+    match session_log::append_session(&config.app_paths.logs_dir, commands) {
+        Ok(path) => uiprintln!("已保存到会话日志: {}", path.display()),
+        Err(e) => tracing::warn!("保存会话日志失败: {e}"),
+    }
+}
+
+/// 首次运行向导：检测 FFmpeg、按两个问题推荐评分档案、选择报告输出目录，并写入配置文件。
+fn run_setup_wizard(config: &mut AppConfig) -> Result<()> {
+    uiprintln!("\n--- 设置向导 ---");
+    let config_path = config.app_paths.config_file();
+    let mut user_config = UserConfig::load(&config_path)?.unwrap_or_default();
+
+    uiprintln!("\n[1/3] 检查 FFmpeg");
+    if find_ffmpeg_path(config.ffmpeg_path.as_deref()).is_err() {
+        uiprintln!("未找到 FFmpeg，分析需要它才能运行。");
+        let installed = match ffmpeg_install_command() {
+            Some(command) => {
+                let line = command.join(" ");
+                let answer = prompt(&format!("是否现在运行 `{line}` 安装 FFmpeg? [y/N]: "))?;
+                answer.eq_ignore_ascii_case("y") && run_ffmpeg_installer(command)
+            }
+            None => false,
+        };
+        if !installed {
+            uiprintln!("可以通过以下方式安装:");
+            uiprintln!("  {}", ffmpeg_install_hint());
+            uiprintln!("  或从 https://ffmpeg.org/download.html 下载后解压到任意位置");
+            loop {
+                let answer =
+                    prompt("已下载到其他位置时请输入 ffmpeg 可执行文件路径（直接回车跳过）: ")?;
+                if answer.is_empty() {
+                    break;
+                }
+                // 与自动查找相同的校验：`-version` 能成功运行才保存。
+                match find_ffmpeg_path(Some(Path::new(&answer))) {
+                    Ok(path) => {
+                        user_config.ffmpeg_path = Some(path);
+                        break;
+                    }
+                    Err(e) => eprintln!("{e}"),
+                }
+            }
+        }
+    }
+
+    uiprintln!("\n[2/3] 选择评分档案");
+    let voice = prompt("主要分析的内容? 1. 音乐（默认）  2. 语音（播客、有声书）: ")? == "2";
+    let archive = prompt("主要用途? 1. 流媒体发布/日常聆听（默认）  2. 存档/收藏: ")? == "2";
+    let profile = wizard_profile(voice, archive);
+    uiprintln!("已选择评分档案: {}", profile.as_str());
+    user_config.profile = Some(profile.as_str().to_string());
+
+    uiprintln!("\n[3/3] 报告输出目录");
+    let answer = prompt("报告保存到哪个目录?（直接回车表示写入被分析的文件夹）: ")?;
+    user_config.output_dir = (!answer.is_empty()).then(|| PathBuf::from(answer));
+
+    user_config.save(&config_path, config.safe_mode)?;
+    uiprintln!("\n设置已保存到: {}", config_path.display());
+
+    config.scoring_profile = profile;
+    config.custom_profile = None;
+    config.output_dir = user_config.output_dir;
+    if user_config.ffmpeg_path.is_some() {
+        config.ffmpeg_path = user_config.ffmpeg_path;
+    }
+    Ok(())
+}
+
+/// 当前生效的评分档案名称（自定义档案文件优先）。
+fn profile_name(config: &AppConfig) -> &str {
+    config
+        .custom_profile
+        .as_ref()
+        .map_or(config.scoring_profile.as_str(), |custom| {
+            custom.name.as_str()
+        })
+}
+
+/// 菜单中直接选择内置评分档案：之后的分析按该档案评分，并保存为配置文件中的默认档案。
+fn choose_profile(config: &mut AppConfig) -> Result<()> {
+    uiprintln!("\n可选的评分档案:");
+    for (i, profile) in ScoringProfile::ALL.iter().enumerate() {
+        uiprintln!("{}. {}", i + 1, profile.as_str());
+    }
+    let answer = prompt(&format!(
+        "请输入编号或名称（直接回车保持 {}）: ",
+        profile_name(config)
+    ))?;
+    if answer.is_empty() {
+        return Ok(());
+    }
+    let profile = match answer.parse::<usize>() {
+        Ok(n) => *n
+            .checked_sub(1)
+            .and_then(|i| ScoringProfile::ALL.get(i))
+            .ok_or_else(|| anyhow!("编号超出范围: {n}"))?,
+        Err(_) => ScoringProfile::from_str(&answer).map_err(|e| anyhow!(e))?,
+    };
+
+    let config_path = config.app_paths.config_file();
+    let mut user_config = UserConfig::load(&config_path)?.unwrap_or_default();
+    user_config.profile = Some(profile.as_str().to_string());
+    user_config.save(&config_path, config.safe_mode)?;
+
+    config.scoring_profile = profile;
+    config.custom_profile = None;
+    config.profile_file = None;
+    uiprintln!("已选择评分档案: {}（已保存为默认档案）", profile.as_str());
+    Ok(())
+}
+
+/// 浏览已有报告：按当前评分设置重新评分报告目录中的 `analysis_data.json`，显示摘要与排名，
+/// 再按文件名关键字查看单个文件的详细指标。
+fn browse_report(config: &AppConfig) -> Result<()> {
+    let answer = prompt("\n请输入报告目录或 analysis_data.json 的路径（直接回车返回）: ")?;
+    if answer.is_empty() {
+        return Ok(());
+    }
+    let (metrics, root) = compare::load_run(Path::new(&answer))?;
+    let analyses = build_scorer(config)?.analyze_files_owned(metrics);
+    let generator = ReportGenerator::new(config.safe_mode)
+        .with_collation(FileNameCollator::parse(&config.collation)?)
+        .with_sort(config.report_sort)
+        .with_ranking_size(config.ranking_size)
+        .with_color(config.color)
+        .with_lang(config.lang);
+    uiprintln!(
+        "\n已加载 {} 条结果（评分档案: {}）: {}",
+        analyses.len(),
+        profile_name(config),
+        root.display()
+    );
+    generator.display_summary(&analyses);
+
+    const MAX_LISTED: usize = 20;
+    loop {
+        let query = prompt("\n输入文件名关键字查看详细指标（直接回车返回菜单）: ")?;
+        if query.is_empty() {
+            return Ok(());
+        }
+        let needle = query.to_lowercase();
+        let matches: Vec<&scoring::QualityAnalysis> = analyses
+            .iter()
+            .filter(|analysis| analysis.file_path.to_lowercase().contains(&needle))
+            .collect();
+        let selected = match matches.as_slice() {
+            [] => {
+                eprintln!("没有匹配的文件: {query}");
+                continue;
+            }
+            [only] => *only,
+            _ => {
+                uiprintln!("匹配到 {} 个文件:", matches.len());
+                for (i, analysis) in matches.iter().take(MAX_LISTED).enumerate() {
+                    uiprintln!(
+                        "{:>3}. [{}] {}",
+                        i + 1,
+                        analysis.quality_score,
+                        sanitize_for_terminal(&analysis.file_path)
+                    );
+                }
+                if matches.len() > MAX_LISTED {
+                    uiprintln!(
+                        "  ... 其余 {} 个，请输入更具体的关键字",
+                        matches.len() - MAX_LISTED
+                    );
+                }
+                let choice = prompt("请输入编号（直接回车重新搜索）: ")?;
+                match choice
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|i| matches.iter().take(MAX_LISTED).nth(i))
+                {
+                    Some(analysis) => *analysis,
+                    None => continue,
+                }
+            }
+        };
+        generator.display_file_details(selected);
+    }
+}
+
+/// 向导的两个问题对应的默认评分档案。
+fn wizard_profile(voice: bool, archive: bool) -> ScoringProfile {
+    match (voice, archive) {
+        (false, false) => ScoringProfile::Pop,
+        (false, true) => ScoringProfile::Archive,
+        (true, false) => ScoringProfile::Podcast,
+        (true, true) => ScoringProfile::Audiobook,
+    }
+}
+
+fn ffmpeg_install_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macOS: brew install ffmpeg"
+    } else if cfg!(target_os = "windows") {
+        "Windows: winget install Gyan.FFmpeg"
+    } else {
+        "Linux: sudo apt install ffmpeg（或发行版对应的包管理器）"
+    }
+}
+
+/// 当前平台可直接运行的 FFmpeg 安装命令；找不到对应的包管理器时为 `None`。
+fn ffmpeg_install_command() -> Option<&'static [&'static str]> {
+    let candidates: &[&'static [&'static str]] = if cfg!(target_os = "macos") {
+        &[&["brew", "install", "ffmpeg"]]
+    } else if cfg!(target_os = "windows") {
+        &[&["winget", "install", "--id", "Gyan.FFmpeg", "-e"]]
+    } else {
+        &[
+            &["sudo", "apt-get", "install", "-y", "ffmpeg"],
+            &["sudo", "dnf", "install", "-y", "ffmpeg"],
+            &["sudo", "pacman", "-S", "--noconfirm", "ffmpeg"],
+        ]
+    };
+    candidates.iter().copied().find(|command| {
+        let manager = match command {
+            ["sudo", manager, ..] => manager,
+            [manager, ..] => manager,
+            [] => return false,
+        };
+        which(manager).is_ok()
+    })
+}
+
+/// 运行安装命令（继承终端，便于输入密码或确认），安装后能在 PATH 中找到可运行的 ffmpeg 才算成功。
+fn run_ffmpeg_installer(command: &[&str]) -> bool {
+    match std::process::Command::new(command[0])
+        .args(&command[1..])
+        .status()
+    {
+        Ok(status) if status.success() => {
+            if find_ffmpeg_path(None).is_ok() {
+                return true;
+            }
+            eprintln!("安装命令已完成，但仍未找到可运行的 ffmpeg（可能需要重新打开终端）");
+        }
+        Ok(status) => eprintln!("安装命令未成功 ({status})"),
+        Err(e) => eprintln!("无法运行安装命令 {}: {e}", command[0]),
+    }
+    false
+}
+
+fn prompt(message: &str) -> Result<String> {
+    uiprint!("{message}");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// 读取报告目录中记录的音乐库根目录；报告直接写在库目录中时没有该文件。
+/// 报告不写在库目录中时创建报告目录，并记录其对应的音乐库根目录。
+fn prepare_report_dir(report_dir: &Path, library_root: &Path, config: &AppConfig) -> Result<()> {
+    if report_dir != library_root {
+        AppPaths::ensure_dir(report_dir)?;
+        let marker = serde_json::json!({ "libraryRoot": library_root });
+        safe_io::atomic_write_string(
+            &report_dir.join(schema::SOURCE_MARKER_FILE_NAME),
+            &marker.to_string(),
+            config.safe_mode,
+        )?;
+        uiprintln!("报告输出目录: {}", report_dir.display());
+    }
+    Ok(())
+}
+
+/// 有失败文件时询问是否只重试这些文件，可连续重试直到全部成功或用户放弃。
+fn offer_retry_failed(
+    base_folder_path: &Path,
+    mut outcome: RunOutcome,
+    config: &AppConfig,
+) -> Result<()> {
+    let errors_csv = config
+        .report_dir(base_folder_path)
+        .join(failures::ERRORS_CSV_FILE_NAME);
+    while outcome.failed_files > 0 && !outcome.cancelled {
+        uiprint!(
+            "\n有 {} 个文件处理失败，是否只重新分析这些文件并合并结果? (y/N): ",
+            outcome.failed_files
+        );
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            uiprintln!("可稍后使用 --retry-failed {} 重试。", errors_csv.display());
+            break;
+        }
+
+        outcome = match retry_failed_files(&errors_csv, config) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("\n重试过程中发生错误: {e}");
+                break;
+            }
+        };
+    }
+    if outcome.cancelled {
+        CANCELLED.store(false, Ordering::SeqCst);
+        uiprintln!("\n重试已取消，已完成部分的结果已保存。");
+    }
+    Ok(())
+}
+
+fn get_path_from_user_interaction() -> Result<PathBuf> {
+    uiprintln!("\n请输入音频文件夹路径（支持相对路径或绝对路径）");
+
+    loop {
+        uiprint!("\n路径: ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let path_str = input.trim();
+
+        if path_str.is_empty() {
+            eprintln!("路径不能为空，请重试。");
+            continue;
+        }
+
+        let path = PathBuf::from(path_str);
+        if path.is_dir() {
+            return path.canonicalize().context("路径规范化失败，请检查权限");
+        }
+
+        if path.exists() {
+            eprintln!("输入路径不是文件夹: {}", path.display());
+        } else {
+            eprintln!("路径不存在: {}", path.display());
+        }
+    }
+}
+
+/// 命令行或环境变量指定的 ffmpeg/ffprobe 必须存在且可执行；写错时直接报错，而不是悄悄换用 PATH 中的版本。
+fn validate_executable(path: &Path, tool: &str) -> Result<PathBuf> {
+    which(path).map_err(|_| {
+        ExitError::new(
+            ExitStatus::Environment,
+            format!(
+                "[E_FFMPEG_PATH] 指定的 {tool} 不存在或不可执行: {}",
+                path.display()
+            ),
+        )
+        .into()
+    })
+}
+
+/// 查找 ffmpeg：指定的路径（命令行、环境变量或配置文件）优先，其次 PATH 与当前目录下的 `resources/`。
+/// 候选文件须能运行 `-version`；指定的路径无法运行时直接报错，不悄悄换用其他位置的版本。
+fn find_ffmpeg_path(configured: Option<&Path>) -> Result<PathBuf> {
+    if let Some(path) = configured {
+        if ffmpeg::responds_to_version(path) {
+            uiprintln!("使用指定的 ffmpeg: {}", path.display());
+            return Ok(path.to_path_buf());
+        }
+        return Err(ExitError::new(
+            ExitStatus::Environment,
+            format!(
+                "[E_FFMPEG_PATH] 指定的 ffmpeg 无法运行（ffmpeg -version 失败）: {}",
+                path.display()
+            ),
+        )
+        .into());
+    }
+
+    if let Some(path) = which("ffmpeg")
+        .ok()
+        .filter(|path| ffmpeg::responds_to_version(path))
+    {
+        uiprintln!("成功在 PATH 中找到 ffmpeg: {}", path.display());
+        return Ok(path);
+    }
+
+    let bundled = env::current_dir()
+        .ok()
+        .map(|cwd| cwd.join("resources").join(tool_file_name("ffmpeg")))
+        .filter(|path| path.is_file() && ffmpeg::responds_to_version(path));
+    if let Some(path) = bundled {
+        uiprintln!("未在 PATH 找到 ffmpeg，使用备用路径: {}", path.display());
+        return Ok(path);
+    }
+
+    Err(ExitError::new(
+        ExitStatus::Environment,
+        "在 PATH 与 resources 目录中均未找到可运行的 ffmpeg，可执行文件缺失。",
+    )
+    .into())
+}
+
+/// 查找 ffprobe：指定的路径优先；显式指定了 ffmpeg 时先找它同目录的 ffprobe，保证两者来自同一套安装，
+/// 否则先找 PATH。候选文件须能运行 `-version`。找不到时返回 `None`，跳过元数据分析。
+fn find_ffprobe_path(ffmpeg_path: &Path, config: &AppConfig) -> Option<PathBuf> {
+    if let Some(path) = &config.ffprobe_path {
+        if ffmpeg::responds_to_version(path) {
+            uiprintln!("使用指定的 ffprobe: {}", path.display());
+            return Some(path.clone());
+        }
+        tracing::warn!(
+            "指定的 ffprobe 无法运行（ffprobe -version 失败），将跳过元数据分析: {}",
+            path.display()
+        );
+        return None;
+    }
+
+    let sibling = ffmpeg_path
+        .parent()
+        .map(|parent| parent.join(tool_file_name("ffprobe")))
+        .filter(|path| path.is_file() && ffmpeg::responds_to_version(path));
+    let on_path = || {
+        which("ffprobe")
+            .ok()
+            .filter(|path| ffmpeg::responds_to_version(path))
+    };
+    let found = if config.ffmpeg_path.is_some() {
+        sibling
+            .map(|path| (path, "使用指定 ffmpeg 同目录的 ffprobe"))
+            .or_else(|| {
+                on_path().map(|path| (path, "指定 ffmpeg 的同目录没有 ffprobe，使用 PATH 中的"))
+            })
+    } else {
+        on_path()
+            .map(|path| (path, "成功在 PATH 中找到 ffprobe"))
+            .or_else(|| sibling.map(|path| (path, "未在 PATH 找到 ffprobe，使用同目录备用路径")))
+    };
+    match found {
+        Some((path, source)) => {
+            uiprintln!("{source}: {}", path.display());
+            Some(path)
+        }
+        None => {
+            uiprintln!("未找到 ffprobe，将跳过采样率/码率/声道等元数据分析。");
+            None
+        }
+    }
+}
+
+/// 当前平台上的可执行文件名（Windows 上带 `.exe`）。
+fn tool_file_name(tool: &str) -> String {
+    format!("{tool}{}", env::consts::EXE_SUFFIX)
+}
+
+fn sanitize_for_terminal(input: &str) -> String {
+    input
+        .chars()
+        .filter(|ch| {
+            let c = *ch as u32;
+            c == 0x09 || c == 0x20 || (0x21..=0x7e).contains(&c) || c >= 0xa0
+        })
+        .collect()
+}
+
+/// 分析范围：扫描整个目录，只重试失败清单中的文件，或继续中断分析的待续队列。
+/// 后两者都只分析列出的文件，并与报告目录中已有的结果合并。
+enum AnalysisScope {
+    FullScan,
+    RetryFailed(Vec<PathBuf>),
+    Resume(Vec<PathBuf>),
+    /// 只分析命令行列出的文件（含多个路径展开后的并集），结果合并进公共目录已有的报告。
+    Selection(Vec<PathBuf>),
+    /// 分析列出的文件并合并进指定的已有报告目录（`--merge`）。
+    Merge {
+        files: Vec<PathBuf>,
+        report_dir: PathBuf,
+    },
+    /// 同 `Selection`，另外下载并分析网络地址上的文件。
+    Remote {
+        files: Vec<PathBuf>,
+        urls: Vec<String>,
+    },
+}
+
+fn run_analysis(base_folder_path: &Path, config: &AppConfig) -> Result<RunOutcome> {
+    analyze(base_folder_path, config, AnalysisScope::FullScan)
+}
+
+/// 只重新分析失败清单中的文件；报告写回清单所在目录，并与该目录已有的结果合并。
+fn retry_failed_files(errors_csv: &Path, config: &AppConfig) -> Result<RunOutcome> {
+    let failed_paths = failures::read_failed_paths(errors_csv)?;
+    let report_dir = errors_csv
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .ok_or_else(|| anyhow!("无法确定失败清单所在目录: {}", errors_csv.display()))?;
+    let base_folder_path = schema::read_source_marker(&report_dir).unwrap_or(report_dir);
+    if failed_paths.is_empty() {
+        uiprintln!("失败清单为空，无需重试: {}", errors_csv.display());
+        return Ok(RunOutcome::default());
+    }
+    analyze(
+        &base_folder_path,
+        config,
+        AnalysisScope::RetryFailed(failed_paths),
+    )
+}
+
+/// 分析输入路径并把结果合并进已有报告目录；报告中的曲库根目录取自该目录的来源标记，
+/// 使增量扫描的新专辑与原有结果共用一份报告。
+fn merge_into_report(
+    inputs: &[PathBuf],
+    report_dir: &Path,
+    config: &AppConfig,
+) -> Result<RunOutcome> {
+    let report_dir = report_dir
+        .canonicalize()
+        .with_context(|| format!("[E_MERGE] 报告目录不存在: {}", report_dir.display()))?;
+    let library_root =
+        schema::read_source_marker(&report_dir).unwrap_or_else(|| report_dir.clone());
+    let files = scan::expand_inputs(inputs, &config.scan_options);
+    if files.is_empty() {
+        uiprintln!("在指定路径下没有找到支持的音频文件。");
+        return Ok(RunOutcome::default());
+    }
+    analyze(
+        &library_root,
+        config,
+        AnalysisScope::Merge { files, report_dir },
+    )
+}
+
+/// `--dry-run`：按与正式分析相同的扫描与过滤规则列出概况，不启动 FFmpeg、不写出任何文件。
+fn dry_run(
+    base_folder_path: &Path,
+    config: &AppConfig,
+    scope: AnalysisScope,
+) -> Result<RunOutcome> {
+    uiprintln!("\n--- 试运行（不分析、不写出报告） ---");
+    uiprintln!("曲库根目录: {}", base_folder_path.display());
+    let mut skipped_extras = 0;
+    let files = match scope {
+        AnalysisScope::FullScan => {
+            let (files, skipped) =
+                scan::scan_audio_files_counted(base_folder_path, &config.scan_options);
+            skipped_extras = skipped;
+            files
+        }
+        AnalysisScope::RetryFailed(paths)
+        | AnalysisScope::Resume(paths)
+        | AnalysisScope::Selection(paths)
+        | AnalysisScope::Merge { files: paths, .. } => {
+            scan::scan_listed_files(base_folder_path, &paths, &config.scan_options)
+        }
+        AnalysisScope::Remote { files, urls } => {
+            uiprintln!("另有 {} 个网络地址（试运行不下载）", urls.len());
+            scan::scan_listed_files(base_folder_path, &files, &config.scan_options)
+        }
+    };
+    let summary = scan::ScanSummary {
+        skipped_extras,
+        ..scan::ScanSummary::of(&files)
+    };
+    uiprintln!(
+        "将分析 {} 个文件，共 {}",
+        summary.files,
+        savings::format_bytes(summary.bytes)
+    );
+    if summary.extra_files > 0 {
+        uiprintln!("其中附加音轨: {} 个", summary.extra_files);
+    }
+    if summary.skipped_extras > 0 {
+        uiprintln!(
+            "跳过附加音轨（隐藏文件、前置间隙等）: {} 个，使用 --include-extras 一并分析",
+            summary.skipped_extras
+        );
+    }
+    if summary.cue_images > 0 {
+        uiprintln!(
+            "带 CUE 的整轨镜像: {} 个（按 {} 首曲目逐轨分析）",
+            summary.cue_images,
+            summary.cue_tracks
+        );
+    }
+    for (ext, count, bytes) in &summary.by_extension {
+        uiprintln!(
+            "  .{:<6} {:>7} 个  {:>10}",
+            ext,
+            count,
+            savings::format_bytes(*bytes)
+        );
+    }
+    Ok(RunOutcome {
+        total_files: summary.files,
+        ..RunOutcome::default()
+    })
+}
+
+/// 提取阶段进度条的附加计数：实际解码的累计字节数与缓存命中的文件数。
+#[derive(Debug, Default)]
+struct ExtractionProgress {
+    bytes: AtomicU64,
+    cache_hits: AtomicU64,
+}
+
+impl ExtractionProgress {
+    fn record(&self, records: &[ProcessedRecord]) {
+        // CUE 整轨镜像的各曲目来自同一文件，只计一次；缓存命中的文件没有解码，不计字节。
+        if records.iter().any(|record| record.metrics.cache_hit) {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(first) = records.first() {
+            self.bytes
+                .fetch_add(first.metrics.file_size_bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 提取进度条：除进度与剩余时间外，显示当前速度、累计吞吐量与缓存命中率，
+/// 便于判断长时间的扫描是否值得继续等待。
+fn extraction_progress_style(progress: &Arc<ExtractionProgress>) -> ProgressStyle {
+    let progress = Arc::clone(progress);
+    ProgressStyle::with_template(
+        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) 剩余 {eta_precise} | {rates} - {msg}",
+    )
+    .unwrap_or_else(|_| ProgressStyle::default_bar())
+    .with_key(
+        "rates",
+        move |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+            let _ = w.write_str(&format_progress_rates(
+                state.per_sec(),
+                state.pos(),
+                state.elapsed(),
+                progress.bytes.load(Ordering::Relaxed),
+                progress.cache_hits.load(Ordering::Relaxed),
+            ));
+        },
+    )
+    .progress_chars("#>- ")
+}
+
+/// 当前速度（近期的平滑值）、累计 MB/s 与缓存命中率。
+fn format_progress_rates(
+    files_per_second: f64,
+    done: u64,
+    elapsed: Duration,
+    bytes: u64,
+    cache_hits: u64,
+) -> String {
+    let throughput = Throughput {
+        files: done as usize,
+        bytes,
+        elapsed,
+    };
+    let hit_percent = if done > 0 {
+        cache_hits as f64 / done as f64 * 100.0
+    } else {
+        0.0
+    };
+    format!(
+        "{:.1} 文件/分钟 | {:.1} MB/s | 缓存命中 {hit_percent:.0}%",
+        files_per_second * 60.0,
+        throughput.mb_per_second()
+    )
+}
+
+fn analyze(
+    base_folder_path: &Path,
+    config: &AppConfig,
+    scope: AnalysisScope,
+) -> Result<RunOutcome> {
+    if config.dry_run {
+        return dry_run(base_folder_path, config, scope);
+    }
+    let _cancellable = CancellableTask::begin();
+    let run_started = Instant::now();
+    tracing::info!("开始分析: {}", base_folder_path.display());
+    uiprintln!("\n--- 开始执行分析流程 ---");
+    uiprintln!("分析开始时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    uiprintln!(
+        "安全模式: {} | 缓存: {} | 命令超时: {}s | 并行任务: {} | 最大并发进程: {} | 评分档案: {}",
+        if config.safe_mode { "开启" } else { "关闭" },
+        if config.cache_enabled {
+            "开启"
+        } else {
+            "关闭"
+        },
+        config.command_timeout.as_secs(),
+        config.jobs.map_or_else(
+            || rayon::current_num_threads().to_string(),
+            |n| n.to_string()
+        ),
+        config.max_ffmpeg_processes,
+        profile_name(config)
+    );
+    if config.fast_sampling {
+        uiprintln!(
+            "快速采样模式: 每个文件仅分析 3 个 {:.0} 秒窗口，结果为估算值",
+            ffmpeg::SAMPLE_WINDOW_SECONDS
+        );
+    }
+
+    let ffmpeg_path = find_ffmpeg_path(config.ffmpeg_path.as_deref())?;
+    let ffprobe_path = find_ffprobe_path(&ffmpeg_path, config);
+
+    let report_dir = match &scope {
+        AnalysisScope::Merge { report_dir, .. } => report_dir.clone(),
+        _ => config.report_dir(base_folder_path),
+    };
+    prepare_report_dir(&report_dir, base_folder_path, config)?;
+
+    // 限制了扫描深度时更深处的文件不在本次范围内，不能据此判定移除。
+    let full_scan =
+        matches!(scope, AnalysisScope::FullScan) && config.scan_options.max_depth.is_none();
+    let resuming = matches!(scope, AnalysisScope::Resume(_));
+    let mut remote: Option<remote::RemoteDownloads> = None;
+    let (audio_files, retried_paths) = match scope {
+        AnalysisScope::FullScan => {
+            uiprintln!("正在扫描文件夹: {}", base_folder_path.display());
+            let (files, skipped_extras) =
+                scan::scan_audio_files_counted(base_folder_path, &config.scan_options);
+            if skipped_extras > 0 {
+                uiprintln!(
+                    "已跳过 {skipped_extras} 个附加音轨（隐藏文件、前置间隙等），使用 --include-extras 一并分析"
+                );
+            }
+            (files, None)
+        }
+        AnalysisScope::RetryFailed(paths) | AnalysisScope::Resume(paths) => {
+            if resuming {
+                uiprintln!(
+                    "继续分析待续队列中的 {} 个文件: {}",
+                    paths.len(),
+                    base_folder_path.display()
+                );
+            } else {
+                uiprintln!(
+                    "重试上次失败的 {} 个文件: {}",
+                    paths.len(),
+                    base_folder_path.display()
+                );
+            }
+            let files = scan::scan_listed_files(base_folder_path, &paths, &config.scan_options);
+            (files, Some(paths))
+        }
+        AnalysisScope::Selection(files) => {
+            uiprintln!(
+                "分析指定的 {} 个文件（曲库根目录: {}）",
+                files.len(),
+                base_folder_path.display()
+            );
+            let scanned = scan::scan_listed_files(base_folder_path, &files, &config.scan_options);
+            (scanned, Some(files))
+        }
+        AnalysisScope::Merge { files, .. } => {
+            uiprintln!(
+                "合并模式: 分析 {} 个文件并合并到已有报告: {}",
+                files.len(),
+                report_dir.display()
+            );
+            let scanned = scan::scan_listed_files(base_folder_path, &files, &config.scan_options);
+            (scanned, Some(files))
+        }
+        AnalysisScope::Remote { files, urls } => {
+            uiprintln!("正在下载 {} 个网络文件到临时目录...", urls.len());
+            let downloads = remote::RemoteDownloads::fetch(
+                &urls,
+                &basic_processing_config(ffmpeg_path.clone(), ffprobe_path.clone(), config),
+            )?;
+            uiprintln!(
+                "下载完成: {}/{}，另分析本地文件 {} 个",
+                urls.len() - downloads.failures.len(),
+                urls.len(),
+                files.len()
+            );
+            let mut scanned =
+                scan::scan_listed_files(base_folder_path, &files, &config.scan_options);
+            // 下载的文件名可能没有可识别的扩展名，不经扩展名过滤直接分析。
+            scanned.extend(downloads.files().into_iter().map(scan::ScannedFile::forced));
+            remote = Some(downloads);
+            (scanned, None)
+        }
+    };
+
+    if audio_files.is_empty() {
+        if let Some(downloads) = remote.as_ref().filter(|d| !d.failures.is_empty()) {
+            return Err(anyhow!(
+                "[E_DOWNLOAD] {} 个网络地址全部下载失败",
+                downloads.failures.len()
+            ));
+        }
+        uiprintln!("在指定路径下没有找到支持的音频文件。");
+        return Ok(RunOutcome::default());
+    }
+
+    let total_files = audio_files.len();
+    let scanned_paths: Vec<String> = audio_files
+        .iter()
+        .map(|f| f.path.to_string_lossy().into_owned())
+        .collect();
+    let extra_files = audio_files
+        .iter()
+        .filter(|f| f.extra_of_album.is_some())
+        .count();
+    tracing::info!("扫描完成: {total_files} 个音频文件");
+    if extra_files > 0 {
+        uiprintln!(
+            "扫描完成，找到 {total_files} 个音频文件（含附加音轨 {extra_files} 个）。开始分析..."
+        );
+    } else {
+        uiprintln!("扫描完成，找到 {total_files} 个音频文件。开始分析...");
+    }
+
+    let cache_path = config.app_paths.library_cache_file(base_folder_path);
+    let mut cache_data = if config.cache_enabled {
+        // 旧版本把缓存写在分析目录中，新位置尚无缓存时沿用一次，之后写入应用缓存目录。
+        let legacy_path = base_folder_path.join(app_paths::LEGACY_CACHE_FILE_NAME);
+        let load_path = if !cache_path.exists() && legacy_path.is_file() {
+            &legacy_path
+        } else {
+            &cache_path
+        };
+        AnalysisCache::load(load_path)
+            .with_context(|| format!("加载增量缓存失败，请检查缓存文件: {}", load_path.display()))?
+    } else {
+        AnalysisCache::default()
+    };
+    let cache_snapshot = cache_data.clone();
+    // 设置了输出目录时单文件结果不写入音乐库，改为镜像到报告目录。
+    let sidecar_location = sidecar::SidecarLocation::new(
+        base_folder_path,
+        config
+            .output_dir
+            .is_some()
+            .then(|| report_dir.join(sidecar::SIDECAR_MIRROR_DIR_NAME)),
+    );
+
+    let mut processing_config = ffmpeg::ProcessingConfig {
+        ffmpeg_path,
+        ffprobe_path,
+        command_timeout: config.command_timeout,
+        process_limiter: ffmpeg::ProcessLimiter::new(config.max_ffmpeg_processes),
+        decode_threads: config.decode_threads,
+        hwaccel: None,
+        hf_bands: config.hf_bands.clone(),
+        fast_sampling: config.fast_sampling,
+        segment_seconds: config.segment_seconds,
+        audio_stream: match config.audio_streams {
+            ffmpeg::StreamSelection::Index(index) => index,
+            ffmpeg::StreamSelection::All => 0,
+        },
+        cue_track: None,
+        ffmpeg_version: None,
+        reanalyze_on_ffmpeg_change: config.reanalyze_on_ffmpeg_change,
+        pcm_md5: config.pcm_md5,
+        detect_limiting: config.detect_limiting,
+    };
+    processing_config.ffmpeg_version = ffmpeg::ffmpeg_version(&processing_config);
+    match &processing_config.ffmpeg_version {
+        Some(version) => uiprintln!("FFmpeg 版本: {version}"),
+        None => uiprintln!("无法识别 FFmpeg 版本，缓存结果的版本一致性不会被检查。"),
+    }
+    let ffprobe_version = ffmpeg::ffprobe_version(&processing_config);
+    if let Some(version) = &ffprobe_version {
+        uiprintln!("FFprobe 版本: {version}");
+    }
+    let toolchain = Toolchain {
+        ffmpeg_path: processing_config.ffmpeg_path.display().to_string(),
+        ffmpeg_version: processing_config.ffmpeg_version.clone(),
+        ffprobe_path: processing_config
+            .ffprobe_path
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        ffprobe_version,
+    };
+    if let Some(hwaccel) = &config.hwaccel {
+        match ffmpeg::hwaccel_available(&processing_config, hwaccel) {
+            Ok(true) => {
+                uiprintln!("启用硬件加速解码: {hwaccel}");
+                processing_config.hwaccel = Some(hwaccel.clone());
+            }
+            Ok(false) => uiprintln!("当前 ffmpeg 不支持硬件加速方式 {hwaccel}，回退为软件解码。"),
+            Err(e) => uiprintln!("无法检测硬件加速支持 ({e})，回退为软件解码。"),
+        }
+    }
+
+    let scorer = build_scorer(config)?;
+    let jsonl_path = report_dir.join(JSONL_PARTIAL_FILE_NAME);
+    let jsonl_stream = if config.emit_jsonl {
+        Some(JsonlStream::create(&jsonl_path, config.safe_mode)?)
+    } else {
+        None
+    };
+
+    let bar = ProgressBar::new(total_files as u64);
+    let progress = Arc::new(ExtractionProgress::default());
+    bar.set_style(extraction_progress_style(&progress));
+    if console::quiet() {
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    let _bar_logging = logging::attach_progress_bar(&bar);
+
+    let extraction_pool = config.jobs.and_then(|threads| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| tracing::warn!("无法创建提取线程池，改用全局线程池: {e}"))
+            .ok()
+    });
+    // 按 `--schedule` 的顺序逐个取出文件（par_bridge 由空闲线程依次领取，而不是预先切块），
+    // 再按扫描下标排回：结果顺序与扫描顺序一致，与处理顺序和各文件的完成先后无关。
+    let order = scan::processing_order(&audio_files, config.schedule);
+    let extract = || -> Vec<Result<Vec<ProcessedRecord>, FailedFile>> {
+        let mut indexed: Vec<(usize, Result<Vec<ProcessedRecord>, FailedFile>)> = order
+            .into_iter()
+            .par_bridge()
+            .map(|index| {
+                let file = &audio_files[index];
+                if CANCELLED.load(Ordering::SeqCst) {
+                    return (index, Ok(Vec::new()));
+                }
+                let path = &file.path;
+                let filename = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                bar.set_message(sanitize_for_terminal(&filename));
+                // 逐文件的 span：其中的日志带上文件路径，关闭时在日志文件中记录耗时。
+                let _span = tracing::info_span!("file", path = %path.display()).entered();
+
+                let result = process_one_file(
+                    path,
+                    &processing_config,
+                    config.audio_streams,
+                    &file.cue_tracks,
+                    config.cache_enabled.then_some(&cache_snapshot),
+                    config.reuse_sidecars.then_some(&sidecar_location),
+                    config.fingerprint_options(),
+                );
+                if let Ok(records) = &result {
+                    progress.record(records);
+                }
+                bar.inc(1);
+
+                let outcome = match result {
+                    Ok(mut records) => {
+                        let extra_of_album = file
+                            .extra_of_album
+                            .as_ref()
+                            .map(|album| album.to_string_lossy().into_owned());
+                        for record in &mut records {
+                            record.metrics.extra_of_album = extra_of_album.clone();
+                        }
+                        if let Some(stream) = &jsonl_stream {
+                            let analyses: Vec<_> = records
+                                .iter()
+                                .map(|record| scorer.analyze_file(&record.metrics))
+                                .filter(|analysis| config.result_filter.matches(analysis))
+                                .collect();
+                            if let Err(e) = stream.append(&analyses) {
+                                tracing::warn!("追加JSONL记录失败: {e:#}");
+                            }
+                        }
+                        Ok(records)
+                    }
+                    Err(e) if ffmpeg::is_not_audio(&e) => {
+                        tracing::info!("跳过非音频文件: {e}");
+                        Err(FailedFile::new(path, &e))
+                    }
+                    Err(e) => {
+                        tracing::warn!("处理失败: {e}");
+                        Err(FailedFile::new(path, &e))
+                    }
+                };
+                (index, outcome)
+            })
+            .collect();
+        indexed.sort_unstable_by_key(|(index, _)| *index);
+        indexed.into_iter().map(|(_, outcome)| outcome).collect()
+    };
+    let file_results = match &extraction_pool {
+        Some(pool) => pool.install(extract),
+        None => extract(),
+    };
+    let mut processed_records: Vec<ProcessedRecord> = Vec::new();
+    let mut failed: Vec<FailedFile> = Vec::new();
+    let mut not_audio: Vec<FailedFile> = Vec::new();
+    for result in file_results {
+        match result {
+            Ok(records) => processed_records.extend(records),
+            Err(failure) if failure.is_not_audio() => not_audio.push(failure),
+            Err(failure) => failed.push(failure),
+        }
+    }
+    let failed_downloads = remote.as_ref().map_or(0, |d| d.failures.len());
+    if let Some(downloads) = &remote {
+        // 报告与失败清单记录原始地址，而不是分析结束即删除的临时文件。
+        for failure in failed.iter_mut().chain(&mut not_audio) {
+            if let Some(url) = downloads.source_of(&failure.file_path) {
+                failure.file_path = url.to_owned();
+            }
+        }
+        failed.extend(
+            downloads
+                .failures
+                .iter()
+                .map(|(url, e)| FailedFile::new(Path::new(url), e)),
+        );
+    }
+    let cancelled = CANCELLED.load(Ordering::SeqCst);
+    let unprocessed: Vec<PathBuf> = if cancelled {
+        let mut done: std::collections::HashSet<&str> = processed_records
+            .iter()
+            .map(|record| record.metrics.file_path.as_str())
+            .collect();
+        done.extend(
+            failed
+                .iter()
+                .chain(&not_audio)
+                .map(|file| file.file_path.as_str()),
+        );
+        scanned_paths
+            .iter()
+            .filter(|path| !done.contains(path.as_str()))
+            .map(PathBuf::from)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if cancelled {
+        bar.abandon_with_message("已取消，正在保存已完成的结果。");
+    } else {
+        bar.finish_with_message("数据提取完成。");
+    }
+
+    let mut results: Vec<FileMetrics> = Vec::with_capacity(processed_records.len());
+    let mut cache_hits = 0usize;
+    // 吞吐量只计本次实际解码的文件：缓存命中不计，CUE 镜像与多音频流的文件只计一次。
+    let mut decoded_files: HashSet<String> = HashSet::new();
+    let mut bytes_analyzed = 0u64;
+    let mut fingerprints: HashMap<String, FileFingerprint> = HashMap::new();
+    for mut record in processed_records {
+        if record.metrics.cache_hit {
+            cache_hits += 1;
+        } else if decoded_files.insert(record.metrics.file_path.clone()) {
+            bytes_analyzed += record.metrics.file_size_bytes;
+        }
+        // 临时文件每次下载的位置都不同，远程文件的结果不写入缓存。
+        let source = remote
+            .as_ref()
+            .and_then(|d| d.source_of(&record.metrics.file_path));
+        if let Some(url) = source {
+            record.metrics.file_path = url.to_owned();
+        }
+        fingerprints.insert(record.metrics.file_path.clone(), record.fingerprint.clone());
+        if config.cache_enabled && source.is_none() {
+            cache_data.upsert(
+                &PathBuf::from(&record.metrics.file_path),
+                record.fingerprint,
+                record.metrics.clone(),
+            );
+        }
+        results.push(record.metrics);
+    }
+    uiprintln!("缓存命中: {cache_hits}/{}", results.len());
+    if let Some(version) = &processing_config.ffmpeg_version {
+        warn_mixed_ffmpeg_versions(&results, version);
+    }
+
+    if config.cache_enabled {
+        let pruned = cache_data.prune(config.cache_max_age_days, cache::now_unix_secs());
+        if pruned.total() > 0 {
+            uiprintln!("{}", describe_pruned(&pruned, config.cache_max_age_days));
+        }
+        AppPaths::ensure_dir(&config.app_paths.cache_dir)?;
+        cache_data
+            .save(&cache_path, config.safe_mode)
+            .with_context(|| format!("保存缓存失败: {}", cache_path.display()))?;
+        uiprintln!("缓存已更新: {}", cache_path.display());
+    }
+    let throughput = Throughput {
+        files: decoded_files.len(),
+        bytes: bytes_analyzed,
+        elapsed: run_started.elapsed(),
+    };
+
+    if let Some(retried) = &retried_paths {
+        results = merge_previous_results(&report_dir, results, retried)?;
+    }
+
+    tracing::info!("数据提取完成，缓存命中 {cache_hits}");
+    let scored = score_and_report(
+        base_folder_path,
+        &report_dir,
+        config,
+        &scorer,
+        ReportInput {
+            results,
+            fingerprints,
+            throughput: Some(throughput),
+            toolchain: Some(toolchain),
+            processing_config: Some(&processing_config),
+            sidecar_location: &sidecar_location,
+        },
+    )?;
+
+    let history_path = config.app_paths.library_history_file(base_folder_path);
+    let mut history = LibraryHistory::load(&history_path)?;
+    let changes = history.record_run(&scanned_paths, &scored.analyses, full_scan);
+    AppPaths::ensure_dir(&config.app_paths.history_dir)?;
+    history
+        .save(&history_path, config.safe_mode)
+        .with_context(|| format!("保存文件历史失败: {}", history_path.display()))?;
+    if !changes.removed.is_empty() {
+        uiprintln!(
+            "\n自上次运行以来移除的文件: {} 个（历史中保留墓碑记录，累计 {} 个）",
+            changes.removed.len(),
+            history.removed_count()
+        );
+        for path in changes.removed.iter().take(10) {
+            uiprintln!("  - {}", sanitize_for_terminal(path));
+        }
+        if changes.removed.len() > 10 {
+            uiprintln!(
+                "  ... 其余 {} 个见 {}",
+                changes.removed.len() - 10,
+                history_path.display()
+            );
+        }
+    }
+    if !changes.restored.is_empty() {
+        uiprintln!("重新出现的文件: {} 个", changes.restored.len());
+    }
+
+    if config.usage_stats_enabled {
+        let files_failed = failed.len() as u64;
+        let mut error_codes: Vec<String> = scored
+            .results
+            .iter()
+            .filter(|m| !m.cache_hit)
+            .flat_map(|m| m.error_codes.iter().cloned())
+            .collect();
+        error_codes.extend((0..files_failed).map(|_| "E_FILE_FAILED".to_string()));
+
+        let run = RunUsage {
+            files_total: total_files as u64,
+            files_failed,
+            cache_hits: cache_hits as u64,
+            bytes_total: bytes_analyzed,
+            wall_time_ms: run_started.elapsed().as_millis() as u64,
+            error_codes,
+        };
+        // 使用统计是可选的附加输出，保存失败只记录警告，不影响已完成的分析。
+        let saved = AppPaths::ensure_dir(&config.app_paths.history_dir).and_then(|dir| {
+            let stats_path = dir.join(usage_stats::USAGE_STATS_FILE_NAME);
+            let mut stats = UsageStats::load(&stats_path);
+            stats.record_run(&run);
+            stats
+                .save(&stats_path, config.safe_mode)
+                .with_context(|| format!("保存使用统计失败: {}", stats_path.display()))?;
+            Ok((stats_path, stats))
+        });
+        match saved {
+            Ok((stats_path, stats)) => {
+                uiprintln!(
+                    "使用统计已更新: {} (累计 {} 次运行, 平均 {:.1} 文件/分钟)",
+                    stats_path.display(),
+                    stats.runs,
+                    stats.average_files_per_minute
+                );
+                for (code, count) in stats.top_errors(3) {
+                    uiprintln!(" - 常见错误 {code}: {count} 次");
+                }
+            }
+            Err(e) => tracing::warn!("使用统计未更新: {e:#}"),
+        }
+    }
+
+    uiprintln!(
+        "\n分析结束时间: {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    uiprintln!("--- 分析流程完成 ---");
+
+    // 取消时未处理的文件不算失败，清单只记录真正出错的文件。
+    let errors_csv_path = report_dir.join(failures::ERRORS_CSV_FILE_NAME);
+    failures::write_errors_csv(&errors_csv_path, &failed, config.safe_mode)?;
+    if !not_audio.is_empty() {
+        display_not_audio_summary(&not_audio);
+    }
+    let failed_files = failed.len();
+    if failed_files > 0 {
+        let _summary = console::summary_section();
+        uiprintln!(
+            "处理失败文件: {failed_files}/{}",
+            total_files + failed_downloads
+        );
+    }
+    if config.json_stdout {
+        print_json_results(
+            &scored.summary,
+            &config.result_filter.apply(&scored.analyses),
+            &failed,
+        )?;
+    }
+    Ok(RunOutcome {
+        total_files: total_files + failed_downloads,
+        failed_files,
+        cancelled,
+        compliance_failures: scored.compliance_failures,
+        delivery_failures: scored.delivery_failures,
+        gate_failures: scored.gate_failures,
+        unprocessed,
+    })
+}
+
+/// 评分与报告阶段的输入：提取阶段的结果，或 `rescore` 读取的已有指标。
+struct ReportInput<'a> {
+    results: Vec<FileMetrics>,
+    /// 按文件路径的内容指纹，写入单文件结果与 SQLite；没有时留空。
+    fingerprints: HashMap<String, FileFingerprint>,
+    throughput: Option<Throughput>,
+    toolchain: Option<Toolchain>,
+    /// 渲染频谱图所用的 FFmpeg；为 `None` 时不生成频谱图。
+    processing_config: Option<&'a ffmpeg::ProcessingConfig>,
+    sidecar_location: &'a sidecar::SidecarLocation,
+}
+
+/// 评分与报告阶段的结果，供文件历史、使用统计与退出码使用。
+struct ScoredRun {
+    /// 按报告顺序排列的原始指标。
+    results: Vec<FileMetrics>,
+    analyses: Vec<scoring::QualityAnalysis>,
+    summary: SummaryStats,
+    compliance_failures: usize,
+    delivery_failures: usize,
+    gate_failures: usize,
+}
+
+/// 对一批指标评分，写出全部报告并显示摘要；不调用 FFmpeg（频谱图除外）。
+fn score_and_report(
+    library_root: &Path,
+    report_dir: &Path,
+    config: &AppConfig,
+    scorer: &QualityScorer,
+    input: ReportInput,
+) -> Result<ScoredRun> {
+    uiprintln!("正在进行质量评分分析...");
+    let mut report_generator = ReportGenerator::new(config.safe_mode)
+        .with_collation(FileNameCollator::parse(&config.collation)?)
+        .with_sort(config.report_sort)
+        .with_ranking_size(config.ranking_size)
+        .with_columns(config.csv_columns.clone())
+        .with_color(config.color)
+        .with_lang(config.lang);
+    if let Some(throughput) = input.throughput {
+        report_generator = report_generator.with_throughput(throughput);
+    }
+    if let Some(toolchain) = input.toolchain {
+        report_generator = report_generator.with_toolchain(toolchain);
+    }
+    let mut results = input.results;
+    // 所有报告统一按本地化排序规则排列，同一艺人/专辑的文件保持相邻；
+    // 同一路径的多条记录再按音轨与 CUE 曲目排列，多次运行的输出可逐行对比。
+    results.sort_by(|a, b| report_generator.compare_records(a, b));
+
+    let mut quality_analyses = scorer.analyze_files(&results);
+
+    let albums = album::summarize_albums(&quality_analyses);
+    if config.album_gain {
+        album::apply_album_gain(&mut quality_analyses, &albums);
+    }
+    let mut compliance_failures = 0;
+    if let Some(standard) = config.compliance {
+        for analysis in &mut quality_analyses {
+            let result =
+                compliance::evaluate(&analysis.metrics, standard, config.compliance_lra_max);
+            compliance_failures += usize::from(!result.passed);
+            analysis.compliance = Some(result);
+        }
+    }
+    // 基线用本次的评分设置重新评分，分数变化只反映文件本身的变化。
+    let baseline_matches = match &config.baseline_file {
+        Some(path) => {
+            let (metrics, baseline_root) = compare::load_run(path)?;
+            let baseline = scorer.analyze_files_owned(metrics);
+            Some(compare::apply_baseline(
+                &mut quality_analyses,
+                library_root,
+                &baseline,
+                &baseline_root,
+            ))
+        }
+        None => None,
+    };
+    let album_csv_path = report_dir.join("audio_quality_albums.csv");
+    report_generator.generate_album_csv_report(&albums, &album_csv_path)?;
+
+    // 结果过滤只影响逐文件报告与摘要；门禁、合规、专辑与原始数据等仍基于全部结果。
+    let reported: std::borrow::Cow<[scoring::QualityAnalysis]> = if config.result_filter.is_active()
+    {
+        let filtered = config.result_filter.apply(&quality_analyses);
+        uiprintln!(
+            "结果过滤: 报告包含 {}/{} 个结果",
+            filtered.len(),
+            quality_analyses.len()
+        );
+        filtered.into()
+    } else {
+        quality_analyses.as_slice().into()
+    };
+    let csv_output_path = report_dir.join("audio_quality_report.csv");
+    report_generator.generate_csv_report(&reported, &csv_output_path)?;
+    if config.emit_json_report {
+        report_generator.generate_json_report(&reported, report_dir.join(JSON_REPORT_FILE_NAME))?;
+    }
+    let spectrograms = match (config.spectrograms, input.processing_config) {
+        (Some(scope), Some(processing_config)) => spectrogram::render_spectrograms(
+            &reported,
+            scope,
+            report_dir,
+            processing_config,
+            config.safe_mode,
+        )?,
+        _ => Vec::new(),
+    };
+    if !config.report_templates.is_empty() {
+        let generated_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        for template in &config.report_templates {
+            template_report::write_template_report(
+                template,
+                template_report::TemplateData {
+                    analyses: &reported,
+                    spectrograms: &spectrograms,
+                    root: library_root,
+                    generated_at: &generated_at,
+                    lang: config.lang,
+                },
+                report_dir,
+                config.safe_mode,
+            )?;
+        }
+    }
+    if let Some(db_path) = &config.sqlite_path {
+        let written = sqlite_report::write_sqlite_report(
+            db_path,
+            &quality_analyses,
+            &input.fingerprints,
+            &Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        )?;
+        uiprintln!(
+            "✅ 已写入 {written} 条记录到 SQLite 数据库: {}",
+            db_path.display()
+        );
+    }
+
+    // 附加音轨默认不计入曲库统计，仍完整写入各报告。
+    let (extras, library): (Vec<_>, Vec<_>) = reported
+        .iter()
+        .cloned()
+        .partition(|a| a.metrics.extra_of_album.is_some());
+    let stats_analyses: &[scoring::QualityAnalysis] = if config.extras_in_stats {
+        &reported
+    } else {
+        &library
+    };
+    report_generator.generate_summary_json(
+        stats_analyses,
+        report_dir.join(summary::SUMMARY_JSON_FILE_NAME),
+    )?;
+    {
+        let _summary = console::summary_section();
+        if config.extras_in_stats {
+            report_generator.display_summary(&reported);
+        } else {
+            report_generator.display_summary(&library);
+            if !extras.is_empty() {
+                uiprintln!(
+                    "\n附加音轨: {} 个（未计入以上统计，详见报告）",
+                    extras.len()
+                );
+            }
+        }
+    }
+    report_generator.display_album_summary(&albums);
+
+    if let Some(standard) = config.compliance {
+        display_compliance_summary(standard, &quality_analyses, compliance_failures);
+    }
+
+    if let Some(matched) = baseline_matches {
+        display_baseline_summary(&quality_analyses, matched);
+    }
+
+    let delivery_failures = check_deliveries(
+        config,
+        library_root,
+        &quality_analyses,
+        &report_dir.join(delivery::DELIVERY_CSV_FILE_NAME),
+    )?;
+    let gate_failures = if config.quality_gate.is_enabled() {
+        // 门禁结果决定退出码，安静模式下也一并说明。
+        let _summary = console::summary_section();
+        display_gate_summary(&config.quality_gate, &quality_analyses)
+    } else {
+        0
+    };
+
+    let action_items = actions::collect(&quality_analyses);
+    display_action_summary(&action_items, config.lang);
+    actions::write_actions_csv(
+        &report_dir.join(actions::ACTIONS_CSV_FILE_NAME),
+        &action_items,
+        config.lang,
+        config.safe_mode,
+    )?;
+
+    let duplicate_groups = duplicates::find(&quality_analyses);
+    let unhashed = duplicates::count_unhashed(&quality_analyses);
+    display_duplicate_summary(&duplicate_groups, unhashed);
+    duplicates::write_duplicates_csv(
+        &report_dir.join(duplicates::DUPLICATES_CSV_FILE_NAME),
+        &duplicate_groups,
+        config.safe_mode,
+    )?;
+
+    let savings_report = savings::analyze(&quality_analyses);
+    display_savings_summary(&savings_report, unhashed);
+    savings::write_savings_csv(
+        &report_dir.join(savings::SAVINGS_CSV_FILE_NAME),
+        &savings_report,
+        config.safe_mode,
+    )?;
+
+    let flagged_albums: Vec<&album::AlbumSummary> =
+        albums.iter().filter(|a| !a.findings.is_empty()).collect();
+    if !flagged_albums.is_empty() {
+        uiprintln!("\n专辑级问题: {} 个专辑", flagged_albums.len());
+        for summary in flagged_albums {
+            for finding in &summary.findings {
+                uiprintln!("  - {}: {}", summary.album_dir, finding.describe());
+            }
+        }
+    }
+
+    let json_output_path = report_dir.join(schema::ANALYSIS_DATA_FILE_NAME);
+    uiprintln!("\n正在保存原始数据到: {}", json_output_path.display());
+    schema::write_analysis_data(&json_output_path, &results, config.safe_mode)
+        .context("无法写入 analysis_data.json 文件")?;
+    // 记录本次实际生效的评分阈值（含命令行覆盖），便于日后复现评分。
+    let profile_json =
+        serde_json::to_string_pretty(&scorer.profile_snapshot()).context("序列化评分档案失败")?;
+    safe_io::atomic_write_string(
+        &report_dir.join(PROFILE_SNAPSHOT_FILE_NAME),
+        &profile_json,
+        config.safe_mode,
+    )?;
+    uiprintln!("原始数据保存成功。");
+
+    if config.emit_jsonl {
+        report_generator
+            .generate_jsonl_report(&reported, report_dir.join(JSONL_REPORT_FILE_NAME))?;
+        let partial = report_dir.join(JSONL_PARTIAL_FILE_NAME);
+        if partial.is_file() {
+            std::fs::remove_file(&partial)
+                .with_context(|| format!("删除中间 JSONL 文件失败: {}", partial.display()))?;
+        }
+    }
+
+    if config.emit_sarif {
+        let sarif_path = report_dir.join("audio_quality_report.sarif.json");
+        report_generator.generate_sarif_report(&reported, &sarif_path)?;
+    }
+
+    // 网络地址没有本地目录，播放列表、单文件结果与目录摘要只包含本地文件。
+    let local_analyses: std::borrow::Cow<[scoring::QualityAnalysis]> = if quality_analyses
+        .iter()
+        .any(|a| remote::is_url(&a.file_path))
+    {
+        quality_analyses
+            .iter()
+            .filter(|a| !remote::is_url(&a.file_path))
+            .cloned()
+            .collect::<Vec<_>>()
+            .into()
+    } else {
+        quality_analyses.as_slice().into()
+    };
+    if config.emit_playlists {
+        let playlist_dir = report_dir.join(playlist::PLAYLIST_DIR_NAME);
+        let written = playlist::export_playlists(&playlist_dir, &local_analyses, config.safe_mode)?;
+        uiprintln!(
+            "✅ 已导出 {written} 个播放列表到: {}",
+            playlist_dir.display()
+        );
+    }
+
+    if config.emit_tags {
+        let tags_path = report_dir.join(TAG_EXPORT_FILE_NAME);
+        report_generator.generate_tag_export(&quality_analyses, &tags_path)?;
+    }
+
+    if config.emit_sidecars {
+        let written = input.sidecar_location.write_all(
+            &local_analyses,
+            &input.fingerprints,
+            config.safe_mode,
+        );
+        uiprintln!(
+            "✅ 已写出 {written} 个单文件结果 (.{})",
+            sidecar::SIDECAR_EXTENSION
+        );
+    }
+
+    if config.emit_folder_summaries {
+        let written = folder_summary::write_folder_summaries(
+            input.sidecar_location,
+            &albums,
+            &local_analyses,
+            &Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            config.safe_mode,
+        );
+        uiprintln!(
+            "✅ 已写出 {written} 个目录摘要 ({})",
+            folder_summary::FOLDER_SUMMARY_FILE_NAME
+        );
+    }
+
+    if let Some(dataset_dir) = &config.dataset_dir {
+        let salt = dataset::load_or_create_salt(
+            &config
+                .app_paths
+                .config_dir
+                .join(dataset::DATASET_SALT_FILE_NAME),
+            config.safe_mode,
+        )?;
+        dataset::export_dataset(
+            dataset_dir,
+            &quality_analyses,
+            &salt,
+            scorer.profile_snapshot(),
+            &config.hf_bands,
+            config.safe_mode,
+        )?;
+    }
+    let summary = report_generator.summary_stats(stats_analyses);
+    Ok(ScoredRun {
+        results,
+        analyses: quality_analyses,
+        summary,
+        compliance_failures,
+        delivery_failures,
+        gate_failures,
+    })
+}
+
+/// `--json-stdout`：把摘要统计、全部评分结果与处理失败的文件作为一个 JSON 对象写到标准输出（单行）。
+fn print_json_results(
+    summary: &SummaryStats,
+    analyses: &[scoring::QualityAnalysis],
+    failed: &[FailedFile],
+) -> Result<()> {
+    let output = serde_json::json!({
+        "schemaVersion": schema::SCHEMA_VERSION,
+        "summary": summary,
+        "results": analyses,
+        "failed": failed,
+    });
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &output).context("序列化JSON结果失败")?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// 列出因内容不是音频而跳过的文件（最多前 20 个）。
+fn display_not_audio_summary(skipped: &[FailedFile]) {
+    const MAX_LISTED: usize = 20;
+    uiprintln!(
+        "跳过非音频内容: {} 个文件（扩展名为音频，其中却没有音频流）",
+        skipped.len()
+    );
+    for file in skipped.iter().take(MAX_LISTED) {
+        uiprintln!("  - {}", sanitize_for_terminal(&file.file_path));
+    }
+    if skipped.len() > MAX_LISTED {
+        uiprintln!("  ... 另有 {} 个", skipped.len() - MAX_LISTED);
+    }
+}
+
+/// 打印与基线相比的改善/变差数量，并列出分数下降的文件。
+fn display_baseline_summary(analyses: &[scoring::QualityAnalysis], matched: usize) {
+    const MAX_LISTED: usize = 20;
+    let deltas: Vec<i32> = analyses
+        .iter()
+        .filter_map(|a| a.baseline.as_ref().map(|b| b.score_delta))
+        .collect();
+    let improved = deltas.iter().filter(|d| **d > 0).count();
+    let regressions = compare::regressions(analyses);
+    uiprintln!(
+        "\n📉 基线对比: 匹配 {matched} 个，提升 {improved}，下降 {}，新增 {}",
+        regressions.len(),
+        analyses.len() - matched
+    );
+    if regressions.is_empty() {
+        return;
+    }
+    uiprintln!("分数下降 (regressions):");
+    for analysis in regressions.iter().take(MAX_LISTED) {
+        let Some(delta) = &analysis.baseline else {
+            continue;
+        };
+        let status = if delta.status_changed {
+            format!("{} → {}", delta.previous_status, analysis.status)
+        } else {
+            analysis.status.to_string()
+        };
+        uiprintln!(
+            "  {:+4}  {} → {}  [{status}]  {}",
+            delta.score_delta,
+            delta.previous_score,
+            analysis.quality_score,
+            sanitize_for_terminal(&analysis.file_path)
+        );
+    }
+    if regressions.len() > MAX_LISTED {
+        uiprintln!(
+            "  ... 另有 {} 个，详见 CSV「较基线分数变化」列",
+            regressions.len() - MAX_LISTED
+        );
+    }
+}
+
+/// 按各交付目录的 `delivery.toml` 检查其中的文件，打印每个交付的 PASS/FAIL 并写出汇总，
+/// 返回未通过的交付数。规格指定了评分档案时，该目录的文件按该档案重新评分后再检查。
+fn check_deliveries(
+    config: &AppConfig,
+    root: &Path,
+    analyses: &[scoring::QualityAnalysis],
+    csv_path: &Path,
+) -> Result<usize> {
+    let deliveries = delivery::discover(root)?;
+    if deliveries.is_empty() {
+        if csv_path.is_file() {
+            std::fs::remove_file(csv_path)
+                .with_context(|| format!("删除过期的交付检查汇总失败: {}", csv_path.display()))?;
+        }
+        return Ok(0);
+    }
+
+    let assignment = delivery::assign(&deliveries, analyses);
+    let mut results = Vec::with_capacity(deliveries.len());
+    for (index, spec) in deliveries.iter().enumerate() {
+        let members: Vec<scoring::QualityAnalysis> = analyses
+            .iter()
+            .zip(&assignment)
+            .filter(|(_, assigned)| **assigned == Some(index))
+            .map(|(analysis, _)| analysis.clone())
+            .collect();
+        let (profile_name, members) = match spec.profile {
+            Some(profile) => {
+                let scorer = build_scorer(&AppConfig {
+                    scoring_profile: profile,
+                    custom_profile: None,
+                    ..config.clone()
+                })?;
+                let metrics = members.into_iter().map(|a| a.metrics).collect();
+                (profile.as_str(), scorer.analyze_files_owned(metrics))
+            }
+            None => (
+                config
+                    .custom_profile
+                    .as_ref()
+                    .map_or(config.scoring_profile.as_str(), |custom| {
+                        custom.name.as_str()
+                    }),
+                members,
+            ),
+        };
+        results.push(delivery::evaluate(spec, profile_name, &members));
+    }
+
+    uiprintln!("\n交付检查: {} 个交付目录", results.len());
+    for result in &results {
+        uiprintln!(
+            "  [{}] {}（{} 档案，{} 个文件）",
+            result.verdict,
+            sanitize_for_terminal(&result.name),
+            result.profile,
+            result.files
+        );
+        if !result.failures.is_empty() {
+            uiprintln!("      {}", sanitize_for_terminal(&result.failures));
+        }
+    }
+    delivery::write_deliveries_csv(csv_path, &results, config.safe_mode)?;
+    Ok(results.iter().filter(|r| !r.passed()).count())
+}
+
+/// 待处理问题摘要：各类别的文件数、处理建议与分数最低的几个文件；没有待处理文件时不输出。
+fn display_action_summary(items: &[actions::ActionItem], lang: Lang) {
+    const MAX_LISTED: usize = 5;
+    if items.is_empty() {
+        return;
+    }
+    match lang {
+        Lang::Zh => uiprintln!(
+            "\n🛠️ 优先处理（完整清单见 {}）:",
+            actions::ACTIONS_CSV_FILE_NAME
+        ),
+        Lang::En => uiprintln!(
+            "\n🛠️ Fix first (full list in {}):",
+            actions::ACTIONS_CSV_FILE_NAME
+        ),
+    }
+    let files = lang.pick("个文件", "files");
+    for (category, count) in actions::totals(items) {
+        uiprintln!(
+            " - {}: {count} {files} → {}",
+            category.label(lang),
+            category.advice(lang)
+        );
+        for item in items
+            .iter()
+            .filter(|item| item.category == category)
+            .take(MAX_LISTED)
+        {
+            uiprintln!(
+                "     [{}] {}",
+                item.quality_score,
+                sanitize_for_terminal(&item.file_path)
+            );
+        }
+        if count > MAX_LISTED {
+            let more = count - MAX_LISTED;
+            match lang {
+                Lang::Zh => uiprintln!("     ... 另有 {more} 个"),
+                Lang::En => uiprintln!("     ... and {more} more"),
+            }
+        }
+    }
+}
+
+/// 打印重复文件的组数与多余副本占用的空间，列出浪费最多的几组；没有重复时不输出。
+/// 有文件缺少内容哈希时另行提示，检测结果可能不完整。
+fn display_duplicate_summary(groups: &[duplicates::DuplicateGroup], unhashed: usize) {
+    const MAX_LISTED: usize = 10;
+    if unhashed > 0 {
+        uiprintln!(
+            "\n⚠️ {unhashed} 个文件没有内容哈希（指纹模式非 strict 或哈希算法非 sha256），重复文件只能按 PCM 摘要（--pcm-md5）识别，结果可能不完整"
+        );
+    }
+    if groups.is_empty() {
+        return;
+    }
+    let redundant: usize = groups.iter().map(|g| g.redundant().len()).sum();
+    let wasted: u64 = groups.iter().map(|g| g.wasted_bytes()).sum();
+    uiprintln!(
+        "\n🧬 重复文件: {} 组，{redundant} 个多余副本，占用 {}（清单见 {}）",
+        groups.len(),
+        savings::format_bytes(wasted),
+        duplicates::DUPLICATES_CSV_FILE_NAME
+    );
+    for group in groups.iter().take(MAX_LISTED) {
+        let keep = group.keep();
+        uiprintln!(
+            " - {}，保留 [{}] {}",
+            group.kind.label(),
+            keep.quality_score,
+            sanitize_for_terminal(&keep.file_path)
+        );
+        for copy in group.redundant() {
+            uiprintln!(
+                "     可删除 [{}] {}",
+                copy.quality_score,
+                sanitize_for_terminal(&copy.file_path)
+            );
+        }
+    }
+    if groups.len() > MAX_LISTED {
+        uiprintln!("   ... 另有 {} 组", groups.len() - MAX_LISTED);
+    }
+}
+
+/// 打印各类可节省空间的文件数与合计大小；没有可节省的文件时不输出。
+fn display_savings_summary(report: &savings::SavingsReport, unhashed: usize) {
+    if report.candidates.is_empty() {
+        return;
+    }
+    uiprintln!(
+        "\n💾 可节省空间: 约 {}（清单见 {}）",
+        savings::format_bytes(report.total_bytes()),
+        savings::SAVINGS_CSV_FILE_NAME
+    );
+    if unhashed > 0 {
+        uiprintln!(" （{unhashed} 个文件没有内容哈希，重复副本一项可能少计）");
+    }
+    for (category, (count, bytes)) in report.totals() {
+        uiprintln!(
+            " - {}: {count} 个文件，{}",
+            category.label(),
+            savings::format_bytes(bytes)
+        );
+    }
+}
+
+/// 合规检查结果：通过/未通过数量与未通过的文件（最多列出前 20 个，完整结果见报告）。
+fn display_compliance_summary(
+    standard: ComplianceStandard,
+    analyses: &[scoring::QualityAnalysis],
+    failures: usize,
+) {
+    const MAX_LISTED: usize = 20;
+    uiprintln!(
+        "
+📋 合规检查 ({}): 通过 {}，未通过 {failures}",
+        standard.label(),
+        analyses.len() - failures
+    );
+    let failed = analyses.iter().filter_map(|analysis| {
+        let result = analysis.compliance.as_ref().filter(|r| !r.passed)?;
+        Some((analysis, result))
+    });
+    for (analysis, result) in failed.take(MAX_LISTED) {
+        uiprintln!(
+            "  - {}: {}",
+            sanitize_for_terminal(&analysis.file_path),
+            result.describe()
+        );
+    }
+    if failures > MAX_LISTED {
+        uiprintln!(
+            "  ... 其余 {} 个见 CSV「合规判定」列",
+            failures - MAX_LISTED
+        );
+    }
+}
+
+/// 打印质量门禁结果并返回违反门禁的文件数。
+fn display_gate_summary(gate: &QualityGate, analyses: &[scoring::QualityAnalysis]) -> usize {
+    const MAX_LISTED: usize = 20;
+    let violations = gate.check(analyses);
+    if violations.is_empty() {
+        uiprintln!("\n🚦 质量门禁: 全部 {} 个文件通过", analyses.len());
+        return 0;
+    }
+    uiprintln!(
+        "\n🚦 质量门禁未通过: {} 个文件（退出码 2）",
+        violations.len()
+    );
+    for violation in violations.iter().take(MAX_LISTED) {
+        uiprintln!(
+            "  - {}: {}",
+            sanitize_for_terminal(&violation.analysis.file_path),
+            violation.reasons.join("；")
+        );
+    }
+    if violations.len() > MAX_LISTED {
+        uiprintln!("  ... 其余 {} 个见报告", violations.len() - MAX_LISTED);
+    }
+    violations.len()
+}
+
+/// 把本次结果合并进该目录已有的 `analysis_data.json`：本次分析（或重试）的文件的旧记录整体替换，
+/// 文件已不存在的旧记录被丢弃，其余记录保留。
+fn merge_previous_results(
+    base_folder_path: &Path,
+    retried_results: Vec<FileMetrics>,
+    retried_paths: &[PathBuf],
+) -> Result<Vec<FileMetrics>> {
+    let json_path = base_folder_path.join(schema::ANALYSIS_DATA_FILE_NAME);
+    let previous: Vec<FileMetrics> = match std::fs::read_to_string(&json_path) {
+        Ok(content) => schema::parse_analysis_data(&content)
+            .with_context(|| format!("解析已有结果失败: {}", json_path.display()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            uiprintln!(
+                "未找到已有结果 {}，仅输出本次分析的文件。",
+                json_path.display()
+            );
+            Vec::new()
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("读取已有结果失败: {}", json_path.display()))
+        }
+    };
+
+    // 内容哈希相同不代表同一条记录（同一文件的多份拷贝、CUE 镜像的各音轨），
+    // 因此按「路径 + 音轨 + CUE 音轨」识别；被移动或改名的文件旧路径已不存在，随之丢弃。
+    let retried: std::collections::HashSet<String> = retried_paths
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    let keys: std::collections::HashSet<(&str, u32, Option<u32>)> =
+        retried_results.iter().map(record_key).collect();
+    let previous_count = previous.len();
+    let mut merged: Vec<FileMetrics> = previous
+        .into_iter()
+        .filter(|m| {
+            !retried.contains(&m.file_path)
+                && !keys.contains(&record_key(m))
+                && Path::new(&m.file_path).exists()
+        })
+        .collect();
+    uiprintln!(
+        "合并结果: 保留已有 {} 条，替换 {} 条，写入本次 {} 条",
+        merged.len(),
+        previous_count - merged.len(),
+        retried_results.len()
+    );
+    merged.extend(retried_results);
+    Ok(merged)
+}
+
+/// 合并结果时识别同一条记录的键：文件路径、音轨序号与 CUE 音轨号。
+fn record_key(metrics: &FileMetrics) -> (&str, u32, Option<u32>) {
+    (
+        metrics.file_path.as_str(),
+        metrics.audio_stream_index,
+        metrics.cue_track.as_ref().map(|track| track.number),
+    )
+}
+
+/// 处理单个文件；`StreamSelection::All` 时每条音轨各产生一条记录，
+/// 带 CUE 的整轨镜像再按 CUE 音轨逐轨产生记录。
+fn process_one_file(
+    path: &Path,
+    processing_config: &ffmpeg::ProcessingConfig,
+    streams: ffmpeg::StreamSelection,
+    cue_tracks: &[CueTrack],
+    cache_snapshot: Option<&AnalysisCache>,
+    sidecars: Option<&sidecar::SidecarLocation>,
+    fingerprint_options: FingerprintOptions,
+) -> Result<Vec<ProcessedRecord>> {
+    let _in_progress = diagnostics::begin_file(path);
+    let fingerprint = cache::fingerprint_file(path, fingerprint_options)?;
+
+    let stream_indices: Vec<u32> = match streams {
+        ffmpeg::StreamSelection::Index(index) => vec![index],
+        ffmpeg::StreamSelection::All => {
+            let count = ffmpeg::count_audio_streams(path, processing_config)?.unwrap_or(1);
+            (0..count.max(1)).collect()
+        }
+    };
+
+    let cue_targets: Vec<Option<&CueTrack>> = if cue_tracks.is_empty() {
+        vec![None]
+    } else {
+        cue_tracks.iter().map(Some).collect()
+    };
+
+    stream_indices
+        .into_iter()
+        .flat_map(|audio_stream| cue_targets.iter().map(move |track| (audio_stream, *track)))
+        .map(|(audio_stream, cue_track)| {
+            let stream_config = ffmpeg::ProcessingConfig {
+                audio_stream,
+                cue_track: cue_track.cloned(),
+                ..processing_config.clone()
+            };
+            process_one_stream(path, &stream_config, &fingerprint, cache_snapshot, sidecars)
+        })
+        .collect()
+}
+
+fn process_one_stream(
+    path: &Path,
+    processing_config: &ffmpeg::ProcessingConfig,
+    fingerprint: &FileFingerprint,
+    cache_snapshot: Option<&AnalysisCache>,
+    sidecars: Option<&sidecar::SidecarLocation>,
+) -> Result<ProcessedRecord> {
+    let cue_number = processing_config
+        .cue_track
+        .as_ref()
+        .map(|track| track.number);
+    // 先查中心缓存，再查随文件夹迁移的单文件结果。
+    let reused = cache_snapshot
+        .and_then(|cache| {
+            cache.lookup(
+                path,
+                processing_config.audio_stream,
+                cue_number,
+                fingerprint,
+            )
+        })
+        .filter(|metrics| reusable_metrics(metrics, processing_config))
+        .or_else(|| {
+            sidecars
+                .and_then(|sidecars| {
+                    sidecars.load_matching(
+                        path,
+                        processing_config.audio_stream,
+                        cue_number,
+                        fingerprint,
+                    )
+                })
+                .filter(|metrics| reusable_metrics(metrics, processing_config))
+        });
+    if let Some(mut metrics) = reused {
+        metrics.processing_time_ms = 0;
+        return Ok(ProcessedRecord {
+            metrics,
+            fingerprint: fingerprint.clone(),
+        });
+    }
+
+    let mut metrics = ffmpeg::process_file(path, processing_config)?;
+    // `contentSha256` 只记录整个文件的 SHA-256 摘要，其他算法与抽样哈希仅保存在缓存指纹中。
+    if fingerprint.mode == FingerprintMode::Strict
+        && fingerprint.hash_algorithm == HashAlgorithm::Sha256
+    {
+        metrics.content_sha256 = Some(fingerprint.content_hash.clone());
+    }
+
+    Ok(ProcessedRecord {
+        metrics,
+        fingerprint: fingerprint.clone(),
+    })
+}
+
+/// 复用的结果由其他 FFmpeg 版本生成时提示：不同版本的测量值可能存在差异。
+fn warn_mixed_ffmpeg_versions(results: &[FileMetrics], current: &str) {
+    let mut other_versions: Vec<&str> = Vec::new();
+    let mut count = 0usize;
+    for metrics in results.iter().filter(|m| m.cache_hit) {
+        let recorded = metrics.ffmpeg_version.as_deref();
+        if recorded != Some(current) {
+            count += 1;
+            let label = recorded.unwrap_or("未知版本");
+            if !other_versions.contains(&label) {
+                other_versions.push(label);
+            }
+        }
+    }
+    if count > 0 {
+        uiprintln!(
+            "⚠️ {count} 个复用的结果由其他 FFmpeg 版本生成（{}），与本次 ({current}) 的测量可能不一致；\
+             可使用 --reanalyze-on-ffmpeg-change 重新分析主版本号不同的结果。",
+            other_versions.join(", ")
+        );
+    }
+}
+
+/// 缓存或单文件结果中的指标能否代替本次分析：分析选项必须覆盖本次请求的内容。
+fn reusable_metrics(metrics: &FileMetrics, processing_config: &ffmpeg::ProcessingConfig) -> bool {
+    metrics.covers_hf_bands(&processing_config.hf_bands)
+        // 旧结果中的高采样率文件缺少升采样检测频段时重新分析。
+        && (!ffmpeg::needs_upsample_check(metrics.sample_rate_hz)
+            || metrics.hf_band_rms(ffmpeg::UPSAMPLE_CHECK_HZ).is_some())
+        // 全文件结果可用于快速模式，采样结果不能代替全文件分析。
+        && (processing_config.fast_sampling || !metrics.sampled)
+        && metrics.audio_stream_index == processing_config.audio_stream
+        // CUE 修改后音轨边界可能变化，缓存的范围必须一致。
+        && metrics.cue_track == processing_config.cue_track
+        // 快速采样不计算 PCM MD5，缺少它的结果照常复用。
+        && (!processing_config.pcm_md5
+            || processing_config.fast_sampling
+            || metrics.pcm_md5.is_some())
+        // 过短的文件测不出贴顶占比，缺少它时不必重新分析。
+        && (!processing_config.detect_limiting
+            || metrics.ceiling_time_percent.is_some()
+            || metrics
+                .duration_seconds
+                .is_some_and(|d| d < segments::CEILING_MIN_SECONDS))
+        && match processing_config.segment_seconds {
+            Some(seconds) => metrics
+                .segment_analysis
+                .as_ref()
+                .is_some_and(|s| s.window_seconds == seconds),
+            None => true,
+        }
+        && match (
+            processing_config.reanalyze_on_ffmpeg_change,
+            &processing_config.ffmpeg_version,
+        ) {
+            (true, Some(current)) => {
+                ffmpeg::ffmpeg_versions_compatible(metrics.ffmpeg_version.as_deref(), current)
+            }
+            _ => true,
+        }
+}
+
+fn build_app_config(cli: &Cli) -> Result<AppConfig> {
+    let app_paths = AppPaths::resolve()?;
+    let user_config = load_user_config(cli, &app_paths)?;
+    build_app_config_from(cli, app_paths, user_config)
+}
+
+/// 读取配置目录的 config.toml，再叠加 `--config` 指定的文件。
+fn load_user_config(cli: &Cli, app_paths: &AppPaths) -> Result<UserConfig> {
+    let mut user_config = UserConfig::load(&app_paths.config_file())?.unwrap_or_default();
+    if let Some(path) = &cli.config {
+        user_config = user_config.overlay(UserConfig::load_required(path)?);
+    }
+    Ok(user_config)
+}
+
+/// 合并命令行参数与已读取的配置文件。命令行参数优先，其次是 --config 指定的文件、
+/// 配置目录的 config.toml，最后是内置默认值。
+fn build_app_config_from(
+    cli: &Cli,
+    mut app_paths: AppPaths,
+    user_config: UserConfig,
+) -> Result<AppConfig> {
+    let default_parallel = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    let prune_cache_path = match &cli.command {
+        Some(Command::Cache {
+            action: CacheCommand::Prune { cache_path, .. },
+        }) => cache_path.as_ref(),
+        _ => None,
+    };
+    // `cache prune --cache-path` 只作用于这次清理，优先于其余来源。
+    let cache_path = prune_cache_path
+        .or(cli.cache_path.as_ref())
+        .or(user_config.cache_path.as_ref())
+        .cloned();
+    if let Some(cache_path) = &cache_path {
+        app_paths = app_paths.with_cache_dir(cache_path);
+    }
+    let file_output = &user_config.output;
+    let file_thresholds = &user_config.thresholds;
+    let file_concurrency = &user_config.concurrency;
+    let jobs = cli.jobs.or(file_concurrency.jobs).filter(|&n| n > 0);
+    let profile = cli
+        .profile
+        .as_deref()
+        .or(user_config.profile.as_deref())
+        .unwrap_or("pop");
+    let scoring_profile =
+        ScoringProfile::from_str(profile).map_err(|e| anyhow!("profile 参数错误: {e}"))?;
+    // 规则脚本在分析开始前先编译一遍，语法错误不必等到整批提取完成后才发现。
+    for file in &cli.rules {
+        ScriptRule::load(file)?;
+    }
+
+    Ok(AppConfig {
+        command_timeout: Duration::from_secs(
+            cli.ffmpeg_timeout_seconds
+                .or(file_concurrency.ffmpeg_timeout_seconds)
+                .unwrap_or(90)
+                .max(1),
+        ),
+        max_ffmpeg_processes: cli
+            .max_ffmpeg_processes
+            .or(file_concurrency.max_ffmpeg_processes)
+            .or(jobs)
+            .unwrap_or(default_parallel)
+            .max(1),
+        safe_mode: !cli.unsafe_mode,
+        cache_enabled: !cli.no_cache,
+        cache_max_age_days: cli.cache_max_age_days.or(user_config.cache_max_age_days),
+        hash_algorithm: cli
+            .hash_algorithm
+            .as_deref()
+            .or(user_config.hash_algorithm.as_deref())
+            .map_or(Ok(HashAlgorithm::default()), str::parse)
+            .map_err(|e| anyhow!("hash-algorithm 参数错误: {e}"))?,
+        fingerprint_mode: cli
+            .fingerprint_mode
+            .parse()
+            .map_err(|e| anyhow!("fingerprint 参数错误: {e}"))?,
+        emit_json_report: cli.json_report || file_output.json.unwrap_or(false),
+        emit_jsonl: cli.jsonl || file_output.jsonl.unwrap_or(false),
+        emit_sarif: cli.sarif || file_output.sarif.unwrap_or(false),
+        json_stdout: cli.json_stdout,
+        quiet: cli.quiet,
+        emit_playlists: cli.export_playlists || file_output.playlists.unwrap_or(false),
+        emit_tags: cli.export_tags || file_output.tags.unwrap_or(false),
+        emit_sidecars: cli.sidecar,
+        emit_folder_summaries: cli.folder_summary,
+        dry_run: cli.dry_run,
+        reuse_sidecars: cli.reuse_sidecars,
+        reanalyze_on_ffmpeg_change: cli.reanalyze_on_ffmpeg_change,
+        pcm_md5: cli.pcm_md5,
+        detect_limiting: cli.detect_limiting,
+        dataset_dir: cli.export_dataset.clone(),
+        sqlite_path: cli.sqlite.clone(),
+        report_templates: match (&cli.templates, &file_output.templates) {
+            (cli_templates, Some(file_templates)) if cli_templates.is_empty() => file_templates,
+            (cli_templates, _) => cli_templates,
+        }
+        .iter()
+        .map(|template| {
+            template
+                .parse::<ReportTemplate>()
+                .map_err(|e| anyhow!("[E_TEMPLATE] template 参数错误: {e}"))
+        })
+        .collect::<Result<Vec<_>>>()
+        .and_then(|templates| {
+            template_report::validate(&templates)?;
+            Ok(templates)
+        })?,
+        spectrograms: cli
+            .spectrograms
+            .as_deref()
+            .map(|scope| {
+                scope
+                    .parse::<SpectrogramScope>()
+                    .map_err(|e| anyhow!("spectrograms 参数错误: {e}"))
+            })
+            .transpose()?,
+        scoring_profile,
+        custom_profile: cli
+            .profile_file
+            .as_deref()
+            .map(|path| scoring::load_profile_file(path, scoring_profile))
+            .transpose()?,
+        profile_file: cli.profile_file.clone(),
+        lossy_model_file: cli.lossy_model.clone(),
+        baseline_file: cli.baseline.clone(),
+        lossy_classifier: cli
+            .lossy_model
+            .as_deref()
+            .map(LossyClassifier::load)
+            .transpose()?,
+        score_weights: cli
+            .score_weights
+            .as_deref()
+            .or(file_thresholds.score_weights.as_deref())
+            .map(|weights| {
+                weights
+                    .parse::<ScoreWeights>()
+                    .map_err(|e| anyhow!("score-weights 参数错误: {e}"))
+            })
+            .transpose()?,
+        threshold_overrides: ThresholdOverrides {
+            target_lufs: cli.target_lufs.or(file_thresholds.target_lufs),
+            true_peak_max: cli.true_peak_max.or(file_thresholds.true_peak_max),
+            min_bitrate_kbps: cli.min_bitrate.or(file_thresholds.min_bitrate),
+        },
+        scorer_version: cli.scorer_version.unwrap_or(scoring::SCORER_VERSION),
+        rule_files: cli.rules.clone(),
+        grade_scale: cli
+            .grade_scale
+            .as_deref()
+            .or(user_config.grade_scale.as_deref())
+            .map(|scale| {
+                scale
+                    .parse::<GradeScale>()
+                    .map_err(|e| anyhow!("grade-scale 参数错误: {e}"))
+            })
+            .transpose()?
+            .unwrap_or_default(),
+        usage_stats_enabled: cli.usage_stats,
+        jobs,
+        decode_threads: cli
+            .decode_threads
+            .or(file_concurrency.decode_threads)
+            .filter(|&n| n > 0),
+        scoring_threads: cli
+            .scoring_threads
+            .or(file_concurrency.scoring_threads)
+            .filter(|&n| n > 0),
+        schedule: cli
+            .schedule
+            .parse()
+            .map_err(|e| anyhow!("schedule 参数错误: {e}"))?,
+        hwaccel: cli
+            .hwaccel
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToOwned::to_owned),
+        max_failure_percent: cli.max_failure_percent.clamp(0.0, 100.0),
+        hf_bands: parse_hf_bands(
+            &cli.hf_bands,
+            cli.lossy_model.is_some() || matches!(cli.command, Some(Command::Train { .. })),
+        )?,
+        fast_sampling: cli.fast,
+        segment_seconds: cli.segment_seconds,
+        audio_streams: cli
+            .audio_stream
+            .parse()
+            .map_err(|e| anyhow!("audio-stream 参数错误: {e}"))?,
+        scan_options: ScanOptions {
+            include_extras: cli.include_extras,
+            ignore_cue: cli.no_cue,
+            extra_extensions: cli
+                .extra_extensions
+                .iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect(),
+            max_depth: if cli.no_recursive {
+                Some(1)
+            } else {
+                cli.max_depth
+            },
+            follow_symlinks: cli.follow_symlinks,
+        },
+        extras_in_stats: cli.extras_in_stats,
+        collation: FileNameCollator::parse(&cli.collation)?.name().to_owned(),
+        report_sort: ReportOrder {
+            key: ReportSort::from_str(&cli.sort_by)
+                .map_err(|e| anyhow!("sort-by 参数错误: {e}"))?,
+            direction: cli
+                .sort_order
+                .as_deref()
+                .map(SortDirection::from_str)
+                .transpose()
+                .map_err(|e| anyhow!("sort-order 参数错误: {e}"))?,
+        },
+        ranking_size: cli.top,
+        color: !cli.no_color && std::env::var_os("NO_COLOR").is_none(),
+        csv_columns: match cli
+            .columns
+            .as_deref()
+            .or(user_config.csv_columns.as_deref())
+        {
+            Some(spec) => report::resolve_csv_columns(spec)?,
+            None => None,
+        },
+        lang: Lang::from_str(&cli.lang).map_err(|e| anyhow!("lang 参数错误: {e}"))?,
+        album_gain: cli.album_gain,
+        compliance: cli
+            .compliance
+            .as_deref()
+            .map(|s| s.parse().map_err(|e| anyhow!("compliance 参数错误: {e}")))
+            .transpose()?,
+        compliance_lra_max: cli.compliance_lra_max,
+        result_filter: ResultFilter {
+            min_score: cli.min_score,
+            max_score: cli.max_score,
+            statuses: cli
+                .status_filter
+                .iter()
+                .map(|status| status.parse().map_err(|e| anyhow!("status 参数错误: {e}")))
+                .collect::<Result<_>>()?,
+        },
+        quality_gate: QualityGate {
+            fail_under: cli.fail_under,
+            fail_on_status: cli
+                .fail_on_status
+                .iter()
+                .map(|status| {
+                    status
+                        .parse()
+                        .map_err(|e| anyhow!("fail-on-status 参数错误: {e}"))
+                })
+                .collect::<Result<_>>()?,
+        },
+        cache_path: cache_path.map(|_| app_paths.cache_dir.clone()),
+        app_paths,
+        output_dir: cli.output_dir.clone().or(user_config.output_dir),
+        ffmpeg_path: match &cli.ffmpeg_path {
+            Some(path) => Some(validate_executable(path, "ffmpeg")?),
+            None => user_config.ffmpeg_path,
+        },
+        ffprobe_path: match &cli.ffprobe_path {
+            Some(path) => Some(validate_executable(path, "ffprobe")?),
+            None => user_config.ffprobe_path,
+        },
+        log_level: cli.log_level,
+        log_file: cli.log_file.clone(),
+    })
+}
+
+/// 校验用户频点并补上评分必需的 14k/16k/18k：缺少它们时文件会被判为缺少数据或高频断崖。
+/// 使用有损来源分类器（`--lossy-model` 或 `train`）时另补上其特征所需的 20k，否则分类器会静默退回固定阈值。
+fn parse_hf_bands(bands: &[u32], lossy_classifier: bool) -> Result<Vec<u32>> {
+    if bands.is_empty() {
+        return Err(anyhow!("hf-bands 参数错误: 至少需要一个频点"));
+    }
+    if let Some(&freq) = bands.iter().find(|&&f| f == 0 || f > 192_000) {
+        return Err(anyhow!(
+            "hf-bands 参数错误: 频点 {freq} Hz 超出范围 (1-192000)"
+        ));
+    }
+    let mut bands = bands.to_vec();
+    bands.extend(ffmpeg::SCORING_HF_BANDS);
+    if lossy_classifier {
+        bands.extend(classifier::FEATURE_HF_BANDS);
+    }
+    bands.sort_unstable();
+    bands.dedup();
+    Ok(bands)
+}
+
+fn install_cancel_handler() {
+    let result = ctrlc::set_handler(|| {
+        // 没有进行中的分析（如停在交互菜单或提示处）时没有需要保存的结果，直接退出。
+        if CANCELLABLE_TASKS.load(Ordering::SeqCst) == 0 {
+            eprintln!("\n已取消。");
+            std::process::exit(i32::from(ExitStatus::Cancelled.code()));
+        }
+        if CANCELLED.swap(true, Ordering::SeqCst) {
+            // 第二次 Ctrl-C：立即退出。
+            std::process::exit(i32::from(ExitStatus::Cancelled.code()));
+        }
+        eprintln!("\n收到取消请求，等待进行中的文件完成（再次按 Ctrl-C 立即退出）...");
+    });
+    if let Err(e) = result {
+        eprintln!("无法注册 Ctrl-C 处理器: {e}");
+    }
+}
+
+fn build_scorer(config: &AppConfig) -> Result<QualityScorer> {
+    let scorer = match &config.custom_profile {
+        Some(custom) => QualityScorer::with_custom_profile(custom),
+        None => QualityScorer::with_profile(config.scoring_profile),
+    };
+    let scorer = match config.score_weights {
+        Some(weights) => scorer.with_weights(weights),
+        None => scorer,
+    };
+    let scorer = scorer
+        .with_grade_scale(config.grade_scale.clone())
+        .with_lang(config.lang)
+        .with_threshold_overrides(config.threshold_overrides)?
+        .with_scorer_version(config.scorer_version)?;
+    let mut scorer = match &config.lossy_classifier {
+        Some(classifier) => scorer.with_lossy_classifier(classifier.clone()),
+        None => scorer,
+    };
+    for file in &config.rule_files {
+        scorer = scorer.with_rule(Box::new(ScriptRule::load(file)?));
+    }
+    let pool = config.scoring_threads.or(config.jobs).and_then(|threads| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| tracing::warn!("无法创建评分线程池，改用全局线程池: {e}"))
+            .ok()
+    });
+    Ok(match pool {
+        Some(pool) => scorer.with_thread_pool(Arc::new(pool)),
+        None => scorer,
+    })
+}
+
+/// `compare` 的一侧：已有的运行结果、需要现场分析的文件夹或单个音频文件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareInput {
+    Run,
+    Folder,
+    File,
+}
+
+impl CompareInput {
+    /// 含 `analysis_data.json` 的目录直接沿用已有结果，不重新分析。
+    fn of(path: &Path) -> Self {
+        if path.is_dir() {
+            if path.join(schema::ANALYSIS_DATA_FILE_NAME).is_file() {
+                CompareInput::Run
+            } else {
+                CompareInput::Folder
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            CompareInput::Run
+        } else {
+            CompareInput::File
+        }
+    }
+}
+
+/// 用当前评分档案重新评分两次运行的原始指标，再按相对路径对比。
+fn compare_runs(
+    old: &Path,
+    new: &Path,
+    html: Option<&Path>,
+    csv: Option<&Path>,
+    config: &AppConfig,
+) -> Result<()> {
+    let (old_input, new_input) = (CompareInput::of(old), CompareInput::of(new));
+    let single_files = old_input == CompareInput::File || new_input == CompareInput::File;
+    if single_files && old_input != new_input {
+        return Err(anyhow!(
+            "[E_COMPARE_INPUT] 单个音频文件只能与另一个音频文件对比"
+        ));
+    }
+    let needs_analysis = old_input != CompareInput::Run || new_input != CompareInput::Run;
+    let processing_config = if needs_analysis {
+        let ffmpeg_path = find_ffmpeg_path(config.ffmpeg_path.as_deref())?;
+        let ffprobe_path = find_ffprobe_path(&ffmpeg_path, config);
+        Some(basic_processing_config(ffmpeg_path, ffprobe_path, config))
+    } else {
+        None
+    };
+    let load = |path: &Path, input| match (input, &processing_config) {
+        (CompareInput::Run, _) | (_, None) => compare::load_run(path),
+        (_, Some(processing_config)) => analyze_for_compare(path, processing_config, config),
+    };
+    let scorer = build_scorer(config)?;
+
+    if single_files {
+        let a = scorer.analyze_files_owned(load(old, old_input)?.0);
+        let b = scorer.analyze_files_owned(load(new, new_input)?.0);
+        // 多音轨或带 CUE 的文件只对比第一条结果。
+        if let (Some(a), Some(b)) = (a.first(), b.first()) {
+            versions::VersionComparison { a, b }.print();
+        }
+        return Ok(());
+    }
+
+    let (old_metrics, old_root) = load(old, old_input)?;
+    let (new_metrics, new_root) = load(new, new_input)?;
+    let comparison = compare::compare_runs(
+        &scorer.analyze_files_owned(old_metrics),
+        &old_root,
+        &scorer.analyze_files_owned(new_metrics),
+        &new_root,
+    );
+    comparison.print_summary();
+
+    // 现场分析的文件夹没有运行目录，对比结果按分析时的规则写入其报告目录。
+    let output_dir = match new_input {
+        CompareInput::Run => new_root,
+        _ => AppPaths::ensure_dir(&config.report_dir(&new_root))?.to_path_buf(),
+    };
+    let html_path = html
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| output_dir.join("audio_quality_compare.html"));
+    comparison.write_html(&html_path, config.safe_mode)?;
+    let csv_path = csv
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| output_dir.join(compare::DIFF_CSV_FILE_NAME));
+    comparison.write_diff_csv(&csv_path, config.safe_mode)
+}
+
+/// 为 `compare` 现场分析一个文件或文件夹：沿用增量缓存，但不更新缓存、不写出报告。
+/// 返回指标与根目录（文件为其所在目录）。
+fn analyze_for_compare(
+    path: &Path,
+    processing_config: &ffmpeg::ProcessingConfig,
+    config: &AppConfig,
+) -> Result<(Vec<FileMetrics>, PathBuf)> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("[E_COMPARE_INPUT] 路径不存在: {}", path.display()))?;
+    let (root, files) = if path.is_dir() {
+        let files = scan::scan_audio_files(&path, &config.scan_options);
+        (path.clone(), files)
+    } else {
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let files =
+            scan::scan_listed_files(&root, std::slice::from_ref(&path), &config.scan_options);
+        (root, files)
+    };
+    if files.is_empty() {
+        return Err(anyhow!(
+            "[E_COMPARE_INPUT] 没有找到支持的音频文件: {}",
+            path.display()
+        ));
+    }
+    let cache = if config.cache_enabled {
+        AnalysisCache::load(&config.app_paths.library_cache_file(&root)).unwrap_or_default()
+    } else {
+        AnalysisCache::default()
+    };
+
+    uiprintln!("正在分析 {} 个文件: {}", files.len(), path.display());
+    let bar = ProgressBar::new(files.len() as u64);
+    let progress = Arc::new(ExtractionProgress::default());
+    bar.set_style(extraction_progress_style(&progress));
+    if console::quiet() {
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    let _bar_logging = logging::attach_progress_bar(&bar);
+    let failures = AtomicUsize::new(0);
+    let metrics: Vec<FileMetrics> = files
+        .into_par_iter()
+        .flat_map_iter(|file| {
+            let _span = tracing::info_span!("file", path = %file.path.display()).entered();
+            let result = process_one_file(
+                &file.path,
+                processing_config,
+                config.audio_streams,
+                &file.cue_tracks,
+                config.cache_enabled.then_some(&cache),
+                None,
+                config.fingerprint_options(),
+            );
+            bar.inc(1);
+            match result {
+                Ok(records) => {
+                    progress.record(&records);
+                    records.into_iter().map(|record| record.metrics).collect()
+                }
+                Err(e) => {
+                    tracing::warn!("处理失败: {e}");
+                    failures.fetch_add(1, Ordering::Relaxed);
+                    Vec::new()
+                }
+            }
+        })
+        .collect();
+    bar.finish_and_clear();
+    let failures = failures.into_inner();
+    if failures > 0 {
+        uiprintln!(
+            "⚠️ {failures} 个文件处理失败，未计入对比: {}",
+            path.display()
+        );
+    }
+    if metrics.is_empty() {
+        return Err(anyhow!(
+            "[E_COMPARE_INPUT] 没有可分析的音频: {}",
+            path.display()
+        ));
+    }
+    Ok((metrics, root))
+}
+
+/// 清理缓存目录中每个曲库的缓存文件；条目全部移除时删除该缓存文件。
+fn prune_caches(max_age_days: Option<u64>, dry_run: bool, config: &AppConfig) -> Result<()> {
+    let files = config.app_paths.library_cache_files()?;
+    if files.is_empty() {
+        uiprintln!(
+            "缓存目录中没有缓存文件: {}",
+            config.app_paths.cache_dir.display()
+        );
+        return Ok(());
+    }
+    let now = cache::now_unix_secs();
+    let mut total = cache::PruneStats::default();
+    let mut remaining = 0usize;
+    let mut offline = 0usize;
+    for path in &files {
+        let mut cache_data = match AnalysisCache::load(path) {
+            Ok(cache_data) => cache_data,
+            Err(e) => {
+                tracing::warn!("跳过无法读取的缓存文件: {e:#}");
+                continue;
+            }
+        };
+        if cache_data.is_offline() {
+            offline += 1;
+            remaining += cache_data.len();
+            uiprintln!(
+                " - {}: 曲库离线（条目所在目录均不存在），跳过 {} 条",
+                path.display(),
+                cache_data.len()
+            );
             continue;
         }
-
-        let path = PathBuf::from(path_str);
-        if path.is_dir() {
-            return path.canonicalize().context("路径规范化失败，请检查权限");
+        let pruned = cache_data.prune(max_age_days, now);
+        total.missing += pruned.missing;
+        total.stale += pruned.stale;
+        remaining += cache_data.len();
+        uiprintln!(
+            " - {}: 保留 {} 条，移除 {} 条",
+            path.display(),
+            cache_data.len(),
+            pruned.total()
+        );
+        if dry_run || pruned.total() == 0 {
+            continue;
         }
-
-        if path.exists() {
-            eprintln!("输入路径不是文件夹: {}", path.display());
+        if cache_data.is_empty() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("删除缓存文件失败: {}", path.display()))?;
         } else {
-            eprintln!("路径不存在: {}", path.display());
+            cache_data
+                .save(path, config.safe_mode)
+                .with_context(|| format!("保存缓存失败: {}", path.display()))?;
         }
     }
-}
-
-fn find_ffmpeg_path() -> Result<PathBuf> {
-    if let Ok(path) = which("ffmpeg") {
-        println!("成功在 PATH 中找到 ffmpeg: {}", path.display());
-        return Ok(path);
+    uiprintln!("\n共保留 {remaining} 条");
+    if offline > 0 {
+        uiprintln!("{offline} 个曲库离线未清理，挂载后再运行 cache prune。");
     }
-
-    let mut candidates = Vec::new();
-    if let Ok(cwd) = env::current_dir() {
-        candidates.push(cwd.join("resources/ffmpeg"));
+    if total.total() == 0 {
+        uiprintln!("没有需要清理的条目。");
+    } else if dry_run {
+        uiprintln!("（预览）{}", describe_pruned(&total, max_age_days));
+    } else {
+        uiprintln!("{}", describe_pruned(&total, max_age_days));
     }
+    Ok(())
+}
 
-    if let Ok(current_exe_path) = env::current_exe() {
-        if let Some(project_root) = current_exe_path.ancestors().nth(3) {
-            candidates.push(project_root.join("resources/ffmpeg"));
-        }
+fn describe_pruned(pruned: &cache::PruneStats, max_age_days: Option<u64>) -> String {
+    let mut text = format!(
+        "缓存清理: 移除 {} 条（文件已删除或移动 {} 条",
+        pruned.total(),
+        pruned.missing
+    );
+    if let Some(days) = max_age_days {
+        text.push_str(&format!("，超过 {days} 天未使用 {} 条", pruned.stale));
     }
+    text.push('）');
+    text
+}
 
-    for candidate in candidates {
-        if candidate.is_file() {
-            println!(
-                "未在 PATH 找到 ffmpeg，使用备用路径: {}",
-                candidate.display()
-            );
-            return Ok(candidate);
-        }
+/// 按正式分析相同的规则查找 FFmpeg/FFprobe 与应用目录，逐项诊断；有必须修复的问题时以环境错误退出。
+fn run_doctor(cli: &Cli) -> Result<ExitStatus> {
+    let app_paths = AppPaths::resolve()?;
+    let config_files: Vec<PathBuf> = std::iter::once(app_paths.config_file())
+        .chain(cli.config.clone())
+        .collect();
+    // 配置无效时报告原因，其余检查按默认设置继续进行。
+    let (config, config_error) = match load_user_config(cli, &app_paths)
+        .and_then(|user_config| build_app_config_from(cli, app_paths.clone(), user_config))
+    {
+        Ok(config) => (config, None),
+        Err(e) => (
+            build_app_config_from(
+                &Cli::parse_from([program_name()]),
+                app_paths,
+                UserConfig::default(),
+            )?,
+            Some(format!("{e:#}")),
+        ),
+    };
+    let config = &config;
+    let processing = find_ffmpeg_path(config.ffmpeg_path.as_deref()).map(|ffmpeg_path| {
+        let ffprobe_path = find_ffprobe_path(&ffmpeg_path, config);
+        basic_processing_config(ffmpeg_path, ffprobe_path, config)
+    });
+    let mut writable_dirs = vec![
+        ("缓存目录", config.app_paths.cache_dir.clone()),
+        ("历史目录", config.app_paths.history_dir.clone()),
+        ("日志目录", config.app_paths.logs_dir.clone()),
+    ];
+    if let Some(dir) = &config.output_dir {
+        writable_dirs.push(("输出目录", dir.clone()));
     }
-
-    Err(anyhow!(
-        "在 PATH 与 resources 目录中均未找到 ffmpeg，可执行文件缺失。"
-    ))
+    let checks = doctor::diagnose(&doctor::Environment {
+        config_files,
+        config_error,
+        processing: processing.as_ref().map_err(|e| e.to_string()),
+        install_hint: ffmpeg_install_hint(),
+        writable_dirs,
+        jobs: config.jobs,
+    });
+    uiprintln!();
+    Ok(if doctor::print(&checks) {
+        ExitStatus::Environment
+    } else {
+        ExitStatus::Success
+    })
 }
 
-fn find_ffprobe_path(ffmpeg_path: &Path) -> Option<PathBuf> {
-    if let Ok(path) = which("ffprobe") {
-        println!("成功在 PATH 中找到 ffprobe: {}", path.display());
-        return Some(path);
+/// 分析之外的 FFmpeg 调用（频谱图、下载）使用的配置：不做采样、分段与硬件加速。
+fn basic_processing_config(
+    ffmpeg_path: PathBuf,
+    ffprobe_path: Option<PathBuf>,
+    config: &AppConfig,
+) -> ffmpeg::ProcessingConfig {
+    ffmpeg::ProcessingConfig {
+        ffmpeg_path,
+        ffprobe_path,
+        command_timeout: config.command_timeout,
+        process_limiter: ffmpeg::ProcessLimiter::new(config.max_ffmpeg_processes),
+        decode_threads: config.decode_threads,
+        hwaccel: None,
+        hf_bands: config.hf_bands.clone(),
+        fast_sampling: false,
+        segment_seconds: None,
+        audio_stream: 0,
+        cue_track: None,
+        ffmpeg_version: None,
+        reanalyze_on_ffmpeg_change: false,
+        pcm_md5: false,
+        detect_limiting: false,
     }
+}
 
-    let sibling = ffmpeg_path
-        .parent()
-        .map(|parent| parent.join("ffprobe"))
-        .filter(|path| path.is_file());
-    if let Some(path) = sibling {
-        println!(
-            "未在 PATH 找到 ffprobe，使用同目录备用路径: {}",
-            path.display()
-        );
-        return Some(path);
+/// 按当前评分设置重新评分一次运行的结果，并在本机提供结果接口，直到按 Ctrl-C 退出。
+fn serve_results(run: &Path, port: u16, ui: bool, config: &AppConfig) -> Result<()> {
+    if ui && !cfg!(feature = "webui") {
+        return Err(anyhow!(
+            "[E_WEBUI_DISABLED] 当前版本未包含网页界面，请以 `--features webui` 重新编译"
+        ));
     }
+    let (metrics, root) = compare::load_run(run)?;
+    let analyses = build_scorer(config)?.analyze_files_owned(metrics);
+    // 频谱图是可选功能，找不到 ffmpeg 时其余接口照常提供。
+    let processing = find_ffmpeg_path(config.ffmpeg_path.as_deref())
+        .ok()
+        .map(|ffmpeg_path| {
+            let ffprobe_path = find_ffprobe_path(&ffmpeg_path, config);
+            basic_processing_config(ffmpeg_path, ffprobe_path, config)
+        });
 
-    println!("未找到 ffprobe，将跳过采样率/码率/声道等元数据分析。");
-    None
-}
-
-fn sanitize_for_terminal(input: &str) -> String {
-    input
-        .chars()
-        .filter(|ch| {
-            let c = *ch as u32;
-            c == 0x09 || c == 0x20 || (0x21..=0x7e).contains(&c) || c >= 0xa0
-        })
-        .collect()
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    uiprintln!("已加载 {} 条结果: {}", analyses.len(), root.display());
+    if ui {
+        uiprintln!("网页界面: http://{addr}/");
+    }
+    uiprintln!("结果接口: http://{addr}/api/results （按 Ctrl-C 退出）");
+    let _cancellable = CancellableTask::begin();
+    server::ResultsServer::new(root, analyses, processing, ui).serve(addr, &CANCELLED)
 }
 
-fn run_analysis(base_folder_path: &Path, config: &AppConfig) -> Result<()> {
-    println!("\n--- 开始执行分析流程 ---");
-    println!("分析开始时间: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
-    println!(
-        "安全模式: {} | 缓存: {} | 命令超时: {}s | 最大并发进程: {} | 评分档案: {}",
-        if config.safe_mode { "开启" } else { "关闭" },
-        if config.cache_enabled {
-            "开启"
-        } else {
-            "关闭"
-        },
-        config.command_timeout.as_secs(),
-        config.max_ffmpeg_processes,
-        config.scoring_profile.as_str()
+/// `rescore`：按当前评分设置对已有结果重新评分并写出全部报告，省去重新解码。
+/// 报告写回该运行的目录；指定了 `--output-dir` 时写入其下以曲库目录名命名的子目录。
+fn rescore_run(run: &Path, config: &AppConfig) -> Result<RunOutcome> {
+    let (results, library_root) = compare::load_run(run)?;
+    let run_dir = compare::run_dir(run);
+    let report_dir = if config.output_dir.is_some() {
+        config.report_dir(&library_root)
+    } else {
+        run_dir
+    };
+    tracing::info!("重新评分: {}", run.display());
+    uiprintln!("\n--- 重新评分 ---");
+    uiprintln!(
+        "读取已有结果 {} 条: {} | 评分档案: {}",
+        results.len(),
+        run.display(),
+        profile_name(config)
     );
-
-    let ffmpeg_path = find_ffmpeg_path()?;
-    let ffprobe_path = find_ffprobe_path(&ffmpeg_path);
-
-    println!("正在扫描文件夹: {}", base_folder_path.display());
-
-    let audio_files: Vec<PathBuf> = WalkDir::new(base_folder_path)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| e.file_type().is_file())
-        .map(|e| e.into_path())
-        .filter(|path| {
-            path.extension()
-                .and_then(|s| s.to_str())
-                .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
-                .unwrap_or(false)
-        })
-        .collect();
-
-    if audio_files.is_empty() {
-        println!("在指定路径下没有找到支持的音频文件。");
-        return Ok(());
+    prepare_report_dir(&report_dir, &library_root, config)?;
+    if config.spectrograms.is_some() {
+        uiprintln!("重新评分不调用 FFmpeg，跳过频谱图。");
     }
+    let sidecar_location = sidecar::SidecarLocation::new(
+        &library_root,
+        config
+            .output_dir
+            .is_some()
+            .then(|| report_dir.join(sidecar::SIDECAR_MIRROR_DIR_NAME)),
+    );
 
-    let total_files = audio_files.len();
-    println!("扫描完成，找到 {total_files} 个音频文件。开始分析...");
-
-    let cache_path = base_folder_path.join(".audio_quality_cache.json");
-    let mut cache_data = if config.cache_enabled {
-        AnalysisCache::load(&cache_path).with_context(|| {
-            format!("加载增量缓存失败，请检查缓存文件: {}", cache_path.display())
-        })?
+    // 沿用缓存中记录的指纹，单文件结果与 SQLite 数据库中的内容指纹不因重新评分而丢失。
+    let fingerprints = if config.cache_enabled {
+        AnalysisCache::load(&config.app_paths.library_cache_file(&library_root))
+            .unwrap_or_default()
+            .fingerprints_for(&results)
     } else {
-        AnalysisCache::default()
+        HashMap::new()
     };
-    let cache_snapshot = cache_data.clone();
 
-    let processing_config = ffmpeg::ProcessingConfig {
-        ffmpeg_path,
-        ffprobe_path,
-        command_timeout: config.command_timeout,
-        process_limiter: ffmpeg::ProcessLimiter::new(config.max_ffmpeg_processes),
-    };
+    let scorer = build_scorer(config)?;
+    let scored = score_and_report(
+        &library_root,
+        &report_dir,
+        config,
+        &scorer,
+        ReportInput {
+            results,
+            fingerprints,
+            throughput: None,
+            toolchain: None,
+            processing_config: None,
+            sidecar_location: &sidecar_location,
+        },
+    )?;
+    uiprintln!("--- 重新评分完成 ---");
+    if config.json_stdout {
+        print_json_results(
+            &scored.summary,
+            &config.result_filter.apply(&scored.analyses),
+            &[],
+        )?;
+    }
+    Ok(RunOutcome {
+        total_files: scored.analyses.len(),
+        compliance_failures: scored.compliance_failures,
+        delivery_failures: scored.delivery_failures,
+        gate_failures: scored.gate_failures,
+        ..RunOutcome::default()
+    })
+}
 
-    let bar = ProgressBar::new(total_files as u64);
-    let style = ProgressStyle::with_template(
-        "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) - {msg}",
-    )
-    .unwrap_or_else(|_| ProgressStyle::default_bar());
-    bar.set_style(style.progress_chars("#>- "));
+/// 解析 `rescore` 之后的参数：按分析时的命令行解析，评分、门禁与输出设置与正式分析一致。
+/// 参数错误（及 `--help`）原样返回 clap 的错误，由 `main` 统一打印并决定退出码。
+fn rescore_options(options: &[String]) -> Result<Cli> {
+    let cli = Cli::try_parse_from(std::iter::once(program_name()).chain(options.iter().cloned()))?;
+    if cli.command.is_some()
+        || !cli.paths.is_empty()
+        || cli.files_from.is_some()
+        || cli.retry_failed.is_some()
+        || cli.merge.is_some()
+    {
+        return Err(anyhow!(
+            "[E_RESCORE] rescore 只接受评分与输出参数，不能再指定输入路径或子命令"
+        ));
+    }
+    Ok(cli)
+}
 
-    let processed_records: Vec<ProcessedRecord> = audio_files
-        .into_par_iter()
-        .filter_map(|path| {
-            let filename = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .into_owned();
-            bar.set_message(sanitize_for_terminal(&filename));
+/// 先按常规流程分析各个标注文件夹（沿用缓存），再用得到的指标训练有损来源分类器。
+fn train_lossy_classifier(
+    lossless: &[PathBuf],
+    transcode: &[PathBuf],
+    output: &Path,
+    config: &AppConfig,
+) -> Result<()> {
+    let collect = |folders: &[PathBuf]| -> Result<Vec<FileMetrics>> {
+        let mut metrics = Vec::new();
+        for folder in folders {
+            let folder = folder
+                .canonicalize()
+                .with_context(|| format!("[E_TRAIN_INPUT] 无法访问文件夹: {}", folder.display()))?;
+            run_analysis(&folder, config)?;
+            metrics.extend(compare::load_run(&config.report_dir(&folder))?.0);
+        }
+        Ok(metrics)
+    };
+    let lossless_metrics = collect(lossless)?;
+    let transcode_metrics = collect(transcode)?;
 
-            let result = process_one_file(
-                &path,
-                &processing_config,
-                &cache_snapshot,
-                config.cache_enabled,
-            );
-            bar.inc(1);
+    let classifier = LossyClassifier::train(&lossless_metrics, &transcode_metrics)?;
+    classifier.save(output, config.safe_mode)?;
+    uiprintln!(
+        "\n✅ 有损来源分类器已保存: {} (训练集准确率 {:.1}%)",
+        output.display(),
+        classifier.accuracy(&lossless_metrics, &transcode_metrics) * 100.0
+    );
+    uiprintln!("使用方法: --lossy-model {}", output.display());
+    Ok(())
+}
 
-            match result {
-                Ok(record) => Some(record),
-                Err(e) => {
-                    bar.println(format!("处理失败 [{}]: {e}", path.display()));
-                    None
-                }
-            }
-        })
-        .collect();
-    bar.finish_with_message("数据提取完成。");
+fn run(cli: Cli) -> Result<ExitStatus> {
+    let (cli, rescore) = match cli.command {
+        Some(Command::Rescore { run, options }) => (rescore_options(&options)?, Some(run)),
+        _ => (cli, None),
+    };
+    // 子命令的输出本身就是结果，交互模式需要菜单与提示：安静模式只作用于命令行分析与重新评分。
+    let quiet = cli.quiet
+        && (rescore.is_some()
+            || cli.command.is_none()
+                && (!cli.paths.is_empty()
+                    || cli.files_from.is_some()
+                    || cli.retry_failed.is_some()));
+    logging::init(cli.log_level, quiet, cli.log_file.as_deref())?;
 
-    let mut results: Vec<FileMetrics> = Vec::with_capacity(processed_records.len());
-    let mut cache_hits = 0usize;
-    for record in processed_records {
-        if record.metrics.cache_hit {
-            cache_hits += 1;
-        }
-        if config.cache_enabled {
-            cache_data.upsert(
-                &PathBuf::from(&record.metrics.file_path),
-                record.fingerprint,
-                record.metrics.clone(),
-            );
+    if let Some(Command::Paths) = cli.command {
+        let paths = AppPaths::resolve()?;
+        // 配置文件指定了缓存目录时显示实际位置；配置文件损坏时仍显示默认位置，便于找到并修复它。
+        let cache_path = UserConfig::load(&paths.config_file())
+            .ok()
+            .flatten()
+            .and_then(|user_config| user_config.cache_path);
+        match cache_path {
+            Some(cache_path) => paths.with_cache_dir(&cache_path).print(),
+            None => paths.print(),
         }
-        results.push(record.metrics);
+        return Ok(ExitStatus::Success);
     }
-    println!("缓存命中: {cache_hits}/{}", results.len());
 
-    if config.cache_enabled {
-        cache_data
-            .save(&cache_path, config.safe_mode)
-            .with_context(|| format!("保存缓存失败: {}", cache_path.display()))?;
-        println!("缓存已更新: {}", cache_path.display());
+    if let Some(Command::Schema { kind }) = &cli.command {
+        let kind = SchemaKind::from_str(kind).map_err(|e| anyhow!("schema 参数错误: {e}"))?;
+        println!("{}", schema::json_schema(kind)?);
+        return Ok(ExitStatus::Success);
     }
 
-    println!("正在进行质量评分分析...");
-    let scorer = QualityScorer::with_profile(config.scoring_profile);
-    let quality_analyses = scorer.analyze_files(&results);
+    // 诊断不能依赖一份有效的配置：配置文件写错时正需要 doctor 指出问题。
+    if let Some(Command::Doctor) = cli.command {
+        return run_doctor(&cli);
+    }
 
-    let report_generator = ReportGenerator::new(config.safe_mode);
+    let config = build_app_config(&cli)?;
+    diagnostics::set_bundle_dir(&config.app_paths.logs_dir);
+    console::set_json_stdout(config.json_stdout);
+    console::set_quiet(quiet);
 
-    let csv_output_path = base_folder_path.join("audio_quality_report.csv");
-    report_generator.generate_csv_report(&quality_analyses, &csv_output_path)?;
+    if let Some(Command::Cache {
+        action:
+            CacheCommand::Prune {
+                max_age_days,
+                dry_run,
+                ..
+            },
+    }) = cli.command
+    {
+        prune_caches(max_age_days.or(config.cache_max_age_days), dry_run, &config)?;
+        return Ok(ExitStatus::Success);
+    }
 
-    report_generator.display_summary(&quality_analyses);
+    if let Some(run) = &rescore {
+        let outcome = rescore_run(run, &config)?;
+        return Ok(outcome.exit_status(&config));
+    }
 
-    let json_output_path = base_folder_path.join("analysis_data.json");
-    println!("\n正在保存原始数据到: {}", json_output_path.display());
-    let json_content = serde_json::to_string_pretty(&results)?;
-    safe_io::atomic_write_string(&json_output_path, &json_content, config.safe_mode)
-        .context("无法写入 analysis_data.json 文件")?;
-    println!("原始数据保存成功。");
+    if let Some(Command::Compare {
+        old,
+        new,
+        html,
+        csv,
+    }) = &cli.command
+    {
+        compare_runs(old, new, html.as_deref(), csv.as_deref(), &config)?;
+        return Ok(ExitStatus::Success);
+    }
 
-    if config.emit_jsonl {
-        let jsonl_path = base_folder_path.join("audio_quality_report.jsonl");
-        report_generator.generate_jsonl_report(&quality_analyses, &jsonl_path)?;
+    if let Some(Command::Train {
+        lossless,
+        transcode,
+        output,
+    }) = &cli.command
+    {
+        train_lossy_classifier(lossless, transcode, output, &config)?;
+        return Ok(ExitStatus::Success);
     }
 
-    if config.emit_sarif {
-        let sarif_path = base_folder_path.join("audio_quality_report.sarif.json");
-        report_generator.generate_sarif_report(&quality_analyses, &sarif_path)?;
+    if let Some(Command::Serve { run, port, ui }) = &cli.command {
+        serve_results(run, *port, *ui, &config)?;
+        return Ok(ExitStatus::Success);
     }
 
-    println!(
-        "\n分析结束时间: {}",
-        Local::now().format("%Y-%m-%d %H:%M:%S")
-    );
-    println!("--- 分析流程完成 ---");
-    Ok(())
-}
+    uiprintln!("欢迎使用音频质量分析器 (Rust 版)");
 
-fn process_one_file(
-    path: &Path,
-    processing_config: &ffmpeg::ProcessingConfig,
-    cache_snapshot: &AnalysisCache,
-    cache_enabled: bool,
-) -> Result<ProcessedRecord> {
-    let fingerprint = cache::fingerprint_file(path)?;
+    if let Some(errors_csv) = &cli.retry_failed {
+        let outcome = retry_failed_files(errors_csv, &config)?;
+        return Ok(outcome.exit_status(&config));
+    }
 
-    if cache_enabled {
-        if let Some(mut metrics) = cache_snapshot.lookup(path, &fingerprint) {
-            metrics.processing_time_ms = 0;
-            return Ok(ProcessedRecord {
-                metrics,
-                fingerprint,
-            });
+    if cli.paths.is_empty() && cli.files_from.is_none() {
+        interactive_mode(&config)?;
+        return Ok(ExitStatus::Success);
+    }
+    let mut inputs = cli
+        .paths
+        .iter()
+        .map(|path| {
+            if remote::is_url_path(path) {
+                return Ok(path.clone());
+            }
+            path.canonicalize()
+                .with_context(|| format!("命令行提供的路径不存在: {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(source) = &cli.files_from {
+        inputs.extend(read_file_list(source, cli.null_separated)?);
+    }
+    let has_playlist = inputs.iter().any(|input| playlist::is_playlist(input));
+    if has_playlist {
+        inputs = expand_playlists(inputs)?;
+    }
+    if inputs.is_empty() {
+        uiprintln!("路径列表为空，没有要分析的文件。");
+        return Ok(ExitStatus::Success);
+    }
+    let (urls, inputs): (Vec<PathBuf>, Vec<PathBuf>) = inputs
+        .into_iter()
+        .partition(|input| remote::is_url_path(input));
+    let outcome = match (&cli.merge, inputs.as_slice()) {
+        (Some(_), _) if !urls.is_empty() => {
+            return Err(anyhow!("[E_REMOTE_MERGE] --merge 不支持网络地址"));
+        }
+        (Some(report_dir), _) => merge_into_report(&inputs, report_dir, &config)?,
+        (None, _) if !urls.is_empty() => analyze_remote(&inputs, &urls, &config)?,
+        // 单个文件夹按完整扫描处理：判定移除的文件并沿用该文件夹的报告。
+        (None, [folder]) if folder.is_dir() && cli.files_from.is_none() && !has_playlist => {
+            run_analysis(folder, &config)?
+        }
+        (None, _) => analyze_selection(&inputs, &config)?,
+    };
+    Ok(outcome.exit_status(&config))
+}
+
+/// 读取 `--files-from` 的路径列表（`-` 为标准输入）。列表通常由其他工具生成，
+/// 不存在的路径只提示并跳过，不中断整批分析。
+fn read_file_list(source: &Path, null_separated: bool) -> Result<Vec<PathBuf>> {
+    let content = if source == Path::new("-") {
+        let mut buffer = Vec::new();
+        io::stdin()
+            .read_to_end(&mut buffer)
+            .context("[E_FILES_FROM] 读取标准输入失败")?;
+        buffer
+    } else {
+        std::fs::read(source)
+            .with_context(|| format!("[E_FILES_FROM] 无法读取路径列表: {}", source.display()))?
+    };
+    let mut paths = Vec::new();
+    for path in scan::parse_path_list(&content, null_separated) {
+        if remote::is_url_path(&path) {
+            paths.push(path);
+            continue;
+        }
+        match path.canonicalize() {
+            Ok(path) => paths.push(path),
+            Err(_) => tracing::warn!("跳过不存在的路径: {}", path.display()),
         }
     }
+    Ok(paths)
+}
 
-    let mut metrics = ffmpeg::process_file(path, processing_config)?;
-    metrics.content_sha256 = Some(fingerprint.content_sha256.clone());
+/// 把输入中的播放列表替换为其中的曲目（保持列表顺序）；不存在的曲目只提示并跳过，
+/// 便于直接检查从多个文件夹挑选出的曲目单。
+fn expand_playlists(inputs: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        if !(input.is_file() && playlist::is_playlist(&input)) {
+            expanded.push(input);
+            continue;
+        }
+        let entries = playlist::read_playlist(&input)?;
+        tracing::info!("播放列表 {}: {} 个曲目", input.display(), entries.len());
+        for entry in entries {
+            if remote::is_url_path(&entry) {
+                expanded.push(entry);
+                continue;
+            }
+            match entry.canonicalize() {
+                Ok(path) => expanded.push(path),
+                Err(_) => tracing::warn!(
+                    "跳过播放列表 {} 中不存在的曲目: {}",
+                    input.display(),
+                    entry.display()
+                ),
+            }
+        }
+    }
+    Ok(expanded)
+}
 
-    Ok(ProcessedRecord {
-        metrics,
-        fingerprint,
-    })
+/// 分析网络地址上的文件（及同时给出的本地路径）。远程文件下载到临时目录分析，报告记录原始地址；
+/// 没有本地路径时报告写入当前目录（或 `--output-dir`）。
+fn analyze_remote(local: &[PathBuf], urls: &[PathBuf], config: &AppConfig) -> Result<RunOutcome> {
+    let root = match scan::common_root(local) {
+        Some(root) => root,
+        None => env::current_dir().context("无法确定当前目录")?,
+    };
+    let files = scan::expand_inputs(local, &config.scan_options);
+    let mut seen = std::collections::HashSet::new();
+    let urls: Vec<String> = urls
+        .iter()
+        .map(|url| url.to_string_lossy().into_owned())
+        .filter(|url| seen.insert(url.clone()))
+        .collect();
+    analyze(&root, config, AnalysisScope::Remote { files, urls })
 }
 
-fn build_app_config(cli: &Cli) -> Result<AppConfig> {
-    let default_parallel = std::thread::available_parallelism()
-        .map(|n| n.get())
-        .unwrap_or(4);
-    let scoring_profile =
-        ScoringProfile::from_str(&cli.profile).map_err(|e| anyhow!("profile 参数错误: {e}"))?;
+/// 分析命令行给出的文件与文件夹的并集；报告、缓存与历史记录归入它们的最近公共目录，
+/// 结果与该目录已有的报告合并，不会用这几个文件覆盖整个曲库的报告。
+fn analyze_selection(inputs: &[PathBuf], config: &AppConfig) -> Result<RunOutcome> {
+    let root = scan::common_root(inputs).ok_or_else(|| anyhow!("没有提供要分析的路径"))?;
+    // 没有共同的上级目录时公共目录退化为文件系统根目录，报告不应写到那里。
+    if root.parent().is_none() && config.output_dir.is_none() {
+        return Err(anyhow!(
+            "[E_SELECTION_ROOT] 给出的路径没有共同的上级目录（公共目录为 {}），请用 --output-dir 指定报告目录",
+            root.display()
+        ));
+    }
+    let files = scan::expand_inputs(inputs, &config.scan_options);
+    if files.is_empty() {
+        uiprintln!("在指定路径下没有找到支持的音频文件。");
+        return Ok(RunOutcome::default());
+    }
+    analyze(&root, config, AnalysisScope::Selection(files))
+}
 
-    Ok(AppConfig {
-        command_timeout: Duration::from_secs(cli.ffmpeg_timeout_seconds.max(1)),
-        max_ffmpeg_processes: cli.max_ffmpeg_processes.unwrap_or(default_parallel).max(1),
-        safe_mode: !cli.unsafe_mode,
-        cache_enabled: !cli.no_cache,
-        emit_jsonl: cli.jsonl,
-        emit_sarif: cli.sarif,
-        scoring_profile,
-    })
+/// 打印 clap 的解析结果（错误、`--help` 或 `--version`）并给出退出码。
+/// clap 默认以 2 表示参数错误，与"门禁未通过"冲突，这里统一归为一般错误。
+fn clap_exit(e: &clap::Error) -> ExitCode {
+    let _ = e.print();
+    if e.use_stderr() {
+        ExitStatus::Failure.into()
+    } else {
+        ExitStatus::Success.into()
+    }
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let config = build_app_config(&cli)?;
+fn main() -> ExitCode {
+    diagnostics::install_panic_hook();
+    install_cancel_handler();
 
-    println!("欢迎使用音频质量分析器 (Rust 版)");
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => return clap_exit(&e),
+    };
 
-    match cli.path {
-        Some(path) => {
-            if path.is_dir() {
-                let absolute_path = path.canonicalize()?;
-                run_analysis(&absolute_path, &config)
-            } else {
-                Err(anyhow!(
-                    "命令行提供的路径不是有效文件夹: {}",
-                    path.display()
-                ))
+    match run(cli) {
+        Ok(status) => status.into(),
+        Err(e) => match e.downcast_ref::<clap::Error>() {
+            Some(clap_error) => clap_exit(clap_error),
+            None => {
+                eprintln!("错误: {e:#}");
+                exit_code::classify(&e).into()
             }
-        }
-        None => interactive_mode(&config),
+        },
     }
 }
 
@@ -445,11 +4531,94 @@ fn main() -> Result<()> {
 mod tests {
     use super::*;
 
+    /// 与 `build_app_config` 相同，但不读取当前用户的配置文件。
+    fn build_app_config(cli: &Cli) -> Result<AppConfig> {
+        let home = std::env::temp_dir().join("audioquality-test-home");
+        build_app_config_from(cli, AppPaths::under_root(&home), UserConfig::default())
+    }
+
     #[test]
-    fn test_supported_extensions_are_lowercase() {
-        for &ext in &SUPPORTED_EXTENSIONS {
-            assert_eq!(ext, ext.to_lowercase());
-        }
+    fn test_wizard_profile_and_report_dir() {
+        assert_eq!(wizard_profile(false, false), ScoringProfile::Pop);
+        assert_eq!(wizard_profile(true, false), ScoringProfile::Podcast);
+
+        let cli = Cli::parse_from(["AudioQuality-rs", "--output-dir", "/reports"]);
+        let config = build_app_config(&cli).expect("build config");
+        let jazz = config.report_dir(Path::new("/music/Jazz"));
+        assert_eq!(jazz.parent(), Some(Path::new("/reports")));
+        let name = jazz
+            .file_name()
+            .and_then(|name| name.to_str())
+            .expect("name");
+        assert!(name.starts_with("Jazz_") && name.len() == "Jazz_".len() + 8);
+        assert_ne!(jazz, config.report_dir(Path::new("/backup/Jazz")));
+        let config = AppConfig {
+            output_dir: None,
+            ..config
+        };
+        assert_eq!(
+            config.report_dir(Path::new("/music/Jazz")),
+            PathBuf::from("/music/Jazz")
+        );
+    }
+
+    #[test]
+    fn test_selection_without_common_folder_requires_output_dir() {
+        let cli = Cli::parse_from(["AudioQuality-rs"]);
+        let config = build_app_config(&cli).expect("build config");
+        let inputs = [PathBuf::from("/a/x.flac"), PathBuf::from("/b/y.flac")];
+        let error = analyze_selection(&inputs, &config).expect_err("root selection");
+        assert!(error.to_string().contains("[E_SELECTION_ROOT]"));
+    }
+
+    #[test]
+    fn test_equivalent_command_round_trips_through_cli() {
+        let cli = Cli::parse_from([
+            "AudioQuality-rs",
+            "--fast",
+            "--hf-bands",
+            "14000,19000",
+            "--profile",
+            "classical",
+            "--output-dir",
+            "/reports",
+            "--audio-stream",
+            "all",
+            "--jsonl",
+            "--json-stdout",
+            "--quiet",
+            "--detect-limiting",
+            "--target-lufs",
+            "-14",
+            "--jobs",
+            "2",
+            "--cache-path",
+            "/var/cache/aq",
+            "--log-level",
+            "debug",
+            "--log-file",
+            "/var/log/aq.log",
+        ]);
+        let config = build_app_config(&cli).expect("build config");
+        let args = equivalent_command("AudioQuality-rs", Path::new("/music/Jazz"), &config);
+        assert!(!args.contains(&"--sarif".to_string()));
+
+        let replayed = build_app_config(&Cli::parse_from(&args)).expect("replayed config");
+        assert!(replayed.fast_sampling && replayed.emit_jsonl && replayed.json_stdout);
+        assert!(!replayed.emit_json_report);
+        assert!(replayed.quiet && replayed.detect_limiting);
+        assert_eq!(replayed.hf_bands, [14_000, 16_000, 18_000, 19_000]);
+        assert_eq!(replayed.scoring_profile, ScoringProfile::Classical);
+        assert_eq!(replayed.output_dir, Some(PathBuf::from("/reports")));
+        assert_eq!(replayed.audio_streams, ffmpeg::StreamSelection::All);
+        assert_eq!(replayed.threshold_overrides.target_lufs, Some(-14.0));
+        assert_eq!(replayed.jobs, Some(2));
+        assert_eq!(replayed.max_ffmpeg_processes, 2);
+        assert!(!args.contains(&"--max-ffmpeg-processes".to_string()));
+        assert_eq!(replayed.app_paths.cache_dir, PathBuf::from("/var/cache/aq"));
+        assert_eq!(replayed.log_level, Some(LevelFilter::DEBUG));
+        assert_eq!(replayed.log_file, Some(PathBuf::from("/var/log/aq.log")));
+        assert_eq!(args.last().map(String::as_str), Some("/music/Jazz"));
     }
 
     #[test]
@@ -458,7 +4627,273 @@ mod tests {
         let config = build_app_config(&cli).expect("build config");
         assert!(config.safe_mode);
         assert!(config.cache_enabled);
+        assert_eq!(config.hash_algorithm, HashAlgorithm::Sha256);
         assert!(config.command_timeout.as_secs() >= 1);
         assert_eq!(config.scoring_profile, ScoringProfile::Pop);
+
+        // 配置文件中的哈希算法作为默认值，命令行参数优先。
+        let home = std::env::temp_dir().join("audioquality-test-home");
+        let user_config = UserConfig {
+            hash_algorithm: Some("blake3".to_string()),
+            ..UserConfig::default()
+        };
+        let from_file =
+            build_app_config_from(&cli, AppPaths::under_root(&home), user_config.clone())
+                .expect("build config");
+        assert_eq!(from_file.hash_algorithm, HashAlgorithm::Blake3);
+        let overridden = Cli::parse_from(["AudioQuality-rs", "--hash-algorithm", "xxh3"]);
+        let from_cli = build_app_config_from(&overridden, AppPaths::under_root(&home), user_config)
+            .expect("build config");
+        assert_eq!(from_cli.hash_algorithm, HashAlgorithm::Xxh3);
+    }
+
+    #[test]
+    fn test_paths_subcommand_parses() {
+        let cli = Cli::parse_from(["AudioQuality-rs", "paths"]);
+        assert!(matches!(cli.command, Some(Command::Paths)));
+        assert!(cli.paths.is_empty());
+
+        let cli = Cli::parse_from(["AudioQuality-rs", "/music"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.paths, [PathBuf::from("/music")]);
+
+        let cli = Cli::parse_from(["AudioQuality-rs", "song.flac", "other.flac", "/music"]);
+        assert_eq!(cli.paths.len(), 3);
+    }
+
+    #[test]
+    fn test_retry_results_replace_previous_records() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let library = tempfile::TempDir::new().expect("library");
+        let file = |name: &str| {
+            let path = library.path().join(name);
+            std::fs::write(&path, b"audio").expect("write audio");
+            path.to_string_lossy().into_owned()
+        };
+        let metrics = |path: &str, size: u64| FileMetrics {
+            file_path: path.to_string(),
+            file_size_bytes: size,
+            ..FileMetrics::default()
+        };
+        let (first, second, third) = (file("01.flac"), file("02.flac"), file("03.flac"));
+        let previous = vec![metrics(&first, 1), metrics(&second, 2)];
+        std::fs::write(
+            dir.path().join("analysis_data.json"),
+            serde_json::to_string(&previous).expect("json"),
+        )
+        .expect("write");
+
+        let merged = merge_previous_results(
+            dir.path(),
+            vec![metrics(&second, 20), metrics(&third, 3)],
+            &[PathBuf::from(&second), PathBuf::from(&third)],
+        )
+        .expect("merge");
+        let sizes: Vec<u64> = merged.iter().map(|m| m.file_size_bytes).collect();
+        assert_eq!(sizes, vec![1, 20, 3]);
+
+        // 合并模式：按路径与音轨逐条替换；文件已不存在的旧记录（被移动/改名）被丢弃，
+        // 内容哈希相同的拷贝保留。
+        let (copy_a, copy_b, moved) = (file("a.flac"), file("b.flac"), file("c.flac"));
+        let hashed = |path: &str, size: u64| FileMetrics {
+            content_sha256: Some("h1".to_string()),
+            ..metrics(path, size)
+        };
+        let stream = |path: &str, index: u32, size: u64| FileMetrics {
+            audio_stream_index: index,
+            ..metrics(path, size)
+        };
+        schema::write_analysis_data(
+            &dir.path().join(schema::ANALYSIS_DATA_FILE_NAME),
+            &[
+                hashed(&copy_a, 1),
+                hashed("/nas/gone/c.flac", 2),
+                stream(&copy_b, 0, 3),
+                stream(&copy_b, 1, 4),
+            ],
+            true,
+        )
+        .expect("write");
+        let merged = merge_previous_results(
+            dir.path(),
+            vec![hashed(&moved, 10), stream(&copy_b, 1, 40)],
+            &[],
+        )
+        .expect("merge by path");
+        let records: Vec<(&str, u64)> = merged
+            .iter()
+            .map(|m| (m.file_path.as_str(), m.file_size_bytes))
+            .collect();
+        assert_eq!(
+            records,
+            [
+                (copy_a.as_str(), 1),
+                (copy_b.as_str(), 3),
+                (moved.as_str(), 10),
+                (copy_b.as_str(), 40)
+            ]
+        );
+        assert!(Cli::try_parse_from(["AudioQuality-rs", "--merge", "/reports"]).is_err());
+
+        assert!(
+            Cli::try_parse_from(["AudioQuality-rs", "/music", "--retry-failed", "e.csv"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_hf_bands_parse_and_validate() {
+        let cli = Cli::parse_from(["AudioQuality-rs", "--hf-bands", "19000,14000,15500,14000"]);
+        let config = build_app_config(&cli).expect("build config");
+        assert_eq!(
+            config.hf_bands,
+            vec![14_000, 15_500, 16_000, 18_000, 19_000]
+        );
+
+        // 只给出 20k 时仍测量评分所需的 14k/16k/18k。
+        let only_20k = Cli::parse_from(["AudioQuality-rs", "--hf-bands", "20000"]);
+        let config = build_app_config(&only_20k).expect("build config");
+        assert_eq!(config.hf_bands, ffmpeg::DEFAULT_HF_BANDS.to_vec());
+
+        let default_cli = Cli::parse_from(["AudioQuality-rs"]);
+        let default_config = build_app_config(&default_cli).expect("build config");
+        assert_eq!(default_config.hf_bands, ffmpeg::DEFAULT_HF_BANDS.to_vec());
+
+        // 有损来源分类器的特征需要 20k，自定义列表中没有时也会补上。
+        assert_eq!(
+            parse_hf_bands(&[14_000, 15_500], true).expect("bands"),
+            vec![14_000, 15_500, 16_000, 18_000, 20_000]
+        );
+        assert!(!parse_hf_bands(&[14_000, 15_500], false)
+            .expect("bands")
+            .contains(&20_000));
+
+        let bad = Cli::parse_from(["AudioQuality-rs", "--hf-bands", "0"]);
+        assert!(build_app_config(&bad).is_err());
+    }
+
+    #[test]
+    fn test_rescore_regenerates_reports_from_saved_metrics() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let files = vec![FileMetrics {
+            file_path: dir.path().join("a.flac").to_string_lossy().into_owned(),
+            ..FileMetrics::default()
+        }];
+        schema::write_analysis_data(
+            &dir.path().join(schema::ANALYSIS_DATA_FILE_NAME),
+            &files,
+            true,
+        )
+        .expect("write");
+
+        let run = dir.path().to_str().unwrap();
+        let options = match Cli::parse_from([
+            "AudioQuality-rs",
+            "rescore",
+            run,
+            "--profile",
+            "broadcast",
+            "--fail-under",
+            "99",
+        ])
+        .command
+        {
+            Some(Command::Rescore { options, .. }) => options,
+            other => panic!("unexpected command: {other:?}"),
+        };
+        let config =
+            build_app_config(&rescore_options(&options).expect("options")).expect("config");
+        assert_eq!(config.scoring_profile, ScoringProfile::Broadcast);
+
+        let outcome = rescore_run(dir.path(), &config).expect("rescore");
+        assert_eq!((outcome.total_files, outcome.gate_failures), (1, 1));
+        assert!(dir.path().join("audio_quality_report.csv").is_file());
+        let profile = std::fs::read_to_string(dir.path().join(PROFILE_SNAPSHOT_FILE_NAME))
+            .expect("profile snapshot");
+        assert!(profile.contains("broadcast"));
+
+        assert!(rescore_options(&["/other".to_string()]).is_err());
+        // 参数错误原样返回 clap 的错误，由 main 打印，而不是在解析处直接退出进程。
+        let error = rescore_options(&["--no-such-flag".to_string()]).expect_err("unknown flag");
+        assert!(error.is::<clap::Error>());
+    }
+
+    #[test]
+    fn test_explicit_ffmpeg_path_is_validated() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let missing = dir.path().join("ffmpeg");
+        let cli = Cli::parse_from([
+            "AudioQuality-rs",
+            "--ffmpeg-path",
+            missing.to_str().unwrap(),
+        ]);
+        let err = build_app_config(&cli).expect_err("missing ffmpeg");
+        assert_eq!(exit_code::classify(&err), ExitStatus::Environment);
+        assert!(err.to_string().contains("[E_FFMPEG_PATH]"));
+
+        #[cfg(unix)]
+        {
+            let cli = Cli::parse_from(["AudioQuality-rs", "--ffprobe-path", "/bin/sh"]);
+            let config = build_app_config(&cli).expect("build config");
+            assert!(config.ffprobe_path.is_some_and(|path| path.ends_with("sh")));
+        }
+    }
+
+    #[test]
+    fn test_run_outcome_exit_status() {
+        let cli = Cli::parse_from(["AudioQuality-rs", "--max-failure-percent", "20"]);
+        let config = build_app_config(&cli).expect("build config");
+
+        let ok = RunOutcome {
+            total_files: 10,
+            failed_files: 2,
+            cancelled: false,
+            compliance_failures: 0,
+            delivery_failures: 0,
+            gate_failures: 0,
+            unprocessed: Vec::new(),
+        };
+        assert_eq!(ok.exit_status(&config), ExitStatus::Success);
+
+        let partial = RunOutcome {
+            failed_files: 3,
+            ..ok.clone()
+        };
+        assert_eq!(partial.exit_status(&config), ExitStatus::PartialFailure);
+
+        let non_compliant = RunOutcome {
+            compliance_failures: 1,
+            ..ok.clone()
+        };
+        assert_eq!(non_compliant.exit_status(&config), ExitStatus::GateFailed);
+
+        let failed_delivery = RunOutcome {
+            delivery_failures: 1,
+            ..ok.clone()
+        };
+        assert_eq!(failed_delivery.exit_status(&config), ExitStatus::GateFailed);
+
+        let below_gate = RunOutcome {
+            gate_failures: 1,
+            ..ok.clone()
+        };
+        assert_eq!(below_gate.exit_status(&config), ExitStatus::GateFailed);
+
+        let cancelled = RunOutcome {
+            cancelled: true,
+            ..ok
+        };
+        assert_eq!(cancelled.exit_status(&config), ExitStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_progress_rates() {
+        assert_eq!(
+            format_progress_rates(0.5, 40, Duration::from_secs(4), 8 * 1_048_576, 10),
+            "30.0 文件/分钟 | 2.0 MB/s | 缓存命中 25%"
+        );
+        assert_eq!(
+            format_progress_rates(0.0, 0, Duration::ZERO, 0, 0),
+            "0.0 文件/分钟 | 0.0 MB/s | 缓存命中 0%"
+        );
     }
 }
@@ -0,0 +1,107 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: pending_session.rs
+// 描述: 交互模式的待续分析队列。分析开始时记录目标目录与等效命令，
+//      中途取消时记录尚未处理的文件，下次启动可从菜单继续。
+// ----------------------------------------------------------------
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::safe_io;
+
+/// 缓存目录下的待续会话文件名。
+pub const PENDING_SESSION_FILE_NAME: &str = "pending_session.json";
+
+/// 一次未完成的交互分析。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingSession {
+    pub started_at: String,
+    /// 分析的音乐库目录。
+    pub library: PathBuf,
+    /// 与当时菜单选项等效的命令行参数（含程序名），继续时按它重建设置。
+    pub args: Vec<String>,
+    /// 取消时尚未处理的文件；进程被直接终止（如断电）时为空，继续时重新分析整个目录。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<Vec<PathBuf>>,
+}
+
+impl PendingSession {
+    pub fn new(library: &Path, args: Vec<String>) -> Self {
+        Self {
+            started_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            library: library.to_path_buf(),
+            args,
+            remaining: None,
+        }
+    }
+
+    /// 读取上次未完成的会话；文件不存在或已损坏时返回 `None`。
+    pub fn load(dir: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(dir.join(PENDING_SESSION_FILE_NAME)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, dir: &Path, safe_mode: bool) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("创建缓存目录失败: {}", dir.display()))?;
+        let content = serde_json::to_string_pretty(self).context("序列化待续会话失败")?;
+        safe_io::atomic_write_string(&dir.join(PENDING_SESSION_FILE_NAME), &content, safe_mode)
+    }
+
+    /// 分析正常结束后删除待续会话。
+    pub fn clear(dir: &Path) -> Result<()> {
+        let path = dir.join(PENDING_SESSION_FILE_NAME);
+        if path.is_file() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("删除待续会话失败: {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// 菜单中显示的一行说明。
+    pub fn describe(&self) -> String {
+        let progress = match &self.remaining {
+            Some(remaining) => format!("剩余 {} 个文件", remaining.len()),
+            None => "未正常结束，将重新分析整个目录".to_string(),
+        };
+        format!(
+            "{}（{} 开始，{progress}）",
+            self.library.display(),
+            self.started_at
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pending_session_round_trip_and_clear() {
+        let dir = TempDir::new().expect("tempdir");
+        assert!(PendingSession::load(dir.path()).is_none());
+
+        let mut session = PendingSession::new(
+            Path::new("/music"),
+            ["AudioQuality-rs", "--profile", "classical", "/music"]
+                .map(String::from)
+                .to_vec(),
+        );
+        session.save(dir.path(), true).expect("save");
+        assert_eq!(PendingSession::load(dir.path()), Some(session.clone()));
+        assert!(session.describe().ends_with("将重新分析整个目录）"));
+
+        session.remaining = Some(vec![PathBuf::from("/music/b.flac")]);
+        session.save(dir.path(), true).expect("save remaining");
+        let loaded = PendingSession::load(dir.path()).expect("load");
+        assert!(loaded.describe().ends_with("剩余 1 个文件）"));
+
+        PendingSession::clear(dir.path()).expect("clear");
+        assert!(PendingSession::load(dir.path()).is_none());
+    }
+}
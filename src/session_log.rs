@@ -0,0 +1,92 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: session_log.rs
+// 描述: 交互会话日志。记录每次交互分析对应的等效命令行，
+//      便于把菜单中的选择直接用于脚本或定时任务。
+// ----------------------------------------------------------------
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 日志目录下的会话日志文件名（追加写入）。
+pub const SESSION_LOG_FILE_NAME: &str = "interactive_sessions.log";
+
+/// 把参数拼成可直接粘贴到终端的一行命令，必要时加引号。
+pub fn format_command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./=,:+@%".contains(c));
+    if is_plain {
+        arg.to_string()
+    } else if cfg!(windows) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// 把本次会话的等效命令追加到会话日志，返回日志路径。
+pub fn append_session(logs_dir: &Path, command_lines: &[String]) -> Result<PathBuf> {
+    std::fs::create_dir_all(logs_dir)
+        .with_context(|| format!("创建日志目录失败: {}", logs_dir.display()))?;
+    let path = logs_dir.join(SESSION_LOG_FILE_NAME);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("打开会话日志失败: {}", path.display()))?;
+
+    let mut entry = format!("# 交互会话 {}\n", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    for line in command_lines {
+        entry.push_str(line);
+        entry.push('\n');
+    }
+    entry.push('\n');
+    file.write_all(entry.as_bytes())
+        .with_context(|| format!("写入会话日志失败: {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_command_line_and_append() {
+        let args = [
+            "AudioQuality-rs",
+            "--profile",
+            "classical",
+            "/music/My Album",
+            "it's",
+        ]
+        .map(String::from);
+        let line = format_command_line(&args);
+        if cfg!(windows) {
+            assert!(line.ends_with("\"/music/My Album\" \"it's\""));
+        } else {
+            assert_eq!(
+                line,
+                r"AudioQuality-rs --profile classical '/music/My Album' 'it'\''s'"
+            );
+        }
+
+        let dir = TempDir::new().expect("tempdir");
+        append_session(dir.path(), std::slice::from_ref(&line)).expect("first");
+        let path = append_session(dir.path(), std::slice::from_ref(&line)).expect("second");
+        let content = std::fs::read_to_string(path).expect("read");
+        assert_eq!(content.matches("# 交互会话").count(), 2);
+        assert_eq!(content.matches(line.as_str()).count(), 2);
+    }
+}
@@ -0,0 +1,279 @@
+// ----------------------------------------------------------------
+// 项目: 音频质量分析器 (Audio Quality Analyzer)
+// 模块: user_config.rs
+// 描述: 用户配置文件（配置目录下的 config.toml，或 `--config` 指定的文件）。
+//      保存首次运行向导的选择与常用参数，作为命令行参数未指定时的默认值。
+// ----------------------------------------------------------------
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::safe_io;
+
+/// 配置目录下的配置文件名。
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 用户配置。所有字段可选，命令行参数优先于配置文件。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    /// 默认评分档案（同 `--profile`）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// 报告输出目录（同 `--output-dir`）；未设置时报告写入被分析的目录。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<PathBuf>,
+    /// FFmpeg 可执行文件路径；未设置时在 PATH 与 resources 目录中查找。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ffmpeg_path: Option<PathBuf>,
+    /// FFprobe 可执行文件路径；未设置时在 PATH 与 ffmpeg 所在目录中查找。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ffprobe_path: Option<PathBuf>,
+    /// 字母等级映射（同 `--grade-scale`），如 `A+=95,A=90,B=80,C=70,D=60,F=0`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grade_scale: Option<String>,
+    /// CSV 报告的列（同 `--columns`），如 `minimal` 或 `score,status,integrated_lufs,file_path`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csv_columns: Option<String>,
+    /// 缓存条目超过该天数未被使用时自动清理（同 `--cache-max-age-days`）。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_max_age_days: Option<u64>,
+    /// 缓存目录（同 `--cache-path`）；未设置时使用平台缓存目录。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_path: Option<PathBuf>,
+    /// 缓存指纹的内容哈希算法（同 `--hash-algorithm`）：`sha256`、`blake3` 或 `xxh3`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash_algorithm: Option<String>,
+    /// `[thresholds]`：评分阈值覆盖。
+    #[serde(skip_serializing_if = "is_default")]
+    pub thresholds: ThresholdConfig,
+    /// `[output]`：额外生成的报告格式。
+    #[serde(skip_serializing_if = "is_default")]
+    pub output: OutputConfig,
+    /// `[concurrency]`：并发与超时。
+    #[serde(skip_serializing_if = "is_default")]
+    pub concurrency: ConcurrencyConfig,
+}
+
+/// 评分阈值，与同名命令行参数含义相同。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdConfig {
+    /// 同 `--target-lufs`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_lufs: Option<f64>,
+    /// 同 `--true-peak-max`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub true_peak_max: Option<f64>,
+    /// 同 `--min-bitrate`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_bitrate: Option<u32>,
+    /// 同 `--score-weights`，如 `"35,20,25,10,10"`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_weights: Option<String>,
+}
+
+/// 额外的报告格式。命令行开关只能打开，配置为 `true` 时即使命令行未指定也会生成。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// 同 `--json-report`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonl: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sarif: Option<bool>,
+    /// 同 `--export-playlists`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlists: Option<bool>,
+    /// 同 `--export-tags`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<bool>,
+    /// 同 `--template`，如 `["html", "markdown"]`；命令行指定了模板时取命令行的。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub templates: Option<Vec<String>>,
+}
+
+/// 并发与超时，与同名命令行参数含义相同。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConcurrencyConfig {
+    /// 同 `--jobs`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_ffmpeg_processes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decode_threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scoring_threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ffmpeg_timeout_seconds: Option<u64>,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl UserConfig {
+    /// 读取配置文件；文件不存在时返回 `None`（用于判断是否首次运行）。
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("读取配置文件失败: {}", path.display()))
+            }
+        };
+        let mut config: Self = toml::from_str(&content)
+            .map_err(|e| anyhow!("[E_CONFIG_FILE] 配置文件格式错误 {}: {e}", path.display()))?;
+        // 相对的缓存目录按配置文件所在目录展开，结果不随启动时的当前目录变化。
+        if let (Some(cache_path), Some(dir)) = (&config.cache_path, path.parent()) {
+            if cache_path.is_relative() {
+                config.cache_path = Some(dir.join(cache_path));
+            }
+        }
+        Ok(Some(config))
+    }
+
+    /// 读取 `--config` 指定的配置文件；与默认配置文件不同，文件不存在时报错。
+    pub fn load_required(path: &Path) -> Result<Self> {
+        Self::load(path)?
+            .ok_or_else(|| anyhow!("[E_CONFIG_FILE] 配置文件不存在: {}", path.display()))
+    }
+
+    /// 用 `other`（如 `--config` 指定的文件）中写出的值覆盖当前配置，未写出的沿用当前值。
+    pub fn overlay(self, other: UserConfig) -> UserConfig {
+        UserConfig {
+            profile: other.profile.or(self.profile),
+            output_dir: other.output_dir.or(self.output_dir),
+            ffmpeg_path: other.ffmpeg_path.or(self.ffmpeg_path),
+            ffprobe_path: other.ffprobe_path.or(self.ffprobe_path),
+            grade_scale: other.grade_scale.or(self.grade_scale),
+            csv_columns: other.csv_columns.or(self.csv_columns),
+            cache_max_age_days: other.cache_max_age_days.or(self.cache_max_age_days),
+            cache_path: other.cache_path.or(self.cache_path),
+            hash_algorithm: other.hash_algorithm.or(self.hash_algorithm),
+            thresholds: ThresholdConfig {
+                target_lufs: other.thresholds.target_lufs.or(self.thresholds.target_lufs),
+                true_peak_max: other
+                    .thresholds
+                    .true_peak_max
+                    .or(self.thresholds.true_peak_max),
+                min_bitrate: other.thresholds.min_bitrate.or(self.thresholds.min_bitrate),
+                score_weights: other
+                    .thresholds
+                    .score_weights
+                    .or(self.thresholds.score_weights),
+            },
+            output: OutputConfig {
+                json: other.output.json.or(self.output.json),
+                jsonl: other.output.jsonl.or(self.output.jsonl),
+                sarif: other.output.sarif.or(self.output.sarif),
+                playlists: other.output.playlists.or(self.output.playlists),
+                tags: other.output.tags.or(self.output.tags),
+                templates: other.output.templates.or(self.output.templates),
+            },
+            concurrency: ConcurrencyConfig {
+                jobs: other.concurrency.jobs.or(self.concurrency.jobs),
+                max_ffmpeg_processes: other
+                    .concurrency
+                    .max_ffmpeg_processes
+                    .or(self.concurrency.max_ffmpeg_processes),
+                decode_threads: other
+                    .concurrency
+                    .decode_threads
+                    .or(self.concurrency.decode_threads),
+                scoring_threads: other
+                    .concurrency
+                    .scoring_threads
+                    .or(self.concurrency.scoring_threads),
+                ffmpeg_timeout_seconds: other
+                    .concurrency
+                    .ffmpeg_timeout_seconds
+                    .or(self.concurrency.ffmpeg_timeout_seconds),
+            },
+        }
+    }
+
+    pub fn save(&self, path: &Path, safe_mode: bool) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("创建配置目录失败: {}", parent.display()))?;
+        }
+        let content = toml::to_string_pretty(self).context("序列化配置失败")?;
+        safe_io::atomic_write_string(path, &content, safe_mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_user_config_round_trip_and_missing_file() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join("config").join(CONFIG_FILE_NAME);
+        assert_eq!(UserConfig::load(&path).expect("missing"), None);
+
+        let config = UserConfig {
+            profile: Some("classical".to_string()),
+            output_dir: Some(PathBuf::from("/reports")),
+            ..UserConfig::default()
+        };
+        config.save(&path, true).expect("save");
+        let content = std::fs::read_to_string(&path).expect("read");
+        assert!(!content.contains("ffmpeg_path"));
+        assert!(!content.contains("[thresholds]"));
+        assert_eq!(UserConfig::load(&path).expect("load"), Some(config));
+
+        std::fs::write(&path, "profile = [").expect("write");
+        let err = UserConfig::load(&path).expect_err("invalid toml");
+        assert!(err.to_string().contains("E_CONFIG_FILE"));
+
+        // 拼错的键（包括分节中的）报错，而不是被悄悄忽略。
+        for typo in ["profle = \"pop\"", "[concurrency]\njob = 4"] {
+            std::fs::write(&path, typo).expect("write");
+            let err = UserConfig::load(&path).expect_err("unknown key");
+            assert!(err.to_string().contains("unknown field"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_per_run_config_overlays_user_config() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join("run.toml");
+        assert!(UserConfig::load_required(&path).is_err());
+        std::fs::write(
+            &path,
+            "profile = \"broadcast\"\ncache_path = \"cache\"\nhash_algorithm = \"xxh3\"\n\n[thresholds]\ntarget_lufs = -23\n\n\
+             [output]\nsarif = false\ntemplates = [\"html\"]\n\n[concurrency]\ndecode_threads = 2\n",
+        )
+        .expect("write");
+        let per_run = UserConfig::load_required(&path).expect("load");
+
+        let base = UserConfig {
+            profile: Some("pop".to_string()),
+            grade_scale: Some("A=90,F=0".to_string()),
+            output: OutputConfig {
+                jsonl: Some(true),
+                sarif: Some(true),
+                ..OutputConfig::default()
+            },
+            ..UserConfig::default()
+        };
+        let merged = base.overlay(per_run);
+        assert_eq!(merged.profile.as_deref(), Some("broadcast"));
+        assert_eq!(merged.grade_scale.as_deref(), Some("A=90,F=0"));
+        assert_eq!(merged.thresholds.target_lufs, Some(-23.0));
+        assert_eq!(merged.output.jsonl, Some(true));
+        assert_eq!(merged.output.sarif, Some(false));
+        assert_eq!(merged.output.templates, Some(vec!["html".to_string()]));
+        assert_eq!(merged.concurrency.decode_threads, Some(2));
+        assert_eq!(merged.cache_path, Some(dir.path().join("cache")));
+        assert_eq!(merged.hash_algorithm.as_deref(), Some("xxh3"));
+    }
+}